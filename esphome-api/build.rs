@@ -105,7 +105,7 @@ fn generate_proto_message_enum(messages: &Vec<(String, i32)>) -> TokenStream {
         .collect();
 
     let enum_def = quote! {
-        #[derive(Debug)]
+        #[derive(Debug, Clone)]
         pub enum ProtoMessage {
             #(
                 #message_names(#message_names),
@@ -0,0 +1,160 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{io::{BufReader, ErrorKind}, net::{SocketAddr, TcpStream, ToSocketAddrs}, time::Duration};
+
+use log::debug;
+
+use crate::{proto::*, proto_plaintext::PlaintextMessageStream};
+
+/// Max time to wait for the server hello, and to connect
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Ping the server when it has been quiet this long, the server closes
+/// subscribed connections that go quiet for a minute
+const KEEPALIVE: Duration = Duration::from_secs(20);
+
+#[derive(thiserror::Error, Debug)]
+pub enum ClientError {
+    #[error("Error connecting to server: {0}")]
+    Connect(#[source] std::io::Error),
+    #[error("Error reading or writing message: {0}")]
+    Proto(#[from] ProtoError),
+    #[error("Expected hello response, found message id {0}")]
+    ExpectedHello(u64),
+    #[error("Server sent disconnect request")]
+    Disconnected,
+    #[error("No reply to ping in {0:?}")]
+    Timeout(Duration)
+}
+
+/// Connection to another ESPHome API server, e.g. a second thermostat.
+/// Only the plaintext protocol is supported, the server can't have an
+/// encryption key set.
+pub struct ApiClient {
+    stream: PlaintextMessageStream,
+    /// Node name from the server hello response
+    name: String,
+    /// Ping was sent and nothing has been read since
+    ping_pending: bool
+}
+
+impl ApiClient {
+    /// Connect and say hello, `client_info` is how the server logs this client
+    pub fn connect<A: ToSocketAddrs>(addr: A, client_info: &str) -> Result<Self, ClientError> {
+        let stream = connect_any(addr)?;
+        stream.set_read_timeout(Some(CONNECT_TIMEOUT)).map_err(ClientError::Connect)?;
+
+        let mut stream = PlaintextMessageStream::new(BufReader::new(stream));
+        stream.write(&ProtoMessage::HelloRequest(HelloRequest {
+            client_info: client_info.to_string(),
+            api_version_major: 1,
+            api_version_minor: 13
+        }))?;
+
+        let name = match stream.read()? {
+            ProtoMessage::HelloResponse(response) => response.name,
+            message => return Err(ClientError::ExpectedHello(message.message_id()))
+        };
+
+        stream.set_read_timeout(Some(KEEPALIVE))?;
+
+        Ok(Self { stream, name, ping_pending: false })
+    }
+
+    /// Node name of the server
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Request entity states, the current state of each is sent right
+    /// away and again whenever it changes
+    pub fn subscribe_states(&mut self) -> Result<(), ClientError> {
+        self.write(&ProtoMessage::SubscribeStatesRequest(SubscribeStatesRequest::default()))
+    }
+
+    pub fn write(&mut self, message: &ProtoMessage) -> Result<(), ClientError> {
+        Ok(self.stream.write(message)?)
+    }
+
+    /// Wait for the next message from the server. Pings are answered
+    /// here, and the server is pinged when it goes quiet.
+    pub fn read(&mut self) -> Result<ProtoMessage, ClientError> {
+        loop {
+            let message = match self.stream.read() {
+                Err(ProtoError::IoError(e)) if is_timeout(&e) => {
+                    if self.ping_pending {
+                        return Err(ClientError::Timeout(KEEPALIVE * 2));
+                    }
+                    self.ping_pending = true;
+                    self.write(&ProtoMessage::PingRequest(PingRequest::default()))?;
+                    continue;
+                }
+                result => result?
+            };
+
+            self.ping_pending = false;
+
+            match message {
+                ProtoMessage::PingRequest(_) => {
+                    self.write(&ProtoMessage::PingResponse(PingResponse::default()))?;
+                }
+                ProtoMessage::PingResponse(_) => { }
+                ProtoMessage::DisconnectRequest(_) => {
+                    self.write(&ProtoMessage::DisconnectResponse(DisconnectResponse::default()))?;
+                    return Err(ClientError::Disconnected);
+                }
+                message => {
+                    debug!("Server {} sent {:?}", self.name, message);
+                    return Ok(message);
+                }
+            }
+        }
+    }
+
+    /// Ask the server to close the connection
+    pub fn disconnect(mut self) -> Result<(), ClientError> {
+        self.write(&ProtoMessage::DisconnectRequest(DisconnectRequest::default()))
+    }
+}
+
+/// First address that accepts a connection, `addr` can resolve to several
+fn connect_any<A: ToSocketAddrs>(addr: A) -> Result<TcpStream, ClientError> {
+    let addrs: Vec<SocketAddr> = addr.to_socket_addrs()
+        .map_err(ClientError::Connect)?
+        .collect();
+
+    let mut last_error = std::io::Error::new(
+        std::io::ErrorKind::AddrNotAvailable,
+        "Address resolved to nothing"
+    );
+
+    for addr in addrs {
+        match TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = e
+        }
+    }
+
+    Err(ClientError::Connect(last_error))
+}
+
+fn is_timeout(error: &std::io::Error) -> bool {
+    // unix returns WouldBlock when a socket read timeout is reached
+    matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut)
+}
@@ -0,0 +1,636 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{collections::HashMap, marker::PhantomData, sync::Arc};
+
+use anyhow::Result;
+
+use crate::proto::*;
+
+/// Entity exposed to Home Assistant, see [`EntityRegistry`]
+pub trait Entity {
+    /// List entities response, with the device `object_id` and assigned `key`
+    fn list_response(&self, object_id: &str, key: u32) -> ProtoMessage;
+}
+
+/// Entities of a device, keys are assigned in the order entities are added
+#[derive(Clone, Default)]
+pub struct EntityRegistry {
+    entities: Vec<Arc<dyn Entity + Send + Sync>>
+}
+
+impl EntityRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add<E: Entity + Send + Sync + 'static>(&mut self, entity: E) -> EntityKey<E> {
+        let key = self.entities.len() as u32;
+        self.entities.push(Arc::new(entity));
+        EntityKey { key, entity: PhantomData }
+    }
+
+    /// Respond to `ListEntitiesRequest` with every entity, then the done message
+    pub fn write_entities<W: MessageWriter>(&self, object_id: &str, writer: &mut W) -> Result<(), ProtoError> {
+        for (key, entity) in self.entities.iter().enumerate() {
+            writer.write(&entity.list_response(object_id, key as u32))?;
+        }
+
+        let message = ListEntitiesDoneResponse::default();
+        writer.write(&ProtoMessage::ListEntitiesDoneResponse(message))
+    }
+}
+
+/// Key assigned to an entity by [`EntityRegistry::add`], for building state
+/// messages and matching commands for that entity
+pub struct EntityKey<E> {
+    key: u32,
+    entity: PhantomData<fn() -> E>
+}
+
+impl<E> Clone for EntityKey<E> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<E> Copy for EntityKey<E> { }
+
+impl<E> EntityKey<E> {
+    pub fn key(&self) -> u32 {
+        self.key
+    }
+}
+
+type CommandFn<C> = Box<dyn Fn(&C, &ProtoMessage) -> Result<()> + Send + Sync>;
+
+/// Dispatches entity commands to the handler registered for the entity key,
+/// with a `context` such as an event sender passed to each handler
+pub struct CommandRouter<C> {
+    handlers: HashMap<u32, CommandFn<C>>
+}
+
+impl<C> Default for CommandRouter<C> {
+    fn default() -> Self {
+        Self { handlers: HashMap::new() }
+    }
+}
+
+impl<C> CommandRouter<C> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn on_climate<F>(self, entity: EntityKey<ClimateEntity>, handler: F) -> Self
+        where F: Fn(&C, &ClimateCommandRequest) -> Result<()> + Send + Sync + 'static
+    {
+        self.route(entity.key, move |context, message| {
+            entity.command(message).map_or(Ok(()), |cmd| handler(context, cmd))
+        })
+    }
+
+    pub fn on_switch<F>(self, entity: EntityKey<SwitchEntity>, handler: F) -> Self
+        where F: Fn(&C, bool) -> Result<()> + Send + Sync + 'static
+    {
+        self.route(entity.key, move |context, message| {
+            entity.command(message).map_or(Ok(()), |state| handler(context, state))
+        })
+    }
+
+    pub fn on_number<F>(self, entity: EntityKey<NumberEntity>, handler: F) -> Self
+        where F: Fn(&C, f32) -> Result<()> + Send + Sync + 'static
+    {
+        self.route(entity.key, move |context, message| {
+            entity.command(message).map_or(Ok(()), |value| handler(context, value))
+        })
+    }
+
+    pub fn on_select<F>(self, entity: EntityKey<SelectEntity>, handler: F) -> Self
+        where F: Fn(&C, &str) -> Result<()> + Send + Sync + 'static
+    {
+        self.route(entity.key, move |context, message| {
+            entity.command(message).map_or(Ok(()), |option| handler(context, option))
+        })
+    }
+
+    pub fn on_button<F>(self, entity: EntityKey<ButtonEntity>, handler: F) -> Self
+        where F: Fn(&C) -> Result<()> + Send + Sync + 'static
+    {
+        self.route(entity.key, move |context, message| {
+            if entity.pressed(message) { handler(context) } else { Ok(()) }
+        })
+    }
+
+    fn route<F>(mut self, key: u32, handler: F) -> Self
+        where F: Fn(&C, &ProtoMessage) -> Result<()> + Send + Sync + 'static
+    {
+        self.handlers.insert(key, Box::new(handler));
+        self
+    }
+
+    /// Run the handler for the entity `message` is a command for. Returns
+    /// false when `message` isn't a command, or no handler has its key.
+    pub fn dispatch(&self, context: &C, message: &ProtoMessage) -> Result<bool> {
+        let handler = command_key(message).and_then(|key| self.handlers.get(&key));
+        match handler {
+            Some(handler) => handler(context, message).map(|_| true),
+            None => Ok(false)
+        }
+    }
+}
+
+/// Entity key of a climate, switch, number, select or button command
+fn command_key(message: &ProtoMessage) -> Option<u32> {
+    match message {
+        ProtoMessage::ClimateCommandRequest(cmd) => Some(cmd.key),
+        ProtoMessage::SwitchCommandRequest(cmd) => Some(cmd.key),
+        ProtoMessage::NumberCommandRequest(cmd) => Some(cmd.key),
+        ProtoMessage::SelectCommandRequest(cmd) => Some(cmd.key),
+        ProtoMessage::ButtonCommandRequest(cmd) => Some(cmd.key),
+        _ => None
+    }
+}
+
+/// Entity object id, the device object id with the entity suffix
+fn entity_object_id(object_id: &str, suffix: &str) -> String {
+    if suffix.is_empty() {
+        object_id.to_string()
+    } else {
+        format!("{object_id}_{suffix}")
+    }
+}
+
+/// Defines an entity builder around a list entities response, with setters
+/// for the fields shared by all entity types
+macro_rules! entity_builder {
+    ($(#[$doc:meta])* $entity:ident, $response:ident) => {
+        $(#[$doc])*
+        #[derive(Clone, Default)]
+        pub struct $entity {
+            suffix: String,
+            message: $response
+        }
+
+        impl $entity {
+            /// Entity with object id `{device object id}_{suffix}`, and display `name`
+            pub fn new(suffix: &str, name: &str) -> Self {
+                Self {
+                    suffix: suffix.to_string(),
+                    message: $response { name: name.to_string(), ..Default::default() }
+                }
+            }
+
+            pub fn icon(mut self, icon: &str) -> Self {
+                self.message.icon = icon.to_string();
+                self
+            }
+
+            pub fn device_class(mut self, device_class: &str) -> Self {
+                self.message.device_class = device_class.to_string();
+                self
+            }
+
+            pub fn category(mut self, category: EntityCategory) -> Self {
+                self.message.set_entity_category(category);
+                self
+            }
+        }
+
+        impl Entity for $entity {
+            fn list_response(&self, object_id: &str, key: u32) -> ProtoMessage {
+                let mut message = self.message.clone();
+                message.object_id = entity_object_id(object_id, &self.suffix);
+                message.key = key;
+                ProtoMessage::$response(message)
+            }
+        }
+    };
+}
+
+entity_builder!(
+    /// Numeric sensor
+    SensorEntity, ListEntitiesSensorResponse
+);
+
+impl SensorEntity {
+    pub fn unit(mut self, unit: &str) -> Self {
+        self.message.unit_of_measurement = unit.to_string();
+        self
+    }
+
+    pub fn accuracy(mut self, decimals: i32) -> Self {
+        self.message.accuracy_decimals = decimals;
+        self
+    }
+
+    pub fn state_class(mut self, state_class: SensorStateClass) -> Self {
+        self.message.set_state_class(state_class);
+        self
+    }
+}
+
+impl EntityKey<SensorEntity> {
+    pub fn state(&self, state: f32) -> ProtoMessage {
+        ProtoMessage::SensorStateResponse(SensorStateResponse {
+            key: self.key,
+            state,
+            ..Default::default()
+        })
+    }
+
+    /// Sensor has no value, shown as "unknown" in Home Assistant
+    pub fn missing_state(&self) -> ProtoMessage {
+        ProtoMessage::SensorStateResponse(SensorStateResponse {
+            key: self.key,
+            state: f32::NAN,
+            missing_state: true,
+            ..Default::default()
+        })
+    }
+}
+
+entity_builder!(
+    /// On/off sensor
+    BinarySensorEntity, ListEntitiesBinarySensorResponse
+);
+
+impl EntityKey<BinarySensorEntity> {
+    pub fn state(&self, state: bool) -> ProtoMessage {
+        ProtoMessage::BinarySensorStateResponse(BinarySensorStateResponse {
+            key: self.key,
+            state,
+            ..Default::default()
+        })
+    }
+}
+
+entity_builder!(
+    /// Sensor with a text value
+    TextSensorEntity, ListEntitiesTextSensorResponse
+);
+
+impl EntityKey<TextSensorEntity> {
+    pub fn state(&self, state: impl Into<String>) -> ProtoMessage {
+        ProtoMessage::TextSensorStateResponse(TextSensorStateResponse {
+            key: self.key,
+            state: state.into(),
+            ..Default::default()
+        })
+    }
+}
+
+entity_builder!(
+    /// On/off switch controlled from Home Assistant
+    SwitchEntity, ListEntitiesSwitchResponse
+);
+
+impl EntityKey<SwitchEntity> {
+    pub fn state(&self, state: bool) -> ProtoMessage {
+        ProtoMessage::SwitchStateResponse(SwitchStateResponse {
+            key: self.key,
+            state,
+            ..Default::default()
+        })
+    }
+
+    /// Requested switch state, when `message` is a command for this switch
+    pub fn command(&self, message: &ProtoMessage) -> Option<bool> {
+        match message {
+            ProtoMessage::SwitchCommandRequest(cmd) if cmd.key == self.key => Some(cmd.state),
+            _ => None
+        }
+    }
+}
+
+entity_builder!(
+    /// Button pressed from Home Assistant
+    ButtonEntity, ListEntitiesButtonResponse
+);
+
+impl EntityKey<ButtonEntity> {
+    /// True when `message` is a press of this button
+    pub fn pressed(&self, message: &ProtoMessage) -> bool {
+        matches!(message, ProtoMessage::ButtonCommandRequest(cmd) if cmd.key == self.key)
+    }
+}
+
+entity_builder!(
+    /// Number set from Home Assistant
+    NumberEntity, ListEntitiesNumberResponse
+);
+
+impl NumberEntity {
+    pub fn range(mut self, min: f32, max: f32, step: f32) -> Self {
+        self.message.min_value = min;
+        self.message.max_value = max;
+        self.message.step = step;
+        self
+    }
+
+    pub fn mode(mut self, mode: NumberMode) -> Self {
+        self.message.set_mode(mode);
+        self
+    }
+}
+
+impl EntityKey<NumberEntity> {
+    pub fn state(&self, state: f32) -> ProtoMessage {
+        ProtoMessage::NumberStateResponse(NumberStateResponse {
+            key: self.key,
+            state,
+            ..Default::default()
+        })
+    }
+
+    /// Requested value, when `message` is a command for this number
+    pub fn command(&self, message: &ProtoMessage) -> Option<f32> {
+        match message {
+            ProtoMessage::NumberCommandRequest(cmd) if cmd.key == self.key => Some(cmd.state),
+            _ => None
+        }
+    }
+}
+
+/// Choice of one option from a list, set from Home Assistant. Select
+/// entities have no device class, so this isn't built with `entity_builder!`
+#[derive(Clone, Default)]
+pub struct SelectEntity {
+    suffix: String,
+    message: ListEntitiesSelectResponse
+}
+
+impl SelectEntity {
+    /// Entity with object id `{device object id}_{suffix}`, and display `name`
+    pub fn new(suffix: &str, name: &str) -> Self {
+        Self {
+            suffix: suffix.to_string(),
+            message: ListEntitiesSelectResponse { name: name.to_string(), ..Default::default() }
+        }
+    }
+
+    pub fn icon(mut self, icon: &str) -> Self {
+        self.message.icon = icon.to_string();
+        self
+    }
+
+    pub fn category(mut self, category: EntityCategory) -> Self {
+        self.message.set_entity_category(category);
+        self
+    }
+
+    pub fn options<T: AsRef<str>>(mut self, options: &[T]) -> Self {
+        self.message.options = options.iter().map(|o| o.as_ref().to_string()).collect();
+        self
+    }
+}
+
+impl Entity for SelectEntity {
+    fn list_response(&self, object_id: &str, key: u32) -> ProtoMessage {
+        let mut message = self.message.clone();
+        message.object_id = entity_object_id(object_id, &self.suffix);
+        message.key = key;
+        ProtoMessage::ListEntitiesSelectResponse(message)
+    }
+}
+
+impl EntityKey<SelectEntity> {
+    pub fn state(&self, state: impl Into<String>) -> ProtoMessage {
+        ProtoMessage::SelectStateResponse(SelectStateResponse {
+            key: self.key,
+            state: state.into(),
+            ..Default::default()
+        })
+    }
+
+    /// Requested option, when `message` is a command for this select
+    pub fn command<'a>(&self, message: &'a ProtoMessage) -> Option<&'a str> {
+        match message {
+            ProtoMessage::SelectCommandRequest(cmd) if cmd.key == self.key => Some(&cmd.state),
+            _ => None
+        }
+    }
+}
+
+/// Climate entity, uses the device object id and name
+#[derive(Clone, Default)]
+pub struct ClimateEntity {
+    message: ListEntitiesClimateResponse
+}
+
+impl ClimateEntity {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn modes(mut self, modes: &[ClimateMode]) -> Self {
+        self.message.supported_modes = modes.iter().map(|m| *m as i32).collect();
+        self
+    }
+
+    pub fn fan_modes(mut self, modes: &[ClimateFanMode]) -> Self {
+        self.message.supported_fan_modes = modes.iter().map(|m| *m as i32).collect();
+        self
+    }
+
+    pub fn presets(mut self, presets: &[ClimatePreset]) -> Self {
+        self.message.supported_presets = presets.iter().map(|p| *p as i32).collect();
+        self
+    }
+
+    pub fn custom_presets(mut self, presets: &[&str]) -> Self {
+        self.message.supported_custom_presets = presets.iter().map(|p| p.to_string()).collect();
+        self
+    }
+
+    /// Range of the target temperature control, and step of the target
+    /// and current temperature
+    pub fn temperature_range(mut self, min: f32, max: f32, step: f32) -> Self {
+        self.message.visual_min_temperature = min;
+        self.message.visual_max_temperature = max;
+        self.message.visual_target_temperature_step = step;
+        self.message.visual_current_temperature_step = step;
+        self
+    }
+
+    /// Bit flags of [`ClimateFeature`] constants
+    pub fn features(mut self, features: u32) -> Self {
+        self.message.feature_flags = features;
+        self
+    }
+}
+
+impl Entity for ClimateEntity {
+    fn list_response(&self, object_id: &str, key: u32) -> ProtoMessage {
+        let mut message = self.message.clone();
+        message.object_id = object_id.to_string();
+        message.key = key;
+        ProtoMessage::ListEntitiesClimateResponse(message)
+    }
+}
+
+impl EntityKey<ClimateEntity> {
+    pub fn state(&self, mut state: ClimateStateResponse) -> ProtoMessage {
+        state.key = self.key;
+        ProtoMessage::ClimateStateResponse(state)
+    }
+
+    /// Command for this climate entity
+    pub fn command<'a>(&self, message: &'a ProtoMessage) -> Option<&'a ClimateCommandRequest> {
+        match message {
+            ProtoMessage::ClimateCommandRequest(cmd) if cmd.key == self.key => Some(cmd),
+            _ => None
+        }
+    }
+}
+
+/// Service (action) called from Home Assistant as `esphome.{node name}_{name}`
+#[derive(Clone, Default)]
+pub struct ServiceEntity {
+    message: ListEntitiesServicesResponse
+}
+
+impl ServiceEntity {
+    /// Service that responds with success or failure, when the caller asks
+    pub fn new(name: &str) -> Self {
+        let mut message = ListEntitiesServicesResponse {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        message.set_supports_response(SupportsResponseType::SupportsResponseStatus);
+        Self { message }
+    }
+
+    pub fn arg(mut self, name: &str, arg_type: ServiceArgType) -> Self {
+        let mut arg = ListEntitiesServicesArgument {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        arg.set_type(arg_type);
+        self.message.args.push(arg);
+        self
+    }
+}
+
+impl Entity for ServiceEntity {
+    fn list_response(&self, _object_id: &str, key: u32) -> ProtoMessage {
+        let mut message = self.message.clone();
+        message.key = key;
+        ProtoMessage::ListEntitiesServicesResponse(message)
+    }
+}
+
+impl EntityKey<ServiceEntity> {
+    /// Call of this service
+    pub fn request<'a>(&self, message: &'a ProtoMessage) -> Option<&'a ExecuteServiceRequest> {
+        match message {
+            ProtoMessage::ExecuteServiceRequest(cmd) if cmd.key == self.key => Some(cmd),
+            _ => None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Messages(Vec<ProtoMessage>);
+
+    impl MessageWriter for Messages {
+        fn write(&mut self, message: &ProtoMessage) -> Result<(), ProtoError> {
+            self.0.push(message.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn keys_assigned_in_add_order() {
+        let mut registry = EntityRegistry::new();
+        let climate = registry.add(ClimateEntity::new());
+        let switch = registry.add(SwitchEntity::new("fan", "Fan"));
+        let sensor = registry.add(SensorEntity::new("humidity", "Humidity"));
+
+        assert_eq!(climate.key(), 0);
+        assert_eq!(switch.key(), 1);
+        assert_eq!(sensor.key(), 2);
+
+        let mut messages = Messages::default();
+        registry.write_entities("nest", &mut messages).unwrap();
+
+        let [climate_list, switch_list, sensor_list, done] = messages.0.as_slice() else {
+            panic!("expected three entities and done, got {:?}", messages.0);
+        };
+        assert!(matches!(climate_list, ProtoMessage::ListEntitiesClimateResponse(m)
+            if m.key == 0 && m.object_id == "nest"));
+        assert!(matches!(switch_list, ProtoMessage::ListEntitiesSwitchResponse(m)
+            if m.key == 1 && m.object_id == "nest_fan"));
+        assert!(matches!(sensor_list, ProtoMessage::ListEntitiesSensorResponse(m)
+            if m.key == 2 && m.object_id == "nest_humidity"));
+        assert!(matches!(done, ProtoMessage::ListEntitiesDoneResponse(_)));
+    }
+
+    #[test]
+    fn commands_routed_by_key() {
+        let mut registry = EntityRegistry::new();
+        let fan = registry.add(SwitchEntity::new("fan", "Fan"));
+        let offset = registry.add(NumberEntity::new("offset", "Offset"));
+        let reset = registry.add(ButtonEntity::new("reset", "Reset"));
+
+        let router = CommandRouter::<RefCell<Vec<String>>>::new()
+            .on_switch(fan, |calls, state| {
+                calls.borrow_mut().push(format!("fan {state}"));
+                Ok(())
+            })
+            .on_number(offset, |calls, value| {
+                calls.borrow_mut().push(format!("offset {value}"));
+                Ok(())
+            });
+
+        let calls = RefCell::new(Vec::new());
+
+        let command = SwitchCommandRequest { key: fan.key(), state: true, ..Default::default() };
+        assert!(router.dispatch(&calls, &ProtoMessage::SwitchCommandRequest(command)).unwrap());
+
+        let command = NumberCommandRequest { key: offset.key(), state: 1.5, ..Default::default() };
+        assert!(router.dispatch(&calls, &ProtoMessage::NumberCommandRequest(command)).unwrap());
+
+        // no handler for the button
+        let command = ButtonCommandRequest { key: reset.key(), ..Default::default() };
+        assert!(!router.dispatch(&calls, &ProtoMessage::ButtonCommandRequest(command)).unwrap());
+
+        // not a command
+        let state = fan.state(false);
+        assert!(!router.dispatch(&calls, &state).unwrap());
+
+        assert_eq!(*calls.borrow(), ["fan true", "offset 1.5"]);
+    }
+
+    #[test]
+    fn handler_error_returned() {
+        let mut registry = EntityRegistry::new();
+        let reset = registry.add(ButtonEntity::new("reset", "Reset"));
+
+        let router = CommandRouter::<()>::new()
+            .on_button(reset, |_| anyhow::bail!("failed"));
+
+        let command = ButtonCommandRequest { key: reset.key(), ..Default::default() };
+        assert!(router.dispatch(&(), &ProtoMessage::ButtonCommandRequest(command)).is_err());
+    }
+}
@@ -16,7 +16,9 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+pub mod client;
 pub mod server;
 pub mod proto;
+pub mod entity;
 mod proto_encrypted;
 mod proto_plaintext;
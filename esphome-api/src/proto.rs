@@ -20,6 +20,8 @@ include!(concat!(env!("OUT_DIR"), "/esphome_proto.rs"));
 include!(concat!(env!("OUT_DIR"), "/message_ids.rs"));
 include!(concat!(env!("OUT_DIR"), "/proto_message.rs"));
 
+use std::future::Future;
+
 use prost::Message;
 
 pub trait MessageId {
@@ -45,7 +47,11 @@ pub enum ProtoError {
     #[error("Handshake disconnected")]
     HandshakeDisconnect,
     #[error("Expected NOISE_HELLO frame")]
-    ExpectedNoiseHello
+    ExpectedNoiseHello,
+    #[error("Message of {0} bytes is too large")]
+    MessageTooLarge(usize),
+    #[error("Connection is closed")]
+    ConnectionClosed
 }
 
 pub trait MessageReader {
@@ -56,8 +62,31 @@ pub trait MessageWriter {
     fn write(&mut self, message: &ProtoMessage) -> Result<(), ProtoError>;
 }
 
-pub trait MessageStream: MessageReader + MessageWriter {
-    fn clone(&self) -> Self;
+/// Read half of a server connection
+pub trait AsyncMessageReader {
+    fn read(&mut self) -> impl Future<Output = Result<ProtoMessage, ProtoError>> + Send;
+}
+
+/// Write half of a server connection
+pub trait AsyncMessageWriter {
+    fn write(&mut self, message: &ProtoMessage) -> impl Future<Output = Result<(), ProtoError>> + Send;
+}
+
+impl ProtoMessage {
+    /// Key of the entity for state responses of the entity types in
+    /// [`crate::entity`], `None` for other messages
+    pub fn state_key(&self) -> Option<u32> {
+        match self {
+            Self::SensorStateResponse(state) => Some(state.key),
+            Self::BinarySensorStateResponse(state) => Some(state.key),
+            Self::TextSensorStateResponse(state) => Some(state.key),
+            Self::SwitchStateResponse(state) => Some(state.key),
+            Self::NumberStateResponse(state) => Some(state.key),
+            Self::SelectStateResponse(state) => Some(state.key),
+            Self::ClimateStateResponse(state) => Some(state.key),
+            _ => None
+        }
+    }
 }
 
 pub struct ClimateFeature;
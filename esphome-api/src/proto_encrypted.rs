@@ -16,16 +16,28 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{io::{BufRead, BufReader, Write}, net::TcpStream, sync::{Arc, Mutex}};
+use std::sync::{Arc, Mutex};
 
 use log::{debug, trace};
 use prost::bytes::{Buf, BufMut, Bytes, BytesMut};
 use snow::TransportState;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt, BufReader},
+    net::tcp::{OwnedReadHalf, OwnedWriteHalf}
+};
 
-use crate::proto::{MessageReader, MessageStream, MessageWriter, ProtoError, ProtoMessage};
+use crate::proto::{AsyncMessageReader, AsyncMessageWriter, ProtoError, ProtoMessage};
 
-pub struct EncryptedMessageStream {
-    reader: BufReader<TcpStream>,
+/// Read half of an encrypted server connection
+pub struct EncryptedReader {
+    reader: BufReader<OwnedReadHalf>,
+    codec: Arc<Mutex<TransportState>>
+}
+
+/// Write half of an encrypted server connection, shares the noise
+/// session with the read half
+pub struct EncryptedWriter {
+    writer: OwnedWriteHalf,
     codec: Arc<Mutex<TransportState>>
 }
 
@@ -33,73 +45,68 @@ pub struct EncryptedMessageStream {
 // https://developers.esphome.io/architecture/api/protocol_details/
 // https://ubihome.github.io/esphome-native-api/native_api/encryption/
 
-impl EncryptedMessageStream {
-    pub fn init(
-        mut reader: BufReader<TcpStream>,
-        key: &[u8; 32],
-        node_name: &str,
-        mac_addr: &str
-    ) -> Result<Self, ProtoError> {
-        let mut noise = snow::Builder::new("Noise_NNpsk0_25519_ChaChaPoly_SHA256".parse()?)
-            // do I need prologue?
-            .prologue(b"NoiseAPIInit\0\0")?
-            .psk(0, &key)?
-            .build_responder()?;
-
-        let frame1 = match read_encrypted_frame(&mut reader) {
-            Err(ProtoError::InvalidIndicator(1, 0)) => {
-                write_handshake_reject(&mut reader.get_ref(), "Bad indicator byte")?;
-                Err(ProtoError::HandshakeDisconnect)
-            }
-            r => r
-        }?;
-
-        // First frame is NOISE_HELLO; zero length
-        if frame1.len() > 0 {
-            return Err(ProtoError::ExpectedNoiseHello.into());
+/// Perform the noise handshake as the responder
+pub async fn handshake(
+    mut reader: BufReader<OwnedReadHalf>,
+    mut writer: OwnedWriteHalf,
+    key: &[u8; 32],
+    node_name: &str,
+    mac_addr: &str
+) -> Result<(EncryptedReader, EncryptedWriter), ProtoError> {
+    let mut noise = snow::Builder::new("Noise_NNpsk0_25519_ChaChaPoly_SHA256".parse()?)
+        // do I need prologue?
+        .prologue(b"NoiseAPIInit\0\0")?
+        .psk(0, key)?
+        .build_responder()?;
+
+    let frame1 = match read_encrypted_frame(&mut reader).await {
+        Err(ProtoError::InvalidIndicator(1, 0)) => {
+            write_handshake_reject(&mut writer, "Bad indicator byte").await?;
+            Err(ProtoError::HandshakeDisconnect)
         }
+        r => r
+    }?;
 
-        write_hello_frame(&mut reader.get_ref(), node_name, mac_addr)?;
+    // First frame is NOISE_HELLO; zero length
+    if frame1.len() > 0 {
+        return Err(ProtoError::ExpectedNoiseHello.into());
+    }
 
-        let frame2 = read_encrypted_frame(&mut reader)?;
+    write_hello_frame(&mut writer, node_name, mac_addr).await?;
 
-        // TODO is static buffer necessary?
-        let mut buffer = vec![0u8; 512];
-        // let mut buffer = BytesMut::new();
-        match noise.read_message(&frame2[1..], &mut buffer) {
-            Err(snow::Error::Decrypt) => {
-                write_handshake_reject(&mut reader.get_ref(), "Handshake MAC failure")?;
-                Err(ProtoError::HandshakeDisconnect)
-            }
-            r => r.map_err(|e| ProtoError::from(e))
-        }?;
+    let frame2 = read_encrypted_frame(&mut reader).await?;
 
-        // let mut buffer = BytesMut::new();
-        let len = noise.write_message(&[], &mut buffer)?;
+    // TODO is static buffer necessary?
+    let mut buffer = vec![0u8; 512];
+    // let mut buffer = BytesMut::new();
+    match noise.read_message(&frame2[1..], &mut buffer) {
+        Err(snow::Error::Decrypt) => {
+            write_handshake_reject(&mut writer, "Handshake MAC failure").await?;
+            Err(ProtoError::HandshakeDisconnect)
+        }
+        r => r.map_err(|e| ProtoError::from(e))
+    }?;
 
-        let mut payload = vec![0x00];
-        payload.extend_from_slice(&buffer[..len]);
+    // let mut buffer = BytesMut::new();
+    let len = noise.write_message(&[], &mut buffer)?;
 
-        write_encrypted_frame(&mut reader.get_ref(), &payload)?;
+    let mut payload = vec![0x00];
+    payload.extend_from_slice(&buffer[..len]);
 
-        let codec = noise.into_transport_mode()?;
-        let codec = Arc::new(Mutex::new(codec));
+    write_encrypted_frame(&mut writer, &payload).await?;
 
-        Ok(Self { reader, codec })
-    }
-}
+    let codec = noise.into_transport_mode()?;
+    let codec = Arc::new(Mutex::new(codec));
 
-impl MessageStream for EncryptedMessageStream {
-    fn clone(&self) -> Self {
-        let stream = self.reader.get_ref().try_clone().unwrap();
-        let codec = self.codec.clone();
-        Self { reader: BufReader::new(stream), codec }
-    }
+    Ok((
+        EncryptedReader { reader, codec: codec.clone() },
+        EncryptedWriter { writer, codec }
+    ))
 }
 
-impl MessageReader for EncryptedMessageStream {
-    fn read(&mut self) -> Result<ProtoMessage, ProtoError> {
-        let frame = read_encrypted_frame(&mut self.reader)?;
+impl AsyncMessageReader for EncryptedReader {
+    async fn read(&mut self) -> Result<ProtoMessage, ProtoError> {
+        let frame = read_encrypted_frame(&mut self.reader).await?;
 
         let mut buffer = vec![0u8; 512];
         let len = self.codec.lock().unwrap().read_message(&frame, &mut buffer)?;
@@ -114,8 +121,8 @@ impl MessageReader for EncryptedMessageStream {
     }
 }
 
-impl MessageWriter for EncryptedMessageStream {
-    fn write(&mut self, message: &ProtoMessage) -> Result<(), ProtoError> {
+impl AsyncMessageWriter for EncryptedWriter {
+    async fn write(&mut self, message: &ProtoMessage) -> Result<(), ProtoError> {
         debug!("Response {:?}", message);
 
         let mut message_buffer = BytesMut::with_capacity(512);
@@ -127,7 +134,7 @@ impl MessageWriter for EncryptedMessageStream {
         let mut buffer = vec![0u8; 512];
         let len = self.codec.lock().unwrap().write_message(&buf, &mut buffer)?;
 
-        write_encrypted_frame(&mut self.reader.get_ref(), &buffer[..len])?;
+        write_encrypted_frame(&mut self.writer, &buffer[..len]).await?;
 
         Ok(())
     }
@@ -144,34 +151,25 @@ fn encode_message<B: BufMut>(message: &ProtoMessage, buffer: &mut B) -> Result<(
     Ok(())
 }
 
-fn read_encrypted_frame<R: BufRead>(stream: &mut R) -> Result<Bytes, ProtoError> {
-    let buf = stream.fill_buf()?;
-    if buf.len() == 0 {
+async fn read_encrypted_frame(reader: &mut BufReader<OwnedReadHalf>) -> Result<Bytes, ProtoError> {
+    let mut byte_zero = [0u8];
+    if reader.read(&mut byte_zero).await? == 0 {
         return Err(ProtoError::ReadZero);
     }
 
-    let mut buffer = Bytes::copy_from_slice(buf);
-
-    let byte_zero = buffer.get_u8();
-    if byte_zero != 1 {
-        return Err(ProtoError::InvalidIndicator(1, byte_zero));
+    if byte_zero[0] != 1 {
+        return Err(ProtoError::InvalidIndicator(1, byte_zero[0]));
     }
 
-    let message_size = buffer.get_u16() as usize;
-    stream.consume(3);
+    let message_size = reader.read_u16().await? as usize;
 
-    let buf = stream.fill_buf()?;
-    if buf.len() < message_size {
-        return Err(ProtoError::BufferUnderrun(buf.len(), message_size));
-    }
+    let mut buffer = vec![0u8; message_size];
+    reader.read_exact(&mut buffer).await?;
 
-    let buffer = Bytes::copy_from_slice(&buf[..message_size]);
-    stream.consume(message_size);
-
-    Ok(buffer)
+    Ok(Bytes::from(buffer))
 }
 
-fn write_encrypted_frame<S: Write>(stream: &mut S, payload: &[u8]) -> Result<(), ProtoError> {
+async fn write_encrypted_frame(writer: &mut OwnedWriteHalf, payload: &[u8]) -> Result<(), ProtoError> {
     let mut buffer = BytesMut::new();
 
     buffer.put_u8(1);
@@ -179,24 +177,35 @@ fn write_encrypted_frame<S: Write>(stream: &mut S, payload: &[u8]) -> Result<(),
     buffer.put(payload);
 
     let buf = buffer.freeze();
-    stream.write_all(&buf)?;
+    writer.write_all(&buf).await?;
 
     Ok(())
 }
 
-fn write_handshake_reject<S: Write>(stream: &mut S, reason: &str) -> Result<(), ProtoError> {
+/// Read the client hello and reply with a handshake reject, for when the
+/// client asks for encryption but the server has no key
+pub async fn reject_handshake(
+    reader: &mut BufReader<OwnedReadHalf>,
+    writer: &mut OwnedWriteHalf,
+    reason: &str
+) -> Result<(), ProtoError> {
+    read_encrypted_frame(reader).await?;
+    write_handshake_reject(writer, reason).await
+}
+
+async fn write_handshake_reject(writer: &mut OwnedWriteHalf, reason: &str) -> Result<(), ProtoError> {
     let mut payload = vec![0x01];
     payload.extend_from_slice(reason.as_bytes());
 
-    write_encrypted_frame(stream, payload.as_slice())
+    write_encrypted_frame(writer, payload.as_slice()).await
 }
 
-fn write_hello_frame<S: Write>(stream: &mut S,  node_name: &str, mac_addr: &str) -> Result<(), ProtoError> {
+async fn write_hello_frame(writer: &mut OwnedWriteHalf, node_name: &str, mac_addr: &str) -> Result<(), ProtoError> {
     let mut payload = vec![0x01];
     payload.extend_from_slice(node_name.as_bytes());
     payload.push(0);
     payload.extend_from_slice(mac_addr.as_bytes());
     payload.push(0);
 
-    write_encrypted_frame(stream, payload.as_slice())
+    write_encrypted_frame(writer, payload.as_slice()).await
 }
@@ -16,13 +16,22 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{io::{BufRead, BufReader, Write}, net::TcpStream};
+use std::{io::{BufRead, BufReader, Write}, net::TcpStream, time::Duration};
 
 use log::{debug, trace};
 use prost::{bytes::{Buf, BufMut, Bytes, BytesMut}, encoding::{decode_varint, encode_varint}};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt, BufReader as AsyncBufReader},
+    net::tcp::{OwnedReadHalf, OwnedWriteHalf}
+};
 
-use crate::proto::{MessageReader, MessageStream, MessageWriter, ProtoError, ProtoMessage};
+use crate::proto::{AsyncMessageReader, AsyncMessageWriter, MessageReader, MessageWriter, ProtoError, ProtoMessage};
 
+/// Largest message a client can send, the encrypted protocol has the same
+/// limit from its 16 bit frame size
+const MAX_MESSAGE_SIZE: usize = u16::MAX as usize;
+
+/// Blocking stream, used by [`crate::client::ApiClient`]
 pub struct PlaintextMessageStream {
     reader: BufReader<TcpStream>
 }
@@ -31,12 +40,10 @@ impl PlaintextMessageStream {
     pub fn new(reader: BufReader<TcpStream>) -> Self {
         Self { reader }
     }
-}
 
-impl MessageStream for PlaintextMessageStream {
-    fn clone(&self) -> Self {
-        let stream = self.reader.get_ref().try_clone().unwrap();
-        PlaintextMessageStream { reader: BufReader::new(stream) }
+    /// Set max time `read()` will block waiting for a message, `None` blocks forever
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<(), ProtoError> {
+        Ok(self.reader.get_ref().set_read_timeout(timeout)?)
     }
 }
 
@@ -94,6 +101,86 @@ impl MessageWriter for PlaintextMessageStream {
     }
 }
 
+/// Read half of a plaintext server connection
+pub struct PlaintextReader {
+    reader: AsyncBufReader<OwnedReadHalf>
+}
+
+impl PlaintextReader {
+    pub fn new(reader: AsyncBufReader<OwnedReadHalf>) -> Self {
+        Self { reader }
+    }
+}
+
+impl AsyncMessageReader for PlaintextReader {
+    async fn read(&mut self) -> Result<ProtoMessage, ProtoError> {
+        let mut byte_zero = [0u8];
+        if self.reader.read(&mut byte_zero).await? == 0 {
+            return Err(ProtoError::ReadZero);
+        }
+
+        if byte_zero[0] != 0 {
+            return Err(ProtoError::InvalidIndicator(0, byte_zero[0]));
+        }
+
+        let message_size = read_varint(&mut self.reader).await? as usize;
+        let message_type = read_varint(&mut self.reader).await?;
+
+        if message_size > MAX_MESSAGE_SIZE {
+            return Err(ProtoError::MessageTooLarge(message_size));
+        }
+
+        let mut buffer = vec![0u8; message_size];
+        self.reader.read_exact(&mut buffer).await?;
+
+        trace!("Read msgid:{} {:x?}", message_type, &buffer[..]);
+        Ok(ProtoMessage::decode(message_type, &mut Bytes::from(buffer))?)
+    }
+}
+
+/// Write half of a plaintext server connection
+pub struct PlaintextWriter {
+    writer: OwnedWriteHalf
+}
+
+impl PlaintextWriter {
+    pub fn new(writer: OwnedWriteHalf) -> Self {
+        Self { writer }
+    }
+}
+
+impl AsyncMessageWriter for PlaintextWriter {
+    async fn write(&mut self, message: &ProtoMessage) -> Result<(), ProtoError> {
+        debug!("Response {:?}", message);
+
+        let mut buffer = BytesMut::with_capacity(512);
+        encode_message(message, &mut buffer)?;
+
+        let buf = buffer.freeze();
+        self.writer.write_all(&buf).await?;
+        trace!("Write {:x?}", buf);
+
+        Ok(())
+    }
+}
+
+/// Varints are read a byte at a time, the reader is buffered
+async fn read_varint(reader: &mut AsyncBufReader<OwnedReadHalf>) -> Result<u64, ProtoError> {
+    let mut bytes = Vec::with_capacity(10);
+
+    loop {
+        let byte = reader.read_u8().await?;
+        bytes.push(byte);
+
+        // the last byte has the high bit clear, decode reports overlong ones
+        if byte & 0x80 == 0 || bytes.len() == 10 {
+            break;
+        }
+    }
+
+    Ok(decode_varint(&mut bytes.as_slice())?)
+}
+
 fn encode_message<B: BufMut>(message: &ProtoMessage, buffer: &mut B) -> Result<(), ProtoError> {
     let message_id = message.message_id();
     let message_len = message.encoded_len();
@@ -17,18 +17,28 @@
  */
 
 use std::{
-    io::BufReader, net::{TcpListener, TcpStream, ToSocketAddrs},
-    sync::{Arc, Mutex, mpsc::{Sender, channel}}, thread
+    collections::HashMap, fmt, future::Future, io::ErrorKind,
+    net::{SocketAddr, TcpListener as StdTcpListener},
+    sync::{Arc, Mutex, PoisonError, RwLock},
+    thread::{self, JoinHandle}, time::{Duration, Instant}
 };
 
 use anyhow::{Result, anyhow};
 use base64::prelude::*;
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    runtime::{self, Runtime},
+    sync::{mpsc, watch},
+    task::{JoinError, JoinSet},
+    time::timeout
+};
 
 use crate::{
     proto::*,
-    proto_encrypted::EncryptedMessageStream,
-    proto_plaintext::PlaintextMessageStream
+    proto_encrypted::{self, EncryptedReader, EncryptedWriter, reject_handshake},
+    proto_plaintext::{PlaintextReader, PlaintextWriter}
 };
 
 pub trait RequestHandler {
@@ -37,6 +47,10 @@ pub trait RequestHandler {
         message: &ProtoMessage,
         writer: &mut W
     ) -> Result<ResponseStatus>;
+
+    /// Fill in details only known at runtime, e.g. hardware versions,
+    /// before [`DefaultHandler`] sends the device info response
+    fn device_info(&self, _info: &mut DeviceInfoResponse) { }
 }
 
 pub enum ResponseStatus {
@@ -44,8 +58,17 @@ pub enum ResponseStatus {
     Disconnect
 }
 
-pub trait MessageStreamProvider<S> {
-    fn setup_stream(&self, stream: TcpStream) -> Result<S, ProtoError>;
+/// Sets up the message protocol on accepted connections
+pub trait MessageStreamProvider {
+    type Reader: AsyncMessageReader + Send + 'static;
+    type Writer: AsyncMessageWriter + Send + 'static;
+
+    /// Split the connection into read and write halves, after any handshake
+    fn setup_stream(&self, stream: TcpStream)
+        -> impl Future<Output = Result<(Self::Reader, Self::Writer), ProtoError>> + Send;
+
+    /// True when messages are sent over the noise encrypted protocol
+    fn is_encrypted(&self) -> bool;
 }
 
 pub struct PlaintextStreamProvider;
@@ -54,95 +77,280 @@ impl PlaintextStreamProvider {
     pub fn new() -> Self { Self }
 }
 
-impl MessageStreamProvider<PlaintextMessageStream> for PlaintextStreamProvider {
-    fn setup_stream(&self, stream: TcpStream) -> Result<PlaintextMessageStream, ProtoError> {
-        Ok(PlaintextMessageStream::new(BufReader::new(stream)))
+impl MessageStreamProvider for PlaintextStreamProvider {
+    type Reader = PlaintextReader;
+    type Writer = PlaintextWriter;
+
+    async fn setup_stream(&self, stream: TcpStream) -> Result<(PlaintextReader, PlaintextWriter), ProtoError> {
+        let peer_addr = stream.peer_addr()?;
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+
+        // Encrypted frames start with 1, tell the client why it can't connect
+        // rather than failing on the first plaintext read
+        if reader.fill_buf().await?.first() == Some(&1) {
+            warn!("HA client {peer_addr} requested encryption, no key configured");
+            reject_handshake(&mut reader, &mut writer, "Encryption not configured").await?;
+            return Err(ProtoError::HandshakeDisconnect);
+        }
+
+        Ok((PlaintextReader::new(reader), PlaintextWriter::new(writer)))
+    }
+
+    fn is_encrypted(&self) -> bool {
+        false
+    }
+}
+
+/// Noise pre-shared key, shared with [`EncryptedStreamProvider`] so the key
+/// can be replaced while the server is running
+#[derive(Clone)]
+pub struct EncryptionKey(Arc<RwLock<[u8; 32]>>);
+
+impl EncryptionKey {
+    /// Key from 32 byte base64 string
+    pub fn new(key: &str) -> Result<Self> {
+        Ok(Self(Arc::new(RwLock::new(decode_key(key)?))))
+    }
+
+    /// Replace the key used for new connections, connected clients keep
+    /// their session. Returns true when the key changed.
+    pub fn set(&self, key: &str) -> Result<bool> {
+        let key = decode_key(key)?;
+        let mut current = self.0.write().unwrap_or_else(PoisonError::into_inner);
+        let changed = *current != key;
+        *current = key;
+        Ok(changed)
+    }
+
+    fn get(&self) -> [u8; 32] {
+        *self.0.read().unwrap_or_else(PoisonError::into_inner)
     }
 }
 
+fn decode_key(key: &str) -> Result<[u8; 32]> {
+    let key_bytes = BASE64_STANDARD.decode(key)?;
+    key_bytes.try_into()
+        .map_err(|_| anyhow!("Key must be 32 bytes"))
+}
+
 pub struct EncryptedStreamProvider {
-    key: [u8; 32],
+    key: EncryptionKey,
     node_name: String,
     mac_addr: String
 }
 
 impl EncryptedStreamProvider {
     pub fn new(key: &str, node_name: &str, mac_addr: &str) -> Result<Self> {
-        let key_bytes = BASE64_STANDARD.decode(key)?;
-        let key: [u8; 32] = key_bytes.try_into()
-            .map_err(|_| anyhow!("Key must be 32 bytes"))?;
-
         Ok(Self {
-            key,
+            key: EncryptionKey::new(key)?,
             node_name: node_name.to_string(),
             mac_addr: mac_addr.to_string()
         })
     }
+
+    /// Handle to the key, for changing it after the server is started
+    pub fn key(&self) -> EncryptionKey {
+        self.key.clone()
+    }
 }
 
-impl MessageStreamProvider<EncryptedMessageStream> for EncryptedStreamProvider {
-    fn setup_stream(&self, stream: TcpStream) -> Result<EncryptedMessageStream, ProtoError> {
-        let reader = BufReader::new(stream);
-        let stream = EncryptedMessageStream::init(reader, &self.key, &self.node_name, &self.mac_addr)?;
-        Ok(stream)
+impl MessageStreamProvider for EncryptedStreamProvider {
+    type Reader = EncryptedReader;
+    type Writer = EncryptedWriter;
+
+    async fn setup_stream(&self, stream: TcpStream) -> Result<(EncryptedReader, EncryptedWriter), ProtoError> {
+        let (reader, writer) = stream.into_split();
+        let key = self.key.get();
+        proto_encrypted::handshake(BufReader::new(reader), writer, &key, &self.node_name, &self.mac_addr).await
     }
+
+    fn is_encrypted(&self) -> bool {
+        true
+    }
+}
+
+pub trait ConnectionObserver {
+    /// `sender` queues messages for the new client, it can be kept to send
+    /// messages outside of request handling
+    fn connected(&self, client: &ClientInfo, sender: &ClientSender) -> Result<()>;
+    fn disconnect(&self, client: &ClientInfo);
+
+    /// Called when a client changes state, subscribes, or sends a message
+    fn client_updated(&self, _client: &ClientInfo) { }
 }
 
-pub trait ConnectionObserver<S> {
-    fn connected(&self, stream: &S) -> Result<()>;
-    fn disconnect(&self);
+/// Snapshot of a connected client, for diagnostics
+#[derive(Debug, Clone)]
+pub struct ClientInfo {
+    pub peer_addr: SocketAddr,
+    pub encrypted: bool,
+    pub state: ConnectionState,
+    pub subscriptions: Subscriptions,
+    pub connected_at: Instant,
+    pub last_activity: Instant,
+    pub message_count: u32
+}
+
+impl ClientInfo {
+    fn new(peer_addr: SocketAddr, encrypted: bool) -> Self {
+        let now = Instant::now();
+        Self {
+            peer_addr,
+            encrypted,
+            state: ConnectionState::Handshake,
+            subscriptions: Subscriptions::default(),
+            connected_at: now,
+            last_activity: now,
+            message_count: 0
+        }
+    }
+}
+
+impl fmt::Display for ClientInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {:?} encrypted:{} subscribed:{} messages:{} idle:{:?}",
+            self.peer_addr,
+            self.state,
+            self.encrypted,
+            self.subscriptions,
+            self.message_count,
+            self.last_activity.elapsed()
+        )
+    }
+}
+
+/// Subscription requests received from a client
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Subscriptions {
+    pub states: bool,
+    pub logs: bool,
+    pub services: bool,
+    pub ha_states: bool
+}
+
+impl Subscriptions {
+    /// Record subscription request, returns true when a new subscription was added
+    fn update(&mut self, message: &ProtoMessage) -> bool {
+        let flag = match message {
+            ProtoMessage::SubscribeStatesRequest(_) => &mut self.states,
+            ProtoMessage::SubscribeLogsRequest(_) => &mut self.logs,
+            ProtoMessage::SubscribeHomeassistantServicesRequest(_) => &mut self.services,
+            ProtoMessage::SubscribeHomeAssistantStatesRequest(_) => &mut self.ha_states,
+            _ => return false
+        };
+
+        !std::mem::replace(flag, true)
+    }
+}
+
+impl fmt::Display for Subscriptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flags = [
+            (self.states, "states"),
+            (self.logs, "logs"),
+            (self.services, "services"),
+            (self.ha_states, "ha_states")
+        ];
+
+        let names: Vec<_> = flags.iter()
+            .filter(|(set, _)| *set)
+            .map(|(_, name)| *name)
+            .collect();
+
+        if names.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", names.join(","))
+        }
+    }
+}
+
+/// Errors that stop the server from starting, or end a client connection
+#[derive(thiserror::Error, Debug)]
+pub enum ServerError {
+    #[error("Error binding listen address: {0}")]
+    Bind(#[source] std::io::Error),
+    #[error("Error starting async runtime: {0}")]
+    Runtime(#[source] std::io::Error),
+    #[error("Error in connection handshake: {0}")]
+    Handshake(#[source] ProtoError),
+    #[error("Error reading or writing message: {0}")]
+    Proto(#[from] ProtoError),
+    #[error("Connection observer failed: {0}")]
+    Observer(#[source] anyhow::Error),
+    #[error("Request handler failed: {0}")]
+    Handler(#[source] anyhow::Error)
 }
 
 #[derive(thiserror::Error, Debug)]
 pub enum MessageThreadError {
-    #[error("Message send failed {0}")]
-    SenderError(#[from] std::sync::mpsc::SendError<ProtoMessage>),
     #[error("Can't send message; not connected")]
     NonConnected
 }
 
+/// Queues messages for one client, the connection's writer task sends
+/// them in order
+#[derive(Clone)]
+pub struct ClientSender(mpsc::UnboundedSender<ProtoMessage>);
+
+impl ClientSender {
+    pub fn send(&self, message: ProtoMessage) -> Result<(), ProtoError> {
+        self.0.send(message).map_err(|_| ProtoError::ConnectionClosed)
+    }
+
+    /// Resolves when the writer task has ended, e.g. after a failed write
+    async fn closed(&self) {
+        self.0.closed().await
+    }
+}
+
+impl MessageWriter for ClientSender {
+    fn write(&mut self, message: &ProtoMessage) -> Result<(), ProtoError> {
+        self.send(message.clone())
+    }
+}
+
+/// Sends messages to every connected client
 #[derive(Clone)]
 pub struct MessageSender {
-    inner: Arc<Mutex<Option<Sender<ProtoMessage>>>>
+    inner: Arc<Mutex<HashMap<SocketAddr, ClientSender>>>
 }
 
 impl MessageSender {
     pub fn new() -> Self {
-        Self { inner: Arc::new(Mutex::new(None)) }
+        Self { inner: Arc::new(Mutex::new(HashMap::new())) }
     }
 
     pub fn send_message(&self, message: ProtoMessage) -> Result<(), MessageThreadError> {
-        let guard = self.inner.lock().unwrap();
+        let mut guard = self.inner.lock().unwrap();
 
-        if let Some(sender) = guard.as_ref() {
-            sender.send(message)?;
-            Ok(())
-        } else {
-            Err(MessageThreadError::NonConnected)
+        if guard.is_empty() {
+            return Err(MessageThreadError::NonConnected);
         }
-    }
-}
-
-impl<S: MessageStream + Send + 'static> ConnectionObserver<S> for MessageSender {
-    fn connected(&self, stream: &S) -> Result<()> {
-        let (tx, rx) = channel();
 
-        *self.inner.lock().unwrap() = Some(tx);
-
-        let mut stream = stream.clone();
-        thread::spawn(move || {
-            while let Ok(message) = rx.recv() {
-                if let Err(e) = stream.write(&message) {
-                    error!("HA message send failed: {e}");
-                }
+        // The writer task ends after a failed write, stop queueing messages
+        // for that client until its connection is closed
+        guard.retain(|peer_addr, sender| {
+            let sent = sender.send(message.clone()).is_ok();
+            if !sent {
+                warn!("HA client {peer_addr} stream is dead, dropping its messages");
             }
+            sent
         });
 
         Ok(())
     }
+}
+
+impl ConnectionObserver for MessageSender {
+    fn connected(&self, client: &ClientInfo, sender: &ClientSender) -> Result<()> {
+        self.inner.lock().unwrap().insert(client.peer_addr, sender.clone());
+        Ok(())
+    }
 
-    fn disconnect(&self) {
-        *self.inner.lock().unwrap() = None;
+    fn disconnect(&self, client: &ClientInfo) {
+        self.inner.lock().unwrap().remove(&client.peer_addr);
     }
 }
 
@@ -213,6 +421,8 @@ impl<D: RequestHandler> RequestHandler for DefaultHandler<D> {
                 // This shows as "Firmware" under device info in HA
                 response.esphome_version = "42.9.0".to_string();
 
+                self.delegate.device_info(&mut response);
+
                 writer.write(&ProtoMessage::DeviceInfoResponse(response))?;
                 Ok(ResponseStatus::Continue)
             }
@@ -221,56 +431,665 @@ impl<D: RequestHandler> RequestHandler for DefaultHandler<D> {
     }
 }
 
-pub fn start_server<S>(
-    addr: impl ToSocketAddrs,
-    stream_factory: &impl MessageStreamProvider<S>,
-    connection_observer: &impl ConnectionObserver<S>,
-    handler: &impl RequestHandler
-) -> Result<()>
-    where S: MessageStream
-{
-    let listener = TcpListener::bind(addr)?;
+/// Wait before accepting again when accept fails, e.g. out of file handles
+const ACCEPT_RETRY: Duration = Duration::from_secs(1);
 
-    info!("Listening for HA connection {}", listener.local_addr()?);
+/// Max time for the encryption handshake, or plaintext check, after accepting
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
 
-    for stream in listener.incoming() {
-        let stream = stream?;
+/// Ping a client that has been quiet this long, once hello is exchanged
+const PING_INTERVAL: Duration = Duration::from_secs(20);
 
-        info!("Establishing HA connection to {}", stream.peer_addr()?);
+/// Pings in a row without any reply before the client is considered gone,
+/// e.g. dropped off wifi without closing the connection
+const MAX_MISSED_PINGS: u32 = 3;
 
-        let message_stream = match stream_factory.setup_stream(stream) {
-            // allow handshake disconnect to re-connect
-            Err(ProtoError::HandshakeDisconnect) => continue,
-            Err(error) => Err(error)?,
-            Ok(stream) => stream
-        };
+/// Max time a write waits on a client that stopped reading, so a dead
+/// client can't hold messages forever
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Listens for clients, handling each connection in its own task.
+///
+/// Errors on a connection are logged and close only that connection.
+pub struct Server {
+    listener: TcpListener,
+    stop_handle: StopHandle
+}
+
+impl Server {
+    pub async fn bind(addr: impl ToSocketAddrs) -> Result<Self, ServerError> {
+        let listener = TcpListener::bind(addr).await.map_err(ServerError::Bind)?;
+        Self::new(listener)
+    }
+
+    /// Listener bound outside of the runtime, must be called within one
+    fn from_std(listener: StdTcpListener) -> Result<Self, ServerError> {
+        listener.set_nonblocking(true).map_err(ServerError::Bind)?;
+        Self::new(TcpListener::from_std(listener).map_err(ServerError::Bind)?)
+    }
+
+    fn new(listener: TcpListener) -> Result<Self, ServerError> {
+        let local_addr = listener.local_addr().map_err(ServerError::Bind)?;
+
+        info!("Listening for HA connection {local_addr}");
+
+        let (stop, _) = watch::channel(false);
+
+        Ok(Self {
+            listener,
+            stop_handle: StopHandle(Arc::new(stop))
+        })
+    }
+
+    /// Address the listener is bound to, e.g. the port picked for port 0
+    pub fn local_addr(&self) -> Result<SocketAddr, ServerError> {
+        self.listener.local_addr().map_err(ServerError::Bind)
+    }
+
+    /// Handle used to stop the server from another task or thread
+    pub fn stop_handle(&self) -> StopHandle {
+        self.stop_handle.clone()
+    }
+
+    /// Accept connections until stopped, returns after all connections close
+    pub async fn serve<F, O, H>(&self, stream_provider: Arc<F>, connection_observer: Arc<O>, handler: Arc<H>)
+        where F: MessageStreamProvider + Send + Sync + 'static,
+            O: ConnectionObserver + Send + Sync + 'static,
+            H: RequestHandler + Send + Sync + 'static
+    {
+        let mut stop = self.stop_handle.subscribe();
+        let mut connections = JoinSet::new();
+
+        loop {
+            let (stream, peer_addr) = tokio::select! {
+                _ = stopped(&mut stop) => break,
+                // reap finished connections so the set doesn't grow
+                Some(result) = connections.join_next() => {
+                    log_panic(result);
+                    continue;
+                }
+                accepted = self.listener.accept() => match accepted {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        warn!("HA accept failed: {e}");
+                        tokio::time::sleep(ACCEPT_RETRY).await;
+                        continue;
+                    }
+                }
+            };
 
-        connection_observer.connected(&message_stream)?;
+            let stream_provider = stream_provider.clone();
+            let connection_observer = connection_observer.clone();
+            let handler = handler.clone();
+            let stop = self.stop_handle.subscribe();
 
-        let result = message_loop(message_stream, handler);
+            connections.spawn(async move {
+                let result = connection_loop(
+                    stream,
+                    peer_addr,
+                    stream_provider.as_ref(),
+                    connection_observer.as_ref(),
+                    handler.as_ref(),
+                    stop
+                ).await;
 
-        connection_observer.disconnect();
+                if let Err(e) = result {
+                    error!("HA connection error: {e}");
+                }
+            });
+        }
+
+        // connections see the stop too, wait for them to say goodbye
+        while let Some(result) = connections.join_next().await {
+            log_panic(result);
+        }
 
-        // Observer disconnect needs to perform cleanup, resolve message loop
-        // result after in case of error.
-        result?;
+        info!("HA server stopped");
     }
+}
+
+/// A connection task that panicked doesn't stop the others
+fn log_panic(result: Result<(), JoinError>) {
+    if let Err(e) = result {
+        error!("HA connection task failed: {e}");
+    }
+}
 
+/// Stops a running [`Server`]
+#[derive(Clone)]
+pub struct StopHandle(Arc<watch::Sender<bool>>);
+
+impl StopHandle {
+    pub fn stop(&self) {
+        self.0.send_replace(true);
+    }
+
+    fn subscribe(&self) -> watch::Receiver<bool> {
+        self.0.subscribe()
+    }
+}
+
+/// Resolves once the server is stopped
+async fn stopped(stop: &mut watch::Receiver<bool>) {
+    // an error means the server is gone, which is stopped too
+    let _ = stop.wait_for(|stopped| *stopped).await;
+}
+
+/// Server running on its own thread, see [`spawn_server`]
+pub struct ServerThread {
+    local_addr: SocketAddr,
+    stop_handle: StopHandle,
+    thread: JoinHandle<()>
+}
+
+impl ServerThread {
+    /// Address the listener is bound to, e.g. the port picked for port 0
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Close all connections and wait for the server thread to finish
+    pub fn stop(self) {
+        self.stop_handle.stop();
+
+        if self.thread.join().is_err() {
+            error!("HA server thread panicked");
+        }
+    }
+}
+
+/// Listen on `addr` and serve clients until there is an error binding
+/// the listener address
+pub async fn start_server<F, O, H>(
+    addr: impl ToSocketAddrs,
+    stream_provider: Arc<F>,
+    connection_observer: Arc<O>,
+    handler: Arc<H>
+) -> Result<(), ServerError>
+    where F: MessageStreamProvider + Send + Sync + 'static,
+        O: ConnectionObserver + Send + Sync + 'static,
+        H: RequestHandler + Send + Sync + 'static
+{
+    let server = Server::bind(addr).await?;
+    server.serve(stream_provider, connection_observer, handler).await;
     Ok(())
 }
 
-fn message_loop<S, H>(mut stream: S, handler: &H) -> Result<()>
-    where S: MessageStream, H: RequestHandler
+/// Blocking [`start_server`], for callers without an async runtime.
+/// Clients are served on a single threaded runtime on the calling thread.
+pub fn run_server<F, O, H>(
+    addr: impl ToSocketAddrs,
+    stream_provider: Arc<F>,
+    connection_observer: Arc<O>,
+    handler: Arc<H>
+) -> Result<(), ServerError>
+    where F: MessageStreamProvider + Send + Sync + 'static,
+        O: ConnectionObserver + Send + Sync + 'static,
+        H: RequestHandler + Send + Sync + 'static
+{
+    new_runtime()?.block_on(start_server(addr, stream_provider, connection_observer, handler))
+}
+
+/// Start the server on a new thread, for callers with their own event loop
+pub fn spawn_server<F, O, H>(
+    addr: impl std::net::ToSocketAddrs,
+    stream_provider: F,
+    connection_observer: O,
+    handler: H
+) -> Result<ServerThread, ServerError>
+    where F: MessageStreamProvider + Send + Sync + 'static,
+        O: ConnectionObserver + Send + Sync + 'static,
+        H: RequestHandler + Send + Sync + 'static
 {
+    // bind here so the caller hears about a bad address
+    let listener = StdTcpListener::bind(addr).map_err(ServerError::Bind)?;
+
+    let runtime = new_runtime()?;
+    let server = {
+        let _context = runtime.enter();
+        Server::from_std(listener)?
+    };
+    let local_addr = server.local_addr()?;
+    let stop_handle = server.stop_handle();
+
+    let thread = thread::spawn(move || {
+        runtime.block_on(server.serve(
+            Arc::new(stream_provider),
+            Arc::new(connection_observer),
+            Arc::new(handler)
+        ));
+    });
+
+    Ok(ServerThread { local_addr, stop_handle, thread })
+}
+
+fn new_runtime() -> Result<Runtime, ServerError> {
+    runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(ServerError::Runtime)
+}
+
+async fn connection_loop<F, O, H>(
+    stream: TcpStream,
+    peer_addr: SocketAddr,
+    stream_provider: &F,
+    connection_observer: &O,
+    handler: &H,
+    mut stop: watch::Receiver<bool>
+) -> Result<(), ServerError>
+    where F: MessageStreamProvider,
+        O: ConnectionObserver,
+        H: RequestHandler
+{
+    info!("Establishing HA connection to {peer_addr}");
+
+    let setup = tokio::select! {
+        _ = stopped(&mut stop) => return Ok(()),
+        setup = timeout(HANDSHAKE_TIMEOUT, stream_provider.setup_stream(stream)) => setup
+    };
+
+    let (reader, writer) = match setup {
+        Err(_) => {
+            info!("HA client {peer_addr} handshake timed out");
+            return Ok(());
+        }
+        // client dropped during handshake, it will reconnect
+        Ok(Err(ProtoError::HandshakeDisconnect | ProtoError::ReadZero)) => return Ok(()),
+        Ok(Err(ProtoError::IoError(e))) if is_disconnect(&e) => {
+            info!("HA client {peer_addr} dropped during handshake: {e}");
+            return Ok(());
+        }
+        Ok(Err(error)) => return Err(ServerError::Handshake(error)),
+        Ok(Ok(halves)) => halves
+    };
+
+    let (sender, messages) = mpsc::unbounded_channel();
+    let sender = ClientSender(sender);
+    let mut writer = tokio::spawn(write_messages(writer, messages, peer_addr));
+
+    let (requests_tx, mut requests) = mpsc::channel(1);
+    let reader = tokio::spawn(read_messages(reader, requests_tx));
+
+    let mut client = ClientInfo::new(peer_addr, stream_provider.is_encrypted());
+
+    let result = match connection_observer.connected(&client, &sender) {
+        Ok(()) => {
+            connection_observer.client_updated(&client);
+
+            let result = message_loop(
+                &mut requests,
+                sender,
+                &mut client,
+                connection_observer,
+                handler,
+                stop
+            ).await;
+
+            // Observer disconnect needs to perform cleanup, resolve message
+            // loop result after in case of error.
+            connection_observer.disconnect(&client);
+
+            result
+        }
+        Err(e) => Err(ServerError::Observer(e))
+    };
+
+    reader.abort();
+
+    // Let queued replies, e.g. disconnect response, go out before the
+    // connection closes. The writer ends once the senders are dropped.
+    if timeout(WRITE_TIMEOUT, &mut writer).await.is_err() {
+        writer.abort();
+    }
+
+    result
+}
+
+/// Read messages into `requests`, waiting for the next one can then be
+/// timed out or cancelled without losing part of a message
+async fn read_messages<R: AsyncMessageReader>(
+    mut reader: R,
+    requests: mpsc::Sender<Result<ProtoMessage, ProtoError>>
+) {
     loop {
-        let request = stream.read()?;
-        debug!("Request {:?}", request);
+        let result = reader.read().await;
+        let failed = result.is_err();
+
+        if requests.send(result).await.is_err() || failed {
+            break;
+        }
+    }
+}
+
+/// Write queued messages until every sender is dropped, or a write fails
+async fn write_messages<W: AsyncMessageWriter>(
+    mut writer: W,
+    mut messages: mpsc::UnboundedReceiver<ProtoMessage>,
+    peer_addr: SocketAddr
+) {
+    while let Some(message) = messages.recv().await {
+        match timeout(WRITE_TIMEOUT, writer.write(&message)).await {
+            Ok(Ok(())) => { }
+            Ok(Err(e)) => {
+                error!("HA message send to {peer_addr} failed: {e}");
+                break;
+            }
+            Err(_) => {
+                error!("HA client {peer_addr} not reading, send timed out after {WRITE_TIMEOUT:?}");
+                break;
+            }
+        }
+    }
+}
 
-        let status = handler.handle_request(&request, &mut stream)?;
+/// Lifecycle of a client connection
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConnectionState {
+    /// Waiting for client hello
+    Handshake,
+    /// Hello exchanged, waiting for client to subscribe to states.
+    /// Password auth is gone from HA, and encryption is established
+    /// before the first message, so hello completes authentication.
+    Authenticated,
+    /// Client is subscribed and receiving state updates
+    Subscribed,
+    /// Client requested disconnect, or handler ended the connection
+    Closing
+}
+
+impl ConnectionState {
+    /// Max time to wait for the next message, `None` when connection is done.
+    /// Once hello is exchanged the client is pinged each time this passes.
+    fn read_timeout(&self) -> Option<Duration> {
+        match self {
+            Self::Handshake => Some(Duration::from_secs(10)),
+            Self::Authenticated | Self::Subscribed => Some(PING_INTERVAL),
+            Self::Closing => None
+        }
+    }
+
+    /// Client is expected to answer pings
+    fn pings(&self) -> bool {
+        matches!(self, Self::Authenticated | Self::Subscribed)
+    }
+
+    /// Returns false for messages that aren't valid in this state
+    fn accepts(&self, message: &ProtoMessage) -> bool {
+        match self {
+            Self::Handshake => matches!(message,
+                ProtoMessage::HelloRequest(_) |
+                ProtoMessage::PingRequest(_) |
+                ProtoMessage::DisconnectRequest(_)
+            ),
+            _ => true
+        }
+    }
+
+    fn next(self, message: &ProtoMessage, status: &ResponseStatus) -> Self {
         if matches!(status, ResponseStatus::Disconnect) {
+            return Self::Closing;
+        }
+
+        match (self, message) {
+            (Self::Handshake, ProtoMessage::HelloRequest(_)) => Self::Authenticated,
+            (Self::Authenticated, ProtoMessage::SubscribeStatesRequest(_)) => Self::Subscribed,
+            (state, _) => state
+        }
+    }
+}
+
+async fn message_loop<O, H>(
+    requests: &mut mpsc::Receiver<Result<ProtoMessage, ProtoError>>,
+    mut sender: ClientSender,
+    client: &mut ClientInfo,
+    connection_observer: &O,
+    handler: &H,
+    mut stop: watch::Receiver<bool>
+) -> Result<(), ServerError>
+    where O: ConnectionObserver, H: RequestHandler
+{
+    let mut missed_pings = 0;
+
+    while let Some(read_timeout) = client.state.read_timeout() {
+        let request = tokio::select! {
+            _ = stopped(&mut stop) => {
+                // ask the client to go, it reconnects once the server is back
+                let _ = sender.send(ProtoMessage::DisconnectRequest(DisconnectRequest::default()));
+                break;
+            }
+            _ = sender.closed() => {
+                warn!("HA client {} stream is dead, closing connection", client.peer_addr);
+                break;
+            }
+            request = timeout(read_timeout, requests.recv()) => request
+        };
+
+        let request = match request {
+            Err(_) if client.state.pings() => {
+                if missed_pings == MAX_MISSED_PINGS {
+                    warn!("HA client {} missed {missed_pings} pings, closing connection", client.peer_addr);
+                    break;
+                }
+
+                missed_pings += 1;
+                if let Err(e) = sender.send(ProtoMessage::PingRequest(PingRequest::default())) {
+                    warn!("HA ping to {} failed, closing connection: {e}", client.peer_addr);
+                    break;
+                }
+                continue;
+            }
+            Err(_) => {
+                warn!("No HA message in {read_timeout:?} while {:?}, closing connection", client.state);
+                break;
+            }
+            // reader task ends after passing on an error
+            Ok(None) | Ok(Some(Err(ProtoError::ReadZero))) => break,
+            Ok(Some(Err(ProtoError::IoError(e)))) if is_disconnect(&e) => {
+                info!("HA client dropped: {e}");
+                break;
+            }
+            Ok(Some(result)) => result?
+        };
+        debug!("Request {:?}", request);
+
+        missed_pings = 0;
+        client.message_count += 1;
+        client.last_activity = Instant::now();
+
+        // reply to a ping from this end, nothing else to do
+        if let ProtoMessage::PingResponse(_) = request {
+            connection_observer.client_updated(client);
+            continue;
+        }
+
+        if !client.state.accepts(&request) {
+            warn!("Unexpected HA message while {:?}, closing connection", client.state);
             break;
         }
+
+        let status = handler.handle_request(&request, &mut sender)
+            .map_err(ServerError::Handler)?;
+
+        let mut changed = client.subscriptions.update(&request);
+
+        let next_state = client.state.next(&request, &status);
+        if next_state != client.state {
+            debug!("HA connection {:?} -> {next_state:?}", client.state);
+            client.state = next_state;
+            changed = true;
+        }
+
+        if changed {
+            info!("HA client {client}");
+        }
+
+        connection_observer.client_updated(client);
     }
 
+    info!(
+        "HA connection closed while {:?} after {:?}, {} messages",
+        client.state,
+        client.connected_at.elapsed(),
+        client.message_count
+    );
+
     Ok(())
 }
+
+fn is_disconnect(error: &std::io::Error) -> bool {
+    matches!(error.kind(),
+        ErrorKind::UnexpectedEof | ErrorKind::ConnectionReset |
+        ErrorKind::ConnectionAborted | ErrorKind::BrokenPipe
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client::ApiClient;
+
+    use super::*;
+
+    struct NoRequests;
+
+    impl RequestHandler for NoRequests {
+        fn handle_request<W: MessageWriter>(&self, _: &ProtoMessage, _: &mut W) -> Result<ResponseStatus> {
+            Ok(ResponseStatus::Continue)
+        }
+    }
+
+    fn spawn_test_server(message_sender: MessageSender) -> ServerThread {
+        let handler = DefaultHandler {
+            delegate: NoRequests,
+            server_info: "test".to_string(),
+            node_name: "node".to_string(),
+            friendly_name: "Node".to_string(),
+            manufacturer: "test".to_string(),
+            model: "test".to_string(),
+            mac_address: "00:00:00:00:00:00".to_string()
+        };
+
+        spawn_server("127.0.0.1:0", PlaintextStreamProvider::new(), message_sender, handler).unwrap()
+    }
+
+    #[test]
+    fn client_receives_broadcast() {
+        let message_sender = MessageSender::new();
+        let server = spawn_test_server(message_sender.clone());
+
+        let mut client = ApiClient::connect(server.local_addr(), "test").unwrap();
+        assert_eq!(client.name(), "node");
+
+        let state = SwitchStateResponse { key: 7, state: true, device_id: 0 };
+        message_sender.send_message(ProtoMessage::SwitchStateResponse(state)).unwrap();
+        assert!(matches!(client.read().unwrap(), ProtoMessage::SwitchStateResponse(s) if s == state));
+
+        server.stop();
+    }
+
+    #[test]
+    fn stop_disconnects_clients() {
+        let server = spawn_test_server(MessageSender::new());
+
+        let mut client = ApiClient::connect(server.local_addr(), "test").unwrap();
+        server.stop();
+
+        // a disconnect request, or a closed socket if the reply raced the close
+        assert!(client.read().is_err());
+    }
+
+    #[test]
+    fn no_message_after_stop() {
+        let message_sender = MessageSender::new();
+        let server = spawn_test_server(message_sender.clone());
+
+        let _client = ApiClient::connect(server.local_addr(), "test").unwrap();
+        assert!(message_sender.send_message(ProtoMessage::PingRequest(PingRequest::default())).is_ok());
+
+        server.stop();
+        assert!(matches!(
+            message_sender.send_message(ProtoMessage::PingRequest(PingRequest::default())),
+            Err(MessageThreadError::NonConnected)
+        ));
+    }
+
+    struct NoObserver;
+
+    impl ConnectionObserver for NoObserver {
+        fn connected(&self, _: &ClientInfo, _: &ClientSender) -> Result<()> {
+            Ok(())
+        }
+
+        fn disconnect(&self, _: &ClientInfo) { }
+    }
+
+    fn hello_client() -> ClientInfo {
+        let mut client = ClientInfo::new("127.0.0.1:6053".parse().unwrap(), false);
+        client.state = ConnectionState::Authenticated;
+        client
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn answered_pings_keep_connection() {
+        let (requests_tx, mut requests) = mpsc::channel(1);
+        let (sender, mut messages) = mpsc::unbounded_channel();
+        let (_stop_tx, stop) = watch::channel(false);
+        let mut client = hello_client();
+
+        let client_loop = async {
+            for _ in 0..=MAX_MISSED_PINGS {
+                let ping = messages.recv().await;
+                assert!(matches!(ping, Some(ProtoMessage::PingRequest(_))));
+
+                let pong = ProtoMessage::PingResponse(PingResponse::default());
+                requests_tx.send(Ok(pong)).await.unwrap();
+            }
+            drop(requests_tx);
+        };
+
+        let started = tokio::time::Instant::now();
+        let (result, ()) = tokio::join!(
+            message_loop(&mut requests, ClientSender(sender), &mut client, &NoObserver, &NoRequests, stop),
+            client_loop
+        );
+
+        assert!(result.is_ok());
+        assert_eq!(client.message_count, MAX_MISSED_PINGS + 1);
+        // still open after more intervals than the missed ping limit
+        assert_eq!(started.elapsed(), PING_INTERVAL * (MAX_MISSED_PINGS + 1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn missed_pings_close_connection() {
+        let (_requests_tx, mut requests) = mpsc::channel(1);
+        let (sender, mut messages) = mpsc::unbounded_channel();
+        let (_stop_tx, stop) = watch::channel(false);
+        let mut client = hello_client();
+
+        let started = tokio::time::Instant::now();
+        let result = message_loop(&mut requests, ClientSender(sender), &mut client, &NoObserver, &NoRequests, stop).await;
+        assert!(result.is_ok());
+
+        // a ping each interval, then one more interval without a reply closes
+        assert_eq!(started.elapsed(), PING_INTERVAL * (MAX_MISSED_PINGS + 1));
+
+        let mut pings = 0;
+        while let Ok(message) = messages.try_recv() {
+            assert!(matches!(message, ProtoMessage::PingRequest(_)));
+            pings += 1;
+        }
+        assert_eq!(pings, MAX_MISSED_PINGS);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn no_pings_before_hello() {
+        let (_requests_tx, mut requests) = mpsc::channel(1);
+        let (sender, mut messages) = mpsc::unbounded_channel();
+        let (_stop_tx, stop) = watch::channel(false);
+        let mut client = ClientInfo::new("127.0.0.1:6053".parse().unwrap(), false);
+
+        let result = message_loop(&mut requests, ClientSender(sender), &mut client, &NoObserver, &NoRequests, stop).await;
+        assert!(result.is_ok());
+        assert!(messages.try_recv().is_err());
+    }
+}
@@ -11,7 +11,7 @@ fn main() -> Result<()> {
         .parse_default_env()
         .init();
 
-    let backplate = BackplateConnection::open("/dev/ttyO2")?;
+    let backplate = BackplateConnection::open("/dev/ttyO2", BackplateConnection::DEFAULT_READ_TIMEOUT)?;
 
     // This triggers a constant stream of messages
     backplate.send_command(BackplateCmd::StatusRequest)?;
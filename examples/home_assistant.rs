@@ -1,17 +1,39 @@
-use std::{io, thread};
+use std::io;
 
 use anyhow::Result;
 use esphome_api::{
+    entity::{ClimateEntity, EntityKey, EntityRegistry},
     proto::*,
     server::{
         DefaultHandler, EncryptedStreamProvider, MessageSender, RequestHandler,
-        ResponseStatus, start_server
+        ResponseStatus, spawn_server
     }
 };
 
 fn main() -> Result<()> {
+    let mut registry = EntityRegistry::new();
+    let climate = registry.add(
+        ClimateEntity::new()
+            .modes(&[
+                ClimateMode::Off,
+                ClimateMode::Heat,
+                ClimateMode::Cool,
+                ClimateMode::HeatCool
+            ])
+            .temperature_range(9.0, 32.0, 0.5)
+            .fan_modes(&[
+                ClimateFanMode::ClimateFanOn,
+                ClimateFanMode::ClimateFanOff,
+                ClimateFanMode::ClimateFanAuto
+            ])
+            .features(
+                ClimateFeature::SUPPORTS_CURRENT_TEMPERATURE |
+                ClimateFeature::SUPPORTS_ACTION
+            )
+    );
+
     let handler = DefaultHandler {
-        delegate: MyRequestHandler { },
+        delegate: MyRequestHandler { registry, climate },
         server_info: "Nest App 0.0.1".to_string(),
         node_name: "hallway-thermostat".to_string(),
         friendly_name: "Hallway Thermostat".to_string(),
@@ -27,11 +49,13 @@ fn main() -> Result<()> {
     )?;
 
     let message_sender = MessageSender::new();
-    let message_sender_clone = message_sender.clone();
 
-    thread::spawn(move || {
-        start_server("0.0.0.0:6053", &stream_factory, &message_sender_clone, &handler).unwrap();
-    });
+    let server = spawn_server(
+        "0.0.0.0:6053",
+        stream_factory,
+        message_sender.clone(),
+        handler
+    )?;
 
     loop {
         println!("Enter current temp to send");
@@ -51,13 +75,18 @@ fn main() -> Result<()> {
         message.current_temperature = temp;
         message.target_temperature = 19.5;
 
-        message_sender.send_message(ProtoMessage::ClimateStateResponse(message))?;
+        message_sender.send_message(climate.state(message))?;
     }
 
+    server.stop();
+
     Ok(())
 }
 
-struct MyRequestHandler;
+struct MyRequestHandler {
+    registry: EntityRegistry,
+    climate: EntityKey<ClimateEntity>
+}
 
 impl RequestHandler for MyRequestHandler {
     fn handle_request<W: MessageWriter>(
@@ -67,31 +96,7 @@ impl RequestHandler for MyRequestHandler {
     ) -> Result<ResponseStatus> {
         match message {
             ProtoMessage::ListEntitiesRequest(_) => {
-                let mut message = ListEntitiesClimateResponse::default();
-                message.object_id = "test_climate_id".to_string();
-                message.supported_modes = vec![
-                    ClimateMode::Off as i32,
-                    ClimateMode::Heat as i32,
-                    ClimateMode::Cool as i32,
-                    ClimateMode::HeatCool as i32
-                ];
-                message.visual_min_temperature = 9.0;
-                message.visual_max_temperature = 32.0;
-                message.visual_target_temperature_step = 0.5;
-                message.visual_current_temperature_step = 0.5;
-                message.supported_fan_modes = vec![
-                    ClimateFanMode::ClimateFanOn  as i32,
-                    ClimateFanMode::ClimateFanOff as i32,
-                    ClimateFanMode::ClimateFanAuto as i32
-                ];
-                message.feature_flags =
-                    ClimateFeature::SUPPORTS_CURRENT_TEMPERATURE |
-                    ClimateFeature::SUPPORTS_ACTION;
-
-                writer.write(&ProtoMessage::ListEntitiesClimateResponse(message))?;
-
-                let message = ListEntitiesDoneResponse::default();
-                writer.write(&ProtoMessage::ListEntitiesDoneResponse(message))?;
+                self.registry.write_entities("test_climate_id", writer)?;
             }
             ProtoMessage::SubscribeStatesRequest(_) => {
                 let mut message = ClimateStateResponse::default();
@@ -101,7 +106,7 @@ impl RequestHandler for MyRequestHandler {
                 message.current_temperature = 20.0;
                 message.target_temperature = 19.5;
 
-                writer.write(&ProtoMessage::ClimateStateResponse(message))?;
+                writer.write(&self.climate.state(message))?;
             }
             _ => { }
         }
@@ -0,0 +1,152 @@
+// Decode backplate messages from a serial port, or a capture file written by
+// `BackplateConnection::open_capture` (plain hex dumps work too).
+//
+// cargo run -p nest-backplate --example decode -- /dev/ttyO2 [seconds]
+// cargo run -p nest-backplate --example decode -- capture.txt
+
+use std::{
+    collections::BTreeMap,
+    env,
+    fs,
+    os::unix::fs::FileTypeExt,
+    path::Path,
+    time::{Duration, Instant}
+};
+
+use nest_backplate::{
+    BackplateCmd, BackplateConnection, BackplateError, BackplateResponse, Direction, Framer, LinkStats,
+    Message, read_capture
+};
+
+fn main() -> Result<(), BackplateError> {
+    let args: Vec<String> = env::args().collect();
+    let Some(path) = args.get(1) else {
+        eprintln!("Usage: {} <serial port | capture file> [seconds]", args[0]);
+        std::process::exit(1);
+    };
+
+    let mut stats = DecodeStats::default();
+
+    if fs::metadata(path)?.file_type().is_char_device() {
+        let seconds = args.get(2).and_then(|s| s.parse().ok()).unwrap_or(60);
+        decode_serial(path, Duration::from_secs(seconds), &mut stats)?;
+    } else {
+        decode_file(Path::new(path), &mut stats)?;
+    }
+
+    stats.print();
+
+    Ok(())
+}
+
+/// Read from the backplate until `duration` has passed
+fn decode_serial(path: &str, duration: Duration, stats: &mut DecodeStats) -> Result<(), BackplateError> {
+    let mut backplate = BackplateConnection::open(path, BackplateConnection::DEFAULT_READ_TIMEOUT)?;
+    backplate.send_command(BackplateCmd::StatusRequest)?;
+
+    let start = Instant::now();
+    while start.elapsed() < duration {
+        let millis = start.elapsed().as_millis() as u64;
+        match backplate.poll_message(Duration::ZERO) {
+            Ok(Some(response)) => stats.response(millis, &response),
+            Ok(None) => { }
+            Err(e) => stats.error(millis, &e)
+        }
+    }
+
+    stats.link = backplate.stats();
+    Ok(())
+}
+
+fn decode_file(path: &Path, stats: &mut DecodeStats) -> Result<(), BackplateError> {
+    let mut framer = Framer::default();
+
+    for record in read_capture(path)? {
+        if record.direction == Direction::Write {
+            println!("{} -> {}", timestamp(record.millis), hex(&record.data));
+            continue;
+        }
+
+        framer.push(&record.data);
+        loop {
+            match framer.next_message() {
+                Ok(Some(message)) => stats.message(record.millis, message),
+                Ok(None) => break,
+                Err(e) => {
+                    stats.error(record.millis, &e);
+                    break;
+                }
+            }
+        }
+    }
+
+    stats.link = framer.stats();
+    Ok(())
+}
+
+/// Message counts by command id, for spotting messages not decoded yet
+#[derive(Default)]
+struct DecodeStats {
+    decoded: usize,
+    unknown: BTreeMap<u16, usize>,
+    errors: usize,
+    link: LinkStats
+}
+
+impl DecodeStats {
+    fn message(&mut self, millis: u64, message: Message) {
+        match BackplateResponse::try_from(message) {
+            Ok(response) => self.response(millis, &response),
+            Err(e) => self.error(millis, &e)
+        }
+    }
+
+    fn response(&mut self, millis: u64, response: &BackplateResponse) {
+        match response {
+            BackplateResponse::Raw(message) => {
+                *self.unknown.entry(message.command_id).or_default() += 1;
+                println!(
+                    "{} <- unknown {:#06x} [{}]",
+                    timestamp(millis), message.command_id, hex(&message.payload)
+                );
+            }
+            response => {
+                self.decoded += 1;
+                println!("{} <- {:?}", timestamp(millis), response);
+            }
+        }
+    }
+
+    fn error(&mut self, millis: u64, error: &BackplateError) {
+        self.errors += 1;
+        println!("{} !! {}", timestamp(millis), error);
+    }
+
+    fn print(&self) {
+        println!();
+        println!("Decoded messages: {}", self.decoded);
+        println!("Decode errors: {}", self.errors);
+        println!(
+            "CRC errors: {}, resyncs: {}, discarded bytes: {}",
+            self.link.crc_errors, self.link.resyncs, self.link.discarded_bytes
+        );
+
+        if !self.unknown.is_empty() {
+            println!("Unknown commands:");
+            for (command_id, count) in &self.unknown {
+                println!("  {command_id:#06x}: {count}");
+            }
+        }
+    }
+}
+
+fn timestamp(millis: u64) -> String {
+    format!("[{:>4}.{:03}]", millis / 1000, millis % 1000)
+}
+
+fn hex(data: &[u8]) -> String {
+    data.iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
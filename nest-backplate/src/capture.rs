@@ -0,0 +1,269 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    collections::VecDeque,
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+    path::Path,
+    sync::{Arc, Mutex, PoisonError},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH}
+};
+
+use crate::{BackplateError, Result};
+
+const READ_MARKER: &str = "<";
+const WRITE_MARKER: &str = ">";
+
+/// Capture file shared by the reading and writing side of a connection.
+/// Each line is the milliseconds since the port was opened, `<` for data
+/// read from the backplate or `>` for data written to it, and the bytes in
+/// hex, e.g. `1534 < d5d5aa96010003...`
+///
+/// Each connection is appended to the file after a `#` comment line, so a
+/// reconnect doesn't lose what was captured before it.
+pub(crate) struct CaptureLog {
+    file: Mutex<BufWriter<File>>,
+    start: Instant
+}
+
+impl CaptureLog {
+    pub(crate) fn create(path: &Path) -> Result<Arc<Self>> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let mut file = BufWriter::new(file);
+
+        let opened = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        writeln!(file, "# port opened at unix time {}", opened.as_secs())?;
+        file.flush()?;
+
+        Ok(Arc::new(Self {
+            file: Mutex::new(file),
+            start: Instant::now()
+        }))
+    }
+
+    fn record(&self, marker: &str, data: &[u8]) -> io::Result<()> {
+        let millis = self.start.elapsed().as_millis();
+        let hex: String = data.iter().map(|b| format!("{b:02x}")).collect();
+
+        // flush each line, so the capture is intact if the process dies
+        let mut file = self.file.lock().unwrap_or_else(PoisonError::into_inner);
+        writeln!(file, "{millis} {marker} {hex}")?;
+        file.flush()
+    }
+}
+
+/// Copies everything read from `inner` to the capture log
+pub(crate) struct CaptureReader<R> {
+    inner: R,
+    log: Arc<CaptureLog>
+}
+
+impl<R> CaptureReader<R> {
+    pub(crate) fn new(inner: R, log: Arc<CaptureLog>) -> Self {
+        Self { inner, log }
+    }
+}
+
+impl<R: Read> Read for CaptureReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.log.record(READ_MARKER, &buf[..len])?;
+        Ok(len)
+    }
+}
+
+/// Copies everything written to `inner` to the capture log
+pub(crate) struct CaptureWriter<W> {
+    inner: W,
+    log: Arc<CaptureLog>
+}
+
+impl<W> CaptureWriter<W> {
+    pub(crate) fn new(inner: W, log: Arc<CaptureLog>) -> Self {
+        Self { inner, log }
+    }
+}
+
+impl<W: Write> Write for CaptureWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = self.inner.write(buf)?;
+        self.log.record(WRITE_MARKER, &buf[..len])?;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Which way data went over the serial port
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    /// Sent by the backplate
+    Read,
+    /// Sent to the backplate
+    Write
+}
+
+/// Data read or written at one time, from a capture file
+#[derive(Debug, Clone)]
+pub struct CaptureRecord {
+    /// Milliseconds since the capture started
+    pub millis: u64,
+    pub direction: Direction,
+    pub data: Vec<u8>
+}
+
+/// Read a capture file, see [`BackplateConnection::open_capture`](crate::BackplateConnection::open_capture).
+/// Lines with only hex bytes, optionally space separated, are read data at
+/// the time of the previous line, so a plain hex dump of the serial port
+/// can also be decoded or replayed. Lines starting with `#` are comments.
+///
+/// Times start over at each connection appended to the file, those are
+/// moved after the previous connection so record times keep increasing.
+pub fn read_capture(path: &Path) -> Result<Vec<CaptureRecord>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut records = Vec::new();
+    let mut millis = 0;
+    // end of the previous connection in the file
+    let mut offset = 0;
+
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let record = match fields[..] {
+            [time, marker @ (READ_MARKER | WRITE_MARKER), hex] if time.parse::<u64>().is_ok() => {
+                let time: u64 = time.parse().unwrap_or_default();
+                if time + offset < millis {
+                    offset = millis;
+                }
+                millis = time + offset;
+                let direction = if marker == READ_MARKER { Direction::Read } else { Direction::Write };
+                parse_hex(hex).map(|data| CaptureRecord { millis, direction, data })
+            }
+            _ => parse_hex(&fields.concat()).map(|data| CaptureRecord {
+                millis,
+                direction: Direction::Read,
+                data
+            })
+        };
+
+        records.push(record.ok_or(BackplateError::InvalidCapture(idx + 1))?);
+    }
+
+    Ok(records)
+}
+
+/// Reads the data from a capture file, in the chunks it was captured.
+/// Once the capture runs out reads time out, like a silent backplate.
+pub(crate) struct ReplayReader {
+    chunks: VecDeque<(Duration, Vec<u8>)>,
+    /// Sleep until each chunk's capture time, instead of returning it immediately
+    paced: bool,
+    start: Instant
+}
+
+impl ReplayReader {
+    pub(crate) fn open(path: &Path, paced: bool) -> Result<Self> {
+        let chunks = read_capture(path)?
+            .into_iter()
+            .filter(|record| record.direction == Direction::Read)
+            .map(|record| (Duration::from_millis(record.millis), record.data))
+            .collect();
+
+        Ok(Self { chunks, paced, start: Instant::now() })
+    }
+}
+
+impl Read for ReplayReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let Some((at, data)) = self.chunks.front_mut() else {
+            return Err(io::ErrorKind::TimedOut.into());
+        };
+
+        if self.paced {
+            thread::sleep(at.saturating_sub(self.start.elapsed()));
+        }
+
+        // a chunk larger than `buf` is returned over several reads
+        let len = data.len().min(buf.len());
+        buf[..len].copy_from_slice(&data[..len]);
+        data.drain(..len);
+
+        if data.is_empty() {
+            self.chunks.pop_front();
+        }
+
+        Ok(len)
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.is_ascii() || !hex.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reconnect_appends_capture() {
+        let path = std::env::temp_dir()
+            .join(format!("nest-backplate-capture-{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        for data in [[0x01], [0x02]] {
+            let log = CaptureLog::create(&path).unwrap();
+            log.record(READ_MARKER, &data).unwrap();
+        }
+
+        let records = read_capture(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let data: Vec<_> = records.iter().map(|record| record.data.clone()).collect();
+        assert_eq!(data, vec![vec![0x01], vec![0x02]]);
+        assert!(records[0].millis <= records[1].millis);
+    }
+
+    #[test]
+    fn appended_times_keep_increasing() {
+        let path = std::env::temp_dir()
+            .join(format!("nest-backplate-capture-times-{}.txt", std::process::id()));
+        std::fs::write(&path, "# first\n0 < 01\n500 < 02\n# second\n0 < 03\n20 < 04\n").unwrap();
+
+        let records = read_capture(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let millis: Vec<_> = records.iter().map(|record| record.millis).collect();
+        assert_eq!(millis, vec![0, 500, 500, 520]);
+    }
+}
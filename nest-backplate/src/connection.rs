@@ -16,70 +16,128 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::io::{BufReader, Read};
+use std::{
+    io::{self, BufReader, Read, Write},
+    path::Path,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant}
+};
 
 use bytes::{BufMut, Bytes};
-use log::{debug, trace};
+use log::{debug, trace, warn};
 use serial2::{SerialPort, Settings};
 
-use crate::{BackplateCmd, BackplateError, BackplateResponse, Message, Result};
+use crate::{
+    BackplateCmd, BackplateError, BackplateResponse, Message, Result, WireProfile,
+    capture::{CaptureLog, CaptureReader, CaptureWriter, ReplayReader}
+};
 
 pub struct BackplateConnection {
-    port: SerialPort,
+    writer: BackplateWriter,
     reader: MessageReader,
     ack_payload: Option<Vec<u8>>,
 }
 
 impl BackplateConnection {
+    /// Read timeout when none is configured, long enough for the reset
+    /// sequence on connect
+    pub const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(3);
+
     pub fn send_command(&self, cmd: BackplateCmd) -> Result<()> {
-        let message: Message = cmd.into();
-        let message_data = message.to_bytes();
-        trace!("Write {:x?}", &message_data[..]);
-        self.port.write(&message_data)?;
-        Ok(())
+        self.writer.send_command(cmd)
+    }
+
+    /// Handle for sending commands from another thread, while this
+    /// connection is blocked reading
+    pub fn writer(&self) -> BackplateWriter {
+        self.writer.clone()
     }
 
     /// Read message from backplate. This method will not block forever. It will
-    /// return a timeout error.
+    /// return a timeout error when nothing is received within the read timeout
+    /// given when opening.
     pub fn read_message(&mut self) -> Result<BackplateResponse> {
-        if let Some(message) = self.reader.read_message()? {
-            // Save payload of WirePowerPresence for sending reset sequence ACK
-            if message.command_id == Message::WIRE_POWER_PRESENCE_ID {
-                self.ack_payload = Some(message.payload.clone());
-            }
+        let message = self.reader.read_message()?;
 
-            Ok(message.try_into()?)
-        } else {
-            // There is more data to read (parial message) when read_message()
-            // returns `None`.
-            self.read_message()
+        // Save payload of WirePowerPresence for sending reset sequence ACK
+        if message.command_id == Message::WIRE_POWER_PRESENCE_ID {
+            self.ack_payload = Some(message.payload.clone());
+        }
+
+        BackplateResponse::decode(message, self.wire_profile())
+    }
+
+    /// Wire ids used for switching and decoding switch acks, standard ids
+    /// until set
+    pub fn wire_profile(&self) -> WireProfile {
+        *self.writer.wire_profile.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Change the wire ids, for this connection and all its writers
+    pub fn set_wire_profile(&self, profile: WireProfile) {
+        *self.writer.wire_profile.lock().unwrap_or_else(PoisonError::into_inner) = profile;
+    }
+
+    /// Wait up to `timeout` for a message, or `None` when nothing arrives.
+    /// Each read waits at most the read timeout given when opening, so this
+    /// can return up to that much later than `timeout`.
+    ///
+    /// Commands don't wait on this, send them from a [`writer`](Self::writer)
+    /// on another thread to have them go out while no messages arrive.
+    pub fn poll_message(&mut self, timeout: Duration) -> Result<Option<BackplateResponse>> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            match self.read_message() {
+                Ok(message) => return Ok(Some(message)),
+                Err(e) if e.is_timeout() && Instant::now() < deadline => { }
+                Err(e) if e.is_timeout() => return Ok(None),
+                Err(e) => return Err(e)
+            }
         }
     }
 
-    pub fn open(path: &str) -> Result<Self> {
-        let port = SerialPort::open(path, |mut settings: Settings| {
-            settings.set_raw();
-            settings.set_baud_rate(115200)?;
-            Ok(settings)
-        })?;
+    /// Serial link error counters since the connection was opened
+    pub fn stats(&self) -> LinkStats {
+        self.reader.framer.stats()
+    }
 
-        // Nest Hacking wiki has tcsendbreak(fd, 1), Cuckoo Nest uses tcsendbreak(fd, 0)
-        // Duration(1) = 1ms, Duration(0) = at least 250ms (< 500ms)
-        // In my testing there doesn't appear to be any need for a delay at all.
-        // In fact, when removing the `set_break` calls, everything still works
-        // but I'm leaving them there since it doesn't hurt anything either.
-        port.set_break(true)?;
-        // thread::sleep(Duration::from_millis(250));
-        port.set_break(false)?;
+    /// Open the backplate serial port, with reads returning a timeout error
+    /// after waiting `read_timeout` for data
+    pub fn open(path: &str, read_timeout: Duration) -> Result<Self> {
+        let port = open_port(path)?;
+        let reader = reader_port(&port, read_timeout)?;
+        Self::connect(Box::new(reader), Box::new(port))
+    }
 
-        // Seems to help reduce (not eliminate) unexpected data in first few reads
-        port.discard_buffers()?;
+    /// Open the backplate like [`open`](Self::open), logging all serial
+    /// traffic to `capture_path` for replaying later
+    pub fn open_capture(path: &str, capture_path: &Path, read_timeout: Duration) -> Result<Self> {
+        let log = CaptureLog::create(capture_path)?;
+        let port = open_port(path)?;
+        let reader = CaptureReader::new(reader_port(&port, read_timeout)?, log.clone());
+        let writer = CaptureWriter::new(port, log);
+        Self::connect(Box::new(reader), Box::new(writer))
+    }
 
-        let reader = MessageReader::new(&port)?;
+    /// Connect to a backplate replaying the reads in a capture file, see
+    /// [`open_capture`](Self::open_capture). Commands are discarded, and
+    /// reads time out once the capture runs out. When `paced` reads are
+    /// delayed to match the capture timing, otherwise they return immediately.
+    pub fn replay(capture_path: &Path, paced: bool) -> Result<Self> {
+        let reader = ReplayReader::open(capture_path, paced)?;
+        Self::connect(Box::new(reader), Box::new(io::sink()))
+    }
+
+    fn connect(reader: Box<dyn Read + Send>, writer: Box<dyn Write + Send>) -> Result<Self> {
+        let writer = BackplateWriter {
+            port: Arc::new(Mutex::new(writer)),
+            wire_profile: Arc::default()
+        };
 
         let mut backplate = BackplateConnection {
-            port,
-            reader,
+            writer,
+            reader: MessageReader::new(reader),
             ack_payload: None,
         };
 
@@ -123,67 +181,330 @@ impl BackplateConnection {
     }
 }
 
+fn open_port(path: &str) -> Result<SerialPort> {
+    let port = SerialPort::open(path, |mut settings: Settings| {
+        settings.set_raw();
+        settings.set_baud_rate(115200)?;
+        Ok(settings)
+    })?;
+
+    // Nest Hacking wiki has tcsendbreak(fd, 1), Cuckoo Nest uses tcsendbreak(fd, 0)
+    // Duration(1) = 1ms, Duration(0) = at least 250ms (< 500ms)
+    // In my testing there doesn't appear to be any need for a delay at all.
+    // In fact, when removing the `set_break` calls, everything still works
+    // but I'm leaving them there since it doesn't hurt anything either.
+    port.set_break(true)?;
+    // thread::sleep(Duration::from_millis(250));
+    port.set_break(false)?;
+
+    // Seems to help reduce (not eliminate) unexpected data in first few reads
+    port.discard_buffers()?;
+
+    Ok(port)
+}
+
+/// Handle for reading from `port`, the timeout only applies to this handle
+fn reader_port(port: &SerialPort, read_timeout: Duration) -> Result<SerialPort> {
+    let mut reader = port.try_clone()?;
+    reader.set_read_timeout(read_timeout)?;
+    Ok(reader)
+}
+
+
+/// Sends commands to the backplate, see [`BackplateConnection::writer`]
+#[derive(Clone)]
+pub struct BackplateWriter {
+    port: Arc<Mutex<Box<dyn Write + Send>>>,
+    wire_profile: Arc<Mutex<WireProfile>>
+}
+
+impl BackplateWriter {
+    pub fn send_command(&self, cmd: BackplateCmd) -> Result<()> {
+        let message = match cmd {
+            BackplateCmd::SwitchWire(wire, enabled) => {
+                let profile = *self.wire_profile.lock().unwrap_or_else(PoisonError::into_inner);
+                Message::switch_wire(wire, enabled, profile)
+            }
+            cmd => cmd.into()
+        };
+        let message_data = message.to_bytes();
+        trace!("Write {:x?}", &message_data[..]);
+
+        // lock so messages written from different threads don't interleave
+        let mut port = self.port.lock().unwrap_or_else(PoisonError::into_inner);
+        port.write_all(&message_data)?;
+        Ok(())
+    }
+
+    /// Discard commands sent from now on, through any handle to this
+    /// connection. Waits for a command being written to finish.
+    pub fn close(&self) {
+        let mut port = self.port.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Err(e) = port.flush() {
+            debug!("Flush on close failed: {e}");
+        }
+        *port = Box::new(io::sink());
+    }
+}
+
+/// Serial link error counters, see [`BackplateConnection::stats`]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LinkStats {
+    /// Frames dropped for a checksum mismatch
+    pub crc_errors: u32,
+    /// Times data was dropped to find the next message preamble
+    pub resyncs: u32,
+    /// Bytes dropped while resynchronizing
+    pub discarded_bytes: u64,
+    /// Times buffered data was dropped for exceeding the buffer limit
+    pub overflows: u32
+}
+
+/// Bytes requested from the serial port per read
+const READ_CHUNK_LEN: usize = 512;
 
 struct MessageReader {
-    reader: BufReader<SerialPort>,
-    buffer: Vec<u8>
+    reader: BufReader<Box<dyn Read + Send>>,
+    framer: Framer
 }
 
 impl MessageReader {
-    fn new(stream: &SerialPort) -> Result<Self> {
-        Ok(Self {
-            reader: BufReader::new(stream.try_clone()?),
-            buffer: Vec::new()
-        })
+    fn new(stream: Box<dyn Read + Send>) -> Self {
+        Self {
+            reader: BufReader::new(stream),
+            framer: Framer::default()
+        }
     }
 
     fn fill_buffer(&mut self) -> Result<usize> {
-        let mut buf = vec![0; 512];
+        let mut buf = [0; READ_CHUNK_LEN];
         let len = self.reader.read(&mut buf)?;
-        self.buffer.put(&buf[..len]);
+        self.framer.push(&buf[..len]);
         trace!("Read {:x?}", &buf[..len]);
         Ok(len)
     }
 
-    fn read_message(&mut self) -> Result<Option<Message>> {
-        // read from stream and append to self.buffer
-        if self.buffer.len() < Message::MIN_RAW_LEN {
+    /// Read until a whole message is received, or the read times out
+    fn read_message(&mut self) -> Result<Message> {
+        loop {
+            if let Some(message) = self.framer.next_message()? {
+                return Ok(message);
+            }
+
             self.fill_buffer()?;
         }
+    }
+}
 
-        trace!("Buffered {:x?}", &self.buffer[..]);
+/// Splits the serial byte stream into messages. Data before a preamble, and
+/// frames with a corrupt length or checksum, are discarded.
+#[derive(Default)]
+pub struct Framer {
+    buffer: Vec<u8>,
+    stats: LinkStats,
+    /// Dropping data, counted as one resync until the next good message
+    resyncing: bool
+}
+
+impl Framer {
+    /// Most data held while waiting for a whole message, enough for the
+    /// largest message plus a read. Only reached when data is pushed without
+    /// taking the messages, the oldest data is dropped to stay under it.
+    pub const MAX_BUFFER_LEN: usize = Message::MIN_RAW_LEN + Message::MAX_PAYLOAD_LEN + READ_CHUNK_LEN;
+
+    pub fn push(&mut self, data: &[u8]) {
+        self.buffer.put(data);
 
-        // search for preamble in buffer
-        let preamble_pos = self.buffer
-            .windows(4) // 4-byte chunks; size of preamble
-            .enumerate()
-            .find(|(_, data)| *data == &Message::PREAMBLE_READ)
-            .map(|(idx, _)| idx);
+        if self.buffer.len() > Self::MAX_BUFFER_LEN {
+            warn!("Message buffer full, dropping oldest data");
+            self.stats.overflows += 1;
+            self.discard(self.buffer.len() - Self::MAX_BUFFER_LEN);
+        }
+    }
 
-        if let Some(idx) = preamble_pos {
-            // discard any data before preamble
-            if idx > 0 {
-                trace!("Discarding unexpected data {:x?}", &self.buffer[..idx]);
-                self.buffer.drain(..idx);
+    /// Error counters for the data pushed so far
+    pub fn stats(&self) -> LinkStats {
+        self.stats
+    }
 
-                // after discarding data, it's possible buffer is below Message::MIN_RAW_LEN
-                // start again to perform buffer length check
-                return self.read_message();
+    /// Next message in the buffer, or `None` when more data is needed
+    pub fn next_message(&mut self) -> Result<Option<Message>> {
+        loop {
+            trace!("Buffered {:x?}", &self.buffer[..]);
+
+            let preamble_len = Message::PREAMBLE_READ.len();
+
+            // search for preamble in buffer
+            let preamble_pos = self.buffer
+                .windows(preamble_len)
+                .position(|data| data == Message::PREAMBLE_READ);
+
+            let Some(idx) = preamble_pos else {
+                // keep the tail, it may be the start of a preamble
+                let keep = self.buffer.len().min(preamble_len - 1);
+                self.discard(self.buffer.len() - keep);
+                return Ok(None);
+            };
+
+            self.discard(idx);
+
+            match Message::parse(Bytes::copy_from_slice(&self.buffer)) {
+                Ok(Some((len, message))) => {
+                    trace!("Parsed message, consumed {} bytes from buffer", len);
+                    self.buffer.drain(..len);
+                    self.resyncing = false;
+                    return Ok(Some(message));
+                }
+                Ok(None) => return Ok(None),
+                Err(e) if e.is_corrupt_frame() => {
+                    // skip this preamble and resync on the next one
+                    warn!("Discarding corrupt message: {e}");
+                    if matches!(e, BackplateError::ChecksumMismatch { .. }) {
+                        self.stats.crc_errors += 1;
+                    }
+                    self.discard(1);
+                }
+                Err(e) => return Err(e)
             }
+        }
+    }
 
-            let message_data = Bytes::from(self.buffer.clone());
-            if let Some((len, message)) = Message::parse(message_data)? {
-                trace!("Parsed message, consumed {} bytes from buffer", len);
-                // remove parsed message data from buffer
-                self.buffer.drain(..len);
-                return Ok(Some(message))
-            } else {
-                // buffer doesn't contain full messages, read and try again
-                self.fill_buffer()?;
-                return self.read_message();
+    fn discard(&mut self, len: usize) {
+        if len > 0 {
+            trace!("Discarding unexpected data {:x?}", &self.buffer[..len]);
+            self.buffer.drain(..len);
+
+            self.stats.discarded_bytes += len as u64;
+            if !self.resyncing {
+                self.stats.resyncs += 1;
+                self.resyncing = true;
             }
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn framer_resync() {
+        let message = Message::with_payload(0x0002, vec![1, 2, 3]).to_bytes();
+        // written messages have a 3 byte preamble, read messages have 4
+        let mut frame = vec![0xd5];
+        frame.extend_from_slice(&message);
+
+        let mut corrupt = frame.clone();
+        corrupt[9] ^= 0xff;
+
+        let mut framer = Framer::default();
+
+        // garbage, corrupt checksum, then a partial message
+        framer.push(&[0x00, 0x42]);
+        framer.push(&corrupt);
+        framer.push(&frame[..6]);
+        assert!(framer.next_message().unwrap().is_none());
+
+        framer.push(&frame[6..]);
+        let parsed = framer.next_message().unwrap().unwrap();
+        assert_eq!(parsed.command_id, 0x0002);
+        assert_eq!(parsed.payload, vec![1, 2, 3]);
+        assert!(framer.buffer.is_empty());
+
+        assert_eq!(framer.stats, LinkStats {
+            crc_errors: 1,
+            resyncs: 1,
+            discarded_bytes: 2 + corrupt.len() as u64,
+            overflows: 0
+        });
+    }
+
+    #[test]
+    fn framer_overflow() {
+        let mut framer = Framer::default();
+
+        // a header for the largest message, which never completes
+        let header = read_frame(0x0002, &[0; Message::MAX_PAYLOAD_LEN]);
+        framer.push(&header[..8]);
+        for _ in 0..10 {
+            framer.push(&[0; READ_CHUNK_LEN]);
+            assert!(framer.buffer.len() <= Framer::MAX_BUFFER_LEN);
+        }
+        assert!(framer.stats.overflows > 0);
+
+        // the dropped header is followed by garbage, resyncs on the next message
+        framer.push(&read_frame(0x0002, &[1, 2, 3]));
+        let parsed = framer.next_message().unwrap().unwrap();
+        assert_eq!(parsed.payload, vec![1, 2, 3]);
+    }
+
+    fn read_frame(command_id: u16, payload: &[u8]) -> Vec<u8> {
+        let mut frame = vec![0xd5];
+        frame.extend_from_slice(&Message::with_payload(command_id, payload.to_vec()).to_bytes());
+        frame
+    }
+
+    fn hex(data: &[u8]) -> String {
+        data.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    #[test]
+    fn replay_capture() {
+        let presence = read_frame(Message::WIRE_POWER_PRESENCE_ID, &[1; 12]);
+        let brk = read_frame(0x0001, b"BRK");
+        let text = read_frame(0x0001, b"hello");
+
+        // the last message is split over two reads
+        let capture = format!(
+            "# reset sequence\n0 > d5aa96\n10 < {}{}\n20 < {}\n\n30 < {}\n",
+            hex(&presence), hex(&brk), hex(&text[..5]), hex(&text[5..])
+        );
+
+        let path = std::env::temp_dir()
+            .join(format!("nest-backplate-replay-{}.txt", std::process::id()));
+        std::fs::write(&path, capture).unwrap();
+
+        let mut backplate = BackplateConnection::replay(&path, false).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let message = backplate.read_message().unwrap();
+        assert!(matches!(message, BackplateResponse::Text(s) if s == "hello"));
+
+        // capture ran out
+        let error = backplate.read_message().unwrap_err();
+        assert!(error.is_timeout());
+        assert!(backplate.poll_message(Duration::ZERO).unwrap().is_none());
+    }
+
+    /// Port shared with the test, so writes can be checked
+    #[derive(Clone, Default)]
+    struct SharedPort(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedPort {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn closed_writer_discards() {
+        let port = SharedPort::default();
+        let writer = BackplateWriter {
+            port: Arc::new(Mutex::new(Box::new(port.clone()))),
+            wire_profile: Arc::default()
+        };
+
+        writer.send_command(BackplateCmd::StatusRequest).unwrap();
+        let written = port.0.lock().unwrap().len();
+        assert!(written > 0);
 
-        Ok(None)
+        // closing one handle closes them all
+        writer.clone().close();
+        writer.send_command(BackplateCmd::StatusRequest).unwrap();
+        assert_eq!(port.0.lock().unwrap().len(), written);
     }
 }
@@ -16,6 +16,8 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+mod capture;
+pub use capture::{CaptureRecord, Direction, read_capture};
 mod connection;
 pub use connection::*;
 mod message;
@@ -35,8 +37,26 @@ pub enum BackplateError {
     PayloadLength { id: u16, expected: usize, found: usize },
     #[error("Message buffer underrun `{0}`")]
     BufferUnderrun(#[from] bytes::TryGetError),
+    #[error("Message data doesn't start with preamble")]
+    InvalidPreamble,
+    #[error("Message `{id:x}` payload length {len} exceeds max {max}")]
+    PayloadTooLong { id: u16, len: usize, max: usize },
     #[error("Reset missing ACK payload")]
     ResetAck,
+    #[error("Invalid capture file line {0}")]
+    InvalidCapture(usize),
+}
+
+impl BackplateError {
+    /// Frame is damaged, reading can resume at the next preamble
+    pub fn is_corrupt_frame(&self) -> bool {
+        matches!(self, Self::ChecksumMismatch { .. } | Self::PayloadTooLong { .. })
+    }
+
+    /// Read timed out waiting for data, e.g. while the backplate is quiet
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, Self::IoError(e) if e.kind() == std::io::ErrorKind::TimedOut)
+    }
 }
 
 pub type Result<T> = std::result::Result<T, BackplateError>;
@@ -34,6 +34,10 @@ impl Message {
     /// Preamble(4) + Cmd(2) + Len(2) + CRC(2)
     pub(crate) const MIN_RAW_LEN: usize = 10;
 
+    /// Largest payload accepted, longer lengths are from a corrupt header.
+    /// Sensor buffer responses are the largest messages seen, well under this.
+    pub(crate) const MAX_PAYLOAD_LEN: usize = 2048;
+
     pub const WIRE_POWER_PRESENCE_ID: u16 = 0x0004;
 
     pub fn command(command_id: u16) -> Self {
@@ -44,6 +48,12 @@ impl Message {
         Self { command_id, payload }
     }
 
+    /// `SwitchWire` command addressing the wire by its id in `profile`
+    pub fn switch_wire(wire: Wire, enabled: bool, profile: WireProfile) -> Self {
+        let enabled = if enabled { 0x01 } else { 0x00 };
+        Message::with_payload(0x0082, vec![profile.wire_id(wire), enabled])
+    }
+
     pub fn to_bytes(&self) -> Bytes {
         let mut buf = BytesMut::new();
 
@@ -59,13 +69,31 @@ impl Message {
         buf.freeze()
     }
 
+    /// Parse message from the start of `buffer`, returning the message and
+    /// number of bytes used. Returns `None` when `buffer` doesn't yet contain
+    /// the whole message.
     pub fn parse(mut buffer: Bytes) -> Result<Option<(usize, Self)>> {
-        // skip preamble; assume caller validated this
+        // wait for at least the header and checksum
+        if buffer.len() < Self::MIN_RAW_LEN {
+            return Ok(None);
+        }
+
+        if buffer[..4] != Self::PREAMBLE_READ {
+            return Err(BackplateError::InvalidPreamble);
+        }
         buffer.advance(4);
 
         let command_id = buffer.try_get_u16_le()?;
         let data_len = buffer.try_get_u16_le()? as usize;
 
+        if data_len > Self::MAX_PAYLOAD_LEN {
+            return Err(BackplateError::PayloadTooLong {
+                id: command_id,
+                len: data_len,
+                max: Self::MAX_PAYLOAD_LEN
+            });
+        }
+
         // check if buffer len includes payload and crc field
         if buffer.remaining() < data_len + 2 {
             return Ok(None);
@@ -79,7 +107,7 @@ impl Message {
             payload
         };
 
-        let checksum = buffer.get_u16_le();
+        let checksum = buffer.try_get_u16_le()?;
         let calc_checksum = crc_from_message(&message);
         if checksum != calc_checksum {
             return Err(BackplateError::ChecksumMismatch {
@@ -125,9 +153,7 @@ impl From<BackplateCmd> for Message {
     fn from(value: BackplateCmd) -> Self {
         match value {
             BackplateCmd::SwitchWire(wire, enabled) => {
-                let wire = wire.to_byte();
-                let enabled = if enabled { 0x01 } else { 0x00 };
-                Message::with_payload(0x0082, vec![wire, enabled])
+                Message::switch_wire(wire, enabled, WireProfile::default())
             }
             BackplateCmd::StatusRequest => {
                 Message::command(0x0083)
@@ -312,16 +338,7 @@ impl TryFrom<Message> for BackplateResponse {
                 BackplateResponse::Pir { val1, val2 }
             }
             Message { command_id: 0x0006, payload } => {
-                let (wire, enabled) = match payload.as_slice() {
-                    [b0, b1, ..] => {
-                        Ok((Wire::try_from_byte(*b0)?, *b1 == 1))
-                    },
-                    _ => Err(BackplateError::PayloadLength {
-                        id: 0x0006, expected: 2, found: payload.len()
-                    })
-                }?;
-
-                BackplateResponse::WireSwitched(wire, enabled)
+                wire_switched(&payload, WireProfile::default())?
             }
             Message { command_id: 0x0007, payload } => {
                 let proximity = match payload.as_slice() {
@@ -375,7 +392,7 @@ impl TryFrom<Message> for BackplateResponse {
             Message { command_id: 0x000b, payload } => {
                 if payload.len() < 14 {
                     return Err(BackplateError::PayloadLength {
-                        id: 0x0009, expected: 12, found: payload.len()
+                        id: 0x000b, expected: 14, found: payload.len()
                     });
                 }
 
@@ -426,7 +443,7 @@ impl TryFrom<Message> for BackplateResponse {
                     });
                 }
 
-                let mask = u16::from_le_bytes(payload.try_into().unwrap());
+                let mask = u16::from_le_bytes([payload[0], payload[1]]);
                 let wakeup_mask = WakeupMask::from_bits_truncate(mask);
 
                 BackplateResponse::WakeupVector(wakeup_mask)
@@ -556,38 +573,85 @@ pub struct BackplateWires<T> {
     pub rh: T
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Wire {
     W1, Y1, G, OB, W2, Y2, Star
 }
 
-impl Wire {
-    fn to_byte(&self) -> u8 {
+/// Terminal ids the backplate uses for each wire in the `SwitchWire` command
+/// and `WireSwitched` response, which can differ between backplate models
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WireProfile {
+    /// Ids in the order of the wire power presence message, from
+    /// https://wiki.exploitee.rs/index.php/Nest_Hacking
+    #[default]
+    Standard,
+    /// Ids in the order of the wire plugged presence message
+    PluggedOrder
+}
+
+/// Wire profile of each known backplate model, by `HardwareVersion`
+const MODEL_PROFILES: &[(&str, WireProfile)] = &[
+    ("01A", WireProfile::Standard),
+    ("02A", WireProfile::Standard)
+];
+
+impl WireProfile {
+    /// Profile for the model reported by `GetHardwareVersion`, or none
+    /// when the model isn't known
+    pub fn for_hardware_version(version: &str) -> Option<Self> {
+        MODEL_PROFILES.iter()
+            .find(|(model, _)| *model == version.trim())
+            .map(|(_, profile)| *profile)
+    }
+
+    fn wire_ids(&self) -> [(Wire, u8); 7] {
         match self {
-            Self::W1 => 0x00,
-            Self::Y1 => 0x01,
-            Self::G => 0x02,
-            Self::OB => 0x03,
-            Self::W2 => 0x04,
-            Self::Y2 => 0x07,
-            Self::Star => 0x0b
+            Self::Standard => [
+                (Wire::W1, 0x00), (Wire::Y1, 0x01), (Wire::G, 0x02), (Wire::OB, 0x03),
+                (Wire::W2, 0x04), (Wire::Y2, 0x07), (Wire::Star, 0x0b)
+            ],
+            Self::PluggedOrder => [
+                (Wire::W1, 0x00), (Wire::Y1, 0x01), (Wire::G, 0x05), (Wire::OB, 0x06),
+                (Wire::W2, 0x07), (Wire::Y2, 0x09), (Wire::Star, 0x0b)
+            ]
         }
     }
 
-    fn try_from_byte(id: u8) -> Result<Self> {
-        match id {
-            0x00 => Ok(Self::W1),
-            0x01 => Ok(Self::Y1),
-            0x02 => Ok(Self::G),
-            0x03 => Ok(Self::OB),
-            0x04 => Ok(Self::W2),
-            0x07 => Ok(Self::Y2),
-            0x0b => Ok(Self::Star),
-            _ => Err(BackplateError::InvalidWireId(id))
+    pub fn wire_id(&self, wire: Wire) -> u8 {
+        self.wire_ids().into_iter()
+            .find_map(|(w, id)| (w == wire).then_some(id))
+            .unwrap()
+    }
+
+    pub fn wire_from_id(&self, id: u8) -> Result<Wire> {
+        self.wire_ids().into_iter()
+            .find_map(|(wire, i)| (i == id).then_some(wire))
+            .ok_or(BackplateError::InvalidWireId(id))
+    }
+}
+
+impl BackplateResponse {
+    /// Decode a message like `try_from`, with wire ids read using `profile`
+    pub fn decode(message: Message, profile: WireProfile) -> Result<Self> {
+        match message {
+            Message { command_id: 0x0006, payload } => wire_switched(&payload, profile),
+            message => message.try_into()
         }
     }
 }
 
+fn wire_switched(payload: &[u8], profile: WireProfile) -> Result<BackplateResponse> {
+    match payload {
+        [b0, b1, ..] => {
+            Ok(BackplateResponse::WireSwitched(profile.wire_from_id(*b0)?, *b1 == 1))
+        },
+        _ => Err(BackplateError::PayloadLength {
+            id: 0x0006, expected: 2, found: payload.len()
+        })
+    }
+}
+
 // https://github.com/mrhooray/crc-rs/issues/54
 // > CCITT is confusing because it's commonly misrepresented.
 // > You probably want CRC-16/KERMIT if init=0x0000 and CRC-16/IBM-3740 if init=0xffff.
@@ -0,0 +1,260 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Decoding of each known backplate message, and random input checks for
+//! the parser and framer. Serial data is noisy, so no input should panic.
+
+use bytes::Bytes;
+use nest_backplate::{
+    BackplateCmd, BackplateError, BackplateResponse, Framer, Message, WakeupMask, Wire, WireProfile
+};
+use proptest::prelude::*;
+
+/// Message as read from the backplate, which has a 4 byte preamble where
+/// written messages have 3
+fn read_frame(command_id: u16, payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0xd5];
+    frame.extend_from_slice(&Message::with_payload(command_id, payload.to_vec()).to_bytes());
+    frame
+}
+
+/// Frame then decode the message, like the connection does
+fn decode(command_id: u16, payload: &[u8]) -> nest_backplate::Result<BackplateResponse> {
+    let frame = read_frame(command_id, payload);
+    let (len, message) = Message::parse(Bytes::from(frame.clone()))?.unwrap();
+    assert_eq!(len, frame.len());
+    message.try_into()
+}
+
+fn approx(a: f32, b: f32) -> bool {
+    (a - b).abs() < 0.001
+}
+
+/// Payloads for each response, and the values they decode to
+#[test]
+fn decode_corpus() {
+    let text = decode(0x0001, b"BRK").unwrap();
+    assert!(text.is_break());
+
+    let climate = decode(0x0002, &[0x2a, 0x08, 0xc2, 0x01]).unwrap();
+    assert!(matches!(climate, BackplateResponse::Climate(c)
+        if approx(c.temperature, 20.9) && approx(c.humidity, 45.0)));
+
+    let presence = decode(0x0004, &[1, 1, 1, 0, 0, 0, 0, 0, 1, 0, 1, 0]).unwrap();
+    assert!(matches!(presence, BackplateResponse::WirePowerPresence(w)
+        if w.w1 && w.y1 && w.g && !w.ob && !w.w2 && !w.y2 && w.c && !w.rc && w.rh && !w.star));
+
+    let pir = decode(0x0005, &[0xff, 0x7f, 0x00, 0x7e]).unwrap();
+    assert!(matches!(pir, BackplateResponse::Pir { val1: 0x7fff, val2: 0x7e00 }));
+
+    let switched = decode(0x0006, &[0x01, 0x01]).unwrap();
+    assert!(matches!(switched, BackplateResponse::WireSwitched(Wire::Y1, true)));
+
+    let switched = decode(0x0006, &[0x0b, 0x00]).unwrap();
+    assert!(matches!(switched, BackplateResponse::WireSwitched(Wire::Star, false)));
+
+    let near_pir = decode(0x0007, &[0x10, 0x00]).unwrap();
+    assert!(matches!(near_pir, BackplateResponse::NearPir(16)));
+
+    let plugged = decode(0x0009, &[1, 1, 1, 0, 1, 1, 0, 0, 0, 0, 0, 0]).unwrap();
+    assert!(matches!(plugged, BackplateResponse::WirePluggedPresence(w)
+        if w.w1 && w.y1 && w.c && !w.rc && w.rh && w.g && !w.ob && !w.w2 && !w.y2 && !w.star));
+
+    let light = decode(0x000a, &[0x2c, 0x01, 0x00, 0x00]).unwrap();
+    assert!(matches!(light, BackplateResponse::AmbientLightSensor(300)));
+
+    let power = decode(0x000b, &[
+        0x00, 0x40, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x60, 0x09, 0x74, 0x0e, 0x3c, 0x0f
+    ]).unwrap();
+    assert!(matches!(power, BackplateResponse::PowerState { charging: true, volts_in, volts_op, volts_bat }
+        if approx(volts_in, 24.0) && approx(volts_op, 3.7) && approx(volts_bat, 3.9)));
+
+    let adc = decode(0x000c, &[
+        0x00, 0x20, 0x01, 0x20, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x2c, 0x01, 0x90, 0x01
+    ]).unwrap();
+    assert!(matches!(adc, BackplateResponse::RawAdcData {
+        pir: 0x2000, px1: 0x2001, px1_div: 5, px2: 0, px2_div: 0, alir: 300, alv: 400
+    }));
+
+    let tfe_id = decode(0x0010, &[0x12, 0x34]).unwrap();
+    assert!(matches!(tfe_id, BackplateResponse::TfeId(id) if id == [0x12, 0x34]));
+
+    let wakeup = decode(0x0014, &[0x09, 0x00]).unwrap();
+    assert!(matches!(wakeup, BackplateResponse::WakeupVector(mask)
+        if mask.bits() == (WakeupMask::TIMER | WakeupMask::PIR).bits()));
+
+    let version = decode(0x0018, b"1.2.3").unwrap();
+    assert!(matches!(version, BackplateResponse::TfeVersion(v) if v == "1.2.3"));
+
+    let build_info = decode(0x0019, b"build 42").unwrap();
+    assert!(matches!(build_info, BackplateResponse::TfeBuildInfo(v) if v == "build 42"));
+
+    let bsl_version = decode(0x001b, b"2.0").unwrap();
+    assert!(matches!(bsl_version, BackplateResponse::BslVersion(v) if v == "2.0"));
+
+    let bsl_info = decode(0x001c, b"BSL").unwrap();
+    assert!(matches!(bsl_info, BackplateResponse::BslInfo(v) if v == "BSL"));
+
+    let bsl_id = decode(0x001d, &[0xbb, 0xbb]).unwrap();
+    assert!(matches!(bsl_id, BackplateResponse::BslId(id) if id == [0xbb, 0xbb]));
+
+    let hardware = decode(0x001e, b"02A").unwrap();
+    assert!(matches!(hardware, BackplateResponse::HardwareVersion(v) if v == "02A"));
+
+    let serial = decode(0x001f, b"02AA01AB123456").unwrap();
+    assert!(matches!(serial, BackplateResponse::Serial(v) if v == "02AA01AB123456"));
+
+    // trailing partial chunks are ignored
+    let climate = decode(0x0022, &[0x2a, 0x08, 0xc2, 0x01, 0xf4, 0x07, 0xbc, 0x02, 0xff]).unwrap();
+    assert!(matches!(climate, BackplateResponse::BufferedClimateData(h)
+        if h.len() == 2 && approx(h[1].temperature, 20.36) && approx(h[1].humidity, 70.0)));
+
+    let power = decode(0x002b, &[
+        0x60, 0x09, 0x3c, 0x0f, 0, 0, 0, 0,
+        0x58, 0x02, 0xd0, 0x0e, 0, 0, 0, 0
+    ]).unwrap();
+    assert!(matches!(power, BackplateResponse::BufferedPowerData(h)
+        if h.len() == 2 && approx(h[0].volts_in, 24.0) && approx(h[1].volts_bat, 3.792)));
+
+    let end = decode(0x002f, &[]).unwrap();
+    assert!(matches!(end, BackplateResponse::EndSensorBuffers));
+
+    let raw = decode(0x0099, &[1, 2, 3]).unwrap();
+    assert!(matches!(raw, BackplateResponse::Raw(m) if m.command_id == 0x0099 && m.payload == [1, 2, 3]));
+}
+
+#[test]
+fn decode_errors() {
+    for id in [0x0002, 0x0004, 0x0005, 0x0006, 0x0007, 0x0009, 0x000a, 0x000b, 0x000c, 0x0014] {
+        let result = decode(id, &[0x01]);
+        assert!(matches!(result, Err(BackplateError::PayloadLength { id: err_id, .. }) if err_id == id),
+            "{id:x} {result:?}");
+    }
+
+    assert!(matches!(decode(0x0006, &[0x05, 0x01]), Err(BackplateError::InvalidWireId(0x05))));
+    assert!(matches!(decode(0x0001, &[0xff, 0xfe]), Err(BackplateError::InvalidAscii(_))));
+}
+
+/// Wire ids follow the profile picked for the backplate model
+#[test]
+fn wire_profiles() {
+    assert_eq!(WireProfile::for_hardware_version("02A"), Some(WireProfile::Standard));
+    assert_eq!(WireProfile::for_hardware_version("01A\n"), Some(WireProfile::Standard));
+    assert_eq!(WireProfile::for_hardware_version("03B"), None);
+
+    let standard: Message = BackplateCmd::SwitchWire(Wire::G, true).into();
+    assert_eq!(standard.payload, [0x02, 0x01]);
+    let plugged = Message::switch_wire(Wire::G, true, WireProfile::PluggedOrder);
+    assert_eq!(plugged.payload, [0x05, 0x01]);
+
+    let switched = BackplateResponse::decode(Message::with_payload(0x0006, vec![0x05, 0x01]),
+        WireProfile::PluggedOrder).unwrap();
+    assert!(matches!(switched, BackplateResponse::WireSwitched(Wire::G, true)));
+
+    for profile in [WireProfile::Standard, WireProfile::PluggedOrder] {
+        for wire in [Wire::W1, Wire::Y1, Wire::G, Wire::OB, Wire::W2, Wire::Y2, Wire::Star] {
+            assert_eq!(profile.wire_from_id(profile.wire_id(wire)).unwrap(), wire);
+        }
+    }
+}
+
+#[test]
+fn parse_errors() {
+    let frame = read_frame(0x0002, &[0x2a, 0x08, 0xc2, 0x01]);
+
+    for len in 0..frame.len() {
+        let result = Message::parse(Bytes::copy_from_slice(&frame[..len]));
+        assert!(matches!(result, Ok(None)), "truncated to {len}");
+    }
+
+    let mut corrupt = frame.clone();
+    corrupt[0] = 0x00;
+    assert!(matches!(Message::parse(Bytes::from(corrupt)), Err(BackplateError::InvalidPreamble)));
+
+    let mut corrupt = frame.clone();
+    *corrupt.last_mut().unwrap() ^= 0xff;
+    assert!(matches!(Message::parse(Bytes::from(corrupt)), Err(BackplateError::ChecksumMismatch { .. })));
+
+    let mut corrupt = frame;
+    corrupt[6..8].copy_from_slice(&0xffffu16.to_le_bytes());
+    assert!(matches!(Message::parse(Bytes::from(corrupt)), Err(BackplateError::PayloadTooLong { .. })));
+}
+
+proptest! {
+    #[test]
+    fn parse_any_bytes(data in proptest::collection::vec(any::<u8>(), 0..64)) {
+        let _ = Message::parse(Bytes::from(data));
+    }
+
+    #[test]
+    fn parse_any_preamble_bytes(data in proptest::collection::vec(any::<u8>(), 0..64)) {
+        let mut frame = vec![0xd5, 0xd5, 0xaa, 0x96];
+        frame.extend(data);
+        let _ = Message::parse(Bytes::from(frame));
+    }
+
+    #[test]
+    fn decode_any_payload(
+        command_id in 0x0000u16..0x0040,
+        payload in proptest::collection::vec(any::<u8>(), 0..64)
+    ) {
+        let _ = BackplateResponse::try_from(Message::with_payload(command_id, payload));
+    }
+
+    /// The framer never fails on random data, it only drops it
+    #[test]
+    fn framer_any_bytes(chunks in proptest::collection::vec(proptest::collection::vec(any::<u8>(), 0..32), 0..16)) {
+        let mut framer = Framer::default();
+        for chunk in chunks {
+            framer.push(&chunk);
+            while framer.next_message().unwrap().is_some() { }
+        }
+    }
+
+    /// Every message is recovered from between garbage, with the stream
+    /// split at random points
+    #[test]
+    fn framer_recovers_messages(
+        messages in proptest::collection::vec(
+            (any::<u16>(), proptest::collection::vec(any::<u8>(), 0..16)),
+            1..8
+        ),
+        // garbage without 0xd5 can't start a preamble
+        garbage in proptest::collection::vec(any::<u8>().prop_filter("preamble", |b| *b != 0xd5), 0..8),
+        split in 1usize..16
+    ) {
+        let mut stream = Vec::new();
+        for (command_id, payload) in &messages {
+            stream.extend_from_slice(&garbage);
+            stream.extend(read_frame(*command_id, payload));
+        }
+
+        let mut framer = Framer::default();
+        let mut parsed = Vec::new();
+        for chunk in stream.chunks(split) {
+            framer.push(chunk);
+            while let Some(message) = framer.next_message().unwrap() {
+                parsed.push((message.command_id, message.payload));
+            }
+        }
+
+        prop_assert_eq!(parsed, messages);
+        prop_assert_eq!(framer.stats().crc_errors, 0);
+    }
+}
@@ -0,0 +1,333 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{collections::VecDeque, fmt, time::{Duration, Instant}};
+
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    events::{Event, EventHandler, EventSender, Topic},
+    state::{ClimateCommand, HvacAction, HvacMode, ThermostatState},
+    temperature::{TempUnit, Temperature}
+};
+
+/// Switches kept in the log, older entries are dropped
+const MAX_ENTRIES: usize = 100;
+
+/// A cause is credited with a switch this long after it happened
+const CAUSE_WINDOW: Duration = Duration::from_mins(1);
+
+/// Changes made this long after turning the dial or pressing the button
+/// are from the thermostat, otherwise from Home Assistant
+const INPUT_WINDOW: Duration = Duration::from_mins(1);
+
+/// What led to the hvac action changing
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+pub enum ActionCause {
+    /// Room temp crossed the target
+    Temperature,
+    Schedule,
+    Away,
+    /// Changed on the thermostat
+    Manual,
+    HomeAssistant,
+    Safety,
+    Backplate
+}
+
+impl ActionCause {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Temperature => "temp",
+            Self::Schedule => "schedule",
+            Self::Away => "away",
+            Self::Manual => "manual",
+            Self::HomeAssistant => "HA",
+            Self::Safety => "safety",
+            Self::Backplate => "backplate"
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActionEntry {
+    pub time: DateTime<Local>,
+    pub from: HvacAction,
+    pub to: HvacAction,
+    pub cause: ActionCause,
+    /// Target temp heating or cooling was working towards, `None` for the
+    /// fan, and entries saved before setpoints were logged
+    pub setpoint: Option<Temperature>
+}
+
+impl ActionEntry {
+    /// Heating or cooling started or stopped, as opposed to the fan alone
+    pub fn is_major(&self) -> bool {
+        let major = |action| matches!(action, HvacAction::Heating | HvacAction::Cooling);
+        major(self.from) || major(self.to)
+    }
+
+    /// One line description, like "Heating started 14:05, setpoint 21.0°C, cause: schedule"
+    pub fn summary(&self, unit: TempUnit) -> String {
+        let name = |action| match action {
+            HvacAction::Idle => "Idle",
+            HvacAction::Heating => "Heating",
+            HvacAction::Cooling => "Cooling",
+            HvacAction::Fan => "Fan"
+        };
+
+        let switch = if self.to == HvacAction::Idle {
+            format!("{} stopped", name(self.from))
+        } else {
+            format!("{} started", name(self.to))
+        };
+        let time = self.time.format("%H:%M");
+
+        let symbol = match unit {
+            TempUnit::Celsius => "°C",
+            TempUnit::Fahrenheit => "°F"
+        };
+
+        match self.setpoint {
+            Some(setpoint) => format!(
+                "{switch} {time}, setpoint {}{symbol}, cause: {}",
+                setpoint.format(unit), self.cause.label()
+            ),
+            None => format!("{switch} {time}, cause: {}", self.cause.label())
+        }
+    }
+}
+
+/// Setpoint the heating or cooling in `state` works towards, or worked
+/// towards before going idle
+fn setpoint(state: &ThermostatState, from: HvacAction) -> Option<Temperature> {
+    let action = if state.action == HvacAction::Idle { from } else { state.action };
+
+    match (state.mode, action) {
+        (HvacMode::HeatCool, HvacAction::Heating) => Some(state.target_temp_low),
+        (HvacMode::HeatCool, HvacAction::Cooling) => Some(state.target_temp_high),
+        (HvacMode::Heat | HvacMode::EmergencyHeat | HvacMode::Cool, HvacAction::Heating | HvacAction::Cooling) => {
+            Some(state.target_temp)
+        }
+        // safety limits run in any mode, without a setpoint
+        _ => None
+    }
+}
+
+/// Most recent hvac action switches, oldest first
+#[derive(Clone, Default, PartialEq)]
+pub struct ActionLog {
+    entries: VecDeque<ActionEntry>
+}
+
+impl fmt::Debug for ActionLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ActionLog")
+            .field("len", &self.entries.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl ActionLog {
+    pub fn new(entries: impl IntoIterator<Item = ActionEntry>) -> Self {
+        let mut log = Self::default();
+        for entry in entries {
+            log.push(entry);
+        }
+        log
+    }
+
+    fn push(&mut self, entry: ActionEntry) {
+        if self.entries.len() == MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    pub fn entries(&self) -> impl DoubleEndedIterator<Item = &ActionEntry> {
+        self.entries.iter()
+    }
+
+    pub fn latest(&self) -> Option<&ActionEntry> {
+        self.entries.back()
+    }
+}
+
+/// Records each hvac action switch with its likely cause, sending
+/// `Event::HvacSwitched` and the updated `Event::ActionLog`
+pub struct ActionLogger<S> {
+    event_sender: S,
+    log: ActionLog,
+    action: Option<HvacAction>,
+    cause: Option<(ActionCause, Instant)>,
+    last_input: Option<Instant>
+}
+
+impl<S: EventSender> ActionLogger<S> {
+    /// `log` is the persisted log, sent right away for the screens
+    pub fn new(log: ActionLog, event_sender: S) -> Result<Self> {
+        event_sender.send_event(Event::ActionLog(log.clone()))?;
+
+        Ok(Self {
+            event_sender,
+            log,
+            action: None,
+            cause: None,
+            last_input: None
+        })
+    }
+
+    /// Cause of a change requested by the user
+    fn user_cause(&self) -> ActionCause {
+        match self.last_input {
+            Some(time) if time.elapsed() < INPUT_WINDOW => ActionCause::Manual,
+            _ => ActionCause::HomeAssistant
+        }
+    }
+
+    fn cause(&self) -> ActionCause {
+        match self.cause {
+            Some((cause, time)) if time.elapsed() < CAUSE_WINDOW => cause,
+            _ => ActionCause::Temperature
+        }
+    }
+}
+
+impl<S: EventSender> EventHandler for ActionLogger<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<()> {
+        let cause = match event {
+            Event::ButtonDown | Event::Dial(_) => {
+                self.last_input = Some(Instant::now());
+                None
+            }
+            Event::ClimateCommand(ClimateCommand { away: Some(_), .. }) => Some(ActionCause::Away),
+            Event::SetTargetTemp(_) | Event::SetTargetRange(..) | Event::SetMode(_)
+                | Event::ClimateCommand(_)
+                | Event::SetFanMode(_) | Event::SetBoost(_) => {
+                Some(self.user_cause())
+            }
+            Event::ScheduledTargetTemp(_) => Some(ActionCause::Schedule),
+            Event::SetAway(_) | Event::Occupancy(_) => Some(ActionCause::Away),
+            Event::BackplateConnected | Event::BackplateDisconnected => Some(ActionCause::Backplate),
+            Event::State(state) => {
+                // first state is the one restored at startup
+                if let Some(from) = self.action.replace(state.action)
+                    && from != state.action
+                {
+                    let entry = ActionEntry {
+                        time: Local::now(),
+                        from,
+                        to: state.action,
+                        cause: if state.safety { ActionCause::Safety } else { self.cause() },
+                        setpoint: setpoint(state, from).filter(|_| !state.safety)
+                    };
+                    self.cause = None;
+                    self.log.push(entry.clone());
+
+                    self.event_sender.send_event(Event::HvacSwitched(entry))?;
+                    self.event_sender.send_event(Event::ActionLog(self.log.clone()))?;
+                }
+                None
+            }
+            _ => None
+        };
+
+        if let Some(cause) = cause {
+            self.cause = Some((cause, Instant::now()));
+        }
+
+        Ok(())
+    }
+
+    fn topics(&self) -> &'static [Topic] {
+        &[Topic::Input, Topic::State, Topic::Backplate]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use crate::events::{DefaultEventSource, EventSource};
+
+    fn state(action: HvacAction) -> Event {
+        Event::State(ThermostatState { action, ..ThermostatState::default() })
+    }
+
+    #[test]
+    fn records_switch_with_cause() -> Result<()> {
+        let event_source = DefaultEventSource::new();
+        let mut logger = ActionLogger::new(ActionLog::default(), event_source.event_sender())?;
+
+        logger.handle_event(&state(HvacAction::Idle))?;
+        logger.handle_event(&Event::ScheduledTargetTemp(Default::default()))?;
+        logger.handle_event(&state(HvacAction::Heating))?;
+
+        logger.handle_event(&Event::Dial(1))?;
+        logger.handle_event(&Event::SetTargetTemp(Default::default()))?;
+        logger.handle_event(&state(HvacAction::Idle))?;
+
+        let causes: Vec<_> = logger.log.entries().map(|e| (e.to, e.cause)).collect();
+        assert_eq!(causes, [
+            (HvacAction::Heating, ActionCause::Schedule),
+            (HvacAction::Idle, ActionCause::Manual)
+        ]);
+
+        // cause is used up by the switch it led to
+        logger.handle_event(&state(HvacAction::Heating))?;
+        assert_eq!(logger.log.latest().map(|e| e.cause), Some(ActionCause::Temperature));
+
+        Ok(())
+    }
+
+    #[test]
+    fn bounded() {
+        let entry = ActionEntry {
+            time: Local::now(),
+            from: HvacAction::Idle,
+            to: HvacAction::Heating,
+            cause: ActionCause::Temperature,
+            setpoint: None
+        };
+
+        let log = ActionLog::new(vec![entry; MAX_ENTRIES + 5]);
+        assert_eq!(log.entries().count(), MAX_ENTRIES);
+    }
+
+    #[test]
+    fn summary() {
+        let time = Local.with_ymd_and_hms(2026, 1, 12, 14, 5, 0).unwrap();
+        let mut entry = ActionEntry {
+            time,
+            from: HvacAction::Idle,
+            to: HvacAction::Heating,
+            cause: ActionCause::Schedule,
+            setpoint: Some(Temperature::from_celsius(21.0))
+        };
+        assert_eq!(entry.summary(TempUnit::Celsius), "Heating started 14:05, setpoint 21.0°C, cause: schedule");
+        assert_eq!(entry.summary(TempUnit::Fahrenheit), "Heating started 14:05, setpoint 69.8°F, cause: schedule");
+
+        entry.from = HvacAction::Cooling;
+        entry.to = HvacAction::Idle;
+        entry.cause = ActionCause::HomeAssistant;
+        entry.setpoint = None;
+        assert_eq!(entry.summary(TempUnit::Celsius), "Cooling stopped 14:05, cause: HA");
+    }
+}
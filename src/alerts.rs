@@ -0,0 +1,189 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use anyhow::Result;
+
+use crate::{
+    config::{AlertsConfig, Config},
+    events::{Event, EventHandler, EventSender, Topic}
+};
+
+/// Degrees back inside a temp threshold before its alert clears, so a
+/// reading wavering on the threshold doesn't keep raising the alert
+const TEMP_HYSTERESIS: f32 = 0.5;
+
+/// Volts above the battery threshold before the alert clears
+const VOLTS_HYSTERESIS: f32 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Alert {
+    Frost,
+    Overheat,
+    LowBattery
+}
+
+impl Alert {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Frost => "Room below frost limit",
+            Self::Overheat => "Room above overheat limit",
+            Self::LowBattery => "Backplate battery low"
+        }
+    }
+}
+
+/// Checks room temp and battery voltage against the alert thresholds,
+/// and sends `Event::Alerts` when the active alerts change
+pub struct AlertMonitor<S> {
+    config: AlertsConfig,
+    event_sender: S,
+    alerts: Vec<Alert>
+}
+
+impl<S: EventSender> AlertMonitor<S> {
+    pub fn new(config: &Config, event_sender: S) -> Self {
+        Self {
+            config: config.alerts.clone(),
+            event_sender,
+            alerts: Vec::new()
+        }
+    }
+
+    fn is_active(&self, alert: Alert) -> bool {
+        self.alerts.contains(&alert)
+    }
+
+    /// Raise or clear the alert, true when it changed
+    fn set_alert(&mut self, alert: Alert, active: bool) -> bool {
+        match (self.is_active(alert), active) {
+            (false, true) => {
+                self.alerts.push(alert);
+                true
+            }
+            (true, false) => {
+                self.alerts.retain(|a| *a != alert);
+                true
+            }
+            _ => false
+        }
+    }
+
+    fn check_temp(&mut self, temp: f32) -> bool {
+        let frost = self.config.frost_temp.is_some_and(|limit| {
+            let limit = limit.celsius();
+            let hysteresis = if self.is_active(Alert::Frost) { TEMP_HYSTERESIS } else { 0.0 };
+            temp < limit + hysteresis
+        });
+
+        let overheat = self.config.overheat_temp.is_some_and(|limit| {
+            let limit = limit.celsius();
+            let hysteresis = if self.is_active(Alert::Overheat) { TEMP_HYSTERESIS } else { 0.0 };
+            temp > limit - hysteresis
+        });
+
+        self.set_alert(Alert::Frost, frost) | self.set_alert(Alert::Overheat, overheat)
+    }
+
+    fn check_battery(&mut self, volts: f32) -> bool {
+        let low = self.config.low_battery.is_some_and(|limit| {
+            let hysteresis = if self.is_active(Alert::LowBattery) { VOLTS_HYSTERESIS } else { 0.0 };
+            volts < limit + hysteresis
+        });
+
+        self.set_alert(Alert::LowBattery, low)
+    }
+}
+
+impl<S: EventSender> EventHandler for AlertMonitor<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<()> {
+        let changed = match event {
+            Event::State(state) => {
+                self.check_temp(state.current_temp.celsius())
+            }
+            Event::BackplatePower(power) => {
+                self.check_battery(power.volts_bat)
+            }
+            Event::ConfigReloaded(reloaded) => {
+                // thresholds are checked again with the next reading
+                self.config = reloaded.config.alerts.clone();
+                false
+            }
+            _ => false
+        };
+
+        if changed || matches!(event, Event::GetState) {
+            self.event_sender.send_event(Event::Alerts(self.alerts.clone()))?;
+        }
+
+        Ok(())
+    }
+
+    fn topics(&self) -> &'static [Topic] {
+        &[Topic::State, Topic::Backplate]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        events::{DefaultEventSource, EventSource},
+        state::ThermostatState,
+        temperature::Temperature
+    };
+
+    /// Last alert list sent since the last call
+    fn last_alerts(event_source: &mut DefaultEventSource) -> Result<Option<Vec<Alert>>> {
+        let mut result = None;
+        while let Some(event) = event_source.poll_event()? {
+            if let Event::Alerts(alerts) = event {
+                result = Some(alerts);
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn room_temp(celsius: f32) -> Event {
+        Event::State(ThermostatState {
+            current_temp: Temperature::from_celsius(celsius),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn frost_alert_clears_past_hysteresis() -> Result<()> {
+        let mut events = DefaultEventSource::new();
+        let mut monitor = AlertMonitor::new(&Config::default(), events.event_sender());
+
+        monitor.handle_event(&room_temp(18.0))?;
+        assert_eq!(last_alerts(&mut events)?, None);
+
+        monitor.handle_event(&room_temp(4.8))?;
+        assert_eq!(last_alerts(&mut events)?, Some(vec![Alert::Frost]));
+
+        // back over the limit, but not by the hysteresis
+        monitor.handle_event(&room_temp(5.2))?;
+        assert_eq!(last_alerts(&mut events)?, None);
+
+        monitor.handle_event(&room_temp(5.6))?;
+        assert_eq!(last_alerts(&mut events)?, Some(vec![]));
+
+        Ok(())
+    }
+}
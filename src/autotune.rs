@@ -0,0 +1,334 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{f32::consts::PI, time::{Duration, Instant}};
+
+use anyhow::Result;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    events::{Event, EventHandler, EventSender, Topic},
+    state::{HvacAction, HvacMode, ThermostatState},
+    timer::TimerId
+};
+
+/// Distance either side of the target temp where heating/cooling is switched
+/// on and off while tuning
+pub const RELAY_HYSTERESIS: f32 = 0.3;
+
+/// Full on/off cycles to measure, the first is only used to settle
+const CYCLES: usize = 4;
+
+/// Give up if the cycles haven't completed in this time
+const MAX_DURATION: Duration = Duration::from_hours(12);
+
+/// Parameters suggested by autotune, persisted and used with time
+/// proportional control, see [`crate::config::ControlConfig`]
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct Tuning {
+    /// Swing past the target under on/off control, suggested `temp_deadband`
+    pub differential: f32,
+    pub proportional_band: f32,
+    pub integral_secs: u64
+}
+
+impl Tuning {
+    /// Ziegler-Nichols PI parameters from a relay experiment, with an
+    /// oscillation of `amplitude` degrees either side of the target
+    fn from_oscillation(amplitude: f32, period: Duration) -> Self {
+        // relay switches between 0% and 100%, half the output either side
+        let ultimate_gain = 4.0 * 0.5 / (PI * amplitude);
+        let proportional_gain = 0.45 * ultimate_gain;
+
+        Self {
+            differential: amplitude,
+            proportional_band: 1.0 / proportional_gain,
+            integral_secs: period.div_f32(1.2).as_secs()
+        }
+    }
+
+    /// Whether the values can be used for control, a stored tuning may be
+    /// corrupt and a zero band would make the demand infinite
+    pub fn is_valid(&self) -> bool {
+        self.proportional_band.is_finite() && self.proportional_band > 0.0
+    }
+
+    pub fn integral_time(&self) -> Duration {
+        Duration::from_secs(self.integral_secs)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AutotuneStatus {
+    Idle,
+    /// Full cycles measured so far
+    Running(usize),
+    Done(Tuning),
+    Failed(&'static str)
+}
+
+impl AutotuneStatus {
+    pub fn is_running(&self) -> bool {
+        matches!(self, Self::Running(_))
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Self::Idle => String::from("Not started"),
+            Self::Running(cycles) => format!("Cycle {} of {CYCLES}", cycles + 1),
+            Self::Done(_) => String::from("Done"),
+            Self::Failed(reason) => format!("Failed, {reason}")
+        }
+    }
+}
+
+/// Relay experiment, heating or cooling is switched on and off around the
+/// target temp by the state manager, and the resulting temp swing measured
+struct Experiment {
+    mode: HvacMode,
+    action: HvacAction,
+    /// Action was running at the last update
+    running: bool,
+    /// Lowest temp while running, or highest while off. Temps are negated
+    /// for cooling so the same logic applies.
+    extreme: Option<f32>,
+    last_on: Option<Instant>,
+    highs: Vec<f32>,
+    lows: Vec<f32>,
+    periods: Vec<Duration>
+}
+
+impl Experiment {
+    fn new(mode: HvacMode, action: HvacAction) -> Self {
+        Self {
+            mode,
+            action,
+            running: false,
+            extreme: None,
+            last_on: None,
+            highs: Vec::new(),
+            lows: Vec::new(),
+            periods: Vec::new()
+        }
+    }
+
+    fn update(&mut self, state: &ThermostatState, now: Instant) {
+        let temp = match self.action {
+            HvacAction::Cooling => -state.current_temp.celsius(),
+            _ => state.current_temp.celsius()
+        };
+        // wires stay off during the min off time lockout
+        let running = state.action == self.action && !state.lockout;
+
+        if running != self.running {
+            // extremes before the first switch on aren't from a full phase
+            if let (Some(extreme), Some(_)) = (self.extreme, self.last_on) {
+                if running {
+                    self.highs.push(extreme);
+                } else {
+                    self.lows.push(extreme);
+                }
+            }
+
+            if running {
+                if let Some(last_on) = self.last_on {
+                    self.periods.push(now.duration_since(last_on));
+                }
+                self.last_on = Some(now);
+            }
+
+            self.running = running;
+            self.extreme = None;
+        }
+
+        self.extreme = Some(match (self.extreme, running) {
+            (Some(extreme), true) => extreme.min(temp),
+            (Some(extreme), false) => extreme.max(temp),
+            (None, _) => temp
+        });
+    }
+
+    fn cycles(&self) -> usize {
+        self.periods.len()
+    }
+
+    /// Tuning from the measured cycles, skipping the first to settle
+    fn tuning(&self) -> Option<Tuning> {
+        fn mean(values: &[f32]) -> f32 {
+            values.iter().sum::<f32>() / values.len() as f32
+        }
+
+        let highs = self.highs.get(1..).filter(|v| !v.is_empty())?;
+        let lows = self.lows.get(1..).filter(|v| !v.is_empty())?;
+        let periods = self.periods.get(1..).filter(|v| !v.is_empty())?;
+
+        let amplitude = (mean(highs) - mean(lows)) / 2.0;
+        if amplitude <= 0.05 {
+            return None;
+        }
+
+        let period = periods.iter().sum::<Duration>() / periods.len() as u32;
+        Some(Tuning::from_oscillation(amplitude, period))
+    }
+}
+
+/// Runs the autotune relay experiment on [`Event::StartAutotune`], sending
+/// progress as [`Event::Autotune`] and the result as [`Event::Tuning`]
+pub struct AutoTuner<S> {
+    event_sender: S,
+    state: ThermostatState,
+    experiment: Option<Experiment>
+}
+
+impl<S: EventSender> AutoTuner<S> {
+    pub fn new(event_sender: S) -> Self {
+        Self {
+            event_sender,
+            state: ThermostatState::default(),
+            experiment: None
+        }
+    }
+
+    fn start(&mut self) -> Result<()> {
+        let action = match self.state.mode {
+            HvacMode::Heat => HvacAction::Heating,
+            HvacMode::Cool => HvacAction::Cooling,
+            _ => return self.finish(AutotuneStatus::Failed("needs heat or cool mode"))
+        };
+
+        info!("Autotune started for {action:?}");
+        self.experiment = Some(Experiment::new(self.state.mode, action));
+        self.event_sender.send_event(Event::TimeoutReset(TimerId::Autotune, MAX_DURATION))?;
+        self.event_sender.send_event(Event::Autotune(AutotuneStatus::Running(0)))?;
+
+        Ok(())
+    }
+
+    fn finish(&mut self, status: AutotuneStatus) -> Result<()> {
+        if self.experiment.take().is_some() {
+            self.event_sender.send_event(Event::CancelTimer(TimerId::Autotune))?;
+        }
+
+        match status {
+            AutotuneStatus::Done(tuning) => {
+                info!("Autotune done {tuning:?}");
+                self.event_sender.send_event(Event::Tuning(tuning))?;
+            }
+            AutotuneStatus::Failed(reason) => warn!("Autotune failed, {reason}"),
+            _ => { }
+        }
+
+        self.event_sender.send_event(Event::Autotune(status))?;
+        Ok(())
+    }
+
+    fn update(&mut self, state: &ThermostatState) -> Result<()> {
+        let Some(experiment) = &mut self.experiment else {
+            return Ok(());
+        };
+
+        if state.mode != experiment.mode {
+            return self.finish(AutotuneStatus::Failed("mode changed"));
+        }
+        if !state.backplate {
+            return self.finish(AutotuneStatus::Failed("backplate disconnected"));
+        }
+
+        let cycles = experiment.cycles();
+        experiment.update(state, Instant::now());
+
+        if experiment.cycles() >= CYCLES {
+            let status = match experiment.tuning() {
+                Some(tuning) => AutotuneStatus::Done(tuning),
+                None => AutotuneStatus::Failed("temp didn't swing")
+            };
+            self.finish(status)?;
+        } else if experiment.cycles() != cycles {
+            self.event_sender.send_event(Event::Autotune(AutotuneStatus::Running(experiment.cycles())))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: EventSender> EventHandler for AutoTuner<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<()> {
+        match event {
+            Event::StartAutotune if self.experiment.is_none() => {
+                self.start()?;
+            }
+            Event::StopAutotune if self.experiment.is_some() => {
+                info!("Autotune stopped");
+                self.finish(AutotuneStatus::Idle)?;
+            }
+            Event::TimeoutReached(TimerId::Autotune) => {
+                self.finish(AutotuneStatus::Failed("timed out"))?;
+            }
+            Event::State(state) => {
+                self.state = state.clone();
+                self.update(state)?;
+            }
+            _ => { }
+        }
+
+        Ok(())
+    }
+
+    fn topics(&self) -> &'static [Topic] {
+        &[Topic::State, Topic::Timer]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temperature::Temperature;
+
+    #[test]
+    fn measures_relay_oscillation() {
+        let start = Instant::now();
+        let mut experiment = Experiment::new(HvacMode::Heat, HvacAction::Heating);
+
+        // swings 1.0 either side of 20.0, once an hour
+        for cycle in 0..CYCLES as u64 + 1 {
+            let phases = [
+                (0, 19.5, HvacAction::Heating),
+                (10, 19.0, HvacAction::Heating),
+                (30, 20.5, HvacAction::Idle),
+                (40, 21.0, HvacAction::Idle),
+            ];
+
+            for (mins, temp, action) in phases {
+                let state = ThermostatState {
+                    current_temp: Temperature::from_celsius(temp),
+                    action,
+                    ..ThermostatState::default()
+                };
+                experiment.update(&state, start + Duration::from_mins(cycle * 60 + mins));
+            }
+        }
+
+        assert!(experiment.cycles() >= CYCLES);
+
+        let tuning = experiment.tuning().unwrap();
+        assert_eq!(tuning.differential, 1.0);
+        assert_eq!(tuning.integral_secs, 3000);
+        assert!((tuning.proportional_band - 3.49).abs() < 0.01);
+    }
+}
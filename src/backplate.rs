@@ -16,15 +16,30 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use std::{sync::atomic::{AtomicU64, Ordering}, time::Duration};
+
 use anyhow::Result;
+use log::{error, info, warn};
+pub use nest_backplate::LinkStats;
 
 use crate::{
-    config::Config,
-    events::{Event, EventHandler, EventSender},
-    state::HvacAction
+    config::{Config, WireConfig, WireId},
+    events::{Event, EventHandler, EventSender, Topic},
+    state::{AuxHeat, HvacAction, HvacMode, ThermostatState},
+    timer::TimerId
 };
 
-#[cfg(feature = "device")]
+mod capabilities;
+mod failsafe;
+mod power;
+mod temp_filter;
+
+pub use capabilities::{BackplateCapabilities, WirePresence};
+use failsafe::{Heartbeat, Watchdog};
+pub use power::PowerReading;
+
+// simulate builds use the device backplate for replaying captures
+#[cfg(any(feature = "device", feature = "simulate"))]
 mod backplate_device;
 
 #[cfg(feature = "device")]
@@ -37,33 +52,306 @@ mod backplate_simulated;
 use backplate_simulated::SimulatedBackplate as BackplateImpl;
 
 trait BackplateDevice {
-    fn new<S>(config: &Config, event_sender: S) -> Result<Self>
-        where S: EventSender + Send + 'static, Self: Sized;
+    /// `heartbeat` is kept going by the event loop, see
+    /// `BackplateConfig::failsafe_timeout`
+    fn new<S>(config: &Config, heartbeat: Heartbeat, event_sender: S) -> Result<Self>
+        where S: EventSender + Clone + Send + 'static, Self: Sized;
+
+    /// Switch wires for `action`, with the stage 2 wire also on when `stage`
+    /// is 2, the aux wire switched for `aux` while heating, and the fan wire
+    /// also on when `fan_on`
+    fn switch_hvac(&self, action: &HvacAction, stage: u8, aux: AuxHeat, fan_on: bool) -> Result<()>;
+
+    /// Stop the backplate message stream and poll for readings instead
+    fn set_quiet(&self, quiet: bool) -> Result<()>;
+
+    /// Switch all wires off, waiting up to `timeout` for the backplate to
+    /// confirm each switch
+    fn switch_off(&self, timeout: Duration) -> Result<()>;
+
+    /// Stop writing to the backplate, waiting up to `timeout` for a write
+    /// or connect in progress. Nothing is written after, even on reconnect.
+    fn stop(&self, timeout: Duration) -> Result<()>;
+
+    /// Switch a single configured wire, for service mode
+    fn switch_wire(&self, wire: WireId, on: bool) -> Result<()>;
 
-    fn switch_hvac(&self, action: &HvacAction) -> Result<()>;
+    /// Switch a single plugged wire, which may not be in the wiring config,
+    /// for testing wiring. Such wires aren't switched off by `switch_hvac`.
+    fn test_wire(&self, wire: WireId, on: bool) -> Result<()>;
 }
 
-pub struct Backplate<D> {
-    device: D
+/// Messages read from the backplate since startup, for the dev overlay
+static MESSAGE_COUNT: AtomicU64 = AtomicU64::new(0);
+
+pub fn message_count() -> u64 {
+    MESSAGE_COUNT.load(Ordering::Relaxed)
+}
+
+/// Don't go quiet when current temp is this close to starting a cycle
+const QUIET_TEMP_MARGIN: f32 = 0.3;
+
+/// Longest wait for the backplate to confirm wires are off when exiting
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(3);
+
+pub struct Backplate<D, S> {
+    device: D,
+    wiring: WireConfig,
+    temp_deadband: f32,
+    quiet_enabled: bool,
+    screen_on: bool,
+    near_cycle: bool,
+    quiet: bool,
+    off_on_shutdown: bool,
+    heartbeat: Heartbeat,
+    watchdog: Option<Watchdog>,
+    /// Last wire state sent to the device, re-asserted on each heartbeat
+    requested: Option<(HvacAction, u8, AuxHeat, bool)>,
+    service_enabled: bool,
+    service_timeout: Duration,
+    /// Wires switched from Home Assistant while in service mode, the
+    /// thermostat state doesn't switch wires until service mode ends
+    service: Option<Vec<(WireId, bool)>>,
+    /// Wire switched on by the wiring setup, with all others off until
+    /// the test ends
+    wire_test: Option<WireId>,
+    event_sender: S
 }
 
-impl Backplate<BackplateImpl> {
-    pub fn new<S>(config: &Config, event_sender: S) -> Result<Self>
-        where S: EventSender + Send + 'static
-    {
-        let device = BackplateImpl::new(config, event_sender)?;
-        Ok(Self { device })
+impl<S: EventSender + Clone + Send + 'static> Backplate<BackplateImpl, S> {
+    pub fn new(config: &Config, event_sender: S) -> Result<Self> {
+        let heartbeat = Heartbeat::default();
+        let device = BackplateImpl::new(config, heartbeat.clone(), event_sender.clone())?;
+
+        let backplate = &config.backplate;
+        let watchdog = if !backplate.failsafe_timeout.is_zero() || backplate.watchdog_device.is_some() {
+            Some(Watchdog::start(
+                backplate.watchdog_device.as_deref(),
+                backplate.failsafe_timeout,
+                heartbeat.clone(),
+                event_sender.clone()
+            )?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            device,
+            wiring: config.backplate.wiring.clone(),
+            temp_deadband: config.temp_deadband,
+            quiet_enabled: config.backplate.quiet_poll_period > Duration::ZERO,
+            screen_on: true,
+            near_cycle: true,
+            quiet: false,
+            off_on_shutdown: config.backplate.off_on_shutdown,
+            heartbeat,
+            watchdog,
+            requested: None,
+            service_enabled: config.backplate.service_mode,
+            service_timeout: config.backplate.service_timeout,
+            service: None,
+            wire_test: None,
+            event_sender
+        })
+    }
+
+    /// Leave HVAC in a safe state before exiting
+    pub fn shutdown(&mut self) {
+        if let Some(watchdog) = self.watchdog.take() {
+            watchdog.stop();
+        }
+
+        if !self.off_on_shutdown {
+            return;
+        }
+
+        info!("Switching wires off before exit");
+        if let Err(e) = self.device.switch_off(SHUTDOWN_TIMEOUT) {
+            error!("Failed to switch wires off: {e}");
+        }
+    }
+
+    /// Stop all writes before restarting, so a command isn't cut off part
+    /// way through. Wires are left as they are for the new process.
+    pub fn stop(&mut self) {
+        if let Some(watchdog) = self.watchdog.take() {
+            watchdog.stop();
+        }
+
+        if let Err(e) = self.device.stop(SHUTDOWN_TIMEOUT) {
+            error!("Failed to stop backplate writes: {e}");
+        }
+    }
+}
+
+impl<D, S> Backplate<D, S> {
+    /// Wires are switched by service mode or the wire test, rather than
+    /// the thermostat state
+    fn wires_held(&self) -> bool {
+        self.service.is_some() || self.wire_test.is_some()
+    }
+
+    /// True when HVAC is running, or current temp is close to starting a cycle
+    fn is_near_cycle(&self, state: &ThermostatState) -> bool {
+        let threshold = match state.mode {
+            HvacMode::Heat | HvacMode::EmergencyHeat => state.target_temp - self.temp_deadband,
+            HvacMode::Cool => state.target_temp + self.temp_deadband,
+            HvacMode::HeatCool => {
+                let heat = state.target_temp_low - self.temp_deadband;
+                let cool = state.target_temp_high + self.temp_deadband;
+                return state.action != HvacAction::Idle
+                    || (state.current_temp - heat).abs() < QUIET_TEMP_MARGIN
+                    || (state.current_temp - cool).abs() < QUIET_TEMP_MARGIN;
+            }
+            _ => return state.action != HvacAction::Idle
+        };
+
+        state.action != HvacAction::Idle
+            || (state.current_temp - threshold).abs() < QUIET_TEMP_MARGIN
+    }
+
+    fn wants_quiet(&self) -> bool {
+        self.quiet_enabled && !self.screen_on && !self.near_cycle
     }
 }
 
-impl<D: BackplateDevice> EventHandler for Backplate<D> {
+impl<D: BackplateDevice, S: EventSender> EventHandler for Backplate<D, S> {
     fn handle_event(&mut self, event: &Event) -> Result<()> {
-        if let Event::State(state) = event {
-            if !state.lockout {
-                self.device.switch_hvac(&state.action)?;
+        // Any event shows the event loop is still running
+        self.heartbeat.beat();
+
+        match event {
+            Event::State(state) if self.wires_held() => {
+                // applied when service mode or the wire test ends
+                if !state.lockout {
+                    self.requested = Some((state.action, state.stage, state.aux, state.fan_on()));
+                }
+                self.near_cycle = self.is_near_cycle(state);
+            }
+            Event::State(state) => {
+                if !state.lockout {
+                    self.device.switch_hvac(&state.action, state.stage, state.aux, state.fan_on())?;
+                    self.requested = Some((state.action, state.stage, state.aux, state.fan_on()));
+                }
+
+                self.near_cycle = self.is_near_cycle(state);
+            }
+            // Restores wires switched off by the failsafe, once responding again
+            Event::Heartbeat if !self.wires_held() => {
+                if let Some((action, stage, aux, fan_on)) = &self.requested {
+                    self.device.switch_hvac(action, *stage, *aux, *fan_on)?;
+                }
+            }
+            Event::SetServiceMode(true) if !self.service_enabled => {
+                warn!("Service mode isn't enabled in the backplate config");
+            }
+            Event::SetServiceMode(true) if self.wire_test.is_some() => {
+                warn!("Ignoring service mode while testing wiring");
+            }
+            Event::SetServiceMode(true) => {
+                // Hand the wires over to Home Assistant, starting with all off
+                if self.service.is_none() {
+                    info!("Entering service mode for {:?}", self.service_timeout);
+                    self.device.switch_hvac(&HvacAction::Idle, 0, AuxHeat::Off, false)?;
+                    let wires = self.wiring.wires().into_iter().map(|w| (w, false)).collect();
+                    self.service = Some(wires);
+                }
+
+                // each request restarts the timeout
+                self.event_sender.send_event(Event::TimeoutReset(TimerId::ServiceMode, self.service_timeout))?;
+                self.event_sender.send_event(Event::ServiceMode(self.service.clone()))?;
+            }
+            Event::SetServiceMode(false) | Event::TimeoutReached(TimerId::ServiceMode) if self.service.is_some() => {
+                info!("Exiting service mode");
+                self.service = None;
+                self.event_sender.send_event(Event::CancelTimer(TimerId::ServiceMode))?;
+                self.event_sender.send_event(Event::ServiceMode(None))?;
+
+                // back to what the thermostat state wants
+                let (action, stage, aux, fan_on) = self.requested
+                    .unwrap_or((HvacAction::Idle, 0, AuxHeat::Off, false));
+                self.device.switch_hvac(&action, stage, aux, fan_on)?;
+            }
+            Event::SetWire(wire, on) => {
+                let Some(wires) = &mut self.service else {
+                    warn!("Ignoring switch of {wire:?}, not in service mode");
+                    return Ok(());
+                };
+                let Some(state) = wires.iter_mut().find(|(w, _)| w == wire) else {
+                    warn!("Ignoring switch of unconfigured wire {wire:?}");
+                    return Ok(());
+                };
+
+                info!("Service mode switching {wire:?} {}", if *on { "on" } else { "off" });
+                self.device.switch_wire(*wire, *on)?;
+                state.1 = *on;
+
+                self.event_sender.send_event(Event::ServiceMode(self.service.clone()))?;
             }
+            Event::TestWire(Some(_)) if self.service.is_some() => {
+                warn!("Ignoring wire test while in service mode");
+            }
+            Event::TestWire(Some(wire)) => {
+                match self.wire_test.replace(*wire) {
+                    Some(tested) => self.device.test_wire(tested, false)?,
+                    None => self.device.switch_hvac(&HvacAction::Idle, 0, AuxHeat::Off, false)?
+                }
+
+                info!("Testing wire {wire:?}");
+                self.device.test_wire(*wire, true)?;
+                self.event_sender.send_event(Event::TimeoutReset(TimerId::WireTest, self.service_timeout))?;
+            }
+            Event::TestWire(None) | Event::TimeoutReached(TimerId::WireTest) => {
+                if let Some(tested) = self.wire_test.take() {
+                    info!("Wire test of {tested:?} ended");
+                    self.device.test_wire(tested, false)?;
+                    self.event_sender.send_event(Event::CancelTimer(TimerId::WireTest))?;
+
+                    let (action, stage, aux, fan_on) = self.requested
+                        .unwrap_or((HvacAction::Idle, 0, AuxHeat::Off, false));
+                    self.device.switch_hvac(&action, stage, aux, fan_on)?;
+                }
+            }
+            Event::GetState => {
+                self.event_sender.send_event(Event::ServiceMode(self.service.clone()))?;
+            }
+            Event::ConfigReloaded(reloaded) => {
+                self.service_enabled = reloaded.config.backplate.service_mode;
+                self.service_timeout = reloaded.config.backplate.service_timeout;
+                if !self.service_enabled && self.service.is_some() {
+                    self.event_sender.send_event(Event::SetServiceMode(false))?;
+                }
+            }
+            Event::Deadband(deadband) => {
+                self.temp_deadband = *deadband;
+            }
+            Event::TimeoutReset(TimerId::Backlight, _) => {
+                self.screen_on = true;
+            }
+            Event::TimeoutReached(TimerId::Backlight) => {
+                self.screen_on = false;
+            }
+            Event::BackplateCapabilities(capabilities) => {
+                info!("Backplate hardware {}", capabilities.hardware_version);
+
+                for wire in capabilities.missing_wires(&self.wiring) {
+                    warn!("Configured wire {wire:?} not detected on backplate");
+                }
+            }
+            _ => { }
+        }
+
+        let quiet = self.wants_quiet();
+        if quiet != self.quiet {
+            info!("Backplate quiet {quiet}");
+            self.quiet = quiet;
+            self.device.set_quiet(quiet)?;
         }
 
         Ok(())
     }
+
+    fn topics(&self) -> &'static [Topic] {
+        &[Topic::State, Topic::Backplate, Topic::Timer]
+    }
 }
@@ -17,152 +17,483 @@
  */
 
 use std::{
-    sync::{Arc, Mutex, mpsc::{Receiver, Sender, channel}},
+    collections::HashMap,
+    sync::{
+        Arc, Mutex,
+        PoisonError, TryLockError,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{Receiver, RecvTimeoutError, SyncSender, TrySendError, sync_channel}
+    },
     thread,
     time::{Duration, Instant}
 };
 
-use anyhow::Result;
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Local, TimeDelta};
 use log::{debug, error, info, warn};
-use nest_backplate::{BackplateCmd, BackplateConnection, BackplateResponse, Wire};
+use nest_backplate::{
+    BackplateCmd, BackplateConnection, BackplateResponse, BackplateWires, BackplateWriter,
+    WakeupMask, Wire, WireProfile
+};
 
 use crate::{
-    config::{BackplateConfig, Config, WireConfig, WireId},
+    config::{BackplateConfig, Config, WireConfig, WireId, WireProfileId},
     events::{Event, EventSender},
-    state::HvacAction
+    state::{AuxHeat, HvacAction},
+    supervisor::{Subsystem, supervise}
+};
+use super::{
+    BackplateCapabilities, BackplateDevice, LinkStats, PowerReading, WirePresence,
+    failsafe::{Failsafe, Heartbeat},
+    temp_filter::TempFilter
 };
-use super::{BackplateDevice};
 
+/// Ambient light change required before a new reading is reported
+const LIGHT_THRESHOLD: u16 = 10;
+
+/// Commands waiting for the writer thread, beyond this switching fails
+const COMMAND_QUEUE_LEN: usize = 32;
+
+/// How often the writer thread checks for missing wire switch acks
+const WRITER_TICK: Duration = Duration::from_millis(100);
+
+/// Wait for a `WireSwitched` ack before re-sending, doubled on each retry
+const ACK_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Longest wait for a message before the read loop checks its timers
+const READ_POLL: Duration = Duration::from_secs(1);
+
+/// Assumed interval between readings in the backplate sensor buffers,
+/// for estimating when each was taken
+const SENSOR_BUFFER_PERIOD: TimeDelta = TimeDelta::seconds(30);
+
+/// Give up on a wire switch after this many sends
+const MAX_SWITCH_ATTEMPTS: u32 = 5;
+
+/// Backplate connection on a supervised thread. Commands are queued to a
+/// writer thread that sends them as they arrive, without waiting for the
+/// read loop, so they go out promptly even while the backplate is silent.
 pub struct DeviceBackplateThread {
-    cmd_sender: Sender<BackplateCmd>,
+    cmd_sender: SyncSender<BackplateCmd>,
     wire_state: Arc<Mutex<SwitchState>>,
+    quiet: Arc<AtomicBool>,
+    writer: Arc<Mutex<ActiveWriter>>
+}
+
+/// Writer for the open connection, closed by `stop` so nothing more is
+/// written to the backplate, including from later connections
+#[derive(Default)]
+struct ActiveWriter {
+    writer: Option<BackplateWriter>,
+    stopped: bool
 }
 
 impl DeviceBackplateThread {
-    const RECONNECT_TIMEOUT: Duration = Duration::from_secs(1);
     const KEEPALIVE_PERIOD: Duration = Duration::from_mins(15);
 
-    pub fn start<S>(config: BackplateConfig, event_sender: S) -> Result<Self>
-        where S: EventSender + Send + 'static
+    pub fn start<S>(config: BackplateConfig, heartbeat: Heartbeat, event_sender: S) -> Result<Self>
+        where S: EventSender + Clone + Send + 'static
     {
-        let (cmd_sender, cmd_receiver) = channel();
-        let serial_port = config.serial_port.clone();
-        let near_pir_threshold = config.near_pir_threshold;
+        let (cmd_sender, mut cmd_receiver) = sync_channel(COMMAND_QUEUE_LEN);
 
         let wire_state = match config.wiring {
-            WireConfig::HeatAndCool { heat_wire, cool_wire, fan_wire } => {
-                SwitchState::new(heat_wire.into(), cool_wire.into(), fan_wire.into())
+            WireConfig::HeatAndCool { heat_wire, cool_wire, fan_wire, heat2_wire, cool2_wire, aux_wire } => {
+                SwitchState::new(
+                    heat_wire.into(), cool_wire.map(Wire::from), fan_wire.map(Wire::from),
+                    heat2_wire.map(Wire::from), cool2_wire.map(Wire::from), aux_wire.map(Wire::from)
+                )
             }
         };
         let wire_state = Arc::new(Mutex::new(wire_state));
         let wire_state_clone = wire_state.clone();
 
-        // Should I have spearate read/write threads?
-        // With a single thread, I am relying on the backplate to send a message
-        // before I can send one back. Maybe that's OK though, since the backplate
-        // seems to constanty send messages.
-        thread::spawn(move || {
-            loop {
-                // drain cmd_receiver incase cmds sent while disconnected
-                while let Ok(_) = cmd_receiver.try_recv() { }
-
-                // reset back to "Idle" since that's the state on backplate connect
-                wire_state.lock().unwrap().clear();
-
-                let result = backplate_main_loop(
-                    &serial_port,
-                    near_pir_threshold,
-                    Self::KEEPALIVE_PERIOD,
-                    &event_sender,
-                    &cmd_receiver,
-                    &wire_state
-                );
-
-                match result {
-                    Ok(_) => unreachable!("Backplate message loop should not return Ok"),
-                    Err(error) => {
-                        event_sender.send_event(Event::BackplateDisconnected).unwrap();
-
-                        error!(
-                            "Backplate thread error `{}`, reconnect in {:?}",
-                            error, Self::RECONNECT_TIMEOUT
-                        );
-
-                        thread::sleep(Self::RECONNECT_TIMEOUT);
-                    }
-                }
-            }
+        let quiet = Arc::new(AtomicBool::new(false));
+        let quiet_clone = quiet.clone();
+
+        let writer = Arc::new(Mutex::new(ActiveWriter::default()));
+        let writer_clone = writer.clone();
+
+        // Kept across reconnects so capabilities are only queried once
+        let mut capabilities = CapabilitiesQuery::default();
+
+        // Reads on the supervised thread, commands are written on a second
+        // thread per connection since the backplate sends nothing while quiet.
+        // The supervisor reconnects with backoff when the connection fails.
+        supervise(Subsystem::Backplate, event_sender.clone(), move || {
+            // drain cmd_receiver incase cmds sent while disconnected
+            while let Ok(_) = cmd_receiver.try_recv() { }
+
+            // reset back to "Idle" since that's the state on backplate connect
+            wire_state.lock().unwrap().clear();
+
+            let result = backplate_main_loop(
+                &config,
+                Self::KEEPALIVE_PERIOD,
+                &event_sender,
+                &mut cmd_receiver,
+                &wire_state,
+                &writer,
+                &mut capabilities,
+                QuietMode::new(quiet.clone(), config.quiet_poll_period),
+                Failsafe::new(heartbeat.clone(), config.failsafe_timeout)
+            );
+
+            event_sender.send_event(Event::BackplateDisconnected)?;
+            result
         });
 
         Ok(Self {
             cmd_sender,
             wire_state: wire_state_clone,
+            quiet: quiet_clone,
+            writer: writer_clone
         })
     }
+
+    fn send_switch(&self, wire: Wire, on: bool) -> Result<()> {
+        match self.cmd_sender.try_send(BackplateCmd::SwitchWire(wire, on)) {
+            Err(TrySendError::Full(_)) => Err(anyhow!("Backplate command queue full")),
+            r => Ok(r?)
+        }
+    }
 }
 
+/// Open the backplate and read messages, with commands written from a
+/// separate thread so they go out even when the backplate is quiet
+#[allow(clippy::too_many_arguments)]
 fn backplate_main_loop<S: EventSender>(
-    dev_path: &str,
-    near_pir_threshold: u16,
+    config: &BackplateConfig,
     keepalive_period: Duration,
     event_sender: &S,
-    cmd_receiver: &Receiver<BackplateCmd>,
-    wire_state: &Arc<Mutex<SwitchState>>
+    cmd_receiver: &mut Receiver<BackplateCmd>,
+    wire_state: &Arc<Mutex<SwitchState>>,
+    active_writer: &Mutex<ActiveWriter>,
+    capabilities: &mut CapabilitiesQuery,
+    quiet: QuietMode,
+    failsafe: Failsafe
 ) -> Result<()> {
-    let mut backplate = BackplateConnection::open(dev_path)?;
+    // held while connecting, so stopping waits for the reset sequence
+    let backplate = {
+        let mut active = active_writer.lock().unwrap_or_else(PoisonError::into_inner);
+        if active.stopped {
+            return Ok(());
+        }
+
+        let backplate = open_connection(config)?;
+        active.writer = Some(backplate.writer());
+        backplate
+    };
 
     event_sender.send_event(Event::BackplateConnected)?;
 
-    // Log backplate version details
-    backplate.send_command(BackplateCmd::GetTfeBuildInfo)?;
+    let tracker = Mutex::new(SwitchTracker::default());
+    let connected = AtomicBool::new(true);
+
+    thread::scope(|scope| {
+        let writer = backplate.writer();
+        scope.spawn(|| command_writer(writer, cmd_receiver, &tracker, &connected));
+
+        let result = backplate_read_loop(
+            backplate,
+            config,
+            keepalive_period,
+            event_sender,
+            wire_state,
+            capabilities,
+            quiet,
+            failsafe,
+            &tracker,
+            &connected
+        );
+
+        // stop the writer, so the scope can end
+        connected.store(false, Ordering::Relaxed);
+
+        result
+    })
+}
+
+/// Connect to the serial port, or replay a capture when configured
+fn open_connection(config: &BackplateConfig) -> Result<BackplateConnection> {
+    let backplate = if let Some(replay_file) = &config.replay_file {
+        info!("Replaying backplate capture {replay_file:?}");
+        BackplateConnection::replay(replay_file, true)?
+    } else if let Some(capture_file) = &config.capture_file {
+        info!("Capturing backplate traffic to {capture_file:?}");
+        BackplateConnection::open_capture(&config.serial_port, capture_file, config.read_timeout)?
+    } else {
+        BackplateConnection::open(&config.serial_port, config.read_timeout)?
+    };
+
+    Ok(backplate)
+}
+
+/// Write queued commands, re-sending wire switches the backplate didn't ack.
+/// Exits when the read loop ends, or when writing fails.
+fn command_writer(
+    writer: BackplateWriter,
+    cmd_receiver: &mut Receiver<BackplateCmd>,
+    tracker: &Mutex<SwitchTracker>,
+    connected: &AtomicBool
+) {
+    while connected.load(Ordering::Relaxed) {
+        let result = match cmd_receiver.recv_timeout(WRITER_TICK) {
+            Ok(BackplateCmd::SwitchWire(wire, on)) => {
+                if tracker.lock().unwrap().sent(wire, on) {
+                    writer.send_command(BackplateCmd::SwitchWire(wire, on))
+                } else {
+                    Ok(())
+                }
+            }
+            Ok(cmd) => writer.send_command(cmd),
+            Err(RecvTimeoutError::Timeout) => Ok(()),
+            Err(RecvTimeoutError::Disconnected) => break
+        };
+
+        let retries = tracker.lock().unwrap().retries();
+        let result = result.and_then(|_| {
+            retries.into_iter().try_for_each(|cmd| writer.send_command(cmd))
+        });
+
+        if let Err(e) = result {
+            error!("Backplate write failed: {e}");
+            connected.store(false, Ordering::Relaxed);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn backplate_read_loop<S: EventSender>(
+    mut backplate: BackplateConnection,
+    config: &BackplateConfig,
+    keepalive_period: Duration,
+    event_sender: &S,
+    wire_state: &Arc<Mutex<SwitchState>>,
+    capabilities: &mut CapabilitiesQuery,
+    mut quiet: QuietMode,
+    mut failsafe: Failsafe,
+    tracker: &Mutex<SwitchTracker>,
+    connected: &AtomicBool
+) -> Result<()> {
+    let mut temp_filter = TempFilter::new(config);
+    let mut last_power: Option<PowerReading> = None;
+    let mut last_light: Option<u16> = None;
+    let mut last_stats: Option<LinkStats> = None;
+    let mut liveness = Liveness::new(config);
+    // No reading yet on this connection, the newest buffered one stands in
+    let mut have_climate = false;
+    // Compared with the switched wires once the next message shows it
+    // wasn't part of a reset, which switches all wires off
+    let mut power_presence: Option<WirePresence> = None;
+
+    // Known once the hardware version has been received, on reconnect
+    // it's set before any wires are switched
+    backplate.set_wire_profile(capabilities.wire_profile(config).into());
+
+    if !capabilities.reported {
+        for cmd in CapabilitiesQuery::commands() {
+            backplate.send_command(cmd)?;
+        }
+    }
+
+    // readings taken before connecting, such as while restarting
+    backplate.send_command(BackplateCmd::GetSensorBuffers)?;
 
     // This triggers a constant stream of messages
     backplate.send_command(BackplateCmd::StatusRequest)?;
     let mut last_status_request = Instant::now();
 
     loop {
-        match backplate.read_message()? {
-            BackplateResponse::Climate(c) => {
-                event_sender.send_event(Event::SetCurrentTemp(c.temperature))?;
+        // Silence is checked below, nothing is sent while quiet
+        let message = backplate.poll_message(READ_POLL)?;
+
+        if let Some(message) = message {
+            super::MESSAGE_COUNT.fetch_add(1, Ordering::Relaxed);
+            liveness.message_received();
+
+            if let Some(power) = power_presence.take()
+                && !message.is_break()
+            {
+                tracker.lock().unwrap().power_presence(&power);
             }
-            BackplateResponse::NearPir(val) => {
-                if val > near_pir_threshold {
-                    event_sender.send_event(Event::ProximityNear)?;
+
+            match message {
+                BackplateResponse::Climate(c) => {
+                    let temp = temp_filter.apply(c.temperature);
+                    event_sender.send_event(Event::BackplateTemp(temp))?;
+                    event_sender.send_event(Event::Humidity(c.humidity))?;
+                    quiet.reading_received(&backplate)?;
+                    have_climate = true;
                 }
-            }
-            BackplateResponse::Pir { val1, val2 } => {
-                if val1 + val2 > 0 {
-                    event_sender.send_event(Event::ProximityFar)?;
+                BackplateResponse::NearPir(val) => {
+                    if val > config.near_pir_threshold {
+                        event_sender.send_event(Event::ProximityNear)?;
+                    }
                 }
-            }
-            BackplateResponse::WireSwitched(wire, state) => {
-                info!("WireSwitched {wire:?}: {state}");
-                wire_state.lock().unwrap().set_wire_state(wire, state);
-            }
-            BackplateResponse::TfeBuildInfo(s) => {
-                info!("{}", s);
-            }
-            // BackplateResponse::AmbientLightSensor(_) => { }
-            // BackplateResponse::Raw(Message { command_id: 19, .. }) => { }
-            x if x.is_break() => {
-                warn!("Break received, resetting");
-                backplate.reset_ack()?;
+                BackplateResponse::Pir { val1, val2 } => {
+                    if val1 + val2 > 0 {
+                        event_sender.send_event(Event::ProximityFar)?;
+                    }
+                }
+                // Sent in response to polling, with activity during sleep.
+                // Proximity wakes the screen, which takes the backplate out of quiet.
+                BackplateResponse::WakeupVector(mask) => {
+                    if mask.contains(WakeupMask::NEAR_PIR) {
+                        event_sender.send_event(Event::ProximityNear)?;
+                    }
+                    if mask.contains(WakeupMask::PIR) {
+                        event_sender.send_event(Event::ProximityFar)?;
+                    }
+                    if mask.contains(WakeupMask::BUFFERS_FULL) {
+                        backplate.send_command(BackplateCmd::GetSensorBuffers)?;
+                    }
+                }
+                BackplateResponse::BufferedClimateData(history) => {
+                    debug!("Buffered climate readings {}", history.len());
+                    let temps = buffered_times(history.len()).into_iter()
+                        .zip(&history)
+                        .map(|(time, c)| (time, temp_filter.calibrate(c.temperature)))
+                        .collect();
+                    event_sender.send_event(Event::BufferedTemps(temps))?;
 
-                // Resume message stream
-                backplate.send_command(BackplateCmd::StatusRequest)?;
+                    if !have_climate && let Some(latest) = history.last() {
+                        event_sender.send_event(Event::BackplateTemp(temp_filter.apply(latest.temperature)))?;
+                        event_sender.send_event(Event::Humidity(latest.humidity))?;
+                        have_climate = true;
+                    }
+                }
+                BackplateResponse::BufferedPowerData(history) => {
+                    debug!("Buffered power readings {}", history.len());
+                    if let (Some(latest), Some(last)) = (history.last(), last_power) {
+                        let reading = PowerReading {
+                            volts_in: latest.volts_in,
+                            volts_bat: latest.volts_bat,
+                            ..last
+                        };
+                        if last.differs(&reading) {
+                            event_sender.send_event(Event::BackplatePower(reading))?;
+                            last_power = Some(reading);
+                        }
+                    }
+                }
+                // Clears the buffers, so the next drain only has new readings
+                BackplateResponse::EndSensorBuffers => {
+                    backplate.send_command(BackplateCmd::AckSensorBuffers)?;
+                }
+                BackplateResponse::PowerState { charging, volts_in, volts_bat, .. } => {
+                    let reading = PowerReading { volts_in, volts_bat, charging };
+                    if last_power.is_none_or(|last| last.differs(&reading)) {
+                        event_sender.send_event(Event::BackplatePower(reading))?;
+                        last_power = Some(reading);
+                    }
+                }
+                BackplateResponse::WireSwitched(wire, state) => {
+                    info!("WireSwitched {wire:?}: {state}");
+                    tracker.lock().unwrap().acked(wire, state);
+                    wire_state.lock().unwrap().set_wire_state(wire, state);
+                }
+                BackplateResponse::WirePowerPresence(wires) => {
+                    power_presence = Some(WirePresence::from(wires));
+                }
+                BackplateResponse::TfeVersion(s) => {
+                    capabilities.tfe_version = Some(s);
+                }
+                BackplateResponse::TfeBuildInfo(s) => {
+                    info!("{}", s);
+                    capabilities.tfe_build_info = Some(s);
+                }
+                BackplateResponse::BslVersion(s) => {
+                    capabilities.bsl_version = Some(s);
+                }
+                BackplateResponse::BslInfo(s) => {
+                    capabilities.bsl_info = Some(s);
+                }
+                BackplateResponse::HardwareVersion(s) => {
+                    if config.wire_profile.is_none() && WireProfile::for_hardware_version(&s).is_none() {
+                        warn!("Unknown backplate model {s}, using standard wire ids");
+                    }
+                    capabilities.hardware_version = Some(s);
+                    let profile = capabilities.wire_profile(config);
+                    info!("Backplate wire profile {profile:?}");
+                    backplate.set_wire_profile(profile.into());
+                }
+                BackplateResponse::Serial(s) => {
+                    // capabilities may have been sent without it, send them again
+                    if capabilities.serial_number.as_ref() != Some(&s) {
+                        capabilities.reported = false;
+                    }
+                    capabilities.serial_number = Some(s);
+                }
+                BackplateResponse::WirePluggedPresence(wires) => {
+                    let presence = WirePresence::from(wires);
+                    let missing = presence.missing_wires(&config.wiring);
+                    let mut state = wire_state.lock().unwrap();
+                    if state.set_missing(&missing) {
+                        warn!("Configured wires not plugged {missing:?}, they won't be switched on");
+                    }
+                    state.plugged = Some(presence.clone());
+                    drop(state);
+                    capabilities.wires = Some(presence);
+                }
+                BackplateResponse::AmbientLightSensor(val) => {
+                    if last_light.is_none_or(|last| last.abs_diff(val) >= LIGHT_THRESHOLD) {
+                        event_sender.send_event(Event::AmbientLight(val))?;
+                        last_light = Some(val);
+                    }
+                }
+                // BackplateResponse::Raw(Message { command_id: 19, .. }) => { }
+                x if x.is_break() => {
+                    warn!("Break received, resetting");
+                    backplate.reset_ack()?;
 
-                // Restore wire state switches
-                for cmd in wire_state.lock().unwrap().commands() {
-                    backplate.send_command(cmd)?;
+                    // Resume message stream
+                    backplate.send_command(BackplateCmd::StatusRequest)?;
+                    quiet.active = false;
+
+                    // Restore wire state switches
+                    for cmd in wire_state.lock().unwrap().commands() {
+                        backplate.send_command(cmd)?;
+                    }
+                }
+                msg => {
+                    debug!("{:?}", msg);
                 }
-            }
-            msg => {
-                debug!("{:?}", msg);
             }
         }
 
-        if let Ok(cmd) = cmd_receiver.try_recv() {
-            backplate.send_command(cmd)?;
+        let stats = backplate.stats();
+        if last_stats != Some(stats) {
+            event_sender.send_event(Event::BackplateLinkStats(stats))?;
+            last_stats = Some(stats);
+        }
+
+        if !capabilities.reported && let Some(value) = capabilities.build(config) {
+            event_sender.send_event(Event::BackplateCapabilities(value))?;
+            capabilities.reported = true;
+        }
+
+        if let Some(faults) = tracker.lock().unwrap().fault_change() {
+            let wires = faults.into_iter().map(WireId::from).collect();
+            event_sender.send_event(Event::HvacFault(wires))?;
+        }
+
+        if !connected.load(Ordering::Relaxed) {
+            return Err(anyhow!("Backplate writer stopped"));
+        }
+
+        quiet.update(&backplate, &mut last_status_request)?;
+        liveness.update(&backplate, &quiet, event_sender)?;
+
+        // Wires come back on with the next heartbeat once responding again
+        match failsafe.update() {
+            Some(true) if !wire_state.lock().unwrap().is_active(&HvacAction::Idle, 0, AuxHeat::Off, false) => {
+                error!("Event loop not responding, switching wires off");
+                for cmd in wire_state.lock().unwrap().switch_commands(&HvacAction::Idle, 0, AuxHeat::Off, false) {
+                    backplate.send_command(cmd)?;
+                }
+            }
+            Some(false) => info!("Event loop responding again"),
+            _ => { }
         }
 
         // Nest will reboot itself 30min after starting backplate comms.
@@ -177,28 +508,383 @@ fn backplate_main_loop<S: EventSender>(
 }
 
 impl BackplateDevice for DeviceBackplateThread {
-    fn new<S>(config: &Config, event_sender: S) -> Result<Self>
-        where S: EventSender + Send + 'static, Self: Sized
+    fn new<S>(config: &Config, heartbeat: Heartbeat, event_sender: S) -> Result<Self>
+        where S: EventSender + Clone + Send + 'static, Self: Sized
     {
         DeviceBackplateThread::start(
             config.backplate.clone(),
+            heartbeat,
             event_sender
         )
     }
 
-    fn switch_hvac(&self, action: &HvacAction) -> Result<()> {
+    fn switch_hvac(&self, action: &HvacAction, stage: u8, aux: AuxHeat, fan_on: bool) -> Result<()> {
         let state = self.wire_state.lock().unwrap();
 
-        if !state.is_active(action) {
-            for cmd in state.switch_commands(action) {
-                self.cmd_sender.send(cmd)?;
+        if !state.is_active(action, stage, aux, fan_on) {
+            for cmd in state.switch_commands(action, stage, aux, fan_on) {
+                match self.cmd_sender.try_send(cmd) {
+                    Err(TrySendError::Full(_)) => {
+                        return Err(anyhow!("Backplate command queue full"));
+                    }
+                    r => r?
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn set_quiet(&self, quiet: bool) -> Result<()> {
+        self.quiet.store(quiet, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn switch_off(&self, timeout: Duration) -> Result<()> {
+        self.switch_hvac(&HvacAction::Idle, 0, AuxHeat::Off, false)?;
+
+        // wire state is updated as the writer thread gets acks
+        let deadline = Instant::now() + timeout;
+        while !self.wire_state.lock().unwrap().is_active(&HvacAction::Idle, 0, AuxHeat::Off, false) {
+            if Instant::now() >= deadline {
+                return Err(anyhow!("Backplate didn't confirm wires off within {timeout:?}"));
+            }
+            thread::sleep(WRITER_TICK);
+        }
+
+        Ok(())
+    }
+
+    fn stop(&self, timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        let mut active = loop {
+            match self.writer.try_lock() {
+                Ok(active) => break active,
+                Err(TryLockError::Poisoned(e)) => break e.into_inner(),
+                Err(TryLockError::WouldBlock) if Instant::now() < deadline => {
+                    thread::sleep(WRITER_TICK);
+                }
+                Err(TryLockError::WouldBlock) => {
+                    return Err(anyhow!("Backplate still connecting after {timeout:?}"));
+                }
+            }
+        };
+
+        active.stopped = true;
+        if let Some(writer) = active.writer.take() {
+            writer.close();
+        }
+
+        Ok(())
+    }
+
+    fn switch_wire(&self, wire: WireId, on: bool) -> Result<()> {
+        let wire = Wire::from(wire);
+        let state = self.wire_state.lock().unwrap();
+
+        if !state.wires().any(|(w, _)| *w == wire) {
+            return Err(anyhow!("Wire {wire:?} isn't in the wiring config"));
+        }
+        if on && state.missing.contains(&wire) {
+            return Err(anyhow!("Wire {wire:?} isn't detected on the backplate"));
+        }
+
+        self.send_switch(wire, on)
+    }
+
+    fn test_wire(&self, wire: WireId, on: bool) -> Result<()> {
+        let plugged = self.wire_state.lock().unwrap().plugged.as_ref()
+            .is_some_and(|p| p.has_wire(wire));
+
+        if on && !plugged {
+            return Err(anyhow!("Wire {wire:?} isn't plugged into the backplate"));
+        }
+
+        self.send_switch(Wire::from(wire), on)
+    }
+}
+
+/// Puts the backplate to sleep when requested by the app, polling it
+/// for readings with `StatusRequest` at `poll_period`. Readings the backplate
+/// buffered while asleep are drained when it wakes.
+struct QuietMode {
+    requested: Arc<AtomicBool>,
+    poll_period: Duration,
+    active: bool,
+    polling: bool
+}
+
+impl QuietMode {
+    fn new(requested: Arc<AtomicBool>, poll_period: Duration) -> Self {
+        Self {
+            requested,
+            poll_period,
+            active: false,
+            polling: false
+        }
+    }
+
+    fn sleep(&mut self, backplate: &BackplateConnection) -> Result<()> {
+        let max_sleep_sec = self.poll_period.as_secs().clamp(1, u16::MAX as u64);
+        backplate.send_command(BackplateCmd::Quiet(max_sleep_sec as u16))?;
+        self.polling = false;
+        Ok(())
+    }
+
+    fn update(
+        &mut self,
+        backplate: &BackplateConnection,
+        last_status_request: &mut Instant
+    ) -> Result<()> {
+        let requested = self.requested.load(Ordering::Relaxed);
+
+        if requested && !self.active {
+            self.sleep(backplate)?;
+            self.active = true;
+            *last_status_request = Instant::now();
+        } else if !requested && self.active {
+            backplate.send_command(BackplateCmd::StatusRequest)?;
+            // readings taken while asleep
+            backplate.send_command(BackplateCmd::GetSensorBuffers)?;
+            self.active = false;
+            *last_status_request = Instant::now();
+        } else if self.active && last_status_request.elapsed() > self.poll_period {
+            backplate.send_command(BackplateCmd::StatusRequest)?;
+            self.polling = true;
+            *last_status_request = Instant::now();
+        }
+
+        Ok(())
+    }
+
+    /// Go back to sleep once the polled reading arrives
+    fn reading_received(&mut self, backplate: &BackplateConnection) -> Result<()> {
+        if self.active && self.polling {
+            self.sleep(backplate)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Estimated times of `count` buffered readings, oldest to newest, with the
+/// newest taken now
+fn buffered_times(count: usize) -> Vec<DateTime<Local>> {
+    let now = Local::now();
+    (0..count)
+        .map(|i| now - SENSOR_BUFFER_PERIOD * (count - 1 - i) as i32)
+        .collect()
+}
+
+/// Detects a backplate that stopped sending. After `silence_timeout` with
+/// nothing received, readings are requested again. After `offline_timeout`
+/// the read loop fails, so the supervisor reconnects with a reset.
+struct Liveness {
+    silence_timeout: Duration,
+    offline_timeout: Duration,
+    last_message: Instant,
+    /// `StatusRequest` sent for the current silence
+    probed: bool
+}
+
+impl Liveness {
+    fn new(config: &BackplateConfig) -> Self {
+        Self {
+            silence_timeout: config.silence_timeout,
+            offline_timeout: config.offline_timeout,
+            last_message: Instant::now(),
+            probed: false
+        }
+    }
+
+    fn message_received(&mut self) {
+        if self.probed {
+            info!("Backplate responding again");
+        }
+        self.last_message = Instant::now();
+        self.probed = false;
+    }
+
+    fn check(&mut self, now: Instant, quiet: &QuietMode) -> Silence {
+        // only polled readings are sent while quiet
+        let expected = if quiet.active { quiet.poll_period } else { Duration::ZERO };
+        let silence = now.saturating_duration_since(self.last_message).saturating_sub(expected);
+
+        if silence > self.offline_timeout {
+            Silence::Offline
+        } else if silence > self.silence_timeout && !self.probed {
+            self.probed = true;
+            Silence::Probe
+        } else {
+            Silence::Expected
+        }
+    }
+
+    fn update<S: EventSender>(
+        &mut self,
+        backplate: &BackplateConnection,
+        quiet: &QuietMode,
+        event_sender: &S
+    ) -> Result<()> {
+        match self.check(Instant::now(), quiet) {
+            Silence::Offline => {
+                event_sender.send_event(Event::BackplateOffline)?;
+                return Err(anyhow!("Backplate silent for {:?}", self.last_message.elapsed()));
             }
+            Silence::Probe => {
+                warn!("Backplate silent for {:?}, requesting status", self.last_message.elapsed());
+                backplate.send_command(BackplateCmd::StatusRequest)?;
+            }
+            Silence::Expected => { }
         }
 
         Ok(())
     }
 }
 
+#[derive(Debug, PartialEq)]
+enum Silence {
+    Expected,
+    /// Silent past `silence_timeout`, request status once
+    Probe,
+    /// Silent past `offline_timeout`, reconnect
+    Offline
+}
+
+/// Tracks wire switch commands waiting for a `WireSwitched` ack from the
+/// backplate, and wires that failed to switch
+#[derive(Default)]
+struct SwitchTracker {
+    pending: HashMap<Wire, PendingSwitch>,
+    /// Last commanded state of each wire
+    expected: HashMap<Wire, bool>,
+    /// Wires that didn't ack a switch, switched without being asked, or
+    /// have power that doesn't match their switch
+    faults: Vec<Wire>,
+    /// Faults last sent to the app, `None` until the first report
+    reported: Option<Vec<Wire>>
+}
+
+struct PendingSwitch {
+    on: bool,
+    sent_at: Instant,
+    attempts: u32
+}
+
+impl SwitchTracker {
+    /// Track a switch command, returns false when the same switch is
+    /// already waiting for an ack and doesn't need to be sent again
+    fn sent(&mut self, wire: Wire, on: bool) -> bool {
+        self.expected.insert(wire, on);
+
+        if self.pending.get(&wire).is_some_and(|p| p.on == on) {
+            return false;
+        }
+
+        self.pending.insert(wire, PendingSwitch { on, sent_at: Instant::now(), attempts: 1 });
+        true
+    }
+
+    fn acked(&mut self, wire: Wire, on: bool) {
+        match self.pending.get(&wire).map(|p| p.on) {
+            Some(pending_on) if pending_on == on => {
+                self.pending.remove(&wire);
+                self.set_fault(wire, false);
+            }
+            // ack of an earlier command, the latest is still pending
+            Some(_) => { }
+            None => {
+                let unexpected = self.expected.get(&wire).is_some_and(|e| *e != on);
+                if unexpected {
+                    warn!("Backplate switched {wire:?} to {on} without a command");
+                }
+                self.set_fault(wire, unexpected);
+            }
+        }
+    }
+
+    /// Compare wire power with the last commanded state of each wire,
+    /// skipping wires still waiting for an ack
+    fn power_presence(&mut self, power: &WirePresence) {
+        let settled: Vec<(Wire, bool)> = self.expected.iter()
+            .filter(|(wire, _)| !self.pending.contains_key(wire))
+            .map(|(wire, on)| (*wire, *on))
+            .collect();
+
+        for (wire, on) in settled {
+            let powered = power.has_wire(wire.into());
+            if powered != on {
+                warn!("Backplate reports {wire:?} powered {powered}, expected {on}");
+            }
+            self.set_fault(wire, powered != on);
+        }
+    }
+
+    /// Commands to re-send for switches with no ack, backing off each attempt
+    fn retries(&mut self) -> Vec<BackplateCmd> {
+        let mut commands = Vec::new();
+        let mut failed = Vec::new();
+
+        self.pending.retain(|wire, pending| {
+            let backoff = ACK_TIMEOUT * 2u32.pow(pending.attempts - 1);
+            if pending.sent_at.elapsed() < backoff {
+                return true;
+            }
+
+            if pending.attempts >= MAX_SWITCH_ATTEMPTS {
+                error!("No ack switching {wire:?} after {} attempts", pending.attempts);
+                failed.push(*wire);
+                return false;
+            }
+
+            warn!("No ack switching {wire:?}, retry {}", pending.attempts);
+            pending.attempts += 1;
+            pending.sent_at = Instant::now();
+            commands.push(BackplateCmd::SwitchWire(*wire, pending.on));
+            true
+        });
+
+        for wire in failed {
+            self.set_fault(wire, true);
+        }
+
+        commands
+    }
+
+    fn set_fault(&mut self, wire: Wire, fault: bool) {
+        let index = self.faults.iter().position(|w| *w == wire);
+        match (index, fault) {
+            (None, true) => self.faults.push(wire),
+            (Some(index), false) => { self.faults.remove(index); }
+            _ => { }
+        }
+    }
+
+    /// Faulted wires when they changed since the last call
+    fn fault_change(&mut self) -> Option<Vec<Wire>> {
+        if self.reported.as_ref() == Some(&self.faults) {
+            return None;
+        }
+
+        self.reported = Some(self.faults.clone());
+        self.reported.clone()
+    }
+}
+
+impl From<Wire> for WireId {
+    fn from(value: Wire) -> Self {
+        match value {
+            Wire::W1 => Self::W1,
+            Wire::Y1 => Self::Y1,
+            Wire::G => Self::G,
+            Wire::OB => Self::OB,
+            Wire::W2 => Self::W2,
+            Wire::Y2 => Self::Y2,
+            Wire::Star => Self::Star
+        }
+    }
+}
+
 impl From<WireId> for Wire {
     fn from(value: WireId) -> Self {
         match value {
@@ -213,88 +899,390 @@ impl From<WireId> for Wire {
     }
 }
 
+/// Collects the responses to the capabilities queries
+#[derive(Default)]
+struct CapabilitiesQuery {
+    tfe_version: Option<String>,
+    tfe_build_info: Option<String>,
+    bsl_version: Option<String>,
+    bsl_info: Option<String>,
+    hardware_version: Option<String>,
+    serial_number: Option<String>,
+    wires: Option<WirePresence>,
+    reported: bool
+}
+
+impl CapabilitiesQuery {
+    // Wire presence isn't queried, it's part of the status message stream
+    fn commands() -> [BackplateCmd; 6] {
+        [
+            BackplateCmd::GetTfeVersion,
+            BackplateCmd::GetTfeBuildInfo,
+            BackplateCmd::GetBslVersion,
+            BackplateCmd::GetBslInfo,
+            BackplateCmd::GetHardwareVersion,
+            BackplateCmd::GetSerial,
+        ]
+    }
+
+    /// Returns capabilities once all responses have been received, the
+    /// serial is left empty until it's received since not all backplates
+    /// answer `GetSerial`
+    fn build(&self, config: &BackplateConfig) -> Option<BackplateCapabilities> {
+        Some(BackplateCapabilities {
+            tfe_version: self.tfe_version.clone()?,
+            tfe_build_info: self.tfe_build_info.clone()?,
+            bsl_version: self.bsl_version.clone()?,
+            bsl_info: self.bsl_info.clone()?,
+            hardware_version: self.hardware_version.clone()?,
+            serial_number: self.serial_number.clone().unwrap_or_default(),
+            wire_profile: self.wire_profile(config),
+            wires: self.wires.clone()?
+        })
+    }
+
+    /// Profile from the config, otherwise the one for the hardware version
+    fn wire_profile(&self, config: &BackplateConfig) -> WireProfileId {
+        config.wire_profile
+            .or_else(|| {
+                let version = self.hardware_version.as_deref()?;
+                WireProfile::for_hardware_version(version).map(WireProfileId::from)
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl From<WireProfile> for WireProfileId {
+    fn from(value: WireProfile) -> Self {
+        match value {
+            WireProfile::Standard => Self::Standard,
+            WireProfile::PluggedOrder => Self::PluggedOrder
+        }
+    }
+}
+
+impl From<WireProfileId> for WireProfile {
+    fn from(value: WireProfileId) -> Self {
+        match value {
+            WireProfileId::Standard => Self::Standard,
+            WireProfileId::PluggedOrder => Self::PluggedOrder
+        }
+    }
+}
+
+impl From<BackplateWires<bool>> for WirePresence {
+    fn from(value: BackplateWires<bool>) -> Self {
+        Self {
+            y1: value.y1,
+            y2: value.y2,
+            g: value.g,
+            ob: value.ob,
+            rc: value.rc,
+            w1: value.w1,
+            w2: value.w2,
+            c: value.c,
+            star: value.star,
+            rh: value.rh
+        }
+    }
+}
+
 struct SwitchState {
     heat_wire: (Wire, bool),
-    cool_wire: (Wire, bool),
-    fan_wire: (Wire, bool),
+    cool_wire: Option<(Wire, bool)>,
+    fan_wire: Option<(Wire, bool)>,
+    heat2_wire: Option<(Wire, bool)>,
+    cool2_wire: Option<(Wire, bool)>,
+    aux_wire: Option<(Wire, bool)>,
+    /// Configured wires the backplate reports as not plugged in
+    missing: Vec<Wire>,
+    /// Wires last reported plugged in, only these can be tested
+    plugged: Option<WirePresence>
 }
 
 impl SwitchState {
-    fn new(heat_wire: Wire, cool_wire: Wire, fan_wire: Wire) -> Self {
+    fn new(
+        heat_wire: Wire,
+        cool_wire: Option<Wire>,
+        fan_wire: Option<Wire>,
+        heat2_wire: Option<Wire>,
+        cool2_wire: Option<Wire>,
+        aux_wire: Option<Wire>
+    ) -> Self {
         Self {
             heat_wire: (heat_wire, false),
-            cool_wire: (cool_wire, false),
-            fan_wire: (fan_wire, false),
+            cool_wire: cool_wire.map(|w| (w, false)),
+            fan_wire: fan_wire.map(|w| (w, false)),
+            heat2_wire: heat2_wire.map(|w| (w, false)),
+            cool2_wire: cool2_wire.map(|w| (w, false)),
+            aux_wire: aux_wire.map(|w| (w, false)),
+            missing: Vec::new(),
+            plugged: None
         }
     }
 
-    fn commands(&self) -> [BackplateCmd; 3] {
+    /// Configured wires and their current on/off state
+    fn wires(&self) -> impl Iterator<Item = &(Wire, bool)> {
         [
-            BackplateCmd::SwitchWire(self.heat_wire.0, self.heat_wire.1),
-            BackplateCmd::SwitchWire(self.cool_wire.0, self.cool_wire.1),
-            BackplateCmd::SwitchWire(self.fan_wire.0, self.fan_wire.1),
+            Some(&self.heat_wire), self.cool_wire.as_ref(), self.fan_wire.as_ref(),
+            self.heat2_wire.as_ref(), self.cool2_wire.as_ref(), self.aux_wire.as_ref()
         ]
+            .into_iter()
+            .flatten()
     }
 
-    fn switch_commands(&self, action: &HvacAction) -> [BackplateCmd; 3] {
-        match action {
-            HvacAction::Heating => {
-                [
-                    BackplateCmd::SwitchWire(self.heat_wire.0, true),
-                    BackplateCmd::SwitchWire(self.cool_wire.0, false),
-                    BackplateCmd::SwitchWire(self.fan_wire.0, false),
-                ]
-            }
-            HvacAction::Cooling => {
-                [
-                    BackplateCmd::SwitchWire(self.heat_wire.0, false),
-                    BackplateCmd::SwitchWire(self.cool_wire.0, true),
-                    BackplateCmd::SwitchWire(self.fan_wire.0, false),
-                ]
-            }
-            HvacAction::Fan => {
-                [
-                    BackplateCmd::SwitchWire(self.heat_wire.0, false),
-                    BackplateCmd::SwitchWire(self.cool_wire.0, false),
-                    BackplateCmd::SwitchWire(self.fan_wire.0, true),
-                ]
-            }
-            HvacAction::Idle => {
-                [
-                    BackplateCmd::SwitchWire(self.heat_wire.0, false),
-                    BackplateCmd::SwitchWire(self.cool_wire.0, false),
-                    BackplateCmd::SwitchWire(self.fan_wire.0, false),
-                ]
-            }
-        }
+    fn wires_mut(&mut self) -> impl Iterator<Item = &mut (Wire, bool)> {
+        [
+            Some(&mut self.heat_wire), self.cool_wire.as_mut(), self.fan_wire.as_mut(),
+            self.heat2_wire.as_mut(), self.cool2_wire.as_mut(), self.aux_wire.as_mut()
+        ]
+            .into_iter()
+            .flatten()
     }
 
-    fn is_active(&self, action: &HvacAction) -> bool {
-        match action {
-            HvacAction::Heating => self.heat_wire.1,
-            HvacAction::Cooling => self.cool_wire.1,
-            HvacAction::Fan => self.fan_wire.1,
-            HvacAction::Idle => {
-                !self.cool_wire.1 && !self.heat_wire.1 && !self.fan_wire.1
-            }
-        }
+    fn commands(&self) -> Vec<BackplateCmd> {
+        self.wires()
+            .map(|(wire, on)| BackplateCmd::SwitchWire(*wire, *on))
+            .collect()
     }
 
-    fn set_wire_state(&mut self, wire: Wire, val: bool) {
-        if wire == self.cool_wire.0 {
-            self.cool_wire.1 = val;
+    /// Wanted on/off state of `wire`
+    fn wire_target(&self, wire: Wire, action: &HvacAction, stage: u8, aux: AuxHeat, fan_on: bool) -> bool {
+        let stage2 = stage >= 2;
+        let heating = *action == HvacAction::Heating;
+
+        if self.missing.contains(&wire) {
+            false
+        } else if self.aux_wire.is_some_and(|(w, _)| w == wire) {
+            heating && aux != AuxHeat::Off
         } else if wire == self.heat_wire.0 {
-            self.heat_wire.1 = val;
-        } else if wire == self.fan_wire.0 {
-            self.fan_wire.1 = val;
+            // heat pump compressor is off while aux heat runs alone
+            heating && aux != AuxHeat::Only
+        } else if self.cool_wire.is_some_and(|(w, _)| w == wire) {
+            *action == HvacAction::Cooling
+        } else if self.fan_wire.is_some_and(|(w, _)| w == wire) {
+            *action == HvacAction::Fan || fan_on
+        } else if self.heat2_wire.is_some_and(|(w, _)| w == wire) {
+            *action == HvacAction::Heating && stage2
+        } else if self.cool2_wire.is_some_and(|(w, _)| w == wire) {
+            *action == HvacAction::Cooling && stage2
         } else {
-            panic!("Unexpected wire {:?}", wire);
+            false
+        }
+    }
+
+    /// Commands for the wires not yet in the target state
+    fn switch_commands(&self, action: &HvacAction, stage: u8, aux: AuxHeat, fan_on: bool) -> Vec<BackplateCmd> {
+        self.wires()
+            .map(|(wire, on)| (*wire, *on, self.wire_target(*wire, action, stage, aux, fan_on)))
+            .filter(|(_, on, target)| on != target)
+            .map(|(wire, _, target)| BackplateCmd::SwitchWire(wire, target))
+            .collect()
+    }
+
+    fn is_active(&self, action: &HvacAction, stage: u8, aux: AuxHeat, fan_on: bool) -> bool {
+        self.wires()
+            .all(|(wire, on)| *on == self.wire_target(*wire, action, stage, aux, fan_on))
+    }
+
+    fn set_wire_state(&mut self, wire: Wire, val: bool) {
+        // wires outside the config are only switched for testing
+        if let Some(state) = self.wires_mut().find(|(w, _)| *w == wire) {
+            state.1 = val;
         }
     }
 
+    /// Returns true when the missing wires changed
+    fn set_missing(&mut self, missing: &[WireId]) -> bool {
+        let missing: Vec<Wire> = missing.iter().map(|w| Wire::from(*w)).collect();
+        let changed = missing != self.missing;
+        self.missing = missing;
+        changed
+    }
+
     fn clear(&mut self) {
-        self.heat_wire.1 = false;
-        self.cool_wire.1 = false;
-        self.fan_wire.1 = false;
+        for (_, on) in self.wires_mut() {
+            *on = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn liveness() -> Liveness {
+        let config = BackplateConfig {
+            silence_timeout: Duration::from_secs(10),
+            offline_timeout: Duration::from_secs(60),
+            ..Default::default()
+        };
+        Liveness::new(&config)
+    }
+
+    fn quiet(active: bool) -> QuietMode {
+        let mut quiet = QuietMode::new(Arc::new(AtomicBool::new(active)), Duration::from_mins(5));
+        quiet.active = active;
+        quiet
+    }
+
+    #[test]
+    fn capabilities_without_serial() {
+        let version = Some("1.0".to_string());
+        let mut query = CapabilitiesQuery {
+            tfe_version: version.clone(),
+            tfe_build_info: version.clone(),
+            bsl_version: version.clone(),
+            bsl_info: version.clone(),
+            hardware_version: version.clone(),
+            ..Default::default()
+        };
+        let config = BackplateConfig::default();
+        assert!(query.build(&config).is_none());
+
+        query.wires = Some(WirePresence::default());
+        assert_eq!(query.build(&config).map(|c| c.serial_number), Some(String::new()));
+
+        query.serial_number = Some("02AA01AC".to_string());
+        assert_eq!(query.build(&config).map(|c| c.serial_number), Some("02AA01AC".to_string()));
+    }
+
+    #[test]
+    fn wire_profile_for_model() {
+        let mut config = BackplateConfig::default();
+        let mut query = CapabilitiesQuery::default();
+        assert_eq!(query.wire_profile(&config), WireProfileId::Standard);
+
+        query.hardware_version = Some("02A".to_string());
+        assert_eq!(query.wire_profile(&config), WireProfileId::Standard);
+
+        // unknown models use the standard ids
+        query.hardware_version = Some("03B".to_string());
+        assert_eq!(query.wire_profile(&config), WireProfileId::Standard);
+
+        config.wire_profile = Some(WireProfileId::PluggedOrder);
+        assert_eq!(query.wire_profile(&config), WireProfileId::PluggedOrder);
+    }
+
+    #[test]
+    fn liveness_probes_then_goes_offline() {
+        let mut liveness = liveness();
+        let start = liveness.last_message;
+        let quiet = quiet(false);
+
+        assert_eq!(liveness.check(start + Duration::from_secs(10), &quiet), Silence::Expected);
+        assert_eq!(liveness.check(start + Duration::from_secs(11), &quiet), Silence::Probe);
+        // only probed once per silence
+        assert_eq!(liveness.check(start + Duration::from_secs(30), &quiet), Silence::Expected);
+        assert_eq!(liveness.check(start + Duration::from_secs(61), &quiet), Silence::Offline);
+
+        liveness.message_received();
+        let start = liveness.last_message;
+        assert_eq!(liveness.check(start + Duration::from_secs(11), &quiet), Silence::Probe);
+    }
+
+    #[test]
+    fn liveness_allows_quiet_poll_period() {
+        let mut liveness = liveness();
+        let start = liveness.last_message;
+        let quiet = quiet(true);
+
+        // nothing is sent between polls while quiet
+        assert_eq!(liveness.check(start + Duration::from_mins(5), &quiet), Silence::Expected);
+        assert_eq!(liveness.check(start + Duration::from_mins(5) + Duration::from_secs(11), &quiet), Silence::Probe);
+        assert_eq!(liveness.check(start + Duration::from_mins(6) + Duration::from_secs(1), &quiet), Silence::Offline);
+    }
+
+    /// Move a pending switch back in time, as if no ack came for `elapsed`
+    fn age(tracker: &mut SwitchTracker, wire: Wire, elapsed: Duration) {
+        let pending = tracker.pending.get_mut(&wire).unwrap();
+        pending.sent_at = pending.sent_at.checked_sub(elapsed).unwrap();
+    }
+
+    #[test]
+    fn switch_ack_matches_latest_command() {
+        let mut tracker = SwitchTracker::default();
+        assert!(tracker.sent(Wire::W1, true));
+        // already waiting for the same ack
+        assert!(!tracker.sent(Wire::W1, true));
+
+        assert!(tracker.sent(Wire::W1, false));
+        // ack of the first command, the second is still pending
+        tracker.acked(Wire::W1, true);
+        assert!(tracker.pending.contains_key(&Wire::W1));
+
+        tracker.acked(Wire::W1, false);
+        assert!(tracker.pending.is_empty());
+        assert!(tracker.retries().is_empty());
+    }
+
+    #[test]
+    fn switch_retried_with_backoff() {
+        let mut tracker = SwitchTracker::default();
+        tracker.sent(Wire::Y1, true);
+        assert!(tracker.retries().is_empty());
+
+        let margin = Duration::from_millis(50);
+        for attempt in 1..MAX_SWITCH_ATTEMPTS {
+            let backoff = ACK_TIMEOUT * 2u32.pow(attempt - 1);
+            age(&mut tracker, Wire::Y1, backoff - margin);
+            assert!(tracker.retries().is_empty());
+
+            age(&mut tracker, Wire::Y1, margin);
+            assert!(matches!(tracker.retries()[..], [BackplateCmd::SwitchWire(Wire::Y1, true)]));
+        }
+
+        // out of attempts, given up rather than sent again
+        age(&mut tracker, Wire::Y1, ACK_TIMEOUT * 2u32.pow(MAX_SWITCH_ATTEMPTS - 1));
+        assert!(tracker.retries().is_empty());
+        assert!(tracker.pending.is_empty());
+    }
+
+    #[test]
+    fn switch_faults() {
+        let mut tracker = SwitchTracker::default();
+        assert_eq!(tracker.fault_change(), Some(vec![]));
+
+        // given up on after the last attempt
+        tracker.sent(Wire::W1, true);
+        for attempt in 1..=MAX_SWITCH_ATTEMPTS {
+            age(&mut tracker, Wire::W1, ACK_TIMEOUT * 2u32.pow(attempt - 1));
+            tracker.retries();
+        }
+        assert_eq!(tracker.fault_change(), Some(vec![Wire::W1]));
+        assert_eq!(tracker.fault_change(), None);
+
+        // a late ack clears it
+        tracker.acked(Wire::W1, true);
+        assert_eq!(tracker.fault_change(), Some(vec![]));
+
+        // switched without a command
+        tracker.acked(Wire::W1, false);
+        assert_eq!(tracker.fault_change(), Some(vec![Wire::W1]));
+    }
+
+    #[test]
+    fn switch_power_presence() {
+        let mut tracker = SwitchTracker::default();
+        tracker.sent(Wire::W1, true);
+        tracker.sent(Wire::Y1, false);
+        tracker.acked(Wire::Y1, false);
+
+        // W1 is still waiting for an ack
+        let mut power = WirePresence::default();
+        tracker.power_presence(&power);
+        assert!(tracker.faults.is_empty());
+
+        tracker.acked(Wire::W1, true);
+        tracker.power_presence(&power);
+        assert_eq!(tracker.faults, vec![Wire::W1]);
+
+        power.w1 = true;
+        tracker.power_presence(&power);
+        assert!(tracker.faults.is_empty());
+
+        power.y1 = true;
+        tracker.power_presence(&power);
+        assert_eq!(tracker.faults, vec![Wire::Y1]);
     }
 }
@@ -16,22 +16,93 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use std::time::Duration;
+
 use anyhow::Result;
 
-use crate::{config::Config, events::{Event, EventSender}, state::HvacAction};
-use super::BackplateDevice;
+use crate::{config::{Config, WireId}, events::{Event, EventSender}, state::{AuxHeat, HvacAction}};
+use super::{
+    BackplateCapabilities, BackplateDevice, LinkStats, PowerReading, WirePresence,
+    backplate_device::DeviceBackplateThread, failsafe::Heartbeat
+};
 
-pub struct SimulatedBackplate;
+pub enum SimulatedBackplate {
+    Fixed,
+    /// Replaying a capture from a real backplate, see `BackplateConfig::replay_file`
+    Replay(DeviceBackplateThread)
+}
 
 impl BackplateDevice for SimulatedBackplate {
-    fn new<S>(_config: &Config, event_sender: S) -> Result<Self>
-        where S: EventSender + Send + 'static, Self: Sized
+    fn new<S>(config: &Config, heartbeat: Heartbeat, event_sender: S) -> Result<Self>
+        where S: EventSender + Clone + Send + 'static, Self: Sized
     {
+        if config.backplate.replay_file.is_some() {
+            return Ok(Self::Replay(DeviceBackplateThread::new(config, heartbeat, event_sender)?));
+        }
+
         event_sender.send_event(Event::BackplateConnected)?;
-        Ok(Self)
+
+        let capabilities = BackplateCapabilities {
+            hardware_version: "simulated".to_string(),
+            serial_number: "SIMULATED".to_string(),
+            wires: WirePresence {
+                w1: true, y1: true, g: true, rh: true, c: true,
+                ..WirePresence::default()
+            },
+            ..BackplateCapabilities::default()
+        };
+        event_sender.send_event(Event::BackplateCapabilities(capabilities))?;
+
+        event_sender.send_event(Event::BackplatePower(PowerReading {
+            volts_in: 24.0,
+            volts_bat: 3.9,
+            charging: false
+        }))?;
+
+        event_sender.send_event(Event::BackplateLinkStats(LinkStats::default()))?;
+
+        Ok(Self::Fixed)
+    }
+
+    fn switch_hvac(&self, action: &HvacAction, stage: u8, aux: AuxHeat, fan_on: bool) -> Result<()> {
+        match self {
+            Self::Fixed => Ok(()),
+            Self::Replay(device) => device.switch_hvac(action, stage, aux, fan_on)
+        }
+    }
+
+    fn set_quiet(&self, quiet: bool) -> Result<()> {
+        match self {
+            Self::Fixed => Ok(()),
+            Self::Replay(device) => device.set_quiet(quiet)
+        }
+    }
+
+    fn switch_off(&self, timeout: Duration) -> Result<()> {
+        match self {
+            Self::Fixed => Ok(()),
+            Self::Replay(device) => device.switch_off(timeout)
+        }
+    }
+
+    fn stop(&self, timeout: Duration) -> Result<()> {
+        match self {
+            Self::Fixed => Ok(()),
+            Self::Replay(device) => device.stop(timeout)
+        }
+    }
+
+    fn switch_wire(&self, wire: WireId, on: bool) -> Result<()> {
+        match self {
+            Self::Fixed => Ok(()),
+            Self::Replay(device) => device.switch_wire(wire, on)
+        }
     }
 
-    fn switch_hvac(&self, _action: &HvacAction) -> Result<()> {
-        Ok(())
+    fn test_wire(&self, wire: WireId, on: bool) -> Result<()> {
+        match self {
+            Self::Fixed => Ok(()),
+            Self::Replay(device) => device.test_wire(wire, on)
+        }
     }
 }
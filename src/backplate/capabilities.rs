@@ -0,0 +1,104 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{WireConfig, WireId, WireProfileId};
+
+/// Backplate hardware details, queried once on the first backplate
+/// connection and persisted so other modules can consult them without
+/// re-querying the backplate.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct BackplateCapabilities {
+    pub tfe_version: String,
+    pub tfe_build_info: String,
+    pub bsl_version: String,
+    pub bsl_info: String,
+    pub hardware_version: String,
+    pub serial_number: String,
+    /// Wire ids used for switching, picked for the hardware version
+    /// unless set in the config
+    pub wire_profile: WireProfileId,
+    /// Wires detected as plugged into the backplate terminals
+    pub wires: WirePresence
+}
+
+impl BackplateCapabilities {
+    /// Configured wires that were not detected on the backplate
+    pub fn missing_wires(&self, wiring: &WireConfig) -> Vec<WireId> {
+        self.wires.missing_wires(wiring)
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct WirePresence {
+    pub y1: bool,
+    pub y2: bool,
+    pub g: bool,
+    pub ob: bool,
+    pub rc: bool,
+    pub w1: bool,
+    pub w2: bool,
+    pub c: bool,
+    pub star: bool,
+    pub rh: bool
+}
+
+impl WirePresence {
+    pub fn has_wire(&self, wire: WireId) -> bool {
+        match wire {
+            WireId::W1 => self.w1,
+            WireId::Y1 => self.y1,
+            WireId::G => self.g,
+            WireId::OB => self.ob,
+            WireId::W2 => self.w2,
+            WireId::Y2 => self.y2,
+            WireId::Star => self.star
+        }
+    }
+
+    /// Configured wires that were not detected on the backplate
+    pub fn missing_wires(&self, wiring: &WireConfig) -> Vec<WireId> {
+        wiring.wires().into_iter()
+            .filter(|w| !self.has_wire(*w))
+            .collect()
+    }
+
+    /// Plugged wires that can be switched, for picking the wiring
+    pub fn switched_wires(&self) -> Vec<WireId> {
+        [WireId::W1, WireId::W2, WireId::Y1, WireId::Y2, WireId::G, WireId::OB, WireId::Star]
+            .into_iter()
+            .filter(|w| self.has_wire(*w))
+            .collect()
+    }
+
+    /// Terminal labels of the plugged wires, e.g. "Rh, C, W1, G"
+    pub fn label(&self) -> String {
+        [
+            (self.rh, "Rh"), (self.rc, "Rc"), (self.c, "C"),
+            (self.w1, "W1"), (self.w2, "W2"), (self.y1, "Y1"), (self.y2, "Y2"),
+            (self.g, "G"), (self.ob, "O/B"), (self.star, "*")
+        ]
+            .into_iter()
+            .filter_map(|(plugged, label)| plugged.then_some(label))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
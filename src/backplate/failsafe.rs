@@ -0,0 +1,179 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    fs::File,
+    io::Write,
+    path::Path,
+    sync::{Arc, Mutex, mpsc::{RecvTimeoutError, Sender, channel}},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant}
+};
+
+use anyhow::Result;
+use log::{debug, error, info};
+
+use crate::events::{Event, EventSender};
+
+/// How often the watchdog thread wakes to ping the event loop and feed the
+/// hardware watchdog
+const PING_PERIOD: Duration = Duration::from_secs(10);
+
+/// When the event loop last handled an event, shared with the threads that
+/// act when it stops responding
+#[derive(Clone)]
+pub struct Heartbeat(Arc<Mutex<Instant>>);
+
+impl Default for Heartbeat {
+    fn default() -> Self {
+        Self(Arc::new(Mutex::new(Instant::now())))
+    }
+}
+
+impl Heartbeat {
+    pub fn beat(&self) {
+        *self.0.lock().unwrap() = Instant::now();
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.0.lock().unwrap().elapsed()
+    }
+
+    /// True when the event loop hasn't responded within `timeout`,
+    /// never when `timeout` is zero
+    fn is_stalled(&self, timeout: Duration) -> bool {
+        !timeout.is_zero() && self.elapsed() > timeout
+    }
+}
+
+/// Tracks when the event loop stops and starts responding again, checked by
+/// the backplate thread to switch wires off while it's hung
+pub struct Failsafe {
+    heartbeat: Heartbeat,
+    timeout: Duration,
+    tripped: bool
+}
+
+impl Failsafe {
+    pub fn new(heartbeat: Heartbeat, timeout: Duration) -> Self {
+        Self { heartbeat, timeout, tripped: false }
+    }
+
+    /// `Some(true)` when the event loop has just stopped responding,
+    /// `Some(false)` once it responds again
+    pub fn update(&mut self) -> Option<bool> {
+        let stalled = self.heartbeat.is_stalled(self.timeout);
+        if stalled != self.tripped {
+            self.tripped = stalled;
+            Some(stalled)
+        } else {
+            None
+        }
+    }
+}
+
+/// Pings the event loop with [`Event::Heartbeat`] when nothing else has kept
+/// the heartbeat going, and feeds the hardware watchdog while it responds
+pub struct Watchdog {
+    stop_sender: Sender<()>,
+    handle: JoinHandle<()>
+}
+
+impl Watchdog {
+    pub fn start<S>(
+        device: Option<&Path>,
+        timeout: Duration,
+        heartbeat: Heartbeat,
+        event_sender: S
+    ) -> Result<Self>
+        where S: EventSender + Send + 'static
+    {
+        let mut device = match device {
+            Some(path) => {
+                info!("Feeding hardware watchdog {path:?}");
+                Some(File::options().write(true).open(path)?)
+            }
+            None => None
+        };
+
+        let (stop_sender, stop_receiver) = channel();
+
+        let handle = thread::spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stop_receiver.recv_timeout(PING_PERIOD) {
+                if heartbeat.elapsed() >= PING_PERIOD
+                    && let Err(e) = event_sender.send_event(Event::Heartbeat)
+                {
+                    debug!("Watchdog stopped: {e}");
+                    return;
+                }
+
+                // Starving the watchdog reboots the thermostat
+                if let Some(file) = &mut device
+                    && !heartbeat.is_stalled(timeout)
+                    && let Err(e) = file.write_all(&[0])
+                {
+                    error!("Failed to feed hardware watchdog: {e}");
+                }
+            }
+
+            // Magic close, so a clean exit doesn't reboot
+            if let Some(file) = &mut device
+                && let Err(e) = file.write_all(b"V")
+            {
+                error!("Failed to disarm hardware watchdog: {e}");
+            }
+        });
+
+        Ok(Self { stop_sender, handle })
+    }
+
+    /// Stop pinging, and disarm the hardware watchdog
+    pub fn stop(self) {
+        drop(self.stop_sender);
+        if self.handle.join().is_err() {
+            error!("Watchdog thread panicked");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trips_once_and_recovers() {
+        let heartbeat = Heartbeat::default();
+        let mut failsafe = Failsafe::new(heartbeat.clone(), Duration::from_millis(20));
+
+        assert_eq!(failsafe.update(), None);
+
+        thread::sleep(Duration::from_millis(30));
+        assert_eq!(failsafe.update(), Some(true));
+        assert_eq!(failsafe.update(), None);
+
+        heartbeat.beat();
+        assert_eq!(failsafe.update(), Some(false));
+    }
+
+    #[test]
+    fn zero_timeout_never_trips() {
+        let mut failsafe = Failsafe::new(Heartbeat::default(), Duration::ZERO);
+        thread::sleep(Duration::from_millis(5));
+        assert_eq!(failsafe.update(), None);
+    }
+}
@@ -0,0 +1,57 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+/// Voltage change required before a new power reading is reported
+const VOLTS_THRESHOLD: f32 = 0.05;
+
+/// Backplate power supply readings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PowerReading {
+    /// Voltage from the HVAC system
+    pub volts_in: f32,
+    pub volts_bat: f32,
+    pub charging: bool
+}
+
+impl PowerReading {
+    /// True when charging changed, or either voltage moved past the threshold
+    pub fn differs(&self, other: &Self) -> bool {
+        self.charging != other.charging
+            || (self.volts_in - other.volts_in).abs() >= VOLTS_THRESHOLD
+            || (self.volts_bat - other.volts_bat).abs() >= VOLTS_THRESHOLD
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn differs_past_threshold() {
+        let reading = PowerReading { volts_in: 24.0, volts_bat: 3.9, charging: false };
+
+        let noise = PowerReading { volts_bat: 3.92, ..reading };
+        assert!(!reading.differs(&noise));
+
+        let dropped = PowerReading { volts_bat: 3.8, ..reading };
+        assert!(reading.differs(&dropped));
+
+        let charging = PowerReading { charging: true, ..reading };
+        assert!(reading.differs(&charging));
+    }
+}
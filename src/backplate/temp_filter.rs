@@ -0,0 +1,75 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::{config::BackplateConfig, temperature::Temperature};
+
+/// Calibrates and smooths raw backplate temperature readings
+pub struct TempFilter {
+    offset: f32,
+    smoothing: f32,
+    value: Option<f32>
+}
+
+impl TempFilter {
+    pub fn new(config: &BackplateConfig) -> Self {
+        Self {
+            offset: config.temp_offset,
+            smoothing: config.temp_smoothing.clamp(0.01, 1.0),
+            value: None
+        }
+    }
+
+    /// Add a raw reading and return the filtered temperature
+    pub fn apply(&mut self, raw: f32) -> Temperature {
+        let raw = raw + self.offset;
+
+        // exponential moving average, seeded with the first reading
+        let value = match self.value {
+            Some(value) => value + self.smoothing * (raw - value),
+            None => raw
+        };
+        self.value = Some(value);
+
+        Temperature::from_celsius(value)
+    }
+
+    /// Calibrate a buffered history reading, without adding it to the average
+    pub fn calibrate(&self, raw: f32) -> Temperature {
+        Temperature::from_celsius(raw + self.offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offset_and_smoothing() {
+        let config = BackplateConfig {
+            temp_offset: -1.0,
+            temp_smoothing: 0.5,
+            ..BackplateConfig::default()
+        };
+
+        let mut filter = TempFilter::new(&config);
+
+        assert_eq!(filter.apply(21.0), Temperature::from_celsius(20.0));
+        assert_eq!(filter.apply(23.0), Temperature::from_celsius(21.0));
+        assert_eq!(filter.apply(23.0), Temperature::from_celsius(21.5));
+    }
+}
@@ -0,0 +1,155 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{fs, path::{Path, PathBuf}};
+
+use anyhow::Result;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::Config,
+    env,
+    events::{Event, EventSender},
+    state::ClimateCommand,
+    storage
+};
+
+/// Config file (including schedules) and persisted state bundled together,
+/// to backup and restore when swapping hardware.
+#[derive(Deserialize, Serialize)]
+struct BackupBundle {
+    /// Contents of the config file, `None` when running with default config
+    config: Option<String>,
+
+    /// Contents of the state file, `None` when state hasn't been saved yet
+    state: Option<String>
+}
+
+#[derive(Clone)]
+pub struct Backup {
+    backup_file: PathBuf,
+    config_file: Option<PathBuf>,
+    state_file: PathBuf
+}
+
+impl Backup {
+    pub fn new(config: &Config, config_file: Option<PathBuf>) -> Self {
+        let backup_file = config.backup_file.clone().unwrap_or_else(|| {
+            default_backup_file(config_file.as_ref(), &config.storage_dir)
+        });
+
+        Self {
+            backup_file,
+            config_file,
+            state_file: config.storage_dir.join(env::state_file_name())
+        }
+    }
+
+    /// Write config and state bundle to the backup file
+    pub fn export(&self) -> Result<()> {
+        let bundle = BackupBundle {
+            config: read_optional(self.config_file.as_ref())?,
+            state: read_optional(Some(&self.state_file))?
+        };
+
+        fs::write(&self.backup_file, toml::to_string(&bundle)?)?;
+
+        info!("Exported backup to {:?}", self.backup_file);
+
+        Ok(())
+    }
+
+    /// Restore config and state from the backup file, and apply both
+    pub fn import(&self, event_sender: &impl EventSender) -> Result<()> {
+        let bundle: BackupBundle = toml::from_str(&fs::read_to_string(&self.backup_file)?)?;
+
+        // parse everything before writing anything,
+        // avoid restoring half of an invalid backup
+        let state = bundle.state.as_deref()
+            .map(storage::parse_state)
+            .transpose()?;
+        if let Some(config_src) = &bundle.config {
+            toml::from_str::<Config>(config_src)?;
+        }
+
+        match (&bundle.config, &self.config_file) {
+            (Some(config_src), Some(config_file)) => {
+                fs::write(config_file, config_src)?;
+                info!("Restored config to {config_file:?}");
+                event_sender.send_event(Event::ReloadConfig)?;
+            }
+            (Some(_), None) => {
+                warn!("Not restoring config; retherm was launched without --config");
+            }
+            _ => { }
+        }
+
+        if let (Some(state_src), Some(state)) = (&bundle.state, state) {
+            fs::write(&self.state_file, state_src)?;
+            event_sender.send_event(Event::ClimateCommand(ClimateCommand {
+                mode: Some(state.mode),
+                target_temp: Some(state.target_temp),
+                target_temp_low: Some(state.target_temp_low),
+                target_temp_high: Some(state.target_temp_high),
+                ..ClimateCommand::default()
+            }))?;
+            info!("Restored state {:?}", state);
+        }
+
+        Ok(())
+    }
+}
+
+fn read_optional(file_path: Option<&PathBuf>) -> Result<Option<String>> {
+    match file_path {
+        Some(file_path) if file_path.is_file() => {
+            Ok(Some(fs::read_to_string(file_path)?))
+        }
+        _ => Ok(None)
+    }
+}
+
+/// Backup file beside the config file, or in the storage dir without one
+fn default_backup_file(config_file: Option<&PathBuf>, storage_dir: &Path) -> PathBuf {
+    match config_file {
+        Some(config_file) => config_file.with_extension("backup.toml"),
+        None => storage_dir.join("retherm.backup.toml")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backup_file_beside_config() {
+        let storage_dir = Path::new("/media/data");
+
+        let config_file = PathBuf::from("/etc/retherm/retherm.toml");
+        assert_eq!(
+            default_backup_file(Some(&config_file), storage_dir),
+            Path::new("/etc/retherm/retherm.backup.toml")
+        );
+
+        assert_eq!(
+            default_backup_file(None, storage_dir),
+            Path::new("/media/data/retherm.backup.toml")
+        );
+    }
+}
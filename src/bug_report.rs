@@ -0,0 +1,147 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    collections::VecDeque,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime}
+};
+
+use anyhow::Result;
+use chrono::Local;
+
+use crate::{env, events::Event};
+
+/// Number of recent events included in a bug report
+const TRACE_LEN: usize = 200;
+
+/// Minimum time between bug reports, so a crash loop doesn't fill storage
+const REPORT_INTERVAL: Duration = Duration::from_hours(1);
+
+const REPORT_PREFIX: &str = "crash-";
+
+/// Ring buffer of recently dispatched events
+#[derive(Clone)]
+pub struct EventTrace {
+    events: Arc<Mutex<VecDeque<String>>>
+}
+
+impl EventTrace {
+    pub fn new() -> Self {
+        Self {
+            events: Arc::new(Mutex::new(VecDeque::with_capacity(TRACE_LEN)))
+        }
+    }
+
+    pub fn record(&self, event: &Event) {
+        let mut events = self.events.lock().unwrap();
+        if events.len() == TRACE_LEN {
+            events.pop_front();
+        }
+
+        let time = Local::now().format("%Y-%m-%d %H:%M:%S%.3f");
+        events.push_back(format!("{time} {event:?}"));
+    }
+
+    fn dump(&self) -> String {
+        // The panicking thread may have been holding the lock
+        let events = self.events.lock()
+            .unwrap_or_else(|e| e.into_inner());
+
+        events.iter()
+            .map(|e| format!("{e}\n"))
+            .collect()
+    }
+}
+
+/// Writes a bug report directory containing the panic details, recent
+/// events, and diagnostics when the app panics
+pub struct BugReporter {
+    report_dir: PathBuf,
+    trace: EventTrace,
+    start_time: Instant
+}
+
+impl BugReporter {
+    pub fn new(report_dir: PathBuf, trace: EventTrace) -> Self {
+        Self {
+            report_dir,
+            trace,
+            start_time: Instant::now()
+        }
+    }
+
+    /// Write report and return its path, or `None` when a report
+    /// was written within the rate limit interval
+    pub fn write_report(&self, panic_report: &str) -> Result<Option<PathBuf>> {
+        if let Some(elapsed) = last_report_elapsed(&self.report_dir)?
+            && elapsed < REPORT_INTERVAL
+        {
+            return Ok(None);
+        }
+
+        let timestamp = Local::now().format("%Y%m%d-%H%M%S");
+        let report_dir = self.report_dir.join(format!("{REPORT_PREFIX}{timestamp}"));
+        fs::create_dir_all(&report_dir)?;
+
+        fs::write(report_dir.join("panic.txt"), panic_report)?;
+        fs::write(report_dir.join("events.txt"), self.trace.dump())?;
+        fs::write(report_dir.join("diagnostics.txt"), self.diagnostics())?;
+
+        Ok(Some(report_dir))
+    }
+
+    fn diagnostics(&self) -> String {
+        let hostname = env::get_hostname()
+            .unwrap_or_else(|e| format!("unknown ({e})"));
+
+        format!(
+            "name: {}\nversion: {}\nhostname: {}\nuptime: {:?}\n",
+            env::get_pkg_name(),
+            env::get_pkg_ver(),
+            hostname,
+            self.start_time.elapsed()
+        )
+    }
+}
+
+/// Time since the most recent report in `report_dir` was written
+fn last_report_elapsed(report_dir: &Path) -> Result<Option<Duration>> {
+    if !report_dir.is_dir() {
+        return Ok(None);
+    }
+
+    let mut last_report: Option<SystemTime> = None;
+
+    for entry in fs::read_dir(report_dir)? {
+        let entry = entry?;
+        if !entry.file_name().to_string_lossy().starts_with(REPORT_PREFIX) {
+            continue;
+        }
+
+        let modified = entry.metadata()?.modified()?;
+        if last_report.is_none_or(|t| modified > t) {
+            last_report = Some(modified);
+        }
+    }
+
+    // Clock going backwards counts as a recent report
+    Ok(last_report.map(|t| t.elapsed().unwrap_or_default()))
+}
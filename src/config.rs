@@ -16,17 +16,19 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{fs, path::{Path, PathBuf}, time::Duration};
+use std::{collections::HashMap, fs, path::{Path, PathBuf}, time::Duration};
 
-use anyhow::Result;
-use serde::Deserialize;
+use anyhow::{Result, bail};
+use chrono::NaiveTime;
+use log::{LevelFilter, warn};
+use serde::{Deserialize, Serialize};
 
 mod config_de;
 mod schedule_config;
 
 pub use schedule_config::*;
 
-use crate::{env, state::HvacMode};
+use crate::{env, state::{HvacAction, HvacMode}, temperature::{Temperature, TempUnit}, theme::DEFAULT_THEME};
 
 /// Config file
 ///
@@ -46,6 +48,11 @@ pub struct Config {
     /// For example, with a target heat temp of 20, and deadband set to 0.4,
     /// the hvac system will turn heat on when temp drops to 19.6.
     ///
+    /// The deadband can also be changed from the settings screen or the
+    /// "Temp Deadband" entity in Home Assistant. A changed deadband is saved
+    /// and used in place of this option until this option is changed, or
+    /// the deadband is set back to this option's value.
+    ///
     /// Defaults to 0.6
     pub temp_deadband: f32,
 
@@ -57,6 +64,13 @@ pub struct Config {
     /// Defaults to 0.4
     pub temp_overrun: f32,
 
+    /// Show "Holding" on screen and report the reason for being idle to
+    /// Home Assistant, when the current temp is inside the deadband but
+    /// hasn't reached the target temp.
+    ///
+    /// Defaults to true
+    pub show_holding: bool,
+
     /// Minimum off time for cooling to allow AC refrigerant pressures to equalize.
     ///
     /// Defaults to "5m"
@@ -74,10 +88,80 @@ pub struct Config {
     /// Defaults to "/media/data"
     pub storage_dir: PathBuf,
 
+    /// File used by the Home Assistant `export_config` and `import_config`
+    /// services to backup and restore config, schedules, and app state.
+    ///
+    /// Defaults to the config file with a ".backup.toml" extension, e.g.
+    /// "retherm.backup.toml" beside "retherm.toml", or to
+    /// "retherm.backup.toml" in `storage_dir` when there is no config file
+    pub backup_file: Option<PathBuf>,
+
+    /// Directory where a bug report is written when the app crashes,
+    /// containing the panic details and recent events. At most one report
+    /// is written per hour.
+    ///
+    /// Defaults to "/media/data/bug_reports"
+    pub bug_report_dir: PathBuf,
+
+    /// Unit used to display temperatures on screen, "Celsius" or "Fahrenheit".
+    ///
+    /// Config values and Home Assistant always use Celsius; Home Assistant
+    /// converts to the unit configured in its own settings.
+    ///
+    /// Defaults to "Celsius"
+    pub temp_unit: TempUnit,
+
+    /// Run without the screen, "Auto", "Always", or "Never".
+    ///
+    /// Backplate, schedules, and Home Assistant keep running when headless.
+    /// With "Auto", the app runs headless when the display can't be opened.
+    ///
+    /// Defaults to "Auto"
+    pub headless: HeadlessMode,
+
+    /// Draw frame rate, draw time, events handled per frame, and backplate
+    /// message rate over the top of the screen.
+    ///
+    /// Defaults to false
+    pub dev_overlay: bool,
+
+    /// Directory of theme files, each `{name}.toml` is a theme that can be
+    /// selected on the settings screen or from Home Assistant.
+    ///
+    /// Defaults to "/etc/retherm/themes"
+    pub theme_dir: PathBuf,
+
+    /// Name of the theme file in `theme_dir` to use, without the extension.
+    /// "Default" is the built-in theme, or the `--theme` file when given.
+    /// A theme chosen from settings or Home Assistant is kept across restarts,
+    /// until this is changed.
+    ///
+    /// Defaults to "Default"
+    pub theme: String,
+
     pub away_mode: AwayConfig,
+    pub safety: SafetyConfig,
+    pub alerts: AlertsConfig,
+    pub occupancy: OccupancyConfig,
+    pub offline_mode: OfflineConfig,
+    pub boost: BoostConfig,
+    pub hold: HoldConfig,
+    pub recovery: RecoveryConfig,
+    pub learning: LearningConfig,
+    pub comfort: ComfortConfig,
+    pub window_open: WindowOpenConfig,
+    pub temp_source: TempSourceConfig,
+    pub outdoor: OutdoorConfig,
+    pub staging: StagingConfig,
+    pub control: ControlConfig,
+    pub zone: ZoneConfig,
     pub backplate: BackplateConfig,
     pub home_assistant: HomeAssistantConfig,
     pub backlight: BacklightConfig,
+    pub sound: SoundConfig,
+    pub quiet_hours: QuietHoursConfig,
+    pub input: InputConfig,
+    pub log: LogConfig,
     pub schedule_heat: Vec<ScheduleConfig>,
     pub schedule_cool: Vec<ScheduleConfig>
 }
@@ -85,13 +169,19 @@ pub struct Config {
 impl Config {
     pub fn load<P: AsRef<Path>>(file_path: P) -> Result<Self> {
         let toml_src = fs::read_to_string(file_path)?;
-        let config = toml::from_str(&toml_src)?;
+        Self::from_toml(&toml_src)
+    }
+
+    pub fn from_toml(toml_src: &str) -> Result<Self> {
+        let mut table: toml::Table = toml::from_str(toml_src)?;
+        migrate_away_timeout(&mut table)?;
+        let config = toml::Value::Table(table).try_into()?;
         Ok(config)
     }
 
     pub fn schedule_for_mode(&self, mode: &HvacMode) -> Option<&[ScheduleConfig]> {
         match mode {
-            HvacMode::Heat => {
+            HvacMode::Heat | HvacMode::EmergencyHeat => {
                 if self.schedule_heat.len() > 0 {
                     Some(&self.schedule_heat)
                 } else {
@@ -110,20 +200,109 @@ impl Config {
     }
 }
 
+/// The away timeout moved from `[away_mode]` to `[occupancy]`, move an old
+/// `timeout` along rather than dropping it as an unknown key
+fn migrate_away_timeout(table: &mut toml::Table) -> Result<()> {
+    let Some(toml::Value::Table(away_mode)) = table.get_mut("away_mode") else {
+        return Ok(());
+    };
+    let Some(timeout) = away_mode.remove("timeout") else {
+        return Ok(());
+    };
+
+    let occupancy = table.entry("occupancy")
+        .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+    let Some(occupancy) = occupancy.as_table_mut() else {
+        return Ok(());
+    };
+
+    if occupancy.contains_key("timeout") {
+        bail!("[away_mode] timeout has moved to [occupancy] timeout, remove one of them");
+    }
+
+    warn!("[away_mode] timeout has moved to [occupancy] timeout, please update the config");
+    occupancy.insert("timeout".to_string(), timeout);
+
+    Ok(())
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
             away_mode: AwayConfig::default(),
+            safety: SafetyConfig::default(),
+            alerts: AlertsConfig::default(),
+            occupancy: OccupancyConfig::default(),
+            offline_mode: OfflineConfig::default(),
+            boost: BoostConfig::default(),
+            hold: HoldConfig::default(),
+            recovery: RecoveryConfig::default(),
+            learning: LearningConfig::default(),
+            comfort: ComfortConfig::default(),
+            window_open: WindowOpenConfig::default(),
+            temp_source: TempSourceConfig::default(),
+            outdoor: OutdoorConfig::default(),
+            staging: StagingConfig::default(),
+            control: ControlConfig::default(),
+            zone: ZoneConfig::default(),
             backplate: BackplateConfig::default(),
             home_assistant: HomeAssistantConfig::default(),
             backlight: BacklightConfig::default(),
+            sound: SoundConfig::default(),
+            quiet_hours: QuietHoursConfig::default(),
+            input: InputConfig::default(),
+            log: LogConfig::default(),
             schedule_heat: Vec::new(),
             schedule_cool: Vec::new(),
             temp_deadband: 0.6,
             temp_overrun: 0.4,
+            show_holding: true,
             min_off_time: Duration::from_mins(5),
             default_fan_timeout: Duration::from_mins(15),
             storage_dir: PathBuf::from("/media/data"),
+            backup_file: None,
+            bug_report_dir: PathBuf::from("/media/data/bug_reports"),
+            temp_unit: TempUnit::Celsius,
+            headless: HeadlessMode::Auto,
+            dev_overlay: false,
+            theme_dir: PathBuf::from("/etc/retherm/themes"),
+            theme: String::from(DEFAULT_THEME),
+        }
+    }
+}
+
+/// Zones
+///
+/// Other ReTherm thermostats sharing the same heating and cooling equipment.
+/// Each peer's ESP Home API is subscribed to for its room temp and hvac
+/// action. The average room temp of all zones is reported to Home
+/// Assistant, and only one zone calls for heating or cooling at a time; a
+/// zone waits for the running zone to go idle. When zones start together,
+/// the zone with the lowest node name keeps running.
+///
+/// Peers can't have an `encryption_key` set. Changes to `peers` take
+/// effect after a restart.
+///
+/// ```toml
+/// [zone]
+/// peers = ["nest-upstairs.local:6053"]
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ZoneConfig {
+    /// ESP Home API address of each peer thermostat, default none
+    pub peers: Vec<String>,
+
+    /// Wait for the running zone to go idle before heating or cooling,
+    /// default true
+    pub exclusive: bool
+}
+
+impl Default for ZoneConfig {
+    fn default() -> Self {
+        Self {
+            peers: Vec::new(),
+            exclusive: true
         }
     }
 }
@@ -146,7 +325,8 @@ pub struct HomeAssistantConfig {
     pub listen_addr: String,
 
     /// Encryption key as 32 byte base64 string. When not provided, the
-    /// connection uses plaintext messages.
+    /// connection uses plaintext messages. A changed key is used for new
+    /// connections after a config reload.
     /// See [ESP Home Native API](https://esphome.io/components/api/)
     /// for a tool that generates a random key.
     pub encryption_key: Option<String>,
@@ -236,29 +416,262 @@ impl Default for HomeAssistantConfig {
     }
 }
 
+/// Sound
+///
+/// Tones played on the piezo speaker. The speaker has no volume control,
+/// use `muted` or the Home Assistant mute switch to silence it.
+///
+/// ```toml
+/// [sound]
+/// dial_click = { freq = 2000, duration = "3ms" }
+/// button_click = { freq = 1500, duration = "5ms" }
+/// confirm_beep = [
+///     { freq = 2600, duration = "40ms" },
+///     { freq = 0, duration = "30ms" },
+///     { freq = 3200, duration = "60ms" }
+/// ]
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct SoundConfig {
+    /// Start with sounds muted, defaults to false
+    pub muted: bool,
+
+    /// Tone played as the dial turns, defaults to
+    /// `{ freq = 2000, duration = "3ms" }`
+    pub dial_click: ToneConfig,
+
+    /// Dial click freq when the dial turns fast, defaults to 2600
+    pub dial_fast_freq: i32,
+
+    /// Tone played when the button is pressed, defaults to
+    /// `{ freq = 1500, duration = "5ms" }`
+    pub button_click: ToneConfig,
+
+    /// Tones played when a mode or fan selection is confirmed,
+    /// defaults to none
+    pub confirm_beep: Vec<ToneConfig>
+}
+
+impl Default for SoundConfig {
+    fn default() -> Self {
+        Self {
+            muted: false,
+            dial_click: ToneConfig {
+                freq: 2000,
+                duration: Duration::from_millis(3)
+            },
+            dial_fast_freq: 2600,
+            button_click: ToneConfig {
+                freq: 1500,
+                duration: Duration::from_millis(5)
+            },
+            confirm_beep: Vec::new()
+        }
+    }
+}
+
+/// Quiet hours
+///
+/// Daily window where click sounds are muted and the backlight is limited
+/// to `backlight.quiet_brightness`. The window can pass midnight.
+///
+/// ```toml
+/// [quiet_hours]
+/// enabled = true
+/// start = "22:00"
+/// end = "07:00"
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct QuietHoursConfig {
+    /// Mute sounds and dim the backlight between `start` and `end`,
+    /// default false
+    pub enabled: bool,
+
+    /// Time quiet hours start, default "22:00"
+    #[serde(deserialize_with = "config_de::time_of_day")]
+    pub start: NaiveTime,
+
+    /// Time quiet hours end, default "07:00"
+    #[serde(deserialize_with = "config_de::time_of_day")]
+    pub end: NaiveTime
+}
+
+impl Default for QuietHoursConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            start: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+            end: NaiveTime::from_hms_opt(7, 0, 0).unwrap()
+        }
+    }
+}
+
+/// Input
+///
+/// Dial and button input devices. Each device is found by scanning
+/// "/dev/input" for a device with a relative axis (dial) or the power key
+/// (button), unless its path is set. A device that can't be opened, or
+/// disappears, is opened again every `reopen_period`.
+///
+/// ```toml
+/// [input]
+/// dial_device = "/dev/input/event1"
+/// button_device = "/dev/input/event2"
+/// reopen_period = "5s"
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct InputConfig {
+    /// Dial evdev device, defaults to the first device found in
+    /// "/dev/input" with a relative axis
+    pub dial_device: Option<PathBuf>,
+
+    /// Button evdev device, defaults to the first device found in
+    /// "/dev/input" with the power key
+    pub button_device: Option<PathBuf>,
+
+    /// Time between attempts to open a missing device, defaults to "5s"
+    #[serde(deserialize_with = "config_de::duration")]
+    pub reopen_period: Duration
+}
+
+impl Default for InputConfig {
+    fn default() -> Self {
+        Self {
+            dial_device: None,
+            button_device: None,
+            reopen_period: Duration::from_secs(5)
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ToneConfig {
+    /// Tone frequency in Hz, 0 for a pause
+    pub freq: i32,
+
+    #[serde(deserialize_with = "config_de::duration")]
+    pub duration: Duration
+}
+
 /// Backlight
 ///
 /// ```toml
 /// [backlight]
 /// brightness = 108
 /// timeout = "15s"
+/// idle_brightness = 10
+/// auto_brightness = true
+/// light_curve = [[0, 20], [50, 60], [200, 108]]
+/// night_light = 10
+/// day_light = 30
+/// device = "/sys/class/backlight/3-0036"
 /// ```
 #[derive(Deserialize, Debug, Clone)]
 #[serde(default)]
 pub struct BacklightConfig {
-    /// Screen brightness, defaults to 108 (max 120)
+    /// Screen brightness, defaults to 108 (max 120). Can be changed from
+    /// Home Assistant until the config is reloaded.
     pub brightness: u32,
 
+    /// Backlight sysfs device directory, defaults to the first device
+    /// found in "/sys/class/backlight"
+    pub device: Option<PathBuf>,
+
     /// Timeout before screen turns off, defaults to "15s"
     #[serde(deserialize_with = "config_de::duration")]
-    pub timeout: Duration
+    pub timeout: Duration,
+
+    /// Brightness of the clock shown after the timeout, instead of turning
+    /// the screen off, defaults to 0 (off)
+    pub idle_brightness: u32,
+
+    /// Follow the ambient light sensor using `light_curve`, instead of the
+    /// fixed `brightness`, defaults to false. Brightness set from Home
+    /// Assistant takes precedence, setting it to zero goes back to auto.
+    pub auto_brightness: bool,
+
+    /// Ambient light to brightness breakpoints as `[light, brightness]`
+    /// pairs, brightness between breakpoints is interpolated,
+    /// defaults to `[[0, 20], [50, 60], [200, 108]]`
+    pub light_curve: Vec<(u16, u32)>,
+
+    /// Switch to the night theme when ambient light drops to this level,
+    /// for themes with a `night` section, defaults to 10
+    pub night_light: u16,
+
+    /// Switch back to the day theme when ambient light rises to this
+    /// level, defaults to 30
+    pub day_light: u16,
+
+    /// Highest brightness during `quiet_hours`, brightness set from Home
+    /// Assistant isn't limited, defaults to 20
+    pub quiet_brightness: u32
+}
+
+impl BacklightConfig {
+    /// Whether the night theme should be used at the ambient `light` level,
+    /// the gap between `night_light` and `day_light` keeps the current theme
+    pub fn is_night(&self, light: u16, night: bool) -> bool {
+        if night {
+            light < self.day_light
+        } else {
+            light <= self.night_light
+        }
+    }
 }
 
 impl Default for BacklightConfig {
     fn default() -> Self {
         Self {
             brightness: 108,
-            timeout: Duration::from_secs(15)
+            device: None,
+            timeout: Duration::from_secs(15),
+            idle_brightness: 0,
+            auto_brightness: false,
+            light_curve: vec![(0, 20), (50, 60), (200, 108)],
+            night_light: 10,
+            day_light: 30,
+            quiet_brightness: 20
+        }
+    }
+}
+
+/// Logging
+///
+/// Levels are "Off", "Error", "Warn", "Info", "Debug", or "Trace". Recent
+/// log lines are kept in memory, and shown on the "Logs" screen.
+///
+/// Syslog output is also limited by the `--syslog` level, and console output
+/// by `RUST_LOG`.
+///
+/// ```toml
+/// [log]
+/// level = "Info"
+///
+/// [log.modules]
+/// "retherm::backplate" = "Debug"
+/// "esphome_api" = "Warn"
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct LogConfig {
+    /// Level for modules not listed in `modules`, default "Info"
+    #[serde(deserialize_with = "config_de::level_filter")]
+    pub level: LevelFilter,
+
+    /// Level per module path, the longest matching path is used, default empty
+    #[serde(deserialize_with = "config_de::level_filters")]
+    pub modules: HashMap<String, LevelFilter>
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level: LevelFilter::Info,
+            modules: HashMap::new()
         }
     }
 }
@@ -266,44 +679,674 @@ impl Default for BacklightConfig {
 
 /// Away Mode
 ///
+/// Target temps used while away, see [`OccupancyConfig`] for when away
+/// mode is entered. The away `timeout` formerly set here is now
+/// `[occupancy] timeout`, an old `timeout` is still read from here.
+///
 /// ```toml
 /// [away_mode]
 /// temp_heat = 16.0
 /// temp_cool = 20.0
-/// timeout = "0s"
 /// ```
 #[derive(Deserialize, Debug, Clone)]
 #[serde(default)]
 pub struct AwayConfig {
     /// Away temp for heating mode, default 16.0
-    pub temp_heat: f32,
+    pub temp_heat: Temperature,
 
     /// Away temp for cooling mode, default 22.0
-    pub temp_cool: f32,
+    pub temp_cool: Temperature
+}
+
+impl Default for AwayConfig {
+    fn default() -> Self {
+        Self {
+            temp_heat: Temperature::from_celsius(16.0),
+            temp_cool: Temperature::from_celsius(22.0)
+        }
+    }
+}
 
-    /// Duration of no proximity movement before going into away mode,
+/// Safety Limits
+///
+/// Room temps that turn on heating or cooling regardless of the mode, even
+/// when off or away, to keep pipes from freezing or the home from
+/// overheating. Cooling only runs when the cool wire is detected on the
+/// backplate.
+///
+/// ```toml
+/// [safety]
+/// min_temp = 7.0
+/// max_temp = 35.0
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct SafetyConfig {
+    /// Heat when the room drops to this temp, default 7.0
+    pub min_temp: Option<Temperature>,
+
+    /// Cool when the room rises to this temp, default none
+    pub max_temp: Option<Temperature>,
+
+    /// Degrees past the limit before heating or cooling stops, default 1.0
+    pub hysteresis: f32
+}
+
+impl Default for SafetyConfig {
+    fn default() -> Self {
+        Self {
+            min_temp: Some(Temperature::from_celsius(7.0)),
+            max_temp: None,
+            hysteresis: 1.0
+        }
+    }
+}
+
+/// Alerts
+///
+/// Conditions that need attention, raised as Home Assistant binary sensors
+/// and a warning on the display until the condition clears or the warning
+/// is dismissed with the button. Remove a threshold to disable its alert.
+///
+/// ```toml
+/// [alerts]
+/// frost_temp = 5.0
+/// overheat_temp = 35.0
+/// low_battery = 3.6
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct AlertsConfig {
+    /// Alert when the room drops below this temp, default 5.0
+    pub frost_temp: Option<Temperature>,
+
+    /// Alert when the room rises above this temp, default 35.0
+    pub overheat_temp: Option<Temperature>,
+
+    /// Alert when the backplate battery drops below this voltage, default 3.6
+    pub low_battery: Option<f32>
+}
+
+impl Default for AlertsConfig {
+    fn default() -> Self {
+        Self {
+            frost_temp: Some(Temperature::from_celsius(5.0)),
+            overheat_temp: Some(Temperature::from_celsius(35.0)),
+            low_battery: Some(3.6)
+        }
+    }
+}
+
+/// Occupancy
+///
+/// Away mode is entered when there has been no activity for the timeout
+/// duration, and exited on the next activity.
+///
+/// Away can instead follow a Home Assistant presence entity, such as
+/// `zone.home` which reports the number of people home, or a person,
+/// device tracker or binary sensor. Home Assistant subscribes to the
+/// entity when it connects, so changing `ha_entity` requires a restart.
+///
+/// ```toml
+/// [occupancy]
+/// timeout = "30m"
+/// proximity = true
+/// input = true
+/// motion_hold = "1m"
+/// source = "Both"
+/// ha_entity = "zone.home"
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct OccupancyConfig {
+    /// Duration of no activity before going into away mode,
     /// or set to zero to disable away mode. Default "30m".
     #[serde(deserialize_with = "config_de::duration")]
+    pub timeout: Duration,
+
+    /// Proximity sensor movement counts as activity, default true
+    pub proximity: bool,
+
+    /// Dial and button input counts as activity, default true
+    pub input: bool,
+
+    /// How long the motion sensor reported to Home Assistant stays on
+    /// after the last proximity movement, must be longer than zero,
+    /// default "1m"
+    #[serde(deserialize_with = "config_de::positive_duration")]
+    pub motion_hold: Duration,
+
+    /// "Activity", "HomeAssistant", or "Both", default "Activity"
+    pub source: OccupancySource,
+
+    /// Home Assistant presence entity id, default none
+    pub ha_entity: Option<String>
+}
+
+impl Default for OccupancyConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_mins(30),
+            proximity: true,
+            input: true,
+            motion_hold: Duration::from_mins(1),
+            source: OccupancySource::Activity,
+            ha_entity: None
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum OccupancySource {
+    /// No activity on the thermostat for `timeout`
+    Activity,
+    /// The `ha_entity` presence, falling back to activity until it reports
+    HomeAssistant,
+    /// Away only when both activity and `ha_entity` say away
+    Both
+}
+
+/// Offline mode
+///
+/// Fall back to a conservative local setpoint when Home Assistant has been
+/// disconnected for an extended period, rather than holding the last
+/// setpoint it commanded indefinitely.
+///
+/// ```toml
+/// [offline_mode]
+/// temp_heat = 18.0
+/// temp_cool = 24.0
+/// timeout = "2h"
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct OfflineConfig {
+    /// Offline temp for heating mode, default 18.0
+    pub temp_heat: Temperature,
+
+    /// Offline temp for cooling mode, default 24.0
+    pub temp_cool: Temperature,
+
+    /// Duration without a Home Assistant connection before going into
+    /// offline mode, or set to zero to disable offline mode. Default "0s".
+    #[serde(deserialize_with = "config_de::duration")]
     pub timeout: Duration
 }
 
-impl Default for AwayConfig {
+impl Default for OfflineConfig {
+    fn default() -> Self {
+        Self {
+            temp_heat: Temperature::from_celsius(18.0),
+            temp_cool: Temperature::from_celsius(24.0),
+            timeout: Duration::ZERO
+        }
+    }
+}
+
+/// Boost
+///
+/// Temporarily raise the heating target (or lower the cooling target) with
+/// a long press of the dial, or the boost button in Home Assistant.
+///
+/// ```toml
+/// [boost]
+/// temp_delta = 2.0
+/// duration = "1h"
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct BoostConfig {
+    /// Degrees added to the target temp while boosting, default 2.0
+    pub temp_delta: f32,
+
+    /// How long boost lasts before reverting to the previous target temp,
+    /// default "1h"
+    #[serde(deserialize_with = "config_de::duration")]
+    pub duration: Duration
+}
+
+impl Default for BoostConfig {
     fn default() -> Self {
         Self {
-            temp_heat: 16.0,
-            temp_cool: 22.0,
-            timeout: Duration::from_mins(30)
+            temp_delta: 2.0,
+            duration: Duration::from_hours(1)
         }
     }
 }
 
+/// Schedule hold
+///
+/// Changing the target temp from the dial or Home Assistant holds it against
+/// the schedule. Selecting the "None" preset in Home Assistant releases the
+/// hold and goes back to the scheduled temp.
+///
+/// ```toml
+/// [hold]
+/// mode = "Duration"
+/// duration = "2h"
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct HoldConfig {
+    /// How long the hold lasts, "NextSetPoint", "Duration", or "Permanent",
+    /// default "NextSetPoint"
+    pub mode: HoldMode,
+
+    /// How long the hold lasts in "Duration" mode, default "2h"
+    #[serde(deserialize_with = "config_de::duration")]
+    pub duration: Duration
+}
+
+impl Default for HoldConfig {
+    fn default() -> Self {
+        Self {
+            mode: HoldMode::NextSetPoint,
+            duration: Duration::from_hours(2)
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum HoldMode {
+    /// Until the schedule reaches the next set point
+    NextSetPoint,
+    /// Until the hold duration elapses
+    Duration,
+    /// Until released from Home Assistant
+    Permanent
+}
+
+/// Smart recovery
+///
+/// Start heating before a scheduled set point, so the room reaches the
+/// set point temp at the scheduled time rather than starting to heat then.
+/// The lead time comes from the heating rate learned from past heating
+/// runs, recovery waits until a rate has been learned.
+///
+/// ```toml
+/// [recovery]
+/// enabled = true
+/// max_lead = "3h"
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct RecoveryConfig {
+    /// Start heating early for scheduled set points, default false
+    pub enabled: bool,
+
+    /// Longest time to start heating before a set point, default "3h"
+    #[serde(deserialize_with = "config_de::duration")]
+    pub max_lead: Duration
+}
+
+impl Default for RecoveryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_lead: Duration::from_hours(3)
+        }
+    }
+}
+
+/// Schedule learning
+///
+/// Keep track of setpoint changes made on the thermostat or from Home
+/// Assistant in heat and cool mode, and suggest schedule set points for
+/// changes repeated around the same time on weekdays or weekends. Suggestions
+/// are reviewed on the settings screen, accepted ones are added to the
+/// schedule like an edit from Home Assistant.
+///
+/// ```toml
+/// [learning]
+/// enabled = true
+/// min_adjustments = 3
+/// time_window = "30m"
+/// max_age_days = 28
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct LearningConfig {
+    /// Track setpoint changes and suggest set points, default false
+    pub enabled: bool,
+
+    /// Changes on separate days needed before a set point is suggested,
+    /// default 3
+    pub min_adjustments: usize,
+
+    /// Changes this close in time of day are counted together, and
+    /// suggested set point times are rounded to it, default "30m"
+    #[serde(deserialize_with = "config_de::duration")]
+    pub time_window: Duration,
+
+    /// Forget changes older than this many days, default 28
+    pub max_age_days: u32
+}
+
+impl Default for LearningConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_adjustments: 3,
+            time_window: Duration::from_mins(30),
+            max_age_days: 28
+        }
+    }
+}
+
+/// Humidity comfort
+///
+/// Heat and cool to the apparent temp, how warm the room feels with the
+/// humidity from the backplate sensor, rather than the air temp. Humid air
+/// feels warmer, so heating stops sooner and cooling starts sooner.
+/// The apparent temp is shown on the main screen while enabled.
+///
+/// ```toml
+/// [comfort]
+/// enabled = true
+/// reference_humidity = 45.0
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ComfortConfig {
+    /// Control with the apparent temp, default false
+    pub enabled: bool,
+
+    /// Humidity percent where the apparent temp is the air temp,
+    /// default 45.0
+    pub reference_humidity: f32
+}
+
+impl Default for ComfortConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            reference_humidity: 45.0
+        }
+    }
+}
+
+/// Window open detection
+///
+/// Pause heating when the room temp drops quickly, like when a window is
+/// left open, instead of heating the outdoors. Heating resumes once the
+/// temp stops falling, or after the longest pause.
+///
+/// ```toml
+/// [window_open]
+/// enabled = true
+/// drop_rate = 0.2
+/// stable_period = "5m"
+/// max_pause = "30m"
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct WindowOpenConfig {
+    /// Pause heating when a window looks open, default false
+    pub enabled: bool,
+
+    /// Degrees per minute the temp has to drop by, default 0.2
+    pub drop_rate: f32,
+
+    /// Heating resumes when the temp hasn't dropped for this long,
+    /// default "5m"
+    #[serde(deserialize_with = "config_de::duration")]
+    pub stable_period: Duration,
+
+    /// Heating resumes after this long even if the temp is still dropping,
+    /// default "30m"
+    #[serde(deserialize_with = "config_de::duration")]
+    pub max_pause: Duration
+}
+
+impl Default for WindowOpenConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            drop_rate: 0.2,
+            stable_period: Duration::from_mins(5),
+            max_pause: Duration::from_mins(30)
+        }
+    }
+}
+
+/// Temperature source
+///
+/// Where the current temp comes from. The Nest sensor can read high when
+/// the wall behind it is warm, a Home Assistant sensor elsewhere in the
+/// room can be used instead, or averaged with it.
+///
+/// The backplate sensor is used whenever the Home Assistant sensor hasn't
+/// reported recently. Home Assistant subscribes to the sensor when it
+/// connects, so changing `ha_entity` requires a restart.
+///
+/// ```toml
+/// [temp_source]
+/// source = "Average"
+/// ha_entity = "sensor.living_room_temperature"
+/// ha_weight = 0.7
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct TempSourceConfig {
+    /// "Backplate", "HomeAssistant", or "Average", default "Backplate"
+    pub source: TempSource,
+
+    /// Home Assistant temperature sensor entity id, default none
+    pub ha_entity: Option<String>,
+
+    /// Unit of the Home Assistant sensor state, "Celsius" or "Fahrenheit",
+    /// default "Celsius"
+    pub ha_unit: TempUnit,
+
+    /// Share of the Home Assistant sensor in "Average", from 0 to 1,
+    /// default 0.5
+    pub ha_weight: f32,
+
+    /// Ignore the Home Assistant sensor when it hasn't reported for this
+    /// long, default "30m"
+    #[serde(deserialize_with = "config_de::duration")]
+    pub ha_max_age: Duration
+}
+
+impl Default for TempSourceConfig {
+    fn default() -> Self {
+        Self {
+            source: TempSource::Backplate,
+            ha_entity: None,
+            ha_unit: TempUnit::Celsius,
+            ha_weight: 0.5,
+            ha_max_age: Duration::from_mins(30)
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum TempSource {
+    /// The Nest's own sensor
+    Backplate,
+    /// The `ha_entity` sensor
+    HomeAssistant,
+    /// Weighted average of both sensors, see `ha_weight`
+    Average
+}
+
+/// Outdoor temperature
+///
+/// Shown on the main screen below the gauge, from a Home Assistant entity
+/// such as a weather forecast. Home Assistant subscribes to the entity when
+/// it connects, so changes require a restart.
+///
+/// ```toml
+/// [outdoor]
+/// ha_entity = "weather.home"
+/// ha_attribute = "temperature"
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct OutdoorConfig {
+    /// Home Assistant entity id, default none
+    pub ha_entity: Option<String>,
+
+    /// Entity attribute holding the temperature, default none to use the
+    /// entity state
+    pub ha_attribute: Option<String>,
+
+    /// Unit of the Home Assistant temperature, "Celsius" or "Fahrenheit",
+    /// default "Celsius"
+    pub ha_unit: TempUnit
+}
+
+impl Default for OutdoorConfig {
+    fn default() -> Self {
+        Self {
+            ha_entity: None,
+            ha_attribute: None,
+            ha_unit: TempUnit::Celsius
+        }
+    }
+}
+
+/// Staging
+///
+/// Only used when a second stage wire is configured in `[backplate.wiring]`.
+/// Each heating or cooling cycle starts on the first stage, and moves up to
+/// the second stage when the first stage runs too long or the temp is far
+/// from the target.
+///
+/// With a heat pump `aux_wire` and no `heat2_wire`, aux heat is the second
+/// heat stage, and the compressor is switched off while aux heat runs.
+///
+/// ```toml
+/// [staging]
+/// stage2_delay = "10m"
+/// stage2_temp_delta = 1.5
+/// stage_down_delta = 0.5
+/// aux_with_compressor = false
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct StagingConfig {
+    /// Time on first stage before moving up to the second stage, default "10m"
+    #[serde(deserialize_with = "config_de::duration")]
+    pub stage2_delay: Duration,
+
+    /// Move up to the second stage right away when the current temp is at
+    /// least this far from the target temp, default 1.5
+    pub stage2_temp_delta: f32,
+
+    /// Move back down to the first stage when the current temp comes within
+    /// this distance of the target temp, default 0.5
+    pub stage_down_delta: f32,
+
+    /// Keep the compressor running along with aux heat on the second stage,
+    /// for heat pumps that can run both at once. Emergency heat mode always
+    /// switches the compressor off. Default false
+    pub aux_with_compressor: bool
+}
+
+impl Default for StagingConfig {
+    fn default() -> Self {
+        Self {
+            stage2_delay: Duration::from_mins(10),
+            stage2_temp_delta: 1.5,
+            stage_down_delta: 0.5,
+            aux_with_compressor: false
+        }
+    }
+}
+
+/// Control
+///
+/// How heating and cooling are switched on and off in heat and cool modes,
+/// heat/cool mode always uses hysteresis.
+///
+/// "Hysteresis" runs until the temp passes the target by `temp_overrun`, and
+/// starts again once it's `temp_deadband` away. "TimeProportional" runs for
+/// a share of each `cycle_period`, from the distance to the target temp,
+/// which suits slow responding systems like radiant heat. Cycles are still
+/// subject to `min_off_time`.
+///
+/// ```toml
+/// [control]
+/// heat = "TimeProportional"
+/// cycle_period = "15m"
+/// proportional_band = 2.0
+/// integral_time = "2h"
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ControlConfig {
+    /// Control strategy in heat mode, "Hysteresis" or "TimeProportional",
+    /// default "Hysteresis"
+    pub heat: ControlStrategy,
+
+    /// Control strategy in cool mode, default "Hysteresis"
+    pub cool: ControlStrategy,
+
+    /// Length of each on/off cycle with time proportional control, must be
+    /// longer than zero, default "15m"
+    #[serde(deserialize_with = "config_de::positive_duration")]
+    pub cycle_period: Duration,
+
+    /// Distance from the target temp where demand reaches 100%, must be
+    /// greater than zero, default 2.0
+    #[serde(deserialize_with = "config_de::positive_f32")]
+    pub proportional_band: f32,
+
+    /// Time for a steady error to add the same demand again as the
+    /// proportional band, correcting for heat loss the proportional part
+    /// can't make up. Set to zero for proportional only. Default "0s"
+    #[serde(deserialize_with = "config_de::duration")]
+    pub integral_time: Duration,
+
+    /// Demand percent below which the cycle stays off, to avoid very short
+    /// runs, default 10
+    pub min_demand: u8,
+
+    /// Use the proportional band and integral time found by autotune in
+    /// place of the values above, once autotune has been run. Default true
+    pub use_tuning: bool
+}
+
+impl ControlConfig {
+    /// Strategy used in `mode`
+    pub fn strategy(&self, mode: &HvacMode) -> ControlStrategy {
+        match mode {
+            HvacMode::Heat | HvacMode::EmergencyHeat => self.heat,
+            HvacMode::Cool => self.cool,
+            _ => ControlStrategy::Hysteresis
+        }
+    }
+}
+
+impl Default for ControlConfig {
+    fn default() -> Self {
+        Self {
+            heat: ControlStrategy::Hysteresis,
+            cool: ControlStrategy::Hysteresis,
+            cycle_period: Duration::from_mins(15),
+            proportional_band: 2.0,
+            integral_time: Duration::ZERO,
+            min_demand: 10,
+            use_tuning: true
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum ControlStrategy {
+    Hysteresis,
+    TimeProportional
+}
+
 /// Backplate
 ///
 /// ```toml
 /// [backplate]
 /// near_pir_threshold = 15
 /// serial_port = "/dev/ttyO2"
-/// wiring = { heat_wire: "W1", cool_wire: "Y1" }
+/// temp_offset = -0.5
+/// wiring = { type = "HeatAndCool", heat_wire = "W1", cool_wire = "Y1", fan_wire = "G", heat2_wire = "W2" }
 /// ```
 #[derive(Deserialize, Debug, Clone)]
 #[serde(default)]
@@ -314,9 +1357,87 @@ pub struct BackplateConfig {
     /// Path to backplate serial device file, default "/dev/ttyO2"
     pub serial_port: String,
 
+    /// While the screen is off and HVAC is idle and not close to starting,
+    /// the backplate is told to stop streaming and is polled for readings
+    /// at this interval instead of every second. Set to zero to always
+    /// stream. Default "30s".
+    #[serde(deserialize_with = "config_de::duration")]
+    pub quiet_poll_period: Duration,
+
+    /// Calibration offset added to the backplate temperature, default 0.0
+    pub temp_offset: f32,
+
+    /// Weight given to each new temperature reading, from 0.01 to 1.0,
+    /// where lower values smooth out more noise, and 1.0 disables smoothing.
+    /// Default 0.2
+    pub temp_smoothing: f32,
+
     /// HVAC wiring configuration, default `{ heat_wire: "W1", cool_wire: "Y1" }`.
     /// Valid wire names: W1, Y1, G, OB, W2, Y2, Star.
-    pub wiring: WireConfig
+    /// `cool_wire` and `fan_wire` can be left out for heat only systems, or
+    /// systems without a fan.
+    /// Optional `heat2_wire` and `cool2_wire` enable two stage heating and cooling.
+    /// Optional `aux_wire` is auxiliary heat for a heat pump, with `heat_wire`
+    /// running the compressor, and enables emergency heat mode.
+    pub wiring: WireConfig,
+
+    /// Wire ids used to switch wires on the backplate, "Standard" or
+    /// "PluggedOrder". Default none, to pick the profile for the backplate
+    /// model detected on connect, falling back to "Standard" for unknown models.
+    pub wire_profile: Option<WireProfileId>,
+
+    /// Log all serial traffic with the backplate to this file, for
+    /// troubleshooting or replaying later, appended to on each connection,
+    /// default none
+    pub capture_file: Option<PathBuf>,
+
+    /// Replay a capture file instead of opening the serial port, with
+    /// readings delivered at the pace they were captured. Also works in
+    /// simulate builds. Default none
+    pub replay_file: Option<PathBuf>,
+
+    /// Longest wait for data on each serial read. Default "3s"
+    #[serde(deserialize_with = "config_de::duration")]
+    pub read_timeout: Duration,
+
+    /// Ask the backplate for readings again when nothing has been received
+    /// for this long, beyond the poll period while quiet. Default "10s"
+    #[serde(deserialize_with = "config_de::duration")]
+    pub silence_timeout: Duration,
+
+    /// Reconnect to the backplate, with the reset sequence, when nothing has
+    /// been received for this long, beyond the poll period while quiet.
+    /// Default "1m"
+    #[serde(deserialize_with = "config_de::duration")]
+    pub offline_timeout: Duration,
+
+    /// Switch all wires off when retherm is stopped with SIGTERM or SIGINT,
+    /// instead of leaving them as they were. Restarts leave wires as they
+    /// are. Default true
+    pub off_on_shutdown: bool,
+
+    /// Switch wires off when the event loop hasn't responded for this long
+    /// while HVAC is running, in case it has hung. Wires are switched back
+    /// once it responds again. Set to zero to disable. Default "2m".
+    #[serde(deserialize_with = "config_de::duration")]
+    pub failsafe_timeout: Duration,
+
+    /// Hardware watchdog device, such as "/dev/watchdog", fed while the event
+    /// loop is responding. The kernel reboots the thermostat if retherm dies,
+    /// or hangs for longer than `failsafe_timeout`, and the backplate comes
+    /// back with all wires off. Default none
+    pub watchdog_device: Option<PathBuf>,
+
+    /// Expose a service mode switch, and a switch for each configured wire,
+    /// to Home Assistant for checking the wiring. While in service mode the
+    /// wires are only switched from Home Assistant. The wiring setup on the
+    /// thermostat tests wires without this. Default false
+    pub service_mode: bool,
+
+    /// Service mode ends on its own after this long, handing the wires
+    /// back to the thermostat. Default "10m"
+    #[serde(deserialize_with = "config_de::duration")]
+    pub service_timeout: Duration
 }
 
 impl Default for BackplateConfig {
@@ -324,26 +1445,170 @@ impl Default for BackplateConfig {
         Self {
             near_pir_threshold: 15,
             serial_port: String::from("/dev/ttyO2"),
+            quiet_poll_period: Duration::from_secs(30),
+            temp_offset: 0.0,
+            temp_smoothing: 0.2,
             wiring: WireConfig::HeatAndCool {
                 heat_wire: WireId::W1,
-                cool_wire: WireId::Y1,
-                fan_wire: WireId::G,
-            }
+                cool_wire: Some(WireId::Y1),
+                fan_wire: Some(WireId::G),
+                heat2_wire: None,
+                cool2_wire: None,
+                aux_wire: None,
+            },
+            wire_profile: None,
+            capture_file: None,
+            replay_file: None,
+            read_timeout: Duration::from_secs(3),
+            silence_timeout: Duration::from_secs(10),
+            offline_timeout: Duration::from_mins(1),
+            off_on_shutdown: true,
+            failsafe_timeout: Duration::from_mins(2),
+            watchdog_device: None,
+            service_mode: false,
+            service_timeout: Duration::from_mins(10)
         }
     }
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum HeadlessMode {
+    Auto, Always, Never
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
 pub enum WireId {
     W1, Y1, G, OB, W2, Y2, Star
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq)]
+pub enum WireProfileId {
+    #[default]
+    Standard,
+    PluggedOrder
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(tag = "type")]
 pub enum WireConfig {
     HeatAndCool {
         heat_wire: WireId,
-        cool_wire: WireId,
-        fan_wire: WireId,
+        /// Left out for heat only systems
+        #[serde(default)]
+        cool_wire: Option<WireId>,
+        /// Left out for systems without a separate fan
+        #[serde(default)]
+        fan_wire: Option<WireId>,
+        /// Second stage heat, typically W2
+        #[serde(default)]
+        heat2_wire: Option<WireId>,
+        /// Second stage cooling, typically Y2
+        #[serde(default)]
+        cool2_wire: Option<WireId>,
+        /// Heat pump auxiliary heat, used alone in emergency heat mode
+        #[serde(default)]
+        aux_wire: Option<WireId>,
+    }
+}
+
+impl WireConfig {
+    /// All configured wires
+    pub fn wires(&self) -> Vec<WireId> {
+        match self {
+            WireConfig::HeatAndCool { heat_wire, cool_wire, fan_wire, heat2_wire, cool2_wire, aux_wire } => {
+                [Some(*heat_wire), *cool_wire, *fan_wire, *heat2_wire, *cool2_wire, *aux_wire]
+                    .into_iter()
+                    .flatten()
+                    .collect()
+            }
+        }
+    }
+
+    /// Wire for first stage cooling, if one is configured
+    pub fn cool_wire(&self) -> Option<WireId> {
+        match self {
+            WireConfig::HeatAndCool { cool_wire, .. } => *cool_wire
+        }
+    }
+
+    /// Heat pump auxiliary heat wire, if one is configured
+    pub fn aux_wire(&self) -> Option<WireId> {
+        match self {
+            WireConfig::HeatAndCool { aux_wire, .. } => *aux_wire
+        }
+    }
+
+    /// True when aux heat is the second heat stage, in place of `heat2_wire`
+    pub fn aux_stage2(&self) -> bool {
+        match self {
+            WireConfig::HeatAndCool { heat2_wire, aux_wire, .. } => {
+                heat2_wire.is_none() && aux_wire.is_some()
+            }
+        }
+    }
+
+    /// This wiring with the heat, cool and fan wires replaced, keeping the
+    /// stage 2 and aux wires unless they're now one of the replaced wires
+    pub fn with_wires(&self, heat: WireId, cool: Option<WireId>, fan: Option<WireId>) -> Self {
+        let picked = [Some(heat), cool, fan];
+        let keep = |wire: &Option<WireId>| wire.filter(|w| !picked.contains(&Some(*w)));
+
+        match self {
+            WireConfig::HeatAndCool { heat2_wire, cool2_wire, aux_wire, .. } => WireConfig::HeatAndCool {
+                heat_wire: heat,
+                cool_wire: cool,
+                fan_wire: fan,
+                heat2_wire: keep(heat2_wire),
+                cool2_wire: keep(cool2_wire),
+                aux_wire: keep(aux_wire)
+            }
+        }
+    }
+
+    /// Wire for the second stage of the given action, if one is configured
+    pub fn stage2_wire(&self, action: &HvacAction) -> Option<WireId> {
+        match self {
+            WireConfig::HeatAndCool { heat2_wire, cool2_wire, aux_wire, .. } => match action {
+                HvacAction::Heating => heat2_wire.or(*aux_wire),
+                HvacAction::Cooling => *cool2_wire,
+                _ => None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn away_timeout_migrated() {
+        let config = Config::from_toml("[away_mode]\ntemp_heat = 15.0\ntimeout = \"10m\"").unwrap();
+        assert_eq!(config.occupancy.timeout, Duration::from_mins(10));
+        assert_eq!(config.away_mode.temp_heat, Temperature::from_celsius(15.0));
+
+        let config = Config::from_toml("[occupancy]\ntimeout = \"5m\"").unwrap();
+        assert_eq!(config.occupancy.timeout, Duration::from_mins(5));
+
+        assert!(Config::from_toml("[away_mode]\ntimeout = \"10m\"\n[occupancy]\ntimeout = \"5m\"").is_err());
+    }
+
+    #[test]
+    fn control_rejects_zero() {
+        assert!(Config::from_toml("[control]\ncycle_period = \"0s\"").is_err());
+        assert!(Config::from_toml("[control]\nproportional_band = 0.0").is_err());
+        assert!(Config::from_toml("[control]\nproportional_band = -1.0").is_err());
+
+        let config = Config::from_toml("[control]\ncycle_period = \"10m\"\nproportional_band = 1.5").unwrap();
+        assert_eq!(config.control.cycle_period, Duration::from_mins(10));
+        assert_eq!(config.control.proportional_band, 1.5);
+    }
+
+    #[test]
+    fn motion_hold_rejects_zero() {
+        assert!(Config::from_toml("[occupancy]\nmotion_hold = \"0s\"").is_err());
+
+        let config = Config::from_toml("[occupancy]\nmotion_hold = \"30s\"").unwrap();
+        assert_eq!(config.occupancy.motion_hold, Duration::from_secs(30));
     }
 }
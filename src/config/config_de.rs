@@ -16,10 +16,11 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::time::Duration;
+use std::{collections::HashMap, time::Duration};
 
 use chrono::NaiveTime;
-use serde::{Deserializer, de::{self, Visitor}};
+use log::LevelFilter;
+use serde::{Deserialize, Deserializer, de::{self, Visitor}};
 
 pub fn duration<'de, D>(deserializer: D) -> Result<Duration, D::Error>
     where D: Deserializer<'de>
@@ -65,6 +66,27 @@ pub fn duration<'de, D>(deserializer: D) -> Result<Duration, D::Error>
     deserializer.deserialize_any(DurationVisitor)
 }
 
+/// A [`duration`] that must be longer than zero
+pub fn positive_duration<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+    where D: Deserializer<'de>
+{
+    let val = duration(deserializer)?;
+    if val.is_zero() {
+        return Err(de::Error::custom("duration must be longer than zero"));
+    }
+    Ok(val)
+}
+
+pub fn positive_f32<'de, D>(deserializer: D) -> Result<f32, D::Error>
+    where D: Deserializer<'de>
+{
+    let val = f32::deserialize(deserializer)?;
+    if val.is_nan() || val <= 0.0 {
+        return Err(de::Error::custom(format!("{val} must be greater than zero")));
+    }
+    Ok(val)
+}
+
 pub fn time_of_day<'de, D>(deserializer: D) -> Result<NaiveTime, D::Error>
     where D: Deserializer<'de>
 {
@@ -94,3 +116,19 @@ pub fn time_of_day<'de, D>(deserializer: D) -> Result<NaiveTime, D::Error>
 
     deserializer.deserialize_any(TimeOfDayVisitor)
 }
+
+pub fn level_filter<'de, D>(deserializer: D) -> Result<LevelFilter, D::Error>
+    where D: Deserializer<'de>
+{
+    let level = String::deserialize(deserializer)?;
+    level.parse().map_err(de::Error::custom)
+}
+
+pub fn level_filters<'de, D>(deserializer: D) -> Result<HashMap<String, LevelFilter>, D::Error>
+    where D: Deserializer<'de>
+{
+    HashMap::<String, String>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(module, level)| Ok((module, level.parse().map_err(de::Error::custom)?)))
+        .collect()
+}
@@ -17,7 +17,9 @@
  */
 
 use chrono::{NaiveTime, Weekday};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::{state::{HvacFanMode, HvacMode}, temperature::Temperature};
 
 use super::config_de;
 
@@ -35,6 +37,11 @@ use super::config_de;
 /// * Heating schedule `[[schedule_heat]]`
 /// * Cooling schedule `[[schedule_cool]]`
 ///
+/// Schedules can also be edited from Home Assistant with the
+/// `set_schedule_point` and `remove_schedule_point` services. Edits are saved
+/// to `retherm.schedule.toml` in the storage dir, and replace the schedules
+/// in the config file until the `reset_schedule` service is called.
+///
 /// You can define more than one schedule entry, and it will overlap the
 /// previous. In the example below, the temperature will be set to 20.0
 /// at 8am everyday, and set down to 16.0 at 9am Monday and Wednsday.
@@ -52,7 +59,21 @@ use super::config_de;
 ///    { time = "09:00", temp = 16.0 }
 /// ]
 /// ```
-#[derive(Deserialize, Debug, Clone)]
+///
+/// A set point can also switch the mode or fan. A schedule that switches the
+/// mode keeps running in modes without a schedule of their own, so it can
+/// switch the mode back later. In the example below heating is turned off
+/// overnight, and back on in the morning.
+///
+/// ```toml
+/// [[schedule_heat]]
+/// days_of_week = "EveryDay"
+/// set_points = [
+///    { time = "07:00", temp = 20.0, mode = "Heat", fan = "Auto" },
+///    { time = "23:00", temp = 16.0, mode = "Off" },
+/// ]
+/// ```
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct ScheduleConfig {
     /// Days of the week.
     ///
@@ -63,11 +84,11 @@ pub struct ScheduleConfig {
     /// List of weekdays ["Monday", "Tuesday", ...]
     pub days_of_week: DaysOfWeek,
 
-    /// List of set points with time of day and temperature
+    /// List of set points with time of day, temperature, and optional mode and fan
     pub set_points: Vec<SetPoint>
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(untagged)]
 pub enum DaysOfWeek {
     Range(WeekDayRange),
@@ -97,14 +118,14 @@ impl DaysOfWeek {
     }
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub enum WeekDayRange {
     EveryDay,
     WeekDays,
     WeekEnd
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub enum WeekDay {
     Mon,
     Tue,
@@ -127,11 +148,33 @@ impl WeekDay {
             Self::Sun => Weekday::Sun
         }
     }
+
+    pub fn from_chrono(day: Weekday) -> Self {
+        match day {
+            Weekday::Mon => Self::Mon,
+            Weekday::Tue => Self::Tue,
+            Weekday::Wed => Self::Wed,
+            Weekday::Thu => Self::Thur,
+            Weekday::Fri => Self::Fri,
+            Weekday::Sat => Self::Sat,
+            Weekday::Sun => Self::Sun
+        }
+    }
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct SetPoint {
-    #[serde(deserialize_with = "config_de::time_of_day")]
+    #[serde(deserialize_with = "config_de::time_of_day", serialize_with = "time_of_day")]
     pub time: NaiveTime,
-    pub temp: f32
+    pub temp: Temperature,
+    /// Switch to this mode when the set point is reached
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<HvacMode>,
+    /// Switch to this fan mode when the set point is reached
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fan: Option<HvacFanMode>
+}
+
+fn time_of_day<S: Serializer>(time: &NaiveTime, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&time.format("%H:%M").to_string())
 }
@@ -0,0 +1,145 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use anyhow::Result;
+use chrono::{Local, NaiveDate};
+
+use crate::{
+    events::{Event, EventHandler, EventSender, Topic},
+    temperature::Temperature
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TempRange {
+    pub min: Temperature,
+    pub max: Temperature,
+}
+
+/// Tracks today's min and max room temp, and sends `Event::DailyTempRange`
+/// when either changes. The range starts over with the first reading
+/// after midnight.
+pub struct DailyTempRange<S> {
+    event_sender: S,
+    day: Option<NaiveDate>,
+    range: Option<TempRange>,
+}
+
+impl<S: EventSender> DailyTempRange<S> {
+    pub fn new(event_sender: S) -> Self {
+        Self {
+            event_sender,
+            day: None,
+            range: None,
+        }
+    }
+
+    /// Add reading for `day`, returns true if the range changed
+    fn record(&mut self, temp: Temperature, day: NaiveDate) -> bool {
+        let range = match self.range {
+            Some(range) if self.day == Some(day) => TempRange {
+                min: if temp < range.min { temp } else { range.min },
+                max: if temp > range.max { temp } else { range.max },
+            },
+            _ => TempRange { min: temp, max: temp }
+        };
+
+        self.day = Some(day);
+
+        if self.range != Some(range) {
+            self.range = Some(range);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl<S: EventSender> EventHandler for DailyTempRange<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<()> {
+        let changed = match event {
+            Event::SetCurrentTemp(temp) => {
+                self.record(temp.round_tenth(), Local::now().date_naive())
+            }
+            // readings taken before connecting or while the backplate was quiet
+            Event::BufferedTemps(temps) => {
+                let mut changed = false;
+                for (time, temp) in temps {
+                    let day = time.date_naive();
+                    // older days would start the range over
+                    if self.day.is_none_or(|current| day >= current) {
+                        changed |= self.record(temp.round_tenth(), day);
+                    }
+                }
+                changed
+            }
+            Event::GetState => true,
+            _ => false
+        };
+
+        if changed && let Some(range) = self.range {
+            self.event_sender.send_event(Event::DailyTempRange(range))?;
+        }
+
+        Ok(())
+    }
+
+    fn topics(&self) -> &'static [Topic] {
+        &[Topic::State, Topic::Backplate]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{DefaultEventSource, EventSource};
+
+    #[test]
+    fn resets_at_midnight() {
+        let events = DefaultEventSource::new();
+        let mut range = DailyTempRange::new(events.event_sender());
+
+        let today = NaiveDate::from_ymd_opt(2026, 1, 10).unwrap();
+        let tomorrow = today.succ_opt().unwrap();
+
+        assert!(range.record(Temperature::from_celsius(20.0), today));
+        assert!(range.record(Temperature::from_celsius(18.5), today));
+        assert!(range.record(Temperature::from_celsius(21.0), today));
+        assert!(!range.record(Temperature::from_celsius(19.0), today));
+        assert_eq!(range.range, Some(TempRange {
+            min: Temperature::from_celsius(18.5),
+            max: Temperature::from_celsius(21.0)
+        }));
+
+        // readings buffered by the backplate while quiet, the one from
+        // yesterday is ignored
+        let at = |day: NaiveDate| day.and_hms_opt(12, 0, 0).unwrap().and_local_timezone(Local).unwrap();
+        let temps = vec![
+            (at(today.pred_opt().unwrap()), Temperature::from_celsius(15.0)),
+            (at(today), Temperature::from_celsius(18.0)),
+            (at(today), Temperature::from_celsius(19.0))
+        ];
+        range.handle_event(&Event::BufferedTemps(temps)).unwrap();
+        assert_eq!(range.range.map(|r| r.min), Some(Temperature::from_celsius(18.0)));
+
+        assert!(range.record(Temperature::from_celsius(19.0), tomorrow));
+        assert_eq!(range.range, Some(TempRange {
+            min: Temperature::from_celsius(19.0),
+            max: Temperature::from_celsius(19.0)
+        }));
+    }
+}
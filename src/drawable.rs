@@ -16,8 +16,10 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use std::{convert::Infallible, mem};
+
 use anyhow::Result;
-use embedded_graphics::pixelcolor::Bgr888;
+use embedded_graphics::{pixelcolor::Bgr888, prelude::*, primitives::Rectangle};
 use embedded_graphics_framebuf::FrameBuf;
 
 /// Trait for screens and components drawn by screens.
@@ -25,4 +27,178 @@ pub trait AppDrawable {
     fn draw(&self, target: &mut AppFrameBuf) -> Result<()>;
 }
 
-pub type AppFrameBuf = FrameBuf<Bgr888, [Bgr888; 320 * 320]>;
+const SCREEN_PIXELS: usize = 320 * 320;
+
+/// Screen buffer that records the areas drawn to since the last flush, so
+/// only those areas need to be copied to the display
+pub struct AppFrameBuf {
+    buffer: FrameBuf<Bgr888, [Bgr888; SCREEN_PIXELS]>,
+    dirty: DirtyRegion
+}
+
+impl AppFrameBuf {
+    pub fn new(width: usize, height: usize) -> Self {
+        let data = [Bgr888::WHITE; SCREEN_PIXELS];
+        Self {
+            buffer: FrameBuf::new(data, width, height),
+            dirty: DirtyRegion::default()
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.buffer.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.buffer.height()
+    }
+
+    /// Pixels in row order
+    pub fn data(&self) -> &[Bgr888] {
+        &self.buffer.data
+    }
+
+    /// Areas drawn since the last call, leaving the buffer clean
+    pub fn take_dirty(&mut self) -> DirtyRegion {
+        mem::take(&mut self.dirty)
+    }
+
+    fn mark_dirty(&mut self, area: &Rectangle) {
+        self.dirty.add(area.intersection(&self.bounding_box()));
+    }
+}
+
+impl OriginDimensions for AppFrameBuf {
+    fn size(&self) -> Size {
+        self.buffer.size()
+    }
+}
+
+impl DrawTarget for AppFrameBuf {
+    type Color = Bgr888;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+        where I: IntoIterator<Item = Pixel<Self::Color>>
+    {
+        // bounds of a single primitive, primitives are drawn one call each
+        let mut corners: Option<(Point, Point)> = None;
+        let pixels = pixels.into_iter().inspect(|Pixel(p, _)| {
+            corners = Some(match corners {
+                Some((min, max)) => (min.component_min(*p), max.component_max(*p)),
+                None => (*p, *p)
+            });
+        });
+
+        self.buffer.draw_iter(pixels)?;
+
+        if let Some((min, max)) = corners {
+            self.mark_dirty(&Rectangle::with_corners(min, max));
+        }
+
+        Ok(())
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+        where I: IntoIterator<Item = Self::Color>
+    {
+        self.mark_dirty(area);
+        self.buffer.fill_contiguous(area, colors)
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        self.mark_dirty(area);
+        self.buffer.fill_solid(area, color)
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.mark_dirty(&self.bounding_box());
+        self.buffer.clear(color)
+    }
+}
+
+/// Most separate dirty areas tracked before they are merged into one
+const MAX_DIRTY_RECTS: usize = 8;
+
+/// Areas of the screen that changed, overlapping areas are merged
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DirtyRegion {
+    rects: Vec<Rectangle>
+}
+
+impl DirtyRegion {
+    pub fn add(&mut self, area: Rectangle) {
+        if area.is_zero_sized() {
+            return;
+        }
+
+        // touching areas are merged too, e.g. the rows of a filled circle
+        let mut area = area;
+        while let Some(i) = self.rects.iter().position(|r| touches(r, &area)) {
+            area = envelope(&self.rects.swap_remove(i), &area);
+        }
+        self.rects.push(area);
+
+        // lots of small areas copy slower than one larger area
+        if self.rects.len() > MAX_DIRTY_RECTS {
+            let all = self.rects.drain(..).reduce(|a, b| envelope(&a, &b));
+            self.rects.extend(all);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rects.is_empty()
+    }
+
+    pub fn rects(&self) -> &[Rectangle] {
+        &self.rects
+    }
+}
+
+fn touches(a: &Rectangle, b: &Rectangle) -> bool {
+    !a.intersection(&b.offset(1)).is_zero_sized()
+}
+
+/// Smallest rectangle containing both `a` and `b`
+pub fn envelope(a: &Rectangle, b: &Rectangle) -> Rectangle {
+    let top_left = a.top_left.component_min(b.top_left);
+    let bottom_right = (a.top_left + a.size).component_max(b.top_left + b.size);
+    let size = bottom_right - top_left;
+
+    Rectangle::new(top_left, Size::new(size.x as u32, size.y as u32))
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_graphics::primitives::{Circle, PrimitiveStyle};
+
+    use super::*;
+
+    #[test]
+    fn dirty_region_merges_overlapping() {
+        let mut dirty = DirtyRegion::default();
+        dirty.add(Rectangle::new(Point::new(0, 0), Size::new(10, 10)));
+        dirty.add(Rectangle::new(Point::new(50, 50), Size::new(10, 10)));
+        assert_eq!(dirty.rects().len(), 2);
+
+        // bridges both areas
+        dirty.add(Rectangle::new(Point::new(5, 5), Size::new(50, 50)));
+        assert_eq!(dirty.rects(), &[Rectangle::new(Point::new(0, 0), Size::new(60, 60))]);
+    }
+
+    #[test]
+    fn draw_marks_dirty() {
+        let mut buffer = AppFrameBuf::new(320, 320);
+        assert!(buffer.take_dirty().is_empty());
+
+        Circle::new(Point::new(310, 10), 20)
+            .into_styled(PrimitiveStyle::with_fill(Bgr888::RED))
+            .draw(&mut buffer)
+            .unwrap();
+
+        // clipped to the screen
+        let dirty = buffer.take_dirty();
+        assert_eq!(dirty.rects(), &[Rectangle::new(Point::new(310, 10), Size::new(10, 20))]);
+        assert!(buffer.take_dirty().is_empty());
+    }
+}
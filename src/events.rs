@@ -18,32 +18,125 @@
 
 use std::{
     cell::RefCell,
-    sync::{Arc, atomic::{AtomicBool, Ordering}, mpsc::{Receiver, Sender, TryRecvError, channel}},
+    fmt,
+    sync::{Arc, atomic::{AtomicBool, Ordering}},
     time::{Duration, Instant}
 };
 
 use anyhow::Result;
+use chrono::{DateTime, Local};
 use debounce::EventDebouncer;
+use log::{debug, warn};
 use throttle::Throttle;
 
-use crate::{screen::ScreenId, state::{HvacMode, ThermostatState}, timer::TimerId};
+use crate::{
+    action_log::{ActionEntry, ActionLog}, alerts::Alert, autotune::{AutotuneStatus, Tuning}, backplate::{BackplateCapabilities, LinkStats, PowerReading}, config::{WireConfig, WireId}, daily_range::TempRange, health::Problem, learning::{AdjustmentLog, Suggestion}, occupancy::Occupancy, recovery::HeatingRate, reload::ReloadedConfig, runtime_stats::RuntimeStats, schedule::{EditedSchedule, NextSetPoint, ScheduleEdit}, screen::ScreenId, state::{ClimateCommand, HvacFanMode, HvacMode, ThermostatState}, supervisor::Subsystem,
+    temperature::Temperature, timer::TimerId, zone::{PeerState, ZoneStatus}
+};
+
+#[cfg(feature = "device")]
+use crate::input_events::InputId;
+
+mod event_bus;
+// The simulator uses the SDL event queue
+#[cfg(any(test, feature = "device"))]
+mod event_queue;
+
+pub use event_bus::EventBus;
+#[cfg(any(test, feature = "device"))]
+pub use event_queue::{QueueReceiver, QueueSender, event_queue};
 
 #[derive(Debug, Clone)]
 pub enum Event {
     Quit,
+    /// Shut down and start again, from Home Assistant
+    Restart,
+    /// Flash the backlight and click, so the device can be found from Home Assistant
+    Identify,
     ButtonDown,
+    ButtonUp,
     Dial(i32),
-    SetTargetTemp(f32),
-    SetCurrentTemp(f32),
+    /// Target temp from the dial, the hvac action waits for it to settle
+    SetTargetTemp(Temperature),
+    /// Low and high setpoints for heat/cool mode from the dial
+    SetTargetRange(Temperature, Temperature),
+    /// Mode, setpoint and preset changes from Home Assistant, applied together
+    ClimateCommand(ClimateCommand),
+    /// Target temp set by the schedule, ignored while the schedule is held
+    ScheduledTargetTemp(Temperature),
+    /// Current temp from the selected sources, see [`crate::temp_source`]
+    SetCurrentTemp(Temperature),
+    /// Reading from the Nest's own sensor
+    BackplateTemp(Temperature),
+    /// Reading from the Home Assistant temperature sensor
+    RemoteTemp(Temperature),
+    /// Home Assistant presence entity, true when someone is home
+    RemotePresence(bool),
+    /// Outdoor temp from Home Assistant, shown on the main screen
+    OutdoorTemp(Temperature),
+    /// Readings buffered by the backplate before connecting or while quiet,
+    /// with the estimated time of each reading, oldest to newest
+    BufferedTemps(Vec<(DateTime<Local>, Temperature)>),
     SetMode(HvacMode),
+    SetFanMode(HvacFanMode),
     SetAway(bool),
+    /// Transition between home and away, sent by occupancy manager
+    Occupancy(Occupancy),
+    /// Start or end a time-limited boost of the target temp
+    SetBoost(bool),
     State(ThermostatState),
     GetState,
     NavigateTo(ScreenId),
     NavigateBack,
     ClickSound,
+    /// Mode or fan selection applied from a screen
+    ConfirmSound,
+    /// Sounds muted, from the config at startup or the Home Assistant switch
+    SetMuted(bool),
+    /// Entered or left the `quiet_hours` window, clicks are muted and the
+    /// backlight dimmed while true
+    QuietHours(bool),
     ProximityNear,
     ProximityFar,
+    /// Proximity movement seen, or the motion hold time elapsed
+    Motion(bool),
+    /// Backplate ambient light sensor, sent when it changes noticeably
+    AmbientLight(u16),
+    /// Backplate relative humidity reading, in percent
+    Humidity(f32),
+    /// Dial or button input device failed to open, or stopped reading
+    #[cfg(feature = "device")]
+    InputDeviceMissing(InputId),
+    /// Missing input device opened again
+    #[cfg(feature = "device")]
+    InputDeviceConnected(InputId),
+    /// Today's min/max room temp, sent when either changes
+    DailyTempRange(TempRange),
+    /// Heating and cooling runtime, sent as it accumulates and at midnight
+    RuntimeStats(RuntimeStats),
+    /// Degraded subsystems, sent by health monitor when the list changes
+    Problems(Vec<Problem>),
+    /// Active alerts, sent by the alert monitor when the list changes
+    Alerts(Vec<Alert>),
+    /// Climate state from the peer thermostat at the address, `None` when disconnected
+    ZonePeer(String, Option<PeerState>),
+    /// Combined peer status, sent by the zone manager when it changes
+    Zone(ZoneStatus),
+    /// Room temp dropped quickly, heating is paused while true
+    WindowOpen(bool),
+    /// Enter or exit service mode, see `BackplateConfig::service_mode`
+    SetServiceMode(bool),
+    /// Switch a wire directly, only while in service mode
+    SetWire(WireId, bool),
+    /// Switch a single plugged wire on with all others off, from the wiring
+    /// setup, or `None` to end the test
+    TestWire(Option<WireId>),
+    /// Write the wiring to the config file and restart, from the wiring setup
+    SaveWiring(WireConfig),
+    /// Wires switched in service mode, or `None` when not in service mode
+    ServiceMode(Option<Vec<(WireId, bool)>>),
+    /// Supervised thread keeps failing, or recovered when false
+    SubsystemFailing(Subsystem, bool),
     /// Start or reset a timeout timer
     TimeoutReset(TimerId, Duration),
     /// Timer reached timeout
@@ -54,14 +147,153 @@ pub enum Event {
     TimerTick(TimerId, Duration),
     CancelTimer(TimerId),
     BackplateConnected,
+    /// Connection failed, or the backplate went silent for longer than
+    /// `BackplateConfig::offline_timeout`, it is reconnected with a reset
     BackplateDisconnected,
+    /// Backplate went silent for longer than `BackplateConfig::offline_timeout`,
+    /// sent before `BackplateDisconnected`
+    BackplateOffline,
+    /// Backplate hardware details, sent from the persisted copy at startup
+    /// and again once queried from the backplate
+    BackplateCapabilities(BackplateCapabilities),
+    /// Backplate power readings, sent when they change noticeably
+    BackplatePower(PowerReading),
+    /// Backplate serial link error counters, sent when they change
+    BackplateLinkStats(LinkStats),
+    /// Wires the backplate failed to switch as commanded, empty when cleared
+    HvacFault(Vec<WireId>),
+    /// Sent while the event loop is otherwise quiet, to show it's still
+    /// responding, see `BackplateConfig::failsafe_timeout`
+    Heartbeat,
+    HaConnected,
+    HaDisconnected,
+    /// Backlight brightness set remotely, zero returns to local brightness
+    SetBrightness(u32),
+    /// Change the configured backlight `brightness` until the config is
+    /// reloaded, from Home Assistant
+    SetDefaultBrightness(u32),
+    /// Change schedules from Home Assistant
+    EditSchedule(ScheduleEdit),
+    /// Edited schedules to persist, `None` when reset to the config schedules
+    ScheduleChanged(Option<EditedSchedule>),
+    /// Upcoming set point of the active schedule, `None` without a schedule
+    NextSetPoint(Option<NextSetPoint>),
+    /// Re-read config and theme files, from SIGHUP or Home Assistant
+    ReloadConfig,
+    /// New config and theme to apply, sent after a successful reload
+    ConfigReloaded(Arc<ReloadedConfig>),
+    /// Switch to the named theme, from the settings screen or Home Assistant
+    SetTheme(String),
+    /// Ambient light crossed the backlight `night_light` or `day_light`
+    /// level, true to use the night variant of the theme
+    NightTheme(bool),
+    /// Start the autotune relay experiment, from the autotune screen or Home Assistant
+    StartAutotune,
+    StopAutotune,
+    /// Autotune progress, heating/cooling is cycled around the target while running
+    Autotune(AutotuneStatus),
+    /// Autotune result, sent when autotune finishes and from the persisted copy at startup
+    Tuning(Tuning),
+    /// Heating rate learned for smart recovery, sent after each heating run
+    /// and from the persisted copy at startup
+    HeatingRate(HeatingRate),
+    /// Change the heat/cool deadband, from the settings screen, Home Assistant,
+    /// or the persisted copy at startup
+    SetDeadband(f32),
+    /// Deadband in use, sent when it changes
+    Deadband(f32),
+    /// Hvac action changed, with the likely cause
+    HvacSwitched(ActionEntry),
+    /// Recent hvac action switches to persist and show, sent at startup
+    /// and after each switch
+    ActionLog(ActionLog),
+    /// Setpoint adjustments to persist, sent after each change
+    Adjustments(AdjustmentLog),
+    /// Schedule set points suggested from the adjustments, sent at startup
+    /// and when they change
+    ScheduleSuggestions(Vec<Suggestion>),
+    /// Add a suggested set point to the schedule, from the suggestions screen
+    AcceptSuggestion(Suggestion),
+    /// Forget the adjustments behind a suggestion, from the suggestions screen
+    DismissSuggestion(Suggestion),
+}
+
+/// Kinds of event, handlers are only passed events of the topics they
+/// subscribe to, see [`EventHandler::topics`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Topic {
+    /// Dial, button and proximity, and the screen and sound feedback for them
+    Input,
+    /// Thermostat state and the settings, schedules and status around it
+    State,
+    /// Backplate readings, wires and connection
+    Backplate,
+    /// Timer resets, timeouts and ticks
+    Timer,
+    /// Home Assistant connection and the entities it follows
+    Ha,
+    /// Quit, restart, reload and state requests, passed to every handler
+    System
+}
+
+impl Topic {
+    pub const ALL: &[Topic] = &[Self::Input, Self::State, Self::Backplate, Self::Timer, Self::Ha];
 }
 
 impl Event {
+    pub fn topic(&self) -> Topic {
+        match self {
+            Self::Quit | Self::Restart | Self::GetState | Self::ReloadConfig | Self::ConfigReloaded(_) => {
+                Topic::System
+            }
+            Self::Identify | Self::ButtonDown | Self::ButtonUp | Self::Dial(_)
+                | Self::NavigateTo(_) | Self::NavigateBack | Self::ClickSound | Self::ConfirmSound
+                | Self::SetMuted(_) | Self::QuietHours(_) | Self::ProximityNear | Self::ProximityFar | Self::Motion(_)
+                | Self::SetBrightness(_) | Self::SetTheme(_)
+                | Self::SetDefaultBrightness(_)
+                | Self::NightTheme(_) => {
+                Topic::Input
+            }
+            #[cfg(feature = "device")]
+            Self::InputDeviceMissing(_) | Self::InputDeviceConnected(_) => {
+                Topic::Input
+            }
+            Self::SetTargetTemp(_) | Self::SetTargetRange(..) | Self::ClimateCommand(_)
+                | Self::ScheduledTargetTemp(_) | Self::SetCurrentTemp(_) | Self::SetMode(_)
+                | Self::SetFanMode(_) | Self::SetAway(_) | Self::Occupancy(_) | Self::SetBoost(_)
+                | Self::State(_) | Self::DailyTempRange(_) | Self::RuntimeStats(_)
+                | Self::Problems(_) | Self::Alerts(_) | Self::ZonePeer(..) | Self::Zone(_)
+                | Self::WindowOpen(_) | Self::SubsystemFailing(..) | Self::EditSchedule(_)
+                | Self::ScheduleChanged(_) | Self::NextSetPoint(_) | Self::StartAutotune
+                | Self::StopAutotune | Self::Autotune(_) | Self::Tuning(_) | Self::HeatingRate(_)
+                | Self::SetDeadband(_) | Self::Deadband(_) | Self::HvacSwitched(_)
+                | Self::ActionLog(_) | Self::Adjustments(_) | Self::ScheduleSuggestions(_)
+                | Self::AcceptSuggestion(_) | Self::DismissSuggestion(_) => {
+                Topic::State
+            }
+            Self::BackplateTemp(_) | Self::BufferedTemps(_) | Self::AmbientLight(_)
+                | Self::Humidity(_) | Self::SetServiceMode(_) | Self::SetWire(..)
+                | Self::TestWire(_) | Self::SaveWiring(_) | Self::ServiceMode(_) | Self::BackplateConnected
+                | Self::BackplateDisconnected | Self::BackplateOffline | Self::BackplateCapabilities(_)
+                | Self::BackplatePower(_) | Self::BackplateLinkStats(_) | Self::HvacFault(_)
+                | Self::Heartbeat => {
+                Topic::Backplate
+            }
+            Self::TimeoutReset(..) | Self::TimeoutReached(_) | Self::StartTickTimer(..)
+                | Self::TimerTick(..) | Self::CancelTimer(_) => {
+                Topic::Timer
+            }
+            Self::RemoteTemp(_) | Self::RemotePresence(_) | Self::OutdoorTemp(_)
+                | Self::HaConnected | Self::HaDisconnected => {
+                Topic::Ha
+            }
+        }
+    }
+
     /// Returns true if the event is one of the types that should cause device wakeup
     pub fn is_wakeup_event(&self) -> bool {
         match self {
-            Self::ButtonDown | Self::Dial(_) | Self::ProximityNear => true,
+            Self::ButtonDown | Self::Dial(_) | Self::ProximityNear | Self::Identify => true,
             _ => false
         }
     }
@@ -76,19 +308,56 @@ impl PartialEq for Event {
     fn eq(&self, other: &Self) -> bool {
         match self {
             Self::Quit => matches!(other, Self::Quit),
+            Self::Restart => matches!(other, Self::Restart),
+            Self::Identify => matches!(other, Self::Identify),
             Self::ButtonDown => matches!(other, Self::ButtonDown),
+            Self::ButtonUp => matches!(other, Self::ButtonUp),
             Self::Dial(_) => matches!(other, Self::Dial(_)),
             Self::SetTargetTemp(_) => matches!(other, Self::SetTargetTemp(_)),
+            Self::SetTargetRange(..) => matches!(other, Self::SetTargetRange(..)),
+            Self::ClimateCommand(_) => matches!(other, Self::ClimateCommand(_)),
+            Self::ScheduledTargetTemp(_) => matches!(other, Self::ScheduledTargetTemp(_)),
             Self::SetCurrentTemp(_) => matches!(other, Self::SetCurrentTemp(_)),
+            Self::BackplateTemp(_) => matches!(other, Self::BackplateTemp(_)),
+            Self::RemoteTemp(_) => matches!(other, Self::RemoteTemp(_)),
+            Self::RemotePresence(_) => matches!(other, Self::RemotePresence(_)),
+            Self::OutdoorTemp(_) => matches!(other, Self::OutdoorTemp(_)),
+            Self::BufferedTemps(_) => matches!(other, Self::BufferedTemps(_)),
             Self::SetMode(_) => matches!(other, Self::SetMode(_)),
+            Self::SetFanMode(_) => matches!(other, Self::SetFanMode(_)),
             Self::SetAway(_) => matches!(other, Self::SetAway(_)),
+            Self::Occupancy(_) => matches!(other, Self::Occupancy(_)),
+            Self::SetBoost(_) => matches!(other, Self::SetBoost(_)),
             Self::State(_) => matches!(other, Self::State(_)),
             Self::GetState => matches!(other, Self::GetState),
             Self::NavigateTo(_) => matches!(other, Self::NavigateTo(_)),
             Self::NavigateBack => matches!(other, Self::NavigateBack),
             Self::ClickSound => matches!(other, Self::ClickSound),
+            Self::ConfirmSound => matches!(other, Self::ConfirmSound),
+            Self::SetMuted(_) => matches!(other, Self::SetMuted(_)),
+            Self::QuietHours(_) => matches!(other, Self::QuietHours(_)),
             Self::ProximityNear => matches!(other, Self::ProximityNear),
             Self::ProximityFar => matches!(other, Self::ProximityFar),
+            Self::Motion(_) => matches!(other, Self::Motion(_)),
+            Self::AmbientLight(_) => matches!(other, Self::AmbientLight(_)),
+            Self::Humidity(_) => matches!(other, Self::Humidity(_)),
+            #[cfg(feature = "device")]
+            Self::InputDeviceMissing(_) => matches!(other, Self::InputDeviceMissing(_)),
+            #[cfg(feature = "device")]
+            Self::InputDeviceConnected(_) => matches!(other, Self::InputDeviceConnected(_)),
+            Self::Problems(_) => matches!(other, Self::Problems(_)),
+            Self::Alerts(_) => matches!(other, Self::Alerts(_)),
+            Self::ZonePeer(..) => matches!(other, Self::ZonePeer(..)),
+            Self::Zone(_) => matches!(other, Self::Zone(_)),
+            Self::WindowOpen(_) => matches!(other, Self::WindowOpen(_)),
+            Self::SetServiceMode(_) => matches!(other, Self::SetServiceMode(_)),
+            Self::SetWire(..) => matches!(other, Self::SetWire(..)),
+            Self::TestWire(_) => matches!(other, Self::TestWire(_)),
+            Self::SaveWiring(_) => matches!(other, Self::SaveWiring(_)),
+            Self::ServiceMode(_) => matches!(other, Self::ServiceMode(_)),
+            Self::SubsystemFailing(..) => matches!(other, Self::SubsystemFailing(..)),
+            Self::DailyTempRange(_) => matches!(other, Self::DailyTempRange(_)),
+            Self::RuntimeStats(_) => matches!(other, Self::RuntimeStats(_)),
             Self::TimeoutReset(_, _) => matches!(other, Self::TimeoutReset(_, _)),
             Self::TimeoutReached(_) => matches!(other, Self::TimeoutReached(_)),
             Self::StartTickTimer(_, _) => matches!(other, Self::StartTickTimer(_, _)),
@@ -96,6 +365,36 @@ impl PartialEq for Event {
             Self::CancelTimer(_) => matches!(other, Self::CancelTimer(_)),
             Self::BackplateConnected => matches!(other, Self::BackplateConnected),
             Self::BackplateDisconnected => matches!(other, Self::BackplateDisconnected),
+            Self::BackplateOffline => matches!(other, Self::BackplateOffline),
+            Self::BackplateCapabilities(_) => matches!(other, Self::BackplateCapabilities(_)),
+            Self::BackplatePower(_) => matches!(other, Self::BackplatePower(_)),
+            Self::BackplateLinkStats(_) => matches!(other, Self::BackplateLinkStats(_)),
+            Self::HvacFault(_) => matches!(other, Self::HvacFault(_)),
+            Self::Heartbeat => matches!(other, Self::Heartbeat),
+            Self::HaConnected => matches!(other, Self::HaConnected),
+            Self::HaDisconnected => matches!(other, Self::HaDisconnected),
+            Self::SetBrightness(_) => matches!(other, Self::SetBrightness(_)),
+            Self::SetDefaultBrightness(_) => matches!(other, Self::SetDefaultBrightness(_)),
+            Self::EditSchedule(_) => matches!(other, Self::EditSchedule(_)),
+            Self::ScheduleChanged(_) => matches!(other, Self::ScheduleChanged(_)),
+            Self::NextSetPoint(_) => matches!(other, Self::NextSetPoint(_)),
+            Self::ReloadConfig => matches!(other, Self::ReloadConfig),
+            Self::ConfigReloaded(_) => matches!(other, Self::ConfigReloaded(_)),
+            Self::SetTheme(_) => matches!(other, Self::SetTheme(_)),
+            Self::StartAutotune => matches!(other, Self::StartAutotune),
+            Self::StopAutotune => matches!(other, Self::StopAutotune),
+            Self::Autotune(_) => matches!(other, Self::Autotune(_)),
+            Self::Tuning(_) => matches!(other, Self::Tuning(_)),
+            Self::HeatingRate(_) => matches!(other, Self::HeatingRate(_)),
+            Self::SetDeadband(_) => matches!(other, Self::SetDeadband(_)),
+            Self::Deadband(_) => matches!(other, Self::Deadband(_)),
+            Self::HvacSwitched(_) => matches!(other, Self::HvacSwitched(_)),
+            Self::ActionLog(_) => matches!(other, Self::ActionLog(_)),
+            Self::Adjustments(_) => matches!(other, Self::Adjustments(_)),
+            Self::ScheduleSuggestions(_) => matches!(other, Self::ScheduleSuggestions(_)),
+            Self::AcceptSuggestion(_) => matches!(other, Self::AcceptSuggestion(_)),
+            Self::DismissSuggestion(_) => matches!(other, Self::DismissSuggestion(_)),
+            Self::NightTheme(_) => matches!(other, Self::NightTheme(_)),
         }
     }
 
@@ -104,12 +403,90 @@ impl PartialEq for Event {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum SendError {
+    /// The event loop has stopped receiving, producers should wind down
+    Disconnected,
+    #[cfg(feature = "simulate")]
+    Failed(String)
+}
+
+impl fmt::Display for SendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Disconnected => write!(f, "Event loop disconnected"),
+            #[cfg(feature = "simulate")]
+            Self::Failed(msg) => write!(f, "Send event failed: {msg}")
+        }
+    }
+}
+
+impl std::error::Error for SendError { }
+
 pub trait EventSender {
-    fn send_event(&self, event: Event) -> Result<()>;
+    fn send_event(&self, event: Event) -> Result<(), SendError>;
 }
 
 pub trait EventHandler {
     fn handle_event(&mut self, event: &Event) -> Result<()>;
+
+    /// Topics of the events to handle, [`Topic::System`] events are always
+    /// handled. Defaults to every topic.
+    fn topics(&self) -> &'static [Topic] {
+        Topic::ALL
+    }
+}
+
+/// Optional subsystems, such as the screen when running headless
+impl<H: EventHandler> EventHandler for Option<H> {
+    fn handle_event(&mut self, event: &Event) -> Result<()> {
+        if let Some(handler) = self {
+            handler.handle_event(event)?;
+        }
+        Ok(())
+    }
+
+    fn topics(&self) -> &'static [Topic] {
+        match self {
+            Some(handler) => handler.topics(),
+            None => &[]
+        }
+    }
+}
+
+/// Times each event handler call and logs the ones that take longer than
+/// the threshold. A slow handler delays drawing the next frame, which is
+/// felt as a laggy dial.
+pub struct SlowHandlerMonitor {
+    threshold: Duration,
+    slow_count: u64
+}
+
+impl SlowHandlerMonitor {
+    pub fn new(threshold: Duration) -> Self {
+        Self { threshold, slow_count: 0 }
+    }
+
+    pub fn handle_event(
+        &mut self,
+        name: &str,
+        handler: &mut dyn EventHandler,
+        event: &Event
+    ) -> Result<()> {
+        let start = Instant::now();
+        handler.handle_event(event)?;
+        let elapsed = start.elapsed();
+
+        if elapsed > self.threshold {
+            self.slow_count += 1;
+            warn!(
+                "Slow handler {name} took {elapsed:?} for {event:?} ({} slow calls)",
+                self.slow_count
+            );
+        }
+
+        Ok(())
+    }
 }
 
 pub trait EventSource<S: EventSender> {
@@ -118,42 +495,44 @@ pub trait EventSource<S: EventSender> {
     fn event_sender(&self) -> S;
 }
 
+#[cfg(any(test, feature = "device"))]
 pub struct DefaultEventSource {
-    sender: Sender<Event>,
-    receiver: Receiver<Event>
+    sender: QueueSender,
+    receiver: QueueReceiver
 }
 
+#[cfg(any(test, feature = "device"))]
 impl DefaultEventSource {
+    #[cfg(test)]
     pub fn new() -> Self {
-        let (sender, receiver) = channel();
+        let (sender, receiver) = event_queue(None);
+        Self { sender, receiver }
+    }
+
+    /// Hold at most `capacity` events, dropping the oldest when a producer
+    /// gets ahead of the event loop
+    #[cfg(feature = "device")]
+    pub fn bounded(capacity: usize) -> Self {
+        let (sender, receiver) = event_queue(Some(capacity));
         Self { sender, receiver }
     }
 }
 
-impl EventSource<Sender<Event>> for DefaultEventSource {
+#[cfg(any(test, feature = "device"))]
+impl EventSource<QueueSender> for DefaultEventSource {
     fn wait_event(&mut self) -> Result<Event> {
-        Ok(self.receiver.recv()?)
+        Ok(self.receiver.recv())
     }
 
     fn poll_event(&mut self) -> Result<Option<Event>> {
-        match self.receiver.try_recv() {
-            Err(TryRecvError::Empty) => Ok(None),
-            Err(err) => Err(err.into()),
-            Ok(event) => Ok(Some(event))
-        }
+        Ok(self.receiver.try_recv())
     }
 
-    fn event_sender(&self) -> Sender<Event> {
+    fn event_sender(&self) -> QueueSender {
         self.sender.clone()
     }
 }
 
-impl EventSender for Sender<Event> {
-    fn send_event(&self, event: Event) -> Result<()> {
-        Ok(self.send(event)?)
-    }
-}
-
 /// Emit a maximum number of events over a specified period of time, dropping
 /// events as necessary.
 pub struct ThrottledEventSender<S> {
@@ -173,7 +552,7 @@ impl<S: EventSender> ThrottledEventSender<S> {
 }
 
 impl<S: EventSender> EventSender for ThrottledEventSender<S> {
-    fn send_event(&self, event: Event) -> Result<()> {
+    fn send_event(&self, event: Event) -> Result<(), SendError> {
         if self.throttle.borrow_mut().accept().is_ok() {
             self.event_sender.send_event(event)?;
         }
@@ -182,6 +561,97 @@ impl<S: EventSender> EventSender for ThrottledEventSender<S> {
     }
 }
 
+/// Sends dial events at most once every interval, movement in between is
+/// summed and sent at the next interval, so a fast spin keeps all of its
+/// movement. Other events are sent right away.
+#[cfg(feature = "device")]
+pub struct CoalescingEventSender<S> {
+    event_sender: S,
+    dial_sender: std::sync::mpsc::Sender<i32>
+}
+
+#[cfg(feature = "device")]
+impl<S: EventSender + Clone + Send + 'static> CoalescingEventSender<S> {
+    /// Send dial events at most once every `interval_ms`
+    pub fn new(event_sender: S, interval_ms: u64) -> Self {
+        use std::sync::mpsc::{RecvTimeoutError, channel};
+
+        let (dial_sender, receiver) = channel();
+        let mut coalescer = DialCoalescer::new(Duration::from_millis(interval_ms));
+        let sender = event_sender.clone();
+
+        // exits once this sender is dropped, or the event loop is gone
+        std::thread::spawn(move || loop {
+            let delta = match coalescer.next_flush() {
+                Some(at) => receiver.recv_timeout(at.saturating_duration_since(Instant::now())),
+                None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected)
+            };
+
+            let delta = match delta {
+                Ok(delta) => coalescer.add(delta, Instant::now()),
+                Err(RecvTimeoutError::Timeout) => coalescer.flush(Instant::now()),
+                Err(RecvTimeoutError::Disconnected) => break
+            };
+
+            if let Some(delta) = delta && sender.send_event(Event::Dial(delta)).is_err() {
+                break;
+            }
+        });
+
+        Self { event_sender, dial_sender }
+    }
+}
+
+#[cfg(feature = "device")]
+impl<S: EventSender> EventSender for CoalescingEventSender<S> {
+    fn send_event(&self, event: Event) -> Result<(), SendError> {
+        match event {
+            Event::Dial(delta) => {
+                // flush thread only stops when the event loop is gone
+                self.dial_sender.send(delta).map_err(|_| SendError::Disconnected)
+            }
+            event => self.event_sender.send_event(event)
+        }
+    }
+}
+
+/// Sums dial movement between intervals, see [`CoalescingEventSender`]
+#[cfg(feature = "device")]
+struct DialCoalescer {
+    interval: Duration,
+    pending: i32,
+    /// Movement can be sent from this time
+    next_slot: Option<Instant>
+}
+
+#[cfg(feature = "device")]
+impl DialCoalescer {
+    fn new(interval: Duration) -> Self {
+        Self { interval, pending: 0, next_slot: None }
+    }
+
+    /// Add movement, returns the movement to send when a slot is free
+    fn add(&mut self, delta: i32, now: Instant) -> Option<i32> {
+        self.pending += delta;
+        self.flush(now)
+    }
+
+    /// Movement held back to send when its slot comes up
+    fn flush(&mut self, now: Instant) -> Option<i32> {
+        if self.pending == 0 || self.next_slot.is_some_and(|slot| now < slot) {
+            return None;
+        }
+
+        self.next_slot = Some(now + self.interval);
+        Some(std::mem::take(&mut self.pending))
+    }
+
+    /// Time to send held back movement, `None` when there is none
+    fn next_flush(&self) -> Option<Instant> {
+        self.next_slot.filter(|_| self.pending != 0)
+    }
+}
+
 /// Emit the last event that occurred within a debounce interval
 pub struct TrailingEventSender {
     event_debounce: EventDebouncer<Event>,
@@ -197,7 +667,9 @@ impl TrailingEventSender {
         let pending_clone = pending.clone();
 
         let event_debounce = EventDebouncer::new(delay, move |e: Event| {
-            event_sender.send_event(e).unwrap();
+            if let Err(err) = event_sender.send_event(e) {
+                debug!("Dropped trailing event: {err}");
+            }
             pending.store(false, Ordering::Relaxed);
         });
 
@@ -213,74 +685,145 @@ impl TrailingEventSender {
 }
 
 impl EventSender for TrailingEventSender {
-    fn send_event(&self, event: Event) -> Result<()> {
+    fn send_event(&self, event: Event) -> Result<(), SendError> {
         self.pending.store(true, Ordering::Relaxed);
         self.event_debounce.put(event);
         Ok(())
     }
 }
 
-/// Smooth out dial events by accumulating the delta values and emitting
-/// the accumulated value at the specified interval
-pub struct SmoothEventSender<S> {
-    event_sender: S,
-    smooting: RefCell<Smoothing>
+/// Time without dial movement before speed drops back to zero
+const DIAL_IDLE: Duration = Duration::from_millis(150);
+
+/// Tracks how fast the dial is turning, in dial units per second
+pub struct DialVelocity {
+    speed: f32,
+    last_update: Instant
 }
 
-impl<S: EventSender> SmoothEventSender<S> {
-    pub fn new(event_sender: S, tick_ms: u64) -> Self {
-        let tick_rate = Duration::from_millis(tick_ms);
+impl DialVelocity {
+    pub fn new() -> Self {
         Self {
-            event_sender,
-            smooting: RefCell::new(Smoothing::new(tick_rate))
+            speed: 0.0,
+            last_update: Instant::now()
         }
     }
-}
 
-impl<S: EventSender> EventSender for SmoothEventSender<S> {
-    fn send_event(&self, event: Event) -> Result<()> {
-        match event {
-            Event::Dial(val) => {
-                if let Some(val) = self.smooting.borrow_mut().tick(val) {
-                    self.event_sender.send_event(Event::Dial(val))?;
-                }
-            },
-            event => {
-                self.event_sender.send_event(event)?;
-            }
-        }
+    pub fn update(&mut self, delta: i32) {
+        self.update_at(delta, Instant::now());
+    }
 
-        Ok(())
+    fn update_at(&mut self, delta: i32, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_update);
+        self.last_update = now;
+
+        // first movement after idle starts from rest
+        let (previous, elapsed) = if elapsed > DIAL_IDLE {
+            (0.0, DIAL_IDLE)
+        } else {
+            (self.speed, elapsed.max(Duration::from_millis(1)))
+        };
+
+        // ease towards new speed, dial events don't arrive at an even rate
+        let speed = delta.abs() as f32 / elapsed.as_secs_f32();
+        self.speed = previous * 0.7 + speed * 0.3;
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed_at(Instant::now())
+    }
+
+    fn speed_at(&self, now: Instant) -> f32 {
+        if now.saturating_duration_since(self.last_update) > DIAL_IDLE {
+            0.0
+        } else {
+            self.speed
+        }
     }
 }
 
-struct Smoothing {
-    last_tick: Instant,
-    tick_rate: Duration,
-    pending_delta: i32
+/// Dial speed where acceleration starts, slower turns move 0.01° per unit
+const ACCEL_START_SPEED: f32 = 200.0;
+/// Largest multiplier applied to dial deltas
+const MAX_ACCEL: f32 = 6.0;
+
+/// Scales dial deltas up with dial speed, so a fast turn covers a large
+/// change quickly while a slow turn keeps fine control
+pub struct DialAccelerator {
+    velocity: DialVelocity
 }
 
-impl Smoothing {
-    fn new(tick_rate: Duration) -> Self {
+impl DialAccelerator {
+    pub fn new() -> Self {
         Self {
-            last_tick: Instant::now(),
-            tick_rate,
-            pending_delta: 0
+            velocity: DialVelocity::new()
         }
     }
 
-    fn tick(&mut self, value: i32) -> Option<i32> {
-        self.pending_delta = self.pending_delta + value;
+    /// Dial delta multiplied in proportion to the current dial speed
+    pub fn apply(&mut self, delta: i32) -> f32 {
+        self.apply_at(delta, Instant::now())
+    }
 
-        let now = Instant::now();
-        if now >= self.last_tick + self.tick_rate {
-            self.last_tick = now;
-            let delta = self.pending_delta;
-            self.pending_delta = 0;
+    fn apply_at(&mut self, delta: i32, now: Instant) -> f32 {
+        self.velocity.update_at(delta, now);
+        let accel = (self.velocity.speed_at(now) / ACCEL_START_SPEED).clamp(1.0, MAX_ACCEL);
+        delta as f32 * accel
+    }
+}
 
-            return Some(delta);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dial_accelerates_with_speed() {
+        let mut accel = DialAccelerator::new();
+        let start = Instant::now() + DIAL_IDLE * 2;
+
+        // a slow turn keeps fine control
+        assert_eq!(accel.apply_at(1, start), 1.0);
+        assert_eq!(accel.apply_at(-1, start + Duration::from_millis(100)), -1.0);
+
+        // speeding up multiplies the delta, up to the limit
+        let mut now = start + Duration::from_millis(100);
+        let mut last = 0.0;
+        for _ in 0..5 {
+            now += Duration::from_millis(5);
+            let scaled = accel.apply_at(5, now);
+            assert!(scaled > last);
+            last = scaled;
         }
+        for _ in 0..20 {
+            now += Duration::from_millis(5);
+            last = accel.apply_at(10, now);
+        }
+        assert_eq!(last, 10.0 * MAX_ACCEL);
+
+        // starts from rest after the dial stops
+        assert_eq!(accel.apply_at(1, now + DIAL_IDLE * 2), 1.0);
+    }
 
-        None
+    #[cfg(feature = "device")]
+    #[test]
+    fn coalesces_dial_between_intervals() {
+        let interval = Duration::from_millis(20);
+        let mut coalescer = DialCoalescer::new(interval);
+        let start = Instant::now();
+
+        assert_eq!(coalescer.add(1, start), Some(1));
+        assert_eq!(coalescer.add(2, start), None);
+        assert_eq!(coalescer.add(3, start + Duration::from_millis(5)), None);
+        assert_eq!(coalescer.next_flush(), Some(start + interval));
+
+        // held back movement is sent at the next slot, without more movement
+        assert_eq!(coalescer.flush(start + Duration::from_millis(10)), None);
+        assert_eq!(coalescer.flush(start + interval), Some(5));
+        assert_eq!(coalescer.next_flush(), None);
+
+        // movement cancelling out isn't sent
+        assert_eq!(coalescer.add(1, start + Duration::from_millis(25)), None);
+        assert_eq!(coalescer.add(-1, start + Duration::from_millis(30)), None);
+        assert_eq!(coalescer.flush(start + interval * 2), None);
     }
 }
@@ -0,0 +1,124 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::time::Duration;
+
+use anyhow::Result;
+
+use super::{Event, EventHandler, SlowHandlerMonitor, Topic};
+
+/// Handler owned by the bus, with the topics it subscribed to
+struct Subscriber {
+    name: &'static str,
+    topics: &'static [Topic],
+    handler: Box<dyn EventHandler>
+}
+
+/// Passes each event to the handlers subscribed to its topic, in the order
+/// they subscribed. Subsystems only used by the event loop are owned by the
+/// bus, so adding one doesn't touch the loop.
+pub struct EventBus {
+    subscribers: Vec<Subscriber>,
+    monitor: SlowHandlerMonitor
+}
+
+impl EventBus {
+    /// Handler calls taking longer than `slow_threshold` are logged
+    pub fn new(slow_threshold: Duration) -> Self {
+        Self {
+            subscribers: Vec::new(),
+            monitor: SlowHandlerMonitor::new(slow_threshold)
+        }
+    }
+
+    /// Add a handler, passed events of the topics from [`EventHandler::topics`]
+    pub fn subscribe<H: EventHandler + 'static>(&mut self, name: &'static str, handler: H) {
+        self.subscribers.push(Subscriber {
+            name,
+            topics: handler.topics(),
+            handler: Box::new(handler)
+        });
+    }
+
+    /// Pass the event to the `borrowed` handlers, then to each subscriber.
+    /// Borrowed handlers are the ones the event loop also uses between
+    /// events, such as the screens it draws.
+    pub fn dispatch(&mut self, event: &Event, borrowed: &mut [(&str, &mut dyn EventHandler)]) -> Result<()> {
+        let topic = event.topic();
+
+        for (name, handler) in borrowed.iter_mut() {
+            if is_subscribed(handler.topics(), topic) {
+                self.monitor.handle_event(name, *handler, event)?;
+            }
+        }
+
+        for subscriber in &mut self.subscribers {
+            if is_subscribed(subscriber.topics, topic) {
+                self.monitor.handle_event(subscriber.name, subscriber.handler.as_mut(), event)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn is_subscribed(topics: &[Topic], topic: Topic) -> bool {
+    topic == Topic::System || topics.contains(&topic)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+
+    /// Records the events it's passed
+    struct Recorder {
+        topics: &'static [Topic],
+        events: Rc<RefCell<Vec<Event>>>
+    }
+
+    impl EventHandler for Recorder {
+        fn handle_event(&mut self, event: &Event) -> Result<()> {
+            self.events.borrow_mut().push(event.clone());
+            Ok(())
+        }
+
+        fn topics(&self) -> &'static [Topic] {
+            self.topics
+        }
+    }
+
+    #[test]
+    fn passes_subscribed_topics() -> Result<()> {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut bus = EventBus::new(Duration::from_secs(1));
+        bus.subscribe("input", Recorder { topics: &[Topic::Input], events: events.clone() });
+
+        let mut all = Recorder { topics: Topic::ALL, events: Rc::new(RefCell::new(Vec::new())) };
+
+        for event in [Event::Dial(1), Event::BackplateConnected, Event::GetState] {
+            bus.dispatch(&event, &mut [("all", &mut all)])?;
+        }
+
+        assert!(matches!(events.borrow().as_slice(), [Event::Dial(1), Event::GetState]));
+        assert_eq!(all.events.borrow().len(), 3);
+
+        Ok(())
+    }
+}
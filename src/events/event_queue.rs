@@ -0,0 +1,150 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError}
+};
+
+use log::warn;
+
+use super::{Event, EventSender, SendError};
+
+/// Create an event queue, optionally bounded to `capacity` events. When a
+/// bounded queue is full, the oldest event is dropped to make room.
+pub fn event_queue(capacity: Option<usize>) -> (QueueSender, QueueReceiver) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(QueueState {
+            events: VecDeque::new(),
+            capacity,
+            receiver_alive: true,
+            dropped: 0
+        }),
+        ready: Condvar::new()
+    });
+
+    (QueueSender { shared: shared.clone() }, QueueReceiver { shared })
+}
+
+struct Shared {
+    state: Mutex<QueueState>,
+    ready: Condvar
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, QueueState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+struct QueueState {
+    events: VecDeque<Event>,
+    capacity: Option<usize>,
+    receiver_alive: bool,
+    /// Events dropped from a full queue since startup
+    dropped: u64
+}
+
+#[derive(Clone)]
+pub struct QueueSender {
+    shared: Arc<Shared>
+}
+
+impl EventSender for QueueSender {
+    fn send_event(&self, event: Event) -> Result<(), SendError> {
+        let mut state = self.shared.lock();
+        if !state.receiver_alive {
+            return Err(SendError::Disconnected);
+        }
+
+        if state.capacity.is_some_and(|capacity| state.events.len() >= capacity) {
+            let oldest = state.events.pop_front();
+            state.dropped += 1;
+
+            // log less often as drops pile up, to not flood the log
+            if state.dropped.is_power_of_two() {
+                warn!("Event queue full, dropped {oldest:?} ({} dropped)", state.dropped);
+            }
+        }
+
+        state.events.push_back(event);
+        drop(state);
+
+        self.shared.ready.notify_one();
+        Ok(())
+    }
+}
+
+/// Receiving end of the queue, senders get [`SendError::Disconnected`]
+/// once this is dropped
+pub struct QueueReceiver {
+    shared: Arc<Shared>
+}
+
+impl QueueReceiver {
+    /// Wait for the next event
+    pub fn recv(&self) -> Event {
+        let mut state = self.shared.lock();
+        loop {
+            if let Some(event) = state.events.pop_front() {
+                return event;
+            }
+
+            state = self.shared.ready.wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    pub fn try_recv(&self) -> Option<Event> {
+        self.shared.lock().events.pop_front()
+    }
+}
+
+impl Drop for QueueReceiver {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.receiver_alive = false;
+        state.events.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_oldest_when_full() {
+        let (sender, receiver) = event_queue(Some(2));
+
+        for val in 1..=3 {
+            sender.send_event(Event::Dial(val)).unwrap();
+        }
+
+        assert!(matches!(receiver.try_recv(), Some(Event::Dial(2))));
+        assert!(matches!(receiver.try_recv(), Some(Event::Dial(3))));
+        assert!(receiver.try_recv().is_none());
+    }
+
+    #[test]
+    fn disconnected_after_receiver_dropped() {
+        let (sender, receiver) = event_queue(None);
+        drop(receiver);
+
+        assert_eq!(sender.send_event(Event::Quit), Err(SendError::Disconnected));
+    }
+}
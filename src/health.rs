@@ -0,0 +1,187 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use anyhow::Result;
+use chrono::{Datelike, Local};
+
+use crate::{events::{Event, EventHandler, EventSender, Topic}, supervisor::Subsystem};
+
+#[cfg(feature = "device")]
+use crate::input_events::InputId;
+
+/// Any year before this means the clock was never set, schedules
+/// would run at the wrong time
+const MIN_CLOCK_YEAR: i32 = 2025;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Problem {
+    BackplateDisconnected,
+    /// Backplate stopped sending, until readings arrive again
+    BackplateOffline,
+    #[cfg(feature = "device")]
+    InputDeviceMissing(InputId),
+    ClockUnsynced,
+    HvacFault,
+    SubsystemFailing(Subsystem),
+}
+
+impl Problem {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::BackplateDisconnected => "Backplate disconnected",
+            Self::BackplateOffline => "Backplate not responding",
+            #[cfg(feature = "device")]
+            Self::InputDeviceMissing(InputId::Dial) => "Dial input missing",
+            #[cfg(feature = "device")]
+            Self::InputDeviceMissing(InputId::Button) => "Button input missing",
+            Self::ClockUnsynced => "Clock not set",
+            Self::HvacFault => "Backplate failed to switch HVAC wire",
+            Self::SubsystemFailing(Subsystem::Backplate) => "Backplate thread failing",
+            Self::SubsystemFailing(Subsystem::HomeAssistant) => "Home Assistant server failing",
+            Self::SubsystemFailing(Subsystem::Schedule) => "Schedule thread failing",
+            Self::SubsystemFailing(Subsystem::Timers) => "Timer threads failing",
+            #[cfg(feature = "device")]
+            Self::SubsystemFailing(Subsystem::Sound) => "Sound thread failing",
+        }
+    }
+}
+
+/// Tracks degraded subsystems, and sends `Event::Problems` when the
+/// list of problems changes
+pub struct HealthMonitor<S> {
+    event_sender: S,
+    problems: Vec<Problem>,
+}
+
+impl<S: EventSender> HealthMonitor<S> {
+    pub fn new(event_sender: S) -> Self {
+        Self {
+            event_sender,
+            // backplate is down until the serial connection is established
+            problems: vec![Problem::BackplateDisconnected],
+        }
+    }
+
+    fn set_problem(&mut self, problem: Problem, active: bool) -> bool {
+        let index = self.problems.iter().position(|p| *p == problem);
+
+        match (index, active) {
+            (None, true) => {
+                self.problems.push(problem);
+                true
+            }
+            (Some(index), false) => {
+                self.problems.remove(index);
+                true
+            }
+            _ => false
+        }
+    }
+}
+
+impl<S: EventSender> EventHandler for HealthMonitor<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<()> {
+        let mut changed = match event {
+            Event::BackplateConnected => {
+                self.set_problem(Problem::BackplateDisconnected, false)
+            }
+            Event::BackplateDisconnected => {
+                self.set_problem(Problem::BackplateDisconnected, true)
+            }
+            Event::BackplateOffline => {
+                self.set_problem(Problem::BackplateOffline, true)
+            }
+            Event::BackplateTemp(_) => {
+                self.set_problem(Problem::BackplateOffline, false)
+            }
+            #[cfg(feature = "device")]
+            Event::InputDeviceMissing(id) => {
+                self.set_problem(Problem::InputDeviceMissing(*id), true)
+            }
+            #[cfg(feature = "device")]
+            Event::InputDeviceConnected(id) => {
+                self.set_problem(Problem::InputDeviceMissing(*id), false)
+            }
+            Event::HvacFault(wires) => {
+                self.set_problem(Problem::HvacFault, !wires.is_empty())
+            }
+            Event::SubsystemFailing(subsystem, failing) => {
+                self.set_problem(Problem::SubsystemFailing(*subsystem), *failing)
+            }
+            _ => false
+        };
+
+        // clock can be set at any time by ntp, cheap enough to check every event
+        let unsynced = Local::now().year() < MIN_CLOCK_YEAR;
+        changed |= self.set_problem(Problem::ClockUnsynced, unsynced);
+
+        if changed || matches!(event, Event::GetState) {
+            self.event_sender.send_event(Event::Problems(self.problems.clone()))?;
+        }
+
+        Ok(())
+    }
+
+    fn topics(&self) -> &'static [Topic] {
+        &[Topic::Input, Topic::State, Topic::Backplate]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::WireId, events::{DefaultEventSource, EventSource}};
+
+    /// Last problem list sent since the last call
+    fn last_problems(event_source: &mut DefaultEventSource) -> Result<Option<Vec<Problem>>> {
+        let mut result = None;
+        while let Some(event) = event_source.poll_event()? {
+            if let Event::Problems(problems) = event {
+                result = Some(problems);
+            }
+        }
+
+        Ok(result)
+    }
+
+    #[test]
+    fn problems_change() -> Result<()> {
+        let mut events = DefaultEventSource::new();
+        let mut monitor = HealthMonitor::new(events.event_sender());
+
+        monitor.handle_event(&Event::BackplateConnected)?;
+        assert_eq!(last_problems(&mut events)?, Some(vec![]));
+
+        monitor.handle_event(&Event::HvacFault(vec![WireId::W1]))?;
+        monitor.handle_event(&Event::BackplateDisconnected)?;
+        assert_eq!(
+            last_problems(&mut events)?,
+            Some(vec![Problem::HvacFault, Problem::BackplateDisconnected])
+        );
+
+        // unchanged problems aren't resent
+        monitor.handle_event(&Event::BackplateDisconnected)?;
+        assert_eq!(last_problems(&mut events)?, None);
+
+        // wire switched again
+        monitor.handle_event(&Event::HvacFault(vec![]))?;
+        assert_eq!(last_problems(&mut events)?, Some(vec![Problem::BackplateDisconnected]));
+
+        Ok(())
+    }
+}
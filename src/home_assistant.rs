@@ -16,52 +16,165 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::thread;
+use std::{cell::RefCell, collections::HashMap, net::SocketAddr, sync::{Arc, Mutex}};
 
-use anyhow::Result;
+use anyhow::{Result, anyhow};
+use chrono::Local;
+use log::{debug, error, info, warn};
 use esphome_api::{
+    entity::{
+        BinarySensorEntity, ButtonEntity, ClimateEntity, CommandRouter, EntityKey, EntityRegistry, NumberEntity,
+        SelectEntity, SensorEntity, ServiceEntity, SwitchEntity, TextSensorEntity
+    },
     proto::*,
     server::{
-        DefaultHandler, MessageSender, MessageStreamProvider,
-        MessageThreadError, RequestHandler, ResponseStatus, start_server
+        ClientInfo, ClientSender, ConnectionObserver, DefaultHandler, EncryptionKey, MessageSender,
+        MessageStreamProvider, MessageThreadError, RequestHandler, ResponseStatus, run_server
     }
 };
 
 use crate::{
-    config::HomeAssistantConfig,
+    action_log::ActionEntry,
+    alerts::Alert,
+    autotune::AutotuneStatus,
+    backplate::{BackplateCapabilities, LinkStats, PowerReading},
+    backup::Backup,
+    config::{BackplateConfig, Config, HomeAssistantConfig, OccupancyConfig, OutdoorConfig, TempSourceConfig, WireConfig, WireId},
     events::{Event, EventHandler, EventSender},
-    state::ThermostatState
+    health::Problem,
+    occupancy::Occupancy,
+    recovery::HeatingRate,
+    schedule::ScheduleEdit,
+    state::{ClimateCommand, HvacFanMode, HvacMode, MAX_DEADBAND, MIN_DEADBAND, ThermostatState},
+    supervisor::{Subsystem, supervise},
+    temperature::{TempUnit, Temperature}
 };
 
+/// Max value of the backlight sysfs brightness
+const MAX_BRIGHTNESS: f32 = 120.0;
+
+/// Home Assistant event fired when heating or cooling starts or stops,
+/// shown in the logbook and usable as an automation trigger
+const HVAC_SWITCHED_EVENT: &str = "esphome.retherm_hvac_switched";
+
 pub struct HomeAssistant {
-    message_sender: MessageSender
+    message_sender: MessageSender,
+    clients: HaClients,
+    entities: HaEntities,
+    brightness: u32,
+    /// Configured backlight brightness, changed from HA until the config
+    /// is reloaded
+    default_brightness: u32,
+    muted: bool,
+    /// Last backplate power reading, re-sent when a client subscribes
+    power: Option<PowerReading>,
+    /// Last ambient light reading, re-sent when a client subscribes
+    light: Option<u16>,
+    /// Last backplate humidity reading, re-sent when a client subscribes
+    humidity: Option<f32>,
+    /// Last backplate link counters, re-sent when a client subscribes
+    link_stats: Option<LinkStats>,
+    /// Learned heating rate, re-sent when a client subscribes
+    heating_rate: Option<HeatingRate>,
+    /// Latest hvac switch, re-sent when a client subscribes
+    last_transition: Option<ActionEntry>,
+    /// Unit of the setpoint in the last transition summary
+    temp_unit: TempUnit,
+    /// Backplate details, re-sent when a client subscribes and shared with
+    /// the request handler for the device info response
+    capabilities: Arc<Mutex<Option<BackplateCapabilities>>>,
+    /// Key of the encrypted listener, updated when the config is reloaded
+    encryption_key: Option<EncryptionKey>,
+    /// Name of the theme in use, re-sent when a client subscribes
+    theme_name: String,
+    /// Last autotune progress, re-sent when a client subscribes
+    autotune: AutotuneStatus,
+    /// Latest state of each entity sent while no client was connected,
+    /// by entity key, flushed when a client subscribes
+    pending: RefCell<HashMap<u32, ProtoMessage>>
 }
 
 impl HomeAssistant {
-    pub fn new() -> Self {
+    /// The theme select entity lists `themes`, with `theme_name` selected.
+    /// Service mode switches are only listed when enabled in the backplate config.
+    pub fn new(themes: &[String], theme_name: &str, config: &Config) -> Self {
         Self {
-            message_sender: MessageSender::new()
+            message_sender: MessageSender::new(),
+            clients: HaClients::default(),
+            entities: HaEntities::new(themes, &config.backplate),
+            brightness: 0,
+            default_brightness: config.backlight.brightness,
+            muted: false,
+            power: None,
+            light: None,
+            humidity: None,
+            link_stats: None,
+            heating_rate: None,
+            last_transition: None,
+            temp_unit: config.temp_unit,
+            capabilities: Arc::default(),
+            encryption_key: None,
+            theme_name: theme_name.to_string(),
+            autotune: AutotuneStatus::Idle,
+            pending: RefCell::default()
+        }
+    }
+
+    /// Shared list of connected clients, updated by the listener thread
+    pub fn clients(&self) -> HaClients {
+        self.clients.clone()
+    }
+
+    /// Key used by the encrypted listener, replaced on config reload
+    pub fn set_encryption_key(&mut self, key: EncryptionKey) {
+        self.encryption_key = Some(key);
+    }
+
+    fn reload_encryption_key(&self, key: Option<&String>) {
+        match (&self.encryption_key, key) {
+            (Some(current), Some(key)) => match current.set(key) {
+                Ok(true) => info!("HA encryption key changed, clients must reconnect with new key"),
+                Ok(false) => { }
+                Err(e) => error!("Invalid HA encryption key, keeping previous key: {e}")
+            }
+            (None, None) => { }
+            _ => warn!("Enabling or disabling HA encryption requires a restart")
         }
     }
 
-    pub fn start_listener<S>(
+    #[allow(clippy::too_many_arguments)]
+    pub fn start_listener(
         &self,
         config: &HomeAssistantConfig,
-        stream_provider: impl MessageStreamProvider<S> + Send + 'static,
-        event_sender: impl EventSender + Send + 'static
-    )
-        where S: MessageStream + Send + 'static
-    {
+        temp_source: &TempSourceConfig,
+        outdoor: &OutdoorConfig,
+        occupancy: &OccupancyConfig,
+        stream_provider: impl MessageStreamProvider + Send + Sync + 'static,
+        backup: Backup,
+        event_sender: impl EventSender + Clone + Send + Sync + 'static
+    ) {
         let addr = config.listen_addr.clone();
 
-        let connection_observer = self.message_sender.clone();
+        let stream_provider = Arc::new(stream_provider);
+
+        let connection_observer = Arc::new(HaConnectionObserver {
+            message_sender: self.message_sender.clone(),
+            clients: self.clients.clone(),
+            event_sender: event_sender.clone()
+        });
 
         let delegate = HvacRequestHandler::new(
-            thermostat_entity(config.get_object_id()),
-            event_sender
+            config.get_object_id(),
+            self.entities.clone(),
+            self.capabilities.clone(),
+            temp_source,
+            outdoor,
+            occupancy,
+            backup,
+            event_sender.clone()
         );
 
-        let handler = DefaultHandler {
+        let handler = Arc::new(DefaultHandler {
             delegate: delegate,
             server_info: config.server_info.clone(),
             node_name: config.get_node_name(),
@@ -69,93 +182,702 @@ impl HomeAssistant {
             manufacturer: config.manufacturer.clone(),
             model: config.model.clone(),
             mac_address: config.get_mac_address()
-        };
+        });
+
+        // Connection errors are handled by the server, it only returns when
+        // the listen address can't be bound, e.g. network not up yet
+        supervise(Subsystem::HomeAssistant, event_sender, move || {
+            run_server(&addr, stream_provider.clone(), connection_observer.clone(), handler.clone())?;
+            Ok(())
+        });
+    }
+}
 
-        thread::spawn(move || {
-            loop {
-                let result = start_server(
-                    &addr,
-                    &stream_provider,
-                    &connection_observer,
-                    &handler
-                );
-
-                // Let home assistant server thread try and recover
-                // Instead of panicing and crashing
-                if let Err(e) = result {
-                    log::error!("Restarting HA thread: {e}");
+impl HomeAssistant {
+    fn send_message(&self, message: ProtoMessage) -> Result<()> {
+        let state_key = message.state_key();
+        let result = self.message_sender.send_message(message.clone());
+        match result {
+            // Hold on to states until a client is back, other messages are dropped
+            Err(MessageThreadError::NonConnected) => {
+                if let Some(key) = state_key {
+                    self.pending.borrow_mut().insert(key, message);
                 }
             }
-        });
+            r => r?
+        }
+
+        Ok(())
+    }
+
+    /// Send states queued while no client was connected
+    fn flush_pending(&self) -> Result<()> {
+        let pending = self.pending.take();
+        for (_, message) in pending {
+            self.send_message(message)?;
+        }
+
+        Ok(())
+    }
+
+    fn send_brightness(&self) -> Result<()> {
+        self.send_message(self.entities.brightness.state(self.brightness as f32))?;
+        self.send_message(self.entities.default_brightness.state(self.default_brightness as f32))
+    }
+
+    fn send_muted(&self) -> Result<()> {
+        self.send_message(self.entities.mute.state(self.muted))
+    }
+
+    fn send_theme(&self) -> Result<()> {
+        self.send_message(self.entities.theme.state(self.theme_name.clone()))
+    }
+
+    fn fire_hvac_switched(&self, entry: &ActionEntry) -> Result<()> {
+        let data = [
+            ("from", format!("{:?}", entry.from)),
+            ("to", format!("{:?}", entry.to)),
+            ("cause", entry.cause.label().to_string())
+        ];
+
+        let request = HomeassistantActionRequest {
+            service: HVAC_SWITCHED_EVENT.to_string(),
+            data: data.into_iter()
+                .map(|(key, value)| HomeassistantServiceMap { key: key.to_string(), value })
+                .collect(),
+            is_event: true,
+            ..Default::default()
+        };
+
+        self.send_message(ProtoMessage::HomeassistantActionRequest(request))
+    }
+
+    fn send_autotune(&self) -> Result<()> {
+        self.send_message(self.entities.autotune.state(self.autotune.label()))
+    }
+
+    fn send_light(&self) -> Result<()> {
+        let Some(light) = self.light else {
+            return Ok(());
+        };
+
+        self.send_message(self.entities.illuminance.state(light as f32))
+    }
+
+    fn send_humidity(&self) -> Result<()> {
+        let Some(humidity) = self.humidity else {
+            return Ok(());
+        };
+
+        self.send_message(self.entities.humidity.state(humidity))
+    }
+
+    fn send_heating_rate(&self) -> Result<()> {
+        let Some(rate) = self.heating_rate else {
+            return Ok(());
+        };
+
+        self.send_message(self.entities.heating_rate.state(rate.degrees_per_hour))
+    }
+
+    fn send_last_transition(&self) -> Result<()> {
+        let Some(entry) = &self.last_transition else {
+            return Ok(());
+        };
+
+        self.send_message(self.entities.last_transition.state(entry.summary(self.temp_unit)))
+    }
+
+    fn send_power(&self) -> Result<()> {
+        let Some(power) = self.power else {
+            return Ok(());
+        };
+
+        self.send_message(self.entities.battery_volts.state(power.volts_bat))?;
+        self.send_message(self.entities.input_volts.state(power.volts_in))?;
+        self.send_message(self.entities.charging.state(power.charging))
+    }
+
+    fn send_link_stats(&self) -> Result<()> {
+        let Some(stats) = self.link_stats else {
+            return Ok(());
+        };
+
+        self.send_message(self.entities.crc_errors.state(stats.crc_errors as f32))?;
+        self.send_message(self.entities.resyncs.state(stats.resyncs as f32))?;
+        self.send_message(self.entities.discarded_bytes.state(stats.discarded_bytes as f32))?;
+        self.send_message(self.entities.buffer_overflows.state(stats.overflows as f32))
+    }
+
+    fn send_capabilities(&self) -> Result<()> {
+        let capabilities = self.capabilities.lock().unwrap();
+        let Some(capabilities) = capabilities.as_ref() else {
+            return Ok(());
+        };
+
+        self.send_message(self.entities.detected_wires.state(capabilities.wires.label()))?;
+        self.send_message(self.entities.backplate_serial.state(capabilities.serial_number.clone()))
     }
 }
 
 impl EventHandler for HomeAssistant {
     fn handle_event(&mut self, event: &Event) -> Result<()> {
-        if let Event::State(state) = event {
-            let message = ProtoMessage::ClimateStateResponse(state.into());
+        match event {
+            Event::State(state) => {
+                let entities = &self.entities;
+                self.send_message(entities.climate.state(state.into()))?;
+                self.send_message(entities.fan.state(state.fan_mode != HvacFanMode::Auto))?;
+                self.send_message(entities.away.state(state.away))?;
+                self.send_message(entities.boost.state(state.boost))?;
 
-            let result = self.message_sender.send_message(message);
-            match result {
-                // Ignoring non-connected errors
-                Err(MessageThreadError::NonConnected) => { },
-                r => r?
+                let holding = state.holding
+                    .map(|reason| reason.label().to_string())
+                    .unwrap_or_default();
+                self.send_message(entities.holding.state(holding))?;
+                self.send_message(entities.stage.state(state.stage as f32))?;
+                self.send_message(entities.demand.state(state.demand as f32))?;
+                self.send_message(entities.hvac_fault.state(state.fault))?;
+                self.send_message(entities.safety.state(state.safety))?;
             }
+            Event::GetState => {
+                // queued states first, so the states re-sent below win
+                self.flush_pending()?;
+                self.send_brightness()?;
+                self.send_muted()?;
+                self.send_theme()?;
+                self.send_autotune()?;
+                self.send_power()?;
+                self.send_light()?;
+                self.send_humidity()?;
+                self.send_link_stats()?;
+                self.send_heating_rate()?;
+                self.send_last_transition()?;
+                self.send_capabilities()?;
+            }
+            Event::NextSetPoint(next) => {
+                let time = next
+                    .map(|next| next.time.format("%a %H:%M").to_string())
+                    .unwrap_or_default();
+                self.send_message(self.entities.next_set_point.state(time))?;
+
+                let temp = match next {
+                    Some(next) => self.entities.next_set_point_temp.state(next.temp.celsius()),
+                    None => self.entities.next_set_point_temp.missing_state()
+                };
+                self.send_message(temp)?;
+            }
+            Event::Autotune(status) => {
+                self.autotune = *status;
+                self.send_autotune()?;
+            }
+            Event::HvacSwitched(entry) if entry.is_major() => {
+                self.fire_hvac_switched(entry)?;
+            }
+            // sent with the saved log at startup, and after each switch
+            Event::ActionLog(log) => {
+                self.last_transition = log.latest().cloned();
+                self.send_last_transition()?;
+            }
+            Event::Motion(motion) => {
+                self.send_message(self.entities.motion.state(*motion))?;
+            }
+            Event::AmbientLight(light) => {
+                self.light = Some(*light);
+                self.send_light()?;
+            }
+            Event::Humidity(humidity) => {
+                self.humidity = Some(*humidity);
+                self.send_humidity()?;
+            }
+            Event::BackplatePower(power) => {
+                self.power = Some(*power);
+                self.send_power()?;
+            }
+            Event::BackplateLinkStats(stats) => {
+                self.link_stats = Some(*stats);
+                self.send_link_stats()?;
+            }
+            Event::HeatingRate(rate) => {
+                self.heating_rate = Some(*rate);
+                self.send_heating_rate()?;
+            }
+            Event::BackplateCapabilities(capabilities) => {
+                *self.capabilities.lock().unwrap() = Some(capabilities.clone());
+                self.send_capabilities()?;
+            }
+            Event::ConfigReloaded(reloaded) => {
+                self.reload_encryption_key(reloaded.config.home_assistant.encryption_key.as_ref());
+                self.theme_name = reloaded.theme_name.clone();
+                self.send_theme()?;
+                self.default_brightness = reloaded.config.backlight.brightness;
+                self.send_brightness()?;
+                self.temp_unit = reloaded.config.temp_unit;
+                self.send_last_transition()?;
+            }
+            Event::SetBrightness(brightness) => {
+                self.brightness = *brightness;
+                self.send_brightness()?;
+            }
+            Event::SetDefaultBrightness(brightness) => {
+                self.default_brightness = *brightness;
+                self.send_brightness()?;
+            }
+            // The theme changes with ConfigReloaded, until then the select
+            // shows the theme in use, which stays when the new one fails to load
+            Event::SetTheme(_) => {
+                self.send_theme()?;
+            }
+            Event::SetMuted(muted) => {
+                self.muted = *muted;
+                self.send_muted()?;
+            }
+            Event::Deadband(deadband) => {
+                self.send_message(self.entities.deadband.state(*deadband))?;
+            }
+            Event::DailyTempRange(range) => {
+                self.send_message(self.entities.today_min.state(range.min.celsius()))?;
+                self.send_message(self.entities.today_max.state(range.max.celsius()))?;
+            }
+            Event::RuntimeStats(runtime) => {
+                let today = runtime.day(Local::now().date_naive());
+                let heating = today.heating.as_secs_f32() / 60.0;
+                let cooling = today.cooling.as_secs_f32() / 60.0;
+                self.send_message(self.entities.heating_runtime.state(heating))?;
+                self.send_message(self.entities.cooling_runtime.state(cooling))?;
+            }
+            Event::Problems(problems) => {
+                self.send_message(self.entities.problem.state(!problems.is_empty()))?;
+
+                let reasons = problems.iter()
+                    .map(Problem::label)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                self.send_message(self.entities.problem_reasons.state(reasons))?;
+            }
+            Event::Alerts(alerts) => {
+                let entities = &self.entities;
+                self.send_message(entities.frost_alert.state(alerts.contains(&Alert::Frost)))?;
+                self.send_message(entities.overheat_alert.state(alerts.contains(&Alert::Overheat)))?;
+                self.send_message(entities.low_battery_alert.state(alerts.contains(&Alert::LowBattery)))?;
+            }
+            Event::Zone(zone) => {
+                let temp = match zone.average_temp {
+                    Some(temp) => self.entities.zone_average_temp.state(temp.celsius()),
+                    None => self.entities.zone_average_temp.missing_state()
+                };
+                self.send_message(temp)?;
+            }
+            Event::WindowOpen(open) => {
+                self.send_message(self.entities.window_open.state(*open))?;
+            }
+            Event::Occupancy(occupancy) => {
+                let home = *occupancy == Occupancy::Home;
+                self.send_message(self.entities.occupancy.state(home))?;
+            }
+            Event::ServiceMode(wires) => {
+                if let Some(service_mode) = self.entities.service_mode {
+                    self.send_message(service_mode.state(wires.is_some()))?;
+                }
+
+                for (wire, key) in &self.entities.wires {
+                    let on = wires.iter().flatten().any(|(w, on)| w == wire && *on);
+                    self.send_message(key.state(on))?;
+                }
+            }
+            _ => { }
         }
 
         Ok(())
     }
 }
 
+/// Clients connected to the ESPHome API server, for the diagnostics screen
+#[derive(Clone, Default)]
+pub struct HaClients {
+    inner: Arc<Mutex<Vec<ClientInfo>>>
+}
+
+impl HaClients {
+    pub fn list(&self) -> Vec<ClientInfo> {
+        self.inner.lock().unwrap().clone()
+    }
+
+    fn update(&self, client: &ClientInfo) {
+        let mut clients = self.inner.lock().unwrap();
+        if let Some(existing) = clients.iter_mut().find(|c| c.peer_addr == client.peer_addr) {
+            *existing = client.clone();
+        } else {
+            clients.push(client.clone());
+        }
+    }
+
+    /// Remove client and return the number of clients still connected
+    fn remove(&self, peer_addr: SocketAddr) -> usize {
+        let mut clients = self.inner.lock().unwrap();
+        clients.retain(|c| c.peer_addr != peer_addr);
+        clients.len()
+    }
+}
+
+/// Wraps the message sender to notify the app of connection changes
+struct HaConnectionObserver<S> {
+    message_sender: MessageSender,
+    clients: HaClients,
+    event_sender: S
+}
+
+impl<S: EventSender> ConnectionObserver for HaConnectionObserver<S> {
+    fn connected(&self, client: &ClientInfo, sender: &ClientSender) -> Result<()> {
+        self.message_sender.connected(client, sender)?;
+        Ok(self.event_sender.send_event(Event::HaConnected)?)
+    }
+
+    fn disconnect(&self, client: &ClientInfo) {
+        self.message_sender.disconnect(client);
+
+        info!("HA client {} disconnected", client.peer_addr);
+
+        // Only offline once the last client is gone
+        if self.clients.remove(client.peer_addr) == 0
+            && let Err(e) = self.event_sender.send_event(Event::HaDisconnected)
+        {
+            error!("Failed to send HA disconnect event: {e}");
+        }
+    }
+
+    fn client_updated(&self, client: &ClientInfo) {
+        self.clients.update(client);
+    }
+}
+
+/// Entities exposed to Home Assistant, with the keys for sending their
+/// state and matching their commands
+#[derive(Clone)]
+struct HaEntities {
+    registry: EntityRegistry,
+    climate: EntityKey<ClimateEntity>,
+    brightness: EntityKey<NumberEntity>,
+    default_brightness: EntityKey<NumberEntity>,
+    deadband: EntityKey<NumberEntity>,
+    mute: EntityKey<SwitchEntity>,
+    theme: EntityKey<SelectEntity>,
+    fan: EntityKey<SwitchEntity>,
+    away: EntityKey<SwitchEntity>,
+    boost: EntityKey<SwitchEntity>,
+    reload_config: EntityKey<ButtonEntity>,
+    identify: EntityKey<ButtonEntity>,
+    restart: EntityKey<ButtonEntity>,
+    start_autotune: EntityKey<ButtonEntity>,
+    autotune: EntityKey<TextSensorEntity>,
+    holding: EntityKey<TextSensorEntity>,
+    occupancy: EntityKey<BinarySensorEntity>,
+    problem: EntityKey<BinarySensorEntity>,
+    problem_reasons: EntityKey<TextSensorEntity>,
+    today_min: EntityKey<SensorEntity>,
+    today_max: EntityKey<SensorEntity>,
+    heating_runtime: EntityKey<SensorEntity>,
+    cooling_runtime: EntityKey<SensorEntity>,
+    stage: EntityKey<SensorEntity>,
+    demand: EntityKey<SensorEntity>,
+    heating_rate: EntityKey<SensorEntity>,
+    battery_volts: EntityKey<SensorEntity>,
+    input_volts: EntityKey<SensorEntity>,
+    crc_errors: EntityKey<SensorEntity>,
+    resyncs: EntityKey<SensorEntity>,
+    discarded_bytes: EntityKey<SensorEntity>,
+    buffer_overflows: EntityKey<SensorEntity>,
+    charging: EntityKey<BinarySensorEntity>,
+    hvac_fault: EntityKey<BinarySensorEntity>,
+    safety: EntityKey<BinarySensorEntity>,
+    window_open: EntityKey<BinarySensorEntity>,
+    frost_alert: EntityKey<BinarySensorEntity>,
+    overheat_alert: EntityKey<BinarySensorEntity>,
+    low_battery_alert: EntityKey<BinarySensorEntity>,
+    detected_wires: EntityKey<TextSensorEntity>,
+    backplate_serial: EntityKey<TextSensorEntity>,
+    motion: EntityKey<BinarySensorEntity>,
+    illuminance: EntityKey<SensorEntity>,
+    humidity: EntityKey<SensorEntity>,
+    next_set_point: EntityKey<TextSensorEntity>,
+    next_set_point_temp: EntityKey<SensorEntity>,
+    last_transition: EntityKey<TextSensorEntity>,
+    zone_average_temp: EntityKey<SensorEntity>,
+    /// Only when service mode is enabled in the backplate config
+    service_mode: Option<EntityKey<SwitchEntity>>,
+    wires: Vec<(WireId, EntityKey<SwitchEntity>)>,
+    export_config: EntityKey<ServiceEntity>,
+    import_config: EntityKey<ServiceEntity>,
+    set_schedule_point: EntityKey<ServiceEntity>,
+    remove_schedule_point: EntityKey<ServiceEntity>,
+    reset_schedule: EntityKey<ServiceEntity>
+}
+
+impl HaEntities {
+    fn new(themes: &[String], backplate: &BackplateConfig) -> Self {
+        let mut registry = EntityRegistry::new();
+
+        let (service_mode, wires) = if backplate.service_mode {
+            let service_mode = registry.add(service_mode_entity());
+            let wires = backplate.wiring.wires().into_iter()
+                .map(|wire| (wire, registry.add(wire_entity(wire))))
+                .collect();
+            (Some(service_mode), wires)
+        } else {
+            (None, Vec::new())
+        };
+
+        Self {
+            climate: registry.add(thermostat_entity(&backplate.wiring)),
+            brightness: registry.add(brightness_entity()),
+            default_brightness: registry.add(default_brightness_entity()),
+            deadband: registry.add(deadband_entity()),
+            mute: registry.add(mute_entity()),
+            theme: registry.add(theme_entity(themes)),
+            fan: registry.add(fan_entity()),
+            away: registry.add(away_entity()),
+            boost: registry.add(boost_entity()),
+            reload_config: registry.add(reload_entity()),
+            identify: registry.add(identify_entity()),
+            restart: registry.add(restart_entity()),
+            start_autotune: registry.add(start_autotune_entity()),
+            autotune: registry.add(autotune_entity()),
+            holding: registry.add(holding_entity()),
+            occupancy: registry.add(occupancy_entity()),
+            problem: registry.add(problem_entity()),
+            problem_reasons: registry.add(problem_reasons_entity()),
+            today_min: registry.add(temp_sensor_entity("today_min", "Today Min")),
+            today_max: registry.add(temp_sensor_entity("today_max", "Today Max")),
+            heating_runtime: registry.add(runtime_sensor_entity("heating_runtime_today", "Heating Runtime Today")),
+            cooling_runtime: registry.add(runtime_sensor_entity("cooling_runtime_today", "Cooling Runtime Today")),
+            stage: registry.add(stage_entity()),
+            demand: registry.add(demand_entity()),
+            heating_rate: registry.add(heating_rate_entity()),
+            battery_volts: registry.add(voltage_sensor_entity("battery_voltage", "Battery Voltage")),
+            input_volts: registry.add(voltage_sensor_entity("input_voltage", "Input Voltage")),
+            crc_errors: registry.add(counter_sensor_entity("backplate_crc_errors", "Backplate CRC Errors")),
+            resyncs: registry.add(counter_sensor_entity("backplate_resyncs", "Backplate Resyncs")),
+            discarded_bytes: registry.add(counter_sensor_entity("backplate_discarded_bytes", "Backplate Discarded Bytes")),
+            buffer_overflows: registry.add(counter_sensor_entity("backplate_buffer_overflows", "Backplate Buffer Overflows")),
+            charging: registry.add(charging_entity()),
+            hvac_fault: registry.add(hvac_fault_entity()),
+            safety: registry.add(safety_entity()),
+            window_open: registry.add(window_open_entity()),
+            frost_alert: registry.add(alert_entity("frost_alert", "Frost Alert", "cold")),
+            overheat_alert: registry.add(alert_entity("overheat_alert", "Overheat Alert", "heat")),
+            low_battery_alert: registry.add(alert_entity("low_battery_alert", "Low Battery Alert", "battery")),
+            detected_wires: registry.add(detected_wires_entity()),
+            backplate_serial: registry.add(backplate_serial_entity()),
+            motion: registry.add(motion_entity()),
+            illuminance: registry.add(illuminance_entity()),
+            humidity: registry.add(humidity_entity()),
+            next_set_point: registry.add(next_set_point_entity()),
+            next_set_point_temp: registry.add(temp_sensor_entity("next_set_point_temp", "Next Set Point Temp")),
+            last_transition: registry.add(last_transition_entity()),
+            zone_average_temp: registry.add(temp_sensor_entity("zone_average_temp", "Zone Average Temp")),
+            service_mode,
+            wires,
+            export_config: registry.add(ServiceEntity::new("export_config")),
+            import_config: registry.add(ServiceEntity::new("import_config")),
+            set_schedule_point: registry.add(
+                ServiceEntity::new("set_schedule_point")
+                    .arg("mode", ServiceArgType::String)
+                    .arg("days", ServiceArgType::String)
+                    .arg("time", ServiceArgType::String)
+                    .arg("temp", ServiceArgType::Float)
+            ),
+            remove_schedule_point: registry.add(
+                ServiceEntity::new("remove_schedule_point")
+                    .arg("mode", ServiceArgType::String)
+                    .arg("days", ServiceArgType::String)
+                    .arg("time", ServiceArgType::String)
+            ),
+            reset_schedule: registry.add(ServiceEntity::new("reset_schedule")),
+            registry
+        }
+    }
+}
+
 struct HvacRequestHandler<S> {
-    thermostat_entity: ListEntitiesClimateResponse,
+    object_id: String,
+    entities: HaEntities,
+    /// Backplate details for the device info response, once known
+    capabilities: Arc<Mutex<Option<BackplateCapabilities>>>,
+    /// Entity commands, handled by sending events
+    commands: CommandRouter<S>,
+    /// Home Assistant sensor subscribed to for the current temp
+    temp_sensor: Option<StateSubscription>,
+    outdoor_sensor: Option<StateSubscription>,
+    /// Home Assistant entity subscribed to for away mode
+    presence_sensor: Option<StateSubscription>,
+    backup: Backup,
     event_sender: S
 }
 
-impl<S: EventSender> HvacRequestHandler<S> {
-    fn new(thermostat_entity: ListEntitiesClimateResponse, event_sender: S) -> Self {
+impl<S: EventSender + 'static> HvacRequestHandler<S> {
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        object_id: String,
+        entities: HaEntities,
+        capabilities: Arc<Mutex<Option<BackplateCapabilities>>>,
+        temp_source: &TempSourceConfig,
+        outdoor: &OutdoorConfig,
+        occupancy: &OccupancyConfig,
+        backup: Backup,
+        event_sender: S
+    ) -> Self {
         Self {
-            thermostat_entity,
+            object_id,
+            commands: Self::command_router(&entities),
+            entities,
+            capabilities,
+            temp_sensor: temp_source.ha_entity.as_ref()
+                .map(|entity_id| StateSubscription::new(entity_id, None, temp_source.ha_unit)),
+            outdoor_sensor: outdoor.ha_entity.as_ref()
+                .map(|entity_id| StateSubscription::new(entity_id, outdoor.ha_attribute.as_ref(), outdoor.ha_unit)),
+            presence_sensor: occupancy.ha_entity.as_ref()
+                .map(|entity_id| StateSubscription::new(entity_id, None, TempUnit::default())),
+            backup,
             event_sender
         }
     }
+
+    fn command_router(entities: &HaEntities) -> CommandRouter<S> {
+        let mut router = CommandRouter::<S>::new();
+
+        if let Some(service_mode) = entities.service_mode {
+            router = router.on_switch(service_mode, |events, on| {
+                Ok(events.send_event(Event::SetServiceMode(on))?)
+            });
+        }
+
+        for &(wire, key) in &entities.wires {
+            router = router.on_switch(key, move |events, on| {
+                Ok(events.send_event(Event::SetWire(wire, on))?)
+            });
+        }
+
+        router
+            .on_climate(entities.climate, climate_command)
+            .on_switch(entities.mute, |events, muted| {
+                Ok(events.send_event(Event::SetMuted(muted))?)
+            })
+            .on_switch(entities.fan, |events, on| {
+                let fan_mode = if on { HvacFanMode::On } else { HvacFanMode::Auto };
+                Ok(events.send_event(Event::SetFanMode(fan_mode))?)
+            })
+            .on_switch(entities.away, |events, away| {
+                Ok(events.send_event(Event::SetAway(away))?)
+            })
+            .on_switch(entities.boost, |events, boost| {
+                Ok(events.send_event(Event::SetBoost(boost))?)
+            })
+            .on_select(entities.theme, |events, name| {
+                Ok(events.send_event(Event::SetTheme(name.to_string()))?)
+            })
+            .on_number(entities.brightness, |events, brightness| {
+                let brightness = brightness.clamp(0.0, MAX_BRIGHTNESS) as u32;
+                Ok(events.send_event(Event::SetBrightness(brightness))?)
+            })
+            .on_number(entities.default_brightness, |events, brightness| {
+                let brightness = brightness.clamp(1.0, MAX_BRIGHTNESS) as u32;
+                Ok(events.send_event(Event::SetDefaultBrightness(brightness))?)
+            })
+            .on_number(entities.deadband, |events, deadband| {
+                if !deadband.is_finite() {
+                    warn!("Ignoring HA deadband {deadband}");
+                    return Ok(());
+                }
+                Ok(events.send_event(Event::SetDeadband(deadband))?)
+            })
+            .on_button(entities.reload_config, |events| Ok(events.send_event(Event::ReloadConfig)?))
+            .on_button(entities.identify, |events| Ok(events.send_event(Event::Identify)?))
+            .on_button(entities.restart, |events| Ok(events.send_event(Event::Restart)?))
+            .on_button(entities.start_autotune, |events| Ok(events.send_event(Event::StartAutotune)?))
+    }
+
+    fn execute_service(&self, cmd: &ExecuteServiceRequest) -> Result<()> {
+        let entities = &self.entities;
+        match cmd.key {
+            key if key == entities.export_config.key() => self.backup.export(),
+            key if key == entities.import_config.key() => self.backup.import(&self.event_sender),
+            key if key == entities.set_schedule_point.key() => {
+                let edit = ScheduleEdit::SetPoint {
+                    mode: ScheduleEdit::parse_mode(string_arg(cmd, 0)?)?,
+                    days: ScheduleEdit::parse_days(string_arg(cmd, 1)?)?,
+                    time: ScheduleEdit::parse_time(string_arg(cmd, 2)?)?,
+                    temp: ScheduleEdit::parse_temp(float_arg(cmd, 3)?)?
+                };
+                Ok(self.event_sender.send_event(Event::EditSchedule(edit))?)
+            }
+            key if key == entities.remove_schedule_point.key() => {
+                let edit = ScheduleEdit::RemovePoint {
+                    mode: ScheduleEdit::parse_mode(string_arg(cmd, 0)?)?,
+                    days: ScheduleEdit::parse_days(string_arg(cmd, 1)?)?,
+                    time: ScheduleEdit::parse_time(string_arg(cmd, 2)?)?
+                };
+                Ok(self.event_sender.send_event(Event::EditSchedule(edit))?)
+            }
+            key if key == entities.reset_schedule.key() => {
+                Ok(self.event_sender.send_event(Event::EditSchedule(ScheduleEdit::Reset))?)
+            }
+            key => Err(anyhow!("Unknown service key {key}"))
+        }
+    }
 }
 
-impl<S: EventSender> RequestHandler for HvacRequestHandler<S> {
+impl<S: EventSender + 'static> RequestHandler for HvacRequestHandler<S> {
     fn handle_request<W: MessageWriter>(
         &self,
         message: &ProtoMessage,
         writer: &mut W
     ) -> Result<ResponseStatus> {
+        if self.commands.dispatch(&self.event_sender, message)? {
+            return Ok(ResponseStatus::Continue);
+        }
+
         match message {
             ProtoMessage::ListEntitiesRequest(_) => {
-                let message = self.thermostat_entity.clone();
-                writer.write(&ProtoMessage::ListEntitiesClimateResponse(message))?;
-
-                let message = ListEntitiesDoneResponse::default();
-                writer.write(&ProtoMessage::ListEntitiesDoneResponse(message))?;
+                self.entities.registry.write_entities(&self.object_id, writer)?;
             }
             ProtoMessage::SubscribeStatesRequest(_) => {
                 self.event_sender.send_event(Event::GetState)?;
             }
-            ProtoMessage::ClimateCommandRequest(cmd) => {
-                if cmd.has_mode {
-                    let mode = cmd.mode().try_into()?;
-                    self.event_sender.send_event(Event::SetMode(mode))?;
+            ProtoMessage::SubscribeHomeAssistantStatesRequest(_) => {
+                let sensors = self.temp_sensor.iter()
+                    .chain(&self.outdoor_sensor)
+                    .chain(&self.presence_sensor);
+                for sensor in sensors {
+                    let message = sensor.subscribe_response();
+                    writer.write(&ProtoMessage::SubscribeHomeAssistantStateResponse(message))?;
                 }
-                if cmd.has_target_temperature {
-                    let temp = cmd.target_temperature;
-                    self.event_sender.send_event(Event::SetTargetTemp(temp))?;
+            }
+            ProtoMessage::HomeAssistantStateResponse(response) => {
+                if let Some(temp) = self.temp_sensor.as_ref().and_then(|s| s.temperature(response)) {
+                    self.event_sender.send_event(Event::RemoteTemp(temp))?;
+                }
+                if let Some(temp) = self.outdoor_sensor.as_ref().and_then(|s| s.temperature(response)) {
+                    self.event_sender.send_event(Event::OutdoorTemp(temp))?;
+                }
+                if let Some(home) = self.presence_sensor.as_ref().and_then(|s| s.presence(response)) {
+                    self.event_sender.send_event(Event::RemotePresence(home))?;
+                }
+            }
+            ProtoMessage::ExecuteServiceRequest(cmd) => {
+                let result = self.execute_service(cmd);
+                if let Err(e) = &result {
+                    error!("HA service failed: {e}");
                 }
-                if cmd.has_preset {
-                    match cmd.preset() {
-                        ClimatePreset::Away => {
-                            self.event_sender.send_event(Event::SetAway(true))?;
-                        }
-                        _ => {
-                            self.event_sender.send_event(Event::SetAway(false))?;
-                        }
+
+                // call_id is zero when client doesn't expect a response
+                if cmd.call_id != 0 {
+                    let mut response = ExecuteServiceResponse::default();
+                    response.call_id = cmd.call_id;
+                    response.success = result.is_ok();
+                    if let Err(e) = result {
+                        response.error_message = e.to_string();
                     }
+
+                    writer.write(&ProtoMessage::ExecuteServiceResponse(response))?;
                 }
             }
             _ => { }
@@ -163,29 +885,445 @@ impl<S: EventSender> RequestHandler for HvacRequestHandler<S> {
 
         Ok(ResponseStatus::Continue)
     }
+
+    /// Backplate hardware version and wire profile in the model, shown on
+    /// the HA device page
+    fn device_info(&self, info: &mut DeviceInfoResponse) {
+        if let Some(capabilities) = self.capabilities.lock().unwrap().as_ref() {
+            info.model = format!("{} ({}, {:?} wire ids)",
+                info.model, capabilities.hardware_version, capabilities.wire_profile);
+        }
+    }
+}
+
+/// Send the fields flagged in the command as one [`ClimateCommand`].
+/// Unsupported values are logged and left out, the rest still apply.
+fn climate_command<S: EventSender>(events: &S, cmd: &ClimateCommandRequest) -> Result<()> {
+    let mut command = ClimateCommand::default();
+
+    if cmd.has_mode {
+        command.mode = ClimateMode::try_from(cmd.mode)
+            .map_err(|e| anyhow!("{e}"))
+            .and_then(HvacMode::try_from)
+            .inspect_err(|e| warn!("Ignoring HA climate mode {}: {e}", cmd.mode))
+            .ok();
+    }
+    if cmd.has_fan_mode {
+        command.fan_mode = ClimateFanMode::try_from(cmd.fan_mode)
+            .map_err(|e| anyhow!("{e}"))
+            .and_then(HvacFanMode::try_from)
+            .inspect_err(|e| warn!("Ignoring HA fan mode {}: {e}", cmd.fan_mode))
+            .ok();
+    }
+    if cmd.has_target_temperature {
+        command.target_temp = finite_temp(cmd.target_temperature);
+    }
+    if cmd.has_target_temperature_low {
+        command.target_temp_low = finite_temp(cmd.target_temperature_low);
+    }
+    if cmd.has_target_temperature_high {
+        command.target_temp_high = finite_temp(cmd.target_temperature_high);
+    }
+    if cmd.has_preset {
+        match ClimatePreset::try_from(cmd.preset) {
+            Ok(ClimatePreset::Away) => {
+                command.away = Some(true);
+            }
+            Ok(ClimatePreset::None) => {
+                // "None" also releases a hold and resumes the schedule,
+                // and leaves emergency heat
+                command.away = Some(false);
+                command.hold = Some(false);
+                command.emergency_heat = Some(false);
+            }
+            _ => warn!("Ignoring unsupported HA preset {}", cmd.preset)
+        }
+    }
+    if cmd.has_custom_preset {
+        command.hold = Some(cmd.custom_preset == ThermostatState::HOLD_PRESET);
+        command.emergency_heat = Some(cmd.custom_preset == ThermostatState::EMERGENCY_HEAT_PRESET);
+    }
+    if cmd.has_swing_mode || cmd.has_custom_fan_mode || cmd.has_target_humidity {
+        warn!("Ignoring unsupported HA swing, custom fan or humidity command");
+    }
+
+    if !command.is_empty() {
+        events.send_event(Event::ClimateCommand(command))?;
+    }
+
+    Ok(())
+}
+
+/// Setpoint from a command, `None` when HA sent NaN or infinity
+fn finite_temp(celsius: f32) -> Option<Temperature> {
+    if celsius.is_finite() {
+        Some(Temperature::from_celsius(celsius))
+    } else {
+        warn!("Ignoring HA setpoint {celsius}");
+        None
+    }
+}
+
+/// Home Assistant entity state, or attribute, holding a temperature
+struct StateSubscription {
+    entity_id: String,
+    attribute: String,
+    unit: TempUnit
+}
+
+impl StateSubscription {
+    fn new(entity_id: &str, attribute: Option<&String>, unit: TempUnit) -> Self {
+        Self {
+            entity_id: entity_id.to_string(),
+            attribute: attribute.cloned().unwrap_or_default(),
+            unit
+        }
+    }
+
+    fn subscribe_response(&self) -> SubscribeHomeAssistantStateResponse {
+        let mut message = SubscribeHomeAssistantStateResponse::default();
+        message.entity_id = self.entity_id.clone();
+        message.attribute = self.attribute.clone();
+        message
+    }
+
+    fn is_update(&self, response: &HomeAssistantStateResponse) -> bool {
+        response.entity_id == self.entity_id && response.attribute == self.attribute
+    }
+
+    /// Parse a state update for this subscription, `None` for other
+    /// entities or when the entity is "unavailable" or "unknown"
+    fn temperature(&self, response: &HomeAssistantStateResponse) -> Option<Temperature> {
+        if !self.is_update(response) {
+            return None;
+        }
+
+        match response.state.parse::<f32>() {
+            Ok(value) => Some(Temperature::from_value(value, self.unit)),
+            Err(_) => {
+                debug!("HA {} state {:?}", self.entity_id, response.state);
+                None
+            }
+        }
+    }
+
+    /// Parse a presence update for this subscription, true when someone is
+    /// home. Zones report the number of people in them, people and device
+    /// trackers "home" or the name of another zone, binary sensors "on".
+    fn presence(&self, response: &HomeAssistantStateResponse) -> Option<bool> {
+        if !self.is_update(response) {
+            return None;
+        }
+
+        match response.state.as_str() {
+            "unavailable" | "unknown" => {
+                debug!("HA {} state {:?}", self.entity_id, response.state);
+                None
+            }
+            "home" | "on" => Some(true),
+            state => Some(state.parse::<f32>().is_ok_and(|count| count > 0.0))
+        }
+    }
+}
+
+fn thermostat_entity(wiring: &WireConfig) -> ClimateEntity {
+    // emergency heat needs an aux wire to heat with
+    let custom_presets: &[&str] = if wiring.aux_wire().is_some() {
+        &[ThermostatState::HOLD_PRESET, ThermostatState::EMERGENCY_HEAT_PRESET]
+    } else {
+        &[ThermostatState::HOLD_PRESET]
+    };
+
+    ClimateEntity::new()
+        .modes(&[
+            ClimateMode::Off,
+            ClimateMode::Heat,
+            ClimateMode::Cool,
+            ClimateMode::HeatCool,
+            ClimateMode::FanOnly
+        ])
+        .temperature_range(
+            ThermostatState::MIN_TEMP.celsius(),
+            ThermostatState::MAX_TEMP.celsius(),
+            0.5
+        )
+        .features(
+            ClimateFeature::SUPPORTS_CURRENT_TEMPERATURE |
+            ClimateFeature::SUPPORTS_TWO_POINT_TARGET_TEMPERATURE |
+            ClimateFeature::SUPPORTS_ACTION
+        )
+        .presets(&[ClimatePreset::None, ClimatePreset::Away])
+        .custom_presets(custom_presets)
+        .fan_modes(&[ClimateFanMode::ClimateFanAuto, ClimateFanMode::ClimateFanOn])
+}
+
+fn brightness_entity() -> NumberEntity {
+    // Zero returns brightness to local control, auto brightness when enabled
+    NumberEntity::new("brightness", "Backlight Brightness")
+        .icon("mdi:brightness-6")
+        .range(0.0, MAX_BRIGHTNESS, 1.0)
+        .category(EntityCategory::Config)
+        .mode(NumberMode::Slider)
+}
+
+fn default_brightness_entity() -> NumberEntity {
+    // the configured brightness, used without an override or auto brightness
+    NumberEntity::new("default_brightness", "Default Brightness")
+        .icon("mdi:brightness-5")
+        .range(1.0, MAX_BRIGHTNESS, 1.0)
+        .category(EntityCategory::Config)
+        .mode(NumberMode::Slider)
+}
+
+fn deadband_entity() -> NumberEntity {
+    // Celsius like the other config temps
+    NumberEntity::new("deadband", "Temp Deadband")
+        .icon("mdi:thermometer-lines")
+        .range(MIN_DEADBAND, MAX_DEADBAND, 0.1)
+        .category(EntityCategory::Config)
+        .mode(NumberMode::Slider)
+}
+
+fn mute_entity() -> SwitchEntity {
+    SwitchEntity::new("mute", "Mute Sounds")
+        .icon("mdi:volume-off")
+        .category(EntityCategory::Config)
+}
+
+/// Names of the themes in the theme dir, listed once at startup
+fn theme_entity(themes: &[String]) -> SelectEntity {
+    SelectEntity::new("theme", "Theme")
+        .icon("mdi:palette")
+        .category(EntityCategory::Config)
+        .options(themes)
+}
+
+/// Runs the fan continuously, same as the climate "on" fan mode
+fn fan_entity() -> SwitchEntity {
+    SwitchEntity::new("fan", "Fan")
+        .icon("mdi:fan")
+}
+
+fn away_entity() -> SwitchEntity {
+    SwitchEntity::new("away", "Away")
+        .icon("mdi:home-export-outline")
+}
+
+/// Hands the wires over to the wire switches, for checking the wiring
+fn service_mode_entity() -> SwitchEntity {
+    SwitchEntity::new("service_mode", "Service Mode")
+        .icon("mdi:wrench")
+        .category(EntityCategory::Diagnostic)
+}
+
+/// Switches a single wire directly, only while in service mode
+fn wire_entity(wire: WireId) -> SwitchEntity {
+    let object_id = format!("wire_{wire:?}").to_lowercase();
+    SwitchEntity::new(&object_id, &format!("Wire {wire:?}"))
+        .icon("mdi:electric-switch")
+        .category(EntityCategory::Diagnostic)
+}
+
+/// Starts a boost, or cancels it early when turned off
+fn boost_entity() -> SwitchEntity {
+    SwitchEntity::new("boost", "Boost")
+        .icon("mdi:rocket-launch")
+}
+
+fn reload_entity() -> ButtonEntity {
+    ButtonEntity::new("reload_config", "Reload Config")
+        .icon("mdi:file-refresh")
+        .category(EntityCategory::Config)
+}
+
+fn identify_entity() -> ButtonEntity {
+    ButtonEntity::new("identify", "Identify")
+        .device_class("identify")
+        .category(EntityCategory::Config)
+}
+
+fn restart_entity() -> ButtonEntity {
+    ButtonEntity::new("restart", "Restart")
+        .device_class("restart")
+        .category(EntityCategory::Config)
+}
+
+fn start_autotune_entity() -> ButtonEntity {
+    ButtonEntity::new("start_autotune", "Start Autotune")
+        .icon("mdi:tune-variant")
+        .category(EntityCategory::Config)
+}
+
+fn autotune_entity() -> TextSensorEntity {
+    TextSensorEntity::new("autotune", "Autotune")
+        .icon("mdi:tune-variant")
+        .category(EntityCategory::Diagnostic)
+}
+
+fn holding_entity() -> TextSensorEntity {
+    // Explains an idle action, climate entities have no attribute for it
+    TextSensorEntity::new("idle_reason", "Idle Reason")
+        .icon("mdi:thermostat")
+        .category(EntityCategory::Diagnostic)
+}
+
+fn occupancy_entity() -> BinarySensorEntity {
+    BinarySensorEntity::new("occupancy", "Occupancy")
+        .device_class("occupancy")
+}
+
+fn motion_entity() -> BinarySensorEntity {
+    BinarySensorEntity::new("motion", "Motion")
+        .device_class("motion")
+}
+
+/// Raw ambient light sensor reading, not calibrated to lux
+fn illuminance_entity() -> SensorEntity {
+    SensorEntity::new("illuminance", "Illuminance")
+        .accuracy(0)
+        .state_class(SensorStateClass::StateClassMeasurement)
+}
+
+fn humidity_entity() -> SensorEntity {
+    SensorEntity::new("humidity", "Humidity")
+        .unit("%")
+        .accuracy(0)
+        .device_class("humidity")
+        .state_class(SensorStateClass::StateClassMeasurement)
+}
+
+fn problem_entity() -> BinarySensorEntity {
+    BinarySensorEntity::new("problem", "Problem")
+        .device_class("problem")
+        .category(EntityCategory::Diagnostic)
+}
+
+fn hvac_fault_entity() -> BinarySensorEntity {
+    BinarySensorEntity::new("hvac_fault", "HVAC Fault")
+        .device_class("problem")
+}
+
+/// On while the `[alerts]` threshold is crossed
+fn alert_entity(object_id: &str, name: &str, device_class: &str) -> BinarySensorEntity {
+    BinarySensorEntity::new(object_id, name)
+        .device_class(device_class)
+}
+
+/// On while heating or cooling is forced by a safety limit
+fn safety_entity() -> BinarySensorEntity {
+    BinarySensorEntity::new("safety_override", "Safety Override")
+        .device_class("safety")
+}
+
+/// On while heating is paused for a quick temp drop
+fn window_open_entity() -> BinarySensorEntity {
+    BinarySensorEntity::new("window_open", "Window Open")
+        .device_class("window")
+}
+
+fn detected_wires_entity() -> TextSensorEntity {
+    TextSensorEntity::new("detected_wires", "Detected Wires")
+        .icon("mdi:connection")
+        .category(EntityCategory::Diagnostic)
+}
+
+fn backplate_serial_entity() -> TextSensorEntity {
+    TextSensorEntity::new("backplate_serial", "Backplate Serial")
+        .icon("mdi:identifier")
+        .category(EntityCategory::Diagnostic)
+}
+
+fn problem_reasons_entity() -> TextSensorEntity {
+    TextSensorEntity::new("problem_reasons", "Problem Reasons")
+        .icon("mdi:alert-circle-outline")
+        .category(EntityCategory::Diagnostic)
+}
+
+fn temp_sensor_entity(suffix: &str, name: &str) -> SensorEntity {
+    SensorEntity::new(suffix, name)
+        .unit("°C")
+        .accuracy(1)
+        .device_class("temperature")
+        .state_class(SensorStateClass::StateClassMeasurement)
+}
+
+/// Minutes of heating or cooling today, starts over at midnight
+fn runtime_sensor_entity(suffix: &str, name: &str) -> SensorEntity {
+    SensorEntity::new(suffix, name)
+        .unit("min")
+        .accuracy(0)
+        .device_class("duration")
+        .state_class(SensorStateClass::StateClassTotalIncreasing)
+}
+
+fn voltage_sensor_entity(suffix: &str, name: &str) -> SensorEntity {
+    SensorEntity::new(suffix, name)
+        .unit("V")
+        .accuracy(2)
+        .device_class("voltage")
+        .state_class(SensorStateClass::StateClassMeasurement)
+        .category(EntityCategory::Diagnostic)
+}
+
+/// Backplate link error count, starts over when the backplate reconnects
+fn counter_sensor_entity(suffix: &str, name: &str) -> SensorEntity {
+    SensorEntity::new(suffix, name)
+        .icon("mdi:alert-circle-outline")
+        .accuracy(0)
+        .state_class(SensorStateClass::StateClassTotalIncreasing)
+        .category(EntityCategory::Diagnostic)
+}
+
+fn charging_entity() -> BinarySensorEntity {
+    BinarySensorEntity::new("charging", "Battery Charging")
+        .device_class("battery_charging")
+        .category(EntityCategory::Diagnostic)
+}
+
+fn stage_entity() -> SensorEntity {
+    SensorEntity::new("stage", "HVAC Stage")
+        .icon("mdi:stairs")
+        .accuracy(0)
+        .state_class(SensorStateClass::StateClassMeasurement)
+}
+
+/// Learned for smart recovery, see [`crate::config::RecoveryConfig`]
+fn heating_rate_entity() -> SensorEntity {
+    SensorEntity::new("heating_rate", "Heating Rate")
+        .icon("mdi:thermometer-chevron-up")
+        .unit("°C/h")
+        .accuracy(2)
+        .state_class(SensorStateClass::StateClassMeasurement)
+        .category(EntityCategory::Diagnostic)
+}
+
+fn demand_entity() -> SensorEntity {
+    SensorEntity::new("demand", "HVAC Demand")
+        .icon("mdi:gauge")
+        .unit("%")
+        .accuracy(0)
+        .state_class(SensorStateClass::StateClassMeasurement)
+}
+
+fn next_set_point_entity() -> TextSensorEntity {
+    TextSensorEntity::new("next_set_point", "Next Set Point")
+        .icon("mdi:calendar-clock")
+}
+
+fn last_transition_entity() -> TextSensorEntity {
+    // Why heating or cooling last switched, without parsing the logs
+    TextSensorEntity::new("last_transition", "Last Transition")
+        .icon("mdi:history")
+}
+
+fn string_arg(cmd: &ExecuteServiceRequest, index: usize) -> Result<&str> {
+    cmd.args.get(index)
+        .map(|arg| arg.string.as_str())
+        .ok_or_else(|| anyhow!("Missing service argument {index}"))
 }
 
-fn thermostat_entity(object_id: String) -> ListEntitiesClimateResponse {
-    let mut entity = ListEntitiesClimateResponse::default();
-
-    entity.object_id = object_id;
-    entity.supported_modes = vec![
-        ClimateMode::Off as i32,
-        ClimateMode::Heat as i32,
-        ClimateMode::Cool as i32,
-        ClimateMode::FanOnly as i32,
-    ];
-    entity.visual_min_temperature = ThermostatState::MIN_TEMP;
-    entity.visual_max_temperature = ThermostatState::MAX_TEMP;
-    entity.visual_target_temperature_step = 0.5;
-    entity.visual_current_temperature_step = 0.5;
-    entity.feature_flags =
-        ClimateFeature::SUPPORTS_CURRENT_TEMPERATURE |
-        ClimateFeature::SUPPORTS_ACTION;
-    entity.supported_presets = vec![
-        ClimatePreset::None as i32,
-        ClimatePreset::Away as i32,
-    ];
-
-    entity
+fn float_arg(cmd: &ExecuteServiceRequest, index: usize) -> Result<f32> {
+    cmd.args.get(index)
+        .map(|arg| arg.float)
+        .ok_or_else(|| anyhow!("Missing service argument {index}"))
 }
@@ -16,52 +16,119 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::thread::{self, JoinHandle};
+#[cfg(feature = "device")]
+use std::{path::{Path, PathBuf}, thread::{self, JoinHandle}, time::Duration};
 
-use anyhow::{Result, anyhow};
+use anyhow::Result;
+#[cfg(feature = "device")]
+use anyhow::anyhow;
+#[cfg(feature = "device")]
 use evdev::{Device, EventSummary, KeyCode};
+#[cfg(feature = "device")]
+use log::{error, info};
 
-use crate::events::{Event, EventSender, EventSource};
+use crate::{config::InputConfig, events::{EventSender, EventSource}};
+#[cfg(feature = "device")]
+use crate::events::Event;
 
-struct InputDevice {
-    device: Device,
-    map_fn: InputEventMapFn
+/// Input devices, found by their capabilities unless a path is configured
+#[cfg(feature = "device")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputId {
+    /// Device with a relative axis
+    Dial,
+    /// Device with the power key
+    Button
 }
 
-type InputEventMapFn = fn(EventSummary) -> Option<Event>;
-
-impl InputDevice {
-    fn open(path: &str, map_fn: InputEventMapFn) -> Result<Self> {
-        let device = Device::open(path)?;
-        Ok(Self {
-            device,
-            map_fn
-        })
+#[cfg(feature = "device")]
+impl InputId {
+    fn is_match(&self, device: &Device) -> bool {
+        match self {
+            Self::Dial => device.supported_relative_axes()
+                .is_some_and(|axes| axes.iter().next().is_some()),
+            Self::Button => device.supported_keys()
+                .is_some_and(|keys| keys.contains(KeyCode::KEY_POWER))
+        }
     }
 
-    fn fetch_events(&mut self) -> Result<impl Iterator<Item = Event>> {
-        let events = self.device.fetch_events()?
-            .filter_map(|event| {
-                (self.map_fn)(event.destructure())
-            });
-        Ok(events)
+    /// Open the device at `path`, or the first matching device in "/dev/input"
+    fn open(&self, path: Option<&Path>) -> Result<(PathBuf, Device)> {
+        if let Some(path) = path {
+            return Ok((path.to_path_buf(), Device::open(path)?));
+        }
+
+        let mut devices: Vec<_> = evdev::enumerate()
+            .filter(|(_, device)| self.is_match(device))
+            .collect();
+        devices.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        devices.into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("No {self:?} device found in /dev/input"))
     }
 }
 
+#[cfg(feature = "device")]
+type InputEventMapFn = fn(EventSummary) -> Option<Event>;
+
+#[cfg(feature = "device")]
 pub struct InputDeviceThread {
     thread: JoinHandle<Result<()>>
 }
 
+#[cfg(feature = "device")]
 impl InputDeviceThread {
-    fn start<S>(mut input_events: InputDevice, sender: S) -> Self
+    /// Read events from the device until the event loop is gone. A device
+    /// that can't be opened, or stops reading, is opened again every
+    /// `reopen_period`.
+    fn start<S>(
+        id: InputId,
+        path: Option<PathBuf>,
+        reopen_period: Duration,
+        map_fn: InputEventMapFn,
+        sender: S
+    ) -> Self
         where S: EventSender + Send + 'static
     {
         let thread = thread::spawn(move || {
+            // missing and connected are only sent when it changes
+            let mut missing = false;
+
             loop {
-                let events = input_events.fetch_events()?;
-                for event in events {
-                    sender.send_event(event)?;
+                match id.open(path.as_deref()) {
+                    Ok((path, mut device)) => {
+                        info!("{id:?} input opened {path:?}");
+                        if missing {
+                            missing = false;
+                            sender.send_event(Event::InputDeviceConnected(id))?;
+                        }
+
+                        loop {
+                            match device.fetch_events() {
+                                Ok(events) => {
+                                    for event in events.filter_map(|e| map_fn(e.destructure())) {
+                                        sender.send_event(event)?;
+                                    }
+                                }
+                                Err(e) => {
+                                    error!("{id:?} input read failed: {e}");
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                    Err(e) if !missing => {
+                        error!("{id:?} input unavailable: {e}");
+                    }
+                    Err(_) => { }
                 }
+
+                if !missing {
+                    missing = true;
+                    sender.send_event(Event::InputDeviceMissing(id))?;
+                }
+                thread::sleep(reopen_period);
             }
         });
 
@@ -76,7 +143,8 @@ impl InputDeviceThread {
     }
 }
 
-fn start_dial_events<S>(sender: S) -> Result<InputDeviceThread>
+#[cfg(feature = "device")]
+fn start_dial_events<S>(config: &InputConfig, sender: S) -> InputDeviceThread
     where S: EventSender + Send + 'static
 {
     fn handle_event(e: EventSummary) -> Option<Event> {
@@ -90,15 +158,17 @@ fn start_dial_events<S>(sender: S) -> Result<InputDeviceThread>
         }
     }
 
-    let input_events = InputDevice::open(
-        "/dev/input/event1",
-        handle_event
-    )?;
-
-    Ok(InputDeviceThread::start(input_events, sender))
+    InputDeviceThread::start(
+        InputId::Dial,
+        config.dial_device.clone(),
+        config.reopen_period,
+        handle_event,
+        sender
+    )
 }
 
-fn start_button_events<S>(sender: S) -> Result<InputDeviceThread>
+#[cfg(feature = "device")]
+fn start_button_events<S>(config: &InputConfig, sender: S) -> InputDeviceThread
     where S: EventSender + Send + 'static
 {
     fn handle_event(e: EventSummary) -> Option<Event> {
@@ -107,34 +177,40 @@ fn start_button_events<S>(sender: S) -> Result<InputDeviceThread>
             EventSummary::Key(_, KeyCode::KEY_POWER, 1) => {
                 Some(Event::ButtonDown)
             }
+            EventSummary::Key(_, KeyCode::KEY_POWER, 0) => {
+                Some(Event::ButtonUp)
+            }
             _ => None
         }
     }
 
-    let input_events = InputDevice::open(
-        "/dev/input/event2",
-        handle_event
-    )?;
-
-    Ok(InputDeviceThread::start(input_events, sender))
+    InputDeviceThread::start(
+        InputId::Button,
+        config.button_device.clone(),
+        config.reopen_period,
+        handle_event,
+        sender
+    )
 }
 
+/// Start reading the dial and button. Missing devices don't stop the app,
+/// HA can still control the thermostat, they're opened once they appear.
 #[cfg(feature = "device")]
-pub fn start_threads<E, S>(events: &E) -> Result<()>
-    where E: EventSource<S>, S: EventSender + Send + 'static
+pub fn start_threads<E, S>(events: &E, config: &InputConfig) -> Result<()>
+    where E: EventSource<S>, S: EventSender + Clone + Send + 'static
 {
-    use crate::events::SmoothEventSender;
+    use crate::events::CoalescingEventSender;
 
-    start_button_events(events.event_sender())?;
+    start_button_events(config, events.event_sender());
 
     // 32ms (~30Hz) "feels" pretty good, 16ms causes the main loop to get overwhelmed
-    let dial_event_sender = SmoothEventSender::new(events.event_sender(), 32);
-    start_dial_events(dial_event_sender)?;
+    let dial_event_sender = CoalescingEventSender::new(events.event_sender(), 32);
+    start_dial_events(config, dial_event_sender);
 
     Ok(())
 }
 
 #[cfg(feature = "simulate")]
-pub fn start_threads<E: EventSource<S>, S: EventSender>(_events: &E) -> Result<()> {
+pub fn start_threads<E: EventSource<S>, S: EventSender>(_events: &E, _config: &InputConfig) -> Result<()> {
     Ok(())
 }
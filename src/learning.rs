@@ -0,0 +1,312 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Local, NaiveTime, TimeDelta, Timelike, Weekday};
+use log::info;
+
+use crate::{
+    config::{DaysOfWeek, LearningConfig, WeekDayRange},
+    events::{Event, EventHandler, EventSender, Topic},
+    schedule::ScheduleEdit,
+    state::{ClimateCommand, HvacMode},
+    temperature::Temperature
+};
+
+/// Changes this close together are one adjustment, the dial sends a
+/// change for each step
+const MERGE_WINDOW: TimeDelta = TimeDelta::minutes(5);
+
+/// Schedule, days and rounded time of day adjustments are grouped by
+type Slot = (HvacMode, WeekDayRange, NaiveTime);
+
+/// Target temp chosen on the thermostat or from Home Assistant
+#[derive(Debug, Clone, PartialEq)]
+pub struct Adjustment {
+    pub time: DateTime<Local>,
+    /// Schedule the adjustment would go in, heat or cool
+    pub mode: HvacMode,
+    pub temp: Temperature
+}
+
+impl Adjustment {
+    /// Suggestion this adjustment counts towards
+    fn slot(&self, window: Duration) -> Slot {
+        let days = match self.time.weekday() {
+            Weekday::Sat | Weekday::Sun => WeekDayRange::WeekEnd,
+            _ => WeekDayRange::WeekDays
+        };
+
+        // nearest multiple of the window, wrapping past midnight
+        let window = (window.as_secs() / 60).max(1) as u32;
+        let minutes = self.time.hour() * 60 + self.time.minute();
+        let minutes = (minutes + window / 2) / window * window % (24 * 60);
+        let time = NaiveTime::from_hms_opt(minutes / 60, minutes % 60, 0).unwrap();
+
+        (self.mode, days, time)
+    }
+}
+
+/// Recent setpoint adjustments, oldest first
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AdjustmentLog {
+    entries: Vec<Adjustment>
+}
+
+impl AdjustmentLog {
+    pub fn new(entries: impl IntoIterator<Item = Adjustment>) -> Self {
+        Self { entries: entries.into_iter().collect() }
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &Adjustment> {
+        self.entries.iter()
+    }
+
+    fn record(&mut self, adjustment: Adjustment) {
+        match self.entries.last_mut() {
+            Some(last) if last.mode == adjustment.mode && adjustment.time - last.time < MERGE_WINDOW => {
+                *last = adjustment;
+            }
+            _ => self.entries.push(adjustment)
+        }
+    }
+
+    /// Drop adjustments older than `max_age_days`
+    fn prune(&mut self, now: DateTime<Local>, max_age_days: u32) {
+        let max_age = TimeDelta::days(max_age_days.into());
+        self.entries.retain(|a| now - a.time < max_age);
+    }
+
+    /// Set points for adjustments repeated around the same time on at least
+    /// `min_adjustments` separate days
+    fn suggestions(&self, config: &LearningConfig) -> Vec<Suggestion> {
+        let mut slots: Vec<(Slot, Vec<&Adjustment>)> = Vec::new();
+        for adjustment in &self.entries {
+            let slot = adjustment.slot(config.time_window);
+            match slots.iter_mut().find(|(s, _)| *s == slot) {
+                Some((_, adjustments)) => adjustments.push(adjustment),
+                None => slots.push((slot, vec![adjustment]))
+            }
+        }
+
+        let mut suggestions: Vec<_> = slots.into_iter()
+            .filter_map(|((mode, days, time), adjustments)| {
+                let mut dates: Vec<_> = adjustments.iter().map(|a| a.time.date_naive()).collect();
+                dates.sort();
+                dates.dedup();
+                if dates.len() < config.min_adjustments {
+                    return None;
+                }
+
+                // average, rounded to the nearest half degree
+                let celsius = adjustments.iter().map(|a| a.temp.celsius()).sum::<f32>()
+                    / adjustments.len() as f32;
+                let temp = Temperature::from_celsius((celsius * 2.0).round() / 2.0);
+
+                Some(Suggestion { mode, days, time, temp, count: dates.len() })
+            })
+            .collect();
+
+        suggestions.sort_by_key(|s| (s.mode == HvacMode::Cool, s.days == WeekDayRange::WeekEnd, s.time));
+        suggestions
+    }
+
+    /// Drop the adjustments behind `suggestion`, once it's accepted or dismissed
+    fn forget(&mut self, suggestion: &Suggestion, window: Duration) {
+        let slot = (suggestion.mode, suggestion.days.clone(), suggestion.time);
+        self.entries.retain(|a| a.slot(window) != slot);
+    }
+}
+
+/// Schedule set point suggested from repeated adjustments
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub mode: HvacMode,
+    /// Weekdays or the weekend
+    pub days: WeekDayRange,
+    pub time: NaiveTime,
+    pub temp: Temperature,
+    /// Days the adjustment was made on
+    pub count: usize
+}
+
+impl Suggestion {
+    /// Edit adding the suggestion to the schedule
+    pub fn schedule_edit(&self) -> ScheduleEdit {
+        ScheduleEdit::SetPoint {
+            mode: self.mode,
+            days: DaysOfWeek::Range(self.days.clone()).normalize(),
+            time: self.time,
+            temp: self.temp
+        }
+    }
+}
+
+/// Records manual setpoint changes and suggests schedule set points from
+/// them, see [`crate::config::LearningConfig`]
+pub struct ScheduleLearner<S> {
+    config: LearningConfig,
+    event_sender: S,
+    log: AdjustmentLog,
+    suggestions: Vec<Suggestion>,
+    /// Schedule adjustments are recorded against, `None` outside of heat
+    /// and cool mode, and while away
+    mode: Option<HvacMode>
+}
+
+impl<S: EventSender> ScheduleLearner<S> {
+    /// `log` is the persisted log, suggestions from it are sent right away
+    pub fn new(config: &LearningConfig, mut log: AdjustmentLog, event_sender: S) -> Result<Self> {
+        log.prune(Local::now(), config.max_age_days);
+        let suggestions = log.suggestions(config);
+        event_sender.send_event(Event::ScheduleSuggestions(suggestions.clone()))?;
+
+        Ok(Self {
+            config: config.clone(),
+            event_sender,
+            log,
+            suggestions,
+            mode: None
+        })
+    }
+
+    fn record(&mut self, temp: Temperature) -> Result<()> {
+        let Some(mode) = self.mode.filter(|_| self.config.enabled) else {
+            return Ok(());
+        };
+
+        let now = Local::now();
+        self.log.record(Adjustment { time: now, mode, temp });
+        self.log.prune(now, self.config.max_age_days);
+        self.log_changed()
+    }
+
+    fn log_changed(&mut self) -> Result<()> {
+        self.event_sender.send_event(Event::Adjustments(self.log.clone()))?;
+        self.update_suggestions()
+    }
+
+    fn update_suggestions(&mut self) -> Result<()> {
+        let suggestions = self.log.suggestions(&self.config);
+        if suggestions != self.suggestions {
+            self.suggestions = suggestions;
+            self.event_sender.send_event(Event::ScheduleSuggestions(self.suggestions.clone()))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: EventSender> EventHandler for ScheduleLearner<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<()> {
+        match event {
+            Event::State(state) => {
+                self.mode = match state.mode {
+                    HvacMode::Heat | HvacMode::EmergencyHeat => Some(HvacMode::Heat),
+                    HvacMode::Cool => Some(HvacMode::Cool),
+                    _ => None
+                }.filter(|_| !state.away);
+            }
+            Event::SetTargetTemp(temp)
+                | Event::ClimateCommand(ClimateCommand { target_temp: Some(temp), .. }) => {
+                self.record(*temp)?;
+            }
+            Event::AcceptSuggestion(suggestion) => {
+                info!("Adding suggested set point {suggestion:?}");
+                self.event_sender.send_event(Event::EditSchedule(suggestion.schedule_edit()))?;
+                self.log.forget(suggestion, self.config.time_window);
+                self.log_changed()?;
+            }
+            Event::DismissSuggestion(suggestion) => {
+                self.log.forget(suggestion, self.config.time_window);
+                self.log_changed()?;
+            }
+            Event::ConfigReloaded(reloaded) => {
+                self.config = reloaded.config.learning.clone();
+                self.update_suggestions()?;
+            }
+            Event::GetState => {
+                self.event_sender.send_event(Event::ScheduleSuggestions(self.suggestions.clone()))?;
+            }
+            _ => { }
+        }
+
+        Ok(())
+    }
+
+    fn topics(&self) -> &'static [Topic] {
+        &[Topic::State]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn adjustment(day: u32, hour: u32, minute: u32, temp: f32) -> Adjustment {
+        Adjustment {
+            // 2026-01-05 is a Monday
+            time: Local.with_ymd_and_hms(2026, 1, day, hour, minute, 0).unwrap(),
+            mode: HvacMode::Heat,
+            temp: Temperature::from_celsius(temp)
+        }
+    }
+
+    #[test]
+    fn suggests_repeated_adjustments() {
+        let config = LearningConfig::default();
+        let mut log = AdjustmentLog::new([
+            adjustment(5, 6, 25, 21.0),
+            adjustment(6, 6, 40, 21.5),
+            // weekend, a different slot
+            adjustment(10, 6, 30, 21.0),
+            adjustment(7, 6, 35, 21.0),
+            // same day as the last, only counted once
+            adjustment(7, 6, 20, 21.0)
+        ]);
+
+        assert_eq!(log.suggestions(&config), [
+            Suggestion {
+                mode: HvacMode::Heat,
+                days: WeekDayRange::WeekDays,
+                time: NaiveTime::from_hms_opt(6, 30, 0).unwrap(),
+                temp: Temperature::from_celsius(21.0),
+                count: 3
+            }
+        ]);
+
+        let suggestion = log.suggestions(&config).remove(0);
+        log.forget(&suggestion, config.time_window);
+        assert_eq!(log.entries().count(), 1);
+        assert!(log.suggestions(&config).is_empty());
+    }
+
+    #[test]
+    fn merges_dial_steps() {
+        let mut log = AdjustmentLog::default();
+        log.record(adjustment(5, 6, 30, 20.5));
+        log.record(adjustment(5, 6, 32, 21.0));
+        log.record(adjustment(5, 7, 0, 20.0));
+
+        let temps: Vec<_> = log.entries().map(|a| a.temp.celsius()).collect();
+        assert_eq!(temps, [21.0, 20.0]);
+    }
+}
@@ -0,0 +1,200 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    collections::VecDeque,
+    sync::{Mutex, PoisonError, RwLock}
+};
+
+use anyhow::Result;
+use chrono::Local;
+use log::{LevelFilter, Log, Metadata, Record};
+
+use crate::{config::LogConfig, env};
+
+/// Number of recent log lines kept for the logs screen
+const BUFFER_LEN: usize = 200;
+
+static LOGGER: AppLogger = AppLogger {
+    output: RwLock::new(None),
+    filter: RwLock::new(LevelFilters { level: LevelFilter::Info, modules: Vec::new() }),
+    buffer: Mutex::new(VecDeque::new())
+};
+
+/// Log to syslog at `syslog_level`, or to the console filtered by `RUST_LOG`
+/// when `None`. Levels from the config are applied with [`apply_config`].
+pub fn init(syslog_level: Option<LevelFilter>) -> Result<()> {
+    let output = if let Some(level) = syslog_level {
+        Output { logger: syslog_logger()?, level }
+    } else {
+        let logger = env_logger::Builder::from_default_env().build();
+        let level = logger.filter();
+        Output { logger: Box::new(logger), level }
+    };
+
+    *LOGGER.output.write().unwrap_or_else(PoisonError::into_inner) = Some(output);
+
+    log::set_logger(&LOGGER)?;
+    log::set_max_level(LOGGER.max_level());
+
+    Ok(())
+}
+
+/// Set levels from config, at startup and after config reload
+pub fn apply_config(config: &LogConfig) {
+    let mut modules: Vec<_> = config.modules.iter()
+        .map(|(module, level)| (module.clone(), *level))
+        .collect();
+
+    // Longest path first, so the most specific match wins
+    modules.sort_by_key(|(module, _)| std::cmp::Reverse(module.len()));
+
+    *LOGGER.filter.write().unwrap_or_else(PoisonError::into_inner) =
+        LevelFilters { level: config.level, modules };
+
+    log::set_max_level(LOGGER.max_level());
+}
+
+/// Up to `count` of the most recent log lines, oldest first
+pub fn recent_lines(count: usize) -> Vec<String> {
+    let buffer = LOGGER.buffer.lock().unwrap_or_else(PoisonError::into_inner);
+    buffer.iter()
+        .skip(buffer.len().saturating_sub(count))
+        .cloned()
+        .collect()
+}
+
+fn syslog_logger() -> Result<Box<dyn Log>> {
+    use syslog::{Facility, Formatter3164, BasicLogger};
+
+    let formatter = Formatter3164 {
+        facility: Facility::LOG_USER,
+        hostname: None,
+        process: env::get_pkg_name().into(),
+        pid: 0
+    };
+
+    let logger = syslog::unix(formatter)?;
+    Ok(Box::new(BasicLogger::new(logger)))
+}
+
+/// Filters records by module, keeps recent lines in memory, and passes
+/// them on to syslog or the console
+struct AppLogger {
+    output: RwLock<Option<Output>>,
+    filter: RwLock<LevelFilters>,
+    buffer: Mutex<VecDeque<String>>
+}
+
+struct Output {
+    logger: Box<dyn Log>,
+    level: LevelFilter
+}
+
+struct LevelFilters {
+    level: LevelFilter,
+    /// Sorted longest module path first
+    modules: Vec<(String, LevelFilter)>
+}
+
+impl LevelFilters {
+    fn level_for(&self, target: &str) -> LevelFilter {
+        self.modules.iter()
+            .find(|(module, _)| {
+                target.strip_prefix(module.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .map(|(_, level)| *level)
+            .unwrap_or(self.level)
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        self.modules.iter()
+            .map(|(_, level)| *level)
+            .fold(self.level, Ord::max)
+    }
+}
+
+impl AppLogger {
+    fn max_level(&self) -> LevelFilter {
+        self.filter.read().unwrap_or_else(PoisonError::into_inner).max_level()
+    }
+
+    fn record_line(&self, record: &Record) {
+        let time = Local::now().format("%H:%M:%S");
+        let line = format!("{time} {} {}", record.level(), record.args());
+
+        let mut buffer = self.buffer.lock().unwrap_or_else(PoisonError::into_inner);
+        if buffer.len() == BUFFER_LEN {
+            buffer.pop_front();
+        }
+        buffer.push_back(line);
+    }
+}
+
+impl Log for AppLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        let filter = self.filter.read().unwrap_or_else(PoisonError::into_inner);
+        metadata.level() <= filter.level_for(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        self.record_line(record);
+
+        let output = self.output.read().unwrap_or_else(PoisonError::into_inner);
+        if let Some(output) = output.as_ref()
+            && record.level() <= output.level
+            && output.logger.enabled(record.metadata())
+        {
+            output.logger.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        let output = self.output.read().unwrap_or_else(PoisonError::into_inner);
+        if let Some(output) = output.as_ref() {
+            output.logger.flush();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn module_levels() {
+        let filter = LevelFilters {
+            level: LevelFilter::Info,
+            modules: vec![
+                ("retherm::backplate".to_string(), LevelFilter::Trace),
+                ("retherm".to_string(), LevelFilter::Warn),
+            ]
+        };
+
+        assert_eq!(filter.level_for("retherm::backplate::backplate_device"), LevelFilter::Trace);
+        assert_eq!(filter.level_for("retherm::backplate_other"), LevelFilter::Warn);
+        assert_eq!(filter.level_for("retherm"), LevelFilter::Warn);
+        assert_eq!(filter.level_for("esphome_api::server"), LevelFilter::Info);
+        assert_eq!(filter.max_level(), LevelFilter::Trace);
+    }
+}
@@ -16,157 +16,350 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+mod action_log;
+mod alerts;
+mod autotune;
 mod backplate;
+mod backup;
+mod bug_report;
 mod cli;
 mod config;
+mod daily_range;
 mod drawable;
 mod env;
 mod events;
+mod health;
 mod home_assistant;
 mod input_events;
+mod learning;
+mod logging;
+mod occupancy;
+mod quiet_hours;
+mod recovery;
+mod reload;
+mod runtime_stats;
 mod schedule;
 mod screen;
 mod sound;
 mod state;
 mod storage;
+mod supervisor;
+mod temp_source;
+mod temperature;
 mod theme;
 mod timer;
 mod widgets;
 mod window;
+mod window_open;
+mod zone;
+
+use std::{path::{Path, PathBuf}, time::Duration};
 
 use anyhow::Result;
 use esphome_api::server::{EncryptedStreamProvider, PlaintextStreamProvider};
-use log::{error, info};
+use log::{error, info, warn};
 
-use crate::events::{Event, EventHandler, EventSource};
+use crate::config::HeadlessMode;
+use crate::events::{Event, EventBus, EventHandler, EventSender, EventSource};
 use crate::home_assistant::HomeAssistant;
 use crate::screen::{MainScreen, ScreenManager};
 
+/// Longest wait for queued state writes when exiting
+const STORAGE_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
 fn main() -> Result<()> {
     let cli = cli::Cli::load();
 
-    if let Some(log_level) = cli.syslog {
-        init_syslog(log_level)?;
-    } else {
-        env_logger::init();
-    }
+    reload::block_signals()?;
+
+    logging::init(cli.syslog)?;
 
-    install_panic_logging();
+    // logged from the start, bug reports need the config
+    install_panic_logging(None);
 
-    let config = if let Some(file_path) = cli.config {
+    let config = if let Some(file_path) = &cli.config {
         config::Config::load(file_path)?
     } else {
         config::Config::default()
     };
+    logging::apply_config(&config.log);
 
-    let theme = if let Some(file_path) = cli.theme {
-        theme::Theme::load(file_path)?
-    } else {
-        theme::Theme::default()
-    };
+    let event_trace = bug_report::EventTrace::new();
+    install_panic_logging(Some(
+        bug_report::BugReporter::new(config.bug_report_dir.clone(), event_trace.clone())
+    ));
+
+    let mut storage = storage::Storage::new(&config)?;
+
+    let theme_file = cli.theme.as_ref().map(PathBuf::from);
+    let stored_theme = storage.read_theme(&config.theme)?;
+    let (theme, theme_name) = load_theme(&config, stored_theme.as_deref(), theme_file.as_deref())?;
 
     let mut event_source = window::new_event_source()?;
 
-    let mut storage = storage::Storage::new(&config)?;
     let state = storage.read_state()?;
 
-    let mut state_manager = state::StateManager::new(
+    if let Some(capabilities) = storage.read_capabilities()? {
+        event_source.event_sender().send_event(Event::BackplateCapabilities(capabilities))?;
+    }
+
+    if let Some(tuning) = storage.read_tuning()? {
+        event_source.event_sender().send_event(Event::Tuning(tuning))?;
+    }
+
+    if let Some(rate) = storage.read_heating_rate()? {
+        event_source.event_sender().send_event(Event::HeatingRate(rate))?;
+    }
+
+    if let Some(deadband) = storage.read_deadband(config.temp_deadband)? {
+        event_source.event_sender().send_event(Event::SetDeadband(deadband))?;
+    }
+
+    let state_manager = state::StateManager::new(
         &config,
         state.clone(),
+        storage.read_runtime()?,
+        event_source.event_sender()
+    )?;
+
+    let occupancy = occupancy::OccupancyManager::new(
+        &config.occupancy,
+        event_source.event_sender()
+    )?;
+
+    let temp_sources = temp_source::TempSources::new(
+        &config.temp_source,
+        event_source.event_sender()
+    );
+
+    let health = health::HealthMonitor::new(event_source.event_sender());
+    let alerts = alerts::AlertMonitor::new(&config, event_source.event_sender());
+    let zone = zone::ZoneManager::new(&config, event_source.event_sender());
+    let window_open = window_open::WindowOpenDetector::new(
+        &config.window_open,
+        event_source.event_sender()
+    );
+    let daily_range = daily_range::DailyTempRange::new(event_source.event_sender());
+    let autotune = autotune::AutoTuner::new(event_source.event_sender());
+    let recovery = recovery::SmartRecovery::new(&config.recovery, event_source.event_sender());
+    let action_log = action_log::ActionLogger::new(
+        storage.read_action_log()?,
+        event_source.event_sender()
+    )?;
+    let learning = learning::ScheduleLearner::new(
+        &config.learning,
+        storage.read_adjustments()?,
         event_source.event_sender()
     )?;
+    let reloader = reload::ConfigReloader::new(
+        cli.config.as_ref().map(PathBuf::from),
+        theme_file,
+        &theme_name,
+        &config.theme,
+        event_source.event_sender()
+    );
+    reload::start_signal_thread(event_source.event_sender());
 
-    let mut schedule = schedule::ScheduleManager::new(&config, event_source.event_sender());
-    schedule.start_schedule(&state.mode);
+    let mut schedule = schedule::ScheduleManager::new(
+        &config,
+        storage.read_schedule()?,
+        event_source.event_sender()
+    );
+    schedule.start_schedule(&state.mode)?;
 
     let mut backplate = backplate::Backplate::new(&config, event_source.event_sender())?;
-    let mut timers = timer::Timers::new(event_source.event_sender());
-    let mut sound = sound::Sound::new()?;
+    let timers = timer::Timers::new(event_source.event_sender());
+    let sound = sound::Sound::new(&config.sound, event_source.event_sender())?;
+    let quiet_hours = quiet_hours::QuietHours::new(&config.quiet_hours, event_source.event_sender())?;
+
+    let mut window = open_window(&config)?;
+
+    let mut home_assistant = HomeAssistant::new(
+        &theme::theme_names(&config.theme_dir),
+        &theme_name,
+        &config
+    );
+
+    // Screens are only needed when there is a window to draw them on
+    let mut screen_manager = window.as_ref().map(|_| {
+        let main_screen = MainScreen::new(
+            &theme,
+            state,
+            config.temp_unit,
+            event_source.event_sender()
+        );
 
-    let mut window = window::new_window(&config.backlight)?;
+        ScreenManager::new(
+            theme,
+            &theme_name,
+            main_screen,
+            home_assistant.clients(),
+            &config,
+            event_source.event_sender()
+        )
+    });
 
-    let main_screen = MainScreen::new(theme.thermostat.clone(), state, event_source.event_sender());
-    let mut screen_manager = ScreenManager::new(theme, main_screen, event_source.event_sender());
+    input_events::start_threads(&event_source, &config.input)?;
 
-    input_events::start_threads(&event_source)?;
+    let backup = backup::Backup::new(&config, cli.config.as_ref().map(PathBuf::from));
 
-    let mut home_assistant = HomeAssistant::new();
     if let Some(key) = &config.home_assistant.encryption_key {
         let stream_factory = EncryptedStreamProvider::new(
             key,
             &config.home_assistant.get_node_name(),
             &config.home_assistant.get_mac_address()
         )?;
+        home_assistant.set_encryption_key(stream_factory.key());
 
         home_assistant.start_listener(
             &config.home_assistant,
+            &config.temp_source,
+            &config.outdoor,
+            &config.occupancy,
             stream_factory,
+            backup,
             event_source.event_sender()
         );
     } else {
         home_assistant.start_listener(
             &config.home_assistant,
+            &config.temp_source,
+            &config.outdoor,
+            &config.occupancy,
             PlaintextStreamProvider::new(),
+            backup,
             event_source.event_sender()
         );
     }
 
-    'running: loop {
-        window.draw_screen(screen_manager.active_screen())?;
+    let mut bus = EventBus::new(Duration::from_millis(50));
+    bus.subscribe("occupancy", occupancy);
+    bus.subscribe("temp_sources", temp_sources);
+    bus.subscribe("zone", zone);
+    bus.subscribe("window_open", window_open);
+    bus.subscribe("state_manager", state_manager);
+    bus.subscribe("schedule", schedule);
+    bus.subscribe("timers", timers);
+    bus.subscribe("sound", sound);
+    bus.subscribe("quiet_hours", quiet_hours);
+    bus.subscribe("home_assistant", home_assistant);
+    bus.subscribe("health", health);
+    bus.subscribe("alerts", alerts);
+    bus.subscribe("daily_range", daily_range);
+    bus.subscribe("autotune", autotune);
+    bus.subscribe("recovery", recovery);
+    bus.subscribe("action_log", action_log);
+    bus.subscribe("learning", learning);
+    bus.subscribe("reloader", reloader);
+
+    // Break with true to restart, init.sh doesn't supervise the process
+    let restart = 'running: loop {
+        if let (Some(window), Some(screen_manager)) = (&mut window, &mut screen_manager) {
+            window.draw_screen(screen_manager.active_screen())?;
+        }
 
         let event = event_source.wait_event()?;
-        if matches!(event, Event::Quit) {
-            break 'running;
-        }
 
-        let mut handlers: [&mut dyn EventHandler; _] = [
-            &mut storage,
-            &mut state_manager,
-            &mut schedule,
-            &mut backplate,
-            &mut timers,
-            &mut sound,
-            &mut window,
-            &mut screen_manager,
-            &mut home_assistant
+        // Used between events for drawing and shutdown, so borrowed for
+        // each dispatch instead of owned by the bus
+        let mut handlers: [(&str, &mut dyn EventHandler); _] = [
+            ("storage", &mut storage),
+            ("backplate", &mut backplate),
+            ("window", &mut window),
+            ("screen_manager", &mut screen_manager)
         ];
 
         let mut event = Some(event);
         while let Some(e) = event {
+            if matches!(e, Event::Quit | Event::Restart) {
+                break 'running e == Event::Restart;
+            }
+
             info!("{:?}", e);
+            event_trace.record(&e);
 
-            for handler in handlers.iter_mut() {
-                handler.handle_event(&e)?;
-            }
+            bus.dispatch(&e, &mut handlers)?;
 
             event = event_source.poll_event()?;
         }
+    };
+
+    if let Some(window) = &mut window {
+        window.shutdown();
+    }
+
+    if restart {
+        backplate.stop();
+    } else {
+        backplate.shutdown();
+    }
+
+    storage.shutdown(STORAGE_SHUTDOWN_TIMEOUT);
+
+    if restart {
+        restart_process()?;
     }
 
     Ok(())
 }
 
-fn init_syslog(log_level: log::LevelFilter) -> Result<()> {
-    use syslog::{Facility, Formatter3164, BasicLogger};
+/// Replace this process with a fresh copy, with the same arguments.
+/// Only returns if starting the new process failed.
+fn restart_process() -> Result<()> {
+    use std::os::unix::process::CommandExt;
 
-    let formatter = Formatter3164 {
-        facility: Facility::LOG_USER,
-        hostname: None,
-        process: env::get_pkg_name().into(),
-        pid: 0
-    };
+    info!("Restarting");
 
-    let logger = syslog::unix(formatter)?;
-    log::set_boxed_logger(Box::new(BasicLogger::new(logger)))
-        .map(|()| log::set_max_level(log_level))?;
+    let exe = std::env::current_exe()?;
+    let error = std::process::Command::new(exe)
+        .args(std::env::args_os().skip(1))
+        .exec();
 
-    Ok(())
+    Err(error.into())
+}
+
+/// Open the display, or `None` to run headless
+fn open_window(config: &config::Config) -> Result<Option<window::AppWindow>> {
+    match config.headless {
+        HeadlessMode::Always => {
+            info!("Running headless");
+            Ok(None)
+        }
+        HeadlessMode::Never => Ok(Some(window::new_window(config)?)),
+        HeadlessMode::Auto => match window::new_window(config) {
+            Ok(window) => Ok(Some(window)),
+            Err(e) => {
+                warn!("Display unavailable, running headless: {e}");
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Theme chosen at runtime before the last restart, or the one named in the
+/// config, and its name. The default theme when the theme can't be loaded.
+fn load_theme(
+    config: &config::Config,
+    stored_theme: Option<&str>,
+    theme_file: Option<&Path>
+) -> Result<(theme::Theme, String)> {
+    let name = stored_theme.unwrap_or(&config.theme);
+    match theme::Theme::load_named(name, &config.theme_dir, theme_file) {
+        Ok(theme) => Ok((theme, name.to_string())),
+        Err(e) if name != theme::DEFAULT_THEME => {
+            error!("Unable to load theme {name}, using default theme: {e}");
+            let theme = theme::Theme::load_named(theme::DEFAULT_THEME, &config.theme_dir, theme_file)?;
+            Ok((theme, theme::DEFAULT_THEME.to_string()))
+        }
+        Err(e) => Err(e)
+    }
 }
 
-fn install_panic_logging() {
-    use std::{panic, thread};
+/// Log panics, and write a bug report once there is a `bug_reporter`
+fn install_panic_logging(bug_reporter: Option<bug_report::BugReporter>) {
+    use std::{backtrace::Backtrace, panic, thread};
 
-    panic::set_hook(Box::new(|info| {
+    panic::set_hook(Box::new(move |info| {
         let thread = thread::current();
         let thread = thread.name().unwrap_or("<unnamed>");
 
@@ -177,5 +370,21 @@ fn install_panic_logging() {
             // error!("Location; {}:{}", loc.file(), loc.line());
             error!("Location; {}", loc);
         }
+
+        let Some(bug_reporter) = &bug_reporter else {
+            return;
+        };
+
+        let report = format!(
+            "thread: {thread}\nreason: {reason}\nlocation: {}\n\n{}",
+            info.location().map(|l| l.to_string()).unwrap_or_default(),
+            Backtrace::force_capture()
+        );
+
+        match bug_reporter.write_report(&report) {
+            Ok(Some(path)) => error!("Bug report written to {path:?}"),
+            Ok(None) => info!("Bug report skipped, last report too recent"),
+            Err(e) => error!("Failed to write bug report: {e}")
+        }
     }));
 }
@@ -0,0 +1,321 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use anyhow::Result;
+
+use crate::{
+    config::{OccupancyConfig, OccupancySource},
+    events::{Event, EventHandler, EventSender, Topic},
+    state::ClimateCommand,
+    timer::TimerId
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Occupancy {
+    Home,
+    Away,
+}
+
+/// Infers occupancy from proximity sensors and user input, and the Home
+/// Assistant presence entity, and sends `Event::Occupancy` on transitions
+/// between home and away.
+/// Also sends `Event::Motion` while there is recent proximity movement.
+pub struct OccupancyManager<S> {
+    event_sender: S,
+    config: OccupancyConfig,
+    occupancy: Occupancy,
+    /// Occupancy from activity on the thermostat
+    activity: Occupancy,
+    /// Occupancy from the presence entity, once it has reported
+    presence: Option<Occupancy>,
+    motion: bool,
+}
+
+impl<S: EventSender> OccupancyManager<S> {
+    pub fn new(config: &OccupancyConfig, event_sender: S) -> Result<Self> {
+        event_sender.send_event(
+            Event::TimeoutReset(TimerId::Away, config.timeout)
+        )?;
+
+        Ok(Self {
+            event_sender,
+            config: config.clone(),
+            occupancy: Occupancy::Home,
+            activity: Occupancy::Home,
+            presence: None,
+            motion: false,
+        })
+    }
+
+    fn is_activity(&self, event: &Event) -> bool {
+        match event {
+            Event::ProximityNear | Event::ProximityFar => self.config.proximity,
+            Event::Dial(_) | Event::ButtonDown => self.config.input,
+            _ => false
+        }
+    }
+
+    /// Combine activity and presence for the configured source
+    fn update_occupancy(&mut self) -> Result<()> {
+        let occupancy = match (self.config.source, self.presence) {
+            (OccupancySource::Activity, _) | (_, None) => self.activity,
+            (OccupancySource::HomeAssistant, Some(presence)) => presence,
+            (OccupancySource::Both, Some(presence)) => {
+                if self.activity == Occupancy::Away && presence == Occupancy::Away {
+                    Occupancy::Away
+                } else {
+                    Occupancy::Home
+                }
+            }
+        };
+
+        self.set_occupancy(occupancy)
+    }
+
+    fn set_occupancy(&mut self, occupancy: Occupancy) -> Result<()> {
+        if occupancy != self.occupancy {
+            self.occupancy = occupancy;
+            self.event_sender.send_event(Event::Occupancy(occupancy))?;
+        }
+
+        Ok(())
+    }
+
+    fn set_motion(&mut self, motion: bool) -> Result<()> {
+        if motion != self.motion {
+            self.motion = motion;
+            self.event_sender.send_event(Event::Motion(motion))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: EventSender> EventHandler for OccupancyManager<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<()> {
+        // Motion is reported even when proximity doesn't count as activity
+        if matches!(event, Event::ProximityNear | Event::ProximityFar) {
+            self.event_sender.send_event(
+                Event::TimeoutReset(TimerId::Motion, self.config.motion_hold)
+            )?;
+            self.set_motion(true)?;
+        }
+
+        // Away set by hand applies regardless of the source, until the
+        // next change in activity or presence
+        match event {
+            Event::SetAway(false)
+            | Event::ClimateCommand(ClimateCommand { away: Some(false), .. }) => {
+                self.event_sender.send_event(
+                    Event::TimeoutReset(TimerId::Away, self.config.timeout)
+                )?;
+                self.activity = Occupancy::Home;
+                self.set_occupancy(Occupancy::Home)?;
+            }
+            Event::SetAway(true)
+            | Event::ClimateCommand(ClimateCommand { away: Some(true), .. }) => {
+                self.activity = Occupancy::Away;
+                self.set_occupancy(Occupancy::Away)?;
+            }
+            e if self.is_activity(e) => {
+                self.event_sender.send_event(
+                    Event::TimeoutReset(TimerId::Away, self.config.timeout)
+                )?;
+                self.activity = Occupancy::Home;
+                self.update_occupancy()?;
+            }
+            Event::TimeoutReached(TimerId::Away) => {
+                self.activity = Occupancy::Away;
+                self.update_occupancy()?;
+            }
+            Event::RemotePresence(home) => {
+                let presence = if *home { Occupancy::Home } else { Occupancy::Away };
+                if self.presence != Some(presence) {
+                    self.presence = Some(presence);
+                    self.update_occupancy()?;
+                }
+            }
+            Event::TimeoutReached(TimerId::Motion) => {
+                self.set_motion(false)?;
+            }
+            Event::GetState => {
+                self.event_sender.send_event(Event::Occupancy(self.occupancy))?;
+                self.event_sender.send_event(Event::Motion(self.motion))?;
+            }
+            Event::ConfigReloaded(reloaded) => {
+                self.config = reloaded.config.occupancy.clone();
+                if self.activity == Occupancy::Home {
+                    self.event_sender.send_event(
+                        Event::TimeoutReset(TimerId::Away, self.config.timeout)
+                    )?;
+                }
+                self.update_occupancy()?;
+            }
+            _ => { }
+        }
+
+        Ok(())
+    }
+
+    fn topics(&self) -> &'static [Topic] {
+        &[Topic::Input, Topic::State, Topic::Timer, Topic::Ha]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{DefaultEventSource, EventSource, QueueSender};
+
+    fn occupancy_manager(
+        config: OccupancyConfig
+    ) -> (DefaultEventSource, OccupancyManager<QueueSender>)
+    {
+        let event_source = DefaultEventSource::new();
+        let manager = OccupancyManager::new(
+            &config,
+            event_source.event_sender()
+        ).unwrap();
+
+        (event_source, manager)
+    }
+
+    /// Occupancy transitions sent since the last call
+    fn transitions(event_source: &mut DefaultEventSource) -> Result<Vec<Occupancy>> {
+        let mut result = Vec::new();
+        while let Some(event) = event_source.poll_event()? {
+            if let Event::Occupancy(occupancy) = event {
+                result.push(occupancy);
+            }
+        }
+
+        Ok(result)
+    }
+
+    #[test]
+    fn away_timeout_and_return() -> Result<()> {
+        let (mut events, mut mgr) = occupancy_manager(OccupancyConfig::default());
+
+        mgr.handle_event(&Event::TimeoutReached(TimerId::Away))?;
+        assert_eq!(transitions(&mut events)?, [Occupancy::Away]);
+
+        // repeat timeout is not a transition
+        mgr.handle_event(&Event::TimeoutReached(TimerId::Away))?;
+        assert_eq!(transitions(&mut events)?, []);
+
+        mgr.handle_event(&Event::ProximityNear)?;
+        assert_eq!(transitions(&mut events)?, [Occupancy::Home]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn ignore_disabled_sources() -> Result<()> {
+        let config = OccupancyConfig {
+            proximity: false,
+            ..OccupancyConfig::default()
+        };
+        let (mut events, mut mgr) = occupancy_manager(config);
+
+        mgr.handle_event(&Event::SetAway(true))?;
+        mgr.handle_event(&Event::ProximityFar)?;
+        assert_eq!(transitions(&mut events)?, [Occupancy::Away]);
+
+        mgr.handle_event(&Event::Dial(10))?;
+        assert_eq!(transitions(&mut events)?, [Occupancy::Home]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn home_assistant_presence() -> Result<()> {
+        let config = OccupancyConfig {
+            source: OccupancySource::HomeAssistant,
+            ..OccupancyConfig::default()
+        };
+        let (mut events, mut mgr) = occupancy_manager(config);
+
+        // activity applies until the entity reports
+        mgr.handle_event(&Event::TimeoutReached(TimerId::Away))?;
+        assert_eq!(transitions(&mut events)?, [Occupancy::Away]);
+
+        mgr.handle_event(&Event::RemotePresence(true))?;
+        assert_eq!(transitions(&mut events)?, [Occupancy::Home]);
+
+        mgr.handle_event(&Event::TimeoutReached(TimerId::Away))?;
+        mgr.handle_event(&Event::RemotePresence(true))?;
+        assert_eq!(transitions(&mut events)?, []);
+
+        mgr.handle_event(&Event::RemotePresence(false))?;
+        mgr.handle_event(&Event::ProximityNear)?;
+        assert_eq!(transitions(&mut events)?, [Occupancy::Away]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn both_agree_on_away() -> Result<()> {
+        let config = OccupancyConfig {
+            source: OccupancySource::Both,
+            ..OccupancyConfig::default()
+        };
+        let (mut events, mut mgr) = occupancy_manager(config);
+
+        mgr.handle_event(&Event::RemotePresence(false))?;
+        assert_eq!(transitions(&mut events)?, []);
+
+        mgr.handle_event(&Event::TimeoutReached(TimerId::Away))?;
+        assert_eq!(transitions(&mut events)?, [Occupancy::Away]);
+
+        mgr.handle_event(&Event::RemotePresence(true))?;
+        assert_eq!(transitions(&mut events)?, [Occupancy::Home]);
+
+        // away by hand doesn't wait for presence
+        mgr.handle_event(&Event::SetAway(true))?;
+        assert_eq!(transitions(&mut events)?, [Occupancy::Away]);
+
+        mgr.handle_event(&Event::ProximityNear)?;
+        assert_eq!(transitions(&mut events)?, [Occupancy::Home]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn motion_hold() -> Result<()> {
+        let (mut events, mut mgr) = occupancy_manager(OccupancyConfig::default());
+
+        let mut motion = || -> Result<Vec<bool>> {
+            let mut result = Vec::new();
+            while let Some(event) = events.poll_event()? {
+                if let Event::Motion(motion) = event {
+                    result.push(motion);
+                }
+            }
+            Ok(result)
+        };
+
+        mgr.handle_event(&Event::ProximityFar)?;
+        mgr.handle_event(&Event::ProximityNear)?;
+        assert_eq!(motion()?, [true]);
+
+        mgr.handle_event(&Event::TimeoutReached(TimerId::Motion))?;
+        assert_eq!(motion()?, [false]);
+
+        Ok(())
+    }
+}
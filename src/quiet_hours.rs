@@ -0,0 +1,172 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{Local, NaiveTime};
+use log::info;
+
+use crate::{
+    config::QuietHoursConfig,
+    events::{Event, EventHandler, EventSender, Topic},
+    timer::TimerId
+};
+
+const DAY_SECS: i64 = 24 * 60 * 60;
+
+/// Sends `Event::QuietHours` when entering and leaving the quiet hours
+/// window, with a timer for the next start or end
+pub struct QuietHours<S> {
+    event_sender: S,
+    config: QuietHoursConfig,
+    quiet: bool
+}
+
+impl<S: EventSender> QuietHours<S> {
+    pub fn new(config: &QuietHoursConfig, event_sender: S) -> Result<Self> {
+        let mut quiet_hours = Self {
+            event_sender,
+            config: config.clone(),
+            quiet: false
+        };
+        quiet_hours.update(Local::now().time())?;
+
+        Ok(quiet_hours)
+    }
+
+    fn is_quiet(&self, time: NaiveTime) -> bool {
+        let (start, end) = (self.config.start, self.config.end);
+
+        if !self.config.enabled {
+            false
+        } else if start <= end {
+            time >= start && time < end
+        } else {
+            // passes midnight
+            time >= start || time < end
+        }
+    }
+
+    /// Time until the next start or end
+    fn next_change(&self, time: NaiveTime) -> Duration {
+        let until = |at: NaiveTime| {
+            let secs = (at - time).num_seconds().rem_euclid(DAY_SECS);
+            if secs == 0 { DAY_SECS } else { secs }
+        };
+
+        let secs = until(self.config.start).min(until(self.config.end));
+        Duration::from_secs(secs as u64)
+    }
+
+    fn update(&mut self, time: NaiveTime) -> Result<()> {
+        let quiet = self.is_quiet(time);
+        if quiet != self.quiet {
+            info!("Quiet hours {}", if quiet { "started" } else { "ended" });
+            self.quiet = quiet;
+            self.event_sender.send_event(Event::QuietHours(quiet))?;
+        }
+
+        if self.config.enabled {
+            self.event_sender.send_event(
+                Event::TimeoutReset(TimerId::QuietHours, self.next_change(time))
+            )?;
+        } else {
+            self.event_sender.send_event(Event::CancelTimer(TimerId::QuietHours))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: EventSender> EventHandler for QuietHours<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<()> {
+        match event {
+            Event::TimeoutReached(TimerId::QuietHours) => {
+                self.update(Local::now().time())?;
+            }
+            Event::ConfigReloaded(reloaded) => {
+                self.config = reloaded.config.quiet_hours.clone();
+                self.update(Local::now().time())?;
+            }
+            Event::GetState => {
+                self.event_sender.send_event(Event::QuietHours(self.quiet))?;
+            }
+            _ => { }
+        }
+
+        Ok(())
+    }
+
+    fn topics(&self) -> &'static [Topic] {
+        &[Topic::Timer]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{DefaultEventSource, EventSource, QueueSender};
+
+    fn time(hour: u32, min: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, min, 0).unwrap()
+    }
+
+    fn quiet_hours(start: NaiveTime, end: NaiveTime) -> (DefaultEventSource, QuietHours<QueueSender>) {
+        let event_source = DefaultEventSource::new();
+        let quiet_hours = QuietHours {
+            event_sender: event_source.event_sender(),
+            config: QuietHoursConfig { enabled: true, start, end },
+            quiet: false
+        };
+
+        (event_source, quiet_hours)
+    }
+
+    #[test]
+    fn window_passes_midnight() -> Result<()> {
+        let (mut events, mut quiet_hours) = quiet_hours(time(22, 0), time(7, 0));
+
+        assert!(!quiet_hours.is_quiet(time(21, 59)));
+        assert!(quiet_hours.is_quiet(time(23, 30)));
+        assert!(quiet_hours.is_quiet(time(6, 59)));
+        assert!(!quiet_hours.is_quiet(time(7, 0)));
+
+        quiet_hours.update(time(22, 0))?;
+        assert!(matches!(events.poll_event()?, Some(Event::QuietHours(true))));
+        assert!(matches!(events.poll_event()?, Some(Event::TimeoutReset(TimerId::QuietHours, wait))
+            if wait == Duration::from_hours(9)));
+
+        // unchanged isn't resent
+        quiet_hours.update(time(1, 0))?;
+        assert!(matches!(events.poll_event()?, Some(Event::TimeoutReset(TimerId::QuietHours, wait))
+            if wait == Duration::from_hours(6)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn window_within_day() {
+        let (_events, quiet_hours) = quiet_hours(time(13, 0), time(15, 0));
+
+        assert!(!quiet_hours.is_quiet(time(12, 0)));
+        assert!(quiet_hours.is_quiet(time(14, 0)));
+        assert!(!quiet_hours.is_quiet(time(15, 0)));
+        assert_eq!(quiet_hours.next_change(time(15, 0)), Duration::from_hours(22));
+    }
+}
@@ -0,0 +1,325 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use chrono::{Local, NaiveDateTime, TimeDelta};
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::RecoveryConfig,
+    events::{Event, EventHandler, EventSender, Topic},
+    schedule::NextSetPoint,
+    state::{HvacAction, HvacMode, ThermostatState},
+    timer::TimerId
+};
+
+/// Shortest heating run to learn from, the start of a run is mostly the
+/// furnace warming up
+const MIN_RUN: Duration = Duration::from_mins(10);
+
+/// Share of each new run in the learned rate
+const LEARNING_WEIGHT: f32 = 0.2;
+
+/// Slowest rate used for lead times, a persisted rate near zero would
+/// otherwise make the lead time overflow
+const MIN_RATE: f32 = 0.1;
+
+/// How fast the room warms while heating, persisted and used to start
+/// heating ahead of scheduled set points
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct HeatingRate {
+    /// Degrees celsius per hour
+    pub degrees_per_hour: f32,
+    /// Heating runs the rate was learned from
+    pub runs: u32
+}
+
+impl HeatingRate {
+    /// Moving average of the rate with the rate of a new run
+    fn learn(rate: Option<Self>, run_rate: f32) -> Self {
+        match rate {
+            Some(rate) => Self {
+                degrees_per_hour: rate.degrees_per_hour
+                    + (run_rate - rate.degrees_per_hour) * LEARNING_WEIGHT,
+                runs: rate.runs + 1
+            },
+            None => Self { degrees_per_hour: run_rate, runs: 1 }
+        }
+    }
+
+    /// Time to warm the room by `rise` degrees, in whole minutes so small
+    /// temp changes don't move the start time
+    fn lead_time(&self, rise: f32) -> Duration {
+        let mins = (rise / self.degrees_per_hour.max(MIN_RATE) * 60.0).round();
+        Duration::from_mins(mins as u64)
+    }
+}
+
+/// Heating run in progress, to measure the rate when it ends
+struct HeatingRun {
+    start: Instant,
+    temp: f32
+}
+
+/// Learns the heating rate from heating runs, sending it as
+/// [`Event::HeatingRate`]. When enabled, sends the next scheduled temp
+/// early as [`Event::ScheduledTargetTemp`], so heating starts in time to
+/// reach it at the scheduled time.
+pub struct SmartRecovery<S> {
+    event_sender: S,
+    config: RecoveryConfig,
+    state: ThermostatState,
+    rate: Option<HeatingRate>,
+    run: Option<HeatingRun>,
+    next: Option<NextSetPoint>,
+    /// Time recovery is waiting to start at, to reset the timer only when
+    /// it moves
+    start: Option<NaiveDateTime>,
+    /// Set point already started early, recovery only starts once for it
+    recovered: Option<NaiveDateTime>
+}
+
+impl<S: EventSender> SmartRecovery<S> {
+    pub fn new(config: &RecoveryConfig, event_sender: S) -> Self {
+        Self {
+            event_sender,
+            config: config.clone(),
+            state: ThermostatState::default(),
+            rate: None,
+            run: None,
+            next: None,
+            start: None,
+            recovered: None
+        }
+    }
+
+    fn update_run(&mut self, state: &ThermostatState, now: Instant) -> Result<()> {
+        // wires stay off during the min off time lockout
+        let heating = state.action == HvacAction::Heating && !state.lockout;
+        let temp = state.current_temp.celsius();
+
+        match &self.run {
+            None if heating => {
+                self.run = Some(HeatingRun { start: now, temp });
+            }
+            Some(run) if !heating => {
+                let elapsed = now.duration_since(run.start);
+                let rise = temp - run.temp;
+
+                if elapsed >= MIN_RUN && rise > 0.0 {
+                    let rate = HeatingRate::learn(self.rate, rise / elapsed.as_secs_f32() * 3600.0);
+                    info!("Heating rate {:.2}/h from {} runs", rate.degrees_per_hour, rate.runs);
+                    self.rate = Some(rate);
+                    self.event_sender.send_event(Event::HeatingRate(rate))?;
+                }
+
+                self.run = None;
+            }
+            _ => { }
+        }
+
+        Ok(())
+    }
+
+    /// Send the next set point temp once it's time to start heating for it
+    fn recover(&mut self, now: NaiveDateTime) -> Result<()> {
+        let start = self.start_time();
+        if start == self.start {
+            return Ok(());
+        }
+        self.start = start;
+
+        let (Some(start), Some(next)) = (start, self.next) else {
+            self.event_sender.send_event(Event::CancelTimer(TimerId::Recovery))?;
+            return Ok(());
+        };
+
+        match (start - now).to_std() {
+            Ok(wait) if !wait.is_zero() => {
+                self.event_sender.send_event(Event::TimeoutReset(TimerId::Recovery, wait))?;
+            }
+            _ => {
+                info!("Recovery started for {} at {}", next.temp, next.time);
+                self.recovered = Some(next.time);
+                self.event_sender.send_event(Event::ScheduledTargetTemp(next.temp))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Time to start heating for the next set point, `None` when the set
+    /// point doesn't need heating or recovery doesn't apply
+    fn start_time(&self) -> Option<NaiveDateTime> {
+        let (Some(next), Some(rate)) = (self.next, self.rate) else {
+            return None;
+        };
+
+        let state = &self.state;
+        if !self.config.enabled
+            || state.mode != HvacMode::Heat
+            || state.away
+            || state.hold
+            || self.recovered == Some(next.time)
+            || next.temp <= state.target_temp
+        {
+            return None;
+        }
+
+        let rise = next.temp.celsius() - state.current_temp.celsius();
+        if rise <= 0.0 {
+            return None;
+        }
+
+        let lead = rate.lead_time(rise).min(self.config.max_lead);
+        Some(next.time - TimeDelta::from_std(lead).ok()?)
+    }
+}
+
+impl<S: EventSender> EventHandler for SmartRecovery<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<()> {
+        match event {
+            Event::State(state) => {
+                self.update_run(state, Instant::now())?;
+                self.state = state.clone();
+                self.recover(Local::now().naive_local())?;
+            }
+            Event::NextSetPoint(next) => {
+                self.next = *next;
+                self.recover(Local::now().naive_local())?;
+            }
+            Event::HeatingRate(rate) => {
+                self.rate = Some(*rate);
+                self.recover(Local::now().naive_local())?;
+            }
+            Event::TimeoutReached(TimerId::Recovery) => {
+                self.start = None;
+                self.recover(Local::now().naive_local())?;
+            }
+            Event::ConfigReloaded(reloaded) => {
+                self.config = reloaded.config.recovery.clone();
+                self.recover(Local::now().naive_local())?;
+            }
+            _ => { }
+        }
+
+        Ok(())
+    }
+
+    fn topics(&self) -> &'static [Topic] {
+        &[Topic::State, Topic::Timer]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        events::{DefaultEventSource, EventSource, QueueSender},
+        temperature::Temperature
+    };
+
+    fn recovery() -> (DefaultEventSource, SmartRecovery<QueueSender>) {
+        let config = RecoveryConfig {
+            enabled: true,
+            ..RecoveryConfig::default()
+        };
+
+        let event_source = DefaultEventSource::new();
+        let recovery = SmartRecovery::new(&config, event_source.event_sender());
+
+        (event_source, recovery)
+    }
+
+    fn heat_state(current: f32, action: HvacAction) -> ThermostatState {
+        ThermostatState {
+            mode: HvacMode::Heat,
+            target_temp: Temperature::from_celsius(17.0),
+            current_temp: Temperature::from_celsius(current),
+            action,
+            ..ThermostatState::default()
+        }
+    }
+
+    #[test]
+    fn lead_time_with_tiny_rate() {
+        let rate = HeatingRate { degrees_per_hour: 1e-30, runs: 1 };
+        assert_eq!(rate.lead_time(1.0), Duration::from_mins(600));
+
+        let rate = HeatingRate { degrees_per_hour: 2.0, runs: 1 };
+        assert_eq!(rate.lead_time(1.0), Duration::from_mins(30));
+    }
+
+    #[test]
+    fn learns_heating_rate() -> Result<()> {
+        let (mut events, mut recovery) = recovery();
+        let start = Instant::now();
+
+        // 1 degree in 30 mins
+        recovery.update_run(&heat_state(17.0, HvacAction::Heating), start)?;
+        recovery.update_run(&heat_state(18.0, HvacAction::Idle), start + Duration::from_mins(30))?;
+        assert_eq!(recovery.rate, Some(HeatingRate { degrees_per_hour: 2.0, runs: 1 }));
+        assert!(matches!(events.poll_event()?, Some(Event::HeatingRate(rate)) if rate.runs == 1));
+
+        // short runs are skipped
+        recovery.update_run(&heat_state(18.0, HvacAction::Heating), start)?;
+        recovery.update_run(&heat_state(18.5, HvacAction::Idle), start + Duration::from_mins(5))?;
+        assert_eq!(recovery.rate.unwrap().runs, 1);
+
+        // 1 degree in an hour moves the rate a fifth of the way
+        recovery.update_run(&heat_state(17.0, HvacAction::Heating), start)?;
+        recovery.update_run(&heat_state(18.0, HvacAction::Idle), start + Duration::from_hours(1))?;
+        let rate = recovery.rate.unwrap();
+        assert!((rate.degrees_per_hour - 1.8).abs() < 0.001);
+        assert_eq!(rate.runs, 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn starts_before_set_point() -> Result<()> {
+        let (mut events, mut recovery) = recovery();
+
+        let set_point = chrono::NaiveDate::from_ymd_opt(2026, 2, 23).unwrap()
+            .and_hms_opt(7, 0, 0).unwrap();
+        recovery.next = Some(NextSetPoint { time: set_point, temp: Temperature::from_celsius(20.0) });
+        recovery.rate = Some(HeatingRate { degrees_per_hour: 2.0, runs: 1 });
+        recovery.state = heat_state(17.0, HvacAction::Idle);
+
+        // 3 degrees at 2 an hour starts at 5:30
+        recovery.recover(set_point - TimeDelta::hours(2))?;
+        assert!(matches!(events.poll_event()?, Some(Event::TimeoutReset(TimerId::Recovery, wait))
+            if wait == Duration::from_mins(30)));
+
+        // timer reached
+        recovery.start = None;
+        recovery.recover(set_point - TimeDelta::minutes(90))?;
+        assert!(matches!(events.poll_event()?, Some(Event::ScheduledTargetTemp(temp))
+            if temp == Temperature::from_celsius(20.0)));
+
+        // only once for each set point
+        recovery.recover(set_point - TimeDelta::minutes(80))?;
+        assert!(matches!(events.poll_event()?, Some(Event::CancelTimer(TimerId::Recovery))));
+        assert_eq!(events.poll_event()?, None);
+
+        Ok(())
+    }
+}
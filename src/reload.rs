@@ -0,0 +1,313 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{fmt, fs, io, path::PathBuf, sync::Arc, thread};
+
+use anyhow::{Result, anyhow};
+use log::{error, info};
+use nix::sys::signal::{SigSet, Signal};
+
+use crate::{
+    config::{Config, WireConfig},
+    events::{Event, EventHandler, EventSender, Topic},
+    logging,
+    theme::Theme
+};
+
+/// Config and theme re-read from disk, sent with [`Event::ConfigReloaded`].
+///
+/// Backplate, Home Assistant, and window settings are only read at startup,
+/// changes to those still require a restart. The exception is the Home
+/// Assistant encryption key, which can be changed but not added or removed.
+pub struct ReloadedConfig {
+    pub config: Config,
+    pub theme: Theme,
+    /// Name of `theme`, see [`Config::theme`]
+    pub theme_name: String
+}
+
+impl fmt::Debug for ReloadedConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReloadedConfig")
+            .field("config", &self.config)
+            .field("theme_name", &self.theme_name)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Re-reads the config and theme files on [`Event::ReloadConfig`], and
+/// loads another theme on [`Event::SetTheme`]. Wiring from the wiring setup
+/// is written to the config file on [`Event::SaveWiring`].
+pub struct ConfigReloader<S> {
+    config_file: Option<PathBuf>,
+    theme_file: Option<PathBuf>,
+    /// Theme in use, kept on reload unless the theme in the config changes
+    theme_name: String,
+    /// Theme named by the config when it was last loaded
+    config_theme: String,
+    event_sender: S
+}
+
+impl<S: EventSender> ConfigReloader<S> {
+    pub fn new(
+        config_file: Option<PathBuf>,
+        theme_file: Option<PathBuf>,
+        theme_name: &str,
+        config_theme: &str,
+        event_sender: S
+    ) -> Self {
+        Self {
+            config_file,
+            theme_file,
+            theme_name: theme_name.to_string(),
+            config_theme: config_theme.to_string(),
+            event_sender
+        }
+    }
+
+    /// Load config and the theme `theme_name`, or the current theme
+    fn load(&self, theme_name: Option<&str>) -> Result<ReloadedConfig> {
+        let config = match &self.config_file {
+            Some(file_path) => Config::load(file_path)?,
+            None => Config::default()
+        };
+
+        let theme_name = match theme_name {
+            Some(name) => name,
+            None if config.theme != self.config_theme => &config.theme,
+            None => &self.theme_name
+        };
+        let theme = Theme::load_named(theme_name, &config.theme_dir, self.theme_file.as_deref())?;
+
+        Ok(ReloadedConfig { theme_name: theme_name.to_string(), config, theme })
+    }
+
+    fn reload(&mut self, theme_name: Option<&str>) -> Result<()> {
+        // Keep running with the current config when the new one is invalid
+        match self.load(theme_name) {
+            Ok(reloaded) => {
+                info!("Config reloaded from {:?}, theme {}", self.config_file, reloaded.theme_name);
+                logging::apply_config(&reloaded.config.log);
+                self.theme_name = reloaded.theme_name.clone();
+                self.config_theme = reloaded.config.theme.clone();
+                self.event_sender.send_event(Event::ConfigReloaded(Arc::new(reloaded)))?;
+            }
+            Err(e) => {
+                error!("Config reload failed: {e}");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<S> ConfigReloader<S> {
+    /// Merge the wiring into the config file, keeping the other settings
+    /// and any wires set in the file but not in `wiring`, unless they're
+    /// now used for another role. Comments aren't kept, so the previous file
+    /// is copied to `.bak` first.
+    fn save_wiring(&self, wiring: &WireConfig) -> Result<PathBuf> {
+        let Some(file_path) = &self.config_file else {
+            return Err(anyhow!("No config file to save to"));
+        };
+
+        let toml_src = match fs::read_to_string(file_path) {
+            Ok(toml_src) => toml_src,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into())
+        };
+
+        let mut table: toml::Table = toml::from_str(&toml_src)?;
+        let backplate = table.entry("backplate")
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+            .as_table_mut()
+            .ok_or_else(|| anyhow!("Config backplate isn't a table"))?;
+        let file_wiring = backplate.entry("wiring")
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+            .as_table_mut()
+            .ok_or_else(|| anyhow!("Config backplate wiring isn't a table"))?;
+
+        let new_wiring = toml::Table::try_from(wiring)?;
+        let used: Vec<_> = new_wiring.values().cloned().collect();
+        for (key, value) in file_wiring.clone() {
+            if key.ends_with("_wire") && used.contains(&value) {
+                file_wiring.remove(&key);
+            }
+        }
+        // heat, cool and fan are always set by the wiring setup
+        for key in ["cool_wire", "fan_wire"] {
+            file_wiring.remove(key);
+        }
+        file_wiring.extend(new_wiring);
+
+        let new_src = toml::to_string(&table)?;
+        // the restart would fail with a config that doesn't load
+        Config::from_toml(&new_src)?;
+
+        if !toml_src.is_empty() {
+            let mut backup_path = file_path.clone().into_os_string();
+            backup_path.push(".bak");
+            fs::write(backup_path, &toml_src)?;
+        }
+        fs::write(file_path, new_src)?;
+
+        Ok(file_path.clone())
+    }
+}
+
+impl<S: EventSender> EventHandler for ConfigReloader<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<()> {
+        match event {
+            Event::ReloadConfig => self.reload(None)?,
+            Event::SetTheme(name) if *name != self.theme_name => self.reload(Some(name))?,
+            // backplate settings are only read at startup
+            Event::SaveWiring(wiring) => match self.save_wiring(wiring) {
+                Ok(file_path) => {
+                    info!("Wiring saved to {file_path:?}, restarting");
+                    self.event_sender.send_event(Event::Restart)?;
+                }
+                Err(e) => error!("Saving wiring failed: {e}")
+            }
+            _ => { }
+        }
+
+        Ok(())
+    }
+
+    fn topics(&self) -> &'static [Topic] {
+        &[Topic::Input, Topic::Backplate]
+    }
+}
+
+/// Block SIGHUP, SIGTERM and SIGINT so they can be waited on by
+/// [`start_signal_thread`].
+///
+/// Threads inherit the signal mask, call this before starting any threads.
+pub fn block_signals() -> Result<()> {
+    handled_signals().thread_block()?;
+    Ok(())
+}
+
+/// Send [`Event::ReloadConfig`] when the process receives SIGHUP, and
+/// [`Event::Quit`] on SIGTERM or SIGINT so the main loop can exit cleanly
+pub fn start_signal_thread<S>(event_sender: S)
+    where S: EventSender + Send + 'static
+{
+    thread::spawn(move || {
+        let signals = handled_signals();
+
+        loop {
+            match signals.wait() {
+                Ok(signal) => {
+                    let event = if signal == Signal::SIGHUP {
+                        Event::ReloadConfig
+                    } else {
+                        info!("Received {signal}, shutting down");
+                        Event::Quit
+                    };
+
+                    if let Err(e) = event_sender.send_event(event) {
+                        error!("Signal thread exiting: {e}");
+                        break;
+                    }
+                }
+                Err(e) => {
+                    error!("Signal wait failed: {e}");
+                    break;
+                }
+            }
+        }
+    });
+}
+
+fn handled_signals() -> SigSet {
+    let mut signals = SigSet::empty();
+    signals.add(Signal::SIGHUP);
+    signals.add(Signal::SIGTERM);
+    signals.add(Signal::SIGINT);
+    signals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::WireId, events::{DefaultEventSource, EventSource}};
+
+    #[test]
+    fn save_wiring_keeps_settings() {
+        let file_path = std::env::temp_dir()
+            .join(format!("retherm-wiring-{}.toml", std::process::id()));
+        fs::write(&file_path, "temp_deadband = 0.8\n\n[backplate]\nserial_port = \"/dev/ttyS1\"\n").unwrap();
+
+        let events = DefaultEventSource::new();
+        let reloader = ConfigReloader::new(Some(file_path.clone()), None, "default", "default", events.event_sender());
+
+        let wiring = WireConfig::HeatAndCool {
+            heat_wire: WireId::W2,
+            cool_wire: Some(WireId::Y1),
+            fan_wire: Some(WireId::G),
+            heat2_wire: None,
+            cool2_wire: None,
+            aux_wire: None
+        };
+        reloader.save_wiring(&wiring).unwrap();
+
+        let config = Config::load(&file_path).unwrap();
+        let mut backup_path = file_path.clone().into_os_string();
+        backup_path.push(".bak");
+        let backup = fs::read_to_string(&backup_path).unwrap();
+        fs::remove_file(&file_path).unwrap();
+        fs::remove_file(&backup_path).unwrap();
+
+        assert_eq!(config.temp_deadband, 0.8);
+        assert_eq!(config.backplate.serial_port, "/dev/ttyS1");
+        assert_eq!(config.backplate.wiring.wires(), vec![WireId::W2, WireId::Y1, WireId::G]);
+        assert!(backup.starts_with("temp_deadband = 0.8"));
+    }
+
+    #[test]
+    fn save_wiring_merges_wires() {
+        let file_path = std::env::temp_dir()
+            .join(format!("retherm-wiring-merge-{}.toml", std::process::id()));
+        fs::write(&file_path, "[backplate.wiring]\ntype = \"HeatAndCool\"\nheat_wire = \"W1\"\n\
+            cool_wire = \"Y1\"\nfan_wire = \"G\"\nheat2_wire = \"W2\"\naux_wire = \"Star\"\n").unwrap();
+
+        let events = DefaultEventSource::new();
+        let reloader = ConfigReloader::new(Some(file_path.clone()), None, "default", "default", events.event_sender());
+
+        // heat only, now on the old stage 2 wire
+        let wiring = WireConfig::HeatAndCool {
+            heat_wire: WireId::W2,
+            cool_wire: None,
+            fan_wire: None,
+            heat2_wire: None,
+            cool2_wire: None,
+            aux_wire: None
+        };
+        reloader.save_wiring(&wiring).unwrap();
+
+        let config = Config::load(&file_path).unwrap();
+        let mut backup_path = file_path.clone().into_os_string();
+        backup_path.push(".bak");
+        fs::remove_file(&file_path).unwrap();
+        fs::remove_file(&backup_path).unwrap();
+
+        assert_eq!(config.backplate.wiring.wires(), vec![WireId::W2, WireId::Star]);
+        assert_eq!(config.backplate.wiring.cool_wire(), None);
+    }
+}
@@ -0,0 +1,164 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{collections::VecDeque, time::Duration};
+
+use chrono::{Days, NaiveDate};
+
+use crate::state::HvacAction;
+
+/// Days of runtime kept for the weekly totals
+const HISTORY_DAYS: u64 = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DailyRuntime {
+    pub day: NaiveDate,
+    pub heating: Duration,
+    pub cooling: Duration,
+}
+
+impl DailyRuntime {
+    pub fn new(day: NaiveDate) -> Self {
+        Self {
+            day,
+            heating: Duration::ZERO,
+            cooling: Duration::ZERO,
+        }
+    }
+}
+
+/// Heating and cooling runtime for each day of the last week
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RuntimeStats {
+    /// Oldest day first, days without a reading are missing
+    days: VecDeque<DailyRuntime>,
+}
+
+impl RuntimeStats {
+    pub fn new(days: impl IntoIterator<Item = DailyRuntime>) -> Self {
+        Self {
+            days: days.into_iter().collect()
+        }
+    }
+
+    pub fn days(&self) -> impl Iterator<Item = &DailyRuntime> {
+        self.days.iter()
+    }
+
+    /// Runtime for `day`, zero when nothing was recorded
+    pub fn day(&self, day: NaiveDate) -> DailyRuntime {
+        self.days.iter()
+            .find(|runtime| runtime.day == day)
+            .copied()
+            .unwrap_or(DailyRuntime::new(day))
+    }
+
+    /// Total heating and cooling runtime for the week ending on `day`
+    pub fn week(&self, day: NaiveDate) -> (Duration, Duration) {
+        self.days.iter()
+            .filter(|runtime| runtime.day <= day && in_week(runtime.day, day))
+            .fold((Duration::ZERO, Duration::ZERO), |(heating, cooling), runtime| {
+                (heating + runtime.heating, cooling + runtime.cooling)
+            })
+    }
+
+    /// Add `elapsed` time spent on `action` to `day`, dropping days that
+    /// are more than a week old. Returns true if the stats changed.
+    pub fn record(&mut self, action: HvacAction, elapsed: Duration, day: NaiveDate) -> bool {
+        let mut changed = false;
+
+        if self.days.back().map(|runtime| runtime.day) != Some(day) {
+            self.days.push_back(DailyRuntime::new(day));
+            changed = true;
+        }
+
+        while self.days.front().is_some_and(|runtime| !in_week(runtime.day, day)) {
+            self.days.pop_front();
+        }
+
+        // back was just set to today above
+        let today = self.days.back_mut().unwrap();
+        let total = match action {
+            HvacAction::Heating => &mut today.heating,
+            HvacAction::Cooling => &mut today.cooling,
+            _ => return changed
+        };
+
+        if !elapsed.is_zero() {
+            *total += elapsed;
+            changed = true;
+        }
+
+        changed
+    }
+}
+
+fn in_week(day: NaiveDate, today: NaiveDate) -> bool {
+    today.checked_sub_days(Days::new(HISTORY_DAYS))
+        .is_none_or(|week_ago| day > week_ago)
+}
+
+/// Format runtime as hours and minutes, e.g. "2h 05m"
+pub fn format_runtime(runtime: Duration) -> String {
+    let minutes = runtime.as_secs() / 60;
+    format!("{}h {:02}m", minutes / 60, minutes % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn starts_new_day() {
+        let mut stats = RuntimeStats::default();
+
+        let today = NaiveDate::from_ymd_opt(2026, 1, 10).unwrap();
+        let tomorrow = today.succ_opt().unwrap();
+        let minutes = |m: u64| Duration::from_secs(m * 60);
+
+        assert!(stats.record(HvacAction::Heating, minutes(10), today));
+        assert!(stats.record(HvacAction::Cooling, minutes(5), today));
+        assert!(!stats.record(HvacAction::Idle, minutes(30), today));
+        assert!(stats.record(HvacAction::Heating, minutes(20), today));
+        assert_eq!(stats.day(today).heating, minutes(30));
+        assert_eq!(stats.day(today).cooling, minutes(5));
+
+        // idle past midnight still starts the new day
+        assert!(stats.record(HvacAction::Idle, minutes(1), tomorrow));
+        assert_eq!(stats.day(tomorrow), DailyRuntime::new(tomorrow));
+
+        assert!(stats.record(HvacAction::Heating, minutes(15), tomorrow));
+        assert_eq!(stats.week(tomorrow), (minutes(45), minutes(5)));
+    }
+
+    #[test]
+    fn keeps_one_week() {
+        let mut stats = RuntimeStats::default();
+        let first = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap();
+
+        for offset in 0..10 {
+            let day = first.checked_add_days(Days::new(offset)).unwrap();
+            stats.record(HvacAction::Heating, Duration::from_secs(60), day);
+        }
+
+        let last = first.checked_add_days(Days::new(9)).unwrap();
+        assert_eq!(stats.days().count(), HISTORY_DAYS as usize);
+        assert_eq!(stats.days().next().map(|runtime| runtime.day), first.checked_add_days(Days::new(3)));
+        assert_eq!(stats.week(last), (Duration::from_secs(7 * 60), Duration::ZERO));
+    }
+}
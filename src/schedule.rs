@@ -20,55 +20,138 @@ use anyhow::Result;
 use log::info;
 
 use crate::{
-    config::Config,
-    events::{Event, EventHandler, EventSender},
-    state::HvacMode
+    config::{Config, ScheduleConfig},
+    events::{Event, EventHandler, EventSender, Topic},
+    state::{ClimateCommand, HvacMode}
 };
 
+mod schedule_edit;
 mod schedule_model;
 mod schedule_thread;
 
+pub use schedule_edit::{EditedSchedule, ScheduleEdit};
+pub use schedule_model::NextSetPoint;
 use schedule_model::Schedule;
 use schedule_thread::ScheduleThread;
 
 pub struct ScheduleManager<S> {
     event_sender: S,
     schedule_thread: Option<ScheduleThread>,
-    config: Config
+    config: Config,
+    /// Mode of the running schedule, to restart it when config is reloaded,
+    /// or schedule is edited
+    mode: Option<HvacMode>,
+    /// Schedules edited from Home Assistant, replacing the config schedules
+    edited: Option<EditedSchedule>
 }
 
 impl<S: EventSender + Clone + Send + 'static> ScheduleManager<S> {
-    pub fn new(config: &Config, event_sender: S) -> Self {
+    pub fn new(config: &Config, edited: Option<EditedSchedule>, event_sender: S) -> Self {
         Self {
             event_sender,
             schedule_thread: None,
-            config: config.clone()
+            config: config.clone(),
+            mode: None,
+            edited
         }
     }
 
-    pub fn start_schedule(&mut self, mode: &HvacMode) {
+    fn schedule_for_mode(&self, mode: &HvacMode) -> Option<&[ScheduleConfig]> {
+        match &self.edited {
+            Some(edited) => edited.for_mode(mode),
+            None => self.config.schedule_for_mode(mode)
+        }
+    }
+
+    fn edit_schedule(&mut self, edit: &ScheduleEdit) -> Result<()> {
+        match edit {
+            ScheduleEdit::Reset => {
+                self.edited = None;
+            }
+            edit => {
+                self.edited
+                    .get_or_insert_with(|| EditedSchedule::from_config(&self.config))
+                    .apply(edit);
+            }
+        }
+
+        self.event_sender.send_event(Event::ScheduleChanged(self.edited.clone()))?;
+
+        if let Some(mode) = self.mode {
+            self.start_schedule(&mode)?;
+        }
+
+        Ok(())
+    }
+
+    /// True when the running schedule has set points that switch the mode
+    fn schedule_sets_mode(&self) -> bool {
+        self.schedule_thread.is_some() && self.mode
+            .and_then(|mode| self.schedule_for_mode(&mode))
+            .is_some_and(|schedule| schedule.iter()
+                .flat_map(|s| &s.set_points)
+                .any(|p| p.mode.is_some()))
+    }
+
+    fn switch_mode(&mut self, mode: &HvacMode) -> Result<()> {
+        if self.mode == Some(*mode) {
+            return Ok(());
+        }
+
+        // keep a schedule that switches to a mode without a schedule running
+        // (i.e. off overnight), so it can switch the mode back later
+        if self.schedule_for_mode(mode).is_none() && self.schedule_sets_mode() {
+            info!("Keep schedule running in {mode:?} mode");
+            return Ok(());
+        }
+
+        self.start_schedule(mode)
+    }
+
+    pub fn start_schedule(&mut self, mode: &HvacMode) -> Result<()> {
+        self.mode = Some(*mode);
+
         if let Some(thread) = self.schedule_thread.take() {
             info!("Stop schedule clock thread");
-            thread.stop()
-                .expect("Schedule thread should stop");
+            thread.stop();
         }
 
-        if let Some(schedule) = self.config.schedule_for_mode(mode) {
+        if let Some(schedule) = self.schedule_for_mode(mode) {
             let schedule = Schedule::new(schedule);
             info!("Start schedule clock thread {:?}", schedule);
             let thread = ScheduleThread::start(schedule, self.event_sender.clone());
             self.schedule_thread = Some(thread);
         } else {
             info!("Empty schedule, skip clock thread");
+            self.event_sender.send_event(Event::NextSetPoint(None))?;
         }
+
+        Ok(())
     }
 }
 
 impl<S: EventSender + Clone + Send + 'static> EventHandler for ScheduleManager<S> {
     fn handle_event(&mut self, event: &Event) -> Result<()> {
-        if let Event::SetMode(mode) = event {
-            self.start_schedule(mode);
+        match event {
+            Event::SetMode(mode)
+                | Event::ClimateCommand(ClimateCommand { mode: Some(mode), .. }) => {
+                self.switch_mode(mode)?;
+            }
+            Event::ConfigReloaded(reloaded) => {
+                self.config = reloaded.config.clone();
+                if let Some(mode) = self.mode {
+                    self.start_schedule(&mode)?;
+                }
+            }
+            Event::EditSchedule(edit) => {
+                self.edit_schedule(edit)?;
+            }
+            _ => { }
         }
         Ok(())
     }
+
+    fn topics(&self) -> &'static [Topic] {
+        &[Topic::State]
+    }
 }
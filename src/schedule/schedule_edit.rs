@@ -0,0 +1,233 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::str::FromStr;
+
+use anyhow::{Result, anyhow};
+use chrono::{NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{Config, DaysOfWeek, ScheduleConfig, SetPoint, WeekDay, WeekDayRange},
+    state::{HvacMode, ThermostatState},
+    temperature::Temperature
+};
+use super::schedule_model::{ScheduleMap, week_schedule};
+
+/// Change to the schedules, requested from Home Assistant
+#[derive(Debug, Clone)]
+pub enum ScheduleEdit {
+    /// Add a set point to each of the days, replacing one at the same time
+    SetPoint {
+        mode: HvacMode,
+        days: Vec<Weekday>,
+        time: NaiveTime,
+        temp: Temperature
+    },
+    /// Remove the set point at `time` from each of the days
+    RemovePoint {
+        mode: HvacMode,
+        days: Vec<Weekday>,
+        time: NaiveTime
+    },
+    /// Discard edits and go back to the config file schedules
+    Reset
+}
+
+impl ScheduleEdit {
+    /// Parse "heat" or "cool"
+    pub fn parse_mode(mode: &str) -> Result<HvacMode> {
+        match mode.to_lowercase().as_str() {
+            "heat" => Ok(HvacMode::Heat),
+            "cool" => Ok(HvacMode::Cool),
+            _ => Err(anyhow!("Schedule mode must be heat or cool, got {mode:?}"))
+        }
+    }
+
+    /// Parse "EveryDay", "WeekDays", "WeekEnd", or a list like "Mon,Wed,Fri"
+    pub fn parse_days(days: &str) -> Result<Vec<Weekday>> {
+        let range = match days {
+            "EveryDay" => Some(WeekDayRange::EveryDay),
+            "WeekDays" => Some(WeekDayRange::WeekDays),
+            "WeekEnd" => Some(WeekDayRange::WeekEnd),
+            _ => None
+        };
+
+        if let Some(range) = range {
+            return Ok(DaysOfWeek::Range(range).normalize());
+        }
+
+        days.split(',')
+            .map(|day| Weekday::from_str(day.trim())
+                .map_err(|_| anyhow!("Invalid day of week {day:?}")))
+            .collect()
+    }
+
+    /// Parse time of day as "hh:mm"
+    pub fn parse_time(time: &str) -> Result<NaiveTime> {
+        Ok(NaiveTime::parse_from_str(time, "%H:%M")?)
+    }
+
+    /// Set point temp in celsius, within the thermostat's min/max temp
+    pub fn parse_temp(celsius: f32) -> Result<Temperature> {
+        let temp = Temperature::from_celsius(celsius);
+        if celsius.is_finite() && temp >= ThermostatState::MIN_TEMP && temp <= ThermostatState::MAX_TEMP {
+            Ok(temp)
+        } else {
+            Err(anyhow!(
+                "Schedule temp must be {} to {}, got {celsius}",
+                ThermostatState::MIN_TEMP.celsius(), ThermostatState::MAX_TEMP.celsius()
+            ))
+        }
+    }
+}
+
+/// Schedules edited from Home Assistant, used instead of the schedules
+/// in the config file until reset
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct EditedSchedule {
+    pub heat: Vec<ScheduleConfig>,
+    pub cool: Vec<ScheduleConfig>
+}
+
+impl EditedSchedule {
+    /// Start editing from the config file schedules
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            heat: config.schedule_heat.clone(),
+            cool: config.schedule_cool.clone()
+        }
+    }
+
+    pub fn for_mode(&self, mode: &HvacMode) -> Option<&[ScheduleConfig]> {
+        let schedule = match mode {
+            HvacMode::Heat | HvacMode::EmergencyHeat => &self.heat,
+            HvacMode::Cool => &self.cool,
+            _ => return None
+        };
+
+        if schedule.is_empty() {
+            None
+        } else {
+            Some(schedule)
+        }
+    }
+
+    pub fn apply(&mut self, edit: &ScheduleEdit) {
+        match edit {
+            ScheduleEdit::SetPoint { mode, days, time, temp } => {
+                self.edit_week(mode, |week| {
+                    for day in days {
+                        let set_point = SetPoint { time: *time, temp: *temp, mode: None, fan: None };
+                        week.entry(*day).or_default().insert(*time, set_point);
+                    }
+                });
+            }
+            ScheduleEdit::RemovePoint { mode, days, time } => {
+                self.edit_week(mode, |week| {
+                    for day in days {
+                        if let Some(set_points) = week.get_mut(day) {
+                            set_points.remove(time);
+                        }
+                    }
+                });
+            }
+            ScheduleEdit::Reset => { }
+        }
+    }
+
+    fn edit_week(&mut self, mode: &HvacMode, edit: impl FnOnce(&mut ScheduleMap)) {
+        let schedule = match mode {
+            HvacMode::Heat => &mut self.heat,
+            HvacMode::Cool => &mut self.cool,
+            _ => return
+        };
+
+        let mut week = week_schedule(schedule);
+        edit(&mut week);
+        *schedule = schedule_config(week);
+    }
+}
+
+/// One schedule entry per day with set points in time order
+fn schedule_config(week: ScheduleMap) -> Vec<ScheduleConfig> {
+    let mut days: Vec<_> = week.into_iter()
+        .filter(|(_, set_points)| !set_points.is_empty())
+        .collect();
+    days.sort_by_key(|(day, _)| day.num_days_from_monday());
+
+    days.into_iter()
+        .map(|(day, set_points)| {
+            let mut set_points: Vec<_> = set_points.into_values().collect();
+            set_points.sort_by_key(|p| p.time);
+
+            ScheduleConfig {
+                days_of_week: DaysOfWeek::List(vec![WeekDay::from_chrono(day)]),
+                set_points
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn temp_out_of_range() -> Result<()> {
+        assert_eq!(ScheduleEdit::parse_temp(21.5)?, Temperature::from_celsius(21.5));
+        assert!(ScheduleEdit::parse_temp(f32::NAN).is_err());
+        assert!(ScheduleEdit::parse_temp(f32::INFINITY).is_err());
+        assert!(ScheduleEdit::parse_temp(500.0).is_err());
+        assert!(ScheduleEdit::parse_temp(-5.0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn set_and_remove_point() -> Result<()> {
+        let mut schedule = EditedSchedule::default();
+
+        schedule.apply(&ScheduleEdit::SetPoint {
+            mode: HvacMode::Heat,
+            days: ScheduleEdit::parse_days("WeekEnd")?,
+            time: ScheduleEdit::parse_time("09:30")?,
+            temp: Temperature::from_celsius(21.0)
+        });
+        assert_eq!(schedule.heat.len(), 2);
+        assert!(schedule.for_mode(&HvacMode::Cool).is_none());
+
+        schedule.apply(&ScheduleEdit::RemovePoint {
+            mode: HvacMode::Heat,
+            days: ScheduleEdit::parse_days("Sat")?,
+            time: ScheduleEdit::parse_time("09:30")?
+        });
+        assert_eq!(schedule.heat.len(), 1);
+        assert!(matches!(
+            &schedule.heat[0].days_of_week,
+            DaysOfWeek::List(days) if matches!(days[..], [WeekDay::Sun])
+        ));
+
+        // round trips through the schedule file
+        let toml_src = toml::to_string(&schedule)?;
+        let parsed: EditedSchedule = toml::from_str(&toml_src)?;
+        assert_eq!(parsed.heat[0].set_points[0].time, ScheduleEdit::parse_time("09:30")?);
+
+        Ok(())
+    }
+}
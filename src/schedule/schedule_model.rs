@@ -18,18 +18,25 @@
 
 use std::{collections::HashMap, time::Duration};
 
-use chrono::prelude::*;
+use chrono::{Days, prelude::*};
 use log::info;
 
-use crate::config::ScheduleConfig;
+use crate::{config::{ScheduleConfig, SetPoint}, temperature::Temperature};
 
-type ScheduleMap = HashMap<Weekday, HashMap<NaiveTime, f32>>;
+pub(super) type ScheduleMap = HashMap<Weekday, HashMap<NaiveTime, SetPoint>>;
+
+/// Upcoming scheduled change of the target temp
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NextSetPoint {
+    pub time: NaiveDateTime,
+    pub temp: Temperature
+}
 
 #[derive(Debug)]
 pub struct Schedule {
     schedule: ScheduleMap,
     max_age: Duration,
-    last_set_point: Option<f32>
+    last_set_point: Option<SetPoint>
 }
 
 impl Schedule {
@@ -42,12 +49,12 @@ impl Schedule {
         }
     }
 
-    pub fn get_target_temp(&mut self, now: DateTime<Local>) -> Option<f32> {
+    pub fn get_set_point(&mut self, now: DateTime<Local>) -> Option<SetPoint> {
         let weekday = now.weekday();
         let time_of_day = now.time();
 
         if let Some(set_points) = self.schedule.get(&weekday) {
-            for (set_point_time, set_point_temp) in set_points {
+            for (set_point_time, set_point) in set_points {
                 // test if set point has been reached, or
                 if time_of_day >= *set_point_time
                     // consider set point reached if time is within small range
@@ -56,9 +63,9 @@ impl Schedule {
                     // don't repreat reporting setpoint more than once
                     && self.last_set_point.is_none()
                 {
-                    info!("Set point reached {set_point_time} {set_point_temp}");
-                    self.last_set_point = Some(*set_point_temp);
-                    return Some(*set_point_temp);
+                    info!("Set point reached {set_point_time} {}", set_point.temp);
+                    self.last_set_point = Some(set_point.clone());
+                    return Some(set_point.clone());
                 }
             }
         }
@@ -66,9 +73,22 @@ impl Schedule {
         self.last_set_point = None;
         None
     }
+
+    /// First set point after `now`, looking ahead one week
+    pub fn next_set_point(&self, now: NaiveDateTime) -> Option<NextSetPoint> {
+        (0..=7).find_map(|offset| {
+            let date = now.date() + Days::new(offset);
+            let set_points = self.schedule.get(&date.weekday())?;
+
+            set_points.iter()
+                .map(|(time, p)| NextSetPoint { time: date.and_time(*time), temp: p.temp })
+                .filter(|set_point| set_point.time > now)
+                .min_by_key(|set_point| set_point.time)
+        })
+    }
 }
 
-fn week_schedule(schedule: &[ScheduleConfig]) -> ScheduleMap {
+pub(super) fn week_schedule(schedule: &[ScheduleConfig]) -> ScheduleMap {
     let mut week_schedule = HashMap::new();
 
     for s in schedule {
@@ -79,9 +99,7 @@ fn week_schedule(schedule: &[ScheduleConfig]) -> ScheduleMap {
 
             let day_schedle = week_schedule.get_mut(&day).unwrap();
             for p in &s.set_points {
-                day_schedle.entry(p.time)
-                    .and_modify(|temp| *temp = p.temp)
-                    .insert_entry(p.temp);
+                day_schedle.insert(p.time, p.clone());
             }
         }
     }
@@ -93,7 +111,11 @@ fn week_schedule(schedule: &[ScheduleConfig]) -> ScheduleMap {
 mod tests {
     use chrono::{Duration, prelude::*};
 
-    use crate::config::{DaysOfWeek, ScheduleConfig, SetPoint, WeekDayRange};
+    use crate::{
+        config::{DaysOfWeek, ScheduleConfig, SetPoint, WeekDayRange},
+        state::HvacMode,
+        temperature::Temperature
+    };
     use super::Schedule;
 
     fn daily_morning_temp_increase() -> Schedule {
@@ -103,11 +125,15 @@ mod tests {
                 set_points: vec![
                     SetPoint {
                         time: NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
-                        temp: 20.0
+                        temp: Temperature::from_celsius(20.0),
+                        mode: None,
+                        fan: None
                     },
                     SetPoint {
                         time: NaiveTime::from_hms_opt(10, 0, 0).unwrap(),
-                        temp: 16.0
+                        temp: Temperature::from_celsius(16.0),
+                        mode: Some(HvacMode::Off),
+                        fan: None
                     }
                 ]
             }
@@ -124,14 +150,32 @@ mod tests {
 
         let mut date = Local.with_ymd_and_hms(2026, 2, 23, 8, 0, 0).unwrap();
 
-        assert_eq!(schedule.get_target_temp(date), Some(20.0));
+        assert_eq!(schedule.get_set_point(date).map(|p| p.temp), Some(Temperature::from_celsius(20.0)));
 
         date = tick(date);
 
-        assert_eq!(schedule.get_target_temp(date), None);
+        assert_eq!(schedule.get_set_point(date), None);
+
+        let date = Local.with_ymd_and_hms(2026, 2, 23, 10, 0, 0).unwrap();
+        let set_point = schedule.get_set_point(date).unwrap();
+        assert_eq!(set_point.temp, Temperature::from_celsius(16.0));
+        assert_eq!(set_point.mode, Some(HvacMode::Off));
+    }
+
+    #[test]
+    fn next_set_point() {
+        let schedule = daily_morning_temp_increase();
+
+        let date = Local.with_ymd_and_hms(2026, 2, 23, 9, 0, 0).unwrap();
+        let next = schedule.next_set_point(date.naive_local()).unwrap();
+        assert_eq!(next.time.time(), NaiveTime::from_hms_opt(10, 0, 0).unwrap());
+        assert_eq!(next.temp, Temperature::from_celsius(16.0));
 
+        // wraps to tomorrow morning
         let date = Local.with_ymd_and_hms(2026, 2, 23, 10, 0, 0).unwrap();
-        assert_eq!(schedule.get_target_temp(date), Some(16.0));
+        let next = schedule.next_set_point(date.naive_local()).unwrap();
+        assert_eq!(next.time.date().day(), 24);
+        assert_eq!(next.temp, Temperature::from_celsius(20.0));
     }
 
     #[test]
@@ -140,7 +184,7 @@ mod tests {
 
         let mut date = Local.with_ymd_and_hms(2026, 2, 23, 7, 59, 59).unwrap();
 
-        assert_eq!(schedule.get_target_temp(date), None);
+        assert_eq!(schedule.get_set_point(date), None);
 
         // clock reaches next set point
         date = tick(date);
@@ -148,6 +192,6 @@ mod tests {
         // next tick advances one sec past set point
         date = tick(date);
 
-        assert_eq!(schedule.get_target_temp(date), Some(20.0));
+        assert_eq!(schedule.get_set_point(date).map(|p| p.temp), Some(Temperature::from_celsius(20.0)));
     }
 }
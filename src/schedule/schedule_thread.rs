@@ -17,39 +17,55 @@
  */
 
 use std::{
-    thread,
     sync::mpsc::{RecvTimeoutError, Sender, channel},
     time::Duration
 };
 
-use anyhow::Result;
 use chrono::Local;
+use log::info;
 
-use crate::events::{Event, EventSender};
+use crate::{events::{Event, EventSender}, supervisor::{Subsystem, supervise}};
 use super::schedule_model::Schedule;
 
+/// Sends the scheduled target temp, mode and fan when a set point is reached.
+/// The thread exits when this handle is stopped or dropped, or when the event
+/// channel closes, and is restarted if it fails.
 pub struct ScheduleThread {
     sender: Sender<()>
 }
 
 impl ScheduleThread {
     pub fn start<S>(mut schedule: Schedule, event_sender: S) -> Self
-        where S: EventSender + Send + 'static
+        where S: EventSender + Clone + Send + 'static
     {
         let tick_delay = Duration::from_secs(1);
 
         let (sender, receiver) = channel();
 
-        thread::spawn(move || {
+        supervise(Subsystem::Schedule, event_sender.clone(), move || {
+            let next = schedule.next_set_point(Local::now().naive_local());
+            event_sender.send_event(Event::NextSetPoint(next))?;
+
             loop {
-                if let Some(temp) = schedule.get_target_temp(Local::now()) {
-                    event_sender.send_event(Event::SetTargetTemp(temp))
-                        .expect("Schedule event sender should send");
+                if let Some(set_point) = schedule.get_set_point(Local::now()) {
+                    let next = schedule.next_set_point(Local::now().naive_local());
+
+                    // mode first, so the target temp applies to the new mode
+                    let events = set_point.mode.map(Event::SetMode).into_iter()
+                        .chain(set_point.fan.map(Event::SetFanMode))
+                        .chain([Event::ScheduledTargetTemp(set_point.temp), Event::NextSetPoint(next)]);
+
+                    // receiver is gone when the app is shutting down
+                    if let Err(e) = events.into_iter().try_for_each(|e| event_sender.send_event(e)) {
+                        info!("Schedule thread exiting, event channel closed: {e}");
+                        return Ok(());
+                    }
                 }
 
+                // stop signal, or handle dropped
                 match receiver.recv_timeout(tick_delay) {
                     Err(RecvTimeoutError::Timeout) => continue,
-                    _ => break
+                    _ => return Ok(())
                 }
             }
         });
@@ -57,7 +73,8 @@ impl ScheduleThread {
         Self { sender }
     }
 
-    pub fn stop(self) -> Result<()> {
-        Ok(self.sender.send(())?)
+    pub fn stop(self) {
+        // send fails when the thread already exited on its own
+        let _ = self.sender.send(());
     }
 }
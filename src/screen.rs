@@ -16,23 +16,113 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use crate::{drawable::AppDrawable, events::EventHandler, state::HvacMode};
+use anyhow::Result;
+use embedded_graphics::{prelude::*, text::{Alignment, Text}};
+
+use crate::{
+    alerts::Alert,
+    config::WireId,
+    drawable::{AppDrawable, AppFrameBuf},
+    events::EventHandler,
+    theme::DiagnosticsTheme
+};
 
 pub use self::{
+    action_log_screen::ActionLogScreen,
+    alert_screen::AlertScreen,
+    autotune_screen::AutotuneScreen,
+    deadband_screen::DeadbandScreen,
+    diagnostics_screen::DiagnosticsScreen,
+    fan_screen::FanScreen,
+    history_screen::HistoryScreen,
+    idle_clock_screen::IdleClockScreen,
+    log_screen::LogScreen,
     main_screen::MainScreen,
+    menu_screen::MenuScreen,
     mode_screen::ModeScreen,
-    screen_manager::ScreenManager
+    schedule_screen::ScheduleScreen,
+    screen_manager::ScreenManager,
+    settings_screen::SettingsScreen,
+    stats_screen::StatsScreen,
+    suggestion_screen::SuggestionScreen,
+    theme_screen::ThemeScreen,
+    wiring_screen::WiringScreen,
+    wiring_setup_screen::WiringSetupScreen
 };
 
+mod action_log_screen;
+mod alert_screen;
+mod autotune_screen;
+mod deadband_screen;
+mod diagnostics_screen;
+mod fan_screen;
+mod history_screen;
+mod idle_clock_screen;
+mod log_screen;
 mod main_screen;
+mod menu_screen;
 mod mode_screen;
+mod schedule_screen;
 mod screen_manager;
+mod settings_screen;
+mod stats_screen;
+mod suggestion_screen;
+mod theme_screen;
+mod wiring_screen;
+mod wiring_setup_screen;
 
 pub trait Screen: AppDrawable + EventHandler { }
 
+/// Line of text centered under the title of a list screen, in the
+/// secondary colour when `dim`
+fn draw_line(
+    theme: &DiagnosticsTheme,
+    target: &mut AppFrameBuf,
+    text: &str,
+    y: i32,
+    dim: bool
+) -> Result<()> {
+    let colour = if dim { theme.dim_colour } else { theme.fg_colour };
+    let font_style = theme.text_font.font_style(colour, theme.bg_colour);
+
+    let position = Point::new(theme.title_center.x, y);
+    Text::with_alignment(text, position, font_style, Alignment::Center)
+        .draw(target)?;
+
+    Ok(())
+}
+
 #[derive(Debug, Clone)]
 pub enum ScreenId {
-    ModeSelect {
-        current_mode: HvacMode
-    }
+    ModeSelect,
+    /// Navigation hub, opened by pressing the button on the main screen
+    Menu,
+    FanSelect,
+    Schedule,
+    Settings,
+    /// Named themes, opened from settings
+    ThemeSelect,
+    /// Autotune progress, opened from settings
+    Autotune,
+    /// Heat/cool deadband, opened from settings
+    Deadband,
+    /// Schedule set points suggested from setpoint adjustments, opened
+    /// from settings
+    Suggestions,
+    History,
+    /// Heating and cooling runtime
+    Stats,
+    Diagnostics,
+    Logs,
+    /// Recent hvac action switches, opened from settings
+    ActionLog,
+    /// Configured wires missing from the backplate, with the detected wires
+    WiringWarning {
+        missing: Vec<WireId>,
+        detected: String
+    },
+    /// Active alerts, shown when an alert is raised
+    Alerts(Vec<Alert>),
+    /// Pick and test the heat, cool and fan wires, opened from settings
+    WiringSetup
 }
@@ -0,0 +1,115 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use anyhow::Result;
+use embedded_graphics::{prelude::*, text::{Alignment, Text}};
+
+use crate::{
+    action_log::ActionLog,
+    drawable::{AppDrawable, AppFrameBuf},
+    events::{Event, EventHandler, EventSender},
+    state::HvacAction,
+    theme::DiagnosticsTheme
+};
+use super::{Screen, draw_line};
+
+/// Lines that fit inside the round display, below the title
+const VISIBLE_LINES: usize = 8;
+
+/// Recent hvac action switches with their cause, newest first, turning the
+/// dial scrolls back
+pub struct ActionLogScreen<S> {
+    log: ActionLog,
+    /// Entries scrolled back from the most recent
+    scroll: f32,
+    event_sender: S,
+    theme: DiagnosticsTheme
+}
+
+impl<S: EventSender> ActionLogScreen<S> {
+    pub fn new(theme: DiagnosticsTheme, log: ActionLog, event_sender: S) -> Self {
+        Self { log, scroll: 0.0, event_sender, theme }
+    }
+}
+
+fn action_label(action: HvacAction) -> &'static str {
+    match action {
+        HvacAction::Idle => "Idle",
+        HvacAction::Heating => "Heat",
+        HvacAction::Cooling => "Cool",
+        HvacAction::Fan => "Fan"
+    }
+}
+
+impl<S: EventSender> Screen for ActionLogScreen<S> { }
+
+impl<S: EventSender> EventHandler for ActionLogScreen<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<()> {
+        match event {
+            Event::ButtonDown => {
+                self.event_sender.send_event(Event::NavigateBack)?;
+            }
+            Event::Dial(dir) => {
+                let max_scroll = self.log.entries().count().saturating_sub(VISIBLE_LINES);
+                self.scroll = (self.scroll - *dir as f32 * 0.01).clamp(0.0, max_scroll as f32);
+            }
+            Event::ActionLog(log) => {
+                self.log = log.clone();
+            }
+            _ => { }
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: EventSender> AppDrawable for ActionLogScreen<S> {
+    fn draw(&self, target: &mut AppFrameBuf) -> Result<()> {
+        target.clear(self.theme.bg_colour)?;
+
+        let font_style = self.theme.title_font
+            .font_style(self.theme.fg_colour, self.theme.bg_colour);
+        Text::with_alignment("Activity", self.theme.title_center, font_style, Alignment::Center)
+            .draw(target)?;
+
+        let mut y = self.theme.title_center.y + self.theme.line_height;
+
+        if self.log.latest().is_none() {
+            return draw_line(&self.theme, target, "No activity", y, false);
+        }
+
+        let entries = self.log.entries().rev()
+            .skip(self.scroll as usize)
+            .take(VISIBLE_LINES);
+
+        for entry in entries {
+            let text = format!(
+                "{} {}>{} {}",
+                entry.time.format("%a %H:%M"),
+                action_label(entry.from),
+                action_label(entry.to),
+                entry.cause.label()
+            );
+
+            draw_line(&self.theme, target, &text, y, false)?;
+            y += self.theme.line_height;
+        }
+
+        Ok(())
+    }
+}
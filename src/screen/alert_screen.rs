@@ -0,0 +1,87 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use anyhow::Result;
+use embedded_graphics::{prelude::*, text::{Alignment, Text}};
+
+use crate::{
+    alerts::Alert,
+    drawable::{AppDrawable, AppFrameBuf},
+    events::{Event, EventHandler, EventSender},
+    theme::DiagnosticsTheme
+};
+use super::{Screen, draw_line};
+
+/// Warns about active alerts, shown until the alerts clear or the button
+/// is pressed to dismiss them
+pub struct AlertScreen<S> {
+    alerts: Vec<Alert>,
+    event_sender: S,
+    theme: DiagnosticsTheme
+}
+
+impl<S: EventSender> AlertScreen<S> {
+    pub fn new(theme: DiagnosticsTheme, alerts: Vec<Alert>, event_sender: S) -> Self {
+        Self { alerts, event_sender, theme }
+    }
+}
+
+impl<S: EventSender> Screen for AlertScreen<S> { }
+
+impl<S: EventSender> EventHandler for AlertScreen<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<()> {
+        match event {
+            Event::ButtonDown => {
+                self.event_sender.send_event(Event::NavigateBack)?;
+            }
+            Event::Alerts(alerts) => {
+                self.alerts = alerts.clone();
+                if self.alerts.is_empty() {
+                    self.event_sender.send_event(Event::NavigateBack)?;
+                }
+            }
+            _ => { }
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: EventSender> AppDrawable for AlertScreen<S> {
+    fn draw(&self, target: &mut AppFrameBuf) -> Result<()> {
+        target.clear(self.theme.bg_colour)?;
+
+        let font_style = self.theme.title_font
+            .font_style(self.theme.fg_colour, self.theme.bg_colour);
+        Text::with_alignment("Alert", self.theme.title_center, font_style, Alignment::Center)
+            .draw(target)?;
+
+        let line_height = self.theme.line_height;
+        let mut y = self.theme.title_center.y + line_height;
+
+        for alert in &self.alerts {
+            draw_line(&self.theme, target, alert.label(), y, false)?;
+            y += line_height;
+        }
+        y += line_height;
+
+        draw_line(&self.theme, target, "Press to dismiss", y, true)?;
+
+        Ok(())
+    }
+}
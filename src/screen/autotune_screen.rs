@@ -0,0 +1,114 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use anyhow::Result;
+use embedded_graphics::{prelude::*, text::{Alignment, Text}};
+
+use crate::{
+    autotune::AutotuneStatus,
+    drawable::{AppDrawable, AppFrameBuf},
+    events::{Event, EventHandler, EventSender},
+    theme::DiagnosticsTheme
+};
+use super::{Screen, draw_line};
+
+/// Autotune progress and result, with the dial choosing between starting
+/// or stopping autotune and going back
+pub struct AutotuneScreen<S> {
+    status: AutotuneStatus,
+    /// Start/stop highlighted, otherwise back
+    action_highlighted: bool,
+    event_sender: S,
+    theme: DiagnosticsTheme
+}
+
+impl<S: EventSender> AutotuneScreen<S> {
+    pub fn new(theme: DiagnosticsTheme, status: AutotuneStatus, event_sender: S) -> Self {
+        Self { status, action_highlighted: true, event_sender, theme }
+    }
+}
+
+impl<S: EventSender> Screen for AutotuneScreen<S> { }
+
+impl<S: EventSender> EventHandler for AutotuneScreen<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<()> {
+        match event {
+            Event::Dial(dir) if *dir != 0 && (*dir > 0) == self.action_highlighted => {
+                self.action_highlighted = !self.action_highlighted;
+                self.event_sender.send_event(Event::ClickSound)?;
+            }
+            Event::ButtonDown if self.action_highlighted => {
+                let event = if self.status.is_running() {
+                    Event::StopAutotune
+                } else {
+                    Event::StartAutotune
+                };
+                self.event_sender.send_event(event)?;
+                self.event_sender.send_event(Event::ConfirmSound)?;
+            }
+            Event::ButtonDown => {
+                self.event_sender.send_event(Event::NavigateBack)?;
+            }
+            Event::Autotune(status) => {
+                self.status = *status;
+            }
+            _ => { }
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: EventSender> AppDrawable for AutotuneScreen<S> {
+    fn draw(&self, target: &mut AppFrameBuf) -> Result<()> {
+        target.clear(self.theme.bg_colour)?;
+
+        let font_style = self.theme.title_font
+            .font_style(self.theme.fg_colour, self.theme.bg_colour);
+        Text::with_alignment("Autotune", self.theme.title_center, font_style, Alignment::Center)
+            .draw(target)?;
+
+        let line_height = self.theme.line_height;
+        let mut y = self.theme.title_center.y + line_height;
+
+        draw_line(&self.theme, target, &self.status.label(), y, false)?;
+        y += line_height;
+
+        // config values are always Celsius
+        if let AutotuneStatus::Done(tuning) = self.status {
+            let lines = [
+                format!("Band {:.1}°C", tuning.proportional_band),
+                format!("Integral {}m", tuning.integral_secs / 60),
+                format!("Differential {:.1}°C", tuning.differential),
+            ];
+
+            for line in lines {
+                draw_line(&self.theme, target, &line, y, true)?;
+                y += line_height;
+            }
+        }
+        y += line_height;
+
+        let action = if self.status.is_running() { "Stop" } else { "Start" };
+        draw_line(&self.theme, target, action, y, !self.action_highlighted)?;
+        y += line_height;
+        draw_line(&self.theme, target, "Back", y, self.action_highlighted)?;
+
+        Ok(())
+    }
+}
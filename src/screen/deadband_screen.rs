@@ -0,0 +1,104 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use anyhow::Result;
+use embedded_graphics::prelude::*;
+
+use crate::{
+    drawable::{AppDrawable, AppFrameBuf},
+    events::{Event, EventHandler, EventSender},
+    state::{MAX_DEADBAND, MIN_DEADBAND},
+    temperature::TempUnit,
+    theme::ModeSelectTheme,
+    widgets::{ListItem, ListWidget}
+};
+use super::{Screen, mode_screen::{draw_list, scroll_list}};
+
+/// Select the heat/cool deadband, in tenths of a degree
+pub struct DeadbandScreen<S> {
+    deadband_list: ListWidget<f32>,
+    event_sender: S,
+    highlight_row: f32,
+    theme: ModeSelectTheme
+}
+
+/// Deadband in celsius, labelled in the display unit
+#[derive(Clone, Copy)]
+struct Deadband(f32, TempUnit);
+
+impl<S: EventSender> DeadbandScreen<S> {
+    pub fn new(theme: ModeSelectTheme, event_sender: S, deadband: f32, temp_unit: TempUnit) -> Self {
+        let steps = ((MAX_DEADBAND - MIN_DEADBAND) * 10.0).round() as usize;
+        let rows: Vec<_> = (0..=steps)
+            .map(|step| Deadband(MIN_DEADBAND + step as f32 / 10.0, temp_unit))
+            .collect();
+        let selected_row = rows.iter()
+            .position(|row| (row.0 - deadband).abs() < 0.05)
+            .unwrap_or_default();
+
+        Self {
+            deadband_list: ListWidget::new(theme.mode_list.clone(), &rows, selected_row),
+            event_sender,
+            highlight_row: selected_row as f32,
+            theme
+        }
+    }
+}
+
+impl<S: EventSender> Screen for DeadbandScreen<S> { }
+
+impl<S: EventSender> EventHandler for DeadbandScreen<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<()> {
+        match event {
+            Event::Dial(dir) => {
+                let highlight = self.highlight_row + (*dir as f32 * 0.01);
+                if scroll_list(&mut self.deadband_list, &mut self.highlight_row, highlight) {
+                    self.event_sender.send_event(Event::ClickSound)?;
+                }
+            }
+            Event::ButtonDown => {
+                let deadband = *self.deadband_list.get_highlighted_value();
+                self.event_sender.send_event(Event::SetDeadband(deadband))?;
+                self.event_sender.send_event(Event::ConfirmSound)?;
+                self.event_sender.send_event(Event::NavigateBack)?;
+            }
+            _ => { }
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: EventSender> AppDrawable for DeadbandScreen<S> {
+    fn draw(&self, target: &mut AppFrameBuf) -> Result<()> {
+        target.clear(self.theme.bg_colour)?;
+        draw_list(target, &self.deadband_list, self.theme.bg_colour)
+    }
+}
+
+impl From<Deadband> for ListItem<f32> {
+    fn from(Deadband(value, temp_unit): Deadband) -> Self {
+        // a difference, so only scaled to fahrenheit
+        let label = match temp_unit {
+            TempUnit::Celsius => format!("{value:.1}°"),
+            TempUnit::Fahrenheit => format!("{:.1}°", value * 1.8)
+        };
+
+        ListItem { value, label }
+    }
+}
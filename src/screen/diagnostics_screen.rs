@@ -0,0 +1,123 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use anyhow::Result;
+use embedded_graphics::{prelude::*, text::{Alignment, Text}};
+
+use crate::{
+    backplate::BackplateCapabilities,
+    drawable::{AppDrawable, AppFrameBuf},
+    events::{Event, EventHandler, EventSender},
+    home_assistant::HaClients,
+    theme::DiagnosticsTheme
+};
+use super::{Screen, draw_line};
+
+/// Backplate versions, and connected Home Assistant clients to debug HA
+/// showing the device as connected but not receiving state
+pub struct DiagnosticsScreen<S> {
+    clients: HaClients,
+    capabilities: Option<BackplateCapabilities>,
+    event_sender: S,
+    theme: DiagnosticsTheme
+}
+
+impl<S: EventSender> DiagnosticsScreen<S> {
+    pub fn new(
+        theme: DiagnosticsTheme,
+        clients: HaClients,
+        capabilities: Option<BackplateCapabilities>,
+        event_sender: S
+    ) -> Self {
+        Self { clients, capabilities, event_sender, theme }
+    }
+}
+
+impl<S: EventSender> Screen for DiagnosticsScreen<S> { }
+
+impl<S: EventSender> EventHandler for DiagnosticsScreen<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<()> {
+        match event {
+            Event::ButtonDown => {
+                self.event_sender.send_event(Event::NavigateBack)?;
+            }
+            Event::BackplateCapabilities(capabilities) => {
+                self.capabilities = Some(capabilities.clone());
+            }
+            _ => { }
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: EventSender> AppDrawable for DiagnosticsScreen<S> {
+    fn draw(&self, target: &mut AppFrameBuf) -> Result<()> {
+        target.clear(self.theme.bg_colour)?;
+
+        let font_style = self.theme.title_font
+            .font_style(self.theme.fg_colour, self.theme.bg_colour);
+        Text::with_alignment("Info", self.theme.title_center, font_style, Alignment::Center)
+            .draw(target)?;
+
+        let line_height = self.theme.line_height;
+        let mut y = self.theme.title_center.y + line_height;
+
+        draw_line(&self.theme, target, concat!("ReTherm ", env!("CARGO_PKG_VERSION")), y, false)?;
+        y += line_height;
+
+        if let Some(capabilities) = &self.capabilities {
+            draw_line(&self.theme, target, &format!("Backplate {}", capabilities.hardware_version), y, true)?;
+            y += line_height;
+            draw_line(&self.theme, target, &format!("Serial {}", capabilities.serial_number), y, true)?;
+            y += line_height;
+            draw_line(&self.theme, target, &format!("TFE {}", capabilities.tfe_version), y, true)?;
+            y += line_height;
+        }
+        y += line_height;
+
+        draw_line(&self.theme, target, "HA Clients", y, false)?;
+        y += line_height;
+
+        let clients = self.clients.list();
+        if clients.is_empty() {
+            draw_line(&self.theme, target, "No clients connected", y, true)?;
+        }
+
+        for client in clients {
+            let encryption = if client.encrypted { "Encrypted" } else { "Plaintext" };
+
+            draw_line(&self.theme, target, &client.peer_addr.to_string(), y, false)?;
+            y += line_height;
+            draw_line(&self.theme, target, &format!("{encryption}, {:?}", client.state), y, true)?;
+            y += line_height;
+            draw_line(&self.theme, target, &format!("Subscribed: {}", client.subscriptions), y, true)?;
+            y += line_height;
+            draw_line(
+                &self.theme,
+                target,
+                &format!("Last active {}s ago", client.last_activity.elapsed().as_secs()),
+                y,
+                true
+            )?;
+            y += line_height * 2;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,104 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use anyhow::Result;
+use embedded_graphics::prelude::*;
+
+use crate::{
+    drawable::{AppDrawable, AppFrameBuf},
+    events::{Event, EventHandler, EventSender},
+    state::HvacFanMode,
+    theme::ModeSelectTheme,
+    widgets::{ListItem, ListWidget}
+};
+use super::{Screen, mode_screen::{draw_list, scroll_list}};
+
+/// Select whether the fan runs with heating/cooling, or continuously
+pub struct FanScreen<S> {
+    fan_list: ListWidget<HvacFanMode>,
+    event_sender: S,
+    highlight_row: f32,
+    theme: ModeSelectTheme
+}
+
+impl<S: EventSender> FanScreen<S> {
+    pub fn new(theme: ModeSelectTheme, event_sender: S, current_fan_mode: HvacFanMode) -> Self {
+        let fan_modes = [
+            HvacFanMode::Auto,
+            HvacFanMode::On,
+            HvacFanMode::Timer
+        ];
+
+        let selected_row = fan_modes.iter()
+            .position(|m| *m == current_fan_mode)
+            .unwrap_or_default();
+
+        Self {
+            fan_list: ListWidget::new(theme.mode_list.clone(), &fan_modes, selected_row),
+            event_sender,
+            highlight_row: selected_row as f32,
+            theme
+        }
+    }
+}
+
+impl<S: EventSender> Screen for FanScreen<S> { }
+
+impl<S: EventSender> EventHandler for FanScreen<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<()> {
+        match event {
+            Event::Dial(dir) => {
+                let highlight = self.highlight_row + (*dir as f32 * 0.01);
+                if scroll_list(&mut self.fan_list, &mut self.highlight_row, highlight) {
+                    self.event_sender.send_event(Event::ClickSound)?;
+                }
+            }
+            Event::ButtonDown => {
+                let fan_mode = *self.fan_list.get_highlighted_value();
+                self.event_sender.send_event(Event::SetFanMode(fan_mode))?;
+                self.event_sender.send_event(Event::ConfirmSound)?;
+                self.event_sender.send_event(Event::NavigateBack)?;
+            }
+            _ => { }
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: EventSender> AppDrawable for FanScreen<S> {
+    fn draw(&self, target: &mut AppFrameBuf) -> Result<()> {
+        target.clear(self.theme.bg_colour)?;
+        draw_list(target, &self.fan_list, self.theme.bg_colour)
+    }
+}
+
+impl From<HvacFanMode> for ListItem<HvacFanMode> {
+    fn from(value: HvacFanMode) -> Self {
+        let label = match value {
+            HvacFanMode::Auto => "Auto",
+            HvacFanMode::On => "On",
+            HvacFanMode::Timer => "Timer"
+        };
+
+        ListItem {
+            value,
+            label: String::from(label)
+        }
+    }
+}
@@ -0,0 +1,93 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use anyhow::Result;
+use embedded_graphics::{prelude::*, text::{Alignment, Text}};
+
+use crate::{
+    daily_range::TempRange,
+    drawable::{AppDrawable, AppFrameBuf},
+    events::{Event, EventHandler, EventSender},
+    temperature::TempUnit,
+    theme::DiagnosticsTheme
+};
+use super::{Screen, draw_line};
+
+/// Recorded temperatures, today's low and high
+pub struct HistoryScreen<S> {
+    daily_range: Option<TempRange>,
+    temp_unit: TempUnit,
+    event_sender: S,
+    theme: DiagnosticsTheme
+}
+
+impl<S: EventSender> HistoryScreen<S> {
+    pub fn new(
+        theme: DiagnosticsTheme,
+        daily_range: Option<TempRange>,
+        temp_unit: TempUnit,
+        event_sender: S
+    ) -> Self {
+        Self { daily_range, temp_unit, event_sender, theme }
+    }
+}
+
+impl<S: EventSender> Screen for HistoryScreen<S> { }
+
+impl<S: EventSender> EventHandler for HistoryScreen<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<()> {
+        match event {
+            Event::ButtonDown => {
+                self.event_sender.send_event(Event::NavigateBack)?;
+            }
+            Event::DailyTempRange(range) => {
+                self.daily_range = Some(*range);
+            }
+            _ => { }
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: EventSender> AppDrawable for HistoryScreen<S> {
+    fn draw(&self, target: &mut AppFrameBuf) -> Result<()> {
+        target.clear(self.theme.bg_colour)?;
+
+        let font_style = self.theme.title_font
+            .font_style(self.theme.fg_colour, self.theme.bg_colour);
+        Text::with_alignment("History", self.theme.title_center, font_style, Alignment::Center)
+            .draw(target)?;
+
+        let line_height = self.theme.line_height;
+        let y = self.theme.title_center.y + line_height;
+
+        if let Some(range) = self.daily_range {
+            let text = format!(
+                "Today {} - {}",
+                range.min.format(self.temp_unit),
+                range.max.format(self.temp_unit)
+            );
+            draw_line(&self.theme, target, &text, y, false)?;
+        } else {
+            draw_line(&self.theme, target, "No readings today", y, true)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,100 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{Local, Timelike};
+use embedded_graphics::{prelude::*, text::{Alignment, Text}};
+
+use crate::{
+    drawable::{AppDrawable, AppFrameBuf},
+    events::{Event, EventHandler, EventSender},
+    temperature::{TempUnit, Temperature},
+    theme::IdleClockTheme,
+    timer::TimerId
+};
+use super::Screen;
+
+/// Night clock shown on a dimmed backlight once the backlight times out,
+/// instead of turning the screen off
+pub struct IdleClockScreen<S> {
+    current_temp: Temperature,
+    temp_unit: TempUnit,
+    event_sender: S,
+    theme: IdleClockTheme
+}
+
+impl<S: EventSender> IdleClockScreen<S> {
+    pub fn new(
+        theme: IdleClockTheme,
+        current_temp: Temperature,
+        temp_unit: TempUnit,
+        event_sender: S
+    ) -> Self {
+        Self { current_temp, temp_unit, event_sender, theme }
+    }
+
+    /// Wake at the top of the next minute so the clock is redrawn
+    pub fn start_refresh(&self) -> Result<()> {
+        let seconds = 60 - Local::now().second() as u64;
+        self.event_sender.send_event(
+            Event::TimeoutReset(TimerId::IdleClock, Duration::from_secs(seconds))
+        )?;
+
+        Ok(())
+    }
+}
+
+impl<S: EventSender> Screen for IdleClockScreen<S> { }
+
+impl<S: EventSender> EventHandler for IdleClockScreen<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<()> {
+        match event {
+            Event::TimeoutReached(TimerId::IdleClock) => {
+                self.start_refresh()?;
+            }
+            Event::State(state) => {
+                self.current_temp = state.current_temp;
+            }
+            _ => { }
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: EventSender> AppDrawable for IdleClockScreen<S> {
+    fn draw(&self, target: &mut AppFrameBuf) -> Result<()> {
+        target.clear(self.theme.bg_colour)?;
+
+        let time = Local::now().format("%H:%M").to_string();
+        let font_style = self.theme.time_font
+            .font_style(self.theme.fg_colour, self.theme.bg_colour);
+        Text::with_alignment(&time, self.theme.time_center, font_style, Alignment::Center)
+            .draw(target)?;
+
+        let temp = format!("{}°", self.current_temp.format(self.temp_unit));
+        let font_style = self.theme.temp_font
+            .font_style(self.theme.dim_colour, self.theme.bg_colour);
+        Text::with_alignment(&temp, self.theme.temp_center, font_style, Alignment::Center)
+            .draw(target)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use anyhow::Result;
+use embedded_graphics::{prelude::*, text::{Alignment, Text}};
+
+use crate::{
+    drawable::{AppDrawable, AppFrameBuf},
+    events::{Event, EventHandler, EventSender},
+    logging,
+    theme::DiagnosticsTheme
+};
+use super::{Screen, draw_line};
+
+/// Lines that fit inside the round display, below the title
+const VISIBLE_LINES: usize = 8;
+/// Longer lines are cut off at the edge of the screen
+const LINE_CHARS: usize = 28;
+
+/// Shows the most recent log lines, turning the dial scrolls back
+pub struct LogScreen<S> {
+    /// Lines scrolled back from the most recent
+    scroll: f32,
+    event_sender: S,
+    theme: DiagnosticsTheme
+}
+
+impl<S: EventSender> LogScreen<S> {
+    pub fn new(theme: DiagnosticsTheme, event_sender: S) -> Self {
+        Self { scroll: 0.0, event_sender, theme }
+    }
+}
+
+impl<S: EventSender> Screen for LogScreen<S> { }
+
+impl<S: EventSender> EventHandler for LogScreen<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<()> {
+        match event {
+            Event::ButtonDown => {
+                self.event_sender.send_event(Event::NavigateBack)?;
+            }
+            Event::Dial(dir) => {
+                self.scroll = (self.scroll - *dir as f32 * 0.01).max(0.0);
+            }
+            _ => { }
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: EventSender> AppDrawable for LogScreen<S> {
+    fn draw(&self, target: &mut AppFrameBuf) -> Result<()> {
+        target.clear(self.theme.bg_colour)?;
+
+        let font_style = self.theme.title_font
+            .font_style(self.theme.fg_colour, self.theme.bg_colour);
+        Text::with_alignment("Logs", self.theme.title_center, font_style, Alignment::Center)
+            .draw(target)?;
+
+        let scroll = self.scroll as usize;
+        let mut lines = logging::recent_lines(VISIBLE_LINES + scroll);
+        lines.truncate(lines.len().saturating_sub(scroll));
+
+        let mut y = self.theme.title_center.y + self.theme.line_height;
+
+        for line in &lines {
+            let text: String = line.chars().take(LINE_CHARS).collect();
+            draw_line(&self.theme, target, &text, y, false)?;
+            y += self.theme.line_height;
+        }
+
+        Ok(())
+    }
+}
@@ -16,22 +16,26 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::time::Duration;
+use std::{cell::RefCell, time::Duration};
 
 use anyhow::Result;
 use embedded_graphics::{
     pixelcolor::Bgr888,
     prelude::*,
+    primitives::Rectangle,
     text::{Alignment, Text, renderer::TextRenderer}
 };
 
 use crate::{
-    drawable::{AppDrawable, AppFrameBuf},
-    events::{Event, EventHandler, EventSender, TrailingEventSender},
-    state::{HvacAction, HvacMode, ThermostatState},
-    theme::MainScreenTheme,
+    drawable::{AppDrawable, AppFrameBuf, envelope},
+    events::{DialAccelerator, Event, EventHandler, EventSender, TrailingEventSender},
+    health::Problem,
+    state::{HoldingReason, HvacAction, HvacMode, ThermostatState},
+    temperature::{TempUnit, Temperature},
+    supervisor::Subsystem,
+    theme::{GaugeAccentStyle, MainScreenTheme, Theme},
     timer::TimerId,
-    widgets::{GaugeWidget, IconWidget}
+    widgets::{GaugeWidget, IconWidget, RangeSetpoint, StatusBarWidget, StatusIcon, TextCache}
 };
 use super::{Screen, ScreenId};
 
@@ -41,19 +45,73 @@ pub struct MainScreen<S> {
     lockout_icon: IconWidget,
     disconnect_icon: IconWidget,
     fan_icon: IconWidget,
+    offline_icon: IconWidget,
+    boost_icon: IconWidget,
+    status_bar: StatusBarWidget,
+    target_text: TextCache,
+    target_decimal_text: TextCache,
     cmd_sender: TrailingEventSender,
     event_sender: S,
+    /// Day or night variant of the theme, whichever is in use
     theme: MainScreenTheme,
+    day_theme: MainScreenTheme,
+    night_theme: Option<MainScreenTheme>,
+    night: bool,
     state: ThermostatState,
+    temp_unit: TempUnit,
+    /// Outdoor temp from Home Assistant, when configured
+    outdoor_temp: Option<Temperature>,
     last_click_val: f32,
+    dial_accel: DialAccelerator,
     fan_timer: Duration,
     lockout_timer: Duration,
+    boost_timer: Duration,
+    /// Button is down and long press timer has not elapsed
+    button_held: bool,
+    /// Heat/cool mode setpoint adjusted by the dial
+    active_setpoint: RangeSetpoint,
+    /// Subsystem that keeps failing to restart, shown over other status
+    failing: Option<Subsystem>,
+    /// Home Assistant connection shown in the status bar
+    ha_connected: bool,
+    /// Faded frame of the heating/cooling status icon animation
+    pulse: bool,
+    /// Status pulse timer is running
+    pulsing: bool,
+    last_frame: RefCell<Option<DrawnFrame>>,
+}
+
+/// How long the button is held to toggle boost (or switch the heat/cool
+/// setpoint) instead of opening mode select
+const LONG_PRESS: Duration = Duration::from_secs(1);
+
+/// Time between frames of the heating/cooling status icon animation
+const PULSE_INTERVAL: Duration = Duration::from_millis(750);
+
+/// What was drawn in the last frame, used to redraw only what changed
+struct DrawnFrame {
+    state: ThermostatState,
+    fan_timer: Duration,
+    lockout_timer: Duration,
+    boost_timer: Duration,
+    failing: Option<Subsystem>,
+    outdoor_temp: Option<Temperature>,
+    ha_connected: bool,
+    pulse: bool,
+    temp_text: Rectangle
 }
 
 impl<S: EventSender> Screen for MainScreen<S> { }
 
 impl<S: EventSender + Clone + Send + 'static> MainScreen<S> {
-    pub fn new(theme: MainScreenTheme, state: ThermostatState, event_sender: S) -> Self {
+    pub fn new(
+        theme: &Theme,
+        state: ThermostatState,
+        temp_unit: TempUnit,
+        event_sender: S
+    ) -> Self {
+        let night_theme = theme.night.as_ref().map(|night| night.thermostat.clone());
+        let theme = theme.thermostat.clone();
         let cmd_sender = TrailingEventSender::new(event_sender.clone(), 250);
         Self {
             gauge: GaugeWidget::new(theme.gauge.clone()),
@@ -61,13 +119,33 @@ impl<S: EventSender + Clone + Send + 'static> MainScreen<S> {
             lockout_icon: IconWidget::new(theme.lockout_icon.clone()),
             disconnect_icon: IconWidget::new(theme.disconnect_icon.clone()),
             fan_icon: IconWidget::new(theme.fan_icon.clone()),
+            offline_icon: IconWidget::new(theme.offline_icon.clone()),
+            boost_icon: IconWidget::new(theme.boost_icon.clone()),
+            status_bar: StatusBarWidget::new(theme.status_bar.clone()),
+            target_text: TextCache::new(theme.target_font.clone()),
+            target_decimal_text: TextCache::new(theme.target_decimal_font.clone()),
             cmd_sender,
             event_sender,
+            day_theme: theme.clone(),
             theme,
+            night_theme,
+            night: false,
             state,
+            temp_unit,
+            outdoor_temp: None,
             last_click_val: 0.0,
+            dial_accel: DialAccelerator::new(),
             fan_timer: Duration::from_secs(0),
             lockout_timer: Duration::from_secs(0),
+            boost_timer: Duration::from_secs(0),
+            button_held: false,
+            active_setpoint: RangeSetpoint::Low,
+            failing: None,
+            // clients connect once the server is started
+            ha_connected: false,
+            pulse: false,
+            pulsing: false,
+            last_frame: RefCell::new(None),
         }
     }
 }
@@ -82,15 +160,47 @@ impl<S: EventSender> EventHandler for MainScreen<S> {
                 if self.state.mode == HvacMode::Fan {
                     let sec_inc = *dir as f32 * 0.5;
                     self.set_fan_timeout(sec_inc)?;
+                } else if self.state.mode == HvacMode::HeatCool {
+                    let temp_inc = self.dial_accel.apply(*dir) * 0.01;
+                    self.set_target_range(temp_inc)?;
                 } else {
-                    let temp_inc = *dir as f32 * 0.01;
+                    let temp_inc = self.dial_accel.apply(*dir) * 0.01;
                     self.set_target_temp(temp_inc)?;
                 }
             }
             Event::ButtonDown if !self.state.away => {
-                self.event_sender.send_event(Event::NavigateTo(ScreenId::ModeSelect {
-                    current_mode: self.state.mode
-                }))?;
+                self.button_held = true;
+                self.event_sender.send_event(
+                    Event::TimeoutReset(TimerId::ButtonHold, LONG_PRESS)
+                )?;
+            }
+            // Short press, released before long press timer elapsed
+            Event::ButtonUp if self.button_held => {
+                self.button_held = false;
+                self.event_sender.send_event(Event::CancelTimer(TimerId::ButtonHold))?;
+                self.event_sender.send_event(Event::NavigateTo(ScreenId::Menu))?;
+            }
+            // Boost doesn't apply to heat/cool, long press switches setpoints instead
+            Event::TimeoutReached(TimerId::ButtonHold)
+                if self.button_held && self.state.mode == HvacMode::HeatCool =>
+            {
+                self.button_held = false;
+                self.active_setpoint = self.active_setpoint.toggle();
+                self.event_sender.send_event(Event::ClickSound)?;
+            }
+            Event::TimeoutReached(TimerId::ButtonHold) if self.button_held => {
+                self.button_held = false;
+                self.event_sender.send_event(Event::SetBoost(!self.state.boost))?;
+            }
+            // Another screen is about to draw over this one
+            Event::NavigateTo(_) => {
+                *self.last_frame.get_mut() = None;
+                // pulse timer fires on the other screen, re-armed once back
+                self.pulsing = false;
+            }
+            Event::TimeoutReached(TimerId::StatusPulse) => {
+                self.pulsing = false;
+                self.pulse = !self.pulse;
             }
             Event::StartTickTimer(TimerId::HvacLockout, duration) => {
                 self.lockout_timer = *duration;
@@ -98,6 +208,12 @@ impl<S: EventSender> EventHandler for MainScreen<S> {
             Event::StartTickTimer(TimerId::Fan, duration) => {
                 self.fan_timer = *duration;
             }
+            Event::StartTickTimer(TimerId::Boost, duration) => {
+                self.boost_timer = *duration;
+            }
+            Event::TimerTick(TimerId::Boost, remaining) => {
+                self.boost_timer = *remaining;
+            }
             Event::TimerTick(TimerId::HvacLockout, remaining) => {
                 self.lockout_timer = *remaining;
             }
@@ -109,22 +225,80 @@ impl<S: EventSender> EventHandler for MainScreen<S> {
             Event::State(state) if !self.cmd_sender.is_pending() => {
                 self.state = state.clone();
             }
+            Event::OutdoorTemp(temp) => {
+                self.outdoor_temp = Some(*temp);
+            }
+            Event::HaConnected => {
+                self.ha_connected = true;
+            }
+            Event::HaDisconnected => {
+                self.ha_connected = false;
+            }
+            Event::ConfigReloaded(reloaded) => {
+                self.day_theme = reloaded.theme.thermostat.clone();
+                self.night_theme = reloaded.theme.night.as_ref().map(|night| night.thermostat.clone());
+                self.apply_theme();
+                self.temp_unit = reloaded.config.temp_unit;
+            }
+            Event::NightTheme(night) => {
+                self.night = *night;
+                if self.night_theme.is_some() {
+                    self.apply_theme();
+                }
+            }
+            Event::Problems(problems) => {
+                self.failing = problems.iter().find_map(|p| match p {
+                    Problem::SubsystemFailing(subsystem) => Some(*subsystem),
+                    _ => None
+                });
+            }
             _ => { }
         }
 
-        Ok(())
+        self.update_pulse()
     }
 }
 
 impl<S: EventSender> MainScreen<S> {
+    /// Keep the status pulse timer running while heating or cooling
+    fn update_pulse(&mut self) -> Result<()> {
+        if !matches!(self.state.action, HvacAction::Heating | HvacAction::Cooling) {
+            self.pulse = false;
+        } else if !self.pulsing {
+            self.pulsing = true;
+            self.event_sender.send_event(Event::TimeoutReset(TimerId::StatusPulse, PULSE_INTERVAL))?;
+        }
+
+        Ok(())
+    }
+
+    /// Use the night theme when it's night and there is one
+    fn apply_theme(&mut self) {
+        let theme = match &self.night_theme {
+            Some(theme) if self.night => theme.clone(),
+            _ => self.day_theme.clone()
+        };
+        self.set_theme(theme);
+    }
+
+    fn set_theme(&mut self, theme: MainScreenTheme) {
+        self.gauge = GaugeWidget::new(theme.gauge.clone());
+        self.away_icon = IconWidget::new(theme.away_icon.clone());
+        self.lockout_icon = IconWidget::new(theme.lockout_icon.clone());
+        self.disconnect_icon = IconWidget::new(theme.disconnect_icon.clone());
+        self.fan_icon = IconWidget::new(theme.fan_icon.clone());
+        self.offline_icon = IconWidget::new(theme.offline_icon.clone());
+        self.boost_icon = IconWidget::new(theme.boost_icon.clone());
+        self.status_bar = StatusBarWidget::new(theme.status_bar.clone());
+        self.target_text = TextCache::new(theme.target_font.clone());
+        self.target_decimal_text = TextCache::new(theme.target_decimal_font.clone());
+        self.theme = theme;
+        *self.last_frame.get_mut() = None;
+    }
+
     fn set_target_temp(&mut self, inc: f32) -> Result<()> {
         let target_temp = self.state.target_temp + inc;
-
-        // click every half degree
-        if (self.last_click_val - target_temp).abs() >= 0.5 {
-            self.last_click_val = target_temp;
-            self.event_sender.send_event(Event::ClickSound)?;
-        }
+        self.temp_click(target_temp)?;
 
         if self.state.set_target_temp(target_temp) {
             self.cmd_sender.send_event(Event::SetTargetTemp(target_temp))?;
@@ -133,6 +307,32 @@ impl<S: EventSender> MainScreen<S> {
         Ok(())
     }
 
+    /// Move the active heat/cool setpoint, the other setpoint stays put
+    fn set_target_range(&mut self, inc: f32) -> Result<()> {
+        let (mut low, mut high) = (self.state.target_temp_low, self.state.target_temp_high);
+        let target_temp = match self.active_setpoint {
+            RangeSetpoint::Low => { low = low + inc; low }
+            RangeSetpoint::High => { high = high + inc; high }
+        };
+        self.temp_click(target_temp)?;
+
+        if self.state.set_target_range(low, high) {
+            self.cmd_sender.send_event(Event::SetTargetRange(low, high))?;
+        }
+
+        Ok(())
+    }
+
+    /// Click every half degree
+    fn temp_click(&mut self, temp: Temperature) -> Result<()> {
+        if (self.last_click_val - temp.celsius()).abs() >= 0.5 {
+            self.last_click_val = temp.celsius();
+            self.event_sender.send_event(Event::ClickSound)?;
+        }
+
+        Ok(())
+    }
+
     fn set_fan_timeout(&mut self, inc: f32) -> Result<()> {
         let fan_timeout = self.fan_timer.as_secs_f32() + inc;
 
@@ -163,48 +363,70 @@ impl<S: EventSender> AppDrawable for MainScreen<S> {
             _ => self.theme.bg_colour
         };
 
+        let ha_connected = self.ha_connected;
+
+        if self.draw_target_change(target, bg_colour, ha_connected)? {
+            return Ok(());
+        }
+
         target.clear(bg_colour)?;
 
-        if self.state.mode == HvacMode::Fan {
+        let temp_text = if self.state.mode == HvacMode::Fan {
             self.draw_fan_timer(target, bg_colour, center)?;
+            Rectangle::zero()
         } else {
-            self.draw_temp_text(target, bg_colour, center)?;
-        }
-
-        let gauge_accent = match self.state.mode {
-            HvacMode::Cool => Some(&self.theme.cool_gauge),
-            HvacMode::Heat => Some(&self.theme.heat_gauge),
-            HvacMode::Fan => Some(&self.theme.fan_gauge),
-            _ => None
+            self.draw_temp_text(target, bg_colour, center)?
         };
 
-        let (gauge_target, gauge_current) = if self.state.mode == HvacMode::Fan {
-            (duration_percent(self.fan_timer), None)
+        if self.state.mode == HvacMode::HeatCool {
+            self.gauge.draw_range(
+                target,
+                bg_colour,
+                &self.theme.heat_cool_gauge,
+                (
+                    ThermostatState::temp_percent(self.state.target_temp_low),
+                    ThermostatState::temp_percent(self.state.target_temp_high)
+                ),
+                self.active_setpoint,
+                Some(self.gauge_current())
+            )?;
         } else {
-            (
-                ThermostatState::temp_percent(self.state.target_temp),
-                Some((
-                    ThermostatState::temp_percent(self.state.current_temp),
-                    format!("{:.1}", self.state.current_temp)
-                ))
-            )
-        };
+            let (gauge_target, gauge_current) = if self.state.mode == HvacMode::Fan {
+                (duration_percent(self.fan_timer), None)
+            } else {
+                (
+                    ThermostatState::temp_percent(self.state.target_temp),
+                    Some(self.gauge_current())
+                )
+            };
+
+            self.gauge.draw(
+                target,
+                bg_colour,
+                self.gauge_accent(),
+                gauge_target,
+                gauge_current
+            )?;
+        }
 
-        self.gauge.draw(
-            target,
-            bg_colour,
-            gauge_accent,
-            gauge_target,
-            gauge_current
-        )?;
+        self.draw_status_bar(target, bg_colour, ha_connected)?;
 
-        if !self.state.backplate {
+        if let Some(subsystem) = self.failing {
+            self.draw_status_text(target, bg_colour, format!("{} error", subsystem.label()))?;
+        } else if !self.state.backplate {
             self.disconnect_icon.draw(
                 target,
                 self.theme.status_icon_center,
                 bg_colour,
                 Some(self.theme.disconnect_icon.colour)
             )?;
+        } else if self.state.offline {
+            self.offline_icon.draw(
+                target,
+                self.theme.status_icon_center,
+                bg_colour,
+                Some(self.theme.offline_icon.colour)
+            )?;
         } else if self.state.away {
             self.away_icon.draw(
                 target,
@@ -212,13 +434,25 @@ impl<S: EventSender> AppDrawable for MainScreen<S> {
                 bg_colour,
                 Some(self.theme.away_icon.colour)
             )?;
-        } else if self.state.mode == HvacMode::Fan {
+        } else if self.state.fault {
+            self.draw_status_text(target, bg_colour, "Fault".to_string())?;
+        } else if self.state.mode == HvacMode::Fan || self.state.fan_on() {
             self.fan_icon.draw(
                 target,
                 self.theme.status_icon_center,
                 bg_colour,
                 Some(self.theme.fan_icon.colour)
             )?;
+        } else if self.state.boost {
+            self.boost_icon.draw(
+                target,
+                self.theme.status_icon_center,
+                bg_colour,
+                Some(self.theme.boost_icon.colour)
+            )?;
+
+            let dur_text = format_duration(self.boost_timer);
+            self.draw_status_text(target, bg_colour, dur_text)?;
         } else if self.state.lockout {
             self.lockout_icon.draw(
                 target,
@@ -229,12 +463,144 @@ impl<S: EventSender> AppDrawable for MainScreen<S> {
 
             let dur_text = format_duration(self.lockout_timer);
             self.draw_status_text(target, bg_colour, dur_text)?;
+        } else if self.state.hold {
+            self.draw_status_text(target, bg_colour, "Hold".to_string())?;
+        } else if let Some(reason @ (HoldingReason::Deadband | HoldingReason::WindowOpen)) = self.state.holding {
+            self.draw_status_text(target, bg_colour, reason.label().to_string())?;
+        }
+
+        if let Some(temp) = self.outdoor_temp {
+            self.draw_outdoor_text(target, bg_colour, temp)?;
+        }
+
+        if let Some(temp) = self.state.apparent_temp {
+            self.draw_apparent_text(target, bg_colour, temp)?;
         }
 
+        *self.last_frame.borrow_mut() = Some(DrawnFrame {
+            state: self.state.clone(),
+            fan_timer: self.fan_timer,
+            lockout_timer: self.lockout_timer,
+            boost_timer: self.boost_timer,
+            failing: self.failing,
+            outdoor_temp: self.outdoor_temp,
+            ha_connected,
+            pulse: self.pulse,
+            temp_text
+        });
+
         Ok(())
     }
 }
 
+impl<S> MainScreen<S> {
+    /// Fast path for dial rotation. When only the target temp changed since
+    /// the last frame, redraw the temp text and the part of the gauge between
+    /// the old and new target instead of the whole screen.
+    /// Returns `false` when a full redraw is needed.
+    fn draw_target_change(
+        &self,
+        target: &mut AppFrameBuf,
+        bg_colour: Bgr888,
+        ha_connected: bool
+    ) -> Result<bool> {
+        let mut last_frame = self.last_frame.borrow_mut();
+        let Some(frame) = last_frame.as_mut() else {
+            return Ok(false);
+        };
+
+        let same_state = ThermostatState {
+            target_temp: frame.state.target_temp,
+            ..self.state.clone()
+        };
+
+        // range gauge is always redrawn, the other setpoint dot may overlap
+        if matches!(self.state.mode, HvacMode::Fan | HvacMode::HeatCool)
+            || frame.state != same_state
+            || frame.fan_timer != self.fan_timer
+            || frame.lockout_timer != self.lockout_timer
+            || frame.boost_timer != self.boost_timer
+            || frame.failing != self.failing
+            || frame.outdoor_temp != self.outdoor_temp
+            || frame.ha_connected != ha_connected
+        {
+            return Ok(false);
+        }
+
+        // same icons in the same place, drawing over them is enough
+        if frame.pulse != self.pulse {
+            self.draw_status_bar(target, bg_colour, ha_connected)?;
+            frame.pulse = self.pulse;
+        }
+
+        if frame.state.target_temp != self.state.target_temp {
+            let center = target.bounding_box().center();
+
+            target.fill_solid(&frame.temp_text, bg_colour)?;
+            frame.temp_text = self.draw_temp_text(target, bg_colour, center)?;
+
+            self.gauge.draw_target_change(
+                target,
+                bg_colour,
+                self.gauge_accent(),
+                ThermostatState::temp_percent(frame.state.target_temp),
+                ThermostatState::temp_percent(self.state.target_temp),
+                Some(self.gauge_current())
+            )?;
+
+            frame.state.target_temp = self.state.target_temp;
+        }
+
+        Ok(true)
+    }
+
+    fn draw_status_bar(&self, target: &mut AppFrameBuf, bg_colour: Bgr888, ha_connected: bool) -> Result<()> {
+        let mut icons = Vec::new();
+
+        icons.push(if ha_connected { StatusIcon::Connected } else { StatusIcon::Disconnected });
+        if self.state.away {
+            icons.push(StatusIcon::Away);
+        }
+        if self.state.hold {
+            icons.push(StatusIcon::Hold);
+        }
+        match self.state.action {
+            HvacAction::Heating => icons.push(StatusIcon::Heating),
+            HvacAction::Cooling => icons.push(StatusIcon::Cooling),
+            _ => { }
+        }
+
+        self.status_bar.draw(target, bg_colour, &icons, self.pulse)?;
+
+        Ok(())
+    }
+
+    fn gauge_accent(&self) -> Option<&GaugeAccentStyle> {
+        match self.state.mode {
+            HvacMode::Cool => Some(&self.theme.cool_gauge),
+            HvacMode::Heat | HvacMode::EmergencyHeat => Some(&self.theme.heat_gauge),
+            HvacMode::Fan => Some(&self.theme.fan_gauge),
+            _ => None
+        }
+    }
+
+    /// Target temp shown in the center, the active setpoint in heat/cool mode
+    fn display_target(&self) -> Temperature {
+        match (self.state.mode, self.active_setpoint) {
+            (HvacMode::HeatCool, RangeSetpoint::Low) => self.state.target_temp_low,
+            (HvacMode::HeatCool, RangeSetpoint::High) => self.state.target_temp_high,
+            _ => self.state.target_temp
+        }
+    }
+
+    fn gauge_current(&self) -> (f32, String) {
+        (
+            ThermostatState::temp_percent(self.state.current_temp),
+            self.state.current_temp.format(self.temp_unit)
+        )
+    }
+}
+
 impl<S> MainScreen<S> {
     fn draw_status_text<D>(
         &self,
@@ -259,15 +625,63 @@ impl<S> MainScreen<S> {
         Ok(())
     }
 
+    fn draw_outdoor_text<D>(
+        &self,
+        target: &mut D,
+        bg_colour: Bgr888,
+        temp: Temperature
+    ) -> Result<(), D::Error>
+        where D: DrawTarget<Color = Bgr888>
+    {
+        let font_style = self.theme.outdoor_temp_font
+            .font_style(self.theme.fg_colour, bg_colour);
+
+        let s = format!("Outside {}°", temp.format(self.temp_unit));
+        let text = Text::with_alignment(
+            &s,
+            self.theme.outdoor_temp_center,
+            font_style,
+            Alignment::Center
+        );
+
+        text.draw(target)?;
+
+        Ok(())
+    }
+
+    fn draw_apparent_text<D>(
+        &self,
+        target: &mut D,
+        bg_colour: Bgr888,
+        temp: Temperature
+    ) -> Result<(), D::Error>
+        where D: DrawTarget<Color = Bgr888>
+    {
+        let font_style = self.theme.apparent_temp_font
+            .font_style(self.theme.fg_colour, bg_colour);
+
+        let s = format!("Feels like {}°", temp.format(self.temp_unit));
+        let text = Text::with_alignment(
+            &s,
+            self.theme.apparent_temp_center,
+            font_style,
+            Alignment::Center
+        );
+
+        text.draw(target)?;
+
+        Ok(())
+    }
+
     fn draw_temp_text<D>(
         &self,
         target: &mut D,
         bg_color: Bgr888,
         center: Point
-    ) -> Result<(), D::Error>
+    ) -> Result<Rectangle, D::Error>
         where D: DrawTarget<Color = Bgr888>
     {
-        let (temp_int, temp_frac) = round_temperature(self.state.target_temp);
+        let (temp_int, temp_frac) = self.display_target().display_parts(self.temp_unit);
         let (temp_int_s, temp_frac_s) = (temp_int.to_string(), temp_frac.to_string());
 
         let font_style = self.theme.target_font
@@ -278,35 +692,37 @@ impl<S> MainScreen<S> {
             center.y - font_style.line_height() as i32 / 2
         );
 
-        let text = Text::with_alignment(
+        let mut bounds = self.target_text.draw(
+            target,
             &temp_int_s,
             text_pos,
-            font_style,
-            Alignment::Center
-        );
-
-        text.draw(target)?;
+            Alignment::Center,
+            self.theme.fg_colour,
+            bg_color
+        )?;
 
         if temp_frac > 0 {
             let font_style = self.theme.target_decimal_font
                 .font_style(self.theme.fg_colour, bg_color);
 
             let text_pos = Point::new(
-                center.x + (text.bounding_box().size.width / 2) as i32,
+                center.x + (bounds.size.width / 2) as i32,
                 text_pos.y + font_style.line_height() as i32 / 2
             );
 
-            let text = Text::with_alignment(
+            let frac_bounds = self.target_decimal_text.draw(
+                target,
                 &temp_frac_s,
                 text_pos,
-                font_style,
-                Alignment::Left
-            );
+                Alignment::Left,
+                self.theme.fg_colour,
+                bg_color
+            )?;
 
-            text.draw(target)?;
+            bounds = envelope(&bounds, &frac_bounds);
         }
 
-        Ok(())
+        Ok(bounds)
     }
 
     fn draw_fan_timer<D>(
@@ -348,15 +764,6 @@ fn format_duration(duration: Duration) -> String {
     format!("{:02}:{:02}", minutes, seconds)
 }
 
-fn round_temperature(value: f32) -> (i32, i32) {
-    let scaled = (value * 2.0).round() as i32;
-
-    let integer_part = scaled / 2;
-    let fraction_part = (scaled % 2) * 5;
-
-    (integer_part, fraction_part)
-}
-
 fn duration_percent(duration: Duration) -> f32 {
     const MAX_SEC: f32 = Duration::from_hours(2).as_secs_f32();
     let duration = duration.as_secs_f32();
@@ -0,0 +1,133 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use anyhow::Result;
+use embedded_graphics::prelude::*;
+
+use crate::{
+    drawable::{AppDrawable, AppFrameBuf},
+    events::{Event, EventHandler, EventSender},
+    theme::ModeSelectTheme,
+    widgets::{ListItem, ListWidget}
+};
+use super::{Screen, ScreenId, mode_screen::{draw_list, scroll_list}};
+
+/// Entry point from the main screen, each item opens a screen on top of
+/// the menu so going back returns here
+pub struct MenuScreen<S> {
+    menu_list: ListWidget<MenuItem>,
+    event_sender: S,
+    highlight_row: f32,
+    theme: ModeSelectTheme
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum MenuItem {
+    Mode,
+    Fan,
+    Schedule,
+    Settings,
+    History,
+    Stats,
+    Info,
+    Back
+}
+
+impl<S: EventSender> MenuScreen<S> {
+    pub fn new(theme: ModeSelectTheme, event_sender: S) -> Self {
+        let items = [
+            MenuItem::Mode,
+            MenuItem::Fan,
+            MenuItem::Schedule,
+            MenuItem::Settings,
+            MenuItem::History,
+            MenuItem::Stats,
+            MenuItem::Info,
+            MenuItem::Back
+        ];
+
+        Self {
+            menu_list: ListWidget::new(theme.mode_list.clone(), &items, 0),
+            event_sender,
+            highlight_row: 0.0,
+            theme
+        }
+    }
+}
+
+impl<S: EventSender> Screen for MenuScreen<S> { }
+
+impl<S: EventSender> EventHandler for MenuScreen<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<()> {
+        match event {
+            Event::Dial(dir) => {
+                let highlight = self.highlight_row + (*dir as f32 * 0.01);
+                if scroll_list(&mut self.menu_list, &mut self.highlight_row, highlight) {
+                    self.event_sender.send_event(Event::ClickSound)?;
+                }
+            }
+            Event::ButtonDown => {
+                let screen = match self.menu_list.get_highlighted_value() {
+                    MenuItem::Mode => ScreenId::ModeSelect,
+                    MenuItem::Fan => ScreenId::FanSelect,
+                    MenuItem::Schedule => ScreenId::Schedule,
+                    MenuItem::Settings => ScreenId::Settings,
+                    MenuItem::History => ScreenId::History,
+                    MenuItem::Stats => ScreenId::Stats,
+                    MenuItem::Info => ScreenId::Diagnostics,
+                    MenuItem::Back => {
+                        self.event_sender.send_event(Event::NavigateBack)?;
+                        return Ok(());
+                    }
+                };
+
+                self.event_sender.send_event(Event::NavigateTo(screen))?;
+            }
+            _ => { }
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: EventSender> AppDrawable for MenuScreen<S> {
+    fn draw(&self, target: &mut AppFrameBuf) -> Result<()> {
+        target.clear(self.theme.bg_colour)?;
+        draw_list(target, &self.menu_list, self.theme.bg_colour)
+    }
+}
+
+impl From<MenuItem> for ListItem<MenuItem> {
+    fn from(value: MenuItem) -> Self {
+        let label = match value {
+            MenuItem::Mode => "Mode",
+            MenuItem::Fan => "Fan",
+            MenuItem::Schedule => "Schedule",
+            MenuItem::Settings => "Settings",
+            MenuItem::History => "History",
+            MenuItem::Stats => "Stats",
+            MenuItem::Info => "Info",
+            MenuItem::Back => "Back"
+        };
+
+        ListItem {
+            value,
+            label: String::from(label)
+        }
+    }
+}
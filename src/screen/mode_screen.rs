@@ -17,12 +17,12 @@
  */
 
 use anyhow::Result;
-use embedded_graphics::{prelude::*, primitives::Rectangle};
+use embedded_graphics::{pixelcolor::Bgr888, prelude::*, primitives::Rectangle};
 
 use crate::{
     drawable::{AppDrawable, AppFrameBuf},
     events::{Event, EventHandler, EventSender},
-    state::HvacMode,
+    state::{HvacAction, HvacMode},
     theme::ModeSelectTheme,
     widgets::{IconWidget, ListItem, ListWidget}
 };
@@ -31,19 +31,40 @@ use super::Screen;
 pub struct ModeScreen<S> {
     mode_icon: IconWidget,
     mode_list: ListWidget<HvacMode>,
+    /// Replaces mode list to confirm switching between heat and cool
+    /// while the hvac system is running
+    confirm_list: Option<ListWidget<Confirm>>,
     event_sender: S,
     highlight_row: f32,
+    current_action: HvacAction,
     theme: ModeSelectTheme
 }
 
+#[derive(Clone, Copy, PartialEq)]
+enum Confirm {
+    Yes,
+    No
+}
+
 impl<S: EventSender> ModeScreen<S> {
-    pub fn new(theme: ModeSelectTheme, event_sender: S, current_mode: &HvacMode) -> Self {
-        let modes = [
+    pub fn new(
+        theme: ModeSelectTheme,
+        event_sender: S,
+        current_mode: &HvacMode,
+        current_action: HvacAction,
+        emergency_heat: bool
+    ) -> Self {
+        let mut modes = vec![
             HvacMode::Heat,
             HvacMode::Cool,
+            HvacMode::HeatCool,
             HvacMode::Fan,
             HvacMode::Off
         ];
+        // only offered with a heat pump aux wire
+        if emergency_heat {
+            modes.insert(1, HvacMode::EmergencyHeat);
+        }
 
         let selected_row = modes.iter()
             .position(|m| m == current_mode)
@@ -56,11 +77,22 @@ impl<S: EventSender> ModeScreen<S> {
                 &modes,
                 selected_row
             ),
+            confirm_list: None,
             event_sender,
             highlight_row: selected_row as f32,
+            current_action,
             theme
         }
     }
+
+    /// Switching directly between heating and cooling mid-cycle
+    fn is_changeover(&self, mode: HvacMode) -> bool {
+        match self.current_action {
+            HvacAction::Heating => mode == HvacMode::Cool,
+            HvacAction::Cooling => matches!(mode, HvacMode::Heat | HvacMode::EmergencyHeat),
+            _ => false
+        }
+    }
 }
 
 impl<S: EventSender> Screen for ModeScreen<S> { }
@@ -70,20 +102,39 @@ impl<S: EventSender> EventHandler for ModeScreen<S> {
         match event {
             Event::Dial(dir) => {
                 let highlight = self.highlight_row + (*dir as f32 * 0.01);
-                let last_selected = self.mode_list.get_highlight_row();
 
-                if self.mode_list.set_highlight_row(highlight as i32) {
-                    self.highlight_row = highlight;
+                let changed = if let Some(confirm_list) = &mut self.confirm_list {
+                    scroll_list(confirm_list, &mut self.highlight_row, highlight)
+                } else {
+                    scroll_list(&mut self.mode_list, &mut self.highlight_row, highlight)
+                };
 
-                    if last_selected != self.mode_list.get_highlight_row() {
-                        self.event_sender.send_event(Event::ClickSound)?;
-                    }
+                if changed {
+                    self.event_sender.send_event(Event::ClickSound)?;
                 }
             }
             Event::ButtonDown => {
-                let mode = self.mode_list.get_highlighted_value();
-                self.event_sender.send_event(Event::SetMode(*mode))?;
-                self.event_sender.send_event(Event::NavigateBack)?;
+                let mode = *self.mode_list.get_highlighted_value();
+
+                match &self.confirm_list {
+                    None if self.is_changeover(mode) => {
+                        // default to cancel, user must dial to confirm
+                        self.confirm_list = Some(ListWidget::new(
+                            self.theme.mode_list.clone(),
+                            &[Confirm::Yes, Confirm::No],
+                            1
+                        ));
+                        self.highlight_row = 1.0;
+                    }
+                    Some(confirm_list) if *confirm_list.get_highlighted_value() == Confirm::No => {
+                        self.event_sender.send_event(Event::NavigateBack)?;
+                    }
+                    _ => {
+                        self.event_sender.send_event(Event::SetMode(mode))?;
+                        self.event_sender.send_event(Event::ConfirmSound)?;
+                        self.event_sender.send_event(Event::NavigateBack)?;
+                    }
+                }
             },
             _ => { }
         }
@@ -98,7 +149,7 @@ impl<S: EventSender> AppDrawable for ModeScreen<S> {
         // draw icon view
 
         let icon_color = match self.mode_list.get_highlighted_value() {
-            HvacMode::Heat => Some(self.theme.icon_heat_colour),
+            HvacMode::Heat | HvacMode::EmergencyHeat => Some(self.theme.icon_heat_colour),
             HvacMode::Cool => Some(self.theme.icon_cool_colour),
             HvacMode::Fan => Some(self.theme.icon_fan_colour),
             _ => None
@@ -107,44 +158,76 @@ impl<S: EventSender> AppDrawable for ModeScreen<S> {
 
         // draw list view
 
-        let list_size = self.mode_list.get_list_size();
-        let list_x = (target.width() as u32 - list_size.width) / 2;
-        let list_y = (target.height() as u32 - list_size.height) / 2;
+        if let Some(confirm_list) = &self.confirm_list {
+            draw_list(target, confirm_list, self.theme.bg_colour)?;
+        } else {
+            draw_list(target, &self.mode_list, self.theme.bg_colour)?;
+        }
 
-        let list_rect = Rectangle {
-            size: list_size,
-            top_left: Point {
-                x: list_x as i32,
-                y: list_y as i32
-            }
-        };
+        Ok(())
+    }
+}
 
-        let mut list_target = target.cropped(&list_rect);
-        self.mode_list.draw(&mut list_target, self.theme.bg_colour)?;
+/// Move list highlight to `highlight` row, returns true when highlighted row changed
+pub(super) fn scroll_list<T>(list: &mut ListWidget<T>, highlight_row: &mut f32, highlight: f32) -> bool {
+    let last_selected = list.get_highlight_row();
 
-        Ok(())
+    if list.set_highlight_row(highlight as i32) {
+        *highlight_row = highlight;
+        last_selected != list.get_highlight_row()
+    } else {
+        false
     }
 }
 
-impl From<HvacMode> for ListItem<HvacMode> {
-    fn from(value: HvacMode) -> Self {
+pub(super) fn draw_list<T>(target: &mut AppFrameBuf, list: &ListWidget<T>, bg_colour: Bgr888) -> Result<()> {
+    let list_size = list.get_list_size();
+    let list_x = (target.width() as u32 - list_size.width) / 2;
+    let list_y = (target.height() as u32 - list_size.height) / 2;
+
+    let list_rect = Rectangle {
+        size: list_size,
+        top_left: Point {
+            x: list_x as i32,
+            y: list_y as i32
+        }
+    };
+
+    let mut list_target = target.cropped(&list_rect);
+    list.draw(&mut list_target, bg_colour)?;
+
+    Ok(())
+}
+
+impl From<Confirm> for ListItem<Confirm> {
+    fn from(value: Confirm) -> Self {
         match value {
-            HvacMode::Off => ListItem {
-                value: value.clone(),
-                label: String::from("Off")
-            },
-            HvacMode::Heat => ListItem {
-                value: value.clone(),
-                label: String::from("Heat")
-            },
-            HvacMode::Cool => ListItem {
-                value: value.clone(),
-                label: String::from("Cool")
-            },
-            HvacMode::Fan => ListItem {
-                value: value.clone(),
-                label: String::from("Fan")
+            Confirm::Yes => ListItem {
+                value,
+                label: String::from("Switch")
             },
+            Confirm::No => ListItem {
+                value,
+                label: String::from("Cancel")
+            }
+        }
+    }
+}
+
+impl From<HvacMode> for ListItem<HvacMode> {
+    fn from(value: HvacMode) -> Self {
+        let label = match value {
+            HvacMode::Off => "Off",
+            HvacMode::Heat => "Heat",
+            HvacMode::Cool => "Cool",
+            HvacMode::HeatCool => "Heat/Cool",
+            HvacMode::Fan => "Fan",
+            HvacMode::EmergencyHeat => "Emergency Heat"
+        };
+
+        ListItem {
+            value,
+            label: String::from(label)
         }
     }
 }
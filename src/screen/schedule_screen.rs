@@ -0,0 +1,107 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use anyhow::Result;
+use embedded_graphics::{prelude::*, text::{Alignment, Text}};
+
+use crate::{
+    drawable::{AppDrawable, AppFrameBuf},
+    events::{Event, EventHandler, EventSender},
+    schedule::NextSetPoint,
+    temperature::TempUnit,
+    theme::DiagnosticsTheme
+};
+use super::{Screen, draw_line};
+
+/// Upcoming schedule change, and whether the target temp is held against it
+pub struct ScheduleScreen<S> {
+    next_set_point: Option<NextSetPoint>,
+    hold: bool,
+    temp_unit: TempUnit,
+    event_sender: S,
+    theme: DiagnosticsTheme
+}
+
+impl<S: EventSender> ScheduleScreen<S> {
+    pub fn new(
+        theme: DiagnosticsTheme,
+        next_set_point: Option<NextSetPoint>,
+        hold: bool,
+        temp_unit: TempUnit,
+        event_sender: S
+    ) -> Self {
+        Self { next_set_point, hold, temp_unit, event_sender, theme }
+    }
+}
+
+impl<S: EventSender> Screen for ScheduleScreen<S> { }
+
+impl<S: EventSender> EventHandler for ScheduleScreen<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<()> {
+        match event {
+            Event::ButtonDown => {
+                self.event_sender.send_event(Event::NavigateBack)?;
+            }
+            Event::NextSetPoint(next) => {
+                self.next_set_point = *next;
+            }
+            Event::State(state) => {
+                self.hold = state.hold;
+            }
+            _ => { }
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: EventSender> AppDrawable for ScheduleScreen<S> {
+    fn draw(&self, target: &mut AppFrameBuf) -> Result<()> {
+        target.clear(self.theme.bg_colour)?;
+
+        let font_style = self.theme.title_font
+            .font_style(self.theme.fg_colour, self.theme.bg_colour);
+        Text::with_alignment("Schedule", self.theme.title_center, font_style, Alignment::Center)
+            .draw(target)?;
+
+        let line_height = self.theme.line_height;
+        let mut y = self.theme.title_center.y + line_height;
+
+        let Some(next) = self.next_set_point else {
+            draw_line(&self.theme, target, "No schedule", y, true)?;
+            return Ok(());
+        };
+
+        draw_line(&self.theme, target, "Next change", y, false)?;
+        y += line_height;
+
+        let text = format!(
+            "{} at {}",
+            next.temp.format(self.temp_unit),
+            next.time.format("%a %H:%M")
+        );
+        draw_line(&self.theme, target, &text, y, false)?;
+        y += line_height * 2;
+
+        if self.hold {
+            draw_line(&self.theme, target, "Target temp on hold", y, true)?;
+        }
+
+        Ok(())
+    }
+}
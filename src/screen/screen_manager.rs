@@ -16,34 +16,121 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use std::path::PathBuf;
+
 use anyhow::Result;
 
 use crate::{
+    action_log::ActionLog,
+    alerts::Alert,
+    autotune::AutotuneStatus,
+    backplate::BackplateCapabilities,
+    config::{BacklightConfig, Config, WireConfig, WireId},
     events::{Event, EventHandler, EventSender},
-    theme::Theme
+    daily_range::TempRange,
+    home_assistant::HaClients,
+    learning::Suggestion,
+    runtime_stats::RuntimeStats,
+    schedule::NextSetPoint,
+    state::ThermostatState,
+    temperature::TempUnit,
+    theme::{self, Theme},
+    timer::TimerId
+};
+use super::{
+    ActionLogScreen, AlertScreen, AutotuneScreen, DeadbandScreen, DiagnosticsScreen, FanScreen, HistoryScreen, IdleClockScreen, LogScreen, MenuScreen, ModeScreen,
+    ScheduleScreen, Screen, ScreenId, SettingsScreen, StatsScreen, SuggestionScreen, ThemeScreen, WiringScreen, WiringSetupScreen
 };
-use super::{ModeScreen, Screen, ScreenId};
 
 pub struct ScreenManager<S> {
     main_screen: Box<dyn Screen>,
     screens: Vec<Box<dyn Screen>>,
+    /// Drawn over all other screens while the backlight is dimmed
+    idle_screen: Option<Box<dyn Screen>>,
+    backlight: BacklightConfig,
+    /// Ambient light is below the backlight `night_light` level
+    night: bool,
     event_sender: S,
-    theme: Theme
+    ha_clients: HaClients,
+    temp_unit: TempUnit,
+    /// Kept here since the history screen isn't around to see updates
+    daily_range: Option<TempRange>,
+    runtime: RuntimeStats,
+    /// Backplate versions and serial, for the info screen
+    capabilities: Option<BackplateCapabilities>,
+    next_set_point: Option<NextSetPoint>,
+    autotune: AutotuneStatus,
+    action_log: ActionLog,
+    state: ThermostatState,
+    wiring: WireConfig,
+    /// Deadband in use, highlighted on the deadband screen
+    deadband: f32,
+    /// Set points suggested from setpoint adjustments, to review
+    suggestions: Vec<Suggestion>,
+    /// Missing wires last warned about, so the warning isn't repeated
+    missing_wires: Vec<WireId>,
+    /// Alerts last shown, dismissed alerts aren't shown again until they
+    /// clear and are raised again
+    alerts: Vec<Alert>,
+    /// Position of the alert screen in `screens` while it's open
+    alert_screen: Option<usize>,
+    theme: Theme,
+    /// Name of `theme`, highlighted on the theme screen
+    theme_name: String,
+    theme_dir: PathBuf
 }
 
 impl<S: EventSender + Clone + 'static> ScreenManager<S> {
-    pub fn new<R>(theme: Theme, main_screen: R, event_sender: S) -> Self
+    pub fn new<R>(
+        theme: Theme,
+        theme_name: &str,
+        main_screen: R,
+        ha_clients: HaClients,
+        config: &Config,
+        event_sender: S
+    ) -> Self
         where R: Screen + 'static
     {
         Self {
             main_screen: Box::new(main_screen),
             screens: Vec::new(),
+            idle_screen: None,
+            backlight: config.backlight.clone(),
+            night: false,
             event_sender,
-            theme
+            ha_clients,
+            temp_unit: config.temp_unit,
+            daily_range: None,
+            runtime: RuntimeStats::default(),
+            capabilities: None,
+            next_set_point: None,
+            autotune: AutotuneStatus::Idle,
+            action_log: ActionLog::default(),
+            state: ThermostatState::default(),
+            wiring: config.backplate.wiring.clone(),
+            deadband: config.temp_deadband,
+            suggestions: Vec::new(),
+            missing_wires: Vec::new(),
+            alerts: Vec::new(),
+            alert_screen: None,
+            theme,
+            theme_name: theme_name.to_string(),
+            theme_dir: config.theme_dir.clone()
         }
     }
 
     pub fn active_screen(&mut self) -> &mut dyn Screen {
+        if let Some(screen) = &mut self.idle_screen {
+            screen.as_mut()
+        } else if let Some(screen) = self.screens.last_mut() {
+            screen.as_mut()
+        } else {
+            self.main_screen.as_mut()
+        }
+    }
+
+    /// Screen under the idle clock
+    fn top_screen(&mut self) -> &mut dyn Screen {
         if let Some(screen) = self.screens.last_mut() {
             screen.as_mut()
         } else {
@@ -51,18 +138,240 @@ impl<S: EventSender + Clone + 'static> ScreenManager<S> {
         }
     }
 
+    fn active_theme(&self) -> &Theme {
+        self.theme.variant(self.night)
+    }
+
     fn show_screen(&mut self, screen: &ScreenId) -> Result<()> {
         match screen {
-            ScreenId::ModeSelect { current_mode } => {
+            ScreenId::ModeSelect => {
                 let screen = ModeScreen::new(
-                    self.theme.mode_select.clone(),
+                    self.active_theme().mode_select.clone(),
+                    self.event_sender.clone(),
+                    &self.state.mode,
+                    self.state.action,
+                    self.wiring.aux_wire().is_some()
+                );
+
+                self.screens.push(Box::new(screen));
+            }
+            ScreenId::Menu => {
+                let screen = MenuScreen::new(
+                    self.active_theme().mode_select.clone(),
+                    self.event_sender.clone()
+                );
+
+                self.screens.push(Box::new(screen));
+            }
+            ScreenId::FanSelect => {
+                let screen = FanScreen::new(
+                    self.active_theme().mode_select.clone(),
+                    self.event_sender.clone(),
+                    self.state.fan_mode
+                );
+
+                self.screens.push(Box::new(screen));
+            }
+            ScreenId::Schedule => {
+                let screen = ScheduleScreen::new(
+                    self.active_theme().diagnostics.clone(),
+                    self.next_set_point,
+                    self.state.hold,
+                    self.temp_unit,
+                    self.event_sender.clone()
+                );
+
+                self.screens.push(Box::new(screen));
+            }
+            ScreenId::Settings => {
+                let screen = SettingsScreen::new(
+                    self.active_theme().mode_select.clone(),
+                    self.event_sender.clone(),
+                    self.state.away,
+                    !self.suggestions.is_empty()
+                );
+
+                self.screens.push(Box::new(screen));
+            }
+            ScreenId::ThemeSelect => {
+                let screen = ThemeScreen::new(
+                    self.active_theme().mode_select.clone(),
+                    self.event_sender.clone(),
+                    &theme::theme_names(&self.theme_dir),
+                    &self.theme_name
+                );
+
+                self.screens.push(Box::new(screen));
+            }
+            ScreenId::Autotune => {
+                let screen = AutotuneScreen::new(
+                    self.active_theme().diagnostics.clone(),
+                    self.autotune,
+                    self.event_sender.clone()
+                );
+
+                self.screens.push(Box::new(screen));
+            }
+            ScreenId::Deadband => {
+                let screen = DeadbandScreen::new(
+                    self.active_theme().mode_select.clone(),
                     self.event_sender.clone(),
-                    current_mode
+                    self.deadband,
+                    self.temp_unit
+                );
+
+                self.screens.push(Box::new(screen));
+            }
+            ScreenId::Suggestions => {
+                let screen = SuggestionScreen::new(
+                    self.active_theme().mode_select.clone(),
+                    self.event_sender.clone(),
+                    self.suggestions.clone(),
+                    self.temp_unit
+                );
+
+                self.screens.push(Box::new(screen));
+            }
+            ScreenId::History => {
+                let screen = HistoryScreen::new(
+                    self.active_theme().diagnostics.clone(),
+                    self.daily_range,
+                    self.temp_unit,
+                    self.event_sender.clone()
+                );
+
+                self.screens.push(Box::new(screen));
+            }
+            ScreenId::Stats => {
+                let screen = StatsScreen::new(
+                    self.active_theme().diagnostics.clone(),
+                    self.runtime.clone(),
+                    self.event_sender.clone()
+                );
+
+                self.screens.push(Box::new(screen));
+            }
+            ScreenId::Diagnostics => {
+                let screen = DiagnosticsScreen::new(
+                    self.active_theme().diagnostics.clone(),
+                    self.ha_clients.clone(),
+                    self.capabilities.clone(),
+                    self.event_sender.clone()
+                );
+
+                self.screens.push(Box::new(screen));
+            }
+            ScreenId::Logs => {
+                let screen = LogScreen::new(
+                    self.active_theme().diagnostics.clone(),
+                    self.event_sender.clone()
+                );
+
+                self.screens.push(Box::new(screen));
+            }
+            ScreenId::ActionLog => {
+                let screen = ActionLogScreen::new(
+                    self.active_theme().diagnostics.clone(),
+                    self.action_log.clone(),
+                    self.event_sender.clone()
+                );
+
+                self.screens.push(Box::new(screen));
+            }
+            ScreenId::WiringWarning { missing, detected } => {
+                let screen = WiringScreen::new(
+                    self.active_theme().diagnostics.clone(),
+                    missing.clone(),
+                    detected.clone(),
+                    self.event_sender.clone()
+                );
+
+                self.screens.push(Box::new(screen));
+            }
+            ScreenId::WiringSetup => {
+                let detected = self.capabilities.as_ref()
+                    .map(|c| c.wires.switched_wires())
+                    .unwrap_or_default();
+
+                let screen = WiringSetupScreen::new(
+                    self.active_theme().diagnostics.clone(),
+                    detected,
+                    self.wiring.clone(),
+                    self.event_sender.clone()
                 );
 
                 self.screens.push(Box::new(screen));
             }
+            ScreenId::Alerts(alerts) => {
+                let screen = AlertScreen::new(
+                    self.active_theme().diagnostics.clone(),
+                    alerts.clone(),
+                    self.event_sender.clone()
+                );
+
+                self.alert_screen = Some(self.screens.len());
+                self.screens.push(Box::new(screen));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn show_idle_clock(&mut self) -> Result<()> {
+        let screen = IdleClockScreen::new(
+            self.active_theme().idle_clock.clone(),
+            self.state.current_temp,
+            self.temp_unit,
+            self.event_sender.clone()
+        );
+        screen.start_refresh()?;
+
+        self.idle_screen = Some(Box::new(screen));
+
+        Ok(())
+    }
+
+    /// Pass events to the idle clock, which swallows the input that wakes
+    /// it. Other events still go to the screen underneath so it's current
+    /// when the clock is dismissed.
+    fn handle_idle_event(&mut self, event: &Event) -> Result<()> {
+        let Some(idle_screen) = &mut self.idle_screen else {
+            return self.top_screen().handle_event(event);
+        };
+
+        idle_screen.handle_event(event)?;
+
+        if event.is_wakeup_event() {
+            self.idle_screen = None;
+            self.event_sender.send_event(Event::CancelTimer(TimerId::IdleClock))?;
+        } else {
+            self.top_screen().handle_event(event)?;
+        }
+
+        Ok(())
+    }
+
+    fn check_wiring(&mut self, capabilities: &BackplateCapabilities) -> Result<()> {
+        let missing = capabilities.missing_wires(&self.wiring);
+        if !missing.is_empty() && missing != self.missing_wires {
+            self.event_sender.send_event(Event::NavigateTo(ScreenId::WiringWarning {
+                missing: missing.clone(),
+                detected: capabilities.wires.label()
+            }))?;
+        }
+        self.missing_wires = missing;
+
+        Ok(())
+    }
+
+    /// Show newly raised alerts, unless the alert screen is already open
+    /// and showing them
+    fn check_alerts(&mut self, alerts: &[Alert]) -> Result<()> {
+        let raised = alerts.iter().any(|a| !self.alerts.contains(a));
+        if raised && self.alert_screen.is_none() {
+            self.event_sender.send_event(Event::NavigateTo(ScreenId::Alerts(alerts.to_vec())))?;
         }
+        self.alerts = alerts.to_vec();
 
         Ok(())
     }
@@ -70,7 +379,39 @@ impl<S: EventSender + Clone + 'static> ScreenManager<S> {
 
 impl<S: EventSender + Clone + 'static> EventHandler for ScreenManager<S> {
     fn handle_event(&mut self, event: &Event) -> Result<()> {
-        self.active_screen().handle_event(event)?;
+        if let Event::ConfigReloaded(reloaded) = event {
+            // Open screens have a copy of the old theme, go back to main screen
+            self.screens.clear();
+            self.alert_screen = None;
+            self.idle_screen = None;
+            self.backlight = reloaded.config.backlight.clone();
+            self.theme = reloaded.theme.clone();
+            self.theme_name = reloaded.theme_name.clone();
+            self.theme_dir = reloaded.config.theme_dir.clone();
+            self.temp_unit = reloaded.config.temp_unit;
+            self.wiring = reloaded.config.backplate.wiring.clone();
+        }
+
+        if let Event::NightTheme(night) = event {
+            self.night = *night;
+
+            // Same as a reload, re-create screens in the new theme variant
+            if self.theme.night.is_some() {
+                self.screens.clear();
+                self.alert_screen = None;
+                if self.idle_screen.is_some() {
+                    self.show_idle_clock()?;
+                }
+            }
+        }
+
+        self.handle_idle_event(event)?;
+
+        // The status bar shows the connection, keep it current while other
+        // screens are open since these aren't repeated
+        if matches!(event, Event::HaConnected | Event::HaDisconnected) && !self.screens.is_empty() {
+            self.main_screen.handle_event(event)?;
+        }
 
         match event {
             Event::NavigateTo(screen) => {
@@ -78,6 +419,50 @@ impl<S: EventSender + Clone + 'static> EventHandler for ScreenManager<S> {
             }
             Event::NavigateBack => {
                 self.screens.pop();
+                if self.alert_screen.is_some_and(|index| index >= self.screens.len()) {
+                    self.alert_screen = None;
+                }
+            }
+            Event::Alerts(alerts) => {
+                self.check_alerts(alerts)?;
+            }
+            Event::DailyTempRange(range) => {
+                self.daily_range = Some(*range);
+            }
+            Event::RuntimeStats(runtime) => {
+                self.runtime = runtime.clone();
+            }
+            Event::NextSetPoint(next) => {
+                self.next_set_point = *next;
+            }
+            Event::Autotune(status) => {
+                self.autotune = *status;
+            }
+            Event::ActionLog(log) => {
+                self.action_log = log.clone();
+            }
+            Event::State(state) => {
+                self.state = state.clone();
+            }
+            Event::Deadband(deadband) => {
+                self.deadband = *deadband;
+            }
+            Event::ScheduleSuggestions(suggestions) => {
+                self.suggestions = suggestions.clone();
+            }
+            Event::AmbientLight(light) => {
+                let night = self.backlight.is_night(*light, self.night);
+                if night != self.night {
+                    self.night = night;
+                    self.event_sender.send_event(Event::NightTheme(night))?;
+                }
+            }
+            Event::BackplateCapabilities(capabilities) => {
+                self.capabilities = Some(capabilities.clone());
+                self.check_wiring(capabilities)?;
+            }
+            Event::TimeoutReached(TimerId::Backlight) if self.backlight.idle_brightness > 0 => {
+                self.show_idle_clock()?;
             }
             _ => { }
         }
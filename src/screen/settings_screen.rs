@@ -0,0 +1,162 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use anyhow::Result;
+use embedded_graphics::prelude::*;
+
+use crate::{
+    drawable::{AppDrawable, AppFrameBuf},
+    events::{Event, EventHandler, EventSender},
+    theme::ModeSelectTheme,
+    widgets::{ListItem, ListWidget}
+};
+use super::{Screen, ScreenId, mode_screen::{draw_list, scroll_list}};
+
+/// Device settings and maintenance actions
+pub struct SettingsScreen<S> {
+    settings_list: ListWidget<SettingsItem>,
+    event_sender: S,
+    highlight_row: f32,
+    theme: ModeSelectTheme
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum SettingsItem {
+    /// Toggle away, labelled with the state it switches to
+    Away(bool),
+    Theme,
+    Deadband,
+    /// Only listed while there are schedule suggestions to review
+    Suggestions,
+    Autotune,
+    Activity,
+    Wiring,
+    Logs,
+    ReloadConfig,
+    Back
+}
+
+impl<S: EventSender> SettingsScreen<S> {
+    pub fn new(theme: ModeSelectTheme, event_sender: S, away: bool, suggestions: bool) -> Self {
+        let mut items = vec![
+            SettingsItem::Away(!away),
+            SettingsItem::Theme,
+            SettingsItem::Deadband,
+            SettingsItem::Autotune,
+            SettingsItem::Activity,
+            SettingsItem::Wiring,
+            SettingsItem::Logs,
+            SettingsItem::ReloadConfig,
+            SettingsItem::Back
+        ];
+        if suggestions {
+            items.insert(1, SettingsItem::Suggestions);
+        }
+
+        Self {
+            settings_list: ListWidget::new(theme.mode_list.clone(), &items, 0),
+            event_sender,
+            highlight_row: 0.0,
+            theme
+        }
+    }
+}
+
+impl<S: EventSender> Screen for SettingsScreen<S> { }
+
+impl<S: EventSender> EventHandler for SettingsScreen<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<()> {
+        match event {
+            Event::Dial(dir) => {
+                let highlight = self.highlight_row + (*dir as f32 * 0.01);
+                if scroll_list(&mut self.settings_list, &mut self.highlight_row, highlight) {
+                    self.event_sender.send_event(Event::ClickSound)?;
+                }
+            }
+            Event::ButtonDown => {
+                match *self.settings_list.get_highlighted_value() {
+                    SettingsItem::Away(away) => {
+                        self.event_sender.send_event(Event::SetAway(away))?;
+                        self.event_sender.send_event(Event::NavigateBack)?;
+                    }
+                    SettingsItem::Theme => {
+                        self.event_sender.send_event(Event::NavigateTo(ScreenId::ThemeSelect))?;
+                    }
+                    SettingsItem::Deadband => {
+                        self.event_sender.send_event(Event::NavigateTo(ScreenId::Deadband))?;
+                    }
+                    SettingsItem::Suggestions => {
+                        self.event_sender.send_event(Event::NavigateTo(ScreenId::Suggestions))?;
+                    }
+                    SettingsItem::Autotune => {
+                        self.event_sender.send_event(Event::NavigateTo(ScreenId::Autotune))?;
+                    }
+                    SettingsItem::Activity => {
+                        self.event_sender.send_event(Event::NavigateTo(ScreenId::ActionLog))?;
+                    }
+                    SettingsItem::Wiring => {
+                        self.event_sender.send_event(Event::NavigateTo(ScreenId::WiringSetup))?;
+                    }
+                    SettingsItem::Logs => {
+                        self.event_sender.send_event(Event::NavigateTo(ScreenId::Logs))?;
+                    }
+                    SettingsItem::ReloadConfig => {
+                        self.event_sender.send_event(Event::ReloadConfig)?;
+                        self.event_sender.send_event(Event::NavigateBack)?;
+                    }
+                    SettingsItem::Back => {
+                        self.event_sender.send_event(Event::NavigateBack)?;
+                    }
+                }
+            }
+            _ => { }
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: EventSender> AppDrawable for SettingsScreen<S> {
+    fn draw(&self, target: &mut AppFrameBuf) -> Result<()> {
+        target.clear(self.theme.bg_colour)?;
+        draw_list(target, &self.settings_list, self.theme.bg_colour)
+    }
+}
+
+impl From<SettingsItem> for ListItem<SettingsItem> {
+    fn from(value: SettingsItem) -> Self {
+        let label = match value {
+            SettingsItem::Away(true) => "Set Away",
+            SettingsItem::Away(false) => "Set Home",
+            SettingsItem::Theme => "Theme",
+            SettingsItem::Deadband => "Deadband",
+            SettingsItem::Suggestions => "Suggestions",
+            SettingsItem::Autotune => "Autotune",
+            SettingsItem::Activity => "Activity",
+            SettingsItem::Wiring => "Wiring",
+            SettingsItem::Logs => "Logs",
+            SettingsItem::ReloadConfig => "Reload",
+            SettingsItem::Back => "Back"
+        };
+
+        ListItem {
+            value,
+            label: String::from(label)
+        }
+    }
+}
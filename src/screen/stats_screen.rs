@@ -0,0 +1,96 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use anyhow::Result;
+use chrono::Local;
+use embedded_graphics::{prelude::*, text::{Alignment, Text}};
+
+use crate::{
+    drawable::{AppDrawable, AppFrameBuf},
+    events::{Event, EventHandler, EventSender},
+    runtime_stats::{RuntimeStats, format_runtime},
+    theme::DiagnosticsTheme
+};
+use super::{Screen, draw_line};
+
+/// Heating and cooling runtime, today and for the last week
+pub struct StatsScreen<S> {
+    runtime: RuntimeStats,
+    event_sender: S,
+    theme: DiagnosticsTheme
+}
+
+impl<S: EventSender> StatsScreen<S> {
+    pub fn new(
+        theme: DiagnosticsTheme,
+        runtime: RuntimeStats,
+        event_sender: S
+    ) -> Self {
+        Self { runtime, event_sender, theme }
+    }
+}
+
+impl<S: EventSender> Screen for StatsScreen<S> { }
+
+impl<S: EventSender> EventHandler for StatsScreen<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<()> {
+        match event {
+            Event::ButtonDown => {
+                self.event_sender.send_event(Event::NavigateBack)?;
+            }
+            Event::RuntimeStats(runtime) => {
+                self.runtime = runtime.clone();
+            }
+            _ => { }
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: EventSender> AppDrawable for StatsScreen<S> {
+    fn draw(&self, target: &mut AppFrameBuf) -> Result<()> {
+        target.clear(self.theme.bg_colour)?;
+
+        let font_style = self.theme.title_font
+            .font_style(self.theme.fg_colour, self.theme.bg_colour);
+        Text::with_alignment("Runtime", self.theme.title_center, font_style, Alignment::Center)
+            .draw(target)?;
+
+        let day = Local::now().date_naive();
+        let today = self.runtime.day(day);
+        let (week_heating, week_cooling) = self.runtime.week(day);
+
+        let lines = [
+            format!("Today heat {}", format_runtime(today.heating)),
+            format!("Today cool {}", format_runtime(today.cooling)),
+            format!("Week heat {}", format_runtime(week_heating)),
+            format!("Week cool {}", format_runtime(week_cooling)),
+        ];
+
+        let line_height = self.theme.line_height;
+        let mut y = self.theme.title_center.y + line_height;
+
+        for line in lines {
+            draw_line(&self.theme, target, &line, y, false)?;
+            y += line_height;
+        }
+
+        Ok(())
+    }
+}
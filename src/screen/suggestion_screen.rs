@@ -0,0 +1,190 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use anyhow::Result;
+use embedded_graphics::prelude::*;
+
+use crate::{
+    config::WeekDayRange,
+    drawable::{AppDrawable, AppFrameBuf},
+    events::{Event, EventHandler, EventSender},
+    learning::Suggestion,
+    state::HvacMode,
+    temperature::TempUnit,
+    theme::ModeSelectTheme,
+    widgets::{ListItem, ListWidget}
+};
+use super::{Screen, mode_screen::{draw_list, scroll_list}};
+
+/// Review set points suggested from setpoint adjustments, each can be
+/// added to the schedule or dismissed
+pub struct SuggestionScreen<S> {
+    suggestions: Vec<Suggestion>,
+    /// Index of each suggestion, `None` for going back
+    suggestion_list: ListWidget<Option<usize>>,
+    /// Accept or dismiss the picked suggestion
+    review_list: Option<(usize, ListWidget<Review>)>,
+    event_sender: S,
+    highlight_row: f32,
+    theme: ModeSelectTheme
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Review {
+    Accept,
+    Dismiss,
+    Cancel
+}
+
+/// Suggestion at an index, labelled in the display unit
+#[derive(Clone)]
+struct Row(Option<(usize, Suggestion)>, TempUnit);
+
+impl<S: EventSender> SuggestionScreen<S> {
+    pub fn new(theme: ModeSelectTheme, event_sender: S, suggestions: Vec<Suggestion>, temp_unit: TempUnit) -> Self {
+        let rows: Vec<_> = suggestions.iter().cloned()
+            .enumerate()
+            .map(|row| Row(Some(row), temp_unit))
+            .chain([Row(None, temp_unit)])
+            .collect();
+
+        Self {
+            suggestions,
+            suggestion_list: ListWidget::new(theme.mode_list.clone(), &rows, 0),
+            review_list: None,
+            event_sender,
+            highlight_row: 0.0,
+            theme
+        }
+    }
+
+    fn review(&mut self, review: Review, index: usize) -> Result<()> {
+        let suggestion = self.suggestions[index].clone();
+        match review {
+            Review::Accept => {
+                self.event_sender.send_event(Event::AcceptSuggestion(suggestion))?;
+            }
+            Review::Dismiss => {
+                self.event_sender.send_event(Event::DismissSuggestion(suggestion))?;
+            }
+            Review::Cancel => {
+                self.review_list = None;
+                self.highlight_row = index as f32;
+                return Ok(());
+            }
+        }
+
+        self.event_sender.send_event(Event::ConfirmSound)?;
+        self.event_sender.send_event(Event::NavigateBack)?;
+        Ok(())
+    }
+}
+
+impl<S: EventSender> Screen for SuggestionScreen<S> { }
+
+impl<S: EventSender> EventHandler for SuggestionScreen<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<()> {
+        match event {
+            Event::Dial(dir) => {
+                let highlight = self.highlight_row + (*dir as f32 * 0.01);
+
+                let changed = if let Some((_, review_list)) = &mut self.review_list {
+                    scroll_list(review_list, &mut self.highlight_row, highlight)
+                } else {
+                    scroll_list(&mut self.suggestion_list, &mut self.highlight_row, highlight)
+                };
+
+                if changed {
+                    self.event_sender.send_event(Event::ClickSound)?;
+                }
+            }
+            Event::ButtonDown => {
+                if let Some((index, review_list)) = &self.review_list {
+                    let (review, index) = (*review_list.get_highlighted_value(), *index);
+                    self.review(review, index)?;
+                } else if let Some(index) = *self.suggestion_list.get_highlighted_value() {
+                    self.review_list = Some((index, ListWidget::new(
+                        self.theme.mode_list.clone(),
+                        &[Review::Accept, Review::Dismiss, Review::Cancel],
+                        0
+                    )));
+                    self.highlight_row = 0.0;
+                } else {
+                    self.event_sender.send_event(Event::NavigateBack)?;
+                }
+            }
+            _ => { }
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: EventSender> AppDrawable for SuggestionScreen<S> {
+    fn draw(&self, target: &mut AppFrameBuf) -> Result<()> {
+        target.clear(self.theme.bg_colour)?;
+
+        if let Some((_, review_list)) = &self.review_list {
+            draw_list(target, review_list, self.theme.bg_colour)
+        } else {
+            draw_list(target, &self.suggestion_list, self.theme.bg_colour)
+        }
+    }
+}
+
+impl From<Row> for ListItem<Option<usize>> {
+    fn from(Row(row, temp_unit): Row) -> Self {
+        let Some((index, suggestion)) = row else {
+            return ListItem { value: None, label: String::from("Back") };
+        };
+
+        let mode = match suggestion.mode {
+            HvacMode::Cool => "Cool",
+            _ => "Heat"
+        };
+        let days = match suggestion.days {
+            WeekDayRange::WeekEnd => "Sat-Sun",
+            WeekDayRange::WeekDays => "Mon-Fri",
+            WeekDayRange::EveryDay => "Daily"
+        };
+
+        ListItem {
+            value: Some(index),
+            label: format!(
+                "{mode} {days} {} {}°",
+                suggestion.time.format("%H:%M"),
+                suggestion.temp.format(temp_unit)
+            )
+        }
+    }
+}
+
+impl From<Review> for ListItem<Review> {
+    fn from(value: Review) -> Self {
+        let label = match value {
+            Review::Accept => "Add",
+            Review::Dismiss => "Dismiss",
+            Review::Cancel => "Cancel"
+        };
+
+        ListItem {
+            value,
+            label: String::from(label)
+        }
+    }
+}
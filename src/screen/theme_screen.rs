@@ -0,0 +1,92 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use anyhow::Result;
+use embedded_graphics::prelude::*;
+
+use crate::{
+    drawable::{AppDrawable, AppFrameBuf},
+    events::{Event, EventHandler, EventSender},
+    theme::ModeSelectTheme,
+    widgets::{ListItem, ListWidget}
+};
+use super::{Screen, mode_screen::{draw_list, scroll_list}};
+
+/// Select one of the named themes, the screens are re-created with the
+/// new theme once it's loaded
+pub struct ThemeScreen<S> {
+    theme_list: ListWidget<String>,
+    event_sender: S,
+    highlight_row: f32,
+    theme: ModeSelectTheme
+}
+
+impl<S: EventSender> ThemeScreen<S> {
+    pub fn new(theme: ModeSelectTheme, event_sender: S, theme_names: &[String], current_theme: &str) -> Self {
+        let selected_row = theme_names.iter()
+            .position(|name| name == current_theme)
+            .unwrap_or_default();
+
+        Self {
+            theme_list: ListWidget::new(theme.mode_list.clone(), theme_names, selected_row),
+            event_sender,
+            highlight_row: selected_row as f32,
+            theme
+        }
+    }
+}
+
+impl<S: EventSender> Screen for ThemeScreen<S> { }
+
+impl<S: EventSender> EventHandler for ThemeScreen<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<()> {
+        match event {
+            Event::Dial(dir) => {
+                let highlight = self.highlight_row + (*dir as f32 * 0.01);
+                if scroll_list(&mut self.theme_list, &mut self.highlight_row, highlight) {
+                    self.event_sender.send_event(Event::ClickSound)?;
+                }
+            }
+            Event::ButtonDown => {
+                let name = self.theme_list.get_highlighted_value().clone();
+                self.event_sender.send_event(Event::SetTheme(name))?;
+                self.event_sender.send_event(Event::ConfirmSound)?;
+                self.event_sender.send_event(Event::NavigateBack)?;
+            }
+            _ => { }
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: EventSender> AppDrawable for ThemeScreen<S> {
+    fn draw(&self, target: &mut AppFrameBuf) -> Result<()> {
+        target.clear(self.theme.bg_colour)?;
+        draw_list(target, &self.theme_list, self.theme.bg_colour)
+    }
+}
+
+impl From<String> for ListItem<String> {
+    fn from(value: String) -> Self {
+        ListItem {
+            label: value.clone(),
+            value
+        }
+    }
+}
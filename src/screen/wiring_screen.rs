@@ -0,0 +1,93 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use anyhow::Result;
+use embedded_graphics::{prelude::*, text::{Alignment, Text}};
+
+use crate::{
+    config::WireId,
+    drawable::{AppDrawable, AppFrameBuf},
+    events::{Event, EventHandler, EventSender},
+    theme::DiagnosticsTheme
+};
+use super::{Screen, draw_line};
+
+/// Warns that configured wires aren't plugged into the backplate,
+/// those wires are never switched on until the wiring is fixed
+pub struct WiringScreen<S> {
+    missing: Vec<WireId>,
+    detected: String,
+    event_sender: S,
+    theme: DiagnosticsTheme
+}
+
+impl<S: EventSender> WiringScreen<S> {
+    pub fn new(
+        theme: DiagnosticsTheme,
+        missing: Vec<WireId>,
+        detected: String,
+        event_sender: S
+    ) -> Self {
+        Self { missing, detected, event_sender, theme }
+    }
+}
+
+impl<S: EventSender> Screen for WiringScreen<S> { }
+
+impl<S: EventSender> EventHandler for WiringScreen<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<()> {
+        if let Event::ButtonDown = event {
+            self.event_sender.send_event(Event::NavigateBack)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: EventSender> AppDrawable for WiringScreen<S> {
+    fn draw(&self, target: &mut AppFrameBuf) -> Result<()> {
+        target.clear(self.theme.bg_colour)?;
+
+        let font_style = self.theme.title_font
+            .font_style(self.theme.fg_colour, self.theme.bg_colour);
+        Text::with_alignment("Wiring", self.theme.title_center, font_style, Alignment::Center)
+            .draw(target)?;
+
+        let line_height = self.theme.line_height;
+        let mut y = self.theme.title_center.y + line_height;
+
+        draw_line(&self.theme, target, "Configured wires not detected", y, false)?;
+        y += line_height;
+
+        let missing = self.missing.iter()
+            .map(|w| format!("{w:?}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        draw_line(&self.theme, target, &missing, y, false)?;
+        y += line_height * 2;
+
+        draw_line(&self.theme, target, "Detected", y, false)?;
+        y += line_height;
+        draw_line(&self.theme, target, &self.detected, y, true)?;
+        y += line_height * 2;
+
+        draw_line(&self.theme, target, "Check wiring config", y, true)?;
+
+        Ok(())
+    }
+}
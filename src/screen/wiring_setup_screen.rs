@@ -0,0 +1,351 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use anyhow::Result;
+use embedded_graphics::{prelude::*, text::{Alignment, Text}};
+
+use crate::{
+    config::{WireConfig, WireId},
+    drawable::{AppDrawable, AppFrameBuf},
+    events::{Event, EventHandler, EventSender},
+    theme::DiagnosticsTheme
+};
+use super::{Screen, draw_line};
+
+/// Wires picked by the setup, in order. Heat is required, the others can
+/// be left out.
+const ROLES: [&str; 3] = ["Heat", "Cool", "Fan"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Step {
+    /// Pick the wire for `ROLES[n]` with the dial
+    Pick(usize),
+    /// Wire for `ROLES[n]` is switched on, until the button is pressed
+    Test(usize),
+    /// Save the wiring, or turn the dial to cancel
+    Confirm
+}
+
+/// Wiring setup, picking the heat, cool and fan wires from the wires detected
+/// on the backplate. Each wire is switched on in turn to check it runs the
+/// right equipment, then the wiring is saved to the config file, keeping
+/// any stage 2 and aux wires already configured.
+pub struct WiringSetupScreen<S: EventSender> {
+    detected: Vec<WireId>,
+    /// Wiring in use, for the wires the setup doesn't pick
+    wiring: WireConfig,
+    step: Step,
+    /// Wire picked for each role, `None` when left out
+    picked: Vec<Option<WireId>>,
+    /// Dial position, the highlighted wire or choice
+    highlight: f32,
+    event_sender: S,
+    theme: DiagnosticsTheme
+}
+
+impl<S: EventSender> WiringSetupScreen<S> {
+    pub fn new(theme: DiagnosticsTheme, detected: Vec<WireId>, wiring: WireConfig, event_sender: S) -> Self {
+        Self {
+            detected,
+            wiring,
+            step: Step::Pick(0),
+            picked: Vec::new(),
+            highlight: 0.0,
+            event_sender,
+            theme
+        }
+    }
+
+    fn highlighted(&self, count: usize) -> usize {
+        (self.highlight as usize).min(count.saturating_sub(1))
+    }
+
+    /// Wires not yet picked for another role, and `None` for optional roles
+    fn choices(&self, role: usize) -> Vec<Option<WireId>> {
+        let mut choices: Vec<_> = self.detected.iter()
+            .filter(|w| !self.picked.contains(&Some(**w)))
+            .map(|w| Some(*w))
+            .collect();
+        if role > 0 {
+            choices.push(None);
+        }
+        choices
+    }
+
+    fn choice_count(&self) -> usize {
+        match self.step {
+            Step::Pick(role) => self.choices(role).len(),
+            Step::Test(_) => 1,
+            Step::Confirm => 2
+        }
+    }
+
+    fn wire(&self, role: usize) -> Option<WireId> {
+        self.picked.get(role).copied().flatten()
+    }
+
+    fn wiring(&self) -> Option<WireConfig> {
+        let heat = self.wire(0)?;
+        Some(self.wiring.with_wires(heat, self.wire(1), self.wire(2)))
+    }
+
+    /// Test the next picked wire from `role` on, or confirm when all
+    /// wires have been tested
+    fn next_test(&mut self, role: usize) -> Result<()> {
+        match (role..ROLES.len()).find(|r| self.wire(*r).is_some()) {
+            Some(role) => {
+                self.step = Step::Test(role);
+                self.event_sender.send_event(Event::TestWire(self.wire(role)))?;
+            }
+            None => {
+                if matches!(self.step, Step::Test(_)) {
+                    self.event_sender.send_event(Event::TestWire(None))?;
+                }
+                self.step = Step::Confirm;
+            }
+        }
+        self.highlight = 0.0;
+
+        Ok(())
+    }
+
+    fn button_down(&mut self) -> Result<()> {
+        match self.step {
+            _ if self.detected.is_empty() => {
+                self.event_sender.send_event(Event::NavigateBack)?;
+            }
+            Step::Pick(role) => {
+                let choices = self.choices(role);
+                self.picked.push(choices[self.highlighted(choices.len())]);
+                self.highlight = 0.0;
+
+                if role + 1 < ROLES.len() {
+                    self.step = Step::Pick(role + 1);
+                } else {
+                    self.next_test(0)?;
+                }
+            }
+            Step::Test(role) => {
+                self.next_test(role + 1)?;
+            }
+            Step::Confirm => {
+                if self.highlighted(2) == 0 && let Some(wiring) = self.wiring() {
+                    self.event_sender.send_event(Event::SaveWiring(wiring))?;
+                }
+                self.event_sender.send_event(Event::NavigateBack)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: EventSender> Screen for WiringSetupScreen<S> { }
+
+/// Leaving the screen mid test, such as on timeout, ends the test
+impl<S: EventSender> Drop for WiringSetupScreen<S> {
+    fn drop(&mut self) {
+        if matches!(self.step, Step::Test(_)) {
+            let _ = self.event_sender.send_event(Event::TestWire(None));
+        }
+    }
+}
+
+impl<S: EventSender> EventHandler for WiringSetupScreen<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<()> {
+        match event {
+            Event::ButtonDown => {
+                self.button_down()?;
+            }
+            Event::Dial(dir) => {
+                let max = self.choice_count().saturating_sub(1) as f32;
+                self.highlight = (self.highlight + *dir as f32 * 0.01).clamp(0.0, max);
+            }
+            _ => { }
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: EventSender> AppDrawable for WiringSetupScreen<S> {
+    fn draw(&self, target: &mut AppFrameBuf) -> Result<()> {
+        target.clear(self.theme.bg_colour)?;
+
+        let font_style = self.theme.title_font
+            .font_style(self.theme.fg_colour, self.theme.bg_colour);
+        Text::with_alignment("Wiring Setup", self.theme.title_center, font_style, Alignment::Center)
+            .draw(target)?;
+
+        let line_height = self.theme.line_height;
+        let mut y = self.theme.title_center.y + line_height;
+
+        if self.detected.is_empty() {
+            draw_line(&self.theme, target, "No wires detected", y, false)?;
+            y += line_height;
+            draw_line(&self.theme, target, "Check the backplate connection", y, true)?;
+            return Ok(());
+        }
+
+        match self.step {
+            Step::Pick(role) => {
+                draw_line(&self.theme, target, &format!("{} wire", ROLES[role]), y, false)?;
+                y += line_height * 2;
+
+                let choices = self.choices(role);
+                let highlighted = self.highlighted(choices.len());
+                for (i, wire) in choices.iter().enumerate() {
+                    draw_line(&self.theme, target, &wire_label(*wire), y, i != highlighted)?;
+                    y += line_height;
+                }
+            }
+            Step::Test(role) => {
+                draw_line(&self.theme, target, &format!("Testing {} on {}", ROLES[role], wire_label(self.wire(role))), y, false)?;
+                y += line_height * 2;
+                draw_line(&self.theme, target, "Check the equipment runs", y, true)?;
+                y += line_height;
+                draw_line(&self.theme, target, "Press to continue", y, true)?;
+            }
+            Step::Confirm => {
+                for (role, name) in ROLES.iter().enumerate() {
+                    draw_line(&self.theme, target, &format!("{name} {}", wire_label(self.wire(role))), y, false)?;
+                    y += line_height;
+                }
+                y += line_height;
+
+                let highlighted = self.highlighted(2);
+                draw_line(&self.theme, target, "Save and restart", y, highlighted != 0)?;
+                y += line_height;
+                draw_line(&self.theme, target, "Cancel", y, highlighted != 1)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn wire_label(wire: Option<WireId>) -> String {
+    match wire {
+        Some(wire) => format!("{wire:?}"),
+        None => String::from("None")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Config, events::{DefaultEventSource, EventSource, QueueSender}};
+
+    fn screen(detected: Vec<WireId>) -> (DefaultEventSource, WiringSetupScreen<QueueSender>) {
+        let events = DefaultEventSource::new();
+        let screen = WiringSetupScreen::new(
+            DiagnosticsTheme::default(),
+            detected,
+            Config::default().backplate.wiring,
+            events.event_sender()
+        );
+        (events, screen)
+    }
+
+    fn events(source: &mut DefaultEventSource) -> Vec<Event> {
+        std::iter::from_fn(|| source.poll_event().unwrap()).collect()
+    }
+
+    fn press(screen: &mut WiringSetupScreen<QueueSender>, dial: i32) -> Result<()> {
+        screen.handle_event(&Event::Dial(dial * 100))?;
+        screen.handle_event(&Event::ButtonDown)
+    }
+
+    #[test]
+    fn picked_wires_not_offered_again() -> Result<()> {
+        let (_events, mut screen) = screen(vec![WireId::W1, WireId::Y1, WireId::G]);
+
+        assert_eq!(screen.choices(0), vec![Some(WireId::W1), Some(WireId::Y1), Some(WireId::G)]);
+        press(&mut screen, 1)?;
+        assert_eq!(screen.choices(1), vec![Some(WireId::W1), Some(WireId::G), None]);
+        press(&mut screen, 0)?;
+        assert_eq!(screen.choices(2), vec![Some(WireId::G), None]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn heat_only_tests_and_saves() -> Result<()> {
+        let (mut source, mut screen) = screen(vec![WireId::W1, WireId::G]);
+
+        press(&mut screen, 0)?;
+        // last choice is none for cool and fan
+        press(&mut screen, 5)?;
+        press(&mut screen, 5)?;
+        assert_eq!(screen.step, Step::Test(0));
+        assert!(matches!(events(&mut source)[..], [Event::TestWire(Some(WireId::W1))]));
+
+        screen.handle_event(&Event::ButtonDown)?;
+        assert_eq!(screen.step, Step::Confirm);
+        assert!(matches!(events(&mut source)[..], [Event::TestWire(None)]));
+
+        screen.handle_event(&Event::ButtonDown)?;
+        let saved = events(&mut source);
+        assert!(matches!(&saved[..], [Event::SaveWiring(wiring), Event::NavigateBack]
+            if wiring.wires() == vec![WireId::W1]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn tests_each_picked_wire() -> Result<()> {
+        let (mut source, mut screen) = screen(vec![WireId::W1, WireId::Y1, WireId::G]);
+
+        press(&mut screen, 0)?;
+        press(&mut screen, 0)?;
+        press(&mut screen, 0)?;
+        assert!(matches!(events(&mut source)[..], [Event::TestWire(Some(WireId::W1))]));
+
+        screen.handle_event(&Event::ButtonDown)?;
+        assert!(matches!(events(&mut source)[..], [Event::TestWire(Some(WireId::Y1))]));
+
+        screen.handle_event(&Event::ButtonDown)?;
+        assert!(matches!(events(&mut source)[..], [Event::TestWire(Some(WireId::G))]));
+
+        screen.handle_event(&Event::ButtonDown)?;
+        assert_eq!(screen.step, Step::Confirm);
+
+        // cancel
+        screen.handle_event(&Event::Dial(100))?;
+        screen.handle_event(&Event::ButtonDown)?;
+        assert!(matches!(events(&mut source)[..], [Event::TestWire(None), Event::NavigateBack]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn leaving_mid_test_switches_off() -> Result<()> {
+        let (mut source, mut screen) = screen(vec![WireId::W1]);
+
+        press(&mut screen, 0)?;
+        press(&mut screen, 0)?;
+        press(&mut screen, 0)?;
+        assert_eq!(screen.step, Step::Test(0));
+        events(&mut source);
+
+        drop(screen);
+        assert!(matches!(events(&mut source)[..], [Event::TestWire(None)]));
+
+        Ok(())
+    }
+}
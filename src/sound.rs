@@ -16,9 +16,14 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use std::time::{Duration, Instant};
+
 use anyhow::Result;
 
-use crate::events::{Event, EventHandler};
+use crate::{
+    config::{SoundConfig, ToneConfig},
+    events::{DialVelocity, Event, EventHandler, EventSender, Topic}
+};
 
 #[cfg(feature = "device")]
 mod sound_evdev;
@@ -33,27 +38,175 @@ mod no_sound;
 use no_sound::NoSound as SoundProviderImpl;
 
 trait SoundProvider {
-    fn new() -> Result<Self> where Self: Sized;
-    fn click(&self) -> Result<()>;
+    fn new<S>(event_sender: S) -> Result<Self>
+        where S: EventSender + Send + 'static, Self: Sized;
+    /// Play `tones` one after the other
+    fn play(&self, tones: Vec<ToneConfig>) -> Result<()>;
 }
 
+/// Dial units per second considered a fast turn, 100 units is one degree
+const FAST_DIAL_SPEED: f32 = 500.0;
+/// Clicks closer together than this blur into a buzz, drop them
+const MIN_CLICK_GAP: Duration = Duration::from_millis(25);
+
 pub struct Sound<P> {
-    provider: P
+    provider: P,
+    config: SoundConfig,
+    muted: bool,
+    /// Clicks and beeps are muted during quiet hours
+    quiet: bool,
+    dial_velocity: DialVelocity,
+    last_click: Instant
 }
 
 impl Sound<SoundProviderImpl> {
-    pub fn new() -> Result<Self> {
+    pub fn new<S>(config: &SoundConfig, event_sender: S) -> Result<Self>
+        where S: EventSender + Send + 'static
+    {
+        // let Home Assistant know the starting state of the mute switch
+        event_sender.send_event(Event::SetMuted(config.muted))?;
+
         Ok(Self {
-            provider: SoundProviderImpl::new()?
+            provider: SoundProviderImpl::new(event_sender)?,
+            config: config.clone(),
+            muted: config.muted,
+            quiet: false,
+            dial_velocity: DialVelocity::new(),
+            last_click: Instant::now()
         })
     }
 }
 
+impl<P> Sound<P> {
+    /// Raise pitch with dial speed, so fast turns feel lighter
+    fn dial_click(&self) -> ToneConfig {
+        let speed = (self.dial_velocity.speed() / FAST_DIAL_SPEED).min(1.0);
+        let click = self.config.dial_click;
+        let freq = click.freq + ((self.config.dial_fast_freq - click.freq) as f32 * speed) as i32;
+
+        ToneConfig { freq, ..click }
+    }
+}
+
 impl<P: SoundProvider> EventHandler for Sound<P> {
     fn handle_event(&mut self, event: &Event) -> Result<()> {
-        if matches!(event, Event::ClickSound) {
-            self.provider.click()?;
+        match event {
+            Event::Dial(delta) => {
+                self.dial_velocity.update(*delta);
+            }
+            Event::SetMuted(muted) => {
+                self.muted = *muted;
+            }
+            Event::QuietHours(quiet) => {
+                self.quiet = *quiet;
+            }
+            Event::ConfigReloaded(reloaded) => {
+                // keep the mute switch, muted only applies at startup
+                self.config = reloaded.config.sound.clone();
+            }
+            _ if self.muted => { }
+            // identify still plays, it's asked for
+            Event::ClickSound | Event::ButtonDown | Event::ConfirmSound if self.quiet => { }
+            Event::ClickSound | Event::Identify if self.last_click.elapsed() >= MIN_CLICK_GAP => {
+                self.last_click = Instant::now();
+                self.provider.play(vec![self.dial_click()])?;
+            }
+            Event::ButtonDown => {
+                self.provider.play(vec![self.config.button_click])?;
+            }
+            Event::ConfirmSound if !self.config.confirm_beep.is_empty() => {
+                self.provider.play(self.config.confirm_beep.clone())?;
+            }
+            _ => { }
+        }
+
+        Ok(())
+    }
+
+    fn topics(&self) -> &'static [Topic] {
+        &[Topic::Input]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    /// Records the tones played
+    #[derive(Default)]
+    struct FakeProvider {
+        played: RefCell<Vec<ToneConfig>>
+    }
+
+    impl SoundProvider for FakeProvider {
+        fn new<S>(_event_sender: S) -> Result<Self>
+            where S: EventSender + Send + 'static, Self: Sized
+        {
+            Ok(Self::default())
+        }
+
+        fn play(&self, tones: Vec<ToneConfig>) -> Result<()> {
+            self.played.borrow_mut().extend(tones);
+            Ok(())
+        }
+    }
+
+    fn sound() -> Sound<FakeProvider> {
+        Sound {
+            provider: FakeProvider::default(),
+            config: SoundConfig::default(),
+            muted: false,
+            quiet: false,
+            dial_velocity: DialVelocity::new(),
+            last_click: Instant::now() - MIN_CLICK_GAP
+        }
+    }
+
+    #[test]
+    fn dial_click_rises_with_speed() -> Result<()> {
+        let mut sound = sound();
+        let config = SoundConfig::default();
+        assert_eq!(sound.dial_click(), config.dial_click);
+
+        // a fast turn, events arriving back to back
+        for _ in 0..10 {
+            sound.handle_event(&Event::Dial(100))?;
         }
+        assert_eq!(sound.dial_click().freq, config.dial_fast_freq);
+        assert_eq!(sound.dial_click().duration, config.dial_click.duration);
+
+        Ok(())
+    }
+
+    #[test]
+    fn clicks_close_together_dropped() -> Result<()> {
+        let mut sound = sound();
+
+        sound.handle_event(&Event::ClickSound)?;
+        sound.handle_event(&Event::ClickSound)?;
+        assert_eq!(sound.provider.played.borrow().len(), 1);
+
+        sound.last_click -= MIN_CLICK_GAP;
+        sound.handle_event(&Event::ClickSound)?;
+        assert_eq!(sound.provider.played.borrow().len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn quiet_hours_mute_clicks() -> Result<()> {
+        let mut sound = sound();
+
+        sound.handle_event(&Event::QuietHours(true))?;
+        sound.handle_event(&Event::ClickSound)?;
+        sound.handle_event(&Event::ButtonDown)?;
+        assert!(sound.provider.played.borrow().is_empty());
+
+        // identify still plays, it's asked for
+        sound.handle_event(&Event::Identify)?;
+        assert_eq!(sound.provider.played.borrow().len(), 1);
 
         Ok(())
     }
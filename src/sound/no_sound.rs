@@ -18,16 +18,19 @@
 
 use anyhow::Result;
 
+use crate::{config::ToneConfig, events::EventSender};
 use super::SoundProvider;
 
 pub struct NoSound;
 
 impl SoundProvider for NoSound {
-    fn new() -> Result<Self> {
+    fn new<S>(_event_sender: S) -> Result<Self>
+        where S: EventSender + Send + 'static
+    {
         Ok(NoSound)
     }
 
-    fn click(&self) -> Result<()> {
+    fn play(&self, _tones: Vec<ToneConfig>) -> Result<()> {
         Ok(())
     }
 }
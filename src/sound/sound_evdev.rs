@@ -16,23 +16,23 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{sync::mpsc::{Sender, channel}, thread, time::Duration};
+use std::{sync::mpsc::{Sender, channel}, thread};
 
 use anyhow::Result;
 use evdev::{Device, SoundCode, SoundEvent};
 
+use crate::{config::ToneConfig, events::EventSender, supervisor::{Subsystem, supervise}};
 use super::SoundProvider;
 
 pub struct SoundThread {
-    sender: Sender<()>
+    sender: Sender<Vec<ToneConfig>>
 }
 
-const CLICK_DURATION: Duration = Duration::from_millis(3);
-const CLICK_FREQ: i32 = 2000;
-
 impl SoundThread {
-    pub fn start(dev_path: &str) -> Result<Self> {
-        let (sender, receiver) = channel();
+    pub fn start<S>(dev_path: &str, event_sender: S) -> Result<Self>
+        where S: EventSender + Send + 'static
+    {
+        let (sender, receiver) = channel::<Vec<ToneConfig>>();
 
         let mut evdev = Device::open(dev_path)?;
 
@@ -41,18 +41,21 @@ impl SoundThread {
         // SND_TONE matches the sound made by nlclient input events
         //    - `value/tone` param changes freq. (higher = higher pitch sound)
 
-        thread::spawn(move || {
-            while let Ok(_) = receiver.recv() {
-                // sound on
-                evdev.send_events(&[*SoundEvent::new(SoundCode::SND_TONE, CLICK_FREQ)])
-                    .expect("Send sound on event");
+        // Stops when the sender is dropped
+        supervise(Subsystem::Sound, event_sender, move || {
+            while let Ok(tones) = receiver.recv() {
+                for tone in tones {
+                    // sound on, zero freq is a pause
+                    evdev.send_events(&[*SoundEvent::new(SoundCode::SND_TONE, tone.freq)])?;
 
-                thread::sleep(CLICK_DURATION);
+                    thread::sleep(tone.duration);
 
-                // sound off
-                evdev.send_events(&[*SoundEvent::new(SoundCode::SND_TONE, 0)])
-                    .expect("Send sound off event");
+                    // sound off
+                    evdev.send_events(&[*SoundEvent::new(SoundCode::SND_TONE, 0)])?;
+                }
             }
+
+            Ok(())
         });
 
         Ok(Self { sender })
@@ -60,11 +63,13 @@ impl SoundThread {
 }
 
 impl SoundProvider for SoundThread {
-    fn new() -> Result<Self> {
-        SoundThread::start("/dev/input/event0")
+    fn new<S>(event_sender: S) -> Result<Self>
+        where S: EventSender + Send + 'static
+    {
+        SoundThread::start("/dev/input/event0", event_sender)
     }
 
-    fn click(&self) -> Result<()> {
-        Ok(self.sender.send(())?)
+    fn play(&self, tones: Vec<ToneConfig>) -> Result<()> {
+        Ok(self.sender.send(tones)?)
     }
 }
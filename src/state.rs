@@ -19,39 +19,94 @@
 use std::time::{Duration, Instant};
 
 use anyhow::Result;
+use chrono::Local;
+use log::warn;
 use esphome_api::proto::{
     ClimateAction, ClimateFanMode, ClimateMode, ClimatePreset, ClimateStateResponse
 };
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    config::Config, events::{Event, EventHandler, EventSender}, timer::TimerId
+    autotune::{self, Tuning},
+    config::{Config, ControlStrategy, HoldMode}, events::{Event, EventHandler, EventSender},
+    occupancy::Occupancy, runtime_stats::RuntimeStats, temperature::Temperature,
+    timer::TimerId, zone::ZoneStatus
 };
 
-#[derive(Debug, Clone)]
+mod duty_cycle;
+
+use duty_cycle::DutyCycle;
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct ThermostatState {
-    pub target_temp: f32,
-    pub current_temp: f32,
+    pub target_temp: Temperature,
+    /// Heat below this temp in heat/cool mode
+    pub target_temp_low: Temperature,
+    /// Cool above this temp in heat/cool mode
+    pub target_temp_high: Temperature,
+    pub current_temp: Temperature,
+    /// How warm the room feels with the humidity, used in place of
+    /// `current_temp` for heating and cooling while comfort is enabled,
+    /// see [`crate::config::ComfortConfig`]
+    pub apparent_temp: Option<Temperature>,
     pub mode: HvacMode,
     pub action: HvacAction,
+    /// Active heating/cooling stage, 0 when idle, see [`crate::config::StagingConfig`]
+    pub stage: u8,
+    /// Heat pump auxiliary heat while heating, see [`crate::config::WireConfig`]
+    pub aux: AuxHeat,
+    /// Heating/cooling demand percent, the share of each cycle with time
+    /// proportional control, otherwise 100 while running
+    pub demand: u8,
+    /// Run the fan independent of heating/cooling
+    pub fan_mode: HvacFanMode,
     pub away: bool,
+    /// Home Assistant offline fallback active
+    pub offline: bool,
+    /// Target temp temporarily boosted, see [`crate::config::BoostConfig`]
+    pub boost: bool,
+    /// Target temp held against the schedule, see [`crate::config::HoldConfig`]
+    pub hold: bool,
     pub lockout: bool,
+    /// Backplate failed to switch a wire, heating or cooling may not be running
+    pub fault: bool,
+    /// Heating or cooling forced on by a safety limit, see [`crate::config::SafetyConfig`]
+    pub safety: bool,
+    /// Reason for being idle in heat/cool mode
+    pub holding: Option<HoldingReason>,
     /// Backplate connected flag
     pub backplate: bool,
 }
 
 impl ThermostatState {
-    pub const MIN_TEMP: f32 = 9.0;
-    pub const MAX_TEMP: f32 = 32.0;
+    pub const MIN_TEMP: Temperature = Temperature::from_celsius(9.0);
+    pub const MAX_TEMP: Temperature = Temperature::from_celsius(32.0);
+    /// Home Assistant custom preset shown while holding
+    pub const HOLD_PRESET: &str = "Hold";
+    /// Home Assistant custom preset for emergency heat mode
+    pub const EMERGENCY_HEAT_PRESET: &str = "Emergency Heat";
+    /// Smallest gap between the heat/cool mode setpoints
+    pub const MIN_RANGE: f32 = 1.0;
+
+    /// Temp moved inside the min/max range
+    pub fn clamp_temp(temp: Temperature) -> Temperature {
+        if temp > Self::MAX_TEMP {
+            Self::MAX_TEMP
+        } else if temp < Self::MIN_TEMP {
+            Self::MIN_TEMP
+        } else {
+            temp
+        }
+    }
 
-    pub fn temp_percent(temp: f32) -> f32 {
-        (temp - Self::MIN_TEMP) / (Self::MAX_TEMP - Self::MIN_TEMP)
+    pub fn temp_percent(temp: Temperature) -> f32 {
+        temp.percent(Self::MIN_TEMP, Self::MAX_TEMP)
     }
 
     /// Attempt to set target temp and return `true` if successful.
     /// Return `false` if value is outside of min/max range, or if value
     /// equals current target temp.
-    pub fn set_target_temp(&mut self, val: f32) -> bool {
+    pub fn set_target_temp(&mut self, val: Temperature) -> bool {
         if val > Self::MIN_TEMP && val < Self::MAX_TEMP && val != self.target_temp {
             self.target_temp = val;
             true
@@ -60,19 +115,62 @@ impl ThermostatState {
         }
     }
 
+    /// Attempt to set the heat/cool setpoints and return `true` if successful.
+    /// Return `false` if either value is outside of min/max range, if the
+    /// setpoints are closer than [`Self::MIN_RANGE`], or if neither changed.
+    pub fn set_target_range(&mut self, low: Temperature, high: Temperature) -> bool {
+        let changed = low != self.target_temp_low || high != self.target_temp_high;
+        if changed && low > Self::MIN_TEMP && high < Self::MAX_TEMP && high - low >= Self::MIN_RANGE {
+            self.target_temp_low = low;
+            self.target_temp_high = high;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Temp heating and cooling is controlled by
+    pub fn control_temp(&self) -> Temperature {
+        self.apparent_temp.unwrap_or(self.current_temp)
+    }
+
+    /// True when the fan wire should be on, regardless of action
+    pub fn fan_on(&self) -> bool {
+        self.fan_mode != HvacFanMode::Auto
+    }
+
     fn to_ha_state(&self) -> ClimateStateResponse {
         let mut state = ClimateStateResponse::default();
-        state.set_fan_mode(ClimateFanMode::ClimateFanAuto);
+        state.set_fan_mode(if self.fan_on() {
+            ClimateFanMode::ClimateFanOn
+        } else {
+            ClimateFanMode::ClimateFanAuto
+        });
 
         state.set_action(self.action.into());
         state.set_mode(self.mode.into());
-        state.current_temperature = self.current_temp;
-        state.target_temperature = self.target_temp;
+        state.current_temperature = self.current_temp.celsius();
+        // Home Assistant treats NaN as unset, showing only the setpoints
+        // that apply to the current mode
+        if self.mode == HvacMode::HeatCool {
+            state.target_temperature = f32::NAN;
+            state.target_temperature_low = self.target_temp_low.celsius();
+            state.target_temperature_high = self.target_temp_high.celsius();
+        } else {
+            state.target_temperature = self.target_temp.celsius();
+            state.target_temperature_low = f32::NAN;
+            state.target_temperature_high = f32::NAN;
+        }
         state.preset = if self.away {
             ClimatePreset::Away as i32
         } else {
             ClimatePreset::None as i32
         };
+        if self.mode == HvacMode::EmergencyHeat {
+            state.custom_preset = Self::EMERGENCY_HEAT_PRESET.to_string();
+        } else if self.hold && !self.away {
+            state.custom_preset = Self::HOLD_PRESET.to_string();
+        }
 
         state
     }
@@ -81,12 +179,25 @@ impl ThermostatState {
 impl Default for ThermostatState {
     fn default() -> Self {
         Self {
-            target_temp: 19.5,
-            current_temp: 20.0,
+            target_temp: Temperature::from_celsius(19.5),
+            target_temp_low: Temperature::from_celsius(19.5),
+            target_temp_high: Temperature::from_celsius(24.0),
+            current_temp: Temperature::from_celsius(20.0),
+            apparent_temp: None,
             action: HvacAction::Idle,
+            stage: 0,
+            aux: AuxHeat::Off,
+            demand: 0,
             mode: HvacMode::Heat,
+            fan_mode: HvacFanMode::Auto,
             away: false,
+            offline: false,
+            boost: false,
+            hold: false,
             lockout: false,
+            fault: false,
+            safety: false,
+            holding: None,
             backplate: false,
         }
     }
@@ -109,7 +220,11 @@ pub enum HvacMode {
     Off,
     Heat,
     Cool,
+    /// Heat below the low setpoint, cool above the high setpoint
+    HeatCool,
     Fan,
+    /// Heat with only the heat pump auxiliary heat, compressor off
+    EmergencyHeat,
 }
 
 impl TryFrom<ClimateMode> for HvacMode {
@@ -120,6 +235,7 @@ impl TryFrom<ClimateMode> for HvacMode {
             ClimateMode::Off => Self::Off,
             ClimateMode::Heat => Self::Heat,
             ClimateMode::Cool => Self::Cool,
+            ClimateMode::HeatCool => Self::HeatCool,
             ClimateMode::FanOnly => Self::Fan,
             v => return Err(anyhow::anyhow!("Unsupported climate mode {v:?}"))
         })
@@ -132,7 +248,10 @@ impl From<HvacMode> for ClimateMode {
             HvacMode::Off => Self::Off,
             HvacMode::Heat => Self::Heat,
             HvacMode::Cool => Self::Cool,
+            HvacMode::HeatCool => Self::HeatCool,
             HvacMode::Fan => Self::FanOnly,
+            // shown to Home Assistant as a custom preset
+            HvacMode::EmergencyHeat => Self::Heat,
         }
     }
 }
@@ -145,6 +264,17 @@ pub enum HvacAction {
     Fan,
 }
 
+/// Heat pump auxiliary heat, switched on the `aux_wire`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum AuxHeat {
+    #[default]
+    Off,
+    /// Aux heat along with the compressor
+    WithCompressor,
+    /// Aux heat alone, compressor off
+    Only
+}
+
 impl From<HvacAction> for ClimateAction {
     fn from(value: HvacAction) -> Self {
         match value {
@@ -156,56 +286,311 @@ impl From<HvacAction> for ClimateAction {
     }
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq)]
+pub enum HvacFanMode {
+    /// Fan only runs with heating/cooling, or in fan mode
+    #[default]
+    Auto,
+    /// Fan runs continuously
+    On,
+    /// Fan runs continuously until the fan timer elapses, then returns to auto
+    Timer,
+}
+
+impl TryFrom<ClimateFanMode> for HvacFanMode {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ClimateFanMode) -> anyhow::Result<Self> {
+        Ok(match value {
+            ClimateFanMode::ClimateFanAuto => Self::Auto,
+            ClimateFanMode::ClimateFanOn => Self::On,
+            v => return Err(anyhow::anyhow!("Unsupported fan mode {v:?}"))
+        })
+    }
+}
+
+/// Fields set in a Home Assistant climate command, applied together as
+/// one state change
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ClimateCommand {
+    pub mode: Option<HvacMode>,
+    pub fan_mode: Option<HvacFanMode>,
+    pub target_temp: Option<Temperature>,
+    pub target_temp_low: Option<Temperature>,
+    pub target_temp_high: Option<Temperature>,
+    pub away: Option<bool>,
+    pub hold: Option<bool>,
+    /// Enter emergency heat mode, or leave it for heat mode
+    pub emergency_heat: Option<bool>
+}
+
+impl ClimateCommand {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HoldingReason {
+    /// Current temp is inside the deadband, short of the target temp
+    Deadband,
+    /// Current temp reached the target temp
+    TargetReached,
+    /// Another zone is heating or cooling, see [`crate::config::ZoneConfig`]
+    Zone,
+    /// Heating paused for a quick temp drop, see [`crate::config::WindowOpenConfig`]
+    WindowOpen,
+}
+
+impl HoldingReason {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Deadband => "Holding",
+            Self::TargetReached => "Target reached",
+            Self::Zone => "Waiting for zone",
+            Self::WindowOpen => "Window open?",
+        }
+    }
+}
+
+/// Time without dialed target temp changes before re-evaluating the hvac
+/// action, so a long dial turn doesn't click relays mid-adjustment
+const TARGET_SETTLE_TIME: Duration = Duration::from_millis(1500);
+
+/// How often runtime of a running hvac action is added to the stats
+const RUNTIME_REFRESH: Duration = Duration::from_secs(5 * 60);
+
+/// Deadband limits when set from the settings screen or Home Assistant
+pub const MIN_DEADBAND: f32 = 0.1;
+pub const MAX_DEADBAND: f32 = 2.0;
+
 pub struct StateManager<S: EventSender> {
     event_sender: S,
     state: ThermostatState,
     config: Config,
-    saved_target_temp: f32,
+    saved_target_temp: Temperature,
+    /// Heat/cool setpoints restored when exiting away mode
+    saved_target_range: (Temperature, Temperature),
+    boost_restore_temp: Temperature,
     restore_mode: Option<HvacMode>,
+    /// Last target temp from the schedule, restored when the hold is released
+    scheduled_temp: Option<Temperature>,
+    /// Schedule is running for the current mode
+    schedule_active: bool,
     last_idle_time: Instant,
+    /// Cool wire detected on the backplate, assumed until capabilities arrive
+    cool_wired: bool,
+    runtime: RuntimeStats,
+    /// Last time runtime of the current hvac action was added to the stats
+    runtime_since: Instant,
+    duty_cycle: DutyCycle,
+    /// Time until the duty cycle switches, while time proportional control is in use
+    duty_switch: Option<Duration>,
+    /// Autotune is cycling heating/cooling around the target
+    autotune: bool,
+    tuning: Option<Tuning>,
+    zone: ZoneStatus,
+    /// Heating or cooling is held off while another zone runs
+    zone_waiting: bool,
+    /// Last backplate humidity reading, for the apparent temp
+    humidity: Option<f32>,
+    /// Heating is paused for an open window
+    window_open: bool,
+    /// Deadband in the config file, `config.temp_deadband` is the one in
+    /// use, which can be set from the settings screen or Home Assistant
+    config_deadband: f32,
 }
 
 impl<S: EventSender> StateManager<S> {
-    pub fn new(config: &Config, state: ThermostatState, event_sender: S) -> Result<Self> {
+    pub fn new(
+        config: &Config,
+        state: ThermostatState,
+        runtime: RuntimeStats,
+        event_sender: S
+    ) -> Result<Self> {
         event_sender.send_event(
-            Event::TimeoutReset(TimerId::Away, config.away_mode.timeout)
+            Event::TimeoutReset(TimerId::Backlight, config.backlight.timeout)
         )?;
         event_sender.send_event(
-            Event::TimeoutReset(TimerId::Backlight, config.backlight.timeout)
+            Event::TimeoutReset(TimerId::HaOffline, config.offline_mode.timeout)
+        )?;
+        event_sender.send_event(
+            Event::TimeoutReset(TimerId::Runtime, RUNTIME_REFRESH)
         )?;
 
         Ok(Self {
             event_sender,
             state,
             config: config.clone(),
-            saved_target_temp: 0.0,
+            saved_target_temp: Temperature::default(),
+            saved_target_range: (Temperature::default(), Temperature::default()),
+            boost_restore_temp: Temperature::default(),
             restore_mode: None,
+            scheduled_temp: None,
+            schedule_active: false,
             last_idle_time: Instant::now(),
+            cool_wired: true,
+            runtime,
+            runtime_since: Instant::now(),
+            duty_cycle: DutyCycle::default(),
+            duty_switch: None,
+            autotune: false,
+            tuning: None,
+            zone: ZoneStatus::default(),
+            zone_waiting: false,
+            humidity: None,
+            window_open: false,
+            config_deadband: config.temp_deadband,
         })
     }
 
-    fn set_target_temp(&mut self, temp: f32) -> bool {
-        let temp = (temp * 10.0).round() / 10.0;
+    /// Add time since the last update to the runtime of `action`
+    fn record_runtime(&mut self, action: HvacAction) -> Result<()> {
+        let elapsed = self.runtime_since.elapsed();
+        self.runtime_since = Instant::now();
+
+        if self.runtime.record(action, elapsed, Local::now().date_naive()) {
+            self.event_sender.send_event(Event::RuntimeStats(self.runtime.clone()))?;
+        }
+
+        Ok(())
+    }
+
+    fn set_target_temp(&mut self, temp: Temperature) -> Result<bool> {
+        let temp = temp.round_tenth();
         if temp != self.state.target_temp {
+            // manually changing the target replaces the boosted target
+            self.end_boost()?;
             self.state.target_temp = temp;
-            true
+            Ok(true)
         } else {
-            false
+            Ok(false)
+        }
+    }
+
+    /// Out of range setpoints are clamped to the min/max temp, setpoints
+    /// closer than [`ThermostatState::MIN_RANGE`] are ignored
+    fn set_target_range(&mut self, low: Temperature, high: Temperature) -> Result<bool> {
+        let low = ThermostatState::clamp_temp(low).round_tenth();
+        let high = ThermostatState::clamp_temp(high).round_tenth();
+        if low == self.state.target_temp_low && high == self.state.target_temp_high {
+            return Ok(false);
+        }
+
+        if high - low < ThermostatState::MIN_RANGE {
+            warn!("Ignoring setpoints {low}/{high}, closer than {}", ThermostatState::MIN_RANGE);
+            return Ok(false);
         }
+
+        // manually changing the setpoints replaces the boosted target
+        self.end_boost()?;
+        self.state.target_temp_low = low;
+        self.state.target_temp_high = high;
+        Ok(true)
+    }
+
+    /// Apply every field of the command before the hvac action is evaluated,
+    /// out of range setpoints are clamped to the min/max temp
+    fn apply_command(&mut self, cmd: &ClimateCommand) -> Result<bool> {
+        let mut changed = false;
+
+        if let Some(mode) = cmd.mode {
+            changed |= self.set_mode(mode)?;
+        }
+        match cmd.emergency_heat {
+            Some(true) => changed |= self.set_mode(HvacMode::EmergencyHeat)?,
+            // picking another preset goes back to regular heat
+            Some(false) if self.state.mode == HvacMode::EmergencyHeat => {
+                changed |= self.set_mode(HvacMode::Heat)?;
+            }
+            _ => { }
+        }
+        if let Some(fan_mode) = cmd.fan_mode {
+            changed |= self.set_fan_mode(fan_mode)?;
+        }
+
+        if let Some(temp) = cmd.target_temp
+            && self.set_target_temp(ThermostatState::clamp_temp(temp))?
+        {
+            changed = true;
+            self.set_hold(true)?;
+        }
+        // the other setpoint stays put when only one is sent
+        if cmd.target_temp_low.is_some() || cmd.target_temp_high.is_some() {
+            let low = cmd.target_temp_low.unwrap_or(self.state.target_temp_low);
+            let high = cmd.target_temp_high.unwrap_or(self.state.target_temp_high);
+
+            if self.set_target_range(low, high)? {
+                changed = true;
+                self.set_hold(true)?;
+            }
+        }
+
+        if let Some(away) = cmd.away {
+            changed |= self.set_away(away)?;
+        }
+        if let Some(hold) = cmd.hold {
+            changed |= self.set_hold(hold)?;
+        }
+
+        Ok(changed)
     }
 
-    fn set_current_temp(&mut self, temp: f32) -> bool {
-        let temp = (temp * 10.0).round() / 10.0;
+    fn set_current_temp(&mut self, temp: Temperature) -> bool {
+        let temp = temp.round_tenth();
         if temp != self.state.current_temp {
             self.state.current_temp = temp;
+            self.set_apparent_temp();
             true
         } else {
             false
         }
     }
 
+    /// Apparent temp from the current temp and humidity, `None` when
+    /// comfort is disabled or there is no humidity reading yet
+    fn set_apparent_temp(&mut self) -> bool {
+        let comfort = &self.config.comfort;
+        let apparent = self.humidity
+            .filter(|_| comfort.enabled)
+            .map(|humidity| {
+                self.state.current_temp.apparent(humidity, comfort.reference_humidity).round_tenth()
+            });
+
+        if apparent != self.state.apparent_temp {
+            self.state.apparent_temp = apparent;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Replace the config deadband, rounded to a tenth of a degree
+    fn set_deadband(&mut self, deadband: f32) -> Result<bool> {
+        if !deadband.is_finite() {
+            warn!("Ignoring deadband {deadband}");
+            return Ok(false);
+        }
+
+        let deadband = ((deadband * 10.0).round() / 10.0).clamp(MIN_DEADBAND, MAX_DEADBAND);
+
+        if deadband != self.config.temp_deadband {
+            self.config.temp_deadband = deadband;
+            self.event_sender.send_event(Event::Deadband(deadband))?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
     fn set_mode(&mut self, mode: HvacMode) -> Result<bool> {
         if mode != self.state.mode {
+            self.set_boost(false)?;
+
+            // the new mode has its own schedule
+            self.end_hold()?;
+            self.scheduled_temp = None;
+
             // switching from fan mode to some other mode
             if self.state.mode == HvacMode::Fan {
                 self.event_sender.send_event(Event::CancelTimer(TimerId::Fan))?;
@@ -219,6 +604,12 @@ impl<S: EventSender> StateManager<S> {
                 self.restore_mode = Some(self.state.mode);
             }
 
+            // Switching modes mid-cycle counts as going idle, so the min off
+            // time lockout applies before the new mode can start
+            if matches!(self.state.action, HvacAction::Heating | HvacAction::Cooling) {
+                self.last_idle_time = Instant::now();
+            }
+
             // Clear action when switching modes to avoid action previous action
             // persisting due to current temp being inside hysteresis band.
             self.state.action = HvacAction::Idle;
@@ -231,45 +622,240 @@ impl<S: EventSender> StateManager<S> {
         }
     }
 
-    fn set_away(&mut self, is_away: bool) -> bool {
+    fn set_fan_mode(&mut self, fan_mode: HvacFanMode) -> Result<bool> {
+        if fan_mode == self.state.fan_mode {
+            return Ok(false);
+        }
+
+        if self.state.fan_mode == HvacFanMode::Timer {
+            self.event_sender.send_event(Event::CancelTimer(TimerId::FanRun))?;
+        }
+        if fan_mode == HvacFanMode::Timer {
+            self.event_sender.send_event(
+                Event::TimeoutReset(TimerId::FanRun, self.config.default_fan_timeout)
+            )?;
+        }
+
+        self.state.fan_mode = fan_mode;
+
+        Ok(true)
+    }
+
+    fn set_away(&mut self, is_away: bool) -> Result<bool> {
         if is_away != self.state.away {
             self.state.away = is_away;
 
             if self.state.away {
+                // end boost first so the saved temp is the un-boosted target
+                self.set_boost(false)?;
                 self.saved_target_temp = self.state.target_temp;
+                self.saved_target_range = (self.state.target_temp_low, self.state.target_temp_high);
                 match self.state.mode {
-                    HvacMode::Heat => {
+                    HvacMode::Heat | HvacMode::EmergencyHeat => {
                         self.state.target_temp = self.config.away_mode.temp_heat;
                     }
                     HvacMode::Cool => {
                         self.state.target_temp = self.config.away_mode.temp_cool;
                     }
+                    HvacMode::HeatCool => {
+                        self.state.target_temp_low = self.config.away_mode.temp_heat;
+                        self.state.target_temp_high = self.config.away_mode.temp_cool;
+                    }
                     _ => { }
                 }
             } else {
                 self.state.target_temp = self.saved_target_temp;
+                (self.state.target_temp_low, self.state.target_temp_high) = self.saved_target_range;
             }
 
-            true
+            Ok(true)
         } else {
-            false
+            Ok(false)
+        }
+    }
+
+    fn set_boost(&mut self, is_boost: bool) -> Result<bool> {
+        if is_boost == self.state.boost {
+            return Ok(false);
+        }
+
+        if is_boost {
+            // away temp takes precedence, exiting away restores the saved temp
+            if self.state.away {
+                return Ok(false);
+            }
+
+            let delta = self.config.boost.temp_delta;
+            let temp = match self.state.mode {
+                HvacMode::Heat | HvacMode::EmergencyHeat => self.state.target_temp + delta,
+                HvacMode::Cool => self.state.target_temp - delta,
+                // boost only applies to heating or cooling
+                _ => return Ok(false)
+            };
+
+            let temp = ThermostatState::clamp_temp(temp);
+
+            self.boost_restore_temp = self.state.target_temp;
+            self.state.target_temp = temp;
+            self.state.boost = true;
+
+            self.event_sender.send_event(
+                Event::StartTickTimer(TimerId::Boost, self.config.boost.duration)
+            )?;
+        } else {
+            self.state.target_temp = self.boost_restore_temp;
+            self.end_boost()?;
+        }
+
+        Ok(true)
+    }
+
+    /// Clear boost flag without restoring the pre-boost target temp
+    fn end_boost(&mut self) -> Result<()> {
+        if self.state.boost {
+            self.state.boost = false;
+            self.event_sender.send_event(Event::CancelTimer(TimerId::Boost))?;
+        }
+
+        Ok(())
+    }
+
+    /// Start holding the target temp against schedule changes, or release
+    /// the hold and go back to the scheduled temp
+    fn set_hold(&mut self, is_hold: bool) -> Result<bool> {
+        if is_hold {
+            // nothing to hold against without a schedule
+            if !self.schedule_active {
+                return Ok(false);
+            }
+
+            // each manual change restarts the hold time
+            if self.config.hold.mode == HoldMode::Duration {
+                self.event_sender.send_event(
+                    Event::TimeoutReset(TimerId::Hold, self.config.hold.duration)
+                )?;
+            }
+        } else if self.state.hold {
+            self.end_hold()?;
+            if let Some(temp) = self.scheduled_temp {
+                self.set_target_temp(temp)?;
+            }
+            return Ok(true);
+        }
+
+        let changed = is_hold != self.state.hold;
+        self.state.hold = is_hold;
+        Ok(changed)
+    }
+
+    /// Clear hold flag without restoring the scheduled temp
+    fn end_hold(&mut self) -> Result<()> {
+        if self.state.hold {
+            self.state.hold = false;
+            if self.config.hold.mode == HoldMode::Duration {
+                self.event_sender.send_event(Event::CancelTimer(TimerId::Hold))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn set_scheduled_temp(&mut self, temp: Temperature) -> Result<bool> {
+        self.scheduled_temp = Some(temp);
+
+        if !self.state.hold {
+            self.set_target_temp(temp)
+        } else if self.config.hold.mode == HoldMode::NextSetPoint {
+            self.set_hold(false)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn set_offline(&mut self, is_offline: bool) -> Result<bool> {
+        if is_offline != self.state.offline {
+            self.state.offline = is_offline;
+
+            // Leave target temp as-is when coming back online,
+            // home assistant will see the fallback temp and can change it
+            if self.state.offline {
+                if self.state.mode == HvacMode::HeatCool {
+                    let range = (self.config.offline_mode.temp_heat, self.config.offline_mode.temp_cool);
+                    if self.state.away {
+                        self.saved_target_range = range;
+                    } else {
+                        (self.state.target_temp_low, self.state.target_temp_high) = range;
+                    }
+                }
+
+                let temp = match self.state.mode {
+                    HvacMode::Heat | HvacMode::EmergencyHeat => Some(self.config.offline_mode.temp_heat),
+                    HvacMode::Cool => Some(self.config.offline_mode.temp_cool),
+                    _ => None
+                };
+
+                if let Some(temp) = temp {
+                    self.end_boost()?;
+
+                    // away mode restores the saved temp when exiting
+                    if self.state.away {
+                        self.saved_target_temp = temp;
+                    } else {
+                        self.state.target_temp = temp;
+                    }
+                }
+            }
+
+            Ok(true)
+        } else {
+            Ok(false)
         }
     }
 
     fn apply_hvac_action(&mut self) -> bool {
         let old_action = self.state.action;
+        self.duty_switch = None;
 
         if !self.state.backplate {
             self.state.action = HvacAction::Idle;
+            self.state.safety = false;
+            self.state.demand = 0;
+            self.duty_cycle.reset();
             return old_action != self.state.action;
         }
 
-        let current_temp = self.state.current_temp;
+        let current_temp = self.state.control_temp();
+        let safety_action = self.safety_action();
+
+        // Let the mode decide from idle once the safety override ends,
+        // otherwise safety heating could carry on in cooling mode
+        if self.state.safety && safety_action.is_none() {
+            self.state.action = HvacAction::Idle;
+        }
+
+        // Autotune measures the swing with a tight on/off band
+        let (strategy, deadband, overrun) = if self.autotune {
+            (ControlStrategy::Hysteresis, autotune::RELAY_HYSTERESIS, autotune::RELAY_HYSTERESIS)
+        } else {
+            (
+                self.config.control.strategy(&self.state.mode),
+                self.config.temp_deadband,
+                self.config.temp_overrun
+            )
+        };
 
         match self.state.mode {
-            HvacMode::Heat => {
-                let target_temp_hi = self.state.target_temp + self.config.temp_overrun;
-                let target_temp_lo = self.state.target_temp - self.config.temp_deadband;
+            HvacMode::Heat | HvacMode::EmergencyHeat if strategy == ControlStrategy::TimeProportional => {
+                let error = self.state.target_temp - current_temp;
+                self.state.action = self.cycle(error, HvacAction::Heating);
+            }
+            HvacMode::Cool if strategy == ControlStrategy::TimeProportional => {
+                let error = current_temp - self.state.target_temp;
+                self.state.action = self.cycle(error, HvacAction::Cooling);
+            }
+            HvacMode::Heat | HvacMode::EmergencyHeat => {
+                let target_temp_hi = self.state.target_temp + overrun;
+                let target_temp_lo = self.state.target_temp - deadband;
 
                 if current_temp <= target_temp_lo {
                     self.state.action = HvacAction::Heating;
@@ -278,8 +864,8 @@ impl<S: EventSender> StateManager<S> {
                 }
             }
             HvacMode::Cool => {
-                let target_temp_hi = self.state.target_temp + self.config.temp_deadband;
-                let target_temp_lo = self.state.target_temp - self.config.temp_overrun;
+                let target_temp_hi = self.state.target_temp + deadband;
+                let target_temp_lo = self.state.target_temp - overrun;
 
                 if current_temp >= target_temp_hi {
                     self.state.action = HvacAction::Cooling;
@@ -287,6 +873,32 @@ impl<S: EventSender> StateManager<S> {
                     self.state.action = HvacAction::Idle;
                 }
             }
+            HvacMode::HeatCool => {
+                let heat_temp = self.state.target_temp_low;
+                let cool_temp = self.state.target_temp_high;
+
+                match self.state.action {
+                    HvacAction::Heating => {
+                        if current_temp >= heat_temp + self.config.temp_overrun {
+                            self.state.action = HvacAction::Idle;
+                        }
+                    }
+                    HvacAction::Cooling => {
+                        if current_temp <= cool_temp - self.config.temp_overrun {
+                            self.state.action = HvacAction::Idle;
+                        }
+                    }
+                    _ => {
+                        self.state.action = if current_temp <= heat_temp - self.config.temp_deadband {
+                            HvacAction::Heating
+                        } else if current_temp >= cool_temp + self.config.temp_deadband {
+                            HvacAction::Cooling
+                        } else {
+                            HvacAction::Idle
+                        };
+                    }
+                }
+            }
             HvacMode::Fan => {
                 self.state.action = HvacAction::Fan;
             }
@@ -295,58 +907,340 @@ impl<S: EventSender> StateManager<S> {
             }
         };
 
+        // Only one zone runs the shared equipment, the running zone keeps it
+        let calling = matches!(self.state.action, HvacAction::Heating | HvacAction::Cooling);
+        let was_calling = matches!(old_action, HvacAction::Heating | HvacAction::Cooling);
+        self.zone_waiting = calling && self.zone.calling && (!was_calling || self.zone.outranked);
+        if self.zone_waiting {
+            self.state.action = HvacAction::Idle;
+        }
+
+        if self.window_open && self.state.action == HvacAction::Heating {
+            self.state.action = HvacAction::Idle;
+        }
+
+        // Safety limits take priority over the mode, including off and away
+        if let Some(action) = safety_action {
+            self.state.action = action;
+        }
+        self.state.safety = safety_action.is_some();
+
+        if self.duty_switch.is_none() {
+            self.duty_cycle.reset();
+        }
+
+        self.state.demand = if self.duty_switch.is_some() && !self.state.safety {
+            self.duty_cycle.demand()
+        } else if matches!(self.state.action, HvacAction::Heating | HvacAction::Cooling) {
+            100
+        } else {
+            0
+        };
+
         old_action != self.state.action
     }
 
-    fn apply_lockout(&mut self) -> Result<()> {
-        if self.state.action == HvacAction::Idle {
-            // don't reset last idle time until min idle time elapsed
-            // i.e. don't re-trigger lockout when it's already active
-            if self.last_idle_time.elapsed() > self.config.min_off_time {
-                self.last_idle_time = Instant::now();
-            }
+    /// Run `action` for the on part of the duty cycle, see [`crate::config::ControlConfig`]
+    fn cycle(&mut self, error: f32, action: HvacAction) -> HvacAction {
+        let mut control = self.config.control.clone();
+        if let Some(tuning) = self.tuning.filter(|_| control.use_tuning) {
+            control.proportional_band = tuning.proportional_band;
+            control.integral_time = tuning.integral_time();
+        }
 
-            self.state.lockout = false;
-        } else {
-            if self.last_idle_time.elapsed() < self.config.min_off_time {
-                let lockout_time = self.config.min_off_time - self.last_idle_time.elapsed();
-                self.state.lockout = true;
-                self.event_sender.send_event(
-                    Event::StartTickTimer(TimerId::HvacLockout, lockout_time)
-                )?;
-            } else {
-                self.state.lockout = false;
-            }
+        let (on, switch_in) = self.duty_cycle.update(&control, error, Instant::now());
+        self.duty_switch = Some(switch_in);
+
+        if on { action } else { HvacAction::Idle }
+    }
+
+    /// Re-evaluate the hvac action when the duty cycle next switches
+    fn apply_duty_timer(&mut self, was_cycling: bool) -> Result<()> {
+        if let Some(switch_in) = self.duty_switch {
+            self.event_sender.send_event(Event::TimeoutReset(TimerId::DutyCycle, switch_in))?;
+        } else if was_cycling {
+            self.event_sender.send_event(Event::CancelTimer(TimerId::DutyCycle))?;
         }
 
         Ok(())
     }
-}
 
-impl<S: EventSender> EventHandler for StateManager<S> {
-    fn handle_event(&mut self, event: &Event) -> Result<()> {
-        let did_change = match event {
-            Event::SetMode(mode) => {
-                self.set_mode(*mode)?
-            }
-            Event::SetTargetTemp(temp) => {
-                self.set_target_temp(*temp)
-            }
-            Event::SetCurrentTemp(temp) => {
-                self.set_current_temp(*temp)
+    /// Action forced by the safety limits, held until the room temp is
+    /// `hysteresis` degrees past the limit
+    fn safety_action(&self) -> Option<HvacAction> {
+        let safety = &self.config.safety;
+        let current_temp = self.state.current_temp;
+        let active = |action| self.state.safety && self.state.action == action;
+
+        if let Some(min_temp) = safety.min_temp
+            && (current_temp <= min_temp
+                || active(HvacAction::Heating) && current_temp < min_temp + safety.hysteresis)
+        {
+            return Some(HvacAction::Heating);
+        }
+
+        if let Some(max_temp) = safety.max_temp
+            && self.cool_wired
+            && (current_temp >= max_temp
+                || active(HvacAction::Cooling) && current_temp > max_temp - safety.hysteresis)
+        {
+            return Some(HvacAction::Cooling);
+        }
+
+        None
+    }
+
+    /// Distance from target temp in the direction of the heating/cooling action
+    fn temp_gap(&self) -> Option<f32> {
+        let (heat_temp, cool_temp) = if self.state.mode == HvacMode::HeatCool {
+            (self.state.target_temp_low, self.state.target_temp_high)
+        } else {
+            (self.state.target_temp, self.state.target_temp)
+        };
+
+        match self.state.action {
+            HvacAction::Heating => Some(heat_temp - self.state.control_temp()),
+            HvacAction::Cooling => Some(self.state.control_temp() - cool_temp),
+            _ => None
+        }
+    }
+
+    fn apply_stage(&mut self) -> Result<()> {
+        let old_stage = self.state.stage;
+        let staging = &self.config.staging;
+        let has_stage2 = !self.is_emergency_heat() && self.config.backplate.wiring
+            .stage2_wire(&self.state.action)
+            .is_some();
+
+        let Some(temp_gap) = self.temp_gap() else {
+            self.state.stage = 0;
+            self.state.aux = AuxHeat::Off;
+            if old_stage != 0 {
+                self.event_sender.send_event(Event::CancelTimer(TimerId::Stage2))?;
             }
-            Event::SetAway(false) | Event::ProximityNear | Event::ProximityFar | Event::Dial(_) => {
+            return Ok(());
+        };
+
+        if old_stage == 0 {
+            self.state.stage = 1;
+            if has_stage2 {
                 self.event_sender.send_event(
-                    Event::TimeoutReset(TimerId::Away, self.config.away_mode.timeout)
+                    Event::TimeoutReset(TimerId::Stage2, staging.stage2_delay)
                 )?;
-                self.set_away(false)
             }
-            Event::SetAway(true) | Event::TimeoutReached(TimerId::Away) => {
-                self.set_away(true)
+        }
+
+        if has_stage2 {
+            if temp_gap >= staging.stage2_temp_delta {
+                self.state.stage = 2;
+            } else if self.state.stage == 2 && temp_gap <= staging.stage_down_delta {
+                self.state.stage = 1;
+                // first stage gets another `stage2_delay` before moving up again
+                self.event_sender.send_event(
+                    Event::TimeoutReset(TimerId::Stage2, staging.stage2_delay)
+                )?;
             }
-            Event::TimeoutReached(TimerId::HvacLockout) => {
-                self.state.lockout = false;
-                true
+        } else {
+            // switched into emergency heat, or the stage 2 wire was removed
+            self.state.stage = 1;
+        }
+
+        // Compressor and aux heat only run together when configured
+        self.state.aux = if self.state.action != HvacAction::Heating {
+            AuxHeat::Off
+        } else if self.is_emergency_heat() {
+            AuxHeat::Only
+        } else if self.state.stage == 2 && self.config.backplate.wiring.aux_stage2() {
+            if staging.aux_with_compressor { AuxHeat::WithCompressor } else { AuxHeat::Only }
+        } else {
+            AuxHeat::Off
+        };
+
+        Ok(())
+    }
+
+    /// Emergency heat mode with an aux wire to heat with, otherwise it heats
+    /// like heat mode
+    fn is_emergency_heat(&self) -> bool {
+        self.state.mode == HvacMode::EmergencyHeat
+            && self.config.backplate.wiring.aux_wire().is_some()
+    }
+
+    /// First stage ran for `stage2_delay`, move up unless nearly at target
+    fn stage_up(&mut self) -> bool {
+        let Some(temp_gap) = self.temp_gap() else {
+            return false;
+        };
+        if self.is_emergency_heat() {
+            return false;
+        }
+
+        if self.state.stage == 1 && temp_gap > self.config.staging.stage_down_delta {
+            self.state.stage = 2;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn apply_holding(&mut self) {
+        let current_temp = self.state.control_temp();
+        let target_temp = self.state.target_temp;
+
+        self.state.holding = if !self.config.show_holding
+            || !self.state.backplate
+            || self.state.action != HvacAction::Idle
+        {
+            None
+        } else if self.window_open
+            && matches!(self.state.mode, HvacMode::Heat | HvacMode::EmergencyHeat | HvacMode::HeatCool)
+        {
+            Some(HoldingReason::WindowOpen)
+        } else if self.zone_waiting {
+            Some(HoldingReason::Zone)
+        } else {
+            match self.state.mode {
+                HvacMode::Heat | HvacMode::EmergencyHeat
+                    if current_temp < target_temp => Some(HoldingReason::Deadband),
+                HvacMode::Cool if current_temp > target_temp => Some(HoldingReason::Deadband),
+                HvacMode::Heat | HvacMode::EmergencyHeat | HvacMode::Cool => Some(HoldingReason::TargetReached),
+                HvacMode::HeatCool
+                    if current_temp < self.state.target_temp_low
+                    || current_temp > self.state.target_temp_high => Some(HoldingReason::Deadband),
+                HvacMode::HeatCool => Some(HoldingReason::TargetReached),
+                _ => None
+            }
+        };
+    }
+
+    fn apply_lockout(&mut self) -> Result<()> {
+        if self.state.action == HvacAction::Idle {
+            // don't reset last idle time until min idle time elapsed
+            // i.e. don't re-trigger lockout when it's already active
+            if self.last_idle_time.elapsed() > self.config.min_off_time {
+                self.last_idle_time = Instant::now();
+            }
+
+            self.state.lockout = false;
+        } else {
+            if self.last_idle_time.elapsed() < self.config.min_off_time {
+                let lockout_time = self.config.min_off_time - self.last_idle_time.elapsed();
+                self.state.lockout = true;
+                self.event_sender.send_event(
+                    Event::StartTickTimer(TimerId::HvacLockout, lockout_time)
+                )?;
+            } else {
+                self.state.lockout = false;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: EventSender> EventHandler for StateManager<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<()> {
+        let action = self.state.action;
+
+        let did_change = match event {
+            Event::SetMode(mode) => {
+                self.set_mode(*mode)?
+            }
+            Event::SetFanMode(fan_mode) => {
+                self.set_fan_mode(*fan_mode)?
+            }
+            Event::TimeoutReached(TimerId::FanRun) => {
+                self.set_fan_mode(HvacFanMode::Auto)?
+            }
+            Event::SetTargetTemp(temp) => {
+                let changed = self.set_target_temp(*temp)?;
+                if changed {
+                    self.event_sender.send_event(
+                        Event::TimeoutReset(TimerId::TargetSettle, TARGET_SETTLE_TIME)
+                    )?;
+                    self.set_hold(true)?;
+                }
+                changed
+            }
+            Event::SetTargetRange(low, high) => {
+                let changed = self.set_target_range(*low, *high)?;
+                if changed {
+                    self.event_sender.send_event(
+                        Event::TimeoutReset(TimerId::TargetSettle, TARGET_SETTLE_TIME)
+                    )?;
+                    self.set_hold(true)?;
+                }
+                changed
+            }
+            Event::ClimateCommand(cmd) => {
+                self.apply_command(cmd)?
+            }
+            Event::ScheduledTargetTemp(temp) => {
+                self.set_scheduled_temp(*temp)?
+            }
+            Event::NextSetPoint(next) => {
+                self.schedule_active = next.is_some();
+                if !self.schedule_active {
+                    self.scheduled_temp = None;
+                    let changed = self.state.hold;
+                    self.end_hold()?;
+                    changed
+                } else {
+                    false
+                }
+            }
+            Event::TimeoutReached(TimerId::Hold) => {
+                self.set_hold(false)?
+            }
+            Event::TimeoutReached(TimerId::TargetSettle) => {
+                true
+            }
+            Event::SetCurrentTemp(temp) => {
+                self.set_current_temp(*temp)
+            }
+            Event::Humidity(humidity) => {
+                self.humidity = Some(*humidity);
+                self.set_apparent_temp()
+            }
+            Event::Occupancy(occupancy) => {
+                self.set_away(*occupancy == Occupancy::Away)?
+            }
+            Event::SetBoost(is_boost) => {
+                self.set_boost(*is_boost)?
+            }
+            Event::TimeoutReached(TimerId::Boost) => {
+                self.set_boost(false)?
+            }
+            Event::TimeoutReached(TimerId::Stage2) => {
+                self.stage_up()
+            }
+            Event::TimeoutReached(TimerId::DutyCycle) => {
+                true
+            }
+            Event::Autotune(status) => {
+                let changed = status.is_running() != self.autotune;
+                self.autotune = status.is_running();
+                changed
+            }
+            Event::Tuning(tuning) if !tuning.is_valid() => {
+                warn!("Ignoring invalid tuning {tuning:?}");
+                false
+            }
+            Event::Tuning(tuning) => {
+                self.tuning = Some(*tuning);
+                false
+            }
+            Event::Zone(zone) => {
+                self.zone = *zone;
+                true
+            }
+            Event::WindowOpen(open) => {
+                self.window_open = *open;
+                true
+            }
+            Event::TimeoutReached(TimerId::HvacLockout) => {
+                self.state.lockout = false;
+                true
             }
             Event::TimeoutReached(TimerId::Fan) => {
                 let mode = self.restore_mode.unwrap_or(HvacMode::Off);
@@ -360,17 +1254,78 @@ impl<S: EventSender> EventHandler for StateManager<S> {
                 self.state.backplate = false;
                 true
             }
+            Event::BackplateCapabilities(capabilities) => {
+                self.cool_wired = self.config.backplate.wiring.cool_wire()
+                    .is_some_and(|wire| capabilities.wires.has_wire(wire));
+                true
+            }
+            Event::HvacFault(wires) => {
+                let fault = !wires.is_empty();
+                let changed = fault != self.state.fault;
+                self.state.fault = fault;
+                changed
+            }
+            Event::HaConnected => {
+                self.event_sender.send_event(Event::CancelTimer(TimerId::HaOffline))?;
+                self.set_offline(false)?
+            }
+            Event::HaDisconnected => {
+                self.event_sender.send_event(
+                    Event::TimeoutReset(TimerId::HaOffline, self.config.offline_mode.timeout)
+                )?;
+                false
+            }
+            Event::TimeoutReached(TimerId::HaOffline) => {
+                self.set_offline(true)?
+            }
+            Event::SetDeadband(deadband) => {
+                self.set_deadband(*deadband)?
+            }
+            Event::ConfigReloaded(reloaded) => {
+                // re-evaluate the hvac action with the new deadband and staging
+                let deadband = self.config.temp_deadband;
+                let config_changed = reloaded.config.temp_deadband != self.config_deadband;
+                self.config = reloaded.config.clone();
+                self.config_deadband = self.config.temp_deadband;
+                // a deadband changed in the config replaces the one set at runtime
+                if !config_changed {
+                    self.config.temp_deadband = deadband;
+                }
+                if self.config.temp_deadband != deadband {
+                    self.event_sender.send_event(Event::Deadband(self.config.temp_deadband))?;
+                }
+                self.set_apparent_temp();
+                true
+            }
             _ => false
         };
 
         if did_change {
-            if self.apply_hvac_action() {
-                self.apply_lockout()?;
+            // Dialed target is shown right away, hvac action waits for it to settle
+            let settling = matches!(event, Event::SetTargetTemp(_) | Event::SetTargetRange(..));
+            if !settling {
+                let was_cycling = self.duty_cycle.is_running();
+                if self.apply_hvac_action() {
+                    self.apply_lockout()?;
+                }
+                self.apply_duty_timer(was_cycling)?;
             }
 
+            self.apply_stage()?;
+            self.apply_holding();
+
             self.event_sender.send_event(Event::State(self.state.clone()))?;
         }
 
+        if matches!(event, Event::TimeoutReached(TimerId::Runtime)) {
+            self.record_runtime(action)?;
+            self.event_sender.send_event(
+                Event::TimeoutReset(TimerId::Runtime, RUNTIME_REFRESH)
+            )?;
+        } else if self.state.action != action {
+            self.record_runtime(action)?;
+        }
+
         if event.is_wakeup_event() {
             self.event_sender.send_event(
                 Event::TimeoutReset(TimerId::Backlight, self.config.backlight.timeout)
@@ -379,6 +1334,8 @@ impl<S: EventSender> EventHandler for StateManager<S> {
 
         if event == &Event::GetState {
             self.event_sender.send_event(Event::State(self.state.clone()))?;
+            self.event_sender.send_event(Event::RuntimeStats(self.runtime.clone()))?;
+            self.event_sender.send_event(Event::Deadband(self.config.temp_deadband))?;
         }
 
         Ok(())
@@ -387,14 +1344,18 @@ impl<S: EventSender> EventHandler for StateManager<S> {
 
 #[cfg(test)]
 mod tests {
-    use std::sync::mpsc::Sender;
-
     use super::*;
-    use crate::events::{DefaultEventSource, EventSource};
+    use crate::events::{DefaultEventSource, EventSource, QueueSender};
+    use crate::backplate::BackplateCapabilities;
+    use crate::config::{WireConfig, WireId};
+    use crate::schedule::NextSetPoint;
+    use crate::reload::ReloadedConfig;
+    use crate::theme::Theme;
+    use std::sync::Arc;
 
     fn state_manager(
         state: ThermostatState
-    ) -> (DefaultEventSource, StateManager<Sender<Event>>)
+    ) -> (DefaultEventSource, StateManager<QueueSender>)
     {
         let mut config = Config::default();
         config.temp_deadband = 0.4;
@@ -402,7 +1363,7 @@ mod tests {
 
         let event_source = DefaultEventSource::new();
         let state_manager = StateManager::new(
-            &config, state,
+            &config, state, RuntimeStats::default(),
             event_source.event_sender()
         ).unwrap();
 
@@ -416,7 +1377,7 @@ mod tests {
         where S: EventSender
     {
         for (temp, action) in steps {
-            state.handle_event(&Event::SetCurrentTemp(*temp))?;
+            state.handle_event(&Event::SetCurrentTemp(Temperature::from_celsius(*temp)))?;
             assert_eq!(
                 state.state.action,
                 *action,
@@ -432,8 +1393,8 @@ mod tests {
     fn temp_hysteresis_heat_on() -> Result<()> {
         let state = ThermostatState {
             mode: HvacMode::Heat,
-            target_temp: 20.0,
-            current_temp: 20.0,
+            target_temp: Temperature::from_celsius(20.0),
+            current_temp: Temperature::from_celsius(20.0),
             action: HvacAction::Idle,
             backplate: true,
             ..ThermostatState::default()
@@ -454,8 +1415,8 @@ mod tests {
     fn temp_hysteresis_heat_off() -> Result<()> {
         let state = ThermostatState {
             mode: HvacMode::Heat,
-            target_temp: 20.0,
-            current_temp: 20.0,
+            target_temp: Temperature::from_celsius(20.0),
+            current_temp: Temperature::from_celsius(20.0),
             action: HvacAction::Heating,
             backplate: true,
             ..ThermostatState::default()
@@ -470,12 +1431,122 @@ mod tests {
         ])
     }
 
+    #[test]
+    fn comfort_heats_dry_air() -> Result<()> {
+        let state = ThermostatState {
+            mode: HvacMode::Heat,
+            target_temp: Temperature::from_celsius(20.0),
+            current_temp: Temperature::from_celsius(20.0),
+            action: HvacAction::Idle,
+            backplate: true,
+            ..ThermostatState::default()
+        };
+
+        let (_x, mut mgr) = state_manager(state);
+
+        // ignored until comfort is enabled
+        mgr.handle_event(&Event::Humidity(25.0))?;
+        assert_eq!(mgr.state.apparent_temp, None);
+        assert_eq!(mgr.state.action, HvacAction::Idle);
+
+        mgr.config.comfort.enabled = true;
+        mgr.handle_event(&Event::Humidity(25.0))?;
+        assert_eq!(mgr.state.apparent_temp, Some(Temperature::from_celsius(18.5)));
+        assert_eq!(mgr.state.action, HvacAction::Heating);
+
+        // feels warm enough once the humidity rises
+        mgr.handle_event(&Event::Humidity(50.0))?;
+        assert_eq!(mgr.state.apparent_temp, Some(Temperature::from_celsius(20.4)));
+        assert_eq!(mgr.state.action, HvacAction::Idle);
+
+        Ok(())
+    }
+
+    #[test]
+    fn deadband_from_settings() -> Result<()> {
+        let state = ThermostatState {
+            mode: HvacMode::Heat,
+            target_temp: Temperature::from_celsius(20.0),
+            current_temp: Temperature::from_celsius(19.7),
+            action: HvacAction::Idle,
+            backplate: true,
+            ..ThermostatState::default()
+        };
+
+        let (mut events, mut mgr) = state_manager(state);
+        while events.poll_event()?.is_some() { }
+
+        // within the 0.4 deadband until it's narrowed
+        mgr.handle_event(&Event::SetDeadband(0.22))?;
+        assert_eq!(mgr.config.temp_deadband, 0.2);
+        assert_eq!(mgr.state.action, HvacAction::Heating);
+        assert!(matches!(events.poll_event()?, Some(Event::Deadband(deadband)) if deadband == 0.2));
+
+        mgr.handle_event(&Event::SetDeadband(5.0))?;
+        assert_eq!(mgr.config.temp_deadband, MAX_DEADBAND);
+
+        mgr.handle_event(&Event::SetDeadband(f32::NAN))?;
+        assert_eq!(mgr.config.temp_deadband, MAX_DEADBAND);
+
+        let reloaded = |temp_deadband| Event::ConfigReloaded(Arc::new(ReloadedConfig {
+            config: Config { temp_deadband, ..Config::default() },
+            theme: Theme::default(),
+            theme_name: String::new()
+        }));
+
+        // kept over the unchanged config deadband
+        mgr.handle_event(&reloaded(0.4))?;
+        assert_eq!(mgr.config.temp_deadband, MAX_DEADBAND);
+
+        // config deadband changed, it's used again
+        mgr.handle_event(&reloaded(0.8))?;
+        assert_eq!(mgr.config.temp_deadband, 0.8);
+
+        // sent for a new HA client
+        while events.poll_event()?.is_some() { }
+        mgr.handle_event(&Event::GetState)?;
+        let mut sent = Vec::new();
+        while let Some(event) = events.poll_event()? {
+            sent.push(event);
+        }
+        assert!(sent.iter().any(|e| matches!(e, Event::Deadband(deadband) if *deadband == 0.8)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn temp_hysteresis_heat_cool() -> Result<()> {
+        let state = ThermostatState {
+            mode: HvacMode::HeatCool,
+            target_temp_low: Temperature::from_celsius(20.0),
+            target_temp_high: Temperature::from_celsius(24.0),
+            current_temp: Temperature::from_celsius(22.0),
+            action: HvacAction::Idle,
+            backplate: true,
+            ..ThermostatState::default()
+        };
+
+        let (_x, mgr) = state_manager(state);
+
+        simulate(mgr, &[
+            (22.0, HvacAction::Idle),
+            (19.7, HvacAction::Idle),
+            (19.6, HvacAction::Heating),
+            (20.1, HvacAction::Heating),
+            (20.2, HvacAction::Idle),
+            (24.3, HvacAction::Idle),
+            (24.4, HvacAction::Cooling),
+            (23.9, HvacAction::Cooling),
+            (23.8, HvacAction::Idle)
+        ])
+    }
+
     #[test]
     fn temp_hysteresis_cool_on() -> Result<()> {
         let state = ThermostatState {
             mode: HvacMode::Cool,
-            target_temp: 20.0,
-            current_temp: 20.0,
+            target_temp: Temperature::from_celsius(20.0),
+            current_temp: Temperature::from_celsius(20.0),
             action: HvacAction::Idle,
             backplate: true,
             ..ThermostatState::default()
@@ -496,8 +1567,8 @@ mod tests {
     fn temp_hysteresis_cool_off() -> Result<()> {
         let state = ThermostatState {
             mode: HvacMode::Cool,
-            target_temp: 20.0,
-            current_temp: 20.0,
+            target_temp: Temperature::from_celsius(20.0),
+            current_temp: Temperature::from_celsius(20.0),
             action: HvacAction::Cooling,
             backplate: true,
             ..ThermostatState::default()
@@ -516,8 +1587,8 @@ mod tests {
     fn min_off_time() -> Result<()> {
         let state = ThermostatState {
             mode: HvacMode::Cool,
-            target_temp: 20.0,
-            current_temp: 20.0,
+            target_temp: Temperature::from_celsius(20.0),
+            current_temp: Temperature::from_celsius(20.0),
             action: HvacAction::Idle,
             backplate: true,
             ..ThermostatState::default()
@@ -526,7 +1597,7 @@ mod tests {
         let (_x, mut mgr) = state_manager(state);
 
         // idle -> cooling = lockout
-        mgr.handle_event(&Event::SetCurrentTemp(21.0))?;
+        mgr.handle_event(&Event::SetCurrentTemp(Temperature::from_celsius(21.0)))?;
         assert!(mgr.state.action == HvacAction::Cooling);
         assert!(mgr.state.lockout);
 
@@ -536,23 +1607,23 @@ mod tests {
         assert!(!mgr.state.lockout);
 
         // cooling -> idle = no lockout
-        mgr.handle_event(&Event::SetCurrentTemp(19.0))?;
+        mgr.handle_event(&Event::SetCurrentTemp(Temperature::from_celsius(19.0)))?;
         assert!(mgr.state.action == HvacAction::Idle);
         assert!(!mgr.state.lockout);
 
         // idle -> cooling = lockout
-        mgr.handle_event(&Event::SetCurrentTemp(21.0))?;
+        mgr.handle_event(&Event::SetCurrentTemp(Temperature::from_celsius(21.0)))?;
         assert!(mgr.state.action == HvacAction::Cooling);
         assert!(mgr.state.lockout);
 
         // cooling -> idle = no lockout
-        mgr.handle_event(&Event::SetCurrentTemp(19.0))?;
+        mgr.handle_event(&Event::SetCurrentTemp(Temperature::from_celsius(19.0)))?;
         assert!(mgr.state.action == HvacAction::Idle);
         assert!(!mgr.state.lockout);
 
         // idle -> long delay -> cooling = no lockout
         mgr.last_idle_time = Instant::now() - Duration::from_mins(10);
-        mgr.handle_event(&Event::SetCurrentTemp(21.0))?;
+        mgr.handle_event(&Event::SetCurrentTemp(Temperature::from_celsius(21.0)))?;
         assert!(mgr.state.action == HvacAction::Cooling);
         assert!(!mgr.state.lockout);
 
@@ -563,8 +1634,8 @@ mod tests {
     fn transition_idle() -> Result<()> {
         let state = ThermostatState {
             mode: HvacMode::Cool,
-            target_temp: 20.0,
-            current_temp: 20.0,
+            target_temp: Temperature::from_celsius(20.0),
+            current_temp: Temperature::from_celsius(20.0),
             action: HvacAction::Idle,
             backplate: true,
             ..ThermostatState::default()
@@ -573,11 +1644,11 @@ mod tests {
         let (_x, mut mgr) = state_manager(state);
 
         // Begin cooling
-        mgr.handle_event(&Event::SetCurrentTemp(21.0))?;
+        mgr.handle_event(&Event::SetCurrentTemp(Temperature::from_celsius(21.0)))?;
         assert!(mgr.state.action == HvacAction::Cooling);
 
         // Temp decreased inside hysteresis range, still cooling
-        mgr.handle_event(&Event::SetCurrentTemp(20.0))?;
+        mgr.handle_event(&Event::SetCurrentTemp(Temperature::from_celsius(20.0)))?;
         assert!(mgr.state.action == HvacAction::Cooling);
 
         // Switch mode to heat, current temp within target temp, go idle
@@ -585,11 +1656,11 @@ mod tests {
         assert!(mgr.state.action == HvacAction::Idle);
 
         // Begin heating
-        mgr.handle_event(&Event::SetCurrentTemp(19.0))?;
+        mgr.handle_event(&Event::SetCurrentTemp(Temperature::from_celsius(19.0)))?;
         assert!(mgr.state.action == HvacAction::Heating);
 
         // Temp decreased inside hysteresis range, still heating
-        mgr.handle_event(&Event::SetCurrentTemp(20.0))?;
+        mgr.handle_event(&Event::SetCurrentTemp(Temperature::from_celsius(20.0)))?;
         assert!(mgr.state.action == HvacAction::Heating);
 
         // Switch mode to cool, current temp within target temp, go idle
@@ -598,4 +1669,517 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn offline_fallback_while_away() -> Result<()> {
+        let state = ThermostatState {
+            mode: HvacMode::Heat,
+            target_temp: Temperature::from_celsius(22.0),
+            backplate: true,
+            ..ThermostatState::default()
+        };
+
+        let (_x, mut mgr) = state_manager(state);
+
+        mgr.handle_event(&Event::Occupancy(Occupancy::Away))?;
+        mgr.handle_event(&Event::TimeoutReached(TimerId::HaOffline))?;
+        assert!(mgr.state.offline);
+        assert_eq!(mgr.state.target_temp, mgr.config.away_mode.temp_heat);
+
+        // Exiting away restores the offline temp, not the last HA setpoint
+        mgr.handle_event(&Event::Occupancy(Occupancy::Home))?;
+        assert_eq!(mgr.state.target_temp, mgr.config.offline_mode.temp_heat);
+
+        mgr.handle_event(&Event::HaConnected)?;
+        assert!(!mgr.state.offline);
+
+        Ok(())
+    }
+
+    #[test]
+    fn changeover_lockout() -> Result<()> {
+        let state = ThermostatState {
+            mode: HvacMode::Heat,
+            target_temp: Temperature::from_celsius(20.0),
+            current_temp: Temperature::from_celsius(20.0),
+            backplate: true,
+            ..ThermostatState::default()
+        };
+
+        let (_x, mut mgr) = state_manager(state);
+
+        // heating long enough that min off time has elapsed
+        mgr.handle_event(&Event::SetCurrentTemp(Temperature::from_celsius(19.0)))?;
+        mgr.last_idle_time = Instant::now() - Duration::from_mins(10);
+        assert!(mgr.state.action == HvacAction::Heating);
+
+        // switch to cool mid-cycle, then temp rises = lockout
+        mgr.handle_event(&Event::SetMode(HvacMode::Cool))?;
+        mgr.handle_event(&Event::SetCurrentTemp(Temperature::from_celsius(21.0)))?;
+        assert!(mgr.state.action == HvacAction::Cooling);
+        assert!(mgr.state.lockout);
+
+        Ok(())
+    }
+
+    #[test]
+    fn boost_reverts() -> Result<()> {
+        let state = ThermostatState {
+            mode: HvacMode::Heat,
+            target_temp: Temperature::from_celsius(20.0),
+            backplate: true,
+            ..ThermostatState::default()
+        };
+
+        let (_x, mut mgr) = state_manager(state);
+
+        mgr.handle_event(&Event::SetBoost(true))?;
+        assert!(mgr.state.boost);
+        assert_eq!(mgr.state.target_temp, Temperature::from_celsius(22.0));
+
+        // boost timer elapsed = restore target
+        mgr.handle_event(&Event::TimeoutReached(TimerId::Boost))?;
+        assert!(!mgr.state.boost);
+        assert_eq!(mgr.state.target_temp, Temperature::from_celsius(20.0));
+
+        // cancelled early, e.g. the HA switch turned off
+        mgr.handle_event(&Event::SetBoost(true))?;
+        mgr.handle_event(&Event::SetBoost(false))?;
+        assert!(!mgr.state.boost);
+        assert_eq!(mgr.state.target_temp, Temperature::from_celsius(20.0));
+
+        // manual target change while boosting keeps the new target
+        mgr.handle_event(&Event::SetBoost(true))?;
+        mgr.handle_event(&Event::SetTargetTemp(Temperature::from_celsius(21.0)))?;
+        mgr.handle_event(&Event::TimeoutReached(TimerId::Boost))?;
+        assert!(!mgr.state.boost);
+        assert_eq!(mgr.state.target_temp, Temperature::from_celsius(21.0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn target_change_settles() -> Result<()> {
+        let state = ThermostatState {
+            mode: HvacMode::Heat,
+            target_temp: Temperature::from_celsius(20.0),
+            current_temp: Temperature::from_celsius(20.0),
+            backplate: true,
+            ..ThermostatState::default()
+        };
+
+        let (_x, mut mgr) = state_manager(state);
+
+        // dialing up past the deadband doesn't start heating yet
+        mgr.handle_event(&Event::SetTargetTemp(Temperature::from_celsius(21.0)))?;
+        assert_eq!(mgr.state.target_temp, Temperature::from_celsius(21.0));
+        assert_eq!(mgr.state.action, HvacAction::Idle);
+
+        // dialed back down before settling, relay never switched
+        mgr.handle_event(&Event::SetTargetTemp(Temperature::from_celsius(20.0)))?;
+        mgr.handle_event(&Event::TimeoutReached(TimerId::TargetSettle))?;
+        assert_eq!(mgr.state.action, HvacAction::Idle);
+
+        mgr.handle_event(&Event::SetTargetTemp(Temperature::from_celsius(21.0)))?;
+        mgr.handle_event(&Event::TimeoutReached(TimerId::TargetSettle))?;
+        assert_eq!(mgr.state.action, HvacAction::Heating);
+
+        Ok(())
+    }
+
+    #[test]
+    fn command_target_applied_right_away() -> Result<()> {
+        let state = ThermostatState {
+            mode: HvacMode::Heat,
+            target_temp: Temperature::from_celsius(20.0),
+            current_temp: Temperature::from_celsius(20.0),
+            backplate: true,
+            ..ThermostatState::default()
+        };
+
+        let (_x, mut mgr) = state_manager(state);
+
+        // only dial input waits to settle
+        let cmd = ClimateCommand {
+            target_temp: Some(Temperature::from_celsius(21.0)),
+            ..ClimateCommand::default()
+        };
+        mgr.handle_event(&Event::ClimateCommand(cmd))?;
+        assert_eq!(mgr.state.action, HvacAction::Heating);
+
+        Ok(())
+    }
+
+    #[test]
+    fn fan_timer_returns_to_auto() -> Result<()> {
+        let (_x, mut mgr) = state_manager(ThermostatState::default());
+
+        mgr.handle_event(&Event::SetFanMode(HvacFanMode::Timer))?;
+        assert!(mgr.state.fan_on());
+
+        mgr.handle_event(&Event::TimeoutReached(TimerId::FanRun))?;
+        assert_eq!(mgr.state.fan_mode, HvacFanMode::Auto);
+        assert!(!mgr.state.fan_on());
+
+        Ok(())
+    }
+
+    #[test]
+    fn heat_staging() -> Result<()> {
+        let state = ThermostatState {
+            mode: HvacMode::Heat,
+            target_temp: Temperature::from_celsius(20.0),
+            current_temp: Temperature::from_celsius(20.0),
+            backplate: true,
+            ..ThermostatState::default()
+        };
+
+        let (mut events, mut mgr) = state_manager(state);
+        mgr.config.backplate.wiring = WireConfig::HeatAndCool {
+            heat_wire: WireId::W1,
+            cool_wire: Some(WireId::Y1),
+            fan_wire: Some(WireId::G),
+            heat2_wire: Some(WireId::W2),
+            cool2_wire: None,
+            aux_wire: None
+        };
+
+        // cycle starts on first stage
+        mgr.handle_event(&Event::SetCurrentTemp(Temperature::from_celsius(19.4)))?;
+        assert_eq!(mgr.state.action, HvacAction::Heating);
+        assert_eq!(mgr.state.stage, 1);
+
+        // stage up after first stage runs too long
+        mgr.handle_event(&Event::TimeoutReached(TimerId::Stage2))?;
+        assert_eq!(mgr.state.stage, 2);
+
+        // stage down when approaching target, and wait again to stage up
+        while events.poll_event()?.is_some() { }
+        mgr.handle_event(&Event::SetCurrentTemp(Temperature::from_celsius(19.6)))?;
+        assert_eq!(mgr.state.stage, 1);
+
+        let mut rearmed = false;
+        while let Some(event) = events.poll_event()? {
+            rearmed |= matches!(event, Event::TimeoutReset(TimerId::Stage2, _));
+        }
+        assert!(rearmed);
+
+        mgr.handle_event(&Event::SetCurrentTemp(Temperature::from_celsius(19.4)))?;
+        mgr.handle_event(&Event::TimeoutReached(TimerId::Stage2))?;
+        assert_eq!(mgr.state.stage, 2);
+
+        mgr.handle_event(&Event::SetCurrentTemp(Temperature::from_celsius(19.6)))?;
+        assert_eq!(mgr.state.stage, 1);
+
+        // stage up right away when far from target
+        mgr.handle_event(&Event::SetCurrentTemp(Temperature::from_celsius(18.4)))?;
+        assert_eq!(mgr.state.stage, 2);
+
+        mgr.handle_event(&Event::SetCurrentTemp(Temperature::from_celsius(20.2)))?;
+        assert_eq!(mgr.state.action, HvacAction::Idle);
+        assert_eq!(mgr.state.stage, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn aux_heat_staging() -> Result<()> {
+        let state = ThermostatState {
+            mode: HvacMode::Heat,
+            target_temp: Temperature::from_celsius(20.0),
+            current_temp: Temperature::from_celsius(20.0),
+            backplate: true,
+            ..ThermostatState::default()
+        };
+
+        let (_x, mut mgr) = state_manager(state);
+        mgr.config.backplate.wiring = WireConfig::HeatAndCool {
+            heat_wire: WireId::Y1,
+            cool_wire: Some(WireId::Y1),
+            fan_wire: Some(WireId::G),
+            heat2_wire: None,
+            cool2_wire: None,
+            aux_wire: Some(WireId::W1)
+        };
+
+        // compressor first, then aux heat alone on the second stage
+        mgr.handle_event(&Event::SetCurrentTemp(Temperature::from_celsius(19.4)))?;
+        assert_eq!((mgr.state.stage, mgr.state.aux), (1, AuxHeat::Off));
+        mgr.handle_event(&Event::TimeoutReached(TimerId::Stage2))?;
+        assert_eq!((mgr.state.stage, mgr.state.aux), (2, AuxHeat::Only));
+
+        mgr.config.staging.aux_with_compressor = true;
+        mgr.handle_event(&Event::SetCurrentTemp(Temperature::from_celsius(18.4)))?;
+        assert_eq!((mgr.state.stage, mgr.state.aux), (2, AuxHeat::WithCompressor));
+
+        // emergency heat never runs the compressor or stages up
+        mgr.handle_event(&Event::SetMode(HvacMode::EmergencyHeat))?;
+        assert_eq!(mgr.state.action, HvacAction::Heating);
+        assert_eq!((mgr.state.stage, mgr.state.aux), (1, AuxHeat::Only));
+        mgr.handle_event(&Event::TimeoutReached(TimerId::Stage2))?;
+        assert_eq!((mgr.state.stage, mgr.state.aux), (1, AuxHeat::Only));
+
+        // any other preset goes back to heat mode
+        let cmd = ClimateCommand { emergency_heat: Some(false), ..ClimateCommand::default() };
+        mgr.handle_event(&Event::ClimateCommand(cmd))?;
+        assert_eq!(mgr.state.mode, HvacMode::Heat);
+
+        mgr.handle_event(&Event::SetCurrentTemp(Temperature::from_celsius(20.2)))?;
+        assert_eq!((mgr.state.stage, mgr.state.aux), (0, AuxHeat::Off));
+
+        Ok(())
+    }
+
+    #[test]
+    fn holding_in_deadband() -> Result<()> {
+        let state = ThermostatState {
+            mode: HvacMode::Heat,
+            target_temp: Temperature::from_celsius(20.0),
+            current_temp: Temperature::from_celsius(21.0),
+            backplate: true,
+            ..ThermostatState::default()
+        };
+
+        let (_x, mut mgr) = state_manager(state);
+
+        // inside deadband, short of target
+        mgr.handle_event(&Event::SetCurrentTemp(Temperature::from_celsius(19.8)))?;
+        assert_eq!(mgr.state.action, HvacAction::Idle);
+        assert_eq!(mgr.state.holding, Some(HoldingReason::Deadband));
+
+        mgr.handle_event(&Event::SetCurrentTemp(Temperature::from_celsius(19.5)))?;
+        assert_eq!(mgr.state.action, HvacAction::Heating);
+        assert_eq!(mgr.state.holding, None);
+
+        mgr.handle_event(&Event::SetCurrentTemp(Temperature::from_celsius(20.2)))?;
+        assert_eq!(mgr.state.holding, Some(HoldingReason::TargetReached));
+
+        Ok(())
+    }
+
+    #[test]
+    fn climate_command_applies_together() -> Result<()> {
+        let state = ThermostatState {
+            mode: HvacMode::Off,
+            target_temp: Temperature::from_celsius(20.0),
+            current_temp: Temperature::from_celsius(19.0),
+            backplate: true,
+            ..ThermostatState::default()
+        };
+
+        let (mut events, mut mgr) = state_manager(state);
+
+        let cmd = ClimateCommand {
+            mode: Some(HvacMode::Heat),
+            target_temp: Some(Temperature::from_celsius(40.0)),
+            ..ClimateCommand::default()
+        };
+        mgr.handle_event(&Event::ClimateCommand(cmd))?;
+
+        // out of range target is clamped, and heating starts right away
+        assert_eq!(mgr.state.target_temp, ThermostatState::MAX_TEMP);
+        assert_eq!(mgr.state.action, HvacAction::Heating);
+
+        let mut states = 0;
+        while let Some(event) = events.poll_event()? {
+            if let Event::State(_) = event {
+                states += 1;
+            }
+        }
+        assert_eq!(states, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn wait_for_zone() -> Result<()> {
+        let state = ThermostatState {
+            mode: HvacMode::Heat,
+            target_temp: Temperature::from_celsius(20.0),
+            current_temp: Temperature::from_celsius(21.0),
+            backplate: true,
+            ..ThermostatState::default()
+        };
+
+        let (_x, mut mgr) = state_manager(state);
+
+        let zone = ZoneStatus { calling: true, ..ZoneStatus::default() };
+        mgr.handle_event(&Event::Zone(zone))?;
+
+        // another zone is running, wait for it
+        mgr.handle_event(&Event::SetCurrentTemp(Temperature::from_celsius(19.0)))?;
+        assert_eq!(mgr.state.action, HvacAction::Idle);
+        assert_eq!(mgr.state.holding, Some(HoldingReason::Zone));
+
+        mgr.handle_event(&Event::Zone(ZoneStatus::default()))?;
+        assert_eq!(mgr.state.action, HvacAction::Heating);
+
+        // keep running when another zone starts, unless it outranks this one
+        mgr.handle_event(&Event::Zone(zone))?;
+        assert_eq!(mgr.state.action, HvacAction::Heating);
+
+        mgr.handle_event(&Event::Zone(ZoneStatus { outranked: true, ..zone }))?;
+        assert_eq!(mgr.state.action, HvacAction::Idle);
+
+        Ok(())
+    }
+
+    #[test]
+    fn schedule_hold() -> Result<()> {
+        let (_x, mut mgr) = state_manager(ThermostatState::default());
+
+        // no schedule running, nothing to hold against
+        mgr.handle_event(&Event::SetTargetTemp(Temperature::from_celsius(21.0)))?;
+        assert!(!mgr.state.hold);
+
+        let next = NextSetPoint {
+            time: chrono::NaiveDateTime::default(),
+            temp: Temperature::from_celsius(18.0)
+        };
+        mgr.handle_event(&Event::NextSetPoint(Some(next)))?;
+
+        mgr.handle_event(&Event::SetTargetTemp(Temperature::from_celsius(22.0)))?;
+        assert!(mgr.state.hold);
+
+        // next set point releases the hold
+        mgr.handle_event(&Event::ScheduledTargetTemp(Temperature::from_celsius(18.0)))?;
+        assert!(!mgr.state.hold);
+        assert_eq!(mgr.state.target_temp, Temperature::from_celsius(18.0));
+
+        // permanent hold ignores the schedule until released
+        mgr.config.hold.mode = HoldMode::Permanent;
+        mgr.handle_event(&Event::SetTargetTemp(Temperature::from_celsius(22.0)))?;
+        mgr.handle_event(&Event::ScheduledTargetTemp(Temperature::from_celsius(16.0)))?;
+        assert!(mgr.state.hold);
+        assert_eq!(mgr.state.target_temp, Temperature::from_celsius(22.0));
+
+        mgr.handle_event(&Event::ClimateCommand(ClimateCommand { hold: Some(false), ..Default::default() }))?;
+        assert!(!mgr.state.hold);
+        assert_eq!(mgr.state.target_temp, Temperature::from_celsius(16.0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn target_range_limits() -> Result<()> {
+        let state = ThermostatState {
+            mode: HvacMode::HeatCool,
+            target_temp_low: Temperature::from_celsius(20.0),
+            target_temp_high: Temperature::from_celsius(24.0),
+            ..ThermostatState::default()
+        };
+
+        let (_x, mut mgr) = state_manager(state);
+        let next = NextSetPoint {
+            time: chrono::NaiveDateTime::default(),
+            temp: Temperature::from_celsius(18.0)
+        };
+        mgr.handle_event(&Event::NextSetPoint(Some(next)))?;
+
+        // reversed, or closer than the minimum range
+        mgr.handle_event(&Event::SetTargetRange(Temperature::from_celsius(24.0), Temperature::from_celsius(20.0)))?;
+        mgr.handle_event(&Event::SetTargetRange(Temperature::from_celsius(22.0), Temperature::from_celsius(22.5)))?;
+        assert_eq!(mgr.state.target_temp_low, Temperature::from_celsius(20.0));
+        assert_eq!(mgr.state.target_temp_high, Temperature::from_celsius(24.0));
+        assert!(!mgr.state.hold);
+
+        // out of range is clamped, and held against the schedule
+        mgr.handle_event(&Event::SetTargetRange(Temperature::from_celsius(20.0), Temperature::from_celsius(99.0)))?;
+        assert_eq!(mgr.state.target_temp_high, ThermostatState::MAX_TEMP);
+        assert!(mgr.state.hold);
+
+        Ok(())
+    }
+
+    #[test]
+    fn safety_limits() -> Result<()> {
+        let state = ThermostatState {
+            mode: HvacMode::Off,
+            current_temp: Temperature::from_celsius(10.0),
+            backplate: true,
+            ..ThermostatState::default()
+        };
+
+        let (_x, mut mgr) = state_manager(state);
+        mgr.config.safety.max_temp = Some(Temperature::from_celsius(35.0));
+        mgr.last_idle_time = Instant::now() - Duration::from_mins(10);
+
+        // freeze protection in off mode, until past the hysteresis
+        mgr.handle_event(&Event::SetCurrentTemp(Temperature::from_celsius(7.0)))?;
+        assert_eq!(mgr.state.action, HvacAction::Heating);
+        assert!(mgr.state.safety);
+
+        mgr.handle_event(&Event::SetCurrentTemp(Temperature::from_celsius(7.5)))?;
+        assert_eq!(mgr.state.action, HvacAction::Heating);
+
+        mgr.handle_event(&Event::SetCurrentTemp(Temperature::from_celsius(8.0)))?;
+        assert_eq!(mgr.state.action, HvacAction::Idle);
+        assert!(!mgr.state.safety);
+
+        // no cooling without a cool wire
+        let capabilities = BackplateCapabilities::default();
+        mgr.handle_event(&Event::BackplateCapabilities(capabilities))?;
+        mgr.handle_event(&Event::SetCurrentTemp(Temperature::from_celsius(36.0)))?;
+        assert_eq!(mgr.state.action, HvacAction::Idle);
+        assert!(!mgr.state.safety);
+
+        Ok(())
+    }
+
+    #[test]
+    fn time_proportional_heat() -> Result<()> {
+        let state = ThermostatState {
+            mode: HvacMode::Heat,
+            target_temp: Temperature::from_celsius(20.0),
+            current_temp: Temperature::from_celsius(20.0),
+            backplate: true,
+            ..ThermostatState::default()
+        };
+
+        let (_x, mut mgr) = state_manager(state);
+        mgr.config.control.heat = ControlStrategy::TimeProportional;
+
+        // half the proportional band below target runs for half the cycle
+        mgr.handle_event(&Event::SetCurrentTemp(Temperature::from_celsius(19.0)))?;
+        assert_eq!(mgr.state.action, HvacAction::Heating);
+        assert_eq!(mgr.state.demand, 50);
+
+        // at target, the rest of the cycle is off
+        mgr.handle_event(&Event::SetCurrentTemp(Temperature::from_celsius(20.0)))?;
+        assert_eq!(mgr.state.action, HvacAction::Idle);
+        assert_eq!(mgr.state.demand, 0);
+
+        // back to hysteresis stops cycling
+        mgr.config.control.heat = ControlStrategy::Hysteresis;
+        mgr.handle_event(&Event::SetCurrentTemp(Temperature::from_celsius(19.0)))?;
+        assert_eq!(mgr.state.action, HvacAction::Heating);
+        assert_eq!(mgr.state.demand, 100);
+        assert!(!mgr.duty_cycle.is_running());
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_tuning_ignored() -> Result<()> {
+        let state = ThermostatState {
+            mode: HvacMode::Heat,
+            target_temp: Temperature::from_celsius(20.0),
+            current_temp: Temperature::from_celsius(20.0),
+            backplate: true,
+            ..ThermostatState::default()
+        };
+
+        let (_x, mut mgr) = state_manager(state);
+        mgr.config.control.heat = ControlStrategy::TimeProportional;
+
+        let tuning = Tuning { differential: 0.5, proportional_band: 0.0, integral_secs: 0 };
+        mgr.handle_event(&Event::Tuning(tuning))?;
+        assert_eq!(mgr.tuning, None);
+
+        // still uses the configured band
+        mgr.handle_event(&Event::SetCurrentTemp(Temperature::from_celsius(19.0)))?;
+        assert_eq!(mgr.state.demand, 50);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,139 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::time::{Duration, Instant};
+
+use crate::config::ControlConfig;
+
+/// Time proportional control, runs heating or cooling for a share of each
+/// cycle from the distance to the target temp, see [`ControlConfig`]
+#[derive(Default)]
+pub struct DutyCycle {
+    cycle_start: Option<Instant>,
+    /// Integral part of the demand, from 0.0 to 1.0
+    integral: f32,
+    /// Share of the cycle to run for, from 0.0 to 1.0
+    demand: f32
+}
+
+impl DutyCycle {
+    /// Update the demand from `error`, the distance from the target temp in
+    /// the direction of heating or cooling. Returns whether to run now, and
+    /// how long until that changes.
+    pub fn update(&mut self, config: &ControlConfig, error: f32, now: Instant) -> (bool, Duration) {
+        let period = config.cycle_period;
+
+        let start = match self.cycle_start {
+            Some(start) if now.duration_since(start) < period => start,
+            Some(_) => {
+                // error is sampled once per cycle, so the integral grows at
+                // the same rate regardless of how often temps arrive
+                if !config.integral_time.is_zero() {
+                    let gain = period.div_duration_f32(config.integral_time) / config.proportional_band;
+                    self.integral = (self.integral + error * gain).clamp(0.0, 1.0);
+                }
+                now
+            }
+            None => now
+        };
+        self.cycle_start = Some(start);
+
+        let demand = (error / config.proportional_band + self.integral).clamp(0.0, 1.0);
+        self.demand = if demand * 100.0 < config.min_demand as f32 { 0.0 } else { demand };
+
+        let on_time = period.mul_f32(self.demand);
+        let elapsed = now.duration_since(start);
+        if elapsed < on_time {
+            (true, on_time - elapsed)
+        } else {
+            (false, period - elapsed)
+        }
+    }
+
+    /// Demand as a percent
+    pub fn demand(&self) -> u8 {
+        (self.demand * 100.0).round() as u8
+    }
+
+    /// Start over, for when time proportional control stops being used
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.cycle_start.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> ControlConfig {
+        ControlConfig {
+            cycle_period: Duration::from_mins(10),
+            proportional_band: 2.0,
+            min_demand: 10,
+            ..ControlConfig::default()
+        }
+    }
+
+    #[test]
+    fn proportional_on_time() {
+        let config = config();
+        let start = Instant::now();
+        let mut duty = DutyCycle::default();
+
+        assert_eq!(duty.update(&config, 1.0, start), (true, Duration::from_mins(5)));
+        assert_eq!(duty.demand(), 50);
+
+        let (on, next) = duty.update(&config, 1.0, start + Duration::from_mins(6));
+        assert!(!on);
+        assert_eq!(next, Duration::from_mins(4));
+
+        // past the band is full demand
+        assert!(duty.update(&config, 3.0, start + Duration::from_mins(10)).0);
+        assert_eq!(duty.demand(), 100);
+    }
+
+    #[test]
+    fn min_demand_stays_off() {
+        let config = config();
+        let mut duty = DutyCycle::default();
+
+        assert!(!duty.update(&config, 0.1, Instant::now()).0);
+        assert_eq!(duty.demand(), 0);
+    }
+
+    #[test]
+    fn integral_grows_each_cycle() {
+        let config = ControlConfig {
+            integral_time: Duration::from_mins(20),
+            ..config()
+        };
+        let start = Instant::now();
+        let mut duty = DutyCycle::default();
+
+        duty.update(&config, 1.0, start);
+        assert_eq!(duty.demand(), 50);
+
+        // half the band for half the integral time adds a quarter
+        duty.update(&config, 1.0, start + Duration::from_mins(10));
+        assert_eq!(duty.demand(), 75);
+    }
+}
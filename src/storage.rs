@@ -16,22 +16,34 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{fs, path::{Path, PathBuf}, sync::mpsc::{Sender, channel}, thread};
+use std::{fs, path::{Path, PathBuf}, sync::mpsc::{Sender, channel}, thread::{self, JoinHandle}, time::{Duration, Instant}};
 
 use anyhow::{Result, anyhow};
+use chrono::{DateTime, Local, NaiveDate};
 use log::{info, warn};
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 
 use crate::{
+    action_log::{ActionCause, ActionEntry, ActionLog},
+    autotune::Tuning,
+    backplate::BackplateCapabilities,
     config::Config,
     env,
-    events::{Event, EventHandler},
-    state::{HvacMode, ThermostatState}
+    events::{Event, EventHandler, Topic},
+    learning::{Adjustment, AdjustmentLog},
+    recovery::HeatingRate,
+    runtime_stats::{DailyRuntime, RuntimeStats},
+    schedule::EditedSchedule,
+    state::{HvacAction, HvacFanMode, HvacMode, ThermostatState},
+    temperature::Temperature
 };
 
 pub struct Storage {
     backend: StorageBackend,
-    write_thread: Sender<Storable>
+    /// Config deadband, a deadband set to it is no longer saved
+    config_deadband: f32,
+    write_thread: Sender<Storable>,
+    write_handle: JoinHandle<()>
 }
 
 impl Storage {
@@ -40,9 +52,12 @@ impl Storage {
             Err(anyhow!("Directory {:?} does not exist", config.storage_dir))
         } else {
             let backend = StorageBackend::new(config.storage_dir.clone());
-            let write_thread = start_write_thread(backend.clone());
+            let (write_thread, write_handle) = start_write_thread(backend.clone());
             Ok(Self {
-                backend, write_thread
+                backend,
+                config_deadband: config.temp_deadband,
+                write_thread,
+                write_handle
             })
         }
     }
@@ -59,40 +74,228 @@ impl Storage {
 
         Ok(state)
     }
+
+    /// Backplate capabilities persisted from a previous connection
+    pub fn read_capabilities(&self) -> Result<Option<BackplateCapabilities>> {
+        self.backend.read(CAPABILITIES_FILE_NAME)
+    }
+
+    /// Schedules edited from Home Assistant, `None` when using config schedules
+    pub fn read_schedule(&self) -> Result<Option<EditedSchedule>> {
+        self.backend.read(SCHEDULE_FILE_NAME)
+    }
+
+    /// Recent hvac action switches persisted before the last restart
+    pub fn read_action_log(&self) -> Result<ActionLog> {
+        let log = self.backend.read::<_, StoredActionLog>(ACTION_LOG_FILE_NAME)?
+            .map(|log| ActionLog::from(&log))
+            .unwrap_or_default();
+
+        Ok(log)
+    }
+
+    /// Result of the last autotune, `None` when it hasn't been run
+    pub fn read_tuning(&self) -> Result<Option<Tuning>> {
+        self.backend.read(TUNING_FILE_NAME)
+    }
+
+    /// Heating rate learned for smart recovery, `None` until a heating run
+    /// has been measured
+    pub fn read_heating_rate(&self) -> Result<Option<HeatingRate>> {
+        self.backend.read(HEATING_RATE_FILE_NAME)
+    }
+
+    /// Deadband changed from the settings screen or Home Assistant, `None`
+    /// when using the config deadband or the config deadband changed since
+    pub fn read_deadband(&self, config_deadband: f32) -> Result<Option<f32>> {
+        let deadband = self.backend.read::<_, StoredDeadband>(DEADBAND_FILE_NAME)?
+            .filter(|stored| stored.config_deadband == config_deadband)
+            .map(|stored| stored.temp_deadband);
+
+        Ok(deadband)
+    }
+
+    /// Theme chosen from the settings screen or Home Assistant, `None` when
+    /// using the config theme or the config theme changed since
+    pub fn read_theme(&self, config_theme: &str) -> Result<Option<String>> {
+        let theme = self.backend.read::<_, StoredTheme>(THEME_FILE_NAME)?
+            .filter(|stored| stored.config_theme == config_theme)
+            .map(|stored| stored.theme);
+
+        Ok(theme)
+    }
+
+    /// Setpoint adjustments persisted before the last restart, for schedule
+    /// suggestions
+    pub fn read_adjustments(&self) -> Result<AdjustmentLog> {
+        let log = self.backend.read::<_, StoredAdjustments>(ADJUSTMENTS_FILE_NAME)?
+            .map(|log| AdjustmentLog::from(&log))
+            .unwrap_or_default();
+
+        Ok(log)
+    }
+
+    /// Heating and cooling runtime persisted before the last restart
+    pub fn read_runtime(&self) -> Result<RuntimeStats> {
+        let runtime = self.backend.read::<_, StoredRuntime>(RUNTIME_FILE_NAME)?
+            .map(|runtime| RuntimeStats::from(&runtime))
+            .unwrap_or_default();
+
+        Ok(runtime)
+    }
+
+    /// Wait up to `timeout` for queued writes to finish, before exiting
+    pub fn shutdown(self, timeout: Duration) {
+        drop(self.write_thread);
+
+        let deadline = Instant::now() + timeout;
+        while !self.write_handle.is_finished() {
+            if Instant::now() >= deadline {
+                warn!("Storage writes didn't finish within {timeout:?}");
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        if self.write_handle.join().is_err() {
+            warn!("Storage write thread panicked");
+        }
+    }
 }
 
-fn start_write_thread(backend: StorageBackend) -> Sender<Storable> {
+const CAPABILITIES_FILE_NAME: &str = "retherm.backplate.toml";
+const SCHEDULE_FILE_NAME: &str = "retherm.schedule.toml";
+const RUNTIME_FILE_NAME: &str = "retherm.runtime.toml";
+const TUNING_FILE_NAME: &str = "retherm.tuning.toml";
+const HEATING_RATE_FILE_NAME: &str = "retherm.recovery.toml";
+const ACTION_LOG_FILE_NAME: &str = "retherm.actions.toml";
+const DEADBAND_FILE_NAME: &str = "retherm.deadband.toml";
+const ADJUSTMENTS_FILE_NAME: &str = "retherm.adjustments.toml";
+const THEME_FILE_NAME: &str = "retherm.theme.toml";
+
+fn start_write_thread(backend: StorageBackend) -> (Sender<Storable>, JoinHandle<()>) {
     let (tx, rx) = channel::<Storable>();
 
-    thread::spawn(move || {
+    let handle = thread::spawn(move || {
         while let Ok(data) = rx.recv() {
             match data {
                 Storable::State(state) => {
                     let state = StoredState::from(&state);
                     backend.write(env::state_file_name(), state).unwrap();
                 }
+                Storable::Capabilities(capabilities) => {
+                    backend.write(CAPABILITIES_FILE_NAME, capabilities).unwrap();
+                }
+                Storable::Schedule(Some(schedule)) => {
+                    backend.write(SCHEDULE_FILE_NAME, schedule).unwrap();
+                }
+                Storable::Schedule(None) => {
+                    backend.remove(SCHEDULE_FILE_NAME).unwrap();
+                }
+                Storable::Runtime(runtime) => {
+                    let runtime = StoredRuntime::from(&runtime);
+                    backend.write(RUNTIME_FILE_NAME, runtime).unwrap();
+                }
+                Storable::Tuning(tuning) => {
+                    backend.write(TUNING_FILE_NAME, tuning).unwrap();
+                }
+                Storable::HeatingRate(rate) => {
+                    backend.write(HEATING_RATE_FILE_NAME, rate).unwrap();
+                }
+                Storable::ActionLog(log) => {
+                    let log = StoredActionLog::from(&log);
+                    backend.write(ACTION_LOG_FILE_NAME, log).unwrap();
+                }
+                Storable::Deadband(Some(deadband)) => {
+                    backend.write(DEADBAND_FILE_NAME, deadband).unwrap();
+                }
+                Storable::Deadband(None) => {
+                    backend.remove(DEADBAND_FILE_NAME).unwrap();
+                }
+                Storable::Adjustments(log) => {
+                    let log = StoredAdjustments::from(&log);
+                    backend.write(ADJUSTMENTS_FILE_NAME, log).unwrap();
+                }
+                Storable::Theme(theme) => {
+                    backend.write(THEME_FILE_NAME, theme).unwrap();
+                }
             }
         }
     });
 
-    tx
+    (tx, handle)
+}
+
+/// Parse contents of persisted state file
+pub fn parse_state(toml_src: &str) -> Result<ThermostatState> {
+    let state: StoredState = toml::from_str(toml_src)?;
+    Ok(ThermostatState::from(&state))
 }
 
 impl EventHandler for Storage {
     fn handle_event(&mut self, event: &Event) -> Result<()> {
-        if let Event::State(state) = event {
-            self.write_thread.send(Storable::State(state.clone()))?;
+        match event {
+            Event::State(state) => {
+                self.write_thread.send(Storable::State(state.clone()))?;
+            }
+            Event::BackplateCapabilities(capabilities) => {
+                self.write_thread.send(Storable::Capabilities(capabilities.clone()))?;
+            }
+            Event::ScheduleChanged(schedule) => {
+                self.write_thread.send(Storable::Schedule(schedule.clone()))?;
+            }
+            Event::RuntimeStats(runtime) => {
+                self.write_thread.send(Storable::Runtime(runtime.clone()))?;
+            }
+            Event::Tuning(tuning) => {
+                self.write_thread.send(Storable::Tuning(*tuning))?;
+            }
+            Event::HeatingRate(rate) => {
+                self.write_thread.send(Storable::HeatingRate(*rate))?;
+            }
+            Event::ActionLog(log) => {
+                self.write_thread.send(Storable::ActionLog(log.clone()))?;
+            }
+            Event::Deadband(deadband) => {
+                // back to the config deadband
+                let stored = (*deadband != self.config_deadband).then_some(StoredDeadband {
+                    temp_deadband: *deadband,
+                    config_deadband: self.config_deadband
+                });
+                self.write_thread.send(Storable::Deadband(stored))?;
+            }
+            Event::Adjustments(log) => {
+                self.write_thread.send(Storable::Adjustments(log.clone()))?;
+            }
+            Event::ConfigReloaded(reloaded) => {
+                self.config_deadband = reloaded.config.temp_deadband;
+                self.write_thread.send(Storable::Theme(StoredTheme {
+                    theme: reloaded.theme_name.clone(),
+                    config_theme: reloaded.config.theme.clone()
+                }))?;
+            }
+            _ => { }
         }
 
         Ok(())
     }
+
+    fn topics(&self) -> &'static [Topic] {
+        &[Topic::State, Topic::Backplate]
+    }
 }
 
 #[derive(Deserialize, Serialize, PartialEq)]
 struct StoredState {
-    target_temp: f32,
-    current_temp: f32,
+    target_temp: Temperature,
+    #[serde(default = "default_target_temp_low")]
+    target_temp_low: Temperature,
+    #[serde(default = "default_target_temp_high")]
+    target_temp_high: Temperature,
+    current_temp: Temperature,
     mode: HvacMode,
+    #[serde(default)]
+    fan_mode: HvacFanMode,
 }
 
 impl From<&ThermostatState> for StoredState {
@@ -104,27 +307,212 @@ impl From<&ThermostatState> for StoredState {
             m => m
         };
 
+        // Same goes for the fan timer
+        let fan_mode = match value.fan_mode {
+            HvacFanMode::Timer => HvacFanMode::Auto,
+            m => m
+        };
+
         Self {
             target_temp: value.target_temp,
+            target_temp_low: value.target_temp_low,
+            target_temp_high: value.target_temp_high,
             current_temp: value.current_temp,
             mode,
+            fan_mode,
         }
     }
 }
 
+fn default_target_temp_low() -> Temperature {
+    ThermostatState::default().target_temp_low
+}
+
+fn default_target_temp_high() -> Temperature {
+    ThermostatState::default().target_temp_high
+}
+
 impl From<&StoredState> for ThermostatState {
     fn from(value: &StoredState) -> Self {
         Self {
             target_temp: value.target_temp,
+            target_temp_low: value.target_temp_low,
+            target_temp_high: value.target_temp_high,
             current_temp: value.current_temp,
             mode: value.mode,
+            fan_mode: value.fan_mode,
             ..Default::default()
         }
     }
 }
 
+#[derive(Deserialize, Serialize, PartialEq)]
+struct StoredRuntime {
+    days: Vec<StoredDailyRuntime>
+}
+
+#[derive(Deserialize, Serialize, PartialEq)]
+struct StoredDailyRuntime {
+    day: String,
+    heating_secs: u64,
+    cooling_secs: u64,
+}
+
+const RUNTIME_DAY_FORMAT: &str = "%Y-%m-%d";
+
+impl From<&RuntimeStats> for StoredRuntime {
+    fn from(value: &RuntimeStats) -> Self {
+        let days = value.days()
+            .map(|runtime| StoredDailyRuntime {
+                day: runtime.day.format(RUNTIME_DAY_FORMAT).to_string(),
+                heating_secs: runtime.heating.as_secs(),
+                cooling_secs: runtime.cooling.as_secs(),
+            })
+            .collect();
+
+        Self { days }
+    }
+}
+
+impl From<&StoredRuntime> for RuntimeStats {
+    fn from(value: &StoredRuntime) -> Self {
+        // skip days that don't parse rather than losing the whole file
+        let days = value.days.iter()
+            .filter_map(|runtime| {
+                let day = NaiveDate::parse_from_str(&runtime.day, RUNTIME_DAY_FORMAT).ok()?;
+                Some(DailyRuntime {
+                    day,
+                    heating: Duration::from_secs(runtime.heating_secs),
+                    cooling: Duration::from_secs(runtime.cooling_secs),
+                })
+            });
+
+        RuntimeStats::new(days)
+    }
+}
+
+/// Deadband in use, stored under its config name with the config deadband
+/// it replaced, so a deadband changed in the config takes over again
+#[derive(Deserialize, Serialize, PartialEq)]
+struct StoredDeadband {
+    temp_deadband: f32,
+    /// Missing before this was stored, the stored deadband is then dropped
+    #[serde(default)]
+    config_deadband: f32
+}
+
+/// Theme in use, with the config theme it replaced so a theme changed in
+/// the config takes over again
+#[derive(Deserialize, Serialize, PartialEq)]
+struct StoredTheme {
+    theme: String,
+    config_theme: String
+}
+
+#[derive(Deserialize, Serialize, PartialEq)]
+struct StoredActionLog {
+    entries: Vec<StoredActionEntry>
+}
+
+#[derive(Deserialize, Serialize, PartialEq)]
+struct StoredActionEntry {
+    time: String,
+    from: HvacAction,
+    to: HvacAction,
+    cause: ActionCause,
+    #[serde(default)]
+    setpoint: Option<Temperature>
+}
+
+impl From<&ActionLog> for StoredActionLog {
+    fn from(value: &ActionLog) -> Self {
+        let entries = value.entries()
+            .map(|entry| StoredActionEntry {
+                time: entry.time.to_rfc3339(),
+                from: entry.from,
+                to: entry.to,
+                cause: entry.cause,
+                setpoint: entry.setpoint
+            })
+            .collect();
+
+        Self { entries }
+    }
+}
+
+impl From<&StoredActionLog> for ActionLog {
+    fn from(value: &StoredActionLog) -> Self {
+        // skip entries that don't parse rather than losing the whole file
+        let entries = value.entries.iter()
+            .filter_map(|entry| {
+                let time = DateTime::parse_from_rfc3339(&entry.time).ok()?;
+                Some(ActionEntry {
+                    time: time.with_timezone(&Local),
+                    from: entry.from,
+                    to: entry.to,
+                    cause: entry.cause,
+                    setpoint: entry.setpoint
+                })
+            });
+
+        ActionLog::new(entries)
+    }
+}
+
+#[derive(Deserialize, Serialize, PartialEq)]
+struct StoredAdjustments {
+    entries: Vec<StoredAdjustment>
+}
+
+#[derive(Deserialize, Serialize, PartialEq)]
+struct StoredAdjustment {
+    time: String,
+    mode: HvacMode,
+    temp: Temperature
+}
+
+impl From<&AdjustmentLog> for StoredAdjustments {
+    fn from(value: &AdjustmentLog) -> Self {
+        let entries = value.entries()
+            .map(|adjustment| StoredAdjustment {
+                time: adjustment.time.to_rfc3339(),
+                mode: adjustment.mode,
+                temp: adjustment.temp
+            })
+            .collect();
+
+        Self { entries }
+    }
+}
+
+impl From<&StoredAdjustments> for AdjustmentLog {
+    fn from(value: &StoredAdjustments) -> Self {
+        // skip entries that don't parse rather than losing the whole file
+        let entries = value.entries.iter()
+            .filter_map(|entry| {
+                let time = DateTime::parse_from_rfc3339(&entry.time).ok()?;
+                Some(Adjustment {
+                    time: time.with_timezone(&Local),
+                    mode: entry.mode,
+                    temp: entry.temp
+                })
+            });
+
+        AdjustmentLog::new(entries)
+    }
+}
+
 enum Storable {
-    State(ThermostatState)
+    State(ThermostatState),
+    Capabilities(BackplateCapabilities),
+    Schedule(Option<EditedSchedule>),
+    Runtime(RuntimeStats),
+    Tuning(Tuning),
+    HeatingRate(HeatingRate),
+    ActionLog(ActionLog),
+    Deadband(Option<StoredDeadband>),
+    Adjustments(AdjustmentLog),
+    Theme(StoredTheme)
 }
 
 #[derive(Clone)]
@@ -176,4 +564,15 @@ impl StorageBackend {
 
         Ok(())
     }
+
+    fn remove<P: AsRef<Path>>(&self, file_name: P) -> Result<()> {
+        let file_path = self.storage_dir.join(file_name);
+
+        if file_path.is_file() {
+            info!("Removing file {:?}", file_path);
+            fs::remove_file(&file_path)?;
+        }
+
+        Ok(())
+    }
 }
@@ -0,0 +1,230 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    sync::{Arc, Mutex, PoisonError},
+    thread,
+    time::{Duration, Instant}
+};
+
+use anyhow::Result;
+use log::{error, info, warn};
+
+use crate::events::{Event, EventSender, SendError};
+
+/// Delay before the first restart, doubled for each failure in a row
+#[cfg(not(test))]
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+#[cfg(test)]
+const MIN_BACKOFF: Duration = Duration::from_millis(10);
+
+/// Failures in a row before the subsystem is reported as failing
+const FAILING_THRESHOLD: u32 = 3;
+
+/// Running this long resets the backoff, and clears the failing state
+#[cfg(not(test))]
+const STABLE_RUN: Duration = Duration::from_mins(2);
+#[cfg(test)]
+const STABLE_RUN: Duration = Duration::from_millis(200);
+
+/// How often the worker thread is checked for having exited
+#[cfg(not(test))]
+const POLL_PERIOD: Duration = Duration::from_secs(1);
+#[cfg(test)]
+const POLL_PERIOD: Duration = Duration::from_millis(10);
+
+/// Long running threads restarted by [`supervise`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Subsystem {
+    Backplate,
+    HomeAssistant,
+    Schedule,
+    Timers,
+    #[cfg(feature = "device")]
+    Sound
+}
+
+impl Subsystem {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Backplate => "Backplate",
+            Self::HomeAssistant => "Home Assistant",
+            Self::Schedule => "Schedule",
+            Self::Timers => "Timers",
+            #[cfg(feature = "device")]
+            Self::Sound => "Sound"
+        }
+    }
+
+    /// Longest delay between restarts
+    fn max_backoff(&self) -> Duration {
+        match self {
+            // the HVAC wires are left to the failsafe while disconnected,
+            // so the backplate is retried sooner than everything else
+            Self::Backplate => Duration::from_secs(5),
+            _ => Duration::from_mins(1)
+        }
+    }
+}
+
+/// Run `run` on a worker thread, and start it again with backoff when it
+/// returns an error or panics. Returning `Ok`, or failing to send because
+/// the event loop is gone, stops the subsystem.
+///
+/// Sends `Event::SubsystemFailing` after repeated failures, and again once
+/// the subsystem has been running long enough to be considered recovered.
+///
+/// Release builds abort on panic, so only errors are recovered from there.
+pub fn supervise<S, F>(subsystem: Subsystem, event_sender: S, run: F)
+    where S: EventSender + Send + 'static,
+        F: FnMut() -> Result<()> + Send + 'static
+{
+    // Shared with each worker, the lock is only poisoned by a panic
+    let run = Arc::new(Mutex::new(run));
+
+    thread::spawn(move || {
+        let mut failures = 0;
+        let mut backoff = MIN_BACKOFF;
+
+        loop {
+            let worker = {
+                let run = run.clone();
+                thread::spawn(move || {
+                    let mut run = run.lock().unwrap_or_else(PoisonError::into_inner);
+                    run()
+                })
+            };
+
+            let started = Instant::now();
+            while !worker.is_finished() {
+                thread::sleep(POLL_PERIOD);
+
+                if failures > 0 && started.elapsed() >= STABLE_RUN {
+                    info!("{} recovered", subsystem.label());
+                    if failures >= FAILING_THRESHOLD
+                        && event_sender.send_event(Event::SubsystemFailing(subsystem, false)).is_err()
+                    {
+                        return;
+                    }
+
+                    failures = 0;
+                    backoff = MIN_BACKOFF;
+                }
+            }
+
+            match worker.join() {
+                Ok(Ok(())) => {
+                    info!("{} stopped", subsystem.label());
+                    return;
+                }
+                Ok(Err(e)) if e.downcast_ref() == Some(&SendError::Disconnected) => {
+                    info!("{} stopped, event loop is gone", subsystem.label());
+                    return;
+                }
+                Ok(Err(e)) => error!("{} failed: {e}", subsystem.label()),
+                Err(_) => error!("{} panicked", subsystem.label())
+            }
+
+            failures += 1;
+
+            // receiver is gone when the app is shutting down
+            if failures == FAILING_THRESHOLD
+                && event_sender.send_event(Event::SubsystemFailing(subsystem, true)).is_err()
+            {
+                return;
+            }
+
+            warn!("Restarting {} in {backoff:?}", subsystem.label());
+            thread::sleep(backoff);
+            backoff = (backoff * 2).min(subsystem.max_backoff());
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::channel;
+
+    use anyhow::anyhow;
+
+    use super::*;
+    use crate::events::{DefaultEventSource, EventSource};
+
+    /// Failing events sent so far
+    fn failing_events(event_source: &mut DefaultEventSource) -> Result<Vec<bool>> {
+        let mut result = Vec::new();
+        while let Some(event) = event_source.poll_event()? {
+            if let Event::SubsystemFailing(Subsystem::Schedule, failing) = event {
+                result.push(failing);
+            }
+        }
+
+        Ok(result)
+    }
+
+    #[test]
+    fn restarts_until_ok() -> Result<()> {
+        let mut events = DefaultEventSource::new();
+        let (done_sender, done) = channel();
+
+        let mut attempts = 0;
+        supervise(Subsystem::Schedule, events.event_sender(), move || {
+            attempts += 1;
+            if attempts <= FAILING_THRESHOLD {
+                return Err(anyhow!("attempt {attempts}"));
+            }
+
+            done_sender.send(attempts)?;
+            Ok(())
+        });
+
+        assert_eq!(done.recv_timeout(Duration::from_secs(5))?, FAILING_THRESHOLD + 1);
+        // started again right away, too soon to be recovered
+        assert_eq!(failing_events(&mut events)?, vec![true]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn failing_clears_after_stable_run() -> Result<()> {
+        let mut events = DefaultEventSource::new();
+        let (done_sender, done) = channel();
+
+        let mut attempts = 0;
+        supervise(Subsystem::Schedule, events.event_sender(), move || {
+            attempts += 1;
+            if attempts <= FAILING_THRESHOLD {
+                return Err(anyhow!("attempt {attempts}"));
+            }
+
+            thread::sleep(STABLE_RUN + POLL_PERIOD * 5);
+            done_sender.send(())?;
+            Ok(())
+        });
+
+        done.recv_timeout(Duration::from_secs(5))?;
+        assert_eq!(failing_events(&mut events)?, vec![true, false]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn backplate_retries_sooner() {
+        assert!(Subsystem::Backplate.max_backoff() < Subsystem::HomeAssistant.max_backoff());
+    }
+}
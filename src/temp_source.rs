@@ -0,0 +1,189 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::time::Instant;
+
+use anyhow::Result;
+use log::info;
+
+use crate::{
+    config::{TempSource, TempSourceConfig},
+    events::{Event, EventHandler, EventSender, Topic},
+    temperature::Temperature
+};
+
+/// Combines readings from the backplate and Home Assistant sensors into
+/// the current temp, as `Event::SetCurrentTemp`
+pub struct TempSources<S> {
+    event_sender: S,
+    config: TempSourceConfig,
+    backplate: Option<Temperature>,
+    remote: Option<(Temperature, Instant)>,
+    /// Whether the remote reading was used for the last current temp,
+    /// to log switching between sensors
+    using_remote: bool,
+    current: Option<Temperature>
+}
+
+impl<S: EventSender> TempSources<S> {
+    pub fn new(config: &TempSourceConfig, event_sender: S) -> Self {
+        Self {
+            event_sender,
+            config: config.clone(),
+            backplate: None,
+            remote: None,
+            using_remote: false,
+            current: None
+        }
+    }
+
+    /// Remote reading, if it's recent enough to use
+    fn fresh_remote(&self, now: Instant) -> Option<Temperature> {
+        self.remote
+            .filter(|(_, received)| now.duration_since(*received) <= self.config.ha_max_age)
+            .map(|(temp, _)| temp)
+    }
+
+    fn current_temp(&self, now: Instant) -> Option<Temperature> {
+        let remote = match self.config.source {
+            TempSource::Backplate => None,
+            TempSource::HomeAssistant | TempSource::Average => self.fresh_remote(now)
+        };
+
+        match (self.config.source, self.backplate, remote) {
+            (TempSource::Average, Some(backplate), Some(remote)) => {
+                let weight = self.config.ha_weight.clamp(0.0, 1.0);
+                let temp = backplate.celsius() * (1.0 - weight) + remote.celsius() * weight;
+                Some(Temperature::from_celsius(temp))
+            }
+            (_, backplate, remote) => remote.or(backplate)
+        }
+    }
+
+    fn update(&mut self, now: Instant) -> Result<()> {
+        let using_remote = self.config.source != TempSource::Backplate
+            && self.fresh_remote(now).is_some();
+        if using_remote != self.using_remote {
+            self.using_remote = using_remote;
+            if using_remote {
+                info!("Using Home Assistant temp sensor");
+            } else {
+                info!("Using backplate temp sensor");
+            }
+        }
+
+        let current = self.current_temp(now);
+        if current.is_some() && current != self.current {
+            self.current = current;
+            if let Some(temp) = current {
+                self.event_sender.send_event(Event::SetCurrentTemp(temp))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: EventSender> EventHandler for TempSources<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<()> {
+        match event {
+            Event::BackplateTemp(temp) => {
+                self.backplate = Some(*temp);
+                self.update(Instant::now())?;
+            }
+            Event::RemoteTemp(temp) => {
+                self.remote = Some((*temp, Instant::now()));
+                self.update(Instant::now())?;
+            }
+            Event::ConfigReloaded(reloaded) => {
+                self.config = reloaded.config.temp_source.clone();
+                self.update(Instant::now())?;
+            }
+            _ => { }
+        }
+
+        Ok(())
+    }
+
+    fn topics(&self) -> &'static [Topic] {
+        &[Topic::Backplate, Topic::Ha]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::events::{DefaultEventSource, EventSource, QueueSender};
+
+    fn temp_sources(source: TempSource) -> (DefaultEventSource, TempSources<QueueSender>) {
+        let event_source = DefaultEventSource::new();
+        let config = TempSourceConfig {
+            source,
+            ha_weight: 0.75,
+            ..Default::default()
+        };
+        let sources = TempSources::new(&config, event_source.event_sender());
+
+        (event_source, sources)
+    }
+
+    fn celsius(val: f32) -> Temperature {
+        Temperature::from_celsius(val)
+    }
+
+    #[test]
+    fn backplate_ignores_remote() -> Result<()> {
+        let (mut events, mut sources) = temp_sources(TempSource::Backplate);
+
+        sources.handle_event(&Event::RemoteTemp(celsius(19.0)))?;
+        assert_eq!(events.poll_event()?, None);
+
+        sources.handle_event(&Event::BackplateTemp(celsius(22.0)))?;
+        assert!(matches!(events.poll_event()?, Some(Event::SetCurrentTemp(t)) if t == celsius(22.0)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn average_weights_remote() -> Result<()> {
+        let (mut events, mut sources) = temp_sources(TempSource::Average);
+
+        sources.handle_event(&Event::BackplateTemp(celsius(24.0)))?;
+        assert!(matches!(events.poll_event()?, Some(Event::SetCurrentTemp(t)) if t == celsius(24.0)));
+
+        sources.handle_event(&Event::RemoteTemp(celsius(20.0)))?;
+        assert!(matches!(events.poll_event()?, Some(Event::SetCurrentTemp(t)) if t == celsius(21.0)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn stale_remote_falls_back() {
+        let (_events, mut sources) = temp_sources(TempSource::HomeAssistant);
+        let received = Instant::now();
+
+        sources.backplate = Some(celsius(23.0));
+        sources.remote = Some((celsius(20.0), received));
+        assert_eq!(sources.current_temp(received), Some(celsius(20.0)));
+
+        let later = received + sources.config.ha_max_age + Duration::from_secs(1);
+        assert_eq!(sources.current_temp(later), Some(celsius(23.0)));
+    }
+}
@@ -0,0 +1,167 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{fmt, ops::{Add, Sub}};
+
+use serde::{Deserialize, Serialize};
+
+/// Temperature value, stored in degrees Celsius.
+///
+/// The backplate, config file and Home Assistant all deal in Celsius, so
+/// that is what gets stored and compared. Use [`TempUnit`] when a value
+/// needs to be shown to the user.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Temperature(f32);
+
+impl Temperature {
+    pub const fn from_celsius(val: f32) -> Self {
+        Self(val)
+    }
+
+    pub fn from_value(val: f32, unit: TempUnit) -> Self {
+        match unit {
+            TempUnit::Celsius => Self(val),
+            TempUnit::Fahrenheit => Self((val - 32.0) * 5.0 / 9.0)
+        }
+    }
+
+    pub fn celsius(&self) -> f32 {
+        self.0
+    }
+
+    pub fn fahrenheit(&self) -> f32 {
+        self.0 * 9.0 / 5.0 + 32.0
+    }
+
+    pub fn value(&self, unit: TempUnit) -> f32 {
+        match unit {
+            TempUnit::Celsius => self.celsius(),
+            TempUnit::Fahrenheit => self.fahrenheit()
+        }
+    }
+
+    /// How warm the air feels at `humidity` percent, compared to
+    /// `reference` percent. The humidity term of Steadman's apparent
+    /// temperature, indoors there is no wind or sun.
+    pub fn apparent(&self, humidity: f32, reference: f32) -> Self {
+        let temp = self.0;
+        // water vapour pressure of saturated air, in hPa
+        let saturated = 6.105 * (17.27 * temp / (237.7 + temp)).exp();
+        Self(temp + 0.33 * saturated * (humidity - reference) / 100.0)
+    }
+
+    /// Round to the nearest tenth of a degree
+    pub fn round_tenth(&self) -> Self {
+        Self((self.0 * 10.0).round() / 10.0)
+    }
+
+    /// Position of temperature within `min` and `max` as a value from 0 to 1
+    pub fn percent(&self, min: Temperature, max: Temperature) -> f32 {
+        (self.0 - min.0) / (max.0 - min.0)
+    }
+
+    /// Split into whole and fraction digits for display.
+    /// Celsius is rounded to the nearest half degree (fraction is 0 or 5),
+    /// Fahrenheit is rounded to the nearest whole degree (fraction is 0).
+    pub fn display_parts(&self, unit: TempUnit) -> (i32, i32) {
+        match unit {
+            TempUnit::Celsius => {
+                let scaled = (self.0 * 2.0).round() as i32;
+                (scaled / 2, (scaled % 2) * 5)
+            }
+            TempUnit::Fahrenheit => {
+                (self.fahrenheit().round() as i32, 0)
+            }
+        }
+    }
+
+    /// Format with a single decimal place in the given unit
+    pub fn format(&self, unit: TempUnit) -> String {
+        format!("{:.1}", self.value(unit))
+    }
+}
+
+/// Add a delta in degrees Celsius
+impl Add<f32> for Temperature {
+    type Output = Self;
+
+    fn add(self, rhs: f32) -> Self {
+        Self(self.0 + rhs)
+    }
+}
+
+/// Subtract a delta in degrees Celsius
+impl Sub<f32> for Temperature {
+    type Output = Self;
+
+    fn sub(self, rhs: f32) -> Self {
+        Self(self.0 - rhs)
+    }
+}
+
+/// Difference between two temperatures in degrees Celsius
+impl Sub for Temperature {
+    type Output = f32;
+
+    fn sub(self, rhs: Self) -> f32 {
+        self.0 - rhs.0
+    }
+}
+
+impl fmt::Display for Temperature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.1}°C", self.0)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub enum TempUnit {
+    #[default]
+    Celsius,
+    Fahrenheit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_parts() {
+        let temp = Temperature::from_celsius(20.3);
+        assert_eq!(temp.display_parts(TempUnit::Celsius), (20, 5));
+        assert_eq!(temp.display_parts(TempUnit::Fahrenheit), (69, 0));
+
+        let temp = Temperature::from_celsius(19.9);
+        assert_eq!(temp.display_parts(TempUnit::Celsius), (20, 0));
+    }
+
+    #[test]
+    fn apparent() {
+        let temp = Temperature::from_celsius(22.0);
+        assert_eq!(temp.apparent(45.0, 45.0), temp);
+        assert_eq!(temp.apparent(65.0, 45.0).round_tenth(), Temperature::from_celsius(23.7));
+        assert_eq!(temp.apparent(25.0, 45.0).round_tenth(), Temperature::from_celsius(20.3));
+    }
+
+    #[test]
+    fn round_tenth() {
+        let temp = Temperature::from_celsius(20.04) + 0.01;
+        assert_eq!(temp.round_tenth(), Temperature::from_celsius(20.1));
+    }
+}
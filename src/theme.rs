@@ -16,9 +16,9 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{fs, path::Path};
+use std::{collections::HashMap, fs, path::{Path, PathBuf}, sync::Arc};
 
-use anyhow::Result;
+use anyhow::{Result, bail};
 use embedded_graphics::{pixelcolor::Bgr888, prelude::*};
 use serde::Deserialize;
 
@@ -28,7 +28,8 @@ pub use self::{
     gauge_style::*,
     icon_style::IconStyle,
     list_style::ListStyle,
-    primitives::RectStyle
+    primitives::RectStyle,
+    status_bar_style::StatusBarStyle
 };
 
 mod font_def_de;
@@ -38,6 +39,7 @@ mod gauge_style;
 mod icon_style;
 mod list_style;
 mod primitives;
+mod status_bar_style;
 mod theme_de;
 
 /// Theme file
@@ -53,6 +55,15 @@ mod theme_de;
 ///
 /// The screen size is 320x320 pixels, with the origin in the top left.
 ///
+/// ## Named themes
+///
+/// Theme files in the `theme_dir` config directory, default
+/// "/etc/retherm/themes", can be switched between at runtime from the
+/// settings screen or the "Theme" select in Home Assistant. The `theme`
+/// config option picks the theme used at startup, by file name without the
+/// `.toml` extension. The "Default" theme is the `--theme` file when given,
+/// or the built-in theme.
+///
 /// ## Fonts
 ///
 /// Font can be specified in the format `"<name>:<size>"` where name is one
@@ -61,24 +72,140 @@ mod theme_de;
 /// * Icon: FontAwesome 7.1.0
 /// * Regular: Roboto Regular
 /// * Bold: Roboto Bold
-#[derive(Deserialize)]
+///
+/// Or the name of a font file listed in `fonts`.
+///
+/// ```toml
+/// [fonts]
+/// display = "fonts/Inter-Bold.ttf"
+/// bold = "/usr/share/fonts/DejaVuSans-Bold.ttf"
+///
+/// [thermostat]
+/// target_font = "display:100"
+/// ```
+#[derive(Deserialize, Clone)]
 #[serde(default)]
 pub struct Theme {
+    /// TTF/OTF font files by name, relative to the theme file. Using the
+    /// name of a built-in font replaces it. When a file can't be loaded the
+    /// built-in font of the same name is used, or Regular, default `{}`
+    pub fonts: HashMap<String, PathBuf>,
     pub thermostat: MainScreenTheme,
-    pub mode_select: ModeSelectTheme
+    pub mode_select: ModeSelectTheme,
+    pub diagnostics: DiagnosticsTheme,
+    pub idle_clock: IdleClockTheme,
+    /// Theme used in low ambient light, options in the `night` section
+    /// override the options above, default none. Font files are only read
+    /// from the top level `fonts`. See `night_light` in the backlight config.
+    ///
+    /// ```toml
+    /// [night.thermostat]
+    /// bg_colour = "#000000"
+    /// fg_colour = "#808080"
+    /// ```
+    #[serde(skip)]
+    pub night: Option<Box<Theme>>
 }
 
 impl Theme {
     pub fn load<P: AsRef<Path>>(file_path: P) -> Result<Self> {
+        let file_path = file_path.as_ref();
         let toml_src = fs::read_to_string(file_path)?;
-        let theme = toml::from_str(&toml_src)?;
-        Ok(theme)
+        let mut table: toml::Table = toml::from_str(&toml_src)?;
+        let night = table.remove("night");
+
+        // load font files first, so font defs in the theme can use them
+        let theme_fonts: ThemeFonts = table.clone().try_into()?;
+        let base_dir = file_path.parent().unwrap_or(Path::new("."));
+        let fonts = Arc::new(Fonts::load(&theme_fonts.fonts, base_dir));
+
+        fonts.scope(|| {
+            let mut theme: Theme = table.clone().try_into()?;
+
+            if let Some(night) = night {
+                let toml::Value::Table(night) = night else {
+                    bail!("Theme `night` must be a table");
+                };
+
+                merge_table(&mut table, night);
+                theme.night = Some(Box::new(table.try_into()?));
+            }
+
+            Ok(theme)
+        })
+    }
+
+    /// Night variant when `night` is true and the theme has one
+    pub fn variant(&self, night: bool) -> &Theme {
+        match &self.night {
+            Some(theme) if night => theme,
+            _ => self
+        }
+    }
+
+    /// Load theme `name` from `{theme_dir}/{name}.toml`. The
+    /// [`DEFAULT_THEME`] is `default_file` when given, or the built-in theme.
+    pub fn load_named(name: &str, theme_dir: &Path, default_file: Option<&Path>) -> Result<Self> {
+        if name == DEFAULT_THEME {
+            return match default_file {
+                Some(file_path) => Self::load(file_path),
+                None => Ok(Self::default())
+            };
+        }
+
+        // the name can come from Home Assistant, only load files in theme_dir
+        if !theme_names(theme_dir).iter().any(|theme| theme == name) {
+            bail!("No theme {name} in {theme_dir:?}");
+        }
+
+        Self::load(theme_dir.join(name).with_extension("toml"))
+    }
+}
+
+/// Theme used when no named theme is selected
+pub const DEFAULT_THEME: &str = "Default";
+
+/// [`DEFAULT_THEME`] followed by the names of the theme files in `theme_dir`
+pub fn theme_names(theme_dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(theme_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .filter_map(|path| path.file_stem()?.to_str().map(String::from))
+        .filter(|name| name != DEFAULT_THEME)
+        .collect();
+    names.sort();
+
+    names.insert(0, DEFAULT_THEME.to_string());
+    names
+}
+
+/// Replace values in `table` with those in `overrides`, merging nested tables
+fn merge_table(table: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (table.get_mut(&key), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(value)) => {
+                merge_table(existing, value);
+            }
+            (_, value) => {
+                table.insert(key, value);
+            }
+        }
     }
 }
 
+/// Just the font files of a theme file
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ThemeFonts {
+    fonts: HashMap<String, PathBuf>
+}
+
 impl Default for Theme {
     fn default() -> Self {
-        let fonts = Fonts::new();
+        let fonts = Fonts::current();
 
         // https://htmlcolorcodes.com/color-picker/
         // Pick dial colour, then use one level lighter for bg, one level higher for dot
@@ -95,7 +222,11 @@ impl Default for Theme {
         let fan_dial = theme_de::colour_from_hex("#0B8899").unwrap();
         let fan_dial_dot = theme_de::colour_from_hex("#086975").unwrap();
 
+        let heat_cool_dial = theme_de::colour_from_hex("#9C4DCC").unwrap();
+        let heat_cool_dial_dot = theme_de::colour_from_hex("#7E3BA6").unwrap();
+
         Theme {
+            fonts: HashMap::new(),
             thermostat: MainScreenTheme {
                 fg_colour: Bgr888::WHITE,
                 bg_colour: Bgr888::BLACK,
@@ -139,10 +270,51 @@ impl Default for Theme {
                     arc_fill: ArcFill::Below
                 },
 
+                heat_cool_gauge: GaugeAccentStyle {
+                    arc_colour: heat_cool_dial,
+                    arc_dot_colour: heat_cool_dial_dot,
+                    arc_fill: ArcFill::Below
+                },
+
                 target_font: fonts.font_def(FontName::Bold, 100),
                 target_decimal_font: fonts.font_def(FontName::Bold, 40),
                 fan_timer_font: fonts.font_def(FontName::Bold, 80),
 
+                status_bar: StatusBarStyle {
+                    center: Point { x: 160, y: 70 },
+                    spacing: 30,
+                    connected_icon: IconStyle {
+                        icon_font: fonts.font_def(FontName::Icon, 18),
+                        icon: "\u{f0c1}".to_string(),
+                        colour: Bgr888::CSS_WHITE
+                    },
+                    disconnected_icon: IconStyle {
+                        icon_font: fonts.font_def(FontName::Icon, 18),
+                        icon: "\u{f127}".to_string(),
+                        colour: theme_de::colour_from_hex("#FF5252").unwrap()
+                    },
+                    away_icon: IconStyle {
+                        icon_font: fonts.font_def(FontName::Icon, 18),
+                        icon: "\u{e50b}".to_string(),
+                        colour: Bgr888::CSS_WHITE
+                    },
+                    hold_icon: IconStyle {
+                        icon_font: fonts.font_def(FontName::Icon, 18),
+                        icon: "\u{f256}".to_string(),
+                        colour: Bgr888::CSS_WHITE
+                    },
+                    heating_icon: IconStyle {
+                        icon_font: fonts.font_def(FontName::Icon, 18),
+                        icon: "\u{f06d}".to_string(),
+                        colour: Bgr888::CSS_WHITE
+                    },
+                    cooling_icon: IconStyle {
+                        icon_font: fonts.font_def(FontName::Icon, 18),
+                        icon: "\u{f2dc}".to_string(),
+                        colour: Bgr888::CSS_WHITE
+                    }
+                },
+
                 status_icon_center: Point { x: 160, y: 230 },
                 away_icon: IconStyle {
                     icon_font: fonts.font_def(FontName::Icon, 42),
@@ -164,8 +336,22 @@ impl Default for Theme {
                     icon: "\u{f863}".to_string(),
                     colour: Bgr888::CSS_WHITE
                 },
+                offline_icon: IconStyle {
+                    icon_font: fonts.font_def(FontName::Icon, 42),
+                    icon: "\u{f127}".to_string(),
+                    colour: Bgr888::CSS_WHITE
+                },
+                boost_icon: IconStyle {
+                    icon_font: fonts.font_def(FontName::Icon, 42),
+                    icon: "\u{f135}".to_string(),
+                    colour: Bgr888::CSS_WHITE
+                },
                 status_msg_center: Point { x: 160, y: 280 },
                 status_msg_font: fonts.font_def(FontName::Regular, 20),
+                outdoor_temp_center: Point { x: 160, y: 262 },
+                outdoor_temp_font: fonts.font_def(FontName::Regular, 18),
+                apparent_temp_center: Point { x: 160, y: 95 },
+                apparent_temp_font: fonts.font_def(FontName::Regular, 18),
             },
             mode_select: ModeSelectTheme {
                 bg_colour: Bgr888::BLACK,
@@ -197,7 +383,26 @@ impl Default for Theme {
 
                     row_size: Size::new(140, 40)
                 }
-            }
+            },
+            diagnostics: DiagnosticsTheme {
+                fg_colour: Bgr888::WHITE,
+                bg_colour: Bgr888::BLACK,
+                dim_colour: Bgr888::CSS_DARK_GRAY,
+                title_font: fonts.font_def(FontName::Bold, 24),
+                text_font: fonts.font_def(FontName::Regular, 18),
+                title_center: Point { x: 160, y: 60 },
+                line_height: 24
+            },
+            idle_clock: IdleClockTheme {
+                fg_colour: Bgr888::CSS_LIGHT_GRAY,
+                bg_colour: Bgr888::BLACK,
+                dim_colour: Bgr888::CSS_DIM_GRAY,
+                time_font: fonts.font_def(FontName::Bold, 72),
+                temp_font: fonts.font_def(FontName::Regular, 32),
+                time_center: Point { x: 160, y: 150 },
+                temp_center: Point { x: 160, y: 215 }
+            },
+            night: None
         }
     }
 }
@@ -247,6 +452,10 @@ pub struct MainScreenTheme {
     /// default `{ arc_colour: "#00BCD4", arc_dot_colour: "#0090A3", arc_fill: "Below" }`
     pub fan_gauge: GaugeAccentStyle,
 
+    /// Dial styling when in heat/cool mode, filled between the setpoints,
+    /// default `{ arc_colour: "#9C4DCC", arc_dot_colour: "#7E3BA6", arc_fill: "Below" }`
+    pub heat_cool_gauge: GaugeAccentStyle,
+
     /// Target temp decimal digit font, default "Bold:100"
     pub target_font: FontDef<'static>,
 
@@ -256,6 +465,8 @@ pub struct MainScreenTheme {
     /// Fan timer font, default "Bold:80"
     pub fan_timer_font: FontDef<'static>,
 
+    pub status_bar: StatusBarStyle,
+
     /// Position of status icon, default `[160, 230]`
     #[serde(deserialize_with = "theme_de::point")]
     pub status_icon_center: Point,
@@ -276,12 +487,36 @@ pub struct MainScreenTheme {
     /// default `{ icon_font: "Icon:42", icon: "\u{f863}", colour: "#ffffff" }`
     pub fan_icon: IconStyle,
 
+    /// Home Assistant offline status icon styling,
+    /// default `{ icon_font: "Icon:42", icon: "\u{f127}", colour: "#ffffff" }`
+    pub offline_icon: IconStyle,
+
+    /// Boost status icon styling,
+    /// default `{ icon_font: "Icon:42", icon: "\u{f135}", colour: "#ffffff" }`
+    pub boost_icon: IconStyle,
+
     /// Position of status message, default `[160, 280]`
     #[serde(deserialize_with = "theme_de::point")]
     pub status_msg_center: Point,
 
     /// Status message font, default "Regular:20"
     pub status_msg_font: FontDef<'static>,
+
+    /// Position of outdoor temp, between the status icon and message,
+    /// default `[160, 262]`
+    #[serde(deserialize_with = "theme_de::point")]
+    pub outdoor_temp_center: Point,
+
+    /// Outdoor temp font, default "Regular:18"
+    pub outdoor_temp_font: FontDef<'static>,
+
+    /// Position of the apparent temp, shown while humidity comfort is
+    /// enabled, default `[160, 95]`
+    #[serde(deserialize_with = "theme_de::point")]
+    pub apparent_temp_center: Point,
+
+    /// Apparent temp font, default "Regular:18"
+    pub apparent_temp_font: FontDef<'static>,
 }
 
 impl Default for MainScreenTheme {
@@ -332,3 +567,134 @@ impl Default for ModeSelectTheme {
         Theme::default().mode_select
     }
 }
+
+/// Diagnostics screen
+///
+/// Customize the look and feel of the diagnostics screen, which lists
+/// connected Home Assistant clients.
+///
+/// ```toml
+/// [diagnostics]
+/// fg_colour = "#ffffff"
+/// ```
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct DiagnosticsTheme {
+    /// Colour of text, default "#ffffff"
+    #[serde(deserialize_with = "theme_de::colour")]
+    pub fg_colour: Bgr888,
+
+    /// Background colour, default "#000000"
+    #[serde(deserialize_with = "theme_de::colour")]
+    pub bg_colour: Bgr888,
+
+    /// Colour of secondary text, default "#A9A9A9"
+    #[serde(deserialize_with = "theme_de::colour")]
+    pub dim_colour: Bgr888,
+
+    /// Title font, default "Bold:24"
+    pub title_font: FontDef<'static>,
+
+    /// Client detail font, default "Regular:18"
+    pub text_font: FontDef<'static>,
+
+    /// Position of title, default `[160, 60]`
+    #[serde(deserialize_with = "theme_de::point")]
+    pub title_center: Point,
+
+    /// Distance between lines of text, default 24
+    pub line_height: i32
+}
+
+impl Default for DiagnosticsTheme {
+    fn default() -> Self {
+        Theme::default().diagnostics
+    }
+}
+
+/// Idle clock screen
+///
+/// Customize the look and feel of the clock shown while the backlight is
+/// dimmed, see `idle_brightness` in the backlight config.
+///
+/// ```toml
+/// [idle_clock]
+/// fg_colour = "#D3D3D3"
+/// ```
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct IdleClockTheme {
+    /// Colour of the time, default "#D3D3D3"
+    #[serde(deserialize_with = "theme_de::colour")]
+    pub fg_colour: Bgr888,
+
+    /// Background colour, default "#000000"
+    #[serde(deserialize_with = "theme_de::colour")]
+    pub bg_colour: Bgr888,
+
+    /// Colour of the current temp, default "#696969"
+    #[serde(deserialize_with = "theme_de::colour")]
+    pub dim_colour: Bgr888,
+
+    /// Time font, default "Bold:72"
+    pub time_font: FontDef<'static>,
+
+    /// Current temp font, default "Regular:32"
+    pub temp_font: FontDef<'static>,
+
+    /// Position of the time, default `[160, 150]`
+    #[serde(deserialize_with = "theme_de::point")]
+    pub time_center: Point,
+
+    /// Position of the current temp, default `[160, 215]`
+    #[serde(deserialize_with = "theme_de::point")]
+    pub temp_center: Point
+}
+
+impl Default for IdleClockTheme {
+    fn default() -> Self {
+        Theme::default().idle_clock
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn night_overrides_day_theme() {
+        let mut table: toml::Table = toml::from_str(r##"
+            [thermostat]
+            fg_colour = "#ff0000"
+            bg_colour = "#00ff00"
+        "##).unwrap();
+
+        let night: toml::Table = toml::from_str(r##"
+            [thermostat]
+            bg_colour = "#000000"
+        "##).unwrap();
+
+        merge_table(&mut table, night);
+        let theme: Theme = table.try_into().unwrap();
+
+        assert_eq!(theme.thermostat.fg_colour, Bgr888::RED);
+        assert_eq!(theme.thermostat.bg_colour, Bgr888::BLACK);
+    }
+
+    #[test]
+    fn load_named_only_loads_listed_themes() {
+        let theme_dir = std::env::temp_dir().join(format!("retherm-themes-{}", std::process::id()));
+        fs::create_dir_all(&theme_dir).unwrap();
+        fs::write(theme_dir.join("dark.toml"), "").unwrap();
+        fs::write(theme_dir.join("secret.txt"), "").unwrap();
+
+        let dark = Theme::load_named("dark", &theme_dir, None);
+        let outside = Theme::load_named("../retherm-themes-outside", &theme_dir, None);
+        let other_file = Theme::load_named("secret", &theme_dir, None);
+        fs::remove_dir_all(&theme_dir).unwrap();
+
+        assert!(dark.is_ok());
+        assert!(outside.is_err());
+        assert!(other_file.is_err());
+    }
+}
@@ -16,12 +16,14 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use std::sync::Arc;
+
 use serde::{Deserialize, de::{self, Visitor}};
 
-use super::{font_def::FontDef, fonts::{FontName, Fonts}};
+use super::{font_def::FontDef, fonts::Fonts};
 
 struct FontDefVisitor {
-    fonts: Fonts
+    fonts: Arc<Fonts>
 }
 
 impl<'de> Visitor<'de> for FontDefVisitor {
@@ -37,13 +39,11 @@ impl<'de> Visitor<'de> for FontDefVisitor {
         let (name, size) = v.split_once(":")
             .ok_or(de::Error::custom("Missing `:` in font def string"))?;
 
-        let name: FontName = name.parse()
-            .map_err(|e| de::Error::custom(e))?;
-
         let size: u32 = size.parse()
             .map_err(|_| de::Error::custom(format!("Invalid font size `{}`", size)))?;
 
-        Ok(self.fonts.font_def(name, size))
+        self.fonts.named_font_def(name, size)
+            .map_err(|e| de::Error::custom(e))
     }
 }
 
@@ -51,8 +51,7 @@ impl<'de> Deserialize<'de> for FontDef<'static> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
         where D: serde::Deserializer<'de>
     {
-        // TODO can I somehow have a single instance of `Fonts`?
-        let visitor = FontDefVisitor { fonts: Fonts::new() };
+        let visitor = FontDefVisitor { fonts: Fonts::current() };
         deserializer.deserialize_any(visitor)
     }
 }
@@ -16,18 +16,39 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock}
+};
+
+use anyhow::{Result, anyhow};
+use log::warn;
 use rusttype::Font;
 
 use super::FontDef;
 
+/// Built-in fonts, parsed once
+static BUILTIN: LazyLock<Arc<Fonts>> = LazyLock::new(|| Arc::new(Fonts::builtin()));
+
+thread_local! {
+    /// Fonts of the theme being loaded on this thread, see [`Fonts::scope`]
+    static THEME_FONTS: RefCell<Option<Arc<Fonts>>> = const { RefCell::new(None) };
+}
+
+#[derive(Clone)]
 pub struct Fonts {
     regular: Font<'static>,
     bold: Font<'static>,
-    icon: Font<'static>
+    icon: Font<'static>,
+    /// Fonts from the theme file, by lowercase name
+    theme: HashMap<String, Font<'static>>
 }
 
 impl Fonts {
-    pub fn new() -> Self {
+    fn builtin() -> Self {
         let roboto_reg = include_bytes!("../../assets/roboto/Roboto-Regular.ttf");
         let roboto_bold = include_bytes!("../../assets/roboto/Roboto-Bold.ttf");
         let fa_solid = include_bytes!("../../assets/fontawesome-free-7.1.0/Font Awesome 7 Free-Solid-900.otf");
@@ -36,18 +57,73 @@ impl Fonts {
         let bold = Font::try_from_bytes(roboto_bold).expect("valid font");
         let icon = Font::try_from_bytes(fa_solid).expect("valid font");
 
-        Self { regular, bold, icon }
+        Self { regular, bold, icon, theme: HashMap::new() }
+    }
+
+    /// Built-in fonts, plus the font files of a theme. Relative paths are
+    /// from `base_dir`. A file that can't be loaded falls back to the
+    /// built-in font of the same name, or Regular.
+    pub fn load(files: &HashMap<String, PathBuf>, base_dir: &Path) -> Self {
+        let mut fonts = BUILTIN.as_ref().clone();
+
+        for (name, path) in files {
+            let name = name.to_lowercase();
+            let path = base_dir.join(path);
+
+            match load_font(&path) {
+                Ok(font) => {
+                    fonts.theme.insert(name, font);
+                }
+                Err(e) => {
+                    warn!("Unable to load font `{name}` from {path:?}, using built-in font: {e}");
+                    if name.parse::<FontName>().is_err() {
+                        fonts.theme.insert(name, fonts.regular.clone());
+                    }
+                }
+            }
+        }
+
+        fonts
+    }
+
+    /// Run `f` with these fonts used for font defs deserialized on this
+    /// thread, and for the theme defaults
+    pub fn scope<R>(self: &Arc<Self>, f: impl FnOnce() -> R) -> R {
+        let previous = THEME_FONTS.replace(Some(self.clone()));
+        let result = f();
+        THEME_FONTS.set(previous);
+        result
+    }
+
+    /// Fonts of the theme being loaded, or the built-in fonts
+    pub fn current() -> Arc<Self> {
+        THEME_FONTS.with_borrow(|fonts| fonts.clone())
+            .unwrap_or_else(|| BUILTIN.clone())
     }
 
     pub fn font_def(&self, name: FontName, size: u32) -> FontDef<'static> {
-        let font = match name {
+        let font = self.theme.get(name.key()).unwrap_or(match name {
             FontName::Regular => &self.regular,
             FontName::Bold => &self.bold,
             FontName::Icon => &self.icon
-        };
+        });
 
         FontDef::new(font, size)
     }
+
+    /// Font def for a theme font or built-in font name, ignoring case
+    pub fn named_font_def(&self, name: &str, size: u32) -> Result<FontDef<'static>, String> {
+        match self.theme.get(&name.to_lowercase()) {
+            Some(font) => Ok(FontDef::new(font, size)),
+            None => Ok(self.font_def(name.parse()?, size))
+        }
+    }
+}
+
+fn load_font(path: &Path) -> Result<Font<'static>> {
+    let data = fs::read(path)?;
+    Font::try_from_vec(data)
+        .ok_or_else(|| anyhow!("Not a TTF/OTF font"))
 }
 
 pub enum FontName {
@@ -56,15 +132,52 @@ pub enum FontName {
     Icon
 }
 
+impl FontName {
+    fn key(&self) -> &'static str {
+        match self {
+            Self::Regular => "regular",
+            Self::Bold => "bold",
+            Self::Icon => "icon"
+        }
+    }
+}
+
 impl std::str::FromStr for FontName {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
+        match s.to_lowercase().as_str() {
             "regular" => Ok(Self::Regular),
             "bold" => Ok(Self::Bold),
             "icon" => Ok(Self::Icon),
-            s => Err(format!("Unsupported font name `{}`", s))
+            _ => Err(format!("Unsupported font name `{}`", s))
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_font_file_falls_back() {
+        let files = HashMap::from([
+            ("Display".to_string(), PathBuf::from("missing.ttf"))
+        ]);
+        let fonts = Fonts::load(&files, Path::new("/nonexistent"));
+
+        assert!(fonts.named_font_def("display", 20).is_ok());
+        assert!(fonts.named_font_def("BOLD", 20).is_ok());
+        assert!(fonts.named_font_def("other", 20).is_err());
+    }
+    #[test]
+    fn theme_fonts_scoped() {
+        let files = HashMap::from([
+            ("Display".to_string(), PathBuf::from("missing.ttf"))
+        ]);
+        let fonts = Arc::new(Fonts::load(&files, Path::new("/nonexistent")));
+
+        assert!(fonts.scope(|| Fonts::current().named_font_def("display", 20).is_ok()));
+        assert!(Fonts::current().named_font_def("display", 20).is_err());
+    }
+}
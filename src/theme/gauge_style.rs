@@ -75,7 +75,8 @@ pub struct GaugeAccentStyle {
     #[serde(deserialize_with = "theme_de::colour")]
     pub arc_dot_colour: Bgr888,
 
-    /// Fill the arc below or above the target value
+    /// Fill the arc below or above the target value,
+    /// ignored by range gauges which fill between the setpoints
     pub arc_fill: ArcFill,
 }
 
@@ -0,0 +1,62 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use embedded_graphics::prelude::Point;
+use serde::Deserialize;
+
+use super::{theme_de, IconStyle};
+
+/// Main screen status bar, a row of small icons above the target temp
+///
+/// ```toml
+/// [thermostat.status_bar]
+/// center = [160, 70]
+/// spacing = 30
+/// heating_icon = { icon_font = "Icon:18", icon = "\uf06d", colour = "#ff9800" }
+/// ```
+#[derive(Deserialize, Clone)]
+pub struct StatusBarStyle {
+    /// Center of the row of icons, default `[160, 70]`
+    #[serde(deserialize_with = "theme_de::point")]
+    pub center: Point,
+
+    /// Distance between icon centers, default 30
+    pub spacing: i32,
+
+    /// Home Assistant connected icon,
+    /// default `{ icon_font: "Icon:18", icon: "\u{f0c1}", colour: "#ffffff" }`
+    pub connected_icon: IconStyle,
+
+    /// Home Assistant disconnected icon,
+    /// default `{ icon_font: "Icon:18", icon: "\u{f127}", colour: "#ff5252" }`
+    pub disconnected_icon: IconStyle,
+
+    /// Away icon, default `{ icon_font: "Icon:18", icon: "\u{e50b}", colour: "#ffffff" }`
+    pub away_icon: IconStyle,
+
+    /// Schedule hold icon, default `{ icon_font: "Icon:18", icon: "\u{f256}", colour: "#ffffff" }`
+    pub hold_icon: IconStyle,
+
+    /// Icon pulsing while heating,
+    /// default `{ icon_font: "Icon:18", icon: "\u{f06d}", colour: "#ffffff" }`
+    pub heating_icon: IconStyle,
+
+    /// Icon pulsing while cooling,
+    /// default `{ icon_font: "Icon:18", icon: "\u{f2dc}", colour: "#ffffff" }`
+    pub cooling_icon: IconStyle
+}
@@ -19,13 +19,12 @@
 use std::{
     collections::HashMap,
     sync::{Arc, Mutex, mpsc::{RecvTimeoutError, Sender, channel}},
-    thread,
     time::Duration
 };
 
-use log::warn;
+use log::{debug, warn};
 
-use crate::events::{Event, EventHandler, EventSender};
+use crate::{events::{Event, EventHandler, EventSender, Topic}, supervisor::{Subsystem, supervise}};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TimerId {
@@ -33,6 +32,24 @@ pub enum TimerId {
     Backlight,
     HvacLockout,
     Fan,
+    HaOffline,
+    Boost,
+    ButtonHold,
+    TargetSettle,
+    FanRun,
+    Stage2,
+    Motion,
+    Hold,
+    IdleClock,
+    Runtime,
+    StatusPulse,
+    DutyCycle,
+    Autotune,
+    ServiceMode,
+    WireTest,
+    Recovery,
+    WindowOpen,
+    QuietHours,
 }
 
 pub struct Timers<S> {
@@ -53,10 +70,10 @@ impl<S: EventSender + Clone + Send + 'static> Timers<S> {
 
         let timers = self.timers.clone();
         let event_sender = self.event_sender.clone();
+        let mut timeout = timeout;
 
-        thread::spawn(move || {
-            let mut timeout = timeout;
-
+        // restarted with the last timeout if it fails
+        supervise(Subsystem::Timers, self.event_sender.clone(), move || {
             loop {
                 // recv_timeout() returns Err when timeout reached
                 // using sender of the channel resets the timeout
@@ -64,7 +81,9 @@ impl<S: EventSender + Clone + Send + 'static> Timers<S> {
                     Ok(new_timeout) => timeout = new_timeout,
                     Err(RecvTimeoutError::Timeout) => {
                         timers.lock().unwrap().remove(&id);
-                        event_sender.send_event(Event::TimeoutReached(id)).unwrap();
+                        if let Err(err) = event_sender.send_event(Event::TimeoutReached(id)) {
+                            debug!("Timer {id:?} stopped: {err}");
+                        }
                         break;
                     }
                     Err(RecvTimeoutError::Disconnected) => {
@@ -73,6 +92,8 @@ impl<S: EventSender + Clone + Send + 'static> Timers<S> {
                     }
                 }
             }
+
+            Ok(())
         });
 
         self.timers.lock().unwrap().insert(id, sender);
@@ -89,10 +110,11 @@ impl<S: EventSender + Clone + Send + 'static> Timers<S> {
         let timers = self.timers.clone();
         let event_sender = self.event_sender.clone();
 
-        thread::spawn(move || {
-            let mut ticks = 0;
-            let mut timeout_ticks = duration_ticks(timeout, tick_duration);
+        let mut ticks = 0;
+        let mut timeout_ticks = duration_ticks(timeout, tick_duration);
 
+        // restarted with the ticks left if it fails
+        supervise(Subsystem::Timers, self.event_sender.clone(), move || {
             while ticks < timeout_ticks {
                 match receiver.recv_timeout(tick_duration) {
                     Ok(new_timeout) => {
@@ -103,17 +125,24 @@ impl<S: EventSender + Clone + Send + 'static> Timers<S> {
                         ticks += 1;
                         let remaining = timeout_ticks - ticks;
                         let remaining = tick_duration.mul_f32(remaining as f32);
-                        event_sender.send_event(Event::TimerTick(id, remaining)).unwrap();
+                        if let Err(err) = event_sender.send_event(Event::TimerTick(id, remaining)) {
+                            debug!("Tick timer {id:?} stopped: {err}");
+                            return Ok(());
+                        }
                     }
                     Err(RecvTimeoutError::Disconnected) => {
                         warn!("Tick thread sender disconnected");
-                        return;
+                        return Ok(());
                     }
                 }
             }
 
             timers.lock().unwrap().remove(&id);
-            event_sender.send_event(Event::TimeoutReached(id)).unwrap();
+            if let Err(err) = event_sender.send_event(Event::TimeoutReached(id)) {
+                debug!("Tick timer {id:?} stopped: {err}");
+            }
+
+            Ok(())
         });
 
         self.timers.lock().unwrap().insert(id, sender);
@@ -145,12 +174,18 @@ impl<S: EventSender + Clone + Send + 'static> EventHandler for Timers<S> {
         }
         Ok(())
     }
+
+    fn topics(&self) -> &'static [Topic] {
+        &[Topic::Timer]
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::thread;
+
     use super::*;
-    use crate::events::{DefaultEventSource, EventSource};
+    use crate::events::{DefaultEventSource, EventSource, QueueSender};
 
     fn setup_logging() {
         let _ = env_logger::builder()
@@ -162,7 +197,7 @@ mod tests {
         mut event_source: S,
         mut handler: H
     ) -> std::thread::JoinHandle<()>
-        where S: EventSource<Sender<Event>> + Send + 'static,
+        where S: EventSource<QueueSender> + Send + 'static,
             H: EventHandler + Send + 'static
     {
         thread::spawn(move || {
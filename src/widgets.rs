@@ -19,9 +19,13 @@
 mod gauge;
 mod icon;
 mod list;
+mod status_bar;
+mod text_cache;
 
 pub use self::{
-    gauge::GaugeWidget,
+    gauge::{GaugeWidget, RangeSetpoint},
     icon::IconWidget,
-    list::{ListItem, ListWidget}
+    list::{ListItem, ListWidget},
+    status_bar::{StatusBarWidget, StatusIcon},
+    text_cache::TextCache
 };
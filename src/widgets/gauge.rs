@@ -16,22 +16,46 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use std::{cell::OnceCell, collections::HashMap};
+
 use embedded_graphics::{
     pixelcolor::Bgr888,
     prelude::*,
     primitives::{Arc, Circle, PrimitiveStyle},
-    text::{Alignment, Text}
+    text::Alignment
 };
 
 use crate::theme::{ArcFill, GaugeAccentStyle, GaugeStyle};
+use super::TextCache;
 
 pub struct GaugeWidget {
-    style: GaugeStyle
+    style: GaugeStyle,
+    /// Arc pixels, traced on first draw
+    arc_mask: OnceCell<ArcMask>,
+    /// Current value labels
+    labels: TextCache
+}
+
+/// Setpoint of a range gauge that is highlighted, and adjusted by the dial
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RangeSetpoint {
+    Low,
+    High
+}
+
+impl RangeSetpoint {
+    pub fn toggle(self) -> Self {
+        match self {
+            Self::Low => Self::High,
+            Self::High => Self::Low
+        }
+    }
 }
 
 impl GaugeWidget {
     pub fn new(style: GaugeStyle) -> Self {
-        Self { style }
+        let labels = TextCache::new(style.font.clone());
+        Self { style, arc_mask: OnceCell::new(), labels }
     }
 
     pub fn draw<D>(
@@ -78,6 +102,115 @@ impl GaugeWidget {
         Ok(())
     }
 
+    /// Draw a gauge with low and high setpoint dots and the arc filled between
+    /// them. The `active` setpoint dot gets the inner dot of a regular target,
+    /// the other dot is drawn in the accent arc colour.
+    pub fn draw_range<D>(
+        &self,
+        target: &mut D,
+        bg_colour: Bgr888,
+        accent: &GaugeAccentStyle,
+        (low_value, high_value): (f32, f32),
+        active: RangeSetpoint,
+        current_value: Option<(f32, String)>
+    ) -> Result<(), D::Error>
+        where D: DrawTarget<Color = Bgr888>
+    {
+        let center = target.bounding_box().center();
+
+        self.draw_arc(target, 0.0, 1.0, center, self.style.arc_bg_colour)?;
+        self.draw_arc(target, low_value, high_value, center, accent.arc_colour)?;
+
+        if let Some((current_value, current_label)) = current_value {
+            self.draw_arc_point(target, current_value, center, self.style.arc_dot_dia, self.style.arc_dot_colour)?;
+
+            let current_value_center = self.get_arc_point(center, current_value, self.style.arc_text_dia);
+            self.draw_text(target, bg_colour, current_value_center, current_label)?;
+        }
+
+        // inactive dot first, so the active dot is on top when they're close
+        let (active_value, inactive_value) = match active {
+            RangeSetpoint::Low => (low_value, high_value),
+            RangeSetpoint::High => (high_value, low_value),
+        };
+
+        self.draw_arc_point(target, inactive_value, center, self.style.arc_target_dot_dia, accent.arc_dot_colour)?;
+        self.draw_arc_point(target, inactive_value, center, self.style.arc_width, accent.arc_colour)?;
+
+        self.draw_arc_point(target, active_value, center, self.style.arc_target_dot_dia, accent.arc_dot_colour)?;
+        self.draw_arc_point(target, active_value, center, self.style.arc_width, self.style.fg_colour)?;
+
+        Ok(())
+    }
+
+    /// Fast path for when only the target value changed since the last draw.
+    /// Erases the old target dot and repaints the arc between the old and new
+    /// target positions, leaving the rest of the gauge untouched.
+    pub fn draw_target_change<D>(
+        &self,
+        target: &mut D,
+        bg_colour: Bgr888,
+        accent: Option<&GaugeAccentStyle>,
+        old_value: f32,
+        target_value: f32,
+        current_value: Option<(f32, String)>
+    ) -> Result<(), D::Error>
+        where D: DrawTarget<Color = Bgr888>
+    {
+        let center = target.bounding_box().center();
+
+        // erase old target dot, including the parts outside of the arc
+        let old_point = self.get_arc_point(center, old_value, self.style.arc_dia);
+        Circle::with_center(old_point, self.style.arc_target_dot_dia + 2)
+            .into_styled(PrimitiveStyle::with_fill(bg_colour))
+            .draw(target)?;
+
+        // range of arc covered by the old and new target dots
+        let margin = self.target_dot_margin();
+        let start = (old_value.min(target_value) - margin).max(0.0);
+        let end = (old_value.max(target_value) + margin).min(1.0);
+
+        self.draw_arc(target, start, end, center, self.style.arc_bg_colour)?;
+
+        // Accent arc is drawn after background arc so that it's end cap
+        // covers the background arc end cap, same as a full draw
+        let dot_colour = if let Some(accent) = accent {
+            let (arc_start, arc_end) = match accent.arc_fill {
+                ArcFill::Below => (0.0, target_value),
+                ArcFill::Above => (target_value, 1.0),
+            };
+
+            let (arc_start, arc_end) = (arc_start.max(start), arc_end.min(end));
+            if arc_start < arc_end {
+                self.draw_arc(target, arc_start, arc_end, center, accent.arc_colour)?;
+            }
+
+            accent.arc_dot_colour
+        } else {
+            self.style.arc_bg_colour
+        };
+
+        // current value dot and label may have been erased with the old dot
+        if let Some((current_value, current_label)) = current_value {
+            self.draw_arc_point(target, current_value, center, self.style.arc_dot_dia, self.style.arc_dot_colour)?;
+
+            let current_value_center = self.get_arc_point(center, current_value, self.style.arc_text_dia);
+            self.draw_text(target, bg_colour, current_value_center, current_label)?;
+        }
+
+        self.draw_arc_point(target, target_value, center, self.style.arc_target_dot_dia, dot_colour)?;
+        self.draw_arc_point(target, target_value, center, self.style.arc_width, self.style.fg_colour)?;
+
+        Ok(())
+    }
+
+    /// Target dot radius (plus erase border) as a percent of the arc sweep
+    fn target_dot_margin(&self) -> f32 {
+        let dot_radius = (self.style.arc_target_dot_dia / 2 + 2) as f32;
+        let arc_radius = (self.style.arc_dia / 2) as f32;
+        (dot_radius / arc_radius).to_degrees() / self.style.arc_sweed_deg
+    }
+
     fn get_arc_point(&self, center: Point, percent: f32, diameter: u32) -> Point {
         let point_angle = self.style.arc_sweed_deg * percent + self.style.arc_start_deg;
         let point_angle = Angle::from_degrees(point_angle);
@@ -97,17 +230,14 @@ impl GaugeWidget {
     ) -> Result<(), D::Error>
         where D: DrawTarget<Color = Bgr888>
     {
-        let font_style = self.style.font
-            .font_style(self.style.fg_colour, bg_color);
-
-        let text = Text::with_alignment(
+        self.labels.draw(
+            target,
             &s,
             center,
-            font_style,
-            Alignment::Center
-        );
-
-        text.draw(target)?;
+            Alignment::Center,
+            self.style.fg_colour,
+            bg_color
+        )?;
 
         Ok(())
     }
@@ -122,25 +252,13 @@ impl GaugeWidget {
     ) -> Result<(), D::Error>
         where D: DrawTarget<Color = Bgr888>
     {
-        let angle_start = self.style.arc_start_deg + (self.style.arc_sweed_deg * start_percent);
-        let sweep_angle = self.style.arc_sweed_deg * (end_percent - start_percent);
+        let mask = self.arc_mask.get_or_init(|| ArcMask::new(&self.style));
 
-        let arc = Arc::with_center(
-            center,
-            self.style.arc_dia,
-            Angle::from_degrees(angle_start),
-            Angle::from_degrees(sweep_angle)
-        );
-
-        // This is most likely less efficient than arc.into_styled().draw()
-        // But we get pretty rounded corners
-        for p in arc.points() {
-            Circle::with_center(p, self.style.arc_width)
-                .into_styled(PrimitiveStyle::with_fill(colour))
-                .draw(target)?;
-        }
+        let pixels = mask.pixels.iter()
+            .filter(|p| p.from <= end_percent && p.to >= start_percent)
+            .map(|p| Pixel(center + p.offset, colour));
 
-        Ok(())
+        target.draw_iter(pixels)
     }
 
     fn draw_arc_point<D>(
@@ -162,3 +280,133 @@ impl GaugeWidget {
         Ok(())
     }
 }
+
+/// Pixels of the full gauge arc relative to the center, each with the range
+/// of arc positions (as percent of the sweep) whose brush covers the pixel.
+///
+/// The arc is drawn with a filled circle at each point for rounded ends,
+/// which is hundreds of circles for the full arc. Tracing it once means a
+/// section of the arc is drawn by picking out the pixels in it's range.
+struct ArcMask {
+    pixels: Vec<ArcPixel>
+}
+
+struct ArcPixel {
+    offset: Point,
+    from: f32,
+    to: f32
+}
+
+impl ArcMask {
+    fn new(style: &GaugeStyle) -> Self {
+        let arc = Arc::with_center(
+            Point::zero(),
+            style.arc_dia,
+            Angle::from_degrees(style.arc_start_deg),
+            Angle::from_degrees(style.arc_sweed_deg)
+        );
+
+        let mut coverage: HashMap<Point, (f32, f32)> = HashMap::new();
+        for p in arc.points() {
+            let percent = arc_percent(style, p);
+            for offset in Circle::with_center(p, style.arc_width).points() {
+                coverage.entry(offset)
+                    .and_modify(|(from, to)| {
+                        *from = from.min(percent);
+                        *to = to.max(percent);
+                    })
+                    .or_insert((percent, percent));
+            }
+        }
+
+        // row order, so a section is drawn top to bottom like a primitive
+        let mut pixels: Vec<_> = coverage.into_iter()
+            .map(|(offset, (from, to))| ArcPixel { offset, from, to })
+            .collect();
+        pixels.sort_by_key(|p| (p.offset.y, p.offset.x));
+
+        Self { pixels }
+    }
+}
+
+/// Position of arc point `p` (relative to the arc center) as percent of the sweep
+fn arc_percent(style: &GaugeStyle, p: Point) -> f32 {
+    let angle = (p.y as f32).atan2(p.x as f32).to_degrees();
+    let mut offset = (angle - style.arc_start_deg).rem_euclid(360.0);
+
+    // points just before the start angle belong to the start of the arc
+    if offset > style.arc_sweed_deg + (360.0 - style.arc_sweed_deg) / 2.0 {
+        offset -= 360.0;
+    }
+
+    offset / style.arc_sweed_deg
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, convert::Infallible};
+
+    use embedded_graphics::primitives::Rectangle;
+
+    use crate::theme::Theme;
+
+    use super::*;
+
+    /// Screen sized target recording which pixels were drawn
+    #[derive(Default)]
+    struct PixelSet(HashSet<Point>);
+
+    impl OriginDimensions for PixelSet {
+        fn size(&self) -> Size {
+            Size::new(320, 320)
+        }
+    }
+
+    impl DrawTarget for PixelSet {
+        type Color = Bgr888;
+        type Error = Infallible;
+
+        fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+            where I: IntoIterator<Item = Pixel<Self::Color>>
+        {
+            self.0.extend(pixels.into_iter().map(|Pixel(p, _)| p));
+            Ok(())
+        }
+
+        fn fill_solid(&mut self, area: &Rectangle, _colour: Self::Color) -> Result<(), Self::Error> {
+            self.0.extend(area.points());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn arc_mask_matches_circles() {
+        let style = Theme::default().thermostat.gauge;
+        let gauge = GaugeWidget::new(style.clone());
+
+        for (start, end) in [(0.0, 1.0), (0.0, 0.35), (0.42, 0.9)] {
+            let mut expected = PixelSet::default();
+            let center = expected.bounding_box().center();
+
+            // the slow way, a circle at each arc point
+            let arc = Arc::with_center(
+                center,
+                style.arc_dia,
+                Angle::from_degrees(style.arc_start_deg + style.arc_sweed_deg * start),
+                Angle::from_degrees(style.arc_sweed_deg * (end - start))
+            );
+            for p in arc.points() {
+                Circle::with_center(p, style.arc_width)
+                    .into_styled(PrimitiveStyle::with_fill(Bgr888::RED))
+                    .draw(&mut expected)
+                    .unwrap();
+            }
+
+            let mut actual = PixelSet::default();
+            gauge.draw_arc(&mut actual, start, end, center, Bgr888::RED).unwrap();
+
+            let diff = expected.0.symmetric_difference(&actual.0).count();
+            assert_eq!(diff, 0, "arc {start}..{end}");
+        }
+    }
+}
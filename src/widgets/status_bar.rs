@@ -0,0 +1,107 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use embedded_graphics::{
+    pixelcolor::{Bgr888, RgbColor},
+    prelude::{DrawTarget, Point}
+};
+
+use crate::theme::StatusBarStyle;
+use super::IconWidget;
+
+/// Icon shown in the status bar
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StatusIcon {
+    Connected,
+    Disconnected,
+    Away,
+    Hold,
+    Heating,
+    Cooling
+}
+
+/// Row of small status icons, centered on the style center
+pub struct StatusBarWidget {
+    style: StatusBarStyle,
+    connected: IconWidget,
+    disconnected: IconWidget,
+    away: IconWidget,
+    hold: IconWidget,
+    heating: IconWidget,
+    cooling: IconWidget
+}
+
+impl StatusBarWidget {
+    pub fn new(style: StatusBarStyle) -> Self {
+        Self {
+            connected: IconWidget::new(style.connected_icon.clone()),
+            disconnected: IconWidget::new(style.disconnected_icon.clone()),
+            away: IconWidget::new(style.away_icon.clone()),
+            hold: IconWidget::new(style.hold_icon.clone()),
+            heating: IconWidget::new(style.heating_icon.clone()),
+            cooling: IconWidget::new(style.cooling_icon.clone()),
+            style
+        }
+    }
+
+    /// Draw `icons` left to right. Heating and cooling icons are drawn
+    /// faded on the `pulse` frames of their animation.
+    pub fn draw<D>(
+        &self,
+        target: &mut D,
+        bg_colour: Bgr888,
+        icons: &[StatusIcon],
+        pulse: bool
+    ) -> Result<(), D::Error>
+        where D: DrawTarget<Color = Bgr888>
+    {
+        let width = self.style.spacing * (icons.len() as i32 - 1);
+        let mut position = self.style.center - Point::new(width / 2, 0);
+
+        for icon in icons {
+            let (widget, colour) = match icon {
+                StatusIcon::Connected => (&self.connected, self.style.connected_icon.colour),
+                StatusIcon::Disconnected => (&self.disconnected, self.style.disconnected_icon.colour),
+                StatusIcon::Away => (&self.away, self.style.away_icon.colour),
+                StatusIcon::Hold => (&self.hold, self.style.hold_icon.colour),
+                StatusIcon::Heating => (&self.heating, self.style.heating_icon.colour),
+                StatusIcon::Cooling => (&self.cooling, self.style.cooling_icon.colour)
+            };
+
+            let colour = match icon {
+                StatusIcon::Heating | StatusIcon::Cooling if pulse => fade(colour, bg_colour),
+                _ => colour
+            };
+
+            widget.draw(target, position, bg_colour, Some(colour))?;
+            position.x += self.style.spacing;
+        }
+
+        Ok(())
+    }
+}
+
+/// Halfway between `colour` and `bg_colour`
+fn fade(colour: Bgr888, bg_colour: Bgr888) -> Bgr888 {
+    let mid = |a: u8, b: u8| ((a as u16 + b as u16) / 2) as u8;
+    Bgr888::new(
+        mid(colour.r(), bg_colour.r()),
+        mid(colour.g(), bg_colour.g()),
+        mid(colour.b(), bg_colour.b())
+    )
+}
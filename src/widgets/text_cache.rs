@@ -0,0 +1,127 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{cell::RefCell, collections::HashMap, convert::Infallible};
+
+use embedded_graphics::{
+    pixelcolor::Bgr888,
+    prelude::*,
+    primitives::Rectangle,
+    text::{Alignment, Text}
+};
+
+use crate::theme::FontDef;
+
+/// Rendered texts kept before the cache is emptied and starts over
+const MAX_ENTRIES: usize = 64;
+
+/// Text that is rendered with the font once, then copied to the target on
+/// later draws. Meant for text redrawn often with few distinct values, such
+/// as the target temp digits.
+pub struct TextCache {
+    font: FontDef<'static>,
+    entries: RefCell<HashMap<TextKey, RenderedText>>
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct TextKey {
+    text: String,
+    alignment: Alignment,
+    fg_colour: Bgr888,
+    bg_colour: Bgr888
+}
+
+/// Pixels and bounds of text rendered at the origin
+struct RenderedText {
+    pixels: Vec<Pixel<Bgr888>>,
+    bounds: Rectangle
+}
+
+impl TextCache {
+    pub fn new(font: FontDef<'static>) -> Self {
+        Self { font, entries: RefCell::new(HashMap::new()) }
+    }
+
+    /// Draw `text` at `position` and return it's bounding box
+    pub fn draw<D>(
+        &self,
+        target: &mut D,
+        text: &str,
+        position: Point,
+        alignment: Alignment,
+        fg_colour: Bgr888,
+        bg_colour: Bgr888
+    ) -> Result<Rectangle, D::Error>
+        where D: DrawTarget<Color = Bgr888>
+    {
+        let key = TextKey { text: text.to_string(), alignment, fg_colour, bg_colour };
+
+        let mut entries = self.entries.borrow_mut();
+        if entries.len() >= MAX_ENTRIES && !entries.contains_key(&key) {
+            entries.clear();
+        }
+
+        let rendered = entries.entry(key)
+            .or_insert_with_key(|key| self.render(key));
+
+        target.draw_iter(
+            rendered.pixels.iter().map(|Pixel(p, c)| Pixel(*p + position, *c))
+        )?;
+
+        Ok(rendered.bounds.translate(position))
+    }
+
+    fn render(&self, key: &TextKey) -> RenderedText {
+        let font_style = self.font.font_style(key.fg_colour, key.bg_colour);
+        let text = Text::with_alignment(&key.text, Point::zero(), font_style, key.alignment);
+
+        let mut recorder = PixelRecorder { pixels: Vec::new() };
+        let Ok(_) = text.draw(&mut recorder);
+
+        RenderedText {
+            pixels: recorder.pixels,
+            bounds: text.bounding_box()
+        }
+    }
+}
+
+/// Size of the area around the origin that text can be rendered in
+const RECORD_SIZE: u32 = 2048;
+
+/// Draw target that keeps the drawn pixels instead of showing them
+struct PixelRecorder {
+    pixels: Vec<Pixel<Bgr888>>
+}
+
+impl Dimensions for PixelRecorder {
+    fn bounding_box(&self) -> Rectangle {
+        Rectangle::with_center(Point::zero(), Size::new_equal(RECORD_SIZE))
+    }
+}
+
+impl DrawTarget for PixelRecorder {
+    type Color = Bgr888;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+        where I: IntoIterator<Item = Pixel<Self::Color>>
+    {
+        self.pixels.extend(pixels);
+        Ok(())
+    }
+}
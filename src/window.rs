@@ -18,7 +18,9 @@
 
 use anyhow::Result;
 
-use crate::config::BacklightConfig;
+use crate::config::Config;
+
+mod dev_overlay;
 
 #[cfg(feature = "device")]
 mod backlight;
@@ -26,21 +28,34 @@ mod backlight;
 mod window_linuxfb;
 
 #[cfg(feature = "device")]
-pub fn new_window(config: &BacklightConfig) -> Result<window_linuxfb::FramebufferWindow> {
-    window_linuxfb::FramebufferWindow::new(config)
+pub type AppWindow = window_linuxfb::FramebufferWindow;
+
+#[cfg(feature = "device")]
+pub fn new_window(config: &Config) -> Result<AppWindow> {
+    window_linuxfb::FramebufferWindow::new(&config.backlight, config.dev_overlay)
 }
 
+/// Events held before the oldest are dropped, well beyond what the event
+/// loop falls behind by in normal use
+#[cfg(feature = "device")]
+const EVENT_QUEUE_CAPACITY: usize = 1024;
+
 #[cfg(feature = "device")]
 pub fn new_event_source() -> Result<crate::events::DefaultEventSource> {
-    Ok(crate::events::DefaultEventSource::new())
+    Ok(crate::events::DefaultEventSource::bounded(EVENT_QUEUE_CAPACITY))
 }
 
 #[cfg(feature = "simulate")]
 mod window_sdl;
+#[cfg(feature = "simulate")]
+mod sim_panel;
+
+#[cfg(feature = "simulate")]
+pub type AppWindow = window_sdl::SdlWindow;
 
 #[cfg(feature = "simulate")]
-pub fn new_window(_config: &BacklightConfig) -> Result<window_sdl::SdlWindow> {
-    window_sdl::SdlWindow::new()
+pub fn new_window(config: &Config) -> Result<AppWindow> {
+    window_sdl::SdlWindow::new(config.dev_overlay)
 }
 
 #[cfg(feature = "simulate")]
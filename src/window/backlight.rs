@@ -16,20 +16,94 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{cmp::min, fs, path::{Path, PathBuf}};
+use std::{
+    cmp::min,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
+    thread,
+    time::Duration
+};
 
 use anyhow::Result;
+use log::{info, warn};
+
+use crate::config::BacklightConfig;
+
+const BACKLIGHT_CLASS_DIR: &str = "/sys/class/backlight";
+
+/// Number of off/on blinks when identifying the device
+const FLASH_COUNT: u32 = 3;
+const FLASH_PERIOD: Duration = Duration::from_millis(300);
+
+/// Auto brightness changes are spread over a few steps, so a change in
+/// ambient light doesn't make the screen jump
+const FADE_STEPS: u32 = 10;
+const FADE_STEP_PERIOD: Duration = Duration::from_millis(30);
+
+/// Open the configured backlight device, or detect one in sysfs.
+/// Returns `None` when no usable device is found; the screen will stay at
+/// whatever brightness it's at.
+pub fn find_backlight(config: &BacklightConfig) -> Option<Backlight> {
+    let device_dir = match &config.device {
+        Some(device_dir) => device_dir.clone(),
+        None => match detect_device_dir() {
+            Some(device_dir) => device_dir,
+            None => {
+                warn!("No backlight found in {BACKLIGHT_CLASS_DIR}, brightness control disabled");
+                return None;
+            }
+        }
+    };
+
+    match Backlight::load(&device_dir, config) {
+        Ok(backlight) => {
+            info!("Using backlight {device_dir:?}");
+            Some(backlight)
+        }
+        Err(e) => {
+            warn!("Error opening backlight {device_dir:?}, brightness control disabled: {e}");
+            None
+        }
+    }
+}
+
+fn detect_device_dir() -> Option<PathBuf> {
+    let mut devices: Vec<PathBuf> = fs::read_dir(BACKLIGHT_CLASS_DIR).ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.join("max_brightness").is_file())
+        .collect();
+
+    // sort for a predictable pick when there is more than one
+    devices.sort();
+
+    if devices.len() > 1 {
+        info!("Found multiple backlights {devices:?}, set backlight.device to choose");
+    }
+
+    devices.into_iter().next()
+}
 
 #[derive(Clone)]
 pub struct Backlight {
-    device: BacklightDirectory,
+    output: Arc<Mutex<BacklightOutput>>,
     max_brightness: u32,
-    default_brightness: u32,
-    current_brightness: u32
+    config: BacklightConfig,
+    brightness_override: Option<u32>,
+    /// Local brightness is limited to `quiet_brightness` during quiet hours
+    quiet: bool,
+    /// Last ambient light sensor reading
+    light: Option<u16>,
+    /// Screen is on, rather than off or dimmed for the idle clock
+    is_on: bool,
+    /// Brightness set or faded to, the written brightness gets there once
+    /// a fade finishes
+    target_brightness: u32
 }
 
 impl Backlight {
-    pub fn load<P>(device_dir: P, default_brightness: u32) -> Result<Self>
+    pub fn load<P>(device_dir: P, config: &BacklightConfig) -> Result<Self>
         where P: AsRef<Path>
     {
         let device = BacklightDirectory::new(device_dir);
@@ -40,30 +114,230 @@ impl Backlight {
         let current_brightness = device.read_value("brightness")?;
 
         Ok(Self {
-            device,
+            output: Arc::new(Mutex::new(BacklightOutput {
+                device,
+                written: current_brightness,
+                writer_id: 0
+            })),
             max_brightness,
-            default_brightness,
-            current_brightness
+            config: config.clone(),
+            brightness_override: None,
+            quiet: false,
+            light: None,
+            is_on: current_brightness > 0,
+            target_brightness: current_brightness
         })
     }
 
     fn set_brightness(&mut self, value: u32) -> Result<()> {
         let value = min(value, self.max_brightness);
+        self.target_brightness = value;
+
+        let mut output = lock(&self.output);
+        output.writer_id += 1;
+        output.write(value)
+    }
+
+    /// Step towards `value` from a separate thread, so the event loop isn't
+    /// blocked. Replaces any fade that is still running.
+    fn fade_brightness(&mut self, value: u32) {
+        let value = min(value, self.max_brightness);
+        self.target_brightness = value;
+
+        let (id, from) = {
+            let mut output = lock(&self.output);
+            output.writer_id += 1;
+            (output.writer_id, output.written as f32)
+        };
+
+        let output = self.output.clone();
+        thread::spawn(move || {
+            for step in 1..=FADE_STEPS {
+                let ratio = step as f32 / FADE_STEPS as f32;
+                let brightness = (from + (value as f32 - from) * ratio).round() as u32;
+                if !write_step(&output, id, brightness) {
+                    return;
+                }
+                thread::sleep(FADE_STEP_PERIOD);
+            }
+        });
+    }
+
+    /// Home Assistant override, then auto brightness, then the configured
+    /// brightness. Quiet hours only limit the local brightness.
+    fn on_brightness(&self) -> u32 {
+        let local = self.light
+            .and_then(|light| auto_brightness(&self.config, light))
+            .unwrap_or(self.config.brightness);
+        let local = if self.quiet {
+            min(local, self.config.quiet_brightness)
+        } else {
+            local
+        };
+
+        self.brightness_override.unwrap_or(local)
+    }
+
+    pub fn turn_on(&mut self) -> Result<()> {
+        self.is_on = true;
+        self.set_brightness(self.on_brightness())
+    }
+
+    /// Brightness set by home assistant takes precedence over the local
+    /// default brightness. Setting zero clears the override.
+    pub fn set_override(&mut self, value: u32) -> Result<()> {
+        self.brightness_override = if value > 0 {
+            Some(value)
+        } else {
+            None
+        };
+
+        // apply immediately when the screen is on
+        if self.is_on {
+            self.turn_on()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn set_ambient_light(&mut self, light: u16) {
+        self.light = Some(light);
+
+        if self.is_on {
+            self.fade_brightness(self.on_brightness());
+        }
+    }
+
+    pub fn set_quiet(&mut self, quiet: bool) {
+        self.quiet = quiet;
+
+        if self.is_on {
+            self.fade_brightness(self.on_brightness());
+        }
+    }
+
+    /// Change the configured brightness, used when there's no override and
+    /// auto brightness is off or has no light reading
+    pub fn set_default_brightness(&mut self, value: u32) -> Result<()> {
+        self.config.brightness = value;
+
+        if self.is_on {
+            self.turn_on()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn set_config(&mut self, config: &BacklightConfig) -> Result<()> {
+        self.config = config.clone();
+
+        if self.is_on {
+            self.turn_on()?;
+        }
+
+        Ok(())
+    }
+
+    /// Dim for the idle clock, or turn off when it's disabled
+    pub fn turn_idle(&mut self) -> Result<()> {
+        self.is_on = false;
+        self.set_brightness(self.config.idle_brightness)
+    }
+
+    /// Stop any fade or flash, leaving the brightness where it was headed.
+    /// Waits for a write from their thread to finish.
+    pub fn stop(&mut self) -> Result<()> {
+        self.set_brightness(self.target_brightness)
+    }
+
+    /// Blink the backlight a few times from a separate thread, so the event
+    /// loop isn't blocked. Ends at the current brightness.
+    pub fn flash(&self) {
+        let brightness = self.target_brightness;
+        let id = {
+            let mut output = lock(&self.output);
+            output.writer_id += 1;
+            output.writer_id
+        };
+
+        let output = self.output.clone();
+        thread::spawn(move || {
+            for _ in 0..FLASH_COUNT {
+                for value in [0, brightness] {
+                    if !write_step(&output, id, value) {
+                        return;
+                    }
+                    thread::sleep(FLASH_PERIOD);
+                }
+            }
+        });
+    }
+}
+
+/// Brightness for the ambient `light` level, or `None` when auto
+/// brightness is disabled
+fn auto_brightness(config: &BacklightConfig, light: u16) -> Option<u32> {
+    if !config.auto_brightness {
+        return None;
+    }
+
+    let (first, last) = (config.light_curve.first()?, config.light_curve.last()?);
+    if light <= first.0 {
+        return Some(first.1);
+    }
+
+    let brightness = config.light_curve.windows(2)
+        .find(|points| light <= points[1].0)
+        .map(|points| {
+            let ((x0, y0), (x1, y1)) = (points[0], points[1]);
+            let ratio = light.saturating_sub(x0) as f32 / x1.saturating_sub(x0).max(1) as f32;
+            (y0 as f32 + (y1 as f32 - y0 as f32) * ratio).round() as u32
+        })
+        .unwrap_or(last.1);
+
+    Some(brightness)
+}
+
+/// The brightness file and the value last written to it, shared with fade
+/// and flash threads
+struct BacklightOutput {
+    device: BacklightDirectory,
+    written: u32,
+    /// Bumped for every change, a fade or flash stops when it doesn't match
+    writer_id: u64
+}
 
-        if value != self.current_brightness {
+impl BacklightOutput {
+    fn write(&mut self, value: u32) -> Result<()> {
+        if value != self.written {
             self.device.write_value("brightness", value)?;
-            self.current_brightness = value;
+            self.written = value;
         }
 
         Ok(())
     }
+}
 
-    pub fn turn_on(&mut self) -> Result<()> {
-        self.set_brightness(self.default_brightness)
+/// A fade or flash thread panicking doesn't stop the backlight
+fn lock(output: &Mutex<BacklightOutput>) -> std::sync::MutexGuard<'_, BacklightOutput> {
+    output.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Write one step of fade or flash `id`, checked under the same lock as
+/// the write so a newer change can't be overwritten. Returns false when
+/// the thread should stop.
+fn write_step(output: &Mutex<BacklightOutput>, id: u64, value: u32) -> bool {
+    let mut output = lock(output);
+    if output.writer_id != id {
+        return false;
     }
 
-    pub fn turn_off(&mut self) -> Result<()> {
-        self.set_brightness(0)
+    match output.write(value) {
+        Ok(()) => true,
+        Err(e) => {
+            warn!("Backlight write failed: {e}");
+            false
+        }
     }
 }
 
@@ -91,3 +365,68 @@ impl BacklightDirectory {
         Ok(fs::write(file_path, value.to_string())?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_backlight(name: &str, brightness: u32) -> (Backlight, PathBuf) {
+        let device_dir = std::env::temp_dir()
+            .join(format!("retherm-backlight-{name}-{}", std::process::id()));
+        fs::create_dir_all(&device_dir).unwrap();
+        fs::write(device_dir.join("max_brightness"), "120").unwrap();
+        fs::write(device_dir.join("brightness"), brightness.to_string()).unwrap();
+
+        let backlight = Backlight::load(&device_dir, &BacklightConfig::default()).unwrap();
+        (backlight, device_dir)
+    }
+
+    fn written(device_dir: &Path) -> u32 {
+        fs::read_to_string(device_dir.join("brightness")).unwrap().parse().unwrap()
+    }
+
+    #[test]
+    fn set_brightness_replaces_fade() {
+        let (mut backlight, device_dir) = test_backlight("replace", 0);
+
+        backlight.fade_brightness(100);
+        // same as the fade target, written right away rather than skipped
+        backlight.set_brightness(100).unwrap();
+        thread::sleep(FADE_STEP_PERIOD * 2);
+
+        let brightness = written(&device_dir);
+        fs::remove_dir_all(&device_dir).unwrap();
+        assert_eq!(brightness, 100);
+    }
+
+    #[test]
+    fn set_brightness_stops_fade() {
+        let (mut backlight, device_dir) = test_backlight("stop", 0);
+
+        backlight.fade_brightness(100);
+        backlight.set_brightness(10).unwrap();
+        thread::sleep(FADE_STEP_PERIOD * (FADE_STEPS + 2));
+
+        let brightness = written(&device_dir);
+        fs::remove_dir_all(&device_dir).unwrap();
+        assert_eq!(brightness, 10);
+    }
+
+    #[test]
+    fn auto_brightness_follows_curve() {
+        let config = BacklightConfig {
+            auto_brightness: true,
+            light_curve: vec![(0, 20), (50, 60), (200, 108)],
+            ..Default::default()
+        };
+
+        assert_eq!(auto_brightness(&config, 0), Some(20));
+        assert_eq!(auto_brightness(&config, 25), Some(40));
+        assert_eq!(auto_brightness(&config, 50), Some(60));
+        assert_eq!(auto_brightness(&config, 125), Some(84));
+        assert_eq!(auto_brightness(&config, 500), Some(108));
+
+        let disabled = BacklightConfig { auto_brightness: false, ..config };
+        assert_eq!(auto_brightness(&disabled, 25), None);
+    }
+}
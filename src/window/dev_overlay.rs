@@ -0,0 +1,117 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use embedded_graphics::{
+    mono_font::{MonoTextStyleBuilder, ascii::FONT_6X10},
+    pixelcolor::Bgr888,
+    prelude::*,
+    text::{Alignment, Text}
+};
+
+use crate::{backplate, drawable::{AppFrameBuf, DirtyRegion}};
+
+/// How often rates are recalculated
+const SAMPLE_PERIOD: Duration = Duration::from_secs(1);
+
+/// Performance stats drawn over the top of the screen, see
+/// [`crate::config::Config::dev_overlay`]
+pub struct DevOverlay {
+    sample_start: Instant,
+    sample_frames: u32,
+    sample_messages: u64,
+    /// Events handled since the last frame was drawn
+    pending_events: u32,
+    fps: f32,
+    draw_time: Duration,
+    /// Percent of the screen redrawn in the last frame
+    dirty_percent: f32,
+    /// Events handled between the last two frames
+    frame_events: u32,
+    message_rate: f32
+}
+
+impl DevOverlay {
+    pub fn new() -> Self {
+        Self {
+            sample_start: Instant::now(),
+            sample_frames: 0,
+            sample_messages: backplate::message_count(),
+            pending_events: 0,
+            fps: 0.0,
+            draw_time: Duration::ZERO,
+            dirty_percent: 0.0,
+            frame_events: 0,
+            message_rate: 0.0
+        }
+    }
+
+    pub fn event_handled(&mut self) {
+        self.pending_events += 1;
+    }
+
+    /// Record time to draw and flush the last frame, and the area redrawn
+    pub fn frame_drawn(&mut self, draw_time: Duration, dirty: &DirtyRegion) {
+        self.draw_time = draw_time;
+        self.dirty_percent = dirty.rects().iter()
+            .map(|r| r.size.width * r.size.height)
+            .sum::<u32>() as f32 / (320.0 * 320.0) * 100.0;
+        self.sample_frames += 1;
+
+        self.frame_events = self.pending_events;
+        self.pending_events = 0;
+
+        let elapsed = self.sample_start.elapsed();
+        if elapsed >= SAMPLE_PERIOD {
+            let messages = backplate::message_count();
+            let secs = elapsed.as_secs_f32();
+
+            self.fps = self.sample_frames as f32 / secs;
+            self.message_rate = (messages - self.sample_messages) as f32 / secs;
+
+            self.sample_start = Instant::now();
+            self.sample_frames = 0;
+            self.sample_messages = messages;
+        }
+    }
+
+    pub fn draw(&self, target: &mut AppFrameBuf) -> Result<()> {
+        let style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X10)
+            .text_color(Bgr888::CSS_LIME)
+            .background_color(Bgr888::BLACK)
+            .build();
+
+        // top center, corners are outside the round display
+        let lines = [
+            format!("{:.0}fps draw:{}ms", self.fps, self.draw_time.as_millis()),
+            format!("dirty:{:.0}%", self.dirty_percent),
+            format!("ev/frame:{} bp:{:.1}/s", self.frame_events, self.message_rate)
+        ];
+
+        for (i, line) in lines.iter().enumerate() {
+            let position = Point::new(160, 30 + i as i32 * 11);
+            Text::with_alignment(line, position, style, Alignment::Center)
+                .draw(target)?;
+        }
+
+        Ok(())
+    }
+}
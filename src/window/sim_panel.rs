@@ -0,0 +1,147 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use anyhow::{Result, anyhow};
+use embedded_graphics::{
+    mono_font::{MonoTextStyleBuilder, ascii::FONT_6X10},
+    pixelcolor::Bgr888,
+    prelude::*,
+    text::Text
+};
+use embedded_graphics_framebuf::FrameBuf;
+use sdl2::{VideoSubsystem, keyboard::Keycode, render::Canvas, video::Window};
+
+use crate::{backplate::PowerReading, events::Event, temperature::Temperature};
+use super::window_sdl::present;
+
+const PANEL_WIDTH: usize = 240;
+const PANEL_HEIGHT: usize = 130;
+const LINE_HEIGHT: i32 = 12;
+
+/// Second window listing sensor readings that can be injected from the
+/// keyboard, so the event pipeline can be exercised without a backplate
+pub struct SimPanel {
+    canvas: Canvas<Window>,
+    buffer: FrameBuf<Bgr888, [Bgr888; PANEL_WIDTH * PANEL_HEIGHT]>,
+    temp: f32,
+    humidity: f32,
+    light: u16,
+    power: PowerReading,
+    connected: bool
+}
+
+impl SimPanel {
+    pub fn new(video: &VideoSubsystem) -> Result<Self> {
+        let window = video
+            .window("ReTherm Simulator", PANEL_WIDTH as u32, PANEL_HEIGHT as u32)
+            .build()?;
+
+        let canvas = window.into_canvas()
+            .build()
+            .map_err(|e| anyhow!(e))?;
+
+        let data = [Bgr888::BLACK; PANEL_WIDTH * PANEL_HEIGHT];
+        let buffer = FrameBuf::new(data, PANEL_WIDTH, PANEL_HEIGHT);
+
+        // matches what the simulated backplate reports at startup
+        Ok(Self {
+            canvas,
+            buffer,
+            temp: 20.0,
+            humidity: 40.0,
+            light: 100,
+            power: PowerReading { volts_in: 24.0, volts_bat: 3.9, charging: false },
+            connected: true
+        })
+    }
+
+    pub fn window_id(&self) -> u32 {
+        self.canvas.window().id()
+    }
+
+    /// Adjust the simulated readings for `keycode`, returning the event
+    /// that reports the change, or `None` for keys the panel doesn't use
+    pub fn key_event(&mut self, keycode: Keycode) -> Option<Event> {
+        let event = match keycode {
+            Keycode::T | Keycode::G => {
+                self.temp += if keycode == Keycode::T { 0.5 } else { -0.5 };
+                Event::BackplateTemp(Temperature::from_celsius(self.temp))
+            }
+            Keycode::H | Keycode::N => {
+                let step = if keycode == Keycode::H { 5.0 } else { -5.0 };
+                self.humidity = (self.humidity + step).clamp(0.0, 100.0);
+                Event::Humidity(self.humidity)
+            }
+            Keycode::L | Keycode::K => {
+                self.light = if keycode == Keycode::L {
+                    self.light.saturating_add(25)
+                } else {
+                    self.light.saturating_sub(25)
+                };
+                Event::AmbientLight(self.light)
+            }
+            Keycode::V => {
+                self.power.volts_in = if self.power.volts_in > 0.0 { 0.0 } else { 24.0 };
+                Event::BackplatePower(self.power)
+            }
+            Keycode::C => {
+                self.power.charging = !self.power.charging;
+                Event::BackplatePower(self.power)
+            }
+            Keycode::D => {
+                self.connected = !self.connected;
+                if self.connected {
+                    Event::BackplateConnected
+                } else {
+                    Event::BackplateDisconnected
+                }
+            }
+            Keycode::O => Event::ProximityFar,
+            _ => return None
+        };
+
+        Some(event)
+    }
+
+    pub fn draw(&mut self) -> Result<()> {
+        self.buffer.clear(Bgr888::BLACK)?;
+
+        let style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X10)
+            .text_color(Bgr888::CSS_LIME)
+            .build();
+
+        let lines = [
+            format!("T/G  temp      {:.1} C", self.temp),
+            format!("H/N  humidity  {:.0} %", self.humidity),
+            format!("L/K  light     {}", self.light),
+            format!("V    input     {:.1} V", self.power.volts_in),
+            format!("C    charging  {}", if self.power.charging { "yes" } else { "no" }),
+            format!("D    backplate {}", if self.connected { "connected" } else { "disconnected" }),
+            "P/O  proximity near/far".to_string(),
+            "Up/Down dial, click button".to_string()
+        ];
+
+        for (i, line) in lines.iter().enumerate() {
+            let position = Point::new(8, 16 + i as i32 * LINE_HEIGHT);
+            Text::new(line, position, style).draw(&mut self.buffer)?;
+        }
+
+        present(&mut self.canvas, &self.buffer.data, PANEL_WIDTH as u32, PANEL_HEIGHT as u32)
+    }
+}
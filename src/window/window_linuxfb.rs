@@ -16,29 +16,43 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use std::{ops::DerefMut, time::{Duration, Instant}};
+
 use anyhow::{Result, anyhow};
-use embedded_graphics::{pixelcolor::Bgr888, prelude::*};
-use embedded_graphics_framebuf::FrameBuf;
+use embedded_graphics::prelude::*;
 use linuxfb::Framebuffer;
+use log::warn;
 
 use crate::{
     config::BacklightConfig,
-    drawable::AppDrawable,
+    drawable::{AppDrawable, AppFrameBuf, DirtyRegion},
     events::{Event, EventHandler},
     timer::TimerId
 };
-use super::backlight::Backlight;
+use super::{backlight::{self, Backlight}, dev_overlay::DevOverlay};
 
 pub struct FramebufferWindow {
     fb_dev: Framebuffer,
-    buffer: FrameBuf<Bgr888, [Bgr888; 320 * 320]>,
-    backlight: Backlight
+    /// Framebuffer memory, mapped once and kept for the life of the window
+    fb_mem: Box<dyn DerefMut<Target = [u8]>>,
+    buffer: AppFrameBuf,
+    /// Pages in the virtual framebuffer, 2 when double buffering
+    pages: u32,
+    /// Page panned into view
+    front_page: u32,
+    /// Areas drawn in the last frame, which the back page is missing
+    last_dirty: DirtyRegion,
+    backlight: Option<Backlight>,
+    overlay: Option<DevOverlay>,
+    last_offset_check: Instant
 }
 
+/// How often to verify the driver hasn't reset the fb0 offset
+const OFFSET_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
 impl FramebufferWindow {
-    pub fn new(config: &BacklightConfig) -> Result<Self> {
-        let mut fb_dev = Framebuffer::new("/dev/fb0")
-            .or(Err(anyhow!("Error opening fb0")))?;
+    pub fn new(config: &BacklightConfig, dev_overlay: bool) -> Result<Self> {
+        let mut fb_dev = open_fb()?;
 
         // sometimes the offset will be (0, 320) after opening fb0
         // causing nothing to appear on screen
@@ -46,54 +60,190 @@ impl FramebufferWindow {
             .or(Err(anyhow!("Error changing offset of fb0")))?;
 
         let (width, height) = fb_dev.get_size();
-        let (width, height) = (width as usize, height as usize);
         // let bpp = fb.get_bytes_per_pixel() as usize;
 
-        // let mut data = vec![Bgr888::BLACK; width * height];
-        // FIXME vec as a backend doesn't compile, but do we care?
+        // Virtual height of two screens, so one page is drawn while the
+        // other is shown, then panned into view
+        let pages = match fb_dev.set_virtual_size(width, height * 2) {
+            Ok(_) => 2,
+            Err(_) => {
+                warn!("Unable to resize fb0 for double buffering, drawing to the visible page");
+                1
+            }
+        };
+
+        let fb_mem = fb_dev.map()
+            .or(Err(anyhow!("Error mapping fb0 mem")))?;
+
         // Nest framebuffer size is known and doesn't change.
-        let data = [Bgr888::WHITE; 320 * 320];
-        let buffer = FrameBuf::new(data, width, height);
+        let buffer = AppFrameBuf::new(width as usize, height as usize);
+
+        let backlight = backlight::find_backlight(config);
 
-        let backlight = Backlight::load("/sys/class/backlight/3-0036", config.brightness)?;
+        let overlay = dev_overlay.then(DevOverlay::new);
 
-        Ok(Self { fb_dev, buffer, backlight })
+        Ok(Self {
+            fb_dev,
+            fb_mem: Box::new(fb_mem),
+            buffer,
+            pages,
+            front_page: 0,
+            last_dirty: DirtyRegion::default(),
+            backlight,
+            overlay,
+            last_offset_check: Instant::now()
+        })
     }
 
-    fn flush(&self) -> Result<()> {
-        // Map the framebuffer into memory, so we can write to it:
-        let mut fb_mem = self.fb_dev.map()
-            .or(Err(anyhow!("Error mapping fb0 mem")))?;
+    /// Vertical offset of the first row of `page`
+    fn page_offset(&self, page: u32) -> u32 {
+        page * self.buffer.height() as u32
+    }
+
+    /// The offset has been observed to change at runtime, which blanks the
+    /// display since writes land outside the visible area. Periodically
+    /// check the offset and put the front page back when needed.
+    fn check_offset(&mut self) -> Result<()> {
+        if self.last_offset_check.elapsed() < OFFSET_CHECK_INTERVAL {
+            return Ok(());
+        }
+
+        self.last_offset_check = Instant::now();
 
-        // FIXME If I can somehow efficiently get the buffer data as u8 slice
-        // then we can use a memcpy with fb_mem.copy_from_slice()
-        for (i, p) in self.buffer.data.iter().enumerate() {
-            let offset = i*4;
-            fb_mem[offset] = p.b();
-            fb_mem[offset+1] = p.g();
-            fb_mem[offset+2] = p.r();
-            // Fourth byte appears to be unused.
-            // I've tried 0, 255, 10; I don't see a change in colour
+        // `get_offset()` returns the screen info cached when the device was
+        // opened, so use a fresh handle to read what the driver is using now
+        let offset = open_fb()?.get_offset();
+        let expected = (0, self.page_offset(self.front_page));
+        if offset != expected {
+            warn!("Framebuffer offset was reset to {offset:?}, restoring {expected:?}");
+            self.fb_dev.set_offset(expected.0, expected.1)
+                .or(Err(anyhow!("Error changing offset of fb0")))?;
         }
 
         Ok(())
     }
 
+    /// Copy the areas drawn since the last flush to the back page, then pan
+    /// it into view. Without double buffering the visible page is written.
+    fn flush(&mut self, dirty: DirtyRegion) -> Result<()> {
+        if dirty.is_empty() {
+            return Ok(());
+        }
+
+        let back_page = (self.front_page + 1) % self.pages;
+
+        // back page was last drawn two frames ago
+        let mut region = dirty.clone();
+        if self.pages > 1 {
+            for rect in self.last_dirty.rects() {
+                region.add(*rect);
+            }
+        }
+
+        let width = self.buffer.width();
+        let page_start = self.page_offset(back_page) as usize * width;
+        let data = self.buffer.data();
+        let mut row = [0u8; 320 * 4];
+
+        for rect in region.rects() {
+            let x = rect.top_left.x as usize;
+            let row_pixels = rect.size.width as usize;
+            let row_bytes = &mut row[..row_pixels * 4];
+
+            for y in rect.rows() {
+                let start = y as usize * width + x;
+                let pixels = &data[start..start + row_pixels];
+
+                for (bytes, p) in row_bytes.chunks_exact_mut(4).zip(pixels) {
+                    bytes[0] = p.b();
+                    bytes[1] = p.g();
+                    bytes[2] = p.r();
+                    // Fourth byte appears to be unused.
+                    // I've tried 0, 255, 10; I don't see a change in colour
+                }
+
+                let fb_start = (page_start + start) * 4;
+                self.fb_mem[fb_start..fb_start + row_bytes.len()].copy_from_slice(row_bytes);
+            }
+        }
+
+        if self.pages > 1 {
+            // panning takes effect on the next refresh, so there's no tearing
+            self.fb_dev.set_offset(0, self.page_offset(back_page))
+                .or(Err(anyhow!("Error changing offset of fb0")))?;
+            self.front_page = back_page;
+        }
+
+        self.last_dirty = dirty;
+
+        Ok(())
+    }
+
     pub fn draw_screen(&mut self, screen: &dyn AppDrawable) -> Result<()> {
+        let started = Instant::now();
+
         screen.draw(&mut self.buffer)?;
-        self.flush()?;
+        if let Some(overlay) = &self.overlay {
+            overlay.draw(&mut self.buffer)?;
+        }
+
+        self.check_offset()?;
+        let dirty = self.buffer.take_dirty();
+        if let Some(overlay) = &mut self.overlay {
+            overlay.frame_drawn(started.elapsed(), &dirty);
+        }
+
+        self.flush(dirty)?;
+
         Ok(())
     }
+
+    /// Stop backlight fades and flashes before restarting, leaving the
+    /// brightness where it was headed
+    pub fn shutdown(&mut self) {
+        if let Some(backlight) = &mut self.backlight
+            && let Err(e) = backlight.stop()
+        {
+            warn!("Failed to stop backlight: {e}");
+        }
+    }
 }
 
 impl EventHandler for FramebufferWindow {
     fn handle_event(&mut self, event: &Event) -> Result<()> {
+        if let Some(overlay) = &mut self.overlay {
+            overlay.event_handled();
+        }
+
+        let Some(backlight) = &mut self.backlight else {
+            return Ok(());
+        };
+
         match event {
             Event::TimeoutReset(TimerId::Backlight, _) => {
-                self.backlight.turn_on()?;
+                backlight.turn_on()?;
             }
             Event::TimeoutReached(TimerId::Backlight) => {
-                self.backlight.turn_off()?;
+                backlight.turn_idle()?;
+            }
+            Event::AmbientLight(light) => {
+                backlight.set_ambient_light(*light);
+            }
+            Event::QuietHours(quiet) => {
+                backlight.set_quiet(*quiet);
+            }
+            Event::ConfigReloaded(reloaded) => {
+                backlight.set_config(&reloaded.config.backlight)?;
+            }
+            Event::SetBrightness(brightness) => {
+                backlight.set_override(*brightness)?;
+            }
+            Event::SetDefaultBrightness(brightness) => {
+                backlight.set_default_brightness(*brightness)?;
+            }
+            Event::Identify => {
+                backlight.turn_on()?;
+                backlight.flash();
             }
             _ => { }
         }
@@ -101,3 +251,8 @@ impl EventHandler for FramebufferWindow {
         Ok(())
     }
 }
+
+fn open_fb() -> Result<Framebuffer> {
+    Framebuffer::new("/dev/fb0")
+        .or(Err(anyhow!("Error opening fb0")))
+}
@@ -16,25 +16,26 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Instant};
 
 use anyhow::{Result, anyhow};
 use embedded_graphics::{pixelcolor::Bgr888, prelude::*};
-use embedded_graphics_framebuf::FrameBuf;
 use sdl2::{
-    EventPump, event::{Event as SdlEvent, EventSender as SdlEventSender},
+    EventPump, event::{Event as SdlEvent, EventSender as SdlEventSender, WindowEvent},
     keyboard::Keycode, pixels::PixelFormatEnum, render::Canvas, video::Window
 };
 
-use crate::{drawable::AppDrawable, events::{Event, EventHandler, EventSender, EventSource}};
+use crate::{drawable::{AppDrawable, AppFrameBuf}, events::{Event, EventHandler, EventSender, EventSource, SendError}};
+use super::{dev_overlay::DevOverlay, sim_panel::SimPanel};
 
 pub struct SdlWindow {
     window_canvas: Canvas<Window>,
-    buffer: FrameBuf<Bgr888, [Bgr888; 320 * 320]>
+    buffer: AppFrameBuf,
+    overlay: Option<DevOverlay>
 }
 
 impl SdlWindow {
-    pub fn new() -> Result<Self> {
+    pub fn new(dev_overlay: bool) -> Result<Self> {
         let sdl_context = sdl2::init()
             .map_err(|e| anyhow!(e))?;
 
@@ -48,53 +49,83 @@ impl SdlWindow {
             .build()
             .map_err(|e| anyhow!(e))?;
 
-        let data = [Bgr888::WHITE; 320 * 320];
-        let buffer = FrameBuf::new(data, 320, 320);
+        let buffer = AppFrameBuf::new(320, 320);
+
+        let overlay = dev_overlay.then(DevOverlay::new);
 
         Ok(
-            Self { window_canvas, buffer }
+            Self { window_canvas, buffer, overlay }
         )
     }
 
-    fn flush(&mut self) -> Result<()> {
-        let texture_creator = self.window_canvas.texture_creator();
-        let mut texture = texture_creator
-            .create_texture_streaming(PixelFormatEnum::BGR888, 320, 320)
-            .map_err(|e| anyhow!(e))?;
+    pub fn draw_screen(&mut self, screen: &dyn AppDrawable) -> Result<()> {
+        let started = Instant::now();
 
-        texture.with_lock(None, |dest, _| {
-            for (i, p) in self.buffer.data.iter().enumerate() {
-                let offset = i*4;
-                dest[offset] = p.r();
-                dest[offset + 1] = p.g();
-                dest[offset + 2] = p.b();
-            }
-        }).map_err(|e| anyhow!(e))?;
+        screen.draw(&mut self.buffer)?;
+        if let Some(overlay) = &self.overlay {
+            overlay.draw(&mut self.buffer)?;
+        }
 
-        self.window_canvas.clear();
-        self.window_canvas.copy(&texture, None, None)
-            .map_err(|e| anyhow!(e))?;
-        self.window_canvas.present();
+        // the window texture is rebuilt each frame, so present all of it
+        let dirty = self.buffer.take_dirty();
+        if !dirty.is_empty() {
+            present(&mut self.window_canvas, self.buffer.data(), 320, 320)?;
+        }
 
-        Ok(())
-    }
+        if let Some(overlay) = &mut self.overlay {
+            overlay.frame_drawn(started.elapsed(), &dirty);
+        }
 
-    pub fn draw_screen(&mut self, screen: &dyn AppDrawable) -> Result<()> {
-        screen.draw(&mut self.buffer)?;
-        self.flush()?;
         Ok(())
     }
+
+    /// Nothing to stop, the simulator window isn't written from other threads
+    pub fn shutdown(&mut self) { }
+}
+
+/// Copy `pixels` to the window and show them
+pub(super) fn present(
+    canvas: &mut Canvas<Window>,
+    pixels: &[Bgr888],
+    width: u32,
+    height: u32
+) -> Result<()> {
+    let texture_creator = canvas.texture_creator();
+    let mut texture = texture_creator
+        .create_texture_streaming(PixelFormatEnum::BGR888, width, height)
+        .map_err(|e| anyhow!(e))?;
+
+    texture.with_lock(None, |dest, _| {
+        for (i, p) in pixels.iter().enumerate() {
+            let offset = i*4;
+            dest[offset] = p.r();
+            dest[offset + 1] = p.g();
+            dest[offset + 2] = p.b();
+        }
+    }).map_err(|e| anyhow!(e))?;
+
+    canvas.clear();
+    canvas.copy(&texture, None, None)
+        .map_err(|e| anyhow!(e))?;
+    canvas.present();
+
+    Ok(())
 }
 
 impl EventHandler for SdlWindow {
     fn handle_event(&mut self, _event: &Event) -> Result<()> {
+        if let Some(overlay) = &mut self.overlay {
+            overlay.event_handled();
+        }
+
         Ok(())
     }
 }
 
 pub struct SdlEventSource {
     event_pump: EventPump,
-    event_sender: SdlEventSenderHandle
+    event_sender: SdlEventSenderHandle,
+    panel: SimPanel
 }
 
 impl SdlEventSource {
@@ -113,15 +144,41 @@ impl SdlEventSource {
 
         let event_sender = SdlEventSenderHandle::new(sdl_events.event_sender());
 
+        let video = sdl_context.video()
+            .map_err(|e| anyhow!(e))?;
+        let mut panel = SimPanel::new(&video)?;
+        panel.draw()?;
+
         Ok(
-            Self { event_pump, event_sender }
+            Self { event_pump, event_sender, panel }
         )
     }
+
+    /// Let the simulator panel handle its keys before the thermostat window
+    fn map_event(&mut self, event: SdlEvent) -> Result<Option<Event>> {
+        match event {
+            SdlEvent::KeyDown { keycode: Some(keycode), .. } => {
+                if let Some(event) = self.panel.key_event(keycode) {
+                    self.panel.draw()?;
+                    return Ok(Some(event));
+                }
+            }
+            SdlEvent::Window { window_id, win_event: WindowEvent::Exposed, .. }
+                if window_id == self.panel.window_id() =>
+            {
+                self.panel.draw()?;
+            }
+            _ => { }
+        }
+
+        Ok(map_sdl_event(event))
+    }
 }
 
 impl EventSource<SdlEventSenderHandle> for SdlEventSource {
     fn wait_event(&mut self) -> Result<Event> {
-        if let Some(event) = map_sdl_event(self.event_pump.wait_event()) {
+        let event = self.event_pump.wait_event();
+        if let Some(event) = self.map_event(event)? {
             Ok(event)
         } else {
             // Unhandled event: wait again
@@ -131,7 +188,7 @@ impl EventSource<SdlEventSenderHandle> for SdlEventSource {
 
     fn poll_event(&mut self) -> Result<Option<Event>> {
         if let Some(event) = self.event_pump.poll_event() {
-            if let Some(event) = map_sdl_event(event) {
+            if let Some(event) = self.map_event(event)? {
                 Ok(Some(event))
             } else {
                 // Unhandled event: poll again
@@ -153,6 +210,8 @@ fn map_sdl_event(event: SdlEvent) -> Option<Event> {
             Some(Event::Quit),
         SdlEvent::MouseButtonDown { .. } =>
             Some(Event::ButtonDown),
+        SdlEvent::MouseButtonUp { .. } =>
+            Some(Event::ButtonUp),
         SdlEvent::MouseWheel { y, .. } if y != 0 =>
             Some(Event::Dial(y * 10)),
         SdlEvent::KeyDown { keycode, .. } if keycode == Some(Keycode::Up) =>
@@ -183,8 +242,8 @@ impl SdlEventSenderHandle {
 }
 
 impl EventSender for SdlEventSenderHandle {
-    fn send_event(&self, event: Event) -> Result<()> {
+    fn send_event(&self, event: Event) -> Result<(), SendError> {
         self.inner.push_custom_event(event)
-            .map_err(|e| anyhow!(e))
+            .map_err(SendError::Failed)
     }
 }
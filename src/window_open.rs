@@ -0,0 +1,238 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{collections::VecDeque, time::{Duration, Instant}};
+
+use anyhow::Result;
+use log::info;
+
+use crate::{
+    config::WindowOpenConfig,
+    events::{Event, EventHandler, EventSender, Topic},
+    timer::TimerId
+};
+
+/// Readings kept to measure the drop rate
+const DETECT_PERIOD: Duration = Duration::from_mins(5);
+
+/// Shortest time a drop is measured over, so one noisy reading doesn't
+/// look like an open window
+const MIN_SPAN: Duration = Duration::from_mins(1);
+
+/// Temp counts as stable when it dropped less than this over the stable period
+const STABLE_DELTA: f32 = 0.1;
+
+/// Window open since, and the temp at the last stable check
+struct OpenWindow {
+    since: Instant,
+    check_temp: f32
+}
+
+/// Watches the current temp for a quick drop, and sends
+/// `Event::WindowOpen` when heating should pause and resume
+pub struct WindowOpenDetector<S> {
+    event_sender: S,
+    config: WindowOpenConfig,
+    /// Recent current temps, oldest first
+    readings: VecDeque<(Instant, f32)>,
+    open: Option<OpenWindow>
+}
+
+impl<S: EventSender> WindowOpenDetector<S> {
+    pub fn new(config: &WindowOpenConfig, event_sender: S) -> Self {
+        Self {
+            event_sender,
+            config: config.clone(),
+            readings: VecDeque::new(),
+            open: None
+        }
+    }
+
+    fn update(&mut self, temp: f32, now: Instant) -> Result<()> {
+        while self.readings.front().is_some_and(|(time, _)| now.duration_since(*time) > DETECT_PERIOD) {
+            self.readings.pop_front();
+        }
+        self.readings.push_back((now, temp));
+
+        if !self.config.enabled || self.open.is_some() {
+            return Ok(());
+        }
+
+        let dropping = self.readings.iter()
+            .filter(|(time, _)| now.duration_since(*time) >= MIN_SPAN)
+            .any(|(time, old_temp)| {
+                let mins = now.duration_since(*time).as_secs_f32() / 60.0;
+                (old_temp - temp) / mins >= self.config.drop_rate
+            });
+
+        if dropping {
+            info!("Temp dropping quickly, window open?");
+            self.open = Some(OpenWindow { since: now, check_temp: temp });
+            self.event_sender.send_event(
+                Event::TimeoutReset(TimerId::WindowOpen, self.config.stable_period)
+            )?;
+            self.event_sender.send_event(Event::WindowOpen(true))?;
+        }
+
+        Ok(())
+    }
+
+    /// Resume once the temp stopped dropping, or the pause is too long
+    fn check_stable(&mut self, now: Instant) -> Result<()> {
+        let Some(open) = &mut self.open else {
+            return Ok(());
+        };
+        let Some(&(_, temp)) = self.readings.back() else {
+            return self.close();
+        };
+
+        if temp > open.check_temp - STABLE_DELTA {
+            info!("Temp stable, resuming heating");
+            self.close()
+        } else if now.duration_since(open.since) >= self.config.max_pause {
+            info!("Temp still dropping, resuming heating after {:?}", self.config.max_pause);
+            self.close()
+        } else {
+            open.check_temp = temp;
+            self.event_sender.send_event(
+                Event::TimeoutReset(TimerId::WindowOpen, self.config.stable_period)
+            )?;
+            Ok(())
+        }
+    }
+
+    fn close(&mut self) -> Result<()> {
+        if self.open.take().is_some() {
+            // the drop that was already detected doesn't count again
+            self.readings.clear();
+            self.event_sender.send_event(Event::WindowOpen(false))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: EventSender> EventHandler for WindowOpenDetector<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<()> {
+        match event {
+            Event::SetCurrentTemp(temp) => {
+                self.update(temp.celsius(), Instant::now())?;
+            }
+            Event::TimeoutReached(TimerId::WindowOpen) => {
+                self.check_stable(Instant::now())?;
+            }
+            Event::GetState => {
+                self.event_sender.send_event(Event::WindowOpen(self.open.is_some()))?;
+            }
+            Event::ConfigReloaded(reloaded) => {
+                self.config = reloaded.config.window_open.clone();
+                if !self.config.enabled && self.open.is_some() {
+                    self.event_sender.send_event(Event::CancelTimer(TimerId::WindowOpen))?;
+                    self.close()?;
+                }
+            }
+            _ => { }
+        }
+
+        Ok(())
+    }
+
+    fn topics(&self) -> &'static [Topic] {
+        &[Topic::State, Topic::Timer]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{DefaultEventSource, EventSource, QueueSender};
+
+    fn detector() -> (DefaultEventSource, WindowOpenDetector<QueueSender>) {
+        let config = WindowOpenConfig {
+            enabled: true,
+            ..WindowOpenConfig::default()
+        };
+
+        let event_source = DefaultEventSource::new();
+        let detector = WindowOpenDetector::new(&config, event_source.event_sender());
+
+        (event_source, detector)
+    }
+
+    /// Window open changes sent since the last call
+    fn window_open(event_source: &mut DefaultEventSource) -> Result<Vec<bool>> {
+        let mut result = Vec::new();
+        while let Some(event) = event_source.poll_event()? {
+            if let Event::WindowOpen(open) = event {
+                result.push(open);
+            }
+        }
+
+        Ok(result)
+    }
+
+    #[test]
+    fn detects_drop_and_resumes() -> Result<()> {
+        let (mut events, mut detector) = detector();
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+
+        // slow drift isn't a window
+        detector.update(20.0, at(0))?;
+        detector.update(19.9, at(120))?;
+        assert!(window_open(&mut events)?.is_empty());
+
+        // a quick drop can't be measured until a minute has passed
+        detector.update(19.6, at(150))?;
+        assert!(window_open(&mut events)?.is_empty());
+
+        // 0.4 in the last minute
+        detector.update(19.5, at(180))?;
+        assert_eq!(window_open(&mut events)?, [true]);
+
+        // still dropping at the first check
+        detector.update(18.5, at(300))?;
+        detector.check_stable(at(480))?;
+        assert!(window_open(&mut events)?.is_empty());
+
+        detector.check_stable(at(780))?;
+        assert_eq!(window_open(&mut events)?, [false]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn resumes_after_max_pause() -> Result<()> {
+        let (mut events, mut detector) = detector();
+        let start = Instant::now();
+
+        detector.update(20.0, start)?;
+        detector.update(19.0, start + Duration::from_mins(2))?;
+        assert_eq!(window_open(&mut events)?, [true]);
+
+        let mut temp = 19.0;
+        for mins in (7..=32).step_by(5) {
+            temp -= 0.5;
+            detector.update(temp, start + Duration::from_mins(mins))?;
+            detector.check_stable(start + Duration::from_mins(mins))?;
+        }
+        assert_eq!(window_open(&mut events)?, [false]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,212 @@
+/*
+ * ReTherm - Home Assistant native interface for Gen2 Nest thermostat
+ * Copyright (C) 2026 Josh Kropf <josh@slashdev.ca>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{collections::HashMap, thread, time::Duration};
+
+use anyhow::Result;
+use esphome_api::{client::ApiClient, proto::{ClimateAction, ProtoMessage}};
+use log::{info, warn};
+
+use crate::{
+    config::Config,
+    events::{Event, EventHandler, EventSender, SendError, Topic},
+    temperature::Temperature
+};
+
+/// Wait before connecting again after a peer disconnects or can't be reached
+const RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// Last climate state from a peer thermostat
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeerState {
+    /// Node name of the peer
+    pub name: String,
+    pub current_temp: Temperature,
+    /// Peer is heating or cooling
+    pub calling: bool
+}
+
+/// Peer thermostats combined, see [`crate::config::ZoneConfig`]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ZoneStatus {
+    /// Another zone is heating or cooling
+    pub calling: bool,
+    /// A zone that is heating or cooling outranks this one, when both
+    /// start at once
+    pub outranked: bool,
+    /// Average room temp of this zone and the connected peers
+    pub average_temp: Option<Temperature>
+}
+
+/// Follows the peer thermostats, and sends `Event::Zone` when the
+/// combined status changes
+pub struct ZoneManager<S> {
+    node_name: String,
+    exclusive: bool,
+    /// Connected peers by address
+    peers: HashMap<String, PeerState>,
+    local_temp: Option<Temperature>,
+    status: ZoneStatus,
+    event_sender: S
+}
+
+impl<S: EventSender + Clone + Send + 'static> ZoneManager<S> {
+    pub fn new(config: &Config, event_sender: S) -> Self {
+        let node_name = config.home_assistant.get_node_name();
+
+        for addr in &config.zone.peers {
+            let addr = addr.clone();
+            let client_info = node_name.clone();
+            let event_sender = event_sender.clone();
+            thread::spawn(move || follow_peer(&addr, &client_info, &event_sender));
+        }
+
+        Self {
+            node_name,
+            exclusive: config.zone.exclusive,
+            peers: HashMap::new(),
+            local_temp: None,
+            status: ZoneStatus::default(),
+            event_sender
+        }
+    }
+}
+
+impl<S: EventSender> ZoneManager<S> {
+    fn zone_status(&self) -> ZoneStatus {
+        let calling = self.exclusive && self.peers.values().any(|p| p.calling);
+        let outranked = self.exclusive && self.peers.values()
+            .any(|p| p.calling && p.name < self.node_name);
+
+        let temps: Vec<f32> = self.local_temp.iter()
+            .chain(self.peers.values().map(|p| &p.current_temp))
+            .map(Temperature::celsius)
+            .collect();
+        let average_temp = (!self.peers.is_empty() && !temps.is_empty())
+            .then(|| Temperature::from_celsius(temps.iter().sum::<f32>() / temps.len() as f32));
+
+        ZoneStatus { calling, outranked, average_temp }
+    }
+}
+
+impl<S: EventSender> EventHandler for ZoneManager<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<()> {
+        match event {
+            Event::ZonePeer(addr, Some(peer)) => {
+                self.peers.insert(addr.clone(), peer.clone());
+            }
+            Event::ZonePeer(addr, None) => {
+                self.peers.remove(addr);
+            }
+            Event::State(state) => {
+                self.local_temp = Some(state.current_temp);
+            }
+            Event::ConfigReloaded(reloaded) => {
+                self.exclusive = reloaded.config.zone.exclusive;
+            }
+            _ => { }
+        }
+
+        let status = self.zone_status();
+        if status != self.status || matches!(event, Event::GetState) {
+            self.status = status;
+            self.event_sender.send_event(Event::Zone(status))?;
+        }
+
+        Ok(())
+    }
+
+    fn topics(&self) -> &'static [Topic] {
+        &[Topic::State]
+    }
+}
+
+/// Send the climate state of the peer at `addr` until the event loop is gone,
+/// connecting again whenever the connection drops
+fn follow_peer<S: EventSender>(addr: &str, client_info: &str, event_sender: &S) {
+    loop {
+        let result = read_peer(addr, client_info, event_sender);
+
+        if event_sender.send_event(Event::ZonePeer(addr.to_string(), None)).is_err() {
+            return;
+        }
+
+        match result {
+            Err(e) if e.downcast_ref() == Some(&SendError::Disconnected) => return,
+            Err(e) => warn!("Zone peer {addr} disconnected: {e}"),
+            Ok(()) => { }
+        }
+
+        thread::sleep(RECONNECT_DELAY);
+    }
+}
+
+fn read_peer<S: EventSender>(addr: &str, client_info: &str, event_sender: &S) -> Result<()> {
+    let mut client = ApiClient::connect(addr, client_info)?;
+    client.subscribe_states()?;
+    info!("Zone peer {} connected at {addr}", client.name());
+
+    loop {
+        if let ProtoMessage::ClimateStateResponse(climate) = client.read()? {
+            let action = ClimateAction::try_from(climate.action).unwrap_or(ClimateAction::Idle);
+            let peer = PeerState {
+                name: client.name().to_string(),
+                current_temp: Temperature::from_celsius(climate.current_temperature),
+                calling: matches!(action, ClimateAction::Heating | ClimateAction::Cooling)
+            };
+
+            event_sender.send_event(Event::ZonePeer(addr.to_string(), Some(peer)))?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{DefaultEventSource, EventSource};
+
+    fn peer(name: &str, celsius: f32, calling: bool) -> Event {
+        Event::ZonePeer(name.to_string(), Some(PeerState {
+            name: name.to_string(),
+            current_temp: Temperature::from_celsius(celsius),
+            calling
+        }))
+    }
+
+    #[test]
+    fn lower_name_outranks() -> Result<()> {
+        let events = DefaultEventSource::new();
+        let mut config = Config::default();
+        config.home_assistant.node_name = Some("nest-b".to_string());
+        let mut zones = ZoneManager::new(&config, events.event_sender());
+
+        zones.handle_event(&peer("nest-c", 20.0, true))?;
+        assert!(zones.status.calling);
+        assert!(!zones.status.outranked);
+
+        zones.handle_event(&peer("nest-a", 22.0, true))?;
+        assert!(zones.status.outranked);
+        assert_eq!(zones.status.average_temp, Some(Temperature::from_celsius(21.0)));
+
+        zones.handle_event(&Event::ZonePeer("nest-a".to_string(), None))?;
+        zones.handle_event(&Event::ZonePeer("nest-c".to_string(), None))?;
+        assert_eq!(zones.status, ZoneStatus::default());
+
+        Ok(())
+    }
+}